@@ -2,13 +2,39 @@
 //!
 //! Handles LLM queries with optional workspace and knowledge context.
 
+use super::knowledge::retrieve_context;
+use super::runs::record_run;
 use clap::Args;
 use futures::StreamExt;
-use guided_core::{config::AppConfig, AppResult};
-use guided_llm::{create_client, LlmClient, LlmRequest};
-use guided_prompt::{build_prompt, load_prompt};
+use guided_core::config::{AppConfig, GenerationConfig};
+use guided_core::render::{
+    complete_blocks_end, renderer_for, side_by_side, MarkdownRenderer, OutputFormat, RenderOptions,
+    Renderer,
+};
+use guided_core::AppResult;
+use guided_knowledge::memory::{self, MemoryStore};
+use guided_llm::{
+    best_of, create_client_from_config, fit_prompt, BestOf, FilterAudit, Filtered, JudgeMode,
+    LlmClient, LlmRequest, LlmResponse, OutputFilter, RunRecord,
+};
+use guided_prompt::{build_prompt, context_variables, load_prompt, rerender};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Maximum bytes of diff context injected into the prompt.
+pub(crate) const MAX_DIFF_CONTEXT_BYTES: usize = 32 * 1024;
+
+/// Template variables filled in by guided, which `--var` cannot set.
+const RESERVED_VARIABLES: &[&str] = &[
+    "prompt",
+    "workspaceContext",
+    "knowledgeContext",
+    "diffContext",
+    "memoryContext",
+];
 
 /// Ask a question with optional context
 #[derive(Args, Debug)]
@@ -32,6 +58,14 @@ pub struct AskCommand {
     #[arg(long)]
     pub with_workspace: bool,
 
+    /// Include the current git diff (working tree changes) as context
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Use staged changes instead of unstaged ones (implies --diff)
+    #[arg(long)]
+    pub staged: bool,
+
     /// Enable streaming (default: true)
     #[arg(long, default_value = "true")]
     pub stream: bool,
@@ -48,13 +82,135 @@ pub struct AskCommand {
     #[arg(long)]
     pub temperature: Option<f32>,
 
-    /// Output format (markdown, text, json)
+    /// Nucleus sampling: sample from the tokens making up this probability mass (0.0-1.0)
+    #[arg(long)]
+    pub top_p: Option<f32>,
+
+    /// Sample from only the K most likely tokens
+    #[arg(long, value_name = "K")]
+    pub top_k: Option<u32>,
+
+    /// Penalize tokens by how often they already appeared (-2.0-2.0)
+    #[arg(long, allow_negative_numbers = true)]
+    pub frequency_penalty: Option<f32>,
+
+    /// Penalize tokens that already appeared at all (-2.0-2.0)
+    #[arg(long, allow_negative_numbers = true)]
+    pub presence_penalty: Option<f32>,
+
+    /// Stop generating at this sequence (repeatable)
+    #[arg(long = "stop", value_name = "SEQUENCE")]
+    pub stop: Vec<String>,
+
+    /// Seed for reproducible sampling
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Prompt to use, from `.guided/prompts/<ID>.yml`
+    #[arg(long, value_name = "ID", default_value = "agent.ask.default")]
+    pub prompt_id: String,
+
+    /// Set a template variable of the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Form of the answer: concise, detailed, bullets, step-by-step, or your
+    /// own words (overrides the prompt's `behavior.style`)
+    #[arg(long)]
+    pub style: Option<String>,
+
+    /// Output format (markdown, text, html, json)
     #[arg(short = 'o', long, default_value = "markdown")]
     pub format: String,
 
     /// Output as JSON
     #[arg(long)]
     pub json: bool,
+
+    /// Stream the answer as JSON events, one per line: `delta` events with
+    /// pieces of the answer, then a `done` event with usage and metadata
+    #[arg(long, conflicts_with_all = ["no_stream", "json", "format", "compare", "best_of"])]
+    pub json_stream: bool,
+
+    /// Copy the answer to the system clipboard
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Also write the answer to a markdown file, with metadata front matter
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Send the prompt to several providers at once and compare the answers
+    /// (e.g. ollama:llama3,openai:gpt-4o)
+    #[arg(
+        long,
+        value_name = "PROVIDER[:MODEL],...",
+        value_delimiter = ',',
+        conflicts_with_all = ["copy", "out"]
+    )]
+    pub compare: Vec<String>,
+
+    /// Sample N answers and keep the one an LLM judge rates best
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(2..=guided_llm::best_of::MAX_SAMPLES as i64),
+        conflicts_with = "compare"
+    )]
+    pub best_of: Option<u32>,
+
+    /// With --best-of, have the judge write a final answer from the samples
+    #[arg(long, requires = "best_of")]
+    pub synthesize: bool,
+
+    /// Reproducible run: temperature and seed 0, and the run recorded with
+    /// its chunk IDs and prompt hashes
+    #[arg(long, conflicts_with_all = ["temperature", "seed", "best_of", "compare"])]
+    pub deterministic: bool,
+
+    /// Fail unless the prompt definition's SHA-256 starts with HASH
+    #[arg(long, value_name = "HASH")]
+    pub pin_prompt: Option<String>,
+
+    /// Add facts remembered from earlier sessions to the prompt
+    #[arg(long, conflicts_with = "no_memory")]
+    pub recall: bool,
+
+    /// Remember facts from this exchange for later sessions
+    #[arg(long, conflicts_with = "no_memory")]
+    pub remember: bool,
+
+    /// Neither recall nor remember, whatever the config says
+    #[arg(long)]
+    pub no_memory: bool,
+}
+
+/// One provider's answer in a `--compare` run.
+struct Comparison {
+    provider: String,
+    model: String,
+    answer: Result<LlmResponse, String>,
+    elapsed: Duration,
+
+    /// What the output filter did to the answer
+    filtered: Option<FilterAudit>,
+}
+
+impl Comparison {
+    /// "provider:model · 1.2s · 345 tokens"
+    fn header(&self) -> String {
+        let outcome = match &self.answer {
+            Ok(response) => format!("{} tokens", response.usage.total_tokens),
+            Err(_) => "failed".to_string(),
+        };
+        format!(
+            "{}:{} · {:.1}s · {}",
+            self.provider,
+            self.model,
+            self.elapsed.as_secs_f64(),
+            outcome
+        )
+    }
 }
 
 impl AskCommand {
@@ -63,16 +219,36 @@ impl AskCommand {
         tracing::info!("Executing ask command");
         tracing::debug!("Ask command options: {:?}", self);
 
+        // Deterministic runs are always recorded, for auditing
+        let recording;
+        let config = if self.deterministic && !config.record_runs {
+            recording = AppConfig {
+                record_runs: true,
+                ..config.clone()
+            };
+            &recording
+        } else {
+            config
+        };
+
         // 1. Get the user input
         let user_input = self
             .get_prompt()
             .ok_or_else(|| guided_core::AppError::Config("No prompt provided".to_string()))?;
 
         tracing::debug!("User input: {}", user_input);
+        self.output_format()?;
 
         // 2. Load prompt definition
-        let mut prompt_def = load_prompt(&config.workspace, "agent.ask.default")?;
+        let mut prompt_def = load_prompt(&config.workspace, &self.prompt_id)?;
+        if let Some(style) = &self.style {
+            prompt_def.behavior.style = style.clone();
+        }
+        prompt_def.behavior.inherit(&config.persona);
         tracing::debug!("Loaded prompt definition: {}", prompt_def.id);
+        if let Some(pin) = &self.pin_prompt {
+            check_pin(&prompt_def.id, &prompt_def.source_hash, pin)?;
+        }
 
         // 3. Override context settings based on CLI flags
         if self.with_workspace {
@@ -86,17 +262,29 @@ impl AskCommand {
 
         // 4. Build prompt with variables
         let mut variables = HashMap::new();
-        variables.insert("prompt".to_string(), user_input);
+        for (key, value) in &self.vars {
+            if !prompt_def.template.contains(key.as_str()) {
+                tracing::warn!(
+                    "Variable '{}' is not used by prompt '{}'",
+                    key,
+                    prompt_def.id
+                );
+            }
+            variables.insert(key.clone(), value.clone());
+        }
+        variables.insert("prompt".to_string(), user_input.clone());
 
         // 5. Fetch knowledge base context if requested
+        let mut chunk_ids = Vec::new();
         let knowledge_context = if let Some(ref kb_name) = self.knowledge_base {
             tracing::info!("Retrieving knowledge from base: {}", kb_name);
 
             match self.retrieve_knowledge(config, kb_name).await {
-                Ok(context) => {
-                    tracing::debug!("Retrieved {} bytes of knowledge context", context.len());
+                Ok(Some((context, ids))) => {
+                    chunk_ids = ids;
                     Some(context)
                 }
+                Ok(None) => None,
                 Err(e) => {
                     tracing::warn!("Knowledge base retrieval failed: {}", e);
                     return Err(guided_core::AppError::Knowledge(format!(
@@ -109,6 +297,37 @@ impl AskCommand {
             None
         };
 
+        // 6. Collect git diff context if requested
+        let diff_sources = if self.diff || self.staged {
+            let (diff_context, sources) = self.collect_diff_context(config)?;
+            if !prompt_def.template.contains("diffContext") {
+                prompt_def
+                    .template
+                    .push_str("\n\n## Working Tree Changes\n\n{{diffContext}}");
+            }
+            variables.insert("diffContext".to_string(), diff_context);
+            sources
+        } else {
+            Vec::new()
+        };
+
+        // 7. Recall facts remembered from earlier sessions
+        if self.recalls(config) {
+            let store = MemoryStore::open(&config.workspace).await?;
+            let memories = store
+                .recall(&user_input, config.memory.recall_limit)
+                .await?;
+            if !memories.is_empty() {
+                tracing::debug!("Recalled {} memories", memories.len());
+                if !prompt_def.template.contains("memoryContext") {
+                    prompt_def
+                        .template
+                        .push_str("\n\n## Remembered from Earlier Sessions\n\n{{memoryContext}}");
+                }
+                variables.insert("memoryContext".to_string(), memory::context(&memories));
+            }
+        }
+
         let built_prompt =
             build_prompt(&prompt_def, variables, &config.workspace, knowledge_context)?;
 
@@ -118,52 +337,115 @@ impl AskCommand {
             built_prompt.metadata.knowledge_base_used
         );
 
-        // 4. Get provider configuration
-        let provider_config = config.get_provider_config(&config.provider)?;
+        // 8. Build LLM request from built prompt. Flags override the
+        // provider's configured generation defaults, which --compare applies
+        // per provider.
+        let mut request = LlmRequest::new(built_prompt.user, &config.model);
 
-        // 5. Resolve endpoint
-        let endpoint = if let Some(ref pc) = provider_config {
-            match pc {
-                guided_core::config::ProviderConfig::Ollama { endpoint, .. } => {
-                    Some(endpoint.as_str())
-                }
-                guided_core::config::ProviderConfig::OpenAI { endpoint, .. } => endpoint.as_deref(),
-                guided_core::config::ProviderConfig::Claude { endpoint, .. } => endpoint.as_deref(),
-                _ => None,
-            }
+        if let Some(system) = built_prompt.system {
+            request = request.with_system(system);
+        }
+
+        let generation = if self.compare.is_empty() {
+            self.generation().or(&config.generation(&config.provider))
         } else {
-            None
+            self.generation()
         };
+        generation.validate()?;
+        request = request.with_generation(&generation);
 
-        // 6. Resolve API key
-        let api_key = config.resolve_api_key(&config.provider)?;
-
-        // 7. Create LLM client via factory
-        let client = create_client(&config.provider, endpoint, api_key.as_deref())
-            .map_err(guided_core::AppError::Config)?;
+        if self.is_streaming() {
+            request = request.with_streaming();
+        }
 
-        // 8. Build LLM request from built prompt
-        let mut request = LlmRequest::new(built_prompt.user, &config.model);
+        // 9. Create LLM client via factory; --compare creates one per provider
+        let client = if self.compare.is_empty() {
+            Some(create_llm_client(config)?)
+        } else {
+            None
+        };
 
-        if let Some(system) = built_prompt.system {
-            request = request.with_system(system);
+        // 10. Shorten the injected context if the prompt overflows the model's
+        // context window
+        let mut overflow = None;
+        if let Some(client) = &client {
+            let mut window = config.context_window(&config.provider);
+            if let Some(strategy) = prompt_def.context.overflow {
+                window.overflow = strategy;
+            }
+            (request, overflow) = fit_prompt(
+                client.as_ref(),
+                &window,
+                request,
+                context_variables(&built_prompt.metadata),
+                |context| rerender(&prompt_def, &built_prompt.metadata, context),
+            )
+            .await?;
+            if let Some(overflow) = &overflow {
+                eprintln!(
+                    "Prompt overflowed the {}-token context window; shortened {} with {} (about {} -> {} tokens)",
+                    overflow.window_tokens,
+                    overflow.shortened.join(", "),
+                    overflow.strategy,
+                    overflow.prompt_tokens,
+                    overflow.fitted_tokens
+                );
+            }
         }
 
-        if let Some(max_tokens) = self.max_tokens {
-            request = request.with_max_tokens(max_tokens);
+        let run = RunRecord::new("ask", &config.provider, &request)
+            .with_prompt_id(&built_prompt.metadata.source_prompt_id)
+            .with_template_hash(&prompt_def.source_hash)
+            .with_chunk_ids(chunk_ids)
+            .with_context_overflow(overflow);
+        if self.deterministic {
+            eprintln!(
+                "Deterministic run: prompt {}, template {}",
+                run.prompt_hash.as_deref().unwrap_or("-"),
+                prompt_def.source_hash
+            );
         }
 
-        if let Some(temperature) = self.temperature {
-            request = request.with_temperature(temperature);
-        }
+        let Some(client) = client else {
+            return self.handle_compare(&request, run, config).await;
+        };
 
-        // 9. Execute request (streaming or non-streaming)
-        if self.is_streaming() {
-            self.handle_streaming(client.as_ref(), &request, &built_prompt.metadata, config)
-                .await
+        // 11. Execute request (streaming or non-streaming)
+        if self.json_stream {
+            let result = self
+                .handle_event_stream(
+                    client.as_ref(),
+                    &request,
+                    &built_prompt.metadata,
+                    &diff_sources,
+                    run,
+                    config,
+                )
+                .await;
+            if let Err(e) = &result {
+                print_event(&serde_json::json!({"type": "error", "message": e.to_string()}));
+            }
+            result
+        } else if self.is_streaming() && self.best_of.is_none() {
+            self.handle_streaming(
+                client.as_ref(),
+                &request,
+                &built_prompt.metadata,
+                &diff_sources,
+                run,
+                config,
+            )
+            .await
         } else {
-            self.handle_non_streaming(client.as_ref(), &request, &built_prompt.metadata, config)
-                .await
+            self.handle_non_streaming(
+                client.as_ref(),
+                &request,
+                &built_prompt.metadata,
+                &diff_sources,
+                run,
+                config,
+            )
+            .await
         }
     }
 
@@ -173,15 +455,39 @@ impl AskCommand {
         client: &dyn LlmClient,
         request: &LlmRequest,
         built_prompt_metadata: &guided_prompt::BuiltPromptMetadata,
+        diff_sources: &[String],
+        run: RunRecord,
         config: &AppConfig,
     ) -> AppResult<()> {
         tracing::info!("Sending non-streaming request to LLM");
 
-        let response = client.complete(request).await?;
+        let started = Instant::now();
+        let hashes = prompt_hashes(&run);
+        let best = match self.best_of {
+            Some(n) => Some(best_of(client, request, n as usize, self.judge_mode()).await?),
+            None => None,
+        };
+        let mut response = match &best {
+            Some(best) => LlmResponse {
+                content: best.answer.clone(),
+                model: best.judge.model.clone(),
+                usage: best.usage.clone(),
+                done: true,
+            },
+            None => client.complete(request).await?,
+        };
+        record_run(
+            config,
+            run.finish(&response.content, response.usage.clone(), started.elapsed()),
+        );
+        let filter = OutputFilter::from_app_config(config)?;
+        let filtered = filter_answer(filter.as_ref(), &response.content).await?;
+        response.content = filtered.text;
 
-        if self.json {
+        let format = self.output_format()?;
+        if format == OutputFormat::Json {
             // Output as structured JSON with metadata
-            let output = serde_json::json!({
+            let mut output = serde_json::json!({
                 "answer": response.content,
                 "model": response.model,
                 "provider": config.provider,
@@ -193,16 +499,44 @@ impl AskCommand {
                 "metadata": {
                     "promptId": built_prompt_metadata.source_prompt_id,
                     "workspaceContext": built_prompt_metadata.workspace_context_included,
-                    "knowledgeBase": built_prompt_metadata.knowledge_base_used
+                    "knowledgeBase": built_prompt_metadata.knowledge_base_used,
+                    "diffSources": diff_sources,
+                    "hashes": hashes
                 }
             });
+            if let Some(audit) = &filtered.audit {
+                output["outputFilter"] = filter_audit_json(audit)?;
+            }
+            // Candidates of a withheld answer would show what was withheld
+            let withheld = filtered.audit.as_ref().is_some_and(FilterAudit::blocked);
+            if let Some(best) = best.as_ref().filter(|_| !withheld) {
+                output["bestOf"] = best_of_json(best, filter.as_ref());
+            }
 
             let json = serde_json::to_string_pretty(&output)
                 .map_err(|e| guided_core::AppError::Serialization(e.to_string()))?;
             println!("{}", json);
+        } else if format == OutputFormat::Html {
+            let renderer = answer_renderer(format, config, &self.get_prompt().unwrap_or_default());
+            println!(
+                "{}",
+                renderer.render(&with_diff_sources(&response.content, diff_sources))
+            );
         } else {
-            // Output as plain text to stdout
-            println!("{}", response.content);
+            let renderer = answer_renderer(format, config, "");
+            println!("{}", renderer.render(&response.content));
+            print_diff_sources(diff_sources);
+            print_filter_note(filtered.audit.as_ref());
+            if let Some(best) = &best {
+                eprintln!(
+                    "\n{}",
+                    best_of_summary(
+                        best.chosen.map(|i| i + 1),
+                        best.candidates.len(),
+                        best.usage.total_tokens
+                    )
+                );
+            }
 
             // Show usage stats if verbose (to stderr)
             if tracing::enabled!(tracing::Level::DEBUG) {
@@ -215,6 +549,16 @@ impl AskCommand {
             }
         }
 
+        self.export_answer(
+            &response.content,
+            &response.model,
+            built_prompt_metadata,
+            diff_sources,
+            config,
+        )?;
+        self.remember_exchange(client, &request.model, &response.content, config)
+            .await;
+
         Ok(())
     }
 
@@ -224,12 +568,26 @@ impl AskCommand {
         client: &dyn LlmClient,
         request: &LlmRequest,
         built_prompt_metadata: &guided_prompt::BuiltPromptMetadata,
+        diff_sources: &[String],
+        run: RunRecord,
         config: &AppConfig,
     ) -> AppResult<()> {
         tracing::info!("Starting streaming request to LLM");
 
+        let format = self.output_format()?;
+        let renderer = answer_renderer(format, config, &self.get_prompt().unwrap_or_default());
+        let filter = OutputFilter::from_app_config(config)?;
+        // HTML is a single document; other formats are printed block by block,
+        // unless the output filter may withhold the whole answer
+        let incremental = !matches!(format, OutputFormat::Json | OutputFormat::Html)
+            && !filter.as_ref().is_some_and(OutputFilter::may_block);
+        let mut audit = FilterAudit::default();
+
+        let hashes = prompt_hashes(&run);
+        let started = Instant::now();
         let mut stream = client.stream(request).await?;
         let mut full_content = String::new();
+        let mut pending = String::new();
         let mut final_usage = None;
 
         while let Some(result) = stream.next().await {
@@ -238,11 +596,19 @@ impl AskCommand {
             if !chunk.content.is_empty() {
                 full_content.push_str(&chunk.content);
 
-                if !self.json {
-                    // Stream to stdout in real-time
-                    print!("{}", chunk.content);
-                    use std::io::Write;
-                    std::io::stdout().flush().ok();
+                if incremental {
+                    // Stream finished blocks to stdout as they arrive
+                    pending.push_str(&chunk.content);
+                    let end = complete_blocks_end(&pending);
+                    if end > 0 {
+                        let block = redact_block(filter.as_ref(), &pending[..end], &mut audit);
+                        let rendered = renderer.render(&block);
+                        if !rendered.is_empty() {
+                            print!("{}\n\n", rendered);
+                            std::io::stdout().flush().ok();
+                        }
+                        pending.drain(..end);
+                    }
                 }
             }
 
@@ -252,9 +618,29 @@ impl AskCommand {
             }
         }
 
-        if self.json {
+        record_run(
+            config,
+            run.finish(
+                &full_content,
+                final_usage.clone().unwrap_or_default(),
+                started.elapsed(),
+            ),
+        );
+        let filtered = if incremental {
+            // The blocks shown so far were redacted as they arrived
+            pending = redact_block(filter.as_ref(), &pending, &mut audit);
+            Filtered {
+                text: redact_block(filter.as_ref(), &full_content, &mut FilterAudit::default()),
+                audit: (!audit.redactions.is_empty()).then_some(audit),
+            }
+        } else {
+            filter_answer(filter.as_ref(), &full_content).await?
+        };
+        let full_content = filtered.text;
+
+        if format == OutputFormat::Json {
             // Output complete response as structured JSON
-            let output = serde_json::json!({
+            let mut output = serde_json::json!({
                 "answer": full_content,
                 "model": request.model,
                 "provider": config.provider,
@@ -266,16 +652,32 @@ impl AskCommand {
                 "metadata": {
                     "promptId": built_prompt_metadata.source_prompt_id,
                     "workspaceContext": built_prompt_metadata.workspace_context_included,
-                    "knowledgeBase": built_prompt_metadata.knowledge_base_used
+                    "knowledgeBase": built_prompt_metadata.knowledge_base_used,
+                    "diffSources": diff_sources,
+                    "hashes": hashes
                 }
             });
+            if let Some(audit) = &filtered.audit {
+                output["outputFilter"] = filter_audit_json(audit)?;
+            }
 
             let json = serde_json::to_string_pretty(&output)
                 .map_err(|e| guided_core::AppError::Serialization(e.to_string()))?;
             println!("{}", json);
+        } else if format == OutputFormat::Html {
+            println!(
+                "{}",
+                renderer.render(&with_diff_sources(&full_content, diff_sources))
+            );
         } else {
-            // Add newline after streaming output
-            println!();
+            // Print the last block, or the whole answer when it was held back
+            let rest = if incremental { &pending } else { &full_content };
+            let rendered = renderer.render(rest);
+            if !rendered.is_empty() {
+                println!("{}", rendered);
+            }
+            print_diff_sources(diff_sources);
+            print_filter_note(filtered.audit.as_ref());
 
             // Show usage stats if verbose (to stderr)
             if let Some(usage) = final_usage {
@@ -290,9 +692,357 @@ impl AskCommand {
             }
         }
 
+        self.export_answer(
+            &full_content,
+            &request.model,
+            built_prompt_metadata,
+            diff_sources,
+            config,
+        )?;
+        self.remember_exchange(client, &request.model, &full_content, config)
+            .await;
+
+        Ok(())
+    }
+
+    /// Handle `--json-stream`: print pieces of the answer as `delta` events
+    /// as they arrive, then a `done` event with the usage and metadata.
+    async fn handle_event_stream(
+        &self,
+        client: &dyn LlmClient,
+        request: &LlmRequest,
+        built_prompt_metadata: &guided_prompt::BuiltPromptMetadata,
+        diff_sources: &[String],
+        run: RunRecord,
+        config: &AppConfig,
+    ) -> AppResult<()> {
+        tracing::info!("Starting streaming request to LLM with JSON events");
+
+        let filter = OutputFilter::from_app_config(config)?;
+        // An answer the filter may withhold is sent in one delta once complete
+        let hold = filter.as_ref().is_some_and(OutputFilter::may_block);
+        let mut audit = FilterAudit::default();
+
+        let hashes = prompt_hashes(&run);
+        let started = Instant::now();
+        let mut stream = client.stream(request).await?;
+        let mut full_content = String::new();
+        let mut pending = String::new();
+        let mut final_usage = None;
+
+        while let Some(result) = stream.next().await {
+            let chunk = result?;
+
+            if !chunk.content.is_empty() {
+                full_content.push_str(&chunk.content);
+
+                if filter.is_none() {
+                    print_event(&delta_event(&chunk.content));
+                } else if !hold {
+                    // Redact finished blocks, so no match is split between deltas
+                    pending.push_str(&chunk.content);
+                    let end = complete_blocks_end(&pending);
+                    if end > 0 {
+                        let block = redact_block(filter.as_ref(), &pending[..end], &mut audit);
+                        print_event(&delta_event(&block));
+                        pending.drain(..end);
+                    }
+                }
+            }
+
+            if chunk.done {
+                final_usage = chunk.usage;
+                break;
+            }
+        }
+
+        record_run(
+            config,
+            run.finish(
+                &full_content,
+                final_usage.clone().unwrap_or_default(),
+                started.elapsed(),
+            ),
+        );
+        let filtered = if hold {
+            let filtered = filter_answer(filter.as_ref(), &full_content).await?;
+            print_event(&delta_event(&filtered.text));
+            filtered
+        } else {
+            if !pending.is_empty() {
+                print_event(&delta_event(&redact_block(
+                    filter.as_ref(),
+                    &pending,
+                    &mut audit,
+                )));
+            }
+            Filtered {
+                text: redact_block(filter.as_ref(), &full_content, &mut FilterAudit::default()),
+                audit: (!audit.redactions.is_empty()).then_some(audit),
+            }
+        };
+
+        let usage = final_usage.unwrap_or_default();
+        let mut done = serde_json::json!({
+            "type": "done",
+            "model": request.model,
+            "provider": config.provider,
+            "usage": {
+                "promptTokens": usage.prompt_tokens,
+                "completionTokens": usage.completion_tokens,
+                "totalTokens": usage.total_tokens
+            },
+            "metadata": {
+                "promptId": built_prompt_metadata.source_prompt_id,
+                "workspaceContext": built_prompt_metadata.workspace_context_included,
+                "knowledgeBase": built_prompt_metadata.knowledge_base_used,
+                "diffSources": diff_sources,
+                "hashes": hashes
+            }
+        });
+        if let Some(audit) = &filtered.audit {
+            done["outputFilter"] = filter_audit_json(audit)?;
+        }
+        print_event(&done);
+
+        self.export_answer(
+            &filtered.text,
+            &request.model,
+            built_prompt_metadata,
+            diff_sources,
+            config,
+        )?;
+        self.remember_exchange(client, &request.model, &filtered.text, config)
+            .await;
+
+        Ok(())
+    }
+
+    /// Send the request to every `--compare` provider concurrently and print
+    /// their answers side by side, or as a JSON array.
+    async fn handle_compare(
+        &self,
+        request: &LlmRequest,
+        run: RunRecord,
+        config: &AppConfig,
+    ) -> AppResult<()> {
+        // Resolve every client first, so a typo fails before any request is sent
+        let mut targets = Vec::new();
+        for spec in &self.compare {
+            let (provider, model) = match spec.trim().split_once(':') {
+                Some((provider, model)) => (provider.trim(), Some(model.trim())),
+                None => (spec.trim(), None),
+            };
+            if provider.is_empty() || model == Some("") {
+                return Err(guided_core::AppError::Config(format!(
+                    "Invalid --compare entry '{}': expected PROVIDER[:MODEL]",
+                    spec
+                )));
+            }
+            let provider_config = config.get_provider_config(provider)?;
+            let api_key = config.resolve_api_key(provider)?;
+            let client = create_client_from_config(
+                provider,
+                provider_config.as_ref(),
+                api_key.as_deref(),
+            )
+            .map_err(|e| guided_core::AppError::Config(format!("--compare {}: {}", spec, e)))?;
+            let model = model
+                .or_else(|| provider_config.as_ref().and_then(|p| p.model()))
+                .unwrap_or(&config.model)
+                .to_string();
+            let generation = self.generation().or(&config.generation(provider));
+            generation.validate()?;
+            targets.push((provider.to_string(), model, generation, client));
+        }
+
+        tracing::info!("Comparing answers from {} providers", targets.len());
+        let filter = OutputFilter::from_app_config(config)?;
+        let filter = filter.as_ref();
+        let answers = targets
+            .into_iter()
+            .map(|(provider, model, generation, client)| {
+                let mut request = request.clone().with_generation(&generation);
+                request.model = model.clone();
+                request.stream = false;
+                let mut run = run.clone();
+                run.provider = provider.clone();
+                run.request = request.clone();
+                async move {
+                    let started = Instant::now();
+                    let answer = client.complete(&request).await;
+                    let elapsed = started.elapsed();
+                    let mut filtered = None;
+                    let answer = match answer {
+                        Ok(mut response) => {
+                            record_run(
+                                config,
+                                run.finish(&response.content, response.usage.clone(), elapsed),
+                            );
+                            filter_answer(filter, &response.content)
+                                .await
+                                .map(|result| {
+                                    response.content = result.text;
+                                    filtered = result.audit;
+                                    response
+                                })
+                        }
+                        Err(e) => Err(e),
+                    };
+                    Comparison {
+                        provider,
+                        model,
+                        answer: answer.map_err(|e| e.to_string()),
+                        elapsed,
+                        filtered,
+                    }
+                }
+            });
+        let comparisons = futures::future::join_all(answers).await;
+
+        let format = self.output_format()?;
+        if format == OutputFormat::Json {
+            let output: Vec<_> = comparisons
+                .iter()
+                .map(|comparison| {
+                    let mut entry = serde_json::json!({
+                        "provider": comparison.provider,
+                        "model": comparison.model,
+                        "latencyMs": comparison.elapsed.as_millis() as u64,
+                    });
+                    match &comparison.answer {
+                        Ok(response) => {
+                            entry["answer"] = response.content.clone().into();
+                            entry["usage"] = serde_json::json!({
+                                "promptTokens": response.usage.prompt_tokens,
+                                "completionTokens": response.usage.completion_tokens,
+                                "totalTokens": response.usage.total_tokens
+                            });
+                        }
+                        Err(e) => entry["error"] = e.clone().into(),
+                    }
+                    if let Some(audit) = &comparison.filtered {
+                        entry["outputFilter"] = serde_json::to_value(audit).unwrap_or_default();
+                    }
+                    entry
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| guided_core::AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else {
+            let answers: Vec<(String, String)> = comparisons
+                .iter()
+                .map(|comparison| {
+                    let header = comparison.header();
+                    match &comparison.answer {
+                        Ok(response) => (header, response.content.clone()),
+                        Err(e) => (header, format!("Error: {}", e)),
+                    }
+                })
+                .collect();
+            if format == OutputFormat::Markdown && std::io::stdout().is_terminal() {
+                println!("{}", side_by_side(&answers, terminal_width()));
+            } else {
+                // One section per provider, in the requested format
+                let markdown = answers
+                    .iter()
+                    .map(|(header, answer)| format!("## {}\n\n{}", header, answer.trim_end()))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let renderer =
+                    answer_renderer(format, config, &self.get_prompt().unwrap_or_default());
+                println!("{}", renderer.render(&markdown));
+            }
+            for comparison in &comparisons {
+                if let Some(audit) = &comparison.filtered {
+                    eprintln!(
+                        "{}:{}: {}",
+                        comparison.provider,
+                        comparison.model,
+                        audit.note()
+                    );
+                }
+            }
+        }
+
+        if comparisons.iter().all(|c| c.answer.is_err()) {
+            return Err(guided_core::AppError::Llm(
+                "No provider returned an answer".to_string(),
+            ));
+        }
         Ok(())
     }
 
+    /// Write the answer to `--out` and copy it with `--copy`.
+    fn export_answer(
+        &self,
+        answer: &str,
+        model: &str,
+        built_prompt_metadata: &guided_prompt::BuiltPromptMetadata,
+        diff_sources: &[String],
+        config: &AppConfig,
+    ) -> AppResult<()> {
+        if let Some(path) = &self.out {
+            let mut front_matter = serde_yaml::Mapping::new();
+            front_matter.insert(
+                "question".into(),
+                self.get_prompt().unwrap_or_default().into(),
+            );
+            front_matter.insert("provider".into(), config.provider.as_str().into());
+            front_matter.insert("model".into(), model.into());
+            front_matter.insert(
+                "promptId".into(),
+                built_prompt_metadata.source_prompt_id.as_str().into(),
+            );
+            if let Some(base) = &built_prompt_metadata.knowledge_base_used {
+                front_matter.insert("knowledgeBase".into(), base.as_str().into());
+            }
+            if !diff_sources.is_empty() {
+                front_matter.insert("sources".into(), diff_sources.to_vec().into());
+            }
+            write_answer_file(path, answer, front_matter)?;
+        }
+        if self.copy {
+            copy_to_clipboard(answer)?;
+        }
+        Ok(())
+    }
+
+    /// Generation parameters set by flags.
+    fn generation(&self) -> GenerationConfig {
+        // Greedy decoding with a fixed seed, where the provider supports them
+        let deterministic = self.deterministic.then_some(0.0);
+        GenerationConfig {
+            temperature: self.temperature.or(deterministic),
+            max_tokens: self.max_tokens,
+            top_p: self.top_p,
+            top_k: self.top_k,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+            stop: self.stop.clone(),
+            seed: self.seed.or(self.deterministic.then_some(0)),
+        }
+    }
+
+    /// How `--best-of` turns its samples into an answer.
+    fn judge_mode(&self) -> JudgeMode {
+        if self.synthesize {
+            JudgeMode::Synthesize
+        } else {
+            JudgeMode::Select
+        }
+    }
+
+    /// Output format from `--format`, or JSON with `--json`.
+    fn output_format(&self) -> AppResult<OutputFormat> {
+        if self.json {
+            Ok(OutputFormat::Json)
+        } else {
+            OutputFormat::parse(&self.format)
+        }
+    }
+
     /// Get the prompt text from various sources.
     fn get_prompt(&self) -> Option<String> {
         self.prompt
@@ -307,45 +1057,374 @@ impl AskCommand {
             })
     }
 
+    /// Collect the git diff and format it as per-hunk context.
+    ///
+    /// Returns the context string and the hunk locations used as sources.
+    fn collect_diff_context(&self, config: &AppConfig) -> AppResult<(String, Vec<String>)> {
+        let raw = guided_core::git::working_tree_diff(&config.workspace, self.staged)?;
+        let files = guided_core::git::parse_diff(&raw);
+
+        if files.is_empty() {
+            return Err(guided_core::AppError::Git(format!(
+                "No {} changes found",
+                if self.staged { "staged" } else { "unstaged" }
+            )));
+        }
+
+        let sources: Vec<String> = files
+            .iter()
+            .flat_map(|f| f.hunks.iter().map(|h| h.location()))
+            .collect();
+
+        let context = guided_core::git::format_diff_context(&files, MAX_DIFF_CONTEXT_BYTES);
+
+        tracing::debug!(
+            "Collected diff context: {} files, {} hunks, {} bytes",
+            files.len(),
+            sources.len(),
+            context.len()
+        );
+
+        Ok((context, sources))
+    }
+
     /// Check if streaming is enabled.
     #[allow(dead_code)]
     pub fn is_streaming(&self) -> bool {
         !self.no_stream && self.stream
     }
 
-    /// Retrieve knowledge base context.
-    async fn retrieve_knowledge(&self, config: &AppConfig, kb_name: &str) -> AppResult<String> {
-        tracing::info!("Retrieving knowledge from base: {}", kb_name);
+    /// Whether remembered facts are added to the prompt.
+    fn recalls(&self, config: &AppConfig) -> bool {
+        !self.no_memory && (self.recall || config.memory.recall)
+    }
 
-        // Use knowledge ask API to retrieve relevant chunks
-        let api_key = config.resolve_api_key(&config.provider).ok().flatten();
+    /// Whether facts of the exchange are remembered.
+    fn remembers(&self, config: &AppConfig) -> bool {
+        !self.no_memory && (self.remember || config.memory.remember)
+    }
 
-        let options = guided_knowledge::AskOptions {
-            base_name: kb_name.to_string(),
-            query: self
-                .get_prompt()
-                .ok_or_else(|| guided_core::AppError::Config("No prompt provided".to_string()))?,
-            top_k: 5, // Default to top 5 chunks
+    /// Have the LLM distill the facts of the exchange worth remembering.
+    ///
+    /// Failures are logged rather than returned: the answer has already
+    /// been given.
+    async fn remember_exchange(
+        &self,
+        client: &dyn LlmClient,
+        model: &str,
+        answer: &str,
+        config: &AppConfig,
+    ) {
+        if !self.remembers(config) {
+            return;
+        }
+        let question = self.get_prompt().unwrap_or_default();
+        let remembered = match MemoryStore::open(&config.workspace).await {
+            Ok(mut store) => memory::distill(&mut store, client, model, &question, answer).await,
+            Err(e) => Err(e),
         };
+        match remembered {
+            Ok(memories) if !memories.is_empty() => {
+                eprintln!("Remembered {} facts (guided memory list)", memories.len());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to remember the exchange: {}", e),
+        }
+    }
+
+    /// Retrieve knowledge base context and the IDs of the chunks used.
+    async fn retrieve_knowledge(
+        &self,
+        config: &AppConfig,
+        kb_name: &str,
+    ) -> AppResult<Option<(String, Vec<String>)>> {
+        let query = self
+            .get_prompt()
+            .ok_or_else(|| guided_core::AppError::Config("No prompt provided".to_string()))?;
+        let base = guided_knowledge::KnowledgeBase::open_cached(
+            &config.workspace,
+            kb_name,
+            &config.provider_configs(),
+        )
+        .await?;
+        retrieve_context(config, &base, query, None).await
+    }
+}
+
+/// Create an LLM client for the active provider.
+///
+/// Resolves the provider endpoint, timeout and API key from configuration.
+pub(crate) fn create_llm_client(config: &AppConfig) -> AppResult<Arc<dyn LlmClient>> {
+    let provider_config = config.get_provider_config(&config.provider)?;
+    let api_key = config.resolve_api_key(&config.provider)?;
+
+    create_client_from_config(
+        &config.provider,
+        provider_config.as_ref(),
+        api_key.as_deref(),
+    )
+    .map_err(guided_core::AppError::Config)
+}
+
+/// Print the diff hunks used as context, if any.
+/// Check a prompt definition's hash against a `--pin-prompt` prefix.
+fn check_pin(prompt_id: &str, source_hash: &str, pin: &str) -> AppResult<()> {
+    let pin = pin.trim().to_lowercase();
+    if pin.len() < 8 {
+        return Err(guided_core::AppError::Config(
+            "--pin-prompt needs at least 8 hex digits of the hash".to_string(),
+        ));
+    }
+    if !source_hash.starts_with(&pin) {
+        return Err(guided_core::AppError::Prompt(format!(
+            "Prompt '{}' has changed: its hash is {}, pinned {}",
+            prompt_id, source_hash, pin
+        )));
+    }
+    Ok(())
+}
 
-        let result = guided_knowledge::ask(&config.workspace, options, api_key.as_deref()).await?;
+/// The template and prompt hashes of a run, for `--json` metadata.
+fn prompt_hashes(run: &RunRecord) -> serde_json::Value {
+    serde_json::json!({
+        "template": run.template_hash,
+        "prompt": run.prompt_hash
+    })
+}
 
-        // Format chunks into context string
-        let context = result
-            .chunks
+/// Parse a `--var KEY=VALUE` argument.
+fn parse_var(arg: &str) -> Result<(String, String), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", arg))?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("invalid variable name '{}'", key));
+    }
+    if RESERVED_VARIABLES.contains(&key) {
+        return Err(format!(
+            "'{}' is set by guided and cannot be overridden",
+            key
+        ));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Renderer for an answer printed to stdout in `format`. Markdown is only
+/// pretty-printed on a terminal, so piped output stays raw markdown.
+pub(crate) fn answer_renderer(
+    format: OutputFormat,
+    config: &AppConfig,
+    title: &str,
+) -> Box<dyn Renderer> {
+    let terminal = std::io::stdout().is_terminal();
+    if format == OutputFormat::Markdown && !terminal {
+        return Box::new(MarkdownRenderer);
+    }
+    renderer_for(
+        format,
+        &RenderOptions {
+            color: terminal && !config.no_color,
+            width: terminal_width().min(100),
+            title: title.chars().take(80).collect(),
+        },
+    )
+}
+
+/// The candidates of a best-of run and the judge's verdict, for `--json`,
+/// redacted by the output filter's rules.
+fn best_of_json(best: &BestOf, filter: Option<&OutputFilter>) -> serde_json::Value {
+    let redact = |text: &str| match filter {
+        Some(filter) => filter.redact(text).text,
+        None => text.to_string(),
+    };
+    let candidates: Vec<String> = best.candidates.iter().map(|c| redact(&c.content)).collect();
+    serde_json::json!({
+        "chosen": best.chosen.map(|i| i + 1),
+        "judge": redact(&best.judge.content),
+        "candidates": candidates,
+        "totalTokens": best.usage.total_tokens
+    })
+}
+
+/// A `--json-stream` event carrying the next piece of the answer.
+fn delta_event(content: &str) -> serde_json::Value {
+    serde_json::json!({"type": "delta", "content": content})
+}
+
+/// Print a `--json-stream` event as one line of JSON.
+fn print_event(event: &serde_json::Value) {
+    println!("{}", event);
+    std::io::stdout().flush().ok();
+}
+
+/// Run `answer` through the output filter, if there is one.
+pub(crate) async fn filter_answer(
+    filter: Option<&OutputFilter>,
+    answer: &str,
+) -> AppResult<Filtered> {
+    match filter {
+        Some(filter) => filter.apply(answer).await,
+        None => Ok(Filtered {
+            text: answer.to_string(),
+            audit: None,
+        }),
+    }
+}
+
+/// Redact a block of a streamed answer, adding its redactions to `audit`.
+fn redact_block(filter: Option<&OutputFilter>, block: &str, audit: &mut FilterAudit) -> String {
+    let Some(filter) = filter else {
+        return block.to_string();
+    };
+    let filtered = filter.redact(block);
+    for redaction in filtered.audit.into_iter().flat_map(|a| a.redactions) {
+        match audit
+            .redactions
+            .iter_mut()
+            .find(|r| r.rule == redaction.rule)
+        {
+            Some(existing) => existing.count += redaction.count,
+            None => audit.redactions.push(redaction),
+        }
+    }
+    filtered.text
+}
+
+/// The output filter's audit, for `--json`.
+pub(crate) fn filter_audit_json(audit: &FilterAudit) -> AppResult<serde_json::Value> {
+    serde_json::to_value(audit).map_err(|e| guided_core::AppError::Serialization(e.to_string()))
+}
+
+/// Print the output filter's audit note to stderr, when it filtered anything.
+pub(crate) fn print_filter_note(audit: Option<&FilterAudit>) {
+    if let Some(audit) = audit {
+        eprintln!("\n{}", audit.note());
+    }
+}
+
+/// One line on how a best-of answer was chosen, from the 1-based number of
+/// the chosen candidate (`None` when synthesized).
+pub(crate) fn best_of_summary(chosen: Option<usize>, candidates: usize, tokens: u32) -> String {
+    match chosen {
+        Some(number) => format!(
+            "Best of {}: candidate {} ({} tokens in total)",
+            candidates, number, tokens
+        ),
+        None => format!(
+            "Best of {}: synthesized by the judge ({} tokens in total)",
+            candidates, tokens
+        ),
+    }
+}
+
+/// Terminal width from `COLUMNS`, or 80.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Write an answer to a markdown file, after YAML front matter holding
+/// `metadata` and the time it was written.
+pub(crate) fn write_answer_file(
+    path: &Path,
+    answer: &str,
+    mut metadata: serde_yaml::Mapping,
+) -> AppResult<()> {
+    metadata.insert(
+        "created".into(),
+        chrono::Utc::now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            .into(),
+    );
+    let front_matter = serde_yaml::to_string(&metadata)
+        .map_err(|e| guided_core::AppError::Serialization(e.to_string()))?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(
+        path,
+        format!("---\n{}---\n\n{}\n", front_matter, answer.trim_end()),
+    )?;
+    eprintln!("Wrote answer to {}", path.display());
+    Ok(())
+}
+
+/// Copy text to the system clipboard with the platform's clipboard tool:
+/// `pbcopy` on macOS, `clip` on Windows, and `wl-copy`, `xclip` or `xsel`
+/// elsewhere.
+pub(crate) fn copy_to_clipboard(text: &str) -> AppResult<()> {
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (program, args) in tools {
+        // Tools that are not installed fail to spawn; try the next one
+        let Ok(mut child) = std::process::Command::new(program)
+            .args(*args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        // wl-copy fails outside Wayland, xclip without an X display
+        if child.wait()?.success() {
+            return Ok(());
+        }
+        tracing::debug!("Clipboard tool '{}' failed", program);
+    }
+
+    Err(guided_core::AppError::Config(format!(
+        "Could not copy to the clipboard: none of {} worked",
+        tools
             .iter()
-            .enumerate()
-            .map(|(i, chunk)| format!("[Chunk {}]\n{}\n", i + 1, chunk.text.trim()))
+            .map(|(program, _)| *program)
             .collect::<Vec<_>>()
-            .join("\n");
+            .join(", ")
+    )))
+}
 
-        tracing::debug!(
-            "Retrieved {} chunks ({} bytes) from knowledge base '{}'",
-            result.chunks.len(),
-            context.len(),
-            kb_name
-        );
+/// An answer followed by the diff files it was given, as one document.
+fn with_diff_sources(answer: &str, diff_sources: &[String]) -> String {
+    if diff_sources.is_empty() {
+        return answer.to_string();
+    }
+    let sources: Vec<String> = diff_sources.iter().map(|s| format!("- `{}`", s)).collect();
+    format!(
+        "{}\n\n**Sources:**\n\n{}\n",
+        answer.trim_end(),
+        sources.join("\n")
+    )
+}
+
+fn print_diff_sources(diff_sources: &[String]) {
+    if diff_sources.is_empty() {
+        return;
+    }
 
-        Ok(context)
+    println!();
+    println!("Sources:");
+    for source in diff_sources {
+        println!("- {}", source);
     }
 }
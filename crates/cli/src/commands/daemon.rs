@@ -0,0 +1,518 @@
+//! Knowledge daemon and status command handlers.
+//!
+//! `guided knowledge daemon` runs the learn profiles of config.yaml that
+//! have an `every` interval whenever they are due, picking up edits to
+//! config.yaml as it goes. It logs to `.guided/logs/daemon.log` and serves
+//! its status on the local socket `.guided/daemon.sock`, which
+//! `guided knowledge status` reads.
+
+use super::knowledge::KnowledgeLearnCommand;
+use chrono::{DateTime, Utc};
+use clap::{Args, FromArgMatches};
+use guided_core::config::{AppConfig, LearnProfile};
+use guided_core::reload::ConfigWatcher;
+use guided_core::{AppError, AppResult};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Longest sleep between checks for due profiles and config changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Run the learn profiles on their schedule in the background
+#[derive(Args, Debug)]
+pub struct KnowledgeDaemonCommand {
+    /// Start the daemon as a background process and return
+    #[arg(long)]
+    pub detach: bool,
+}
+
+/// Show what the knowledge daemon is doing
+#[derive(Args, Debug)]
+pub struct KnowledgeStatusCommand {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// A scheduled learn profile.
+struct Job {
+    name: String,
+    profile: LearnProfile,
+    every: Duration,
+    running: bool,
+    last_run: Option<DateTime<Utc>>,
+    /// Summary of the last run, or why it failed
+    last_result: Option<Result<String, String>>,
+    next_run: DateTime<Utc>,
+}
+
+/// What the daemon serves on its socket.
+struct DaemonState {
+    started_at: DateTime<Utc>,
+    log_file: PathBuf,
+    jobs: Vec<Job>,
+    /// Profiles without a valid `every`, which are not run
+    unscheduled: Vec<String>,
+}
+
+impl DaemonState {
+    /// Schedule the profiles of `config`, keeping the history of the ones
+    /// already scheduled.
+    fn schedule(&mut self, config: &AppConfig) {
+        let now = Utc::now();
+        let mut previous = std::mem::take(&mut self.jobs);
+        self.unscheduled.clear();
+
+        for (name, profile) in &config.knowledge.profiles {
+            let every = match profile.interval() {
+                Ok(Some(every)) => every,
+                Ok(None) => {
+                    self.unscheduled.push(name.clone());
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Not scheduling learn profile {}: {}", name, e);
+                    self.unscheduled.push(name.clone());
+                    continue;
+                }
+            };
+            let kept = previous
+                .iter()
+                .position(|job| &job.name == name)
+                .map(|i| previous.swap_remove(i));
+            self.jobs.push(match kept {
+                // An edited profile is due `every` after its last run
+                Some(job) => Job {
+                    profile: profile.clone(),
+                    every,
+                    next_run: job.last_run.map_or(now, |last| after(last, every)),
+                    ..job
+                },
+                None => Job {
+                    name: name.clone(),
+                    profile: profile.clone(),
+                    every,
+                    running: false,
+                    last_run: None,
+                    last_result: None,
+                    next_run: now,
+                },
+            });
+        }
+
+        tracing::info!(
+            "Scheduled {} learn profiles{}",
+            self.jobs.len(),
+            if self.unscheduled.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "; without an `every` interval: {}",
+                    self.unscheduled.join(", ")
+                )
+            }
+        );
+    }
+
+    /// Names and profiles of the jobs due at `now`.
+    fn due(&self, now: DateTime<Utc>) -> Vec<(String, LearnProfile)> {
+        self.jobs
+            .iter()
+            .filter(|job| job.next_run <= now)
+            .map(|job| (job.name.clone(), job.profile.clone()))
+            .collect()
+    }
+
+    /// How long to sleep before the next job is due.
+    fn until_next(&self, now: DateTime<Utc>) -> Duration {
+        self.jobs
+            .iter()
+            .map(|job| (job.next_run - now).to_std().unwrap_or_default())
+            .min()
+            .unwrap_or(POLL_INTERVAL)
+            .min(POLL_INTERVAL)
+    }
+
+    fn job(&mut self, name: &str) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.name == name)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let jobs: Vec<_> = self
+            .jobs
+            .iter()
+            .map(|job| {
+                let (ok, summary) = match &job.last_result {
+                    Some(Ok(summary)) => (Some(true), Some(summary)),
+                    Some(Err(error)) => (Some(false), Some(error)),
+                    None => (None, None),
+                };
+                serde_json::json!({
+                    "name": job.name,
+                    "base": job.profile.base,
+                    "every": job.profile.every,
+                    "running": job.running,
+                    "lastRun": job.last_run,
+                    "lastOk": ok,
+                    "lastResult": summary,
+                    "nextRun": job.next_run,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "pid": std::process::id(),
+            "startedAt": self.started_at,
+            "logFile": self.log_file,
+            "profiles": jobs,
+            "unscheduled": self.unscheduled,
+        })
+    }
+}
+
+impl KnowledgeDaemonCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing knowledge daemon command");
+
+        let log_file = config.guided_dir().join("logs").join("daemon.log");
+        if self.detach {
+            return detach(&log_file);
+        }
+
+        let socket_path = socket_path(config);
+        let listener = bind(&socket_path).await?;
+
+        // Everything from here on goes to the log file
+        std::fs::create_dir_all(log_file.parent().unwrap())?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file)?;
+        let file = Mutex::new(file);
+        eprintln!(
+            "Knowledge daemon running (pid {}); logs in {}, status with `guided knowledge status`",
+            std::process::id(),
+            log_file.display()
+        );
+        let _divert = guided_core::logging::divert_logs(Arc::new(move |level, message| {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            writeln!(
+                file,
+                "{} {:>5} {}",
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                level,
+                message
+            )
+            .ok();
+        }));
+
+        let state = Arc::new(Mutex::new(DaemonState {
+            started_at: Utc::now(),
+            log_file,
+            jobs: Vec::new(),
+            unscheduled: Vec::new(),
+        }));
+        state.lock().unwrap().schedule(config);
+        let server = tokio::spawn(serve(listener, Arc::clone(&state)));
+
+        let result = run(config.clone(), &state).await;
+
+        server.abort();
+        std::fs::remove_file(&socket_path).ok();
+        tracing::info!("Knowledge daemon stopped");
+        result
+    }
+}
+
+/// Run due profiles until Ctrl-C or SIGTERM.
+async fn run(mut config: AppConfig, state: &Mutex<DaemonState>) -> AppResult<()> {
+    let cancel = guided_core::cancel::shutdown_token();
+    let stop = async {
+        tokio::select! {
+            _ = cancel.cancelled() => {}
+            _ = terminated() => cancel.cancel(),
+        }
+    };
+    tokio::pin!(stop);
+
+    let mut watcher = ConfigWatcher::new(&config);
+    loop {
+        if let Some(reloaded) = watcher.reload(&config) {
+            config = reloaded;
+            state.lock().unwrap().schedule(&config);
+        }
+
+        let due = state.lock().unwrap().due(Utc::now());
+        for (name, profile) in due {
+            if cancel.is_cancelled() {
+                return Ok(());
+            }
+            run_profile(&config, &name, &profile, state).await;
+        }
+
+        let wait = state.lock().unwrap().until_next(Utc::now());
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = &mut stop => return Ok(()),
+        }
+    }
+}
+
+/// Learn one profile and record the outcome.
+async fn run_profile(
+    config: &AppConfig,
+    name: &str,
+    profile: &LearnProfile,
+    state: &Mutex<DaemonState>,
+) {
+    if let Some(job) = state.lock().unwrap().job(name) {
+        job.running = true;
+    }
+    tracing::info!("Learning profile {} into base '{}'", name, profile.base);
+
+    let result = learn_profile(config, name, profile).await;
+    match &result {
+        Ok(summary) => tracing::info!("Profile {}: {}", name, summary),
+        Err(e) => tracing::warn!("Profile {} failed: {}", name, e),
+    }
+
+    let now = Utc::now();
+    if let Some(job) = state.lock().unwrap().job(name) {
+        job.running = false;
+        job.last_run = Some(now);
+        job.last_result = Some(result.map_err(|e| e.to_string()));
+        job.next_run = after(now, job.every);
+    }
+}
+
+async fn learn_profile(
+    config: &AppConfig,
+    name: &str,
+    profile: &LearnProfile,
+) -> AppResult<String> {
+    // The options `guided knowledge learn --profile <name>` would use
+    let command = clap::Command::new("learn");
+    let matches = KnowledgeLearnCommand::augment_args(command)
+        .try_get_matches_from(["learn", "--profile", name])
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    let command = KnowledgeLearnCommand::from_arg_matches(&matches)
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    let options = command.learn_options(config, &profile.base, Some(profile))?;
+
+    let api_key = config.resolve_api_key(&config.provider).ok().flatten();
+    let stats = guided_knowledge::learn_with_progress(
+        &config.workspace,
+        &options,
+        api_key.as_deref(),
+        guided_knowledge::ProgressReporter::noop(),
+    )
+    .await?;
+
+    let mut summary = format!(
+        "{} sources ({} chunks) in {:.1}s",
+        stats.sources_count, stats.chunks_count, stats.duration_secs
+    );
+    if stats.unchanged_count > 0 {
+        summary.push_str(&format!(", {} unchanged", stats.unchanged_count));
+    }
+    if !stats.errors.is_empty() {
+        summary.push_str(&format!(", {} skipped", stats.errors.len()));
+    }
+    Ok(summary)
+}
+
+impl KnowledgeStatusCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing knowledge status command");
+
+        let status = read_status(&socket_path(config)).await?;
+        if self.json {
+            let json = serde_json::to_string_pretty(&status)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        let text = |value: &serde_json::Value| value.as_str().unwrap_or("-").to_string();
+        let time = |value: &serde_json::Value| {
+            value
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        println!(
+            "Knowledge daemon (pid {}) running since {}; logs in {}",
+            status["pid"],
+            time(&status["startedAt"]),
+            text(&status["logFile"])
+        );
+        let profiles = status["profiles"].as_array().cloned().unwrap_or_default();
+        if profiles.is_empty() {
+            println!("No learn profiles with an `every` interval are scheduled.");
+        }
+        for profile in &profiles {
+            let last = if profile["running"].as_bool() == Some(true) {
+                "running now".to_string()
+            } else if profile["lastRun"].is_null() {
+                "not run yet".to_string()
+            } else {
+                format!(
+                    "last {} {}: {}",
+                    time(&profile["lastRun"]),
+                    if profile["lastOk"].as_bool() == Some(true) {
+                        "ok"
+                    } else {
+                        "failed"
+                    },
+                    text(&profile["lastResult"])
+                )
+            };
+            println!(
+                "  {:<16} base {:<16} every {:<5} next {}  {}",
+                text(&profile["name"]),
+                text(&profile["base"]),
+                text(&profile["every"]),
+                time(&profile["nextRun"]),
+                last
+            );
+        }
+        if let Some(unscheduled) = status["unscheduled"].as_array() {
+            if !unscheduled.is_empty() {
+                let names: Vec<String> = unscheduled.iter().map(text).collect();
+                println!("Not scheduled (no `every`): {}", names.join(", "));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn after(time: DateTime<Utc>, every: Duration) -> DateTime<Utc> {
+    time + chrono::Duration::from_std(every).unwrap_or_else(|_| chrono::Duration::days(1))
+}
+
+fn socket_path(config: &AppConfig) -> PathBuf {
+    config.guided_dir().join("daemon.sock")
+}
+
+/// Start this command again as a detached process.
+fn detach(log_file: &Path) -> AppResult<()> {
+    let args = std::env::args_os().skip(1).filter(|arg| arg != "--detach");
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    #[cfg(unix)]
+    {
+        // Out of the terminal's process group, so closing it does not stop
+        // the daemon
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let child = command.spawn()?;
+    println!(
+        "Knowledge daemon started (pid {}); logs in {}",
+        child.id(),
+        log_file.display()
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+type Listener = tokio::net::UnixListener;
+#[cfg(not(unix))]
+type Listener = ();
+
+/// Listen on the status socket, refusing to start a second daemon.
+#[cfg(unix)]
+async fn bind(socket_path: &Path) -> AppResult<Listener> {
+    if socket_path.exists() {
+        if tokio::net::UnixStream::connect(socket_path).await.is_ok() {
+            return Err(AppError::Knowledge(format!(
+                "A knowledge daemon is already running ({}); see `guided knowledge status`",
+                socket_path.display()
+            )));
+        }
+        // Left behind by a daemon that was killed
+        std::fs::remove_file(socket_path)?;
+    }
+    Ok(tokio::net::UnixListener::bind(socket_path)?)
+}
+
+#[cfg(not(unix))]
+async fn bind(_socket_path: &Path) -> AppResult<Listener> {
+    Err(unsupported())
+}
+
+/// Answer each connection with the daemon's status as JSON.
+#[cfg(unix)]
+async fn serve(listener: Listener, state: Arc<Mutex<DaemonState>>) {
+    use tokio::io::AsyncWriteExt;
+    loop {
+        match listener.accept().await {
+            Ok((mut stream, _)) => {
+                let body = state.lock().unwrap().to_json().to_string();
+                if let Err(e) = stream.write_all(body.as_bytes()).await {
+                    tracing::debug!("Status client went away: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Status socket failed: {}", e),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn serve(_listener: Listener, _state: Arc<Mutex<DaemonState>>) {}
+
+#[cfg(unix)]
+async fn read_status(socket_path: &Path) -> AppResult<serde_json::Value> {
+    use tokio::io::AsyncReadExt;
+    let mut stream = tokio::net::UnixStream::connect(socket_path)
+        .await
+        .map_err(|_| {
+            AppError::Knowledge(
+                "No knowledge daemon is running in this workspace; start one with \
+                 `guided knowledge daemon --detach`"
+                    .to_string(),
+            )
+        })?;
+    let mut body = String::new();
+    stream.read_to_string(&mut body).await?;
+    serde_json::from_str(&body).map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+#[cfg(not(unix))]
+async fn read_status(_socket_path: &Path) -> AppResult<serde_json::Value> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> AppError {
+    AppError::Other("The knowledge daemon needs Unix domain sockets".to_string())
+}
+
+/// Wait for SIGTERM, as sent by `kill` and service managers.
+#[cfg(unix)]
+async fn terminated() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            sigterm.recv().await;
+        }
+        Err(_) => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminated() {
+    std::future::pending().await
+}
@@ -0,0 +1,197 @@
+//! Edit command handler.
+//!
+//! Applies unified diffs (typically proposed by the model) to the workspace
+//! after a preview and confirmation, and manages the resulting backups.
+
+use clap::{Args, Subcommand};
+use guided_core::{config::AppConfig, AppError, AppResult};
+use guided_edit::{
+    apply_changes, confirm, list_backups, parse_patch, plan_changes, render_preview, restore_backup,
+};
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Apply patches to the workspace with preview and backups
+#[derive(Args, Debug)]
+pub struct EditCommand {
+    #[command(subcommand)]
+    pub action: EditAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EditAction {
+    /// Preview and apply a unified diff
+    Apply(EditApplyCommand),
+    /// List backups created by applied patches
+    Backups(EditBackupsCommand),
+    /// Restore files from a backup
+    Restore(EditRestoreCommand),
+}
+
+/// Preview and apply a unified diff
+#[derive(Args, Debug)]
+pub struct EditApplyCommand {
+    /// Patch file (reads stdin when omitted or "-")
+    pub patch: Option<PathBuf>,
+
+    /// Apply without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Show the preview without changing any files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl EditApplyCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing edit apply command");
+        tracing::debug!("Edit apply options: {:?}", self);
+
+        let text = self.read_patch()?;
+        let patches = parse_patch(&text)?;
+        // Fails before anything is written if any hunk does not apply
+        let changes = plan_changes(&config.workspace, &patches)?;
+
+        if !self.json {
+            eprint!("{}", render_preview(&patches, !config.no_color));
+        }
+
+        let applied = if self.dry_run {
+            None
+        } else if self.yes || confirm(&format!("Apply changes to {} file(s)?", changes.len()))? {
+            Some(apply_changes(&config.workspace, &changes)?)
+        } else {
+            eprintln!("Aborted; no files were changed.");
+            None
+        };
+
+        if self.json {
+            let output = serde_json::json!({
+                "files": changes.iter().map(|c| serde_json::json!({
+                    "path": c.path,
+                    "kind": c.kind,
+                    "additions": c.additions,
+                    "deletions": c.deletions
+                })).collect::<Vec<_>>(),
+                "applied": applied.is_some(),
+                "dryRun": self.dry_run,
+                "backupId": applied.as_ref().map(|r| &r.backup_id)
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else if let Some(report) = applied {
+            println!(
+                "Applied changes to {} file(s). Backup: {}",
+                report.files.len(),
+                report.backup_id
+            );
+            println!("Undo with `guided edit restore {}`.", report.backup_id);
+        } else if self.dry_run {
+            println!("Dry run; no files were changed.");
+        }
+
+        Ok(())
+    }
+
+    fn read_patch(&self) -> AppResult<String> {
+        match self.patch {
+            Some(ref path) if path.as_os_str() != "-" => Ok(std::fs::read_to_string(path)
+                .map_err(|e| AppError::Edit(format!("Cannot read patch {:?}: {}", path, e)))?),
+            _ => {
+                let mut text = String::new();
+                std::io::stdin().read_to_string(&mut text)?;
+                Ok(text)
+            }
+        }
+    }
+}
+
+/// List backups created by applied patches
+#[derive(Args, Debug)]
+pub struct EditBackupsCommand {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl EditBackupsCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing edit backups command");
+
+        let backups = list_backups(&config.workspace)?;
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&backups)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        if backups.is_empty() {
+            println!("No backups found.");
+            return Ok(());
+        }
+
+        for backup in &backups {
+            println!(
+                "{}  {}  {} file(s)",
+                backup.id,
+                backup.created_at,
+                backup.entries.len()
+            );
+            for entry in &backup.entries {
+                println!("    {:?} {}", entry.kind, entry.path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Restore files from a backup
+#[derive(Args, Debug)]
+pub struct EditRestoreCommand {
+    /// Backup ID (see `guided edit backups`)
+    pub id: String,
+
+    /// Restore without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl EditRestoreCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing edit restore command for {}", self.id);
+
+        if !self.yes && !confirm(&format!("Restore backup {}?", self.id))? {
+            eprintln!("Aborted; no files were changed.");
+            return Ok(());
+        }
+
+        let manifest = restore_backup(&config.workspace, &self.id)?;
+        println!(
+            "Restored {} file(s) from backup {}",
+            manifest.entries.len(),
+            manifest.id
+        );
+
+        Ok(())
+    }
+}
+
+impl EditCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        match &self.action {
+            EditAction::Apply(cmd) => cmd.execute(config).await,
+            EditAction::Backups(cmd) => cmd.execute(config).await,
+            EditAction::Restore(cmd) => cmd.execute(config).await,
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Git command handler.
+//!
+//! Generates commit messages and pull request descriptions from the
+//! current git changes.
+
+use super::ask::{create_llm_client, MAX_DIFF_CONTEXT_BYTES};
+use super::runs::record_run;
+use clap::{Args, Subcommand};
+use guided_core::{config::AppConfig, git, AppError, AppResult};
+use guided_llm::{fit_prompt, LlmRequest, LlmResponse, RunRecord};
+use guided_prompt::{build_prompt, context_variables, load_prompt, rerender};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Git workflow helpers (commit messages, PR descriptions)
+#[derive(Args, Debug)]
+pub struct GitCommand {
+    #[command(subcommand)]
+    pub action: GitAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GitAction {
+    /// Generate a commit message from staged changes
+    CommitMsg(GitCommitMsgCommand),
+    /// Generate a pull request description for the current branch
+    PrDescription(GitPrDescriptionCommand),
+}
+
+/// Generate a commit message from staged changes
+#[derive(Args, Debug)]
+pub struct GitCommitMsgCommand {
+    /// Use unstaged working tree changes instead of staged ones
+    #[arg(long)]
+    pub unstaged: bool,
+
+    /// Format the message as a Conventional Commit
+    #[arg(long)]
+    pub conventional: bool,
+
+    /// Conventional commit type (implies --conventional)
+    #[arg(long = "type", value_parser = clap::builder::PossibleValuesParser::new(git::CONVENTIONAL_TYPES))]
+    pub commit_type: Option<String>,
+
+    /// Conventional commit scope (implies --conventional)
+    #[arg(long)]
+    pub scope: Option<String>,
+
+    /// Write the message to .git/COMMIT_EDITMSG
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl GitCommitMsgCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing git commit-msg command");
+        tracing::debug!("Git commit-msg options: {:?}", self);
+
+        let staged = !self.unstaged;
+        let raw = git::working_tree_diff(&config.workspace, staged)?;
+        let files = git::parse_diff(&raw);
+
+        if files.is_empty() {
+            return Err(AppError::Git(format!(
+                "No {} changes found",
+                if staged { "staged" } else { "unstaged" }
+            )));
+        }
+
+        let conventional = self.is_conventional();
+
+        let mut variables = HashMap::new();
+        variables.insert(
+            "diffContext".to_string(),
+            git::format_diff_context(&files, MAX_DIFF_CONTEXT_BYTES),
+        );
+        variables.insert("fileSummary".to_string(), format_file_summary(&files));
+        if conventional {
+            variables.insert("conventional".to_string(), "true".to_string());
+        }
+        if let Some(ref commit_type) = self.commit_type {
+            variables.insert("commitType".to_string(), commit_type.clone());
+        }
+        if let Some(ref scope) = self.scope {
+            variables.insert("scope".to_string(), scope.clone());
+        }
+
+        let response = generate(config, "agent.git.commit-msg", variables).await?;
+
+        // Only force a prefix when the type is known; otherwise trust the model
+        let message = git::format_commit_message(
+            &response.content,
+            self.commit_type.as_deref(),
+            self.scope.as_deref(),
+        );
+
+        if conventional {
+            let header = message.lines().next().unwrap_or_default();
+            if !git::is_conventional_header(header) {
+                tracing::warn!("Generated header is not a conventional commit: {}", header);
+            }
+        }
+
+        let applied = if self.apply {
+            Some(git::write_commit_message(&config.workspace, &message)?)
+        } else {
+            None
+        };
+
+        if self.json {
+            let output = serde_json::json!({
+                "message": message,
+                "files": files.iter().map(|f| &f.path).collect::<Vec<_>>(),
+                "staged": staged,
+                "conventional": conventional,
+                "appliedTo": applied,
+                "model": response.model,
+                "provider": config.provider,
+                "usage": {
+                    "promptTokens": response.usage.prompt_tokens,
+                    "completionTokens": response.usage.completion_tokens,
+                    "totalTokens": response.usage.total_tokens
+                }
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else {
+            println!("{}", message);
+
+            if let Some(path) = applied {
+                eprintln!();
+                eprintln!("Wrote commit message to {}", path.display());
+                eprintln!(
+                    "Run `git commit -e -F {}` to review and commit.",
+                    path.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_conventional(&self) -> bool {
+        self.conventional || self.commit_type.is_some() || self.scope.is_some()
+    }
+}
+
+/// Generate a pull request description for the current branch
+#[derive(Args, Debug)]
+pub struct GitPrDescriptionCommand {
+    /// Base branch or ref to compare against
+    #[arg(long, default_value = "main")]
+    pub base: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl GitPrDescriptionCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing git pr-description command against {}", self.base);
+        tracing::debug!("Git pr-description options: {:?}", self);
+
+        let raw = git::branch_diff(&config.workspace, &self.base)?;
+        let files = git::parse_diff(&raw);
+
+        if files.is_empty() {
+            return Err(AppError::Git(format!(
+                "No changes found between {} and HEAD",
+                self.base
+            )));
+        }
+
+        let commits = git::commit_log(&config.workspace, &self.base)?;
+
+        let mut variables = HashMap::new();
+        variables.insert("base".to_string(), self.base.clone());
+        variables.insert(
+            "diffContext".to_string(),
+            git::format_diff_context(&files, MAX_DIFF_CONTEXT_BYTES),
+        );
+        variables.insert("fileSummary".to_string(), format_file_summary(&files));
+        variables.insert(
+            "commitLog".to_string(),
+            commits
+                .iter()
+                .map(|c| format!("- {}", c))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let response = generate(config, "agent.git.pr-description", variables).await?;
+        let description = response.content.trim();
+
+        if self.json {
+            let output = serde_json::json!({
+                "description": description,
+                "base": self.base,
+                "commits": commits,
+                "files": files.iter().map(|f| &f.path).collect::<Vec<_>>(),
+                "model": response.model,
+                "provider": config.provider,
+                "usage": {
+                    "promptTokens": response.usage.prompt_tokens,
+                    "completionTokens": response.usage.completion_tokens,
+                    "totalTokens": response.usage.total_tokens
+                }
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else {
+            println!("{}", description);
+        }
+
+        Ok(())
+    }
+}
+
+impl GitCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        match &self.action {
+            GitAction::CommitMsg(cmd) => cmd.execute(config).await,
+            GitAction::PrDescription(cmd) => cmd.execute(config).await,
+        }
+    }
+}
+
+/// Render a prompt definition and run a non-streaming completion.
+async fn generate(
+    config: &AppConfig,
+    prompt_id: &str,
+    variables: HashMap<String, String>,
+) -> AppResult<LlmResponse> {
+    let mut prompt_def = load_prompt(&config.workspace, prompt_id)?;
+    prompt_def.behavior.inherit(&config.persona);
+    let built_prompt = build_prompt(&prompt_def, variables, &config.workspace, None)?;
+
+    let client = create_llm_client(config)?;
+
+    let generation = config.generation(&config.provider);
+    generation.validate()?;
+    let mut request =
+        LlmRequest::new(built_prompt.user, &config.model).with_generation(&generation);
+    if let Some(system) = built_prompt.system {
+        request = request.with_system(system);
+    }
+
+    let mut window = config.context_window(&config.provider);
+    if let Some(strategy) = prompt_def.context.overflow {
+        window.overflow = strategy;
+    }
+    let (request, overflow) = fit_prompt(
+        client.as_ref(),
+        &window,
+        request,
+        context_variables(&built_prompt.metadata),
+        |context| rerender(&prompt_def, &built_prompt.metadata, context),
+    )
+    .await?;
+
+    let started = Instant::now();
+    let response = client.complete(&request).await?;
+    record_run(
+        config,
+        RunRecord::new("git", &config.provider, &request)
+            .with_prompt_id(prompt_id)
+            .with_context_overflow(overflow)
+            .finish(&response.content, response.usage.clone(), started.elapsed()),
+    );
+
+    Ok(response)
+}
+
+/// Summarize changed files as "- path (+added -removed)" lines.
+fn format_file_summary(files: &[git::FileDiff]) -> String {
+    files
+        .iter()
+        .map(|f| {
+            let additions: usize = f.hunks.iter().map(|h| h.additions()).sum();
+            let deletions: usize = f.hunks.iter().map(|h| h.deletions()).sum();
+
+            let status = if f.is_new {
+                " [new]"
+            } else if f.is_deleted {
+                " [deleted]"
+            } else if f.is_binary {
+                " [binary]"
+            } else {
+                ""
+            };
+
+            match &f.old_path {
+                Some(old) => format!(
+                    "- {} -> {}{} (+{} -{})",
+                    old, f.path, status, additions, deletions
+                ),
+                None => format!("- {}{} (+{} -{})", f.path, status, additions, deletions),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -2,9 +2,27 @@
 //!
 //! Handles local RAG knowledge base management.
 
+use super::ask::{
+    answer_renderer, best_of_summary, copy_to_clipboard, filter_answer, filter_audit_json,
+    print_filter_note, write_answer_file,
+};
+use super::daemon::{KnowledgeDaemonCommand, KnowledgeStatusCommand};
 use clap::{Args, Subcommand};
-use guided_core::{config::AppConfig, AppResult};
-use guided_knowledge::{AskOptions, LearnOptions};
+use guided_core::config::{AppConfig, LearnProfile};
+use guided_core::render::OutputFormat;
+use guided_core::AppResult;
+use guided_knowledge::rag::context::build_context;
+use guided_knowledge::rag::locate::locate;
+use guided_knowledge::rag::{highlight, Boost, Mix, SearchFilters, Verification};
+use guided_knowledge::symbol_index::SymbolIndex;
+use guided_knowledge::{
+    AskOptions, BenchOptions, ChunkSetting, ContentStats, CrawlOptions, DiscoveryOptions,
+    DryRunReport, ImageReader, InlineText, KnowledgeScope, LearnError, LearnOptions, LearnStats,
+    MergeOptions, PostProcessingOverrides, RefreshOptions, SummarizeOptions, TagOptions,
+    Transcriber, TuneOptions,
+};
+use guided_llm::OutputFilter;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 /// Knowledge base management (local RAG)
@@ -17,20 +35,60 @@ pub struct KnowledgeCommand {
 #[derive(Subcommand, Debug)]
 pub enum KnowledgeAction {
     /// Learn from sources (files, URLs, etc.)
-    Learn(KnowledgeLearnCommand),
+    Learn(Box<KnowledgeLearnCommand>),
     /// Query the knowledge base
-    Ask(KnowledgeAskCommand),
+    Ask(Box<KnowledgeAskCommand>),
     /// Clean up knowledge base
     Clean(KnowledgeCleanCommand),
     /// Show knowledge base statistics
     Stats(KnowledgeStatsCommand),
+    /// Find where a function or method is defined
+    Symbol(KnowledgeSymbolCommand),
+    /// List the files and lines that best match a query, without an LLM
+    Locate(KnowledgeLocateCommand),
+    /// Edit the tags and description of a learned source
+    Tag(KnowledgeTagCommand),
+    /// Copy the chunks and sources of other bases into one
+    Merge(KnowledgeMergeCommand),
+    /// Copy a base under a new name
+    Clone(KnowledgeCloneCommand),
+    /// Rename a base
+    Rename(KnowledgeRenameCommand),
+    /// Compare chunk settings on a sample before a full learn
+    Tune(KnowledgeTuneCommand),
+    /// Learn the new entries of the feeds the base subscribes to
+    Refresh(KnowledgeRefreshCommand),
+    /// Summarize sources and directories for hierarchical ask
+    Summarize(KnowledgeSummarizeCommand),
+    /// Rate the last answer, or report on the ratings
+    Feedback(KnowledgeFeedbackCommand),
+    /// Run a quick local performance profile of chunking, embedding and search
+    Bench(KnowledgeBenchCommand),
+    /// Run the learn profiles on their schedule in the background
+    Daemon(KnowledgeDaemonCommand),
+    /// Show what the knowledge daemon is doing
+    Status(KnowledgeStatusCommand),
 }
 
 /// Learn from sources
 #[derive(Args, Debug)]
 pub struct KnowledgeLearnCommand {
     /// Knowledge base name
-    pub base: String,
+    #[arg(
+        required_unless_present_any = ["profile", "all_profiles"],
+        value_parser = parse_base_name
+    )]
+    pub base: Option<String>,
+
+    /// Run the learn profile of this name from knowledge.profiles in
+    /// .guided/config.yaml; other flags add to it. Files unchanged since
+    /// they were last learned are skipped
+    #[arg(long, conflicts_with_all = ["base", "all_profiles"])]
+    pub profile: Option<String>,
+
+    /// Run every learn profile in .guided/config.yaml, e.g. from cron
+    #[arg(long, conflicts_with_all = ["base", "inline", "resume", "tui"])]
+    pub all_profiles: bool,
 
     /// Paths to learn from
     #[arg(long)]
@@ -40,18 +98,171 @@ pub struct KnowledgeLearnCommand {
     #[arg(long)]
     pub url: Vec<String>,
 
-    /// Include patterns (glob)
+    /// Also learn the pages linked from --url (and its sitemap), obeying
+    /// robots.txt
+    #[arg(long, requires = "url")]
+    pub crawl: bool,
+
+    /// Links to follow away from a --url when crawling
+    #[arg(long, default_value = "2", requires = "crawl")]
+    pub max_depth: u32,
+
+    /// Only crawl pages on the hosts of the --url values
+    #[arg(long, requires = "crawl")]
+    pub same_domain: bool,
+
+    /// Milliseconds to wait between requests to one site when crawling
+    #[arg(long, default_value = "500", requires = "crawl")]
+    pub delay_ms: u64,
+
+    /// Learn text read from standard input as one source (needs --title)
+    #[arg(long, group = "inline", requires = "title")]
+    pub stdin: bool,
+
+    /// Learn this text as one source (needs --title)
+    #[arg(long, group = "inline", requires = "title")]
+    pub text: Option<String>,
+
+    /// Title of the --stdin or --text source
+    #[arg(long, requires = "inline")]
+    pub title: Option<String>,
+
+    /// Tag the --stdin or --text source (repeatable)
+    #[arg(long = "tag", requires = "inline")]
+    pub tags: Vec<String>,
+
+    /// Sync documents from a connector in .guided/connectors/<name>.yaml
+    /// (Confluence, Notion); unchanged documents are skipped
+    #[arg(long)]
+    pub connector: Option<String>,
+
+    /// Subscribe the base to an RSS or Atom feed and learn its entries;
+    /// `guided knowledge refresh` learns the new ones (repeatable)
+    #[arg(long)]
+    pub feed: Vec<String>,
+
+    /// Include patterns (glob), on top of the base config's `include`
     #[arg(long)]
     pub include: Vec<String>,
 
-    /// Exclude patterns (glob)
+    /// Exclude patterns (glob), on top of the base config's `exclude` and
+    /// .guided/ignore
     #[arg(long)]
     pub exclude: Vec<String>,
 
-    /// Reset base before learning
+    /// Follow symbolic links to directories (e.g. shared docs linked into
+    /// the workspace); each file is learned once and link cycles are skipped
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Levels of directories to descend below each --path (0 learns only
+    /// the files directly in it)
+    #[arg(long)]
+    pub depth: Option<usize>,
+
+    /// Do not descend into other file systems mounted below a --path
+    #[arg(long)]
+    pub one_file_system: bool,
+
+    /// Learn PNG, JPEG and SVG diagrams from text made by OCR (tesseract)
+    /// or by a vision model served by Ollama
+    #[arg(long, value_parser = ["tesseract", "ollama"])]
+    pub images: Option<String>,
+
+    /// Vision model for --images ollama
+    #[arg(long, default_value = "llava", requires = "images")]
+    pub vision_model: String,
+
+    /// OCR languages for --images tesseract (e.g. eng+deu)
+    #[arg(long, requires = "images")]
+    pub ocr_languages: Option<String>,
+
+    /// Learn MP3, MP4, M4A and WAV recordings from timestamped transcripts
+    /// made by the local `whisper` command
+    #[arg(long)]
+    pub transcribe: bool,
+
+    /// Whisper model for --transcribe (tiny, base, small, medium, large)
+    #[arg(long, default_value = "base", requires = "transcribe")]
+    pub whisper_model: String,
+
+    /// Spoken language for --transcribe (e.g. en); detected when unset
+    #[arg(long, requires = "transcribe")]
+    pub transcribe_language: Option<String>,
+
+    /// Merge consecutive small chunks and drop the tiniest (the default);
+    /// this and the other chunk post-processing flags are saved to the base
+    /// config
+    #[arg(long, overrides_with = "no_merge_small")]
+    pub merge_small: bool,
+
+    /// Keep small chunks as they were split
+    #[arg(long)]
+    pub no_merge_small: bool,
+
+    /// Split oversized chunks at the end of a sentence
+    #[arg(long, overrides_with = "no_snap_sentences")]
+    pub snap_sentences: bool,
+
+    /// Split oversized chunks at any whitespace (the default)
+    #[arg(long)]
+    pub no_snap_sentences: bool,
+
+    /// Start each chunk with the end of the previous one, up to the base's
+    /// chunk_overlap
+    #[arg(long, overrides_with = "no_overlap_chunks")]
+    pub overlap_chunks: bool,
+
+    /// Do not repeat the end of a chunk in the next (the default)
+    #[arg(long)]
+    pub no_overlap_chunks: bool,
+
+    /// Namespace to store the learned chunks in (e.g. docs, code, tickets)
+    #[arg(long)]
+    pub namespace: Option<String>,
+
+    /// Reset base before learning (only the namespace with --namespace)
     #[arg(long)]
     pub reset: bool,
 
+    /// Continue an interrupted learn, skipping files already indexed
+    #[arg(long, conflicts_with_all = ["reset", "path", "url", "inline", "connector", "feed"])]
+    pub resume: bool,
+
+    /// Where to create a new base: this workspace, or ~/.guided/knowledge
+    /// to share it across workspaces
+    #[arg(long, value_parser = ["workspace", "global"])]
+    pub scope: Option<String>,
+
+    /// Encrypt chunk text and metadata with the key in GUIDED_KNOWLEDGE_KEY
+    /// (new bases, or together with --reset)
+    #[arg(long)]
+    pub encrypt: bool,
+
+    /// Re-embed the existing chunks when the embedding provider, model or
+    /// dimensions changed since the base was built
+    #[arg(long, conflicts_with = "reset")]
+    pub migrate: bool,
+
+    /// Exit with an error when any file or feed could not be learned
+    /// (the others are still learned)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Follow the learn in a full-screen dashboard: phase progress,
+    /// throughput and recent warnings (q cancels)
+    #[arg(long, conflicts_with = "json")]
+    pub tui: bool,
+
+    /// Parse and chunk the files without embedding or indexing them, and
+    /// report what would be learned, which files are excluded and why, and
+    /// how long embedding would take
+    #[arg(
+        long,
+        conflicts_with_all = ["url", "connector", "feed", "reset", "resume", "migrate", "encrypt", "tui"]
+    )]
+    pub dry_run: bool,
+
     /// Output as JSON
     #[arg(long)]
     pub json: bool,
@@ -59,43 +270,67 @@ pub struct KnowledgeLearnCommand {
 
 impl KnowledgeLearnCommand {
     pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
-        tracing::info!("Executing knowledge learn command for base '{}'", self.base);
+        if self.all_profiles {
+            return self.learn_all_profiles(config).await;
+        }
+        match (&self.profile, &self.base) {
+            (Some(name), _) => {
+                let profile = config.knowledge.profile(name)?;
+                self.learn(config, &profile.base, Some(profile)).await
+            }
+            (None, Some(base)) => self.learn(config, base, None).await,
+            (None, None) => Err(guided_core::AppError::Config(
+                "Name a knowledge base, --profile or --all-profiles".to_string(),
+            )),
+        }
+    }
 
-        // Resolve embedding provider/model from LlmConfig or fallback to trigram (fast local)
-        let (provider, model) = if let Some(llm_config) = &config.llm {
-            // Use activeEmbeddingProvider from config
-            let embedding_provider = &llm_config.active_embedding_provider;
-            if let Some(provider_config) = llm_config.providers.get(embedding_provider) {
-                let embedding_model = match provider_config {
-                    guided_core::config::ProviderConfig::OpenAI { embedding_model, .. } => {
-                        embedding_model.clone().unwrap_or_else(|| "text-embedding-3-small".to_string())
-                    }
-                    guided_core::config::ProviderConfig::Ollama { embedding_model, .. } => {
-                        embedding_model.clone().unwrap_or_else(|| "nomic-embed-text".to_string())
-                    }
-                    _ => "trigram-v1".to_string(),
-                };
-                (embedding_provider.clone(), embedding_model)
-            } else {
-                // Fallback if provider not found - use fast local trigram
-                ("trigram".to_string(), "trigram-v1".to_string())
+    /// Run every learn profile, going on after a failed one.
+    async fn learn_all_profiles(&self, config: &AppConfig) -> AppResult<()> {
+        let profiles = &config.knowledge.profiles;
+        if profiles.is_empty() {
+            return Err(guided_core::AppError::Config(
+                "No learn profiles; define them under knowledge.profiles in .guided/config.yaml"
+                    .to_string(),
+            ));
+        }
+
+        let mut failed = Vec::new();
+        for (name, profile) in profiles {
+            if !self.json {
+                println!("Profile {} (base '{}')", name, profile.base);
             }
+            if let Err(e) = self.learn(config, &profile.base, Some(profile)).await {
+                eprintln!("Profile {} failed: {}", name, e);
+                failed.push(name.as_str());
+            }
+            if guided_core::cancel::shutdown_token().is_cancelled() {
+                break;
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
         } else {
-            // Fallback if no llm config - use fast local trigram
-            ("trigram".to_string(), "trigram-v1".to_string())
-        };
+            Err(guided_core::AppError::Knowledge(format!(
+                "{} of {} learn profiles failed: {}",
+                failed.len(),
+                profiles.len(),
+                failed.join(", ")
+            )))
+        }
+    }
 
-        let options = LearnOptions {
-            base_name: self.base.clone(),
-            paths: self.path.clone(),
-            urls: self.url.clone(),
-            include: self.include.clone(),
-            exclude: self.exclude.clone(),
-            reset: self.reset,
-            provider: Some(provider),
-            model: Some(model),
-        };
+    /// Learn into `base` from the flags, and from `profile` if given.
+    async fn learn(
+        &self,
+        config: &AppConfig,
+        base: &str,
+        profile: Option<&LearnProfile>,
+    ) -> AppResult<()> {
+        tracing::info!("Executing knowledge learn command for base '{}'", base);
 
+        let options = self.learn_options(config, base, profile)?;
         let api_key = config.resolve_api_key(&config.provider).ok().flatten();
 
         // Create progress reporter for user-facing output
@@ -108,20 +343,65 @@ impl KnowledgeLearnCommand {
             }))
         };
 
-        let stats = guided_knowledge::learn_with_progress(
-            &config.workspace,
-            &options,
-            api_key.as_deref(),
-            progress_reporter,
-        ).await?;
+        if self.dry_run {
+            let report = guided_knowledge::dry_run(
+                &config.workspace,
+                &options,
+                api_key.as_deref(),
+                progress_reporter,
+            )
+            .await?;
+            print_dry_run(base, &report, self.json);
+            if self.strict && !report.errors.is_empty() {
+                return Err(guided_core::AppError::Knowledge(format!(
+                    "{} of the given sources could not be learned (--strict)",
+                    report.errors.len()
+                )));
+            }
+            return Ok(());
+        }
+
+        let stats = if self.tui {
+            #[cfg(feature = "tui")]
+            {
+                crate::tui::learn(base, &options.cancel, |progress| {
+                    guided_knowledge::learn_with_progress(
+                        &config.workspace,
+                        &options,
+                        api_key.as_deref(),
+                        progress,
+                    )
+                })
+                .await?
+            }
+            #[cfg(not(feature = "tui"))]
+            return Err(tui_unavailable());
+        } else {
+            guided_knowledge::learn_with_progress(
+                &config.workspace,
+                &options,
+                api_key.as_deref(),
+                progress_reporter,
+            )
+            .await?
+        };
 
         if self.json {
             let output = serde_json::json!({
-                "base": self.base,
+                "base": base,
                 "sourcesCount": stats.sources_count,
                 "chunksCount": stats.chunks_count,
                 "bytesProcessed": stats.bytes_processed,
                 "durationSecs": stats.duration_secs,
+                "unchangedCount": stats.unchanged_count,
+                "removedCount": stats.removed_count,
+                "tokensEstimate": stats.content.tokens_estimate,
+                "avgChunkTokens": stats.content.avg_chunk_tokens(),
+                "chunksByType": stats.content.chunks_by_type,
+                "provider": stats.provider,
+                "model": stats.model,
+                "embeddingUsage": stats.embedding_usage,
+                "errors": stats.errors,
             });
             println!("{}", serde_json::to_string_pretty(&output).unwrap());
         } else {
@@ -129,49 +409,588 @@ impl KnowledgeLearnCommand {
                 "Learned {} sources ({} chunks, {} bytes) in {:.2}s",
                 stats.sources_count, stats.chunks_count, stats.bytes_processed, stats.duration_secs
             );
+            print_learned_content(&stats);
+            if options.incremental || options.connector.is_some() || !options.feeds.is_empty() {
+                println!(
+                    "{} unchanged, {} outdated sources removed",
+                    stats.unchanged_count, stats.removed_count
+                );
+            }
+            print_learn_errors(&stats.errors);
+        }
+
+        if self.strict && !stats.errors.is_empty() {
+            return Err(guided_core::AppError::Knowledge(format!(
+                "{} of the given sources could not be learned (--strict)",
+                stats.errors.len()
+            )));
         }
 
         Ok(())
     }
+
+    /// Options to learn into `base` from the flags, and from `profile` if
+    /// given.
+    pub(crate) fn learn_options(
+        &self,
+        config: &AppConfig,
+        base: &str,
+        profile: Option<&LearnProfile>,
+    ) -> AppResult<LearnOptions> {
+        // Resolve embedding provider/model from LlmConfig, falling back to the
+        // best local provider in this build (fastembed, else trigram)
+        let local_default = || {
+            if guided_knowledge::embeddings::FASTEMBED_AVAILABLE {
+                (
+                    "fastembed".to_string(),
+                    guided_knowledge::embeddings::DEFAULT_FASTEMBED_MODEL.to_string(),
+                )
+            } else {
+                ("trigram".to_string(), "trigram-v2".to_string())
+            }
+        };
+        let (provider, model) = if let Some(llm_config) = &config.llm {
+            // Use activeEmbeddingProvider from config
+            let embedding_provider = &llm_config.active_embedding_provider;
+            if let Some(provider_config) = llm_config.providers.get(embedding_provider) {
+                let embedding_model = match provider_config {
+                    guided_core::config::ProviderConfig::OpenAI { embedding_model, .. } => {
+                        embedding_model.clone().unwrap_or_else(|| "text-embedding-3-small".to_string())
+                    }
+                    guided_core::config::ProviderConfig::Ollama { embedding_model, .. } => {
+                        embedding_model.clone().unwrap_or_else(|| "nomic-embed-text".to_string())
+                    }
+                    guided_core::config::ProviderConfig::FastEmbed { embedding_model } => {
+                        embedding_model.clone()
+                    }
+                    _ => "trigram-v2".to_string(),
+                };
+                (embedding_provider.clone(), embedding_model)
+            } else if embedding_provider == "fastembed" {
+                // No provider entry needed for the default model
+                (
+                    "fastembed".to_string(),
+                    guided_knowledge::embeddings::DEFAULT_FASTEMBED_MODEL.to_string(),
+                )
+            } else if embedding_provider == "trigram" {
+                ("trigram".to_string(), "trigram-v2".to_string())
+            } else {
+                // Fallback if provider not found
+                local_default()
+            }
+        } else {
+            // Fallback if no llm config
+            local_default()
+        };
+
+        let scope = self
+            .scope
+            .as_deref()
+            .map(str::parse::<KnowledgeScope>)
+            .transpose()
+            .map_err(guided_core::AppError::Knowledge)?;
+
+        let text = if self.stdin {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+            Some(text)
+        } else {
+            self.text.clone()
+        };
+        let texts = text
+            .map(|text| InlineText {
+                title: self.title.clone().unwrap_or_default(),
+                text,
+                tags: self.tags.clone(),
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+
+        // A profile's sources and patterns come first; flags add to them.
+        // Profiles are re-run on a schedule, so only changed files are learned
+        let incremental = profile.is_some();
+        let profile = profile.cloned().unwrap_or_default();
+        let with_flags = |from_profile: Vec<String>, flags: &[String]| -> Vec<String> {
+            from_profile
+                .into_iter()
+                .chain(flags.iter().cloned())
+                .collect()
+        };
+
+        let options = LearnOptions {
+            base_name: base.to_string(),
+            paths: profile
+                .paths
+                .iter()
+                .map(|path| config.workspace.join(path))
+                .chain(self.path.iter().cloned())
+                .collect(),
+            urls: with_flags(profile.urls, &self.url),
+            crawl: self.crawl.then(|| CrawlOptions {
+                max_depth: self.max_depth,
+                same_domain: self.same_domain,
+                delay: std::time::Duration::from_millis(self.delay_ms),
+            }),
+            texts,
+            connector: self.connector.clone().or(profile.connector),
+            feeds: with_flags(profile.feeds, &self.feed),
+            include: with_flags(profile.include, &self.include),
+            exclude: with_flags(profile.exclude, &self.exclude),
+            discovery: DiscoveryOptions {
+                follow_links: self.follow_links,
+                max_depth: self.depth,
+                same_file_system: self.one_file_system,
+            },
+            post_processing: PostProcessingOverrides {
+                merge_small: toggle(self.merge_small, self.no_merge_small),
+                snap_to_sentences: toggle(self.snap_sentences, self.no_snap_sentences),
+                inject_overlap: toggle(self.overlap_chunks, self.no_overlap_chunks),
+            },
+            images: self.images.as_deref().map(|reader| match reader {
+                "ollama" => ImageReader::Ollama {
+                    model: self.vision_model.clone(),
+                },
+                _ => ImageReader::Tesseract {
+                    languages: self.ocr_languages.clone(),
+                },
+            }),
+            transcribe: self.transcribe.then(|| Transcriber {
+                language: self.transcribe_language.clone(),
+                ..Transcriber::new(&self.whisper_model)
+            }),
+            namespace: self.namespace.clone().or(profile.namespace),
+            reset: self.reset,
+            resume: self.resume,
+            incremental,
+            provider: Some(provider),
+            model: Some(model),
+            scope,
+            encrypt: self.encrypt,
+            migrate: self.migrate,
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
+        };
+        Ok(options)
+    }
+}
+
+/// Retrieve context for a query from an open base, as the prompt context
+/// string (each chunk introduced by its file, heading path and lines, like
+/// `knowledge ask` answers) and the IDs of the chunks used; `None` when
+/// nothing matched.
+///
+/// Shared by the commands that ground a prompt in a knowledge base (ask,
+/// review, testgen), which decide themselves whether a failure is fatal.
+pub(crate) async fn retrieve_context(
+    config: &AppConfig,
+    base: &guided_knowledge::KnowledgeBase,
+    query: String,
+    top_k: Option<u32>,
+) -> AppResult<Option<(String, Vec<String>)>> {
+    let api_key = config.resolve_api_key(&config.provider).ok().flatten();
+    let options = AskOptions {
+        base_name: base.name().to_string(),
+        query,
+        top_k,
+        provider_configs: config.provider_configs(),
+        cancel: guided_core::cancel::shutdown_token(),
+        ..Default::default()
+    };
+
+    let result = base.ask(options, api_key.as_deref()).await?;
+    if result.chunks.is_empty() {
+        return Ok(None);
+    }
+
+    let context = build_context(&result.chunks);
+
+    tracing::debug!(
+        "Retrieved {} chunks ({} bytes) from knowledge base '{}'",
+        result.chunks.len(),
+        context.len(),
+        base.name()
+    );
+
+    Ok(Some((
+        context,
+        result.chunks.iter().map(|c| c.id.clone()).collect(),
+    )))
+}
+
+/// Setting of a `--flag`/`--no-flag` pair; `None` when neither is given.
+fn toggle(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Error for --tui in a build without the `tui` feature.
+#[cfg(not(feature = "tui"))]
+fn tui_unavailable() -> guided_core::AppError {
+    guided_core::AppError::Other(
+        "This build of guided does not include the terminal UI. Rebuild with \
+         `cargo install --path crates/cli --features tui`."
+            .to_string(),
+    )
+}
+
+/// Print what the learned chunks are made of, under the learn summary.
+fn print_learned_content(stats: &LearnStats) {
+    if stats.chunks_count == 0 {
+        return;
+    }
+    println!(
+        "  {} chunks; about {} tokens ({} per chunk)",
+        chunks_by_type(&stats.content),
+        stats.content.tokens_estimate,
+        stats.content.avg_chunk_tokens()
+    );
+    println!("  Embedded with {}/{}", stats.provider, stats.model);
+    let usage = &stats.embedding_usage;
+    if usage.requests > 0 {
+        let tokens = if usage.tokens > 0 {
+            format!(", {} tokens", usage.tokens)
+        } else {
+            String::new()
+        };
+        println!(
+            "  Embedded {} texts ({} characters{}) in {} requests",
+            usage.texts, usage.characters, tokens, usage.requests
+        );
+    }
+}
+
+/// Instructions for an answer in `style`, as a prompt's `behavior.style`
+/// would give them.
+fn answer_style(style: &str) -> Option<String> {
+    guided_prompt::PromptBehavior {
+        style: style.to_string(),
+        ..Default::default()
+    }
+    .system_prompt()
+}
+
+/// Reject base names that cannot name a base directory (e.g., `../../etc`)
+/// before any command runs.
+fn parse_base_name(arg: &str) -> Result<String, String> {
+    guided_knowledge::config::validate_base_name(arg).map_err(|e| match e {
+        guided_core::AppError::Knowledge(message) => message,
+        other => other.to_string(),
+    })?;
+    Ok(arg.to_string())
+}
+
+/// Print the files and feeds a learn skipped, one row each.
+fn print_learn_errors(errors: &[LearnError]) {
+    if errors.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("Failed to learn {} source(s):", errors.len());
+    let width = errors
+        .iter()
+        .map(|error| error.source.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(6, 60);
+    println!("  {:<6} {:<width$} ERROR", "STAGE", "SOURCE", width = width);
+    for error in errors {
+        println!(
+            "  {:<6} {:<width$} {}",
+            error.stage.as_str(),
+            error.source,
+            error.message.lines().next().unwrap_or_default(),
+            width = width
+        );
+    }
+}
+
+/// Print what a learn would do, from `--dry-run`.
+fn print_dry_run(base: &str, report: &DryRunReport, json: bool) {
+    if json {
+        let output = serde_json::json!({
+            "base": base,
+            "dryRun": true,
+            "sourcesCount": report.sources_count,
+            "chunksCount": report.chunks_count,
+            "bytesProcessed": report.bytes_processed,
+            "durationSecs": report.duration_secs,
+            "tokensEstimate": report.content.tokens_estimate,
+            "avgChunkTokens": report.content.avg_chunk_tokens(),
+            "chunksByType": report.content.chunks_by_type,
+            "excluded": report.excluded,
+            "errors": report.errors,
+            "provider": report.provider,
+            "model": report.model,
+            "localProvider": report.local_provider,
+            "embedSecsEstimate": report.embed_secs_estimate,
+            "embedEstimateError": report.embed_estimate_error,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return;
+    }
+
+    println!(
+        "Would learn {} sources ({} chunks, {} bytes) into '{}' (dry run, nothing was written)",
+        report.sources_count, report.chunks_count, report.bytes_processed, base
+    );
+    if report.chunks_count > 0 {
+        println!(
+            "  {} chunks; about {} tokens ({} per chunk)",
+            chunks_by_type(&report.content),
+            report.content.tokens_estimate,
+            report.content.avg_chunk_tokens()
+        );
+    }
+
+    let cost = if report.local_provider {
+        "runs locally, no API cost".to_string()
+    } else {
+        format!(
+            "billed by the provider for about {} tokens",
+            report.content.tokens_estimate
+        )
+    };
+    match (report.embed_secs_estimate, &report.embed_estimate_error) {
+        (Some(secs), _) => println!(
+            "  Embedding with {}/{}: about {:.1}s ({})",
+            report.provider, report.model, secs, cost
+        ),
+        (None, error) => println!(
+            "  Embedding with {}/{}: time unknown, {} ({})",
+            report.provider,
+            report.model,
+            error.as_deref().unwrap_or("provider unavailable"),
+            cost
+        ),
+    }
+
+    if !report.excluded.is_empty() {
+        println!();
+        println!("Excluded {} file(s):", report.excluded.len());
+        let width = report
+            .excluded
+            .iter()
+            .map(|file| file.path.chars().count())
+            .max()
+            .unwrap_or(0)
+            .clamp(4, 60);
+        println!("  {:<width$} REASON", "PATH", width = width);
+        for file in &report.excluded {
+            println!("  {:<width$} {}", file.path, file.reason, width = width);
+        }
+    }
+    print_learn_errors(&report.errors);
+}
+
+/// Chunk counts by file type, largest first ("12 markdown, 3 code").
+fn chunks_by_type(content: &ContentStats) -> String {
+    let mut counts: Vec<(&String, &u32)> = content.chunks_by_type.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    counts
+        .into_iter()
+        .map(|(file_type, chunks)| format!("{} {}", chunks, file_type))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Query knowledge base
 #[derive(Args, Debug)]
 pub struct KnowledgeAskCommand {
     /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
     pub base: String,
 
     /// Query text
     pub query: String,
 
-    /// Number of chunks to retrieve
-    #[arg(short = 'k', long, default_value = "5")]
-    pub top_k: u32,
+    /// Number of chunks to give the LLM [default: the base's
+    /// retrieval.top_k, 5 unless configured]
+    #[arg(short = 'k', long)]
+    pub top_k: Option<u32>,
+
+    /// Number of candidates to retrieve before filtering and re-ranking
+    /// down to --top-k [default: the base's retrieval.candidates]
+    #[arg(long, value_name = "N")]
+    pub candidates: Option<u32>,
+
+    /// Retrieve code and docs with separate searches, this many chunks of
+    /// each, and interleave them (e.g. code:3,docs:2; instead of --top-k)
+    #[arg(long, value_name = "KIND:COUNT,...", conflicts_with = "top_k")]
+    pub mix: Option<Mix>,
+
+    /// Prefer varied chunks over near-duplicates: 0.0 (relevance only) to 1.0
+    #[arg(long)]
+    pub diversity: Option<f32>,
+
+    /// Only search this namespace of the base
+    #[arg(long)]
+    pub namespace: Option<String>,
+
+    /// Only search sources whose path starts with this (e.g. crates/knowledge/)
+    #[arg(long, value_name = "PREFIX")]
+    pub path_prefix: Option<String>,
+
+    /// Only search sources whose path matches this glob (e.g. 'crates/knowledge/**')
+    #[arg(long, value_name = "GLOB")]
+    pub path_glob: Option<String>,
+
+    /// Only search sources with this tag (repeatable); sources pinned with
+    /// `knowledge tag --pin` that have it are always included
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Leave out sources with this tag (repeatable)
+    #[arg(long = "exclude-tag", value_name = "TAG")]
+    pub exclude_tags: Vec<String>,
+
+    /// Leave out sources under this path or matching this glob (repeatable)
+    #[arg(long = "exclude-path", value_name = "PATH")]
+    pub exclude_paths: Vec<String>,
+
+    /// Multiply the scores of chunks whose metadata field has a value, as
+    /// FIELD:VALUE=FACTOR (repeatable; e.g. file_type:markdown=1.2)
+    #[arg(long = "boost", value_name = "FIELD:VALUE=FACTOR")]
+    pub boosts: Vec<Boost>,
+
+    /// Pick sources by their summaries first (run `knowledge summarize` before)
+    #[arg(long)]
+    pub hierarchical: bool,
+
+    /// Always ask the LLM, without reading or writing the answer cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Print the cited snippet under each source, with the query's words
+    /// highlighted
+    #[arg(long)]
+    pub show_snippets: bool,
+
+    /// Open the n-th listed source in $VISUAL or $EDITOR at the cited lines
+    #[arg(long)]
+    pub open: Option<usize>,
+
+    /// Output format (markdown, text, html, json)
+    #[arg(short = 'o', long, default_value = "markdown")]
+    pub format: String,
 
     /// Output as JSON
     #[arg(long)]
     pub json: bool,
+
+    /// Copy the answer to the system clipboard
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Also write the answer to a markdown file, with metadata front matter
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Sample N answers and keep the one an LLM judge rates best
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(2..=guided_llm::best_of::MAX_SAMPLES as i64)
+    )]
+    pub best_of: Option<u32>,
+
+    /// With --best-of, have the judge write a final answer from the samples
+    #[arg(long, requires = "best_of")]
+    pub synthesize: bool,
+
+    /// Answer without an LLM: quote the retrieved sentences that best match
+    /// the question
+    #[arg(long, conflicts_with = "best_of")]
+    pub no_llm: bool,
+
+    /// Have the LLM check each claim of the answer against the retrieved
+    /// chunks and flag the ones they do not support
+    #[arg(long, conflicts_with = "no_llm")]
+    pub verify: bool,
+
+    /// Form of the answer: concise, detailed, bullets, step-by-step, or your
+    /// own words
+    #[arg(long, conflicts_with = "no_llm")]
+    pub style: Option<String>,
+
+    /// Watch the answer stream in next to its sources in a full-screen
+    /// view; it is printed as usual when the view closes
+    #[arg(long, conflicts_with = "json")]
+    pub tui: bool,
 }
 
 impl KnowledgeAskCommand {
     pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
         tracing::info!("Executing knowledge ask command for base '{}'", self.base);
 
+        let format = if self.json {
+            OutputFormat::Json
+        } else {
+            OutputFormat::parse(&self.format)?
+        };
+
         let options = AskOptions {
             base_name: self.base.clone(),
             query: self.query.clone(),
             top_k: self.top_k,
+            candidates: self.candidates,
+            namespace: self.namespace.clone(),
+            filters: SearchFilters {
+                path_prefix: self.path_prefix.clone(),
+                path_glob: self.path_glob.clone(),
+                tags: (!self.tags.is_empty()).then(|| self.tags.clone()),
+                exclude_tags: (!self.exclude_tags.is_empty()).then(|| self.exclude_tags.clone()),
+                exclude_paths: (!self.exclude_paths.is_empty()).then(|| self.exclude_paths.clone()),
+                boosts: self.boosts.clone(),
+                ..Default::default()
+            },
+            diversity: self.diversity,
+            hierarchical: self.hierarchical,
+            mix: self.mix.clone(),
+            cache: !self.no_cache,
+            best_of: self.best_of,
+            synthesize: self.synthesize,
+            extractive: self.no_llm,
+            verify: self.verify,
+            style: self.style.as_deref().and_then(answer_style),
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
         };
 
         let api_key = config.resolve_api_key(&config.provider).ok().flatten();
 
         // Use RAG answering (LLM synthesis)
-        let response = guided_knowledge::rag::ask::ask_rag(
-            &config.workspace,
-            options,
-            &config.provider,
-            api_key.as_deref()
-        ).await?;
+        let mut response = if self.tui {
+            #[cfg(feature = "tui")]
+            {
+                let cancel = options.cancel.clone();
+                crate::tui::ask(&self.query, &cancel, |observer| async move {
+                    guided_knowledge::rag::ask::ask_rag_streaming(
+                        &config.workspace,
+                        options,
+                        &config.provider,
+                        api_key.as_deref(),
+                        observer.as_ref(),
+                    )
+                    .await
+                })
+                .await?
+            }
+            #[cfg(not(feature = "tui"))]
+            return Err(tui_unavailable());
+        } else {
+            guided_knowledge::rag::ask::ask_rag(
+                &config.workspace,
+                options,
+                &config.provider,
+                api_key.as_deref(),
+            )
+            .await?
+        };
 
         // Log diagnostic info
         tracing::debug!(
@@ -181,44 +1000,266 @@ impl KnowledgeAskCommand {
             response.sources.len()
         );
 
-        if self.json {
-            let output = serde_json::to_value(&response)
-                .map_err(|e| guided_core::AppError::Knowledge(format!("JSON serialization failed: {}", e)))?;
+        let filter = OutputFilter::from_app_config(config)?;
+        let filtered = filter_answer(filter.as_ref(), &response.answer).await?;
+        response.answer = filtered.text;
+        if filtered.audit.as_ref().is_some_and(|audit| audit.blocked()) {
+            // Candidates and claims of a withheld answer would show what was withheld
+            response.best_of = None;
+            response.verification = None;
+        } else if let Some(filter) = &filter {
+            if let Some(best) = &mut response.best_of {
+                best.judge = filter.redact(&best.judge).text;
+                for candidate in &mut best.candidates {
+                    *candidate = filter.redact(candidate).text;
+                }
+            }
+            if let Some(verification) = &mut response.verification {
+                for check in &mut verification.claims {
+                    check.claim = filter.redact(&check.claim).text;
+                }
+            }
+        }
+
+        if format == OutputFormat::Json {
+            let mut output = serde_json::to_value(&response).map_err(|e| {
+                guided_core::AppError::Knowledge(format!("JSON serialization failed: {}", e))
+            })?;
+            if let Some(audit) = &filtered.audit {
+                output["outputFilter"] = filter_audit_json(audit)?;
+            }
             println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else if format == OutputFormat::Html {
+            // One page with the answer and its sources
+            let mut document = format!("{}\n\n## Sources\n\n", response.answer.trim_end());
+            for (i, source_ref) in response.sources.iter().enumerate() {
+                document.push_str(&format!(
+                    "{}. `{}` ({})\n",
+                    i + 1,
+                    source_ref.source,
+                    source_ref.location
+                ));
+            }
+            let renderer = answer_renderer(format, config, &self.query);
+            println!("{}", renderer.render(&document));
         } else {
             // Human-readable output
-            println!("Answer:");
-            println!("{}", response.answer);
+            if response.cached {
+                println!("Answer (cached):");
+            } else if response.extractive {
+                println!("Answer (extracted without an LLM):");
+            } else {
+                println!("Answer:");
+            }
+            let renderer = answer_renderer(format, config, "");
+            println!("{}", renderer.render(&response.answer));
             println!();
 
             if response.sources.is_empty() {
                 println!("Sources: (no sources available)");
             } else {
+                let color = std::io::stdout().is_terminal() && !config.no_color;
                 println!("Sources:");
-                for source_ref in &response.sources {
-                    println!("- {} ({})", source_ref.source, source_ref.location);
+                for (i, source_ref) in response.sources.iter().enumerate() {
+                    if source_ref.time_range.is_some() {
+                        println!(
+                            "[{}] {} @ {}",
+                            i + 1,
+                            source_ref.source,
+                            source_ref.location
+                        );
+                    } else {
+                        println!(
+                            "[{}] {} ({})",
+                            i + 1,
+                            source_ref.source,
+                            source_ref.location
+                        );
+                    }
+                    if self.show_snippets {
+                        // Bold yellow marks the query's words on a terminal
+                        let snippet = if color {
+                            highlight::mark(
+                                &source_ref.snippet,
+                                &source_ref.highlights,
+                                "\x1b[1;33m",
+                                "\x1b[0m",
+                            )
+                        } else {
+                            source_ref.snippet.clone()
+                        };
+                        for line in snippet.lines() {
+                            println!("    > {}", line);
+                        }
+                    }
                 }
             }
+            if let Some(verification) = &response.verification {
+                println!();
+                print_verification(verification);
+            }
+            print_filter_note(filtered.audit.as_ref());
+            if let Some(best) = &response.best_of {
+                eprintln!(
+                    "\n{}",
+                    best_of_summary(best.chosen, best.candidates.len(), best.total_tokens)
+                );
+            }
+        }
+
+        if let Some(path) = &self.out {
+            let mut front_matter = serde_yaml::Mapping::new();
+            front_matter.insert("question".into(), self.query.as_str().into());
+            front_matter.insert("knowledgeBase".into(), self.base.as_str().into());
+            if response.extractive {
+                front_matter.insert("extractive".into(), true.into());
+            } else {
+                front_matter.insert("provider".into(), config.provider.as_str().into());
+            }
+            if response.cached {
+                front_matter.insert("cached".into(), true.into());
+            }
+            if let Some(verification) = &response.verification {
+                front_matter.insert("grounded".into(), verification.grounded.into());
+            }
+            if let Some(audit) = &filtered.audit {
+                front_matter.insert("outputFilter".into(), audit.note().into());
+            }
+            let sources: Vec<String> = response
+                .sources
+                .iter()
+                .map(|s| format!("{} ({})", s.source, s.location))
+                .collect();
+            if !sources.is_empty() {
+                front_matter.insert("sources".into(), sources.into());
+            }
+            write_answer_file(path, &response.answer, front_matter)?;
+        }
+        if self.copy {
+            copy_to_clipboard(&response.answer)?;
+        }
+
+        if let Some(n) = self.open {
+            let source_ref = n
+                .checked_sub(1)
+                .and_then(|i| response.sources.get(i))
+                .ok_or_else(|| {
+                    guided_core::AppError::Knowledge(format!(
+                        "--open {}: the answer cites {} source(s)",
+                        n,
+                        response.sources.len()
+                    ))
+                })?;
+            if source_ref.time_range.is_some() {
+                return Err(guided_core::AppError::Knowledge(format!(
+                    "Source '{}' is a recording; play it at {}",
+                    source_ref.source, source_ref.location
+                )));
+            }
+            let path = source_ref.path.as_deref().ok_or_else(|| {
+                guided_core::AppError::Knowledge(format!(
+                    "Source '{}' has no file path to open",
+                    source_ref.source
+                ))
+            })?;
+            open_in_editor(
+                &config.workspace.join(path),
+                source_ref.line_range.map(|(start, _)| start),
+            )?;
         }
 
         Ok(())
     }
 }
 
+/// Open a file in $VISUAL or $EDITOR (vi, or notepad on Windows, if neither
+/// is set), at `line` when the editor supports it.
+fn open_in_editor(path: &std::path::Path, line: Option<usize>) -> AppResult<()> {
+    if !path.is_file() {
+        return Err(guided_core::AppError::Knowledge(format!(
+            "Source file not found: {}",
+            path.display()
+        )));
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    // Allow editors with arguments, e.g. EDITOR="code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let mut command = std::process::Command::new(program);
+    command.args(parts);
+
+    let name = std::path::Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let path_arg = path.display().to_string();
+    match (line, name.as_str()) {
+        (Some(line), "code" | "code-insiders" | "codium" | "cursor") => {
+            command.arg("-g").arg(format!("{}:{}", path_arg, line))
+        }
+        (Some(line), "subl" | "zed" | "hx" | "helix") => {
+            command.arg(format!("{}:{}", path_arg, line))
+        }
+        (
+            Some(line),
+            "vi" | "vim" | "nvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak",
+        ) => command.arg(format!("+{}", line)).arg(&path_arg),
+        _ => command.arg(&path_arg),
+    };
+
+    let status = command.status().map_err(|e| {
+        guided_core::AppError::Knowledge(format!("Failed to start editor '{}': {}", program, e))
+    })?;
+    if !status.success() {
+        return Err(guided_core::AppError::Knowledge(format!(
+            "Editor '{}' exited with {}",
+            program, status
+        )));
+    }
+
+    Ok(())
+}
+
 /// Clean knowledge base
 #[derive(Args, Debug)]
 pub struct KnowledgeCleanCommand {
     /// Knowledge base name
-    pub base: String,
+    #[arg(required_unless_present = "embedding_cache", value_parser = parse_base_name)]
+    pub base: Option<String>,
+
+    /// Only clean this namespace of the base
+    #[arg(long, requires = "base")]
+    pub namespace: Option<String>,
+
+    /// Also delete the workspace embedding cache (shared by all bases)
+    #[arg(long)]
+    pub embedding_cache: bool,
 }
 
 impl KnowledgeCleanCommand {
     pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
-        tracing::info!("Executing knowledge clean command for base '{}'", self.base);
+        tracing::info!("Executing knowledge clean command for base {:?}", self.base);
 
-        guided_knowledge::clean(&config.workspace, &self.base).await?;
+        if let Some(ref base) = self.base {
+            guided_knowledge::clean(&config.workspace, base, self.namespace.as_deref()).await?;
+            match self.namespace {
+                Some(ref namespace) => println!(
+                    "Namespace '{}' of knowledge base '{}' cleaned",
+                    namespace, base
+                ),
+                None => println!("Knowledge base '{}' cleaned", base),
+            }
+        }
 
-        println!("Knowledge base '{}' cleaned", self.base);
+        if self.embedding_cache {
+            let removed = guided_knowledge::embeddings::cache::clear_cache(&config.workspace)?;
+            println!("Embedding cache cleared ({} vectors)", removed);
+        }
 
         Ok(())
     }
@@ -228,6 +1269,7 @@ impl KnowledgeCleanCommand {
 #[derive(Args, Debug)]
 pub struct KnowledgeStatsCommand {
     /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
     pub base: String,
 
     /// Output as JSON
@@ -247,13 +1289,35 @@ impl KnowledgeStatsCommand {
                 "sourcesCount": stats.sources_count,
                 "chunksCount": stats.chunks_count,
                 "dbSizeBytes": stats.db_size_bytes,
+                "tokensEstimate": stats.content.tokens_estimate,
+                "avgChunkTokens": stats.content.avg_chunk_tokens(),
+                "chunksByType": stats.content.chunks_by_type,
+                "provider": stats.provider,
+                "model": stats.model,
                 "lastLearnAt": stats.last_learn_at,
+                "scope": stats.scope,
+                "baseDir": stats.base_dir,
             });
             println!("{}", serde_json::to_string_pretty(&output).unwrap());
         } else {
             println!("Knowledge base: {}", stats.base_name);
+            println!("  Scope: {} ({})", stats.scope, stats.base_dir.display());
             println!("  Sources: {}", stats.sources_count);
-            println!("  Chunks: {}", stats.chunks_count);
+            if stats.content.chunks_by_type.is_empty() {
+                println!("  Chunks: {}", stats.chunks_count);
+            } else {
+                println!(
+                    "  Chunks: {} ({})",
+                    stats.chunks_count,
+                    chunks_by_type(&stats.content)
+                );
+            }
+            println!(
+                "  Tokens: about {} ({} per chunk)",
+                stats.content.tokens_estimate,
+                stats.content.avg_chunk_tokens()
+            );
+            println!("  Embeddings: {}/{}", stats.provider, stats.model);
             println!("  DB size: {} bytes", stats.db_size_bytes);
             if let Some(last_learn) = stats.last_learn_at {
                 println!("  Last learn: {}", last_learn);
@@ -264,6 +1328,847 @@ impl KnowledgeStatsCommand {
     }
 }
 
+/// Find where a function or method is defined
+#[derive(Args, Debug)]
+pub struct KnowledgeSymbolCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Symbol name, plain or qualified (e.g., `parse` or `Parser::parse`)
+    pub name: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeSymbolCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge symbol command for '{}' in base '{}'",
+            self.name,
+            self.base
+        );
+
+        let symbols = SymbolIndex::load(&config.workspace, &self.base)?;
+        if symbols.is_empty() {
+            return Err(guided_core::AppError::Knowledge(format!(
+                "Knowledge base '{}' has no symbol index; learn its source files to build one",
+                self.base
+            )));
+        }
+        let found = symbols.lookup(&self.name);
+        if found.is_empty() {
+            return Err(guided_core::AppError::Knowledge(format!(
+                "No function or method named '{}' in knowledge base '{}'",
+                self.name, self.base
+            )));
+        }
+
+        if self.json {
+            let output: Vec<_> = found
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "name": entry.definition.name,
+                        "qualifiedName": entry.definition.qualified_name,
+                        "kind": entry.definition.kind,
+                        "signature": entry.definition.signature,
+                        "path": entry.path,
+                        "startLine": entry.definition.line_range.0,
+                        "endLine": entry.definition.line_range.1,
+                        "namespace": entry.namespace,
+                        "chunkId": entry.chunk_id,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            return Ok(());
+        }
+
+        for entry in found {
+            let (start, end) = entry.definition.line_range;
+            println!(
+                "{}:{}-{}  {}",
+                entry.path, start, end, entry.definition.qualified_name
+            );
+            println!("    {}", entry.definition.signature);
+        }
+
+        Ok(())
+    }
+}
+
+/// Print whether the sources support an answer, listing the claims they
+/// do not.
+fn print_verification(verification: &Verification) {
+    let unsupported: Vec<_> = verification.unsupported().collect();
+    if verification.grounded {
+        println!(
+            "Verification: grounded ({} claims supported by the sources)",
+            verification.claims.len()
+        );
+    } else if verification.claims.is_empty() {
+        println!("Verification: the verifier found no claims to check");
+    } else {
+        println!(
+            "Verification: {} of {} claims not supported by the sources:",
+            unsupported.len(),
+            verification.claims.len()
+        );
+        for claim in unsupported {
+            println!("  - {} (support {:.1})", claim.claim, claim.support);
+        }
+    }
+}
+
+/// List the files and lines that best match a query, without an LLM
+#[derive(Args, Debug)]
+pub struct KnowledgeLocateCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Query text
+    pub query: String,
+
+    /// Number of matches to list
+    #[arg(short = 'k', long, default_value = "10")]
+    pub top_k: u32,
+
+    /// Only search this namespace of the base
+    #[arg(long)]
+    pub namespace: Option<String>,
+
+    /// Only search sources whose path starts with this (e.g. crates/knowledge/)
+    #[arg(long, value_name = "PREFIX")]
+    pub path_prefix: Option<String>,
+
+    /// Only search sources whose path matches this glob (e.g. 'crates/knowledge/**')
+    #[arg(long, value_name = "GLOB")]
+    pub path_glob: Option<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeLocateCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge locate command for base '{}'",
+            self.base
+        );
+
+        let options = AskOptions {
+            base_name: self.base.clone(),
+            query: self.query.clone(),
+            top_k: Some(self.top_k),
+            namespace: self.namespace.clone(),
+            filters: SearchFilters {
+                path_prefix: self.path_prefix.clone(),
+                path_glob: self.path_glob.clone(),
+                ..Default::default()
+            },
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
+            ..Default::default()
+        };
+        let api_key = config.resolve_api_key(&config.provider).ok().flatten();
+        let result = guided_knowledge::ask(&config.workspace, options, api_key.as_deref()).await?;
+        let locations = locate(&config.workspace, &result.chunks, &result.scores);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&locations).unwrap());
+            return Ok(());
+        }
+
+        if locations.is_empty() {
+            println!("No matching lines found");
+        }
+        for location in &locations {
+            println!(
+                "{}:{}-{}  ({:.3})",
+                location.path, location.start_line, location.end_line, location.score
+            );
+            if let Some(line) = location.snippet.lines().next() {
+                println!("    {}", line);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Edit the tags and description of a learned source
+#[derive(Args, Debug)]
+pub struct KnowledgeTagCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Learned file, as given to learn or as a path suffix
+    pub path: String,
+
+    /// Tags to add
+    #[arg(long)]
+    pub add: Vec<String>,
+
+    /// Tags to remove, including tags derived from directory names
+    #[arg(long)]
+    pub remove: Vec<String>,
+
+    /// Description of the source ("" clears it)
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Multiply the source's scores by this; above 1 for authoritative
+    /// sources (1 clears it)
+    #[arg(long)]
+    pub weight: Option<f32>,
+
+    /// Include the source in answers to every query filtering on one of its
+    /// tags (ask --tag)
+    #[arg(long, conflicts_with = "unpin")]
+    pub pin: bool,
+
+    /// Stop pinning the source
+    #[arg(long)]
+    pub unpin: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeTagCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge tag command for '{}' in base '{}'",
+            self.path,
+            self.base
+        );
+
+        let options = TagOptions {
+            base_name: self.base.clone(),
+            path: self.path.clone(),
+            add: self.add.clone(),
+            remove: self.remove.clone(),
+            description: self.description.clone(),
+            weight: self.weight,
+            pinned: (self.pin || self.unpin).then_some(self.pin),
+        };
+        let result = guided_knowledge::tag(&config.workspace, &options).await?;
+
+        if self.json {
+            let output = serde_json::json!({
+                "base": self.base,
+                "path": result.source.path,
+                "tags": result.tags,
+                "addedTags": result.source.added_tags,
+                "removedTags": result.source.removed_tags,
+                "description": result.source.description,
+                "weight": result.source.weight,
+                "pinned": result.source.pinned,
+                "chunksUpdated": result.chunks_updated,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            println!("Source: {}", result.source.path);
+            println!("  Tags: {}", result.tags.join(", "));
+            if let Some(ref description) = result.source.description {
+                println!("  Description: {}", description);
+            }
+            if let Some(weight) = result.source.weight {
+                println!("  Weight: {}", weight);
+            }
+            if result.source.pinned {
+                println!("  Pinned: yes");
+            }
+            println!("  Chunks updated: {}", result.chunks_updated);
+        }
+
+        Ok(())
+    }
+}
+
+/// Copy the chunks and sources of other bases into one
+#[derive(Args, Debug)]
+pub struct KnowledgeMergeCommand {
+    /// Knowledge base to merge into (created if it does not exist)
+    #[arg(value_parser = parse_base_name)]
+    pub target: String,
+
+    /// Knowledge bases to merge, embedded with the target's provider and model
+    #[arg(required = true, value_parser = parse_base_name)]
+    pub sources: Vec<String>,
+
+    /// Where to create a new target: this workspace, or ~/.guided/knowledge
+    /// to share it across workspaces
+    #[arg(long, value_parser = ["workspace", "global"])]
+    pub scope: Option<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeMergeCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge merge command into base '{}'",
+            self.target
+        );
+
+        let options = MergeOptions {
+            target: self.target.clone(),
+            sources: self.sources.clone(),
+            scope: self
+                .scope
+                .as_deref()
+                .map(str::parse::<KnowledgeScope>)
+                .transpose()
+                .map_err(guided_core::AppError::Knowledge)?,
+        };
+        let stats = guided_knowledge::merge(&config.workspace, &options).await?;
+
+        if self.json {
+            let output = serde_json::json!({
+                "base": self.target,
+                "created": stats.created,
+                "sourcesAdded": stats.sources_added,
+                "chunksAdded": stats.chunks_added,
+                "duplicateSources": stats.duplicate_sources,
+                "duplicateChunks": stats.duplicate_chunks,
+                "durationSecs": stats.duration_secs,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            if stats.created {
+                println!("Created knowledge base '{}'", self.target);
+            }
+            println!(
+                "Merged {} sources ({} chunks) into '{}' in {:.2}s",
+                stats.sources_added, stats.chunks_added, self.target, stats.duration_secs
+            );
+            if stats.duplicate_sources > 0 {
+                println!(
+                    "{} sources ({} chunks) skipped: '{}' already has their content",
+                    stats.duplicate_sources, stats.duplicate_chunks, self.target
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Copy a base under a new name
+#[derive(Args, Debug)]
+pub struct KnowledgeCloneCommand {
+    /// Knowledge base to copy
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Name of the copy
+    #[arg(value_parser = parse_base_name)]
+    pub new_name: String,
+
+    /// Where to put the copy: this workspace, or ~/.guided/knowledge
+    /// (defaults to where the base is)
+    #[arg(long, value_parser = ["workspace", "global"])]
+    pub scope: Option<String>,
+}
+
+impl KnowledgeCloneCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing knowledge clone command for base '{}'", self.base);
+
+        let scope = self
+            .scope
+            .as_deref()
+            .map(str::parse::<KnowledgeScope>)
+            .transpose()
+            .map_err(guided_core::AppError::Knowledge)?;
+        let dir =
+            guided_knowledge::clone_base(&config.workspace, &self.base, &self.new_name, scope)?;
+        println!(
+            "Knowledge base '{}' cloned to '{}' ({})",
+            self.base,
+            self.new_name,
+            dir.display()
+        );
+
+        Ok(())
+    }
+}
+
+/// Rename a base
+#[derive(Args, Debug)]
+pub struct KnowledgeRenameCommand {
+    /// Knowledge base to rename
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// New name
+    #[arg(value_parser = parse_base_name)]
+    pub new_name: String,
+}
+
+impl KnowledgeRenameCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge rename command for base '{}'",
+            self.base
+        );
+
+        guided_knowledge::rename_base(&config.workspace, &self.base, &self.new_name)?;
+        println!(
+            "Knowledge base '{}' renamed to '{}'",
+            self.base, self.new_name
+        );
+
+        Ok(())
+    }
+}
+
+/// Compare chunk settings on a sample before a full learn
+#[derive(Args, Debug)]
+pub struct KnowledgeTuneCommand {
+    /// Knowledge base to tune (its provider and model are used)
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Sample files or directories to learn at every setting
+    #[arg(long, required = true)]
+    pub path: Vec<PathBuf>,
+
+    /// Query set: a YAML or JSON list of `query` and `expected` source paths
+    #[arg(long, value_name = "FILE")]
+    pub queries: PathBuf,
+
+    /// Chunk setting to try, as SIZE:OVERLAP (repeatable; defaults to
+    /// 256:32, 512:64 and 1024:128)
+    #[arg(long = "setting", value_name = "SIZE:OVERLAP")]
+    pub settings: Vec<ChunkSetting>,
+
+    /// Number of chunks retrieved per question
+    #[arg(short = 'k', long, default_value = "5")]
+    pub top_k: u32,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeTuneCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing knowledge tune command for base '{}'", self.base);
+
+        let options = TuneOptions {
+            base_name: self.base.clone(),
+            paths: self.path.clone(),
+            cases: guided_knowledge::tune::load_cases(&self.queries)?,
+            settings: self.settings.clone(),
+            top_k: self.top_k,
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
+        };
+        let result = guided_knowledge::tune(&config.workspace, &options).await?;
+
+        if self.json {
+            let scores: Vec<_> = result
+                .scores
+                .iter()
+                .map(|score| {
+                    serde_json::json!({
+                        "chunkSize": score.setting.chunk_size,
+                        "chunkOverlap": score.setting.chunk_overlap,
+                        "chunksCount": score.chunks_count,
+                        "recall": score.recall,
+                        "mrr": score.mrr,
+                        "learnSecs": score.learn_secs,
+                    })
+                })
+                .collect();
+            let output = serde_json::json!({
+                "base": self.base,
+                "questions": options.cases.len(),
+                "topK": self.top_k,
+                "scores": scores,
+                "best": {
+                    "chunkSize": result.best.chunk_size,
+                    "chunkOverlap": result.best.chunk_overlap,
+                },
+                "durationSecs": result.duration_secs,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            println!(
+                "{:<12} {:>8} {:>8} {:>8} {:>10}",
+                "SETTING", "CHUNKS", "RECALL", "MRR", "LEARN"
+            );
+            for score in &result.scores {
+                let best = if score.setting == result.best {
+                    "  *"
+                } else {
+                    ""
+                };
+                println!(
+                    "{:<12} {:>8} {:>8.3} {:>8.3} {:>9.2}s{}",
+                    score.setting.to_string(),
+                    score.chunks_count,
+                    score.recall,
+                    score.mrr,
+                    score.learn_secs,
+                    best
+                );
+            }
+            println!(
+                "Best: chunk_size {}, chunk_overlap {} ({} questions, top {})",
+                result.best.chunk_size,
+                result.best.chunk_overlap,
+                options.cases.len(),
+                self.top_k
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn the new entries of a base's feeds
+#[derive(Args, Debug)]
+pub struct KnowledgeRefreshCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeRefreshCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge refresh command for base '{}'",
+            self.base
+        );
+
+        let options = RefreshOptions {
+            base_name: self.base.clone(),
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
+        };
+
+        let progress_reporter = if self.json {
+            guided_knowledge::ProgressReporter::noop()
+        } else {
+            use std::sync::Arc;
+            guided_knowledge::ProgressReporter::new(Arc::new(|event| {
+                eprintln!("{}", event.format_simple());
+            }))
+        };
+
+        let stats =
+            guided_knowledge::refresh_with_progress(&config.workspace, &options, progress_reporter)
+                .await?;
+
+        if self.json {
+            let output = serde_json::json!({
+                "base": self.base,
+                "sourcesCount": stats.sources_count,
+                "chunksCount": stats.chunks_count,
+                "bytesProcessed": stats.bytes_processed,
+                "durationSecs": stats.duration_secs,
+                "unchangedCount": stats.unchanged_count,
+                "removedCount": stats.removed_count,
+                "tokensEstimate": stats.content.tokens_estimate,
+                "avgChunkTokens": stats.content.avg_chunk_tokens(),
+                "chunksByType": stats.content.chunks_by_type,
+                "provider": stats.provider,
+                "model": stats.model,
+                "embeddingUsage": stats.embedding_usage,
+                "errors": stats.errors,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            println!(
+                "Learned {} new or changed entries ({} chunks) in {:.2}s",
+                stats.sources_count, stats.chunks_count, stats.duration_secs
+            );
+            print_learned_content(&stats);
+            println!(
+                "{} entries unchanged, {} outdated entries removed",
+                stats.unchanged_count, stats.removed_count
+            );
+            print_learn_errors(&stats.errors);
+        }
+
+        Ok(())
+    }
+}
+
+/// Summarize sources and directories for hierarchical ask
+#[derive(Args, Debug)]
+pub struct KnowledgeSummarizeCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeSummarizeCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!(
+            "Executing knowledge summarize command for base '{}'",
+            self.base
+        );
+
+        let options = SummarizeOptions {
+            base_name: self.base.clone(),
+            llm_provider: config.provider.clone(),
+            llm_model: config.model.clone(),
+            provider_configs: config.provider_configs(),
+            cancel: guided_core::cancel::shutdown_token(),
+        };
+        let api_key = config.resolve_api_key(&config.provider).ok().flatten();
+
+        let stats =
+            guided_knowledge::summarize(&config.workspace, &options, api_key.as_deref()).await?;
+
+        if self.json {
+            let output = serde_json::json!({
+                "base": self.base,
+                "sourcesSummarized": stats.sources_summarized,
+                "directoriesSummarized": stats.directories_summarized,
+                "summariesReused": stats.summaries_reused,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            println!(
+                "Summarized {} sources and {} directories ({} summaries unchanged)",
+                stats.sources_summarized, stats.directories_summarized, stats.summaries_reused
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Rate the last answer, or report on the ratings
+#[derive(Args, Debug)]
+pub struct KnowledgeFeedbackCommand {
+    /// Knowledge base name
+    #[arg(value_parser = parse_base_name)]
+    pub base: String,
+
+    /// Rate the last answer of `knowledge ask` on this base
+    #[arg(long, requires = "verdict", required_unless_present = "report")]
+    pub last: bool,
+
+    /// The answer helped
+    #[arg(long, group = "verdict")]
+    pub helpful: bool,
+
+    /// The answer did not help
+    #[arg(long, group = "verdict")]
+    pub not_helpful: bool,
+
+    /// What was wrong or missing
+    #[arg(long, requires = "last")]
+    pub note: Option<String>,
+
+    /// Report sources that keep appearing in unhelpful answers, with settings to try
+    #[arg(long, conflicts_with = "last")]
+    pub report: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeFeedbackCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        use guided_knowledge::rag::feedback;
+
+        tracing::info!(
+            "Executing knowledge feedback command for base '{}'",
+            self.base
+        );
+
+        if self.report {
+            let report = feedback::feedback_report(&config.workspace, &self.base)?;
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                return Ok(());
+            }
+
+            println!(
+                "Ratings: {} helpful, {} not helpful",
+                report.helpful, report.not_helpful
+            );
+            if !report.unhelpful_sources.is_empty() {
+                println!();
+                println!("Sources in unhelpful answers:");
+                for source in &report.unhelpful_sources {
+                    println!(
+                        "  {} ({} unhelpful, {} helpful)",
+                        source.source, source.not_helpful, source.helpful
+                    );
+                }
+            }
+            if !report.suggestions.is_empty() {
+                println!();
+                println!("Suggestions:");
+                for suggestion in &report.suggestions {
+                    println!("  - {}", suggestion);
+                }
+            }
+            return Ok(());
+        }
+
+        let verdict = if self.helpful {
+            feedback::Verdict::Helpful
+        } else {
+            feedback::Verdict::NotHelpful
+        };
+        let entry =
+            feedback::rate_last_answer(&config.workspace, &self.base, verdict, self.note.clone())?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&entry).unwrap());
+        } else {
+            println!(
+                "Recorded {} for \"{}\" ({} chunks)",
+                match verdict {
+                    feedback::Verdict::Helpful => "helpful",
+                    feedback::Verdict::NotHelpful => "not helpful",
+                },
+                entry.answer.query,
+                entry.answer.chunk_ids.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Run a quick local performance profile of chunking, embedding and search
+#[derive(Args, Debug)]
+pub struct KnowledgeBenchCommand {
+    /// Sizes of the indexes to time searches on, in chunks
+    #[arg(long, value_delimiter = ',', default_value = "1000,10000")]
+    pub sizes: Vec<usize>,
+
+    /// Searches timed per index
+    #[arg(long, default_value = "50")]
+    pub queries: usize,
+
+    /// Sections of the generated markdown corpus (about 1 KB each)
+    #[arg(long, default_value = "2000")]
+    pub sections: usize,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl KnowledgeBenchCommand {
+    pub async fn execute(&self) -> AppResult<()> {
+        tracing::info!("Executing knowledge bench command");
+
+        let options = BenchOptions {
+            sections: self.sections,
+            index_sizes: self.sizes.clone(),
+            queries: self.queries,
+        };
+        let report = guided_knowledge::bench(&options).await?;
+
+        if self.json {
+            let chunking: Vec<_> = report
+                .chunking
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "corpus": c.corpus,
+                        "bytes": c.bytes,
+                        "chunks": c.chunks,
+                        "secs": c.secs,
+                        "mbPerSec": c.mb_per_sec,
+                    })
+                })
+                .collect();
+            let search: Vec<_> = report
+                .search
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "indexSize": s.index_size,
+                        "queries": s.queries,
+                        "buildSecs": s.build_secs,
+                        "meanMs": s.mean_ms,
+                        "p50Ms": s.p50_ms,
+                        "p95Ms": s.p95_ms,
+                    })
+                })
+                .collect();
+            let output = serde_json::json!({
+                "chunking": chunking,
+                "embedding": {
+                    "provider": report.embedding.provider,
+                    "model": report.embedding.model,
+                    "texts": report.embedding.texts,
+                    "secs": report.embedding.secs,
+                    "textsPerSec": report.embedding.texts_per_sec,
+                },
+                "search": search,
+                "durationSecs": report.duration_secs,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            println!("Chunking");
+            println!(
+                "  {:<10} {:>10} {:>8} {:>9} {:>10}",
+                "CORPUS", "BYTES", "CHUNKS", "TIME", "MB/S"
+            );
+            for c in &report.chunking {
+                println!(
+                    "  {:<10} {:>10} {:>8} {:>8.3}s {:>10.2}",
+                    c.corpus, c.bytes, c.chunks, c.secs, c.mb_per_sec
+                );
+            }
+            println!(
+                "Embedding ({} {})",
+                report.embedding.provider, report.embedding.model
+            );
+            println!(
+                "  {} texts in {:.3}s ({:.0} texts/s)",
+                report.embedding.texts, report.embedding.secs, report.embedding.texts_per_sec
+            );
+            println!("Search");
+            println!(
+                "  {:<10} {:>9} {:>10} {:>10} {:>10}",
+                "CHUNKS", "BUILD", "MEAN", "P50", "P95"
+            );
+            for s in &report.search {
+                println!(
+                    "  {:<10} {:>8.2}s {:>8.2}ms {:>8.2}ms {:>8.2}ms",
+                    s.index_size, s.build_secs, s.mean_ms, s.p50_ms, s.p95_ms
+                );
+            }
+            println!("Done in {:.2}s", report.duration_secs);
+        }
+
+        Ok(())
+    }
+}
+
 impl KnowledgeCommand {
     pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
         match &self.action {
@@ -271,6 +2176,19 @@ impl KnowledgeCommand {
             KnowledgeAction::Ask(cmd) => cmd.execute(config).await,
             KnowledgeAction::Clean(cmd) => cmd.execute(config).await,
             KnowledgeAction::Stats(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Symbol(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Locate(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Tag(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Merge(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Clone(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Rename(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Tune(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Refresh(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Summarize(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Feedback(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Bench(cmd) => cmd.execute().await,
+            KnowledgeAction::Daemon(cmd) => cmd.execute(config).await,
+            KnowledgeAction::Status(cmd) => cmd.execute(config).await,
         }
     }
 }
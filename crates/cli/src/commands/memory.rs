@@ -0,0 +1,103 @@
+//! Memory command handler.
+//!
+//! Lists and forgets the facts remembered from past `ask` sessions in
+//! `.guided/memory/`.
+
+use clap::{Args, Subcommand};
+use guided_core::{config::AppConfig, AppError, AppResult};
+use guided_knowledge::memory::MemoryStore;
+
+/// Inspect conversation memory
+#[derive(Args, Debug)]
+pub struct MemoryCommand {
+    #[command(subcommand)]
+    pub action: MemoryAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MemoryAction {
+    /// List remembered facts, newest first
+    List(MemoryListCommand),
+    /// Forget a remembered fact, or all of them
+    Forget(MemoryForgetCommand),
+}
+
+/// List remembered facts, newest first
+#[derive(Args, Debug)]
+pub struct MemoryListCommand {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl MemoryListCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing memory list command");
+
+        let memories = MemoryStore::open(&config.workspace).await?.list().await?;
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&memories)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        if memories.is_empty() {
+            println!("Nothing remembered. Enable with `memory: {{ remember: true }}` in .guided/config.yaml or `guided ask --remember`.");
+            return Ok(());
+        }
+
+        for memory in &memories {
+            println!(
+                "{}  {}  {}",
+                &memory.id[..8.min(memory.id.len())],
+                memory.created_at.format("%Y-%m-%d %H:%M"),
+                memory.text
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Forget a remembered fact, or all of them
+#[derive(Args, Debug)]
+pub struct MemoryForgetCommand {
+    /// Memory ID, or an unambiguous prefix of it (see `guided memory list`)
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub id: Option<String>,
+
+    /// Forget every remembered fact
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl MemoryForgetCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing memory forget command");
+
+        let mut store = MemoryStore::open(&config.workspace).await?;
+        match &self.id {
+            Some(id) => {
+                let memory = store.forget(id).await?;
+                println!("Forgot: {}", memory.text);
+            }
+            None => {
+                let count = store.clear().await?;
+                println!("Forgot {} memories", count);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoryCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        match &self.action {
+            MemoryAction::List(cmd) => cmd.execute(config).await,
+            MemoryAction::Forget(cmd) => cmd.execute(config).await,
+        }
+    }
+}
@@ -3,12 +3,25 @@
 //! This module organizes all CLI commands into separate submodules.
 
 pub mod ask;
+pub mod daemon;
+pub mod edit;
+pub mod git;
 pub mod knowledge;
+pub mod memory;
+pub mod review;
+pub mod runs;
 pub mod stats;
 pub mod task;
+pub mod testgen;
 
 // Re-export command types for convenience
 pub use ask::AskCommand;
+pub use edit::EditCommand;
+pub use git::GitCommand;
 pub use knowledge::KnowledgeCommand;
+pub use memory::MemoryCommand;
+pub use review::ReviewCommand;
+pub use runs::RunsCommand;
 pub use stats::StatsCommand;
 pub use task::TaskCommand;
+pub use testgen::TestgenCommand;
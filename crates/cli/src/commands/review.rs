@@ -0,0 +1,279 @@
+//! Review command handler.
+//!
+//! Reviews the diff between two refs file by file, optionally grounded in
+//! knowledge base context, and reports structured findings.
+
+use super::ask::create_llm_client;
+use super::knowledge::retrieve_context;
+use super::runs::record_run;
+use clap::Args;
+use guided_core::{
+    config::AppConfig,
+    git,
+    review::{self, ReviewFinding, Severity},
+    AppError, AppResult,
+};
+use guided_llm::{LlmClient, LlmRequest, RunRecord};
+use guided_prompt::{build_prompt, load_prompt};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Maximum bytes of diff per review request.
+const MAX_REVIEW_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Maximum bytes of a diff chunk used as a knowledge base query.
+const MAX_QUERY_BYTES: usize = 2 * 1024;
+
+/// Review changes between two refs
+#[derive(Args, Debug)]
+pub struct ReviewCommand {
+    /// Base ref to compare against
+    #[arg(long, default_value = "main")]
+    pub base: String,
+
+    /// Head ref to review
+    #[arg(long, default_value = "HEAD")]
+    pub head: String,
+
+    /// Knowledge base to query for related context per file
+    #[arg(short, long)]
+    pub knowledge_base: Option<String>,
+
+    /// Number of knowledge chunks to retrieve per file
+    #[arg(long, default_value = "3")]
+    pub top_k: u32,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Output as SARIF 2.1.0 (for CI annotation)
+    #[arg(long, conflicts_with = "json")]
+    pub sarif: bool,
+}
+
+impl ReviewCommand {
+    /// Execute the review command.
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing review command: {}...{}", self.base, self.head);
+        tracing::debug!("Review options: {:?}", self);
+
+        let raw = git::range_diff(&config.workspace, &self.base, &self.head)?;
+        let files = git::parse_diff(&raw);
+
+        if files.is_empty() {
+            return Err(AppError::Git(format!(
+                "No changes found between {} and {}",
+                self.base, self.head
+            )));
+        }
+
+        let mut prompt_def = load_prompt(&config.workspace, "agent.review")?;
+        prompt_def.behavior.inherit(&config.persona);
+        if self.knowledge_base.is_some() {
+            prompt_def.context.include_knowledge_base = true;
+            prompt_def.context.knowledge_base_name = self.knowledge_base.clone();
+        }
+
+        // Opened once for all files; a base that fails to open leaves the
+        // review without context, like a failed retrieval
+        let knowledge = match &self.knowledge_base {
+            Some(kb_name) => {
+                match guided_knowledge::KnowledgeBase::open(&config.workspace, kb_name).await {
+                    Ok(base) => Some(base.with_provider_configs(config.provider_configs())),
+                    Err(e) => {
+                        tracing::warn!("Failed to open knowledge base '{}': {}", kb_name, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let client = create_llm_client(config)?;
+        let mut findings: Vec<ReviewFinding> = Vec::new();
+        let mut reviewed_files = Vec::new();
+
+        for file in &files {
+            if file.is_binary || file.is_deleted || file.hunks.is_empty() {
+                tracing::debug!("Skipping {} (binary, deleted or empty)", file.path);
+                continue;
+            }
+
+            reviewed_files.push(file.path.clone());
+
+            for part in git::split_file_diff(file, MAX_REVIEW_CHUNK_BYTES) {
+                let diff_context =
+                    git::format_diff_context(std::slice::from_ref(&part), usize::MAX);
+
+                let (knowledge_context, chunk_ids) = match &knowledge {
+                    Some(base) => self
+                        .retrieve_knowledge(config, base, &part.path, &diff_context)
+                        .await
+                        .unzip(),
+                    None => (None, None),
+                };
+
+                let mut variables = HashMap::new();
+                variables.insert("file".to_string(), part.path.clone());
+                variables.insert("diffContext".to_string(), diff_context);
+
+                let built_prompt =
+                    build_prompt(&prompt_def, variables, &config.workspace, knowledge_context)?;
+
+                let part_findings = self
+                    .review_part(
+                        client.as_ref(),
+                        built_prompt.user,
+                        built_prompt.system,
+                        &part.path,
+                        chunk_ids.unwrap_or_default(),
+                        config,
+                    )
+                    .await?;
+                findings.extend(part_findings);
+            }
+        }
+
+        // Most severe first, then by location
+        findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.file.cmp(&b.file))
+                .then_with(|| a.start_line.cmp(&b.start_line))
+        });
+
+        tracing::info!(
+            "Review complete: {} findings across {} files",
+            findings.len(),
+            reviewed_files.len()
+        );
+
+        if self.sarif {
+            let sarif = review::to_sarif(&findings, env!("CARGO_PKG_VERSION"));
+            let json = serde_json::to_string_pretty(&sarif)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else if self.json {
+            let output = serde_json::json!({
+                "base": self.base,
+                "head": self.head,
+                "files": reviewed_files,
+                "findings": findings,
+                "model": config.model,
+                "provider": config.provider
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else {
+            print_findings(&findings, reviewed_files.len());
+        }
+
+        Ok(())
+    }
+
+    /// Run one review request and parse its findings.
+    ///
+    /// Unparseable output is logged and treated as no findings so one bad
+    /// response does not abort the whole review.
+    async fn review_part(
+        &self,
+        client: &dyn LlmClient,
+        prompt: String,
+        system: Option<String>,
+        file: &str,
+        chunk_ids: Vec<String>,
+        config: &AppConfig,
+    ) -> AppResult<Vec<ReviewFinding>> {
+        tracing::info!("Reviewing {}", file);
+
+        let generation = config.generation(&config.provider);
+        generation.validate()?;
+        let mut request = LlmRequest::new(prompt, &config.model)
+            .with_temperature(0.2)
+            .with_generation(&generation);
+        if let Some(system) = system {
+            request = request.with_system(system);
+        }
+        let started = Instant::now();
+        let response = client.complete(&request).await?;
+        record_run(
+            config,
+            RunRecord::new("review", &config.provider, &request)
+                .with_prompt_id("agent.review")
+                .with_chunk_ids(chunk_ids)
+                .finish(&response.content, response.usage.clone(), started.elapsed()),
+        );
+
+        match review::parse_findings(&response.content, file) {
+            Ok(findings) => Ok(findings),
+            Err(e) => {
+                tracing::warn!("Could not parse review findings for {}: {}", file, e);
+                tracing::debug!("Raw review output: {}", response.content);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Retrieve knowledge base context related to a file's changes, with the
+    /// IDs of the chunks used.
+    ///
+    /// Retrieval failures are logged and the file is reviewed without context.
+    async fn retrieve_knowledge(
+        &self,
+        config: &AppConfig,
+        base: &guided_knowledge::KnowledgeBase,
+        file: &str,
+        diff_context: &str,
+    ) -> Option<(String, Vec<String>)> {
+        let mut query = format!("{}\n{}", file, diff_context);
+        if query.len() > MAX_QUERY_BYTES {
+            let mut end = MAX_QUERY_BYTES;
+            while !query.is_char_boundary(end) {
+                end -= 1;
+            }
+            query.truncate(end);
+        }
+
+        match retrieve_context(config, base, query, Some(self.top_k)).await {
+            Ok(context) => context,
+            Err(e) => {
+                tracing::warn!("Knowledge retrieval failed for {}: {}", file, e);
+                None
+            }
+        }
+    }
+}
+
+/// Print findings as plain text.
+fn print_findings(findings: &[ReviewFinding], file_count: usize) {
+    if findings.is_empty() {
+        println!("No findings in {} reviewed files.", file_count);
+        return;
+    }
+
+    for finding in findings {
+        println!(
+            "[{}] {} {}",
+            finding.severity,
+            finding.location(),
+            finding.message
+        );
+        if let Some(ref suggestion) = finding.suggestion {
+            println!("  Suggestion: {}", suggestion);
+        }
+    }
+
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    println!();
+    println!(
+        "{} findings ({} errors, {} warnings, {} info) in {} files",
+        findings.len(),
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info),
+        file_count
+    );
+}
@@ -0,0 +1,353 @@
+//! Runs command handler.
+//!
+//! Lists, shows and replays recorded LLM interactions from `.guided/runs/`.
+
+use super::ask::create_llm_client;
+use clap::{Args, Subcommand};
+use guided_core::{config::AppConfig, AppError, AppResult};
+use guided_llm::{diff_responses, DiffLine, RunRecord, RunStore, UsageEntry, UsageLedger};
+use std::time::Instant;
+
+/// Inspect recorded LLM interactions
+#[derive(Args, Debug)]
+pub struct RunsCommand {
+    #[command(subcommand)]
+    pub action: RunsAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RunsAction {
+    /// List recorded runs, newest first
+    List(RunsListCommand),
+    /// Show the prompt, context and response of a run
+    Show(RunsShowCommand),
+    /// Re-run a recorded request and diff the answers
+    Replay(RunsReplayCommand),
+}
+
+/// List recorded runs, newest first
+#[derive(Args, Debug)]
+pub struct RunsListCommand {
+    /// Maximum number of runs to list
+    #[arg(short, long, default_value = "20")]
+    pub limit: usize,
+
+    /// Only list runs from this command (e.g., "ask", "review")
+    #[arg(long)]
+    pub command: Option<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl RunsListCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing runs list command");
+
+        let runs: Vec<RunRecord> = RunStore::new(&config.workspace)
+            .list()?
+            .into_iter()
+            .filter(|r| self.command.as_ref().is_none_or(|c| &r.command == c))
+            .take(self.limit)
+            .collect();
+
+        if self.json {
+            let output: Vec<_> = runs
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "id": r.id,
+                        "createdAt": r.created_at,
+                        "command": r.command,
+                        "promptId": r.prompt_id,
+                        "provider": r.provider,
+                        "model": r.request.model,
+                        "chunks": r.chunk_ids.len(),
+                        "totalTokens": r.usage.total_tokens,
+                        "durationMs": r.duration_ms
+                    })
+                })
+                .collect();
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        if runs.is_empty() {
+            println!("No runs recorded. Enable with `runs: {{ record: true }}` in .guided/config.yaml or GUIDED_RECORD_RUNS=1.");
+            return Ok(());
+        }
+
+        for run in &runs {
+            println!(
+                "{}  {:<10} {:<24} {}/{}  {} tokens  {}ms",
+                run.id,
+                run.command,
+                run.prompt_id.as_deref().unwrap_or("-"),
+                run.provider,
+                run.request.model,
+                run.usage.total_tokens,
+                run.duration_ms
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Show the prompt, context and response of a run
+#[derive(Args, Debug)]
+pub struct RunsShowCommand {
+    /// Run ID (see `guided runs list`)
+    pub id: String,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl RunsShowCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing runs show command for {}", self.id);
+
+        let run = RunStore::new(&config.workspace).load(&self.id)?;
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&run)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        println!("Run:       {}", run.id);
+        println!("Created:   {}", run.created_at);
+        println!("Command:   {}", run.command);
+        if let Some(ref prompt_id) = run.prompt_id {
+            println!("Prompt:    {}", prompt_id);
+        }
+        if let Some(ref template_hash) = run.template_hash {
+            println!("Template:  sha256 {}", template_hash);
+        }
+        if let Some(ref prompt_hash) = run.prompt_hash {
+            println!("Rendered:  sha256 {}", prompt_hash);
+        }
+        println!("Model:     {}/{}", run.provider, run.request.model);
+        println!(
+            "Params:    temperature={} maxTokens={} topP={} topK={} seed={} stream={}",
+            display_opt(run.request.temperature),
+            display_opt(run.request.max_tokens),
+            display_opt(run.request.top_p),
+            display_opt(run.request.top_k),
+            display_opt(run.request.seed),
+            run.request.stream
+        );
+        println!(
+            "Usage:     {} prompt + {} completion = {} tokens",
+            run.usage.prompt_tokens, run.usage.completion_tokens, run.usage.total_tokens
+        );
+        println!("Duration:  {}ms", run.duration_ms);
+        if let Some(ref overflow) = run.context_overflow {
+            println!(
+                "Overflow:  {} of {} ({}-token window, about {} -> {} tokens)",
+                overflow.strategy,
+                overflow.shortened.join(", "),
+                overflow.window_tokens,
+                overflow.prompt_tokens,
+                overflow.fitted_tokens
+            );
+        }
+        if !run.chunk_ids.is_empty() {
+            println!("Chunks:    {}", run.chunk_ids.join(", "));
+        }
+
+        if let Some(ref system) = run.request.system {
+            println!("\n--- System ---\n{}", system);
+        }
+        println!("\n--- Prompt ---\n{}", run.request.prompt);
+        println!("\n--- Response ---\n{}", run.response);
+
+        Ok(())
+    }
+}
+
+/// Re-run a recorded request and diff the answers
+#[derive(Args, Debug)]
+pub struct RunsReplayCommand {
+    /// Run ID (see `guided runs list`)
+    pub id: String,
+
+    /// Model to replay against (default: the recorded model)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Provider to replay against (default: the recorded provider)
+    #[arg(short, long)]
+    pub provider: Option<String>,
+
+    /// Exit with an error if the new answer differs from the recorded one
+    #[arg(long)]
+    pub fail_on_diff: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl RunsReplayCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing runs replay command for {}", self.id);
+        tracing::debug!("Runs replay options: {:?}", self);
+
+        let original = RunStore::new(&config.workspace).load(&self.id)?;
+
+        // Same rendered prompt and sampling parameters; only the target may change
+        let mut replay_config = config.clone();
+        replay_config.provider = self
+            .provider
+            .clone()
+            .unwrap_or_else(|| original.provider.clone());
+
+        let mut request = original.request.clone();
+        request.stream = false;
+        if let Some(ref model) = self.model {
+            request.model = model.clone();
+        }
+
+        tracing::info!(
+            "Replaying run {} ({}/{}) against {}/{}",
+            original.id,
+            original.provider,
+            original.request.model,
+            replay_config.provider,
+            request.model
+        );
+
+        let client = create_llm_client(&replay_config)?;
+        let started = Instant::now();
+        let response = client.complete(&request).await?;
+
+        let mut record = RunRecord::new("replay", &replay_config.provider, &request)
+            .with_chunk_ids(original.chunk_ids.clone())
+            .finish(&response.content, response.usage.clone(), started.elapsed());
+        record.prompt_id = original.prompt_id.clone();
+        record.template_hash = original.template_hash.clone();
+        record.replay_of = Some(original.id.clone());
+        record_run(config, record);
+
+        let diff = diff_responses(&original.response, &response.content);
+        let identical = diff.iter().all(|l| matches!(l, DiffLine::Same(_)));
+
+        if self.json {
+            let output = serde_json::json!({
+                "runId": original.id,
+                "original": {
+                    "provider": original.provider,
+                    "model": original.request.model,
+                    "response": original.response,
+                    "usage": original.usage
+                },
+                "replay": {
+                    "provider": replay_config.provider,
+                    "model": request.model,
+                    "response": response.content,
+                    "usage": response.usage
+                },
+                "identical": identical,
+                "diff": diff
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else {
+            println!(
+                "--- {} ({}/{})",
+                original.id, original.provider, original.request.model
+            );
+            println!("+++ replay ({}/{})", replay_config.provider, request.model);
+            print_diff(&diff, !config.no_color);
+
+            let added = diff
+                .iter()
+                .filter(|l| matches!(l, DiffLine::Added(_)))
+                .count();
+            let removed = diff
+                .iter()
+                .filter(|l| matches!(l, DiffLine::Removed(_)))
+                .count();
+            println!();
+            if identical {
+                println!("Answers are identical.");
+            } else {
+                println!("Answers differ: +{} -{} lines.", added, removed);
+            }
+        }
+
+        if self.fail_on_diff && !identical {
+            return Err(AppError::Other(format!(
+                "Replay of run {} produced a different answer",
+                original.id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl RunsCommand {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        match &self.action {
+            RunsAction::List(cmd) => cmd.execute(config).await,
+            RunsAction::Show(cmd) => cmd.execute(config).await,
+            RunsAction::Replay(cmd) => cmd.execute(config).await,
+        }
+    }
+}
+
+/// Meter a completion in the usage ledger, and save its run record if
+/// recording is enabled.
+///
+/// Failures are logged rather than returned so auditing never breaks the
+/// command being audited.
+pub(crate) fn record_run(config: &AppConfig, record: RunRecord) {
+    UsageLedger::record(
+        &config.workspace,
+        &UsageEntry::chat(
+            &record.command,
+            &record.provider,
+            &record.request.model,
+            &record.usage,
+        ),
+    );
+
+    if !config.record_runs {
+        return;
+    }
+
+    match RunStore::new(&config.workspace).save(record) {
+        Ok(saved) => tracing::info!("Recorded run {}", saved.id),
+        Err(e) => tracing::warn!("Failed to record run: {}", e),
+    }
+}
+
+/// Print a response diff, colored unless disabled.
+fn print_diff(diff: &[DiffLine], color: bool) {
+    for line in diff {
+        match line {
+            DiffLine::Same(text) => println!(" {}", text),
+            DiffLine::Removed(text) if color => println!("\x1b[31m-{}\x1b[0m", text),
+            DiffLine::Removed(text) => println!("-{}", text),
+            DiffLine::Added(text) if color => println!("\x1b[32m+{}\x1b[0m", text),
+            DiffLine::Added(text) => println!("+{}", text),
+        }
+    }
+}
+
+fn display_opt<T: std::fmt::Display>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -1,14 +1,19 @@
 //! Stats command handler.
 //!
-//! Handles usage statistics display.
+//! Totals the usage ledger in `.guided/operation/usage.jsonl`: chat
+//! completions and embedding calls by provider and model.
 
+use chrono::{Duration, Utc};
 use clap::Args;
-use guided_core::AppResult;
+use guided_core::{config::AppConfig, AppError, AppResult};
+use guided_edit::confirm;
+use guided_llm::usage::{totals, totals_by_command};
+use guided_llm::{UsageKind, UsageLedger, UsageTotal};
 
 /// Show usage statistics
 #[derive(Args, Debug)]
 pub struct StatsCommand {
-    /// Show detailed statistics
+    /// Also break usage down by command
     #[arg(short, long)]
     pub detailed: bool,
 
@@ -30,18 +35,104 @@ pub struct StatsCommand {
 }
 
 impl StatsCommand {
-    pub async fn execute(&self) -> AppResult<()> {
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
         tracing::info!("Executing stats command");
         tracing::debug!("Stats options: {:?}", self);
 
-        // TODO: Implement stats display in future phases
-        // 1. Load UsageStats from .guided/operation/stats.json
-        // 2. Filter by period
-        // 3. Display in requested format
+        let ledger = UsageLedger::new(&config.workspace);
 
-        println!("Stats command not yet implemented");
-        println!("Period: {}", self.period);
+        if self.reset {
+            if self.yes || confirm("Delete all recorded usage?")? {
+                ledger.clear()?;
+                println!("Usage statistics reset");
+            }
+            return Ok(());
+        }
+
+        // Periods reach back from now rather than to calendar boundaries
+        let since = match self.period.as_str() {
+            "today" => Some(Utc::now() - Duration::days(1)),
+            "week" => Some(Utc::now() - Duration::days(7)),
+            "month" => Some(Utc::now() - Duration::days(30)),
+            "all" => None,
+            other => {
+                return Err(AppError::Config(format!(
+                    "Invalid --period '{}': expected today, week, month or all",
+                    other
+                )))
+            }
+        };
+
+        let entries = ledger.entries()?;
+        let rows = totals(&entries, since);
+
+        let by_command = if self.detailed {
+            totals_by_command(&entries, since)
+        } else {
+            Vec::new()
+        };
+
+        if self.json {
+            let mut output = serde_json::json!({
+                "period": self.period,
+                "totals": rows,
+            });
+            if self.detailed {
+                output["byCommand"] = serde_json::json!(by_command);
+            }
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+            return Ok(());
+        }
+
+        if rows.is_empty() {
+            println!("No usage recorded ({})", self.period);
+            return Ok(());
+        }
+
+        println!("Usage ({})", self.period);
+        print_kind("Chat", UsageKind::Chat, &rows);
+        print_kind("Embeddings", UsageKind::Embedding, &rows);
+
+        if self.detailed {
+            println!();
+            println!("By command:");
+            for total in &by_command {
+                println!(
+                    "  {:<10} {:<10} {:>6} requests {:>10} tokens",
+                    total.kind.map_or("", |kind| kind.as_str()),
+                    total.command,
+                    total.requests,
+                    total.total_tokens()
+                );
+            }
+        }
 
         Ok(())
     }
 }
+
+/// Print the rows of one kind, one per provider and model.
+fn print_kind(title: &str, kind: UsageKind, rows: &[UsageTotal]) {
+    let rows: Vec<&UsageTotal> = rows.iter().filter(|row| row.kind == Some(kind)).collect();
+    if rows.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}:", title);
+    for row in rows {
+        let model = format!("{}/{}", row.provider, row.model);
+        match kind {
+            UsageKind::Chat => println!(
+                "  {:<32} {:>6} requests {:>10} prompt + {:>8} completion tokens",
+                model, row.requests, row.prompt_tokens, row.completion_tokens
+            ),
+            UsageKind::Embedding => println!(
+                "  {:<32} {:>6} requests {:>10} texts {:>12} characters {:>10} tokens",
+                model, row.requests, row.texts, row.characters, row.prompt_tokens
+            ),
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Testgen command handler.
+//!
+//! Generates unit tests for the functions in a source file, optionally
+//! grounded in knowledge base context.
+
+use super::ask::create_llm_client;
+use super::knowledge::retrieve_context;
+use super::runs::record_run;
+use clap::Args;
+use guided_core::{config::AppConfig, AppError, AppResult};
+use guided_knowledge::chunk::{
+    detect_content_type, extract_symbols, ContentType, Language, Symbol,
+};
+use guided_llm::{LlmRequest, RunRecord};
+use guided_prompt::{build_prompt, load_prompt};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Maximum bytes of source code included in the prompt.
+const MAX_SOURCE_BYTES: usize = 24 * 1024;
+
+/// Generate unit tests for a source file
+#[derive(Args, Debug)]
+pub struct TestgenCommand {
+    /// Source file to generate tests for
+    pub path: PathBuf,
+
+    /// Only generate tests for these functions (repeatable)
+    #[arg(long = "function")]
+    pub functions: Vec<String>,
+
+    /// Target file for generated tests (default depends on language)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Knowledge base to query for related context
+    #[arg(short, long)]
+    pub knowledge_base: Option<String>,
+
+    /// Number of knowledge chunks to retrieve
+    #[arg(long, default_value = "5")]
+    pub top_k: u32,
+
+    /// Preview generated tests without writing files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Replace the target file instead of appending to it
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl TestgenCommand {
+    /// Execute the testgen command.
+    pub async fn execute(&self, config: &AppConfig) -> AppResult<()> {
+        tracing::info!("Executing testgen command for {:?}", self.path);
+        tracing::debug!("Testgen options: {:?}", self);
+
+        let source_path = if self.path.is_absolute() {
+            self.path.clone()
+        } else {
+            config.workspace.join(&self.path)
+        };
+
+        let source = std::fs::read_to_string(&source_path).map_err(|e| {
+            AppError::Config(format!(
+                "Failed to read source file {:?}: {}",
+                source_path, e
+            ))
+        })?;
+
+        let language = match detect_content_type(Some(&source_path), &source) {
+            ContentType::Code { language } if language.has_tree_sitter_support() => language,
+            other => {
+                return Err(AppError::Config(format!(
+                    "Unsupported file for test generation: {:?} ({:?})",
+                    self.path, other
+                )))
+            }
+        };
+
+        let symbols = self.select_symbols(extract_symbols(&language, &source)?)?;
+
+        let target = match self.output {
+            Some(ref output) if output.is_absolute() => output.clone(),
+            Some(ref output) => config.workspace.join(output),
+            None => config
+                .workspace
+                .join(default_test_path(&self.path, &language)),
+        };
+
+        let existing_tests = if target.exists() && !self.overwrite {
+            std::fs::read_to_string(&target).ok()
+        } else {
+            None
+        };
+
+        tracing::info!(
+            "Generating tests for {} functions into {:?}",
+            symbols.len(),
+            target
+        );
+
+        // Build prompt
+        let mut prompt_def = load_prompt(&config.workspace, "agent.testgen")?;
+        prompt_def.behavior.inherit(&config.persona);
+
+        let (knowledge_context, chunk_ids) = match self.knowledge_base {
+            Some(ref kb_name) => {
+                prompt_def.context.include_knowledge_base = true;
+                prompt_def.context.knowledge_base_name = Some(kb_name.clone());
+                self.retrieve_knowledge(config, kb_name, &symbols)
+                    .await
+                    .unzip()
+            }
+            None => (None, None),
+        };
+
+        let mut variables = HashMap::new();
+        variables.insert("file".to_string(), self.path.display().to_string());
+        variables.insert("language".to_string(), language_name(&language).to_string());
+        variables.insert("symbols".to_string(), format_symbols(&symbols));
+        variables.insert("source".to_string(), truncate_source(&source));
+        variables.insert("target".to_string(), self.target_display(&target, config));
+        if let Some(ref existing) = existing_tests {
+            variables.insert("existingTests".to_string(), truncate_source(existing));
+        }
+
+        let built_prompt =
+            build_prompt(&prompt_def, variables, &config.workspace, knowledge_context)?;
+
+        // Generate
+        let client = create_llm_client(config)?;
+        let mut request = LlmRequest::new(built_prompt.user, &config.model).with_temperature(0.2);
+        if let Some(system) = built_prompt.system {
+            request = request.with_system(system);
+        }
+        let started = Instant::now();
+        let response = client.complete(&request).await?;
+        record_run(
+            config,
+            RunRecord::new("testgen", &config.provider, &request)
+                .with_prompt_id("agent.testgen")
+                .with_chunk_ids(chunk_ids.unwrap_or_default())
+                .finish(&response.content, response.usage.clone(), started.elapsed()),
+        );
+
+        let tests = extract_code_block(&response.content);
+        if tests.trim().is_empty() {
+            return Err(AppError::Llm("Model returned no test code".to_string()));
+        }
+
+        // Write (unless dry run)
+        let written = if self.dry_run {
+            false
+        } else {
+            write_tests(&target, &tests, existing_tests.is_some())?;
+            true
+        };
+
+        if self.json {
+            let output = serde_json::json!({
+                "source": self.path,
+                "target": self.target_display(&target, config),
+                "language": language_name(&language),
+                "functions": symbols.iter().map(|s| s.qualified_name()).collect::<Vec<_>>(),
+                "tests": tests,
+                "written": written,
+                "appended": written && existing_tests.is_some(),
+                "model": response.model,
+                "provider": config.provider
+            });
+
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|e| AppError::Serialization(e.to_string()))?;
+            println!("{}", json);
+        } else if self.dry_run {
+            println!("{}", tests);
+            eprintln!();
+            eprintln!(
+                "Dry run: would {} {}",
+                if existing_tests.is_some() {
+                    "append to"
+                } else {
+                    "write"
+                },
+                self.target_display(&target, config)
+            );
+        } else {
+            println!(
+                "{} tests for {} functions to {}",
+                if existing_tests.is_some() {
+                    "Appended"
+                } else {
+                    "Wrote"
+                },
+                symbols.len(),
+                self.target_display(&target, config)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Filter extracted symbols by `--function`, erroring on unknown names.
+    fn select_symbols(&self, symbols: Vec<Symbol>) -> AppResult<Vec<Symbol>> {
+        if symbols.is_empty() {
+            return Err(AppError::Config(format!(
+                "No functions found in {:?}",
+                self.path
+            )));
+        }
+
+        if self.functions.is_empty() {
+            return Ok(symbols);
+        }
+
+        for name in &self.functions {
+            if !symbols
+                .iter()
+                .any(|s| &s.name == name || &s.qualified_name() == name)
+            {
+                return Err(AppError::Config(format!(
+                    "Function '{}' not found in {:?}",
+                    name, self.path
+                )));
+            }
+        }
+
+        Ok(symbols
+            .into_iter()
+            .filter(|s| {
+                self.functions
+                    .iter()
+                    .any(|name| &s.name == name || &s.qualified_name() == name)
+            })
+            .collect())
+    }
+
+    /// Retrieve knowledge base context for the selected functions, with the
+    /// IDs of the chunks used.
+    ///
+    /// Retrieval failures are logged and generation continues without context.
+    async fn retrieve_knowledge(
+        &self,
+        config: &AppConfig,
+        kb_name: &str,
+        symbols: &[Symbol],
+    ) -> Option<(String, Vec<String>)> {
+        let query = format!(
+            "{} {}",
+            self.path.display(),
+            symbols
+                .iter()
+                .map(|s| s.signature.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+
+        let retrieved = match guided_knowledge::KnowledgeBase::open_cached(
+            &config.workspace,
+            kb_name,
+            &config.provider_configs(),
+        )
+        .await
+        {
+            Ok(base) => retrieve_context(config, &base, query, Some(self.top_k)).await,
+            Err(e) => Err(e),
+        };
+
+        match retrieved {
+            Ok(context) => context,
+            Err(e) => {
+                tracing::warn!("Knowledge retrieval failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Display a target path relative to the workspace when possible.
+    fn target_display(&self, target: &Path, config: &AppConfig) -> String {
+        target
+            .strip_prefix(&config.workspace)
+            .unwrap_or(target)
+            .display()
+            .to_string()
+    }
+}
+
+/// Conventional test file location for a source file.
+fn default_test_path(source: &Path, language: &Language) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("generated");
+    let dir = source.parent().unwrap_or_else(|| Path::new(""));
+
+    match language {
+        Language::Rust => PathBuf::from("tests").join(format!("{}_test.rs", stem)),
+        Language::Python => PathBuf::from("tests").join(format!("test_{}.py", stem)),
+        Language::Go => dir.join(format!("{}_test.go", stem)),
+        Language::TypeScript => dir.join(format!("{}.test.ts", stem)),
+        Language::JavaScript => dir.join(format!("{}.test.js", stem)),
+        _ => dir.join(format!("{}_test.txt", stem)),
+    }
+}
+
+/// Language name used in prompts and output.
+fn language_name(language: &Language) -> &'static str {
+    match language {
+        Language::Rust => "Rust",
+        Language::TypeScript => "TypeScript",
+        Language::JavaScript => "JavaScript",
+        Language::Python => "Python",
+        Language::Go => "Go",
+        Language::C => "C",
+        Language::Cpp => "C++",
+        Language::Java => "Java",
+        Language::Ruby => "Ruby",
+        Language::Php => "PHP",
+        Language::Unknown => "unknown",
+    }
+}
+
+/// Format symbols as a bullet list for the prompt.
+fn format_symbols(symbols: &[Symbol]) -> String {
+    symbols
+        .iter()
+        .map(|s| {
+            format!(
+                "- {} (lines {}-{}): `{}`",
+                s.qualified_name(),
+                s.line_range.0,
+                s.line_range.1,
+                s.signature
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cap source text at `MAX_SOURCE_BYTES` on a char boundary.
+fn truncate_source(source: &str) -> String {
+    if source.len() <= MAX_SOURCE_BYTES {
+        return source.to_string();
+    }
+
+    let mut end = MAX_SOURCE_BYTES;
+    while !source.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n... (truncated)", &source[..end])
+}
+
+/// Extract the first fenced code block, or the whole text if unfenced.
+fn extract_code_block(text: &str) -> String {
+    let Some(start) = text.find("```") else {
+        return text.trim().to_string();
+    };
+
+    // Skip the info string (e.g., "```rust")
+    let body_start = text[start..]
+        .find('\n')
+        .map(|i| start + i + 1)
+        .unwrap_or(text.len());
+
+    let body = &text[body_start..];
+    let end = body.find("```").unwrap_or(body.len());
+    body[..end].trim().to_string()
+}
+
+/// Write generated tests, appending to an existing file when requested.
+fn write_tests(target: &Path, tests: &str, append: bool) -> AppResult<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let contents = if append {
+        let existing = std::fs::read_to_string(target)?;
+        format!("{}\n\n{}\n", existing.trim_end(), tests)
+    } else {
+        format!("{}\n", tests)
+    };
+
+    std::fs::write(target, contents)?;
+    tracing::info!("Wrote generated tests to {:?}", target);
+
+    Ok(())
+}
@@ -4,10 +4,15 @@
 //! Provides commands for AI-assisted development with local-first RAG.
 
 mod commands;
+#[cfg(feature = "tui")]
+mod tui;
 
 use clap::{Parser, Subcommand};
-use commands::{AskCommand, KnowledgeCommand, StatsCommand, TaskCommand};
-use guided_core::{config::AppConfig, logging, AppResult};
+use commands::{
+    AskCommand, EditCommand, GitCommand, KnowledgeCommand, MemoryCommand, ReviewCommand,
+    RunsCommand, StatsCommand, TaskCommand, TestgenCommand,
+};
+use guided_core::{config::AppConfig, logging, AppError, AppResult};
 use std::path::PathBuf;
 
 /// Guided Agent CLI - AI-assisted development with local-first RAG
@@ -55,7 +60,7 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Ask a question with optional context
-    Ask(AskCommand),
+    Ask(Box<AskCommand>),
 
     /// Multi-step task planning and execution
     Task(TaskCommand),
@@ -63,6 +68,24 @@ enum Commands {
     /// Knowledge base management (local RAG)
     Knowledge(KnowledgeCommand),
 
+    /// Git workflow helpers (commit messages, PR descriptions)
+    Git(GitCommand),
+
+    /// Review changes between two refs
+    Review(ReviewCommand),
+
+    /// Generate unit tests for a source file
+    Testgen(TestgenCommand),
+
+    /// Apply patches with preview, confirmation and backups
+    Edit(EditCommand),
+
+    /// Inspect recorded LLM interactions
+    Runs(RunsCommand),
+
+    /// Inspect conversation memory
+    Memory(MemoryCommand),
+
     /// Show usage statistics
     Stats(StatsCommand),
 }
@@ -102,6 +125,12 @@ async fn main() -> AppResult<()> {
     tracing::debug!("Provider: {}", config.provider);
     tracing::debug!("Model: {}", config.model);
 
+    // First Ctrl-C asks long-running work to stop at a safe point
+    guided_core::cancel::install_ctrl_c_handler();
+
+    // Share per-provider rate limits across every client in this process
+    guided_llm::rate_limit::configure_from(&config);
+
     // Ensure .guided directory exists
     config.ensure_guided_dir()?;
 
@@ -110,6 +139,12 @@ async fn main() -> AppResult<()> {
         Commands::Ask(_) => "ask",
         Commands::Task(_) => "task",
         Commands::Knowledge(_) => "knowledge",
+        Commands::Git(_) => "git",
+        Commands::Review(_) => "review",
+        Commands::Testgen(_) => "testgen",
+        Commands::Edit(_) => "edit",
+        Commands::Runs(_) => "runs",
+        Commands::Memory(_) => "memory",
         Commands::Stats(_) => "stats",
     };
     let _span = tracing::info_span!("command", name = command_name).entered();
@@ -119,7 +154,13 @@ async fn main() -> AppResult<()> {
         Commands::Ask(cmd) => cmd.execute(&config).await,
         Commands::Task(cmd) => cmd.execute().await,
         Commands::Knowledge(cmd) => cmd.execute(&config).await,
-        Commands::Stats(cmd) => cmd.execute().await,
+        Commands::Git(cmd) => cmd.execute(&config).await,
+        Commands::Review(cmd) => cmd.execute(&config).await,
+        Commands::Testgen(cmd) => cmd.execute(&config).await,
+        Commands::Edit(cmd) => cmd.execute(&config).await,
+        Commands::Runs(cmd) => cmd.execute(&config).await,
+        Commands::Memory(cmd) => cmd.execute(&config).await,
+        Commands::Stats(cmd) => cmd.execute(&config).await,
     };
 
     // Log completion
@@ -128,5 +169,11 @@ async fn main() -> AppResult<()> {
         Err(e) => tracing::error!("Command failed: {}", e),
     }
 
+    // Distinct exit code so scripts can tell an interrupt from a failure
+    if let Err(AppError::Cancelled(ref reason)) = result {
+        eprintln!("Cancelled: {}", reason);
+        std::process::exit(guided_core::cancel::EXIT_CANCELLED);
+    }
+
     result
 }
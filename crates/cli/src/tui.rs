@@ -0,0 +1,500 @@
+//! Full-screen terminal views for long-running commands (`--tui`).
+//!
+//! `knowledge learn --tui` shows a dashboard with the progress of every
+//! phase, throughput over time and recent warnings; `knowledge ask --tui`
+//! shows the answer as the LLM writes it next to the sources it cites.
+//! Log messages are diverted into the views while they own the terminal.
+
+use guided_core::logging::{self, DivertGuard, LogSink};
+use guided_core::{AppError, AppResult, CancellationToken};
+use guided_knowledge::rag::{AnswerEvent, AnswerObserver};
+use guided_knowledge::{LearnStats, ProgressEvent, ProgressReporter, RagResponse, RagSourceRef};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph, Sparkline, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Level;
+
+/// How often the views redraw and read keys.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Warnings kept by the learn dashboard.
+const MAX_WARNINGS: usize = 100;
+
+/// Seconds of history in the learn dashboard's throughput charts.
+const THROUGHPUT_SECS: usize = 120;
+
+/// Something a running command reports to its view.
+enum Update {
+    Progress(ProgressEvent),
+    Answer(AnswerEvent),
+    Log(Level, String),
+}
+
+/// The terminal while a view owns it; restored, with logging, when dropped.
+struct Screen {
+    terminal: DefaultTerminal,
+    _logs: DivertGuard,
+}
+
+impl Screen {
+    fn open(updates: Sender<Update>) -> AppResult<Self> {
+        if !std::io::stdout().is_terminal() {
+            return Err(AppError::Other(
+                "--tui needs an interactive terminal".to_string(),
+            ));
+        }
+
+        // Anything written to stderr from here on would garble the screen
+        let sink: LogSink = Arc::new(move |level, message| {
+            let _ = updates.send(Update::Log(level, message));
+        });
+        let logs = logging::divert_logs(sink);
+        let terminal = ratatui::try_init()?;
+        Ok(Self {
+            terminal,
+            _logs: logs,
+        })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Keys pressed since the last tick.
+fn pressed_keys() -> AppResult<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    while event::poll(Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                keys.push(key);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// q, Esc or Ctrl-C (raw mode turns Ctrl-C into a key press).
+fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Run a learn under the dashboard; quitting cancels it at a safe point.
+///
+/// The warnings seen are printed to stderr once the dashboard closes.
+pub async fn learn<F, Fut>(base: &str, cancel: &CancellationToken, run: F) -> AppResult<LearnStats>
+where
+    F: FnOnce(ProgressReporter) -> Fut,
+    Fut: Future<Output = AppResult<LearnStats>>,
+{
+    let (updates, received) = mpsc::channel();
+    let progress = updates.clone();
+    let reporter = ProgressReporter::new(Arc::new(move |event| {
+        let _ = progress.send(Update::Progress(event));
+    }));
+
+    let mut screen = Screen::open(updates)?;
+    let mut dashboard = Dashboard::new(base);
+    let mut work = std::pin::pin!(run(reporter));
+    let mut tick = tokio::time::interval(TICK);
+    let result = loop {
+        tokio::select! {
+            result = &mut work => break result,
+            _ = tick.tick() => {
+                dashboard.receive(&received);
+                if pressed_keys()?.iter().any(is_quit) {
+                    cancel.cancel();
+                    dashboard.cancelling = true;
+                }
+                screen.terminal.draw(|frame| dashboard.render(frame))?;
+            }
+        }
+    };
+    drop(screen);
+
+    dashboard.receive(&received);
+    for warning in &dashboard.warnings {
+        eprintln!("{}", warning);
+    }
+    result
+}
+
+/// State of the learn dashboard.
+struct Dashboard {
+    title: String,
+    started: Instant,
+    /// Latest event of every phase, in the order the phases started
+    phases: Vec<ProgressEvent>,
+    warnings: VecDeque<String>,
+    files: Throughput,
+    embeddings: Throughput,
+    cancelling: bool,
+}
+
+impl Dashboard {
+    fn new(base: &str) -> Self {
+        Self {
+            title: format!(" Learning '{}' ", base),
+            started: Instant::now(),
+            phases: Vec::new(),
+            warnings: VecDeque::new(),
+            files: Throughput::new(),
+            embeddings: Throughput::new(),
+            cancelling: false,
+        }
+    }
+
+    fn receive(&mut self, received: &Receiver<Update>) {
+        for update in received.try_iter() {
+            match update {
+                Update::Progress(event) if event.phase == "retry" => {
+                    self.warn(format!("WARN {}", event.message));
+                }
+                Update::Progress(event) => {
+                    match event.phase.as_str() {
+                        "parse" => self.files.observe(event.current),
+                        "embed" => self.embeddings.observe(event.current),
+                        _ => {}
+                    }
+                    match self.phases.iter_mut().find(|p| p.phase == event.phase) {
+                        Some(phase) => *phase = event,
+                        None => self.phases.push(event),
+                    }
+                }
+                Update::Log(level, message) if level <= Level::WARN => {
+                    self.warn(format!("{} {}", level, message));
+                }
+                Update::Log(..) | Update::Answer(_) => {}
+            }
+        }
+        self.files.tick();
+        self.embeddings.tick();
+    }
+
+    fn warn(&mut self, warning: String) {
+        if self.warnings.len() == MAX_WARNINGS {
+            self.warnings.pop_front();
+        }
+        self.warnings.push_back(warning);
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let elapsed = self.started.elapsed().as_secs();
+        let outer = Block::bordered()
+            .title(self.title.as_str().bold())
+            .title_bottom(
+                Line::from(format!(" {}:{:02} ", elapsed / 60, elapsed % 60)).right_aligned(),
+            );
+        let area = outer.inner(frame.area());
+        frame.render_widget(outer, frame.area());
+
+        let [phases, charts, warnings, footer] = Layout::vertical([
+            Constraint::Length(self.phases.len().max(1) as u16 + 2),
+            Constraint::Length(7),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        self.render_phases(frame, phases);
+        let [files, embeddings] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(charts);
+        self.files.render(frame, files, "Files/s", Color::Cyan);
+        self.embeddings
+            .render(frame, embeddings, "Embeddings/s", Color::Magenta);
+        self.render_warnings(frame, warnings);
+
+        let hint = if self.cancelling {
+            "Cancelling at the next safe point...".yellow()
+        } else {
+            "q: cancel".dark_gray()
+        };
+        frame.render_widget(Paragraph::new(hint), footer);
+    }
+
+    fn render_phases(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Progress ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        if self.phases.is_empty() {
+            frame.render_widget(Paragraph::new("Starting...".dark_gray()), inner);
+            return;
+        }
+
+        let rows = Layout::vertical(vec![Constraint::Length(1); self.phases.len()]).split(inner);
+        for (event, row) in self.phases.iter().zip(rows.iter()) {
+            let [name, gauge] =
+                Layout::horizontal([Constraint::Length(10), Constraint::Min(10)]).areas(*row);
+            frame.render_widget(Paragraph::new(event.phase.as_str().bold()), name);
+
+            let count = match event.total {
+                Some(total) => format!("{}/{}", event.current, total),
+                None => event.current.to_string(),
+            };
+            let ratio = event
+                .percentage
+                .map_or(0.0, |p| (p / 100.0).clamp(0.0, 1.0));
+            frame.render_widget(
+                Gauge::default()
+                    .gauge_style(Style::default().fg(Color::Green))
+                    .ratio(ratio)
+                    .label(format!("{} - {}", count, event.message)),
+                gauge,
+            );
+        }
+    }
+
+    fn render_warnings(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(format!(" Warnings ({}) ", self.warnings.len()));
+        let visible = block.inner(area).height as usize;
+        // Newest at the bottom, like a log
+        let items: Vec<ListItem> = self
+            .warnings
+            .iter()
+            .skip(self.warnings.len().saturating_sub(visible))
+            .map(|warning| ListItem::new(warning.as_str().yellow()))
+            .collect();
+        frame.render_widget(List::new(items).block(block), area);
+    }
+}
+
+/// Work done per second, from a phase's running count.
+struct Throughput {
+    last: u64,
+    pending: u64,
+    second_started: Instant,
+    history: VecDeque<u64>,
+}
+
+impl Throughput {
+    fn new() -> Self {
+        Self {
+            last: 0,
+            pending: 0,
+            second_started: Instant::now(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Record a phase's count; a count lower than the last one started over.
+    fn observe(&mut self, current: u64) {
+        self.pending += current.checked_sub(self.last).unwrap_or(current);
+        self.last = current;
+    }
+
+    /// Close the seconds that have passed.
+    fn tick(&mut self) {
+        while self.second_started.elapsed() >= Duration::from_secs(1) {
+            self.history.push_back(std::mem::take(&mut self.pending));
+            if self.history.len() > THROUGHPUT_SECS {
+                self.history.pop_front();
+            }
+            self.second_started += Duration::from_secs(1);
+        }
+    }
+
+    fn render(&self, frame: &mut Frame, area: Rect, title: &str, color: Color) {
+        let rate = self.history.back().copied().unwrap_or(0);
+        let block = Block::bordered().title(format!(" {} ({}) ", title, rate));
+        // Most recent seconds that fit, ending at the right edge
+        let width = block.inner(area).width as usize;
+        let data: Vec<u64> = self
+            .history
+            .iter()
+            .skip(self.history.len().saturating_sub(width))
+            .copied()
+            .collect();
+        frame.render_widget(
+            Sparkline::default()
+                .block(block)
+                .style(Style::default().fg(color))
+                .data(&data),
+            area,
+        );
+    }
+}
+
+/// Run an ask under the split view of answer and sources; quitting while
+/// it runs cancels it.
+///
+/// Once answered, the view stays open until q, Esc or Enter, so the answer
+/// can be read and scrolled (arrow keys, j/k) before it is printed as usual.
+pub async fn ask<F, Fut>(query: &str, cancel: &CancellationToken, run: F) -> AppResult<RagResponse>
+where
+    F: FnOnce(Arc<AnswerObserver<'static>>) -> Fut,
+    Fut: Future<Output = AppResult<RagResponse>>,
+{
+    let (updates, received) = mpsc::channel();
+    let answers = updates.clone();
+    let observer: Arc<AnswerObserver<'static>> = Arc::new(move |event| {
+        let _ = answers.send(Update::Answer(event));
+    });
+
+    let mut screen = Screen::open(updates)?;
+    let mut view = AnswerView::new(query);
+    let mut work = std::pin::pin!(run(observer));
+    let mut tick = tokio::time::interval(TICK);
+    let response = loop {
+        tokio::select! {
+            result = &mut work => break result?,
+            _ = tick.tick() => {
+                view.receive(&received);
+                for key in pressed_keys()? {
+                    if is_quit(&key) {
+                        cancel.cancel();
+                        view.status = "Cancelling...".to_string();
+                    } else {
+                        view.scroll(&key);
+                    }
+                }
+                screen.terminal.draw(|frame| view.render(frame))?;
+            }
+        }
+    };
+
+    view.receive(&received);
+    view.status = if response.cached {
+        "Answered from cache. q/Enter: close".to_string()
+    } else {
+        "Answered. q/Enter: close".to_string()
+    };
+    loop {
+        screen.terminal.draw(|frame| view.render(frame))?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if is_quit(&key) || key.code == KeyCode::Enter {
+                break;
+            }
+            view.scroll(&key);
+        }
+    }
+    Ok(response)
+}
+
+/// State of the ask view.
+struct AnswerView {
+    query: String,
+    answer: String,
+    sources: Option<Vec<RagSourceRef>>,
+    status: String,
+    scroll: u16,
+}
+
+impl AnswerView {
+    fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            answer: String::new(),
+            sources: None,
+            status: "Searching the knowledge base...".to_string(),
+            scroll: 0,
+        }
+    }
+
+    fn receive(&mut self, received: &Receiver<Update>) {
+        for update in received.try_iter() {
+            match update {
+                Update::Answer(AnswerEvent::Sources(sources)) => {
+                    self.sources = Some(sources);
+                    self.status = "Writing the answer...".to_string();
+                }
+                Update::Answer(AnswerEvent::Token(token)) => self.answer.push_str(&token),
+                Update::Log(level, message) if level <= Level::WARN => {
+                    self.status = format!("{} {}", level, message);
+                }
+                Update::Log(..) | Update::Progress(_) => {}
+            }
+        }
+    }
+
+    fn scroll(&mut self, key: &KeyEvent) {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            _ => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [question, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(self.query.as_str())
+                .block(Block::bordered().title(" Question "))
+                .wrap(Wrap { trim: true }),
+            question,
+        );
+
+        let [answer, sources] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(body);
+        frame.render_widget(
+            Paragraph::new(self.answer.as_str())
+                .block(Block::bordered().title(" Answer "))
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            answer,
+        );
+        self.render_sources(frame, sources);
+
+        frame.render_widget(Paragraph::new(self.status.as_str().dark_gray()), footer);
+    }
+
+    fn render_sources(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Sources ");
+        let Some(sources) = &self.sources else {
+            frame.render_widget(
+                Paragraph::new("Retrieving...".dark_gray()).block(block),
+                area,
+            );
+            return;
+        };
+        if sources.is_empty() {
+            frame.render_widget(
+                Paragraph::new("(no sources)".dark_gray()).block(block),
+                area,
+            );
+            return;
+        }
+
+        let items: Vec<ListItem> = sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| {
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::from(format!("[{}] ", i + 1)).bold(),
+                        Span::from(source.source.as_str()),
+                    ]),
+                    Line::from(format!("    {}", source.location).dark_gray()),
+                ])
+            })
+            .collect();
+        frame.render_widget(List::new(items).block(block), area);
+    }
+}
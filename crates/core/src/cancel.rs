@@ -0,0 +1,138 @@
+//! Cooperative cancellation for long-running operations.
+//!
+//! Operations such as `knowledge learn` check a [`CancellationToken`] at safe
+//! points (between batches) so that Ctrl-C stops them with consistent
+//! on-disk state instead of killing the process mid-write.
+
+use crate::error::{AppError, AppResult};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Notify;
+
+/// Exit code for a cancelled command (128 + SIGINT, as shells report it).
+pub const EXIT_CANCELLED: i32 = 130;
+
+/// Shared flag that tells running work to stop.
+///
+/// Clones observe the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation and wake everything waiting on [`cancelled`].
+    ///
+    /// [`cancelled`]: CancellationToken::cancelled
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return `AppError::Cancelled` if cancellation was requested.
+    pub fn check(&self, operation: &str) -> AppResult<()> {
+        if self.is_cancelled() {
+            return Err(AppError::Cancelled(operation.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Wait until cancellation is requested.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Run a future, abandoning it with `AppError::Cancelled` on cancellation.
+    ///
+    /// Only use this around work that is safe to drop midway (network calls,
+    /// reads); writes should check the token between steps instead.
+    pub async fn run<T, F>(&self, operation: &str, future: F) -> AppResult<T>
+    where
+        F: Future<Output = AppResult<T>>,
+    {
+        tokio::select! {
+            result = future => result,
+            _ = self.cancelled() => Err(AppError::Cancelled(operation.to_string())),
+        }
+    }
+}
+
+/// Process-wide token cancelled by Ctrl-C (see [`install_ctrl_c_handler`]).
+pub fn shutdown_token() -> CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN.get_or_init(CancellationToken::new).clone()
+}
+
+/// Cancel [`shutdown_token`] on the first Ctrl-C; exit on the second.
+///
+/// Must be called from within a tokio runtime.
+pub fn install_ctrl_c_handler() {
+    let token = shutdown_token();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            tracing::warn!("Could not listen for Ctrl-C; cancellation is disabled");
+            return;
+        }
+        eprintln!(
+            "\nCancelling... finishing the current step (press Ctrl-C again to exit immediately)"
+        );
+        token.cancel();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(EXIT_CANCELLED);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_run() {
+        let token = CancellationToken::new();
+        assert!(token.check("learn").is_ok());
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            canceller.cancel();
+        });
+
+        let result: AppResult<()> = token
+            .run("ask", async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::Cancelled(ref op)) if op == "ask"));
+        assert!(token.is_cancelled());
+        assert!(token.check("learn").is_err());
+
+        // Already-cancelled tokens resolve immediately
+        token.cancelled().await;
+    }
+}
@@ -8,8 +8,9 @@
 //! The configuration is workspace-centric, with most state stored in `.guided/`.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::error::{AppError, AppResult};
 
@@ -45,6 +46,30 @@ pub struct AppConfig {
 
     /// LLM provider configurations
     pub llm: Option<LlmConfig>,
+
+    /// Record each LLM interaction under .guided/runs/
+    #[serde(default)]
+    pub record_runs: bool,
+
+    /// Tool settings (shell allowlist, timeouts)
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    /// Conversation memory settings (recall and distillation in `ask`)
+    #[serde(default)]
+    pub memory: MemoryConfig,
+
+    /// Default persona of prompts that do not set their own behavior
+    #[serde(default)]
+    pub persona: PersonaConfig,
+
+    /// Knowledge settings (named learn profiles)
+    #[serde(default)]
+    pub knowledge: KnowledgeConfig,
+
+    /// Filter applied to LLM answers before they are shown or emitted
+    #[serde(default)]
+    pub output_filter: OutputFilterConfig,
 }
 
 /// LLM configuration from config.yaml.
@@ -57,6 +82,192 @@ pub struct LlmConfig {
     pub active_embedding_provider: String,
 
     pub providers: HashMap<String, ProviderConfig>,
+
+    /// Per-provider rate limits, keyed by provider name
+    #[serde(default, rename = "rateLimits")]
+    pub rate_limits: HashMap<String, RateLimitConfig>,
+
+    /// Per-provider default generation parameters, keyed by provider name
+    #[serde(default)]
+    pub generation: HashMap<String, GenerationConfig>,
+
+    /// Per-provider context window sizes and overflow handling, keyed by
+    /// provider name
+    #[serde(default, rename = "contextWindows")]
+    pub context_windows: HashMap<String, ContextWindowConfig>,
+}
+
+/// Sampling and generation parameters for completions.
+///
+/// Unset parameters are left to the provider. Command-line flags override
+/// the defaults configured under `llm.generation.<provider>`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    /// Nucleus sampling: sample from the tokens making up this probability mass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    /// Sample from only the k most likely tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+
+    /// Penalize tokens by how often they already appeared (-2.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    /// Penalize tokens that already appeared at all (-2.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+
+    /// Stop generating at any of these sequences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+
+    /// Seed for reproducible sampling, where the provider supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl GenerationConfig {
+    /// These parameters, with unset ones taken from `defaults`.
+    pub fn or(self, defaults: &GenerationConfig) -> Self {
+        Self {
+            temperature: self.temperature.or(defaults.temperature),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            top_p: self.top_p.or(defaults.top_p),
+            top_k: self.top_k.or(defaults.top_k),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            stop: if self.stop.is_empty() {
+                defaults.stop.clone()
+            } else {
+                self.stop
+            },
+            seed: self.seed.or(defaults.seed),
+        }
+    }
+
+    /// Check that every set parameter is in range.
+    pub fn validate(&self) -> AppResult<()> {
+        let check = |name: &str, value: Option<f32>, min: f32, max: f32| match value {
+            Some(v) if !(min..=max).contains(&v) => Err(AppError::Config(format!(
+                "{} must be between {} and {}, got {}",
+                name, min, max, v
+            ))),
+            _ => Ok(()),
+        };
+        check("Temperature", self.temperature, 0.0, 2.0)?;
+        check("top-p", self.top_p, 0.0, 1.0)?;
+        check("Frequency penalty", self.frequency_penalty, -2.0, 2.0)?;
+        check("Presence penalty", self.presence_penalty, -2.0, 2.0)?;
+        if self.top_k == Some(0) {
+            return Err(AppError::Config("top-k must be at least 1".to_string()));
+        }
+        if self.max_tokens == Some(0) {
+            return Err(AppError::Config(
+                "Max tokens must be at least 1".to_string(),
+            ));
+        }
+        if self.stop.iter().any(String::is_empty) {
+            return Err(AppError::Config(
+                "Stop sequences cannot be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What to do when a built prompt does not fit the model's context window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowStrategy {
+    /// Drop the earliest injected context until the prompt fits
+    #[default]
+    TruncateOldest,
+
+    /// Condense the injected context with a (cheaper) summary model
+    Summarize,
+
+    /// Refuse to send the prompt
+    Error,
+}
+
+impl std::fmt::Display for OverflowStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::TruncateOldest => "truncate-oldest",
+            Self::Summarize => "summarize",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Context window of a provider's models and how prompts that overflow it
+/// are handled.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextWindowConfig {
+    /// Context window in tokens; prompts are not checked when unset
+    #[serde(default)]
+    pub tokens: Option<u32>,
+
+    /// Strategy for prompts that do not fit
+    #[serde(default)]
+    pub overflow: OverflowStrategy,
+
+    /// Model that condenses context for `summarize` (the request's model
+    /// when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_model: Option<String>,
+}
+
+/// Rate limit settings for a provider.
+///
+/// Shared by completions and embeddings against the same provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    /// Maximum requests started per minute (unlimited when unset)
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+
+    /// Maximum requests in flight at once (unlimited when unset)
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+
+    /// Retries after a 429 response before giving up
+    #[serde(default = "default_rate_limit_retries")]
+    pub max_retries: u32,
+
+    /// Base delay for exponential backoff after a 429, in milliseconds
+    #[serde(default = "default_rate_limit_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: None,
+            max_concurrent: None,
+            max_retries: default_rate_limit_retries(),
+            backoff_ms: default_rate_limit_backoff_ms(),
+        }
+    }
+}
+
+fn default_rate_limit_retries() -> u32 {
+    5
+}
+
+fn default_rate_limit_backoff_ms() -> u64 {
+    500
 }
 
 /// Provider-specific configuration.
@@ -97,6 +308,360 @@ pub enum ProviderConfig {
         #[serde(rename = "contextSize")]
         context_size: Option<u32>,
     },
+    /// Scripted replies for tests and offline demos (`--provider mock`):
+    /// the path of a YAML script of replies. Without an entry, the mock
+    /// provider echoes prompts.
+    Mock { script: String },
+    /// Local ONNX embeddings (requires the `fastembed` build feature).
+    /// Listed last: with one required field it would otherwise match
+    /// other providers' entries.
+    FastEmbed {
+        #[serde(rename = "embeddingModel")]
+        embedding_model: String,
+    },
+}
+
+impl ProviderConfig {
+    /// Base URL of the provider's API, if configured.
+    pub fn endpoint(&self) -> Option<&str> {
+        match self {
+            ProviderConfig::Ollama { endpoint, .. } => Some(endpoint),
+            ProviderConfig::OpenAI { endpoint, .. } | ProviderConfig::Claude { endpoint, .. } => {
+                endpoint.as_deref()
+            }
+            ProviderConfig::GgufLocal { .. }
+            | ProviderConfig::Mock { .. }
+            | ProviderConfig::FastEmbed { .. } => None,
+        }
+    }
+
+    /// Chat model of the provider, if it has one.
+    pub fn model(&self) -> Option<&str> {
+        match self {
+            ProviderConfig::OpenAI { model, .. }
+            | ProviderConfig::Claude { model, .. }
+            | ProviderConfig::Ollama { model, .. } => Some(model),
+            ProviderConfig::GgufLocal { .. }
+            | ProviderConfig::Mock { .. }
+            | ProviderConfig::FastEmbed { .. } => None,
+        }
+    }
+
+    /// Request timeout in seconds, if configured.
+    pub fn timeout_secs(&self) -> Option<u64> {
+        match self {
+            ProviderConfig::Ollama { timeout, .. } => *timeout,
+            _ => None,
+        }
+    }
+}
+
+/// Conversation memory settings from config.yaml.
+///
+/// Memories are facts distilled from earlier `ask` exchanges, kept per
+/// workspace under `.guided/memory/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryConfig {
+    /// Add the memories relevant to each `ask` prompt to it
+    #[serde(default)]
+    pub recall: bool,
+
+    /// Distill facts worth remembering from each `ask` exchange
+    #[serde(default)]
+    pub remember: bool,
+
+    /// Most memories added to one prompt
+    #[serde(default = "default_recall_limit")]
+    pub recall_limit: usize,
+}
+
+fn default_recall_limit() -> usize {
+    5
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            recall: false,
+            remember: false,
+            recall_limit: default_recall_limit(),
+        }
+    }
+}
+
+/// Workspace default persona from config.yaml.
+///
+/// Fills in the `behavior` fields a prompt definition leaves unset, so a
+/// team can have every prompt answer for the same audience.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonaConfig {
+    /// Tone (e.g., "professional", "casual", "technical")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+
+    /// Style (e.g., "concise", "detailed", "conversational")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+
+    /// How much to say (e.g., "low", "medium", "high")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<String>,
+
+    /// Who the answers are for (e.g., "beginner", "expert", "new team members")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+}
+
+/// Output filter settings from config.yaml.
+///
+/// Answers are checked before they are displayed or emitted as JSON:
+/// matches of a redacting pattern are replaced, and a match of a blocking
+/// pattern (or an unsafe verdict of the classifier) withholds the answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputFilterConfig {
+    /// Redact common secrets (API keys, tokens, private keys, passwords)
+    #[serde(default = "default_redact_secrets")]
+    pub secrets: bool,
+
+    /// Domains whose hostnames are internal (e.g., "corp.example.com");
+    /// hostnames under them are redacted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub internal_domains: Vec<String>,
+
+    /// Further patterns to redact or block
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<OutputPattern>,
+
+    /// An LLM asked whether each answer is safe to show
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classifier: Option<OutputClassifier>,
+}
+
+fn default_redact_secrets() -> bool {
+    true
+}
+
+impl Default for OutputFilterConfig {
+    fn default() -> Self {
+        Self {
+            secrets: default_redact_secrets(),
+            internal_domains: Vec::new(),
+            patterns: Vec::new(),
+            classifier: None,
+        }
+    }
+}
+
+/// A configured output filter pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputPattern {
+    /// Name shown in the audit note
+    pub name: String,
+
+    /// Regular expression to look for. With a group named `secret`, only
+    /// that group is redacted.
+    pub regex: String,
+
+    /// What to do with a match
+    #[serde(default)]
+    pub action: FilterAction,
+}
+
+/// What the output filter does with a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    /// Replace the match and show the rest of the answer
+    #[default]
+    Redact,
+    /// Withhold the whole answer
+    Block,
+}
+
+/// LLM classifier of the output filter.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputClassifier {
+    /// Provider to ask (the active provider by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Model to ask (the provider's model by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// What the answer should not contain, in plain words (e.g.,
+    /// "customer names or account numbers")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+}
+
+/// Knowledge settings from config.yaml.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Named learn profiles, run with `guided knowledge learn --profile`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, LearnProfile>,
+}
+
+impl KnowledgeConfig {
+    /// The profile called `name`.
+    pub fn profile(&self, name: &str) -> AppResult<&LearnProfile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            AppError::Config(if available.is_empty() {
+                format!(
+                    "No learn profile '{}'; define one under knowledge.profiles in .guided/config.yaml",
+                    name
+                )
+            } else {
+                format!(
+                    "No learn profile '{}'. Available profiles: {}",
+                    name,
+                    available.join(", ")
+                )
+            })
+        })
+    }
+}
+
+/// A pre-declared `guided knowledge learn`: what to learn into which base.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearnProfile {
+    /// Knowledge base to learn into
+    pub base: String,
+
+    /// Paths to learn, relative to the workspace
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+
+    /// URLs to fetch and learn
+    #[serde(default)]
+    pub urls: Vec<String>,
+
+    /// Connector in .guided/connectors/<name>.yaml to sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connector: Option<String>,
+
+    /// RSS or Atom feeds to subscribe to
+    #[serde(default)]
+    pub feeds: Vec<String>,
+
+    /// Include patterns (glob), on top of the base config's `include`
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Exclude patterns (glob), on top of the base config's `exclude`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Namespace to store the learned chunks in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// How often the profile should be refreshed (e.g., "30m", "6h", "1d"),
+    /// a hint for schedulers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+}
+
+impl LearnProfile {
+    /// The refresh interval of [`LearnProfile::every`], if set.
+    pub fn interval(&self) -> AppResult<Option<Duration>> {
+        self.every.as_deref().map(parse_interval).transpose()
+    }
+}
+
+/// Parse an interval such as "45s", "30m", "6h" or "1d".
+pub fn parse_interval(text: &str) -> AppResult<Duration> {
+    let text = text.trim();
+    let invalid = || {
+        AppError::Config(format!(
+            "Invalid interval '{}'; expected a number followed by s, m, h or d (e.g. 6h)",
+            text
+        ))
+    };
+    let split = text.len().checked_sub(1).ok_or_else(invalid)?;
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.trim().parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    if number == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(number * seconds))
+}
+
+/// Tool configuration from config.yaml.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    #[serde(default)]
+    pub shell: ShellToolConfig,
+}
+
+/// Settings for the execute-shell tool.
+///
+/// A command runs only if it matches an `allow` entry and no `deny` entry.
+/// Entries match whole leading words, so `cargo test` allows
+/// `cargo test --workspace` but not `cargo testx`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellToolConfig {
+    #[serde(default = "default_shell_allow")]
+    pub allow: Vec<String>,
+
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Per-command timeout in seconds
+    #[serde(default = "default_shell_timeout")]
+    pub timeout_secs: u64,
+
+    /// Maximum bytes of stdout/stderr returned to the model (each)
+    #[serde(default = "default_shell_max_output")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            allow: default_shell_allow(),
+            deny: Vec::new(),
+            timeout_secs: default_shell_timeout(),
+            max_output_bytes: default_shell_max_output(),
+        }
+    }
+}
+
+fn default_shell_allow() -> Vec<String> {
+    [
+        "cargo build",
+        "cargo check",
+        "cargo test",
+        "cargo clippy",
+        "git status",
+        "git diff",
+        "git log",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_shell_timeout() -> u64 {
+    120
+}
+
+fn default_shell_max_output() -> usize {
+    16 * 1024
 }
 
 /// Full configuration file structure.
@@ -105,6 +670,13 @@ struct ConfigFile {
     llm: Option<LlmConfig>,
     workspace: Option<WorkspaceConfig>,
     logging: Option<LoggingConfig>,
+    tools: Option<ToolsConfig>,
+    runs: Option<RunsConfig>,
+    memory: Option<MemoryConfig>,
+    persona: Option<PersonaConfig>,
+    knowledge: Option<KnowledgeConfig>,
+    #[serde(rename = "outputFilter")]
+    output_filter: Option<OutputFilterConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +684,12 @@ struct WorkspaceConfig {
     path: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunsConfig {
+    #[serde(default)]
+    record: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct LoggingConfig {
     level: Option<String>,
@@ -130,6 +708,12 @@ impl Default for AppConfig {
             verbose: false,
             no_color: false,
             llm: None,
+            record_runs: false,
+            tools: ToolsConfig::default(),
+            memory: MemoryConfig::default(),
+            persona: PersonaConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            output_filter: OutputFilterConfig::default(),
         }
     }
 }
@@ -145,6 +729,7 @@ impl AppConfig {
     /// - `GUIDED_API_KEY`: API key
     /// - `RUST_LOG`: Log level
     /// - `NO_COLOR`: Disable colored output
+    /// - `GUIDED_RECORD_RUNS`: Record LLM interactions (`1`/`true` or `0`/`false`)
     ///
     /// # Example
     /// ```no_run
@@ -174,26 +759,13 @@ impl AppConfig {
         }
 
         // Load from YAML config file if it exists
-        let config_path = if let Some(ref cf) = config.config_file {
-            cf.clone()
-        } else {
-            config.workspace.join(".guided/config.yaml")
-        };
-
+        let config_path = config.config_path();
         if config_path.exists() {
             config = config.merge_yaml(&config_path)?;
         }
 
         // Environment variables override YAML config
-        if let Ok(provider) = std::env::var("GUIDED_PROVIDER") {
-            config.provider = provider;
-        }
-
-        if let Ok(model) = std::env::var("GUIDED_MODEL") {
-            config.model = model;
-        }
-
-        config.api_key = std::env::var("GUIDED_API_KEY").ok();
+        config.apply_env();
         config.log_level = std::env::var("RUST_LOG").ok();
 
         // Check for NO_COLOR environment variable
@@ -204,6 +776,57 @@ impl AppConfig {
         Ok(config)
     }
 
+    /// Load the configuration again from the same workspace and config
+    /// file, for long-lived processes picking up edits to config.yaml.
+    ///
+    /// Environment variables are applied again; the workspace and the
+    /// logging settings of the running process are kept. Command-line
+    /// overrides such as `--provider` are not reapplied.
+    pub fn reloaded(&self) -> AppResult<Self> {
+        let mut config = Self {
+            workspace: self.workspace.clone(),
+            config_file: self.config_file.clone(),
+            ..Self::default()
+        };
+        let config_path = config.config_path();
+        if config_path.exists() {
+            config = config.merge_yaml(&config_path)?;
+        }
+        config.apply_env();
+
+        config.workspace = self.workspace.clone();
+        config.log_level = self.log_level.clone();
+        config.verbose = self.verbose;
+        config.no_color = self.no_color;
+        Ok(config)
+    }
+
+    /// Path of the YAML config file: `--config`, or the workspace's
+    /// `.guided/config.yaml`.
+    pub fn config_path(&self) -> PathBuf {
+        match &self.config_file {
+            Some(path) => path.clone(),
+            None => self.workspace.join(".guided/config.yaml"),
+        }
+    }
+
+    /// Apply the environment variables that override YAML config.
+    fn apply_env(&mut self) {
+        if let Ok(provider) = std::env::var("GUIDED_PROVIDER") {
+            self.provider = provider;
+        }
+
+        if let Ok(model) = std::env::var("GUIDED_MODEL") {
+            self.model = model;
+        }
+
+        self.api_key = std::env::var("GUIDED_API_KEY").ok();
+
+        if let Ok(record) = std::env::var("GUIDED_RECORD_RUNS") {
+            self.record_runs = matches!(record.to_lowercase().as_str(), "1" | "true" | "yes");
+        }
+    }
+
     /// Merge YAML configuration file into this config.
     fn merge_yaml(&mut self, path: &PathBuf) -> AppResult<Self> {
         let contents = std::fs::read_to_string(path).map_err(|e| {
@@ -245,12 +868,44 @@ impl AppConfig {
                     ProviderConfig::Claude { model, .. } => model.clone(),
                     ProviderConfig::Ollama { model, .. } => model.clone(),
                     ProviderConfig::GgufLocal { .. } => "gguf-local".to_string(),
+                    ProviderConfig::Mock { .. } => "mock".to_string(),
+                    ProviderConfig::FastEmbed { embedding_model } => embedding_model.clone(),
                 };
             }
 
             result.llm = Some(llm);
         }
 
+        // Merge run recording settings
+        if let Some(runs) = config_file.runs {
+            result.record_runs = runs.record;
+        }
+
+        // Merge tool settings
+        if let Some(tools) = config_file.tools {
+            result.tools = tools;
+        }
+
+        // Merge memory settings
+        if let Some(memory) = config_file.memory {
+            result.memory = memory;
+        }
+
+        // Merge the default persona
+        if let Some(persona) = config_file.persona {
+            result.persona = persona;
+        }
+
+        // Merge knowledge settings
+        if let Some(knowledge) = config_file.knowledge {
+            result.knowledge = knowledge;
+        }
+
+        // Merge output filter settings
+        if let Some(output_filter) = config_file.output_filter {
+            result.output_filter = output_filter;
+        }
+
         Ok(result)
     }
 
@@ -329,6 +984,38 @@ impl AppConfig {
         }
     }
 
+    /// All configured providers (`llm.providers`), keyed by name.
+    pub fn provider_configs(&self) -> HashMap<String, ProviderConfig> {
+        self.llm
+            .as_ref()
+            .map(|llm| llm.providers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the rate limit settings for a provider (unlimited by default).
+    pub fn rate_limit(&self, provider: &str) -> RateLimitConfig {
+        self.llm
+            .as_ref()
+            .and_then(|llm| llm.rate_limits.get(provider).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Get the default generation parameters for a provider (none by default).
+    pub fn generation(&self, provider: &str) -> GenerationConfig {
+        self.llm
+            .as_ref()
+            .and_then(|llm| llm.generation.get(provider).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Get the context window settings for a provider (unchecked by default).
+    pub fn context_window(&self, provider: &str) -> ContextWindowConfig {
+        self.llm
+            .as_ref()
+            .and_then(|llm| llm.context_windows.get(provider).cloned())
+            .unwrap_or_default()
+    }
+
     /// Resolve API key from environment variable.
     pub fn resolve_api_key(&self, provider: &str) -> AppResult<Option<String>> {
         // Check explicit GUIDED_API_KEY first
@@ -358,7 +1045,7 @@ impl AppConfig {
     pub fn validate(&self) -> AppResult<()> {
         // Check if provider is known
         let provider = &self.provider;
-        let known_providers = ["openai", "claude", "ollama", "gguf-local"];
+        let known_providers = ["openai", "claude", "ollama", "gguf-local", "mock"];
 
         if !known_providers.contains(&provider.as_str()) {
             return Err(AppError::Config(format!(
@@ -396,8 +1083,16 @@ impl AppConfig {
                         )));
                     }
                 }
-                ProviderConfig::Ollama { .. } => {
-                    // Ollama doesn't require API keys
+                ProviderConfig::Mock { script } => {
+                    if !PathBuf::from(&script).is_file() {
+                        return Err(AppError::Config(format!(
+                            "Mock script not found: {}",
+                            script
+                        )));
+                    }
+                }
+                ProviderConfig::Ollama { .. } | ProviderConfig::FastEmbed { .. } => {
+                    // Local providers don't require API keys
                 }
             }
         }
@@ -447,15 +1142,185 @@ mod tests {
 
     #[test]
     fn test_validate_unknown_provider() {
-        let mut config = AppConfig::default();
-        config.provider = "unknown".to_string();
+        let config = AppConfig {
+            provider: "unknown".to_string(),
+            ..Default::default()
+        };
         assert!(config.validate().is_err());
     }
 
     #[test]
     fn test_validate_ollama() {
-        let mut config = AppConfig::default();
-        config.provider = "ollama".to_string();
+        let config = AppConfig {
+            provider: "ollama".to_string(),
+            ..Default::default()
+        };
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_tools_config_from_yaml() {
+        let yaml = "tools:\n  shell:\n    allow: [\"make\"]\n    timeoutSecs: 5\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let shell = file.tools.unwrap().shell;
+
+        assert_eq!(shell.allow, vec!["make"]);
+        assert!(shell.deny.is_empty());
+        assert_eq!(shell.timeout_secs, 5);
+        assert_eq!(shell.max_output_bytes, 16 * 1024);
+    }
+
+    #[test]
+    fn test_memory_config_from_yaml() {
+        let yaml = "memory:\n  recall: true\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let memory = file.memory.unwrap();
+
+        assert!(memory.recall);
+        assert!(!memory.remember);
+        assert_eq!(memory.recall_limit, 5);
+    }
+
+    #[test]
+    fn test_learn_profiles_from_yaml() {
+        let yaml = "knowledge:\n  profiles:\n    docs:\n      base: docs\n      paths: [docs, README.md]\n      exclude: [\"drafts/**\"]\n      every: 6h\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let knowledge = file.knowledge.unwrap();
+
+        let docs = knowledge.profile("docs").unwrap();
+        assert_eq!(docs.base, "docs");
+        assert_eq!(
+            docs.paths,
+            vec![PathBuf::from("docs"), PathBuf::from("README.md")]
+        );
+        assert_eq!(docs.exclude, vec!["drafts/**"]);
+        assert_eq!(
+            docs.interval().unwrap(),
+            Some(Duration::from_secs(6 * 3600))
+        );
+
+        let err = knowledge.profile("code").unwrap_err();
+        assert!(err.to_string().contains("Available profiles: docs"));
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        for invalid in ["", "6", "h", "0h", "6w", "-1h"] {
+            assert!(parse_interval(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_persona_config_from_yaml() {
+        let yaml = "persona:\n  tone: casual\n  audience: new team members\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let persona = file.persona.unwrap();
+
+        assert_eq!(persona.tone.as_deref(), Some("casual"));
+        assert_eq!(persona.style, None);
+        assert_eq!(persona.audience.as_deref(), Some("new team members"));
+    }
+
+    #[test]
+    fn test_output_filter_config_from_yaml() {
+        let yaml = "outputFilter:\n  internalDomains: [corp.example.com]\n  patterns:\n    - name: ticket\n      regex: 'SEC-\\d+'\n      action: block\n  classifier:\n    model: llama3\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let filter = file.output_filter.unwrap();
+
+        assert!(filter.secrets);
+        assert_eq!(filter.internal_domains, vec!["corp.example.com"]);
+        assert_eq!(filter.patterns[0].regex, "SEC-\\d+");
+        assert_eq!(filter.patterns[0].action, FilterAction::Block);
+        assert_eq!(filter.classifier.unwrap().model.as_deref(), Some("llama3"));
+    }
+
+    #[test]
+    fn test_rate_limits_from_yaml() {
+        let yaml = "llm:\n  activeProvider: ollama\n  activeEmbeddingProvider: ollama\n  providers: {}\n  rateLimits:\n    openai:\n      requestsPerMinute: 60\n      maxConcurrent: 2\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let config = AppConfig {
+            llm: file.llm,
+            ..AppConfig::default()
+        };
+
+        let openai = config.rate_limit("openai");
+        assert_eq!(openai.requests_per_minute, Some(60));
+        assert_eq!(openai.max_concurrent, Some(2));
+        assert_eq!(openai.max_retries, 5);
+        assert_eq!(config.rate_limit("ollama"), RateLimitConfig::default());
+    }
+
+    #[test]
+    fn test_provider_endpoint_and_timeout_from_yaml() {
+        let yaml = "llm:\n  activeProvider: ollama\n  activeEmbeddingProvider: ollama\n  providers:\n    ollama:\n      endpoint: http://gpu-box:11434\n      model: llama3\n      timeout: 120\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let config = AppConfig {
+            llm: file.llm,
+            ..AppConfig::default()
+        };
+
+        let providers = config.provider_configs();
+        let ollama = &providers["ollama"];
+        assert_eq!(ollama.endpoint(), Some("http://gpu-box:11434"));
+        assert_eq!(ollama.timeout_secs(), Some(120));
+        assert!(AppConfig::default().provider_configs().is_empty());
+    }
+
+    #[test]
+    fn test_generation_defaults_from_yaml() {
+        let yaml = "llm:\n  activeProvider: ollama\n  activeEmbeddingProvider: ollama\n  providers: {}\n  generation:\n    ollama:\n      topP: 0.9\n      topK: 40\n      stop: [\"</answer>\"]\n      seed: 7\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let config = AppConfig {
+            llm: file.llm,
+            ..AppConfig::default()
+        };
+
+        let defaults = config.generation("ollama");
+        assert_eq!(defaults.top_k, Some(40));
+        assert_eq!(config.generation("openai"), GenerationConfig::default());
+
+        // Flags win over the configured defaults
+        let flags = GenerationConfig {
+            top_p: Some(0.5),
+            ..Default::default()
+        };
+        let merged = flags.or(&defaults);
+        assert_eq!(merged.top_p, Some(0.5));
+        assert_eq!(merged.top_k, Some(40));
+        assert_eq!(merged.stop, vec!["</answer>".to_string()]);
+        assert_eq!(merged.seed, Some(7));
+        assert!(merged.validate().is_ok());
+
+        let invalid = GenerationConfig {
+            top_p: Some(1.5),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_context_windows_from_yaml() {
+        let yaml = "llm:\n  activeProvider: ollama\n  activeEmbeddingProvider: ollama\n  providers: {}\n  contextWindows:\n    ollama:\n      tokens: 8192\n      overflow: summarize\n      summaryModel: qwen2.5:0.5b\n";
+        let file: ConfigFile = serde_yaml::from_str(yaml).unwrap();
+        let config = AppConfig {
+            llm: file.llm,
+            ..AppConfig::default()
+        };
+
+        let ollama = config.context_window("ollama");
+        assert_eq!(ollama.tokens, Some(8192));
+        assert_eq!(ollama.overflow, OverflowStrategy::Summarize);
+        assert_eq!(ollama.summary_model.as_deref(), Some("qwen2.5:0.5b"));
+
+        let unset = config.context_window("openai");
+        assert_eq!(unset.tokens, None);
+        assert_eq!(unset.overflow, OverflowStrategy::TruncateOldest);
+        assert_eq!(
+            OverflowStrategy::TruncateOldest.to_string(),
+            "truncate-oldest"
+        );
+    }
 }
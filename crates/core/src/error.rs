@@ -2,7 +2,7 @@
 //!
 //! This module defines a unified error enum that covers all error categories
 //! in the application, including configuration, I/O, LLM, knowledge, prompt,
-//! and task errors.
+//! git, edit, tool, and task errors, and user cancellation.
 
 use thiserror::Error;
 
@@ -32,10 +32,26 @@ pub enum AppError {
     #[error("Prompt error: {0}")]
     Prompt(String),
 
+    /// Git integration errors
+    #[error("Git error: {0}")]
+    Git(String),
+
+    /// File editing and patch application errors
+    #[error("Edit error: {0}")]
+    Edit(String),
+
+    /// Tool invocation errors (shell commands, etc.)
+    #[error("Tool error: {0}")]
+    Tool(String),
+
     /// Task planning and execution errors
     #[error("Task error: {0}")]
     Task(String),
 
+    /// Operation stopped by the user (Ctrl-C)
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
     /// Serialization/deserialization errors
     #[error("Serialization error: {0}")]
     Serialization(String),
@@ -0,0 +1,566 @@
+//! Git integration for diff-aware context.
+//!
+//! This module shells out to the `git` binary to collect working-tree
+//! changes and parses unified diffs into per-file, per-hunk structures
+//! that can be injected into prompts as context.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// A single hunk from a unified diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// Path of the file this hunk belongs to (new path for renames)
+    pub file: String,
+
+    /// Raw hunk header (e.g., "@@ -10,7 +10,8 @@ fn main()")
+    pub header: String,
+
+    /// First line in the old file
+    pub old_start: u32,
+
+    /// Number of lines in the old file
+    pub old_lines: u32,
+
+    /// First line in the new file
+    pub new_start: u32,
+
+    /// Number of lines in the new file
+    pub new_lines: u32,
+
+    /// Hunk body (context, added and removed lines)
+    pub content: String,
+}
+
+impl DiffHunk {
+    /// Human-readable location of this hunk (e.g., "src/main.rs lines 10-17").
+    pub fn location(&self) -> String {
+        let end = self.new_start + self.new_lines.saturating_sub(1);
+        format!("{} lines {}-{}", self.file, self.new_start, end)
+    }
+
+    /// Number of added lines in this hunk.
+    pub fn additions(&self) -> usize {
+        self.content.lines().filter(|l| l.starts_with('+')).count()
+    }
+
+    /// Number of removed lines in this hunk.
+    pub fn deletions(&self) -> usize {
+        self.content.lines().filter(|l| l.starts_with('-')).count()
+    }
+}
+
+/// All changes to a single file in a diff.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// Current path of the file
+    pub path: String,
+
+    /// Previous path when the file was renamed
+    pub old_path: Option<String>,
+
+    /// Whether git reported the file as binary
+    pub is_binary: bool,
+
+    /// Whether the file was newly added
+    pub is_new: bool,
+
+    /// Whether the file was deleted
+    pub is_deleted: bool,
+
+    /// Hunks in file order
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Check whether the workspace is inside a git work tree.
+pub fn is_git_repo(workspace: &Path) -> bool {
+    Command::new("git")
+        .arg("rev-parse")
+        .arg("--is-inside-work-tree")
+        .current_dir(workspace)
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+/// Run a git command in the workspace and return its stdout.
+pub fn run_git(workspace: &Path, args: &[&str]) -> AppResult<String> {
+    tracing::debug!("Running git {:?} in {:?}", args, workspace);
+
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace)
+        .output()
+        .map_err(|e| AppError::Git(format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Git(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Collect the working-tree diff.
+///
+/// When `staged` is true, returns the diff of the index against HEAD
+/// (what would be committed); otherwise the unstaged working-tree changes.
+pub fn working_tree_diff(workspace: &Path, staged: bool) -> AppResult<String> {
+    if !is_git_repo(workspace) {
+        return Err(AppError::Git(format!(
+            "Not a git repository: {:?}",
+            workspace
+        )));
+    }
+
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--cached");
+    }
+
+    run_git(workspace, &args)
+}
+
+/// Collect the diff of the current branch against a base ref.
+///
+/// Uses the merge base (`base...HEAD`) so only changes made on this branch
+/// are included.
+pub fn branch_diff(workspace: &Path, base: &str) -> AppResult<String> {
+    range_diff(workspace, base, "HEAD")
+}
+
+/// Collect the diff between two refs, relative to their merge base.
+pub fn range_diff(workspace: &Path, base: &str, head: &str) -> AppResult<String> {
+    if !is_git_repo(workspace) {
+        return Err(AppError::Git(format!(
+            "Not a git repository: {:?}",
+            workspace
+        )));
+    }
+
+    let range = format!("{}...{}", base, head);
+    run_git(workspace, &["diff", "--no-color", "--no-ext-diff", &range])
+}
+
+/// List commit subjects on the current branch that are not in `base`.
+pub fn commit_log(workspace: &Path, base: &str) -> AppResult<Vec<String>> {
+    let range = format!("{}..HEAD", base);
+    let output = run_git(workspace, &["log", "--no-color", "--format=%s", &range])?;
+
+    Ok(output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Resolve the `.git` directory for the workspace (handles worktrees).
+pub fn git_dir(workspace: &Path) -> AppResult<PathBuf> {
+    let output = run_git(workspace, &["rev-parse", "--git-dir"])?;
+    let dir = PathBuf::from(output.trim());
+
+    if dir.is_absolute() {
+        Ok(dir)
+    } else {
+        Ok(workspace.join(dir))
+    }
+}
+
+/// Write a commit message to `.git/COMMIT_EDITMSG`.
+///
+/// Returns the path written, so callers can point `git commit -F` at it.
+pub fn write_commit_message(workspace: &Path, message: &str) -> AppResult<PathBuf> {
+    let path = git_dir(workspace)?.join("COMMIT_EDITMSG");
+
+    let mut contents = message.trim_end().to_string();
+    contents.push('\n');
+
+    std::fs::write(&path, contents)
+        .map_err(|e| AppError::Git(format!("Failed to write commit message {:?}: {}", path, e)))?;
+
+    tracing::info!("Wrote commit message to {:?}", path);
+
+    Ok(path)
+}
+
+/// Commit types accepted by the Conventional Commits format.
+pub const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// Check whether a line is a Conventional Commits header (`type(scope)!: subject`).
+pub fn is_conventional_header(line: &str) -> bool {
+    let Some((prefix, subject)) = line.split_once(':') else {
+        return false;
+    };
+
+    if subject.trim().is_empty() {
+        return false;
+    }
+
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let commit_type = match prefix.split_once('(') {
+        Some((t, scope)) => {
+            if !scope.ends_with(')') || scope.len() < 2 {
+                return false;
+            }
+            t
+        }
+        None => prefix,
+    };
+
+    CONVENTIONAL_TYPES.contains(&commit_type)
+}
+
+/// Clean up a generated commit message.
+///
+/// Strips surrounding code fences and blank lines. When `commit_type` is
+/// given and the header is not already conventional, the header is
+/// prefixed with `type(scope): `.
+pub fn format_commit_message(raw: &str, commit_type: Option<&str>, scope: Option<&str>) -> String {
+    let lines: Vec<&str> = raw
+        .trim()
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("```"))
+        .collect();
+    let message = lines.join("\n").trim().to_string();
+
+    let Some(commit_type) = commit_type else {
+        return message;
+    };
+
+    let (header, body) = match message.split_once('\n') {
+        Some((h, b)) => (h.trim(), Some(b)),
+        None => (message.as_str(), None),
+    };
+
+    if is_conventional_header(header) {
+        return message;
+    }
+
+    let prefix = match scope {
+        Some(scope) => format!("{}({})", commit_type, scope),
+        None => commit_type.to_string(),
+    };
+
+    match body {
+        Some(body) => format!("{}: {}\n{}", prefix, header, body),
+        None => format!("{}: {}", prefix, header),
+    }
+}
+
+/// Parse unified diff text into per-file diffs.
+pub fn parse_diff(text: &str) -> Vec<FileDiff> {
+    let mut files = Vec::new();
+    let mut current: Option<FileDiff> = None;
+    let mut hunk: Option<DiffHunk> = None;
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            flush_hunk(&mut current, &mut hunk);
+            if let Some(file) = current.take() {
+                files.push(file);
+            }
+
+            // "a/old b/new" — the b/ path is the current name
+            let path = rest
+                .rsplit_once(" b/")
+                .map(|(_, new)| new.to_string())
+                .unwrap_or_else(|| rest.to_string());
+            current = Some(FileDiff {
+                path,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(file) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(header) = line.strip_prefix("@@") {
+            flush_hunk(&mut current, &mut hunk);
+            let file_path = current.as_ref().map(|f| f.path.clone()).unwrap_or_default();
+            let (old_start, old_lines, new_start, new_lines) = parse_hunk_header(header);
+            hunk = Some(DiffHunk {
+                file: file_path,
+                header: line.to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                content: String::new(),
+            });
+            continue;
+        }
+
+        if let Some(h) = hunk.as_mut() {
+            if line.starts_with('+')
+                || line.starts_with('-')
+                || line.starts_with(' ')
+                || line.starts_with('\\')
+                || line.is_empty()
+            {
+                h.content.push_str(line);
+                h.content.push('\n');
+                continue;
+            }
+        }
+
+        // File-level headers between "diff --git" and the first hunk
+        if line.starts_with("new file mode") {
+            file.is_new = true;
+        } else if line.starts_with("deleted file mode") {
+            file.is_deleted = true;
+        } else if let Some(old) = line.strip_prefix("rename from ") {
+            file.old_path = Some(old.to_string());
+        } else if let Some(new) = line.strip_prefix("rename to ") {
+            file.path = new.to_string();
+        } else if line.starts_with("Binary files ") {
+            file.is_binary = true;
+        }
+    }
+
+    flush_hunk(&mut current, &mut hunk);
+    if let Some(file) = current.take() {
+        files.push(file);
+    }
+
+    files
+}
+
+/// Move the in-progress hunk into the current file.
+fn flush_hunk(current: &mut Option<FileDiff>, hunk: &mut Option<DiffHunk>) {
+    if let (Some(file), Some(h)) = (current.as_mut(), hunk.take()) {
+        file.hunks.push(h);
+    }
+}
+
+/// Parse "@@ -a,b +c,d @@" ranges. Missing counts default to 1.
+fn parse_hunk_header(header: &str) -> (u32, u32, u32, u32) {
+    let mut old = (0, 0);
+    let mut new = (0, 0);
+
+    for part in header.split_whitespace() {
+        if let Some(range) = part.strip_prefix('-') {
+            old = parse_range(range);
+        } else if let Some(range) = part.strip_prefix('+') {
+            new = parse_range(range);
+        } else if part == "@@" && (old.0 > 0 || new.0 > 0) {
+            break;
+        }
+    }
+
+    (old.0, old.1, new.0, new.1)
+}
+
+fn parse_range(range: &str) -> (u32, u32) {
+    match range.split_once(',') {
+        Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    }
+}
+
+/// Format parsed diffs as prompt context, one block per hunk.
+///
+/// Output is capped at `max_bytes`; remaining hunks are summarized as omitted.
+pub fn format_diff_context(files: &[FileDiff], max_bytes: usize) -> String {
+    let mut context = String::new();
+    let mut omitted = 0usize;
+
+    for file in files {
+        if file.is_binary {
+            let line = format!("[{}] binary file changed\n\n", file.path);
+            if context.len() + line.len() <= max_bytes {
+                context.push_str(&line);
+            } else {
+                omitted += 1;
+            }
+            continue;
+        }
+
+        for hunk in &file.hunks {
+            let block = format!(
+                "[{}]\n{}\n{}\n",
+                hunk.location(),
+                hunk.header,
+                hunk.content.trim_end()
+            );
+
+            if context.len() + block.len() > max_bytes {
+                omitted += 1;
+                continue;
+            }
+
+            context.push_str(&block);
+            context.push('\n');
+        }
+    }
+
+    if omitted > 0 {
+        context.push_str(&format!(
+            "({} more hunks omitted to fit the context budget)\n",
+            omitted
+        ));
+    }
+
+    context
+}
+
+/// Split a file diff into parts whose hunk content fits within `max_bytes`.
+///
+/// Hunks are never split; a single oversized hunk becomes its own part.
+pub fn split_file_diff(file: &FileDiff, max_bytes: usize) -> Vec<FileDiff> {
+    let mut parts = Vec::new();
+    let mut current: Vec<DiffHunk> = Vec::new();
+    let mut size = 0usize;
+
+    for hunk in &file.hunks {
+        let hunk_size = hunk.header.len() + hunk.content.len();
+        if !current.is_empty() && size + hunk_size > max_bytes {
+            parts.push(FileDiff {
+                hunks: std::mem::take(&mut current),
+                ..file.clone()
+            });
+            size = 0;
+        }
+        size += hunk_size;
+        current.push(hunk.clone());
+    }
+
+    if !current.is_empty() || parts.is_empty() {
+        parts.push(FileDiff {
+            hunks: current,
+            ..file.clone()
+        });
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_DIFF: &str = r#"diff --git a/src/main.rs b/src/main.rs
+index 83db48f..bf269f4 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
+ fn main() {
++    println!("hello");
+     run();
+ }
+@@ -10,2 +11,2 @@ fn run() {
+-    old();
++    new();
+diff --git a/README.md b/README.md
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/README.md
+@@ -0,0 +1 @@
++# Title
+"#;
+
+    #[test]
+    fn test_parse_diff_files_and_hunks() {
+        let files = parse_diff(SAMPLE_DIFF);
+        assert_eq!(files.len(), 2);
+
+        assert_eq!(files[0].path, "src/main.rs");
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[0].new_start, 1);
+        assert_eq!(files[0].hunks[0].new_lines, 4);
+        assert_eq!(files[0].hunks[1].old_start, 10);
+        assert_eq!(files[0].hunks[1].additions(), 1);
+        assert_eq!(files[0].hunks[1].deletions(), 1);
+
+        assert_eq!(files[1].path, "README.md");
+        assert!(files[1].is_new);
+        assert_eq!(files[1].hunks[0].new_lines, 1);
+    }
+
+    #[test]
+    fn test_hunk_location() {
+        let files = parse_diff(SAMPLE_DIFF);
+        assert_eq!(files[0].hunks[0].location(), "src/main.rs lines 1-4");
+    }
+
+    #[test]
+    fn test_format_diff_context_respects_budget() {
+        let files = parse_diff(SAMPLE_DIFF);
+
+        let full = format_diff_context(&files, usize::MAX);
+        assert!(full.contains("[src/main.rs lines 1-4]"));
+        assert!(full.contains("+# Title"));
+
+        let truncated = format_diff_context(&files, 80);
+        assert!(truncated.contains("omitted"));
+    }
+
+    #[test]
+    fn test_parse_empty_diff() {
+        assert!(parse_diff("").is_empty());
+    }
+
+    #[test]
+    fn test_split_file_diff() {
+        let files = parse_diff(SAMPLE_DIFF);
+
+        let whole = split_file_diff(&files[0], usize::MAX);
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].hunks.len(), 2);
+
+        let parts = split_file_diff(&files[0], 1);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|p| p.path == "src/main.rs"));
+    }
+
+    #[test]
+    fn test_is_conventional_header() {
+        assert!(is_conventional_header("feat: add diff context"));
+        assert!(is_conventional_header("fix(cli): handle empty diff"));
+        assert!(is_conventional_header("refactor(core)!: rename module"));
+        assert!(!is_conventional_header("Add diff context"));
+        assert!(!is_conventional_header("feature: add diff context"));
+        assert!(!is_conventional_header("feat:"));
+    }
+
+    #[test]
+    fn test_format_commit_message() {
+        let raw = "```\nAdd diff context\n\nInjects hunks into the prompt.\n```";
+        assert_eq!(
+            format_commit_message(raw, None, None),
+            "Add diff context\n\nInjects hunks into the prompt."
+        );
+        assert_eq!(
+            format_commit_message(raw, Some("feat"), Some("ask")),
+            "feat(ask): Add diff context\n\nInjects hunks into the prompt."
+        );
+        assert_eq!(
+            format_commit_message("fix: already conventional", Some("feat"), None),
+            "fix: already conventional"
+        );
+    }
+
+    #[test]
+    fn test_write_commit_message() {
+        let temp = tempfile::TempDir::new().unwrap();
+        if run_git(temp.path(), &["init", "-q"]).is_err() {
+            return; // git not available
+        }
+
+        let path = write_commit_message(temp.path(), "feat: test").unwrap();
+        assert!(path.ends_with("COMMIT_EDITMSG"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "feat: test\n");
+    }
+}
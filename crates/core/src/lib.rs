@@ -2,14 +2,24 @@
 //!
 //! This crate provides the foundational utilities for the Guided Agent CLI:
 //! - Error handling (`AppError`, `AppResult`)
+//! - Ctrl-C cancellation (`CancellationToken`)
 //! - Logging infrastructure
-//! - Configuration management
+//! - Configuration management, with hot reload for long-lived processes
+//! - Git integration (diff collection and parsing)
+//! - Code review findings (parsing and SARIF output)
+//! - Output renderers for answers (terminal, plain text, HTML)
 //! - Shared types and helpers
 
+pub mod cancel;
 pub mod config;
 pub mod error;
+pub mod git;
 pub mod logging;
+pub mod reload;
+pub mod render;
+pub mod review;
 
 // Re-export commonly used types
+pub use cancel::CancellationToken;
 pub use config::AppConfig;
 pub use error::{AppError, AppResult};
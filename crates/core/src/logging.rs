@@ -1,8 +1,13 @@
 //! Logging infrastructure for the Guided Agent CLI.
 //!
 //! This module initializes the tracing subscriber for structured logging.
-//! All logs are emitted to stderr to keep stdout clean for data output.
+//! All logs are emitted to stderr to keep stdout clean for data output,
+//! unless a full-screen UI diverts them with [`divert_logs`].
 
+use std::sync::{Arc, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use crate::error::AppResult;
@@ -35,7 +40,13 @@ pub fn init_logging(log_level: Option<&str>, no_color: bool) -> AppResult<()> {
 
     // Configure format layer with color control
     let fmt_layer = fmt::layer()
-        .with_writer(std::io::stderr)
+        .with_writer(|| -> Box<dyn std::io::Write> {
+            if diverted() {
+                Box::new(std::io::sink())
+            } else {
+                Box::new(std::io::stderr())
+            }
+        })
         .with_target(true)
         .with_level(true)
         .with_ansi(!no_color && supports_color());
@@ -43,12 +54,65 @@ pub fn init_logging(log_level: Option<&str>, no_color: bool) -> AppResult<()> {
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
+        .with(DivertLayer)
         .try_init()
         .map_err(|e| crate::error::AppError::Config(format!("Failed to init logging: {}", e)))?;
 
     Ok(())
 }
 
+/// Receiver of log messages diverted from stderr, with their level.
+pub type LogSink = Arc<dyn Fn(Level, String) + Send + Sync>;
+
+static SINK: RwLock<Option<LogSink>> = RwLock::new(None);
+
+/// Send log messages to `sink` instead of stderr until the returned guard
+/// is dropped, so they don't scribble over a full-screen UI.
+pub fn divert_logs(sink: LogSink) -> DivertGuard {
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+    DivertGuard(())
+}
+
+/// Restores logging to stderr when dropped.
+pub struct DivertGuard(());
+
+impl Drop for DivertGuard {
+    fn drop(&mut self) {
+        *SINK.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+fn diverted() -> bool {
+    SINK.read().map(|sink| sink.is_some()).unwrap_or(false)
+}
+
+/// Hands each event that passed the filter to the diverting sink, if any.
+struct DivertLayer;
+
+impl<S: Subscriber> Layer<S> for DivertLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(sink) = SINK.read().ok().and_then(|sink| sink.clone()) else {
+            return;
+        };
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        sink(*event.metadata().level(), message.0);
+    }
+}
+
+/// Collects an event's message, followed by its other fields.
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
 /// Check if the terminal supports color output.
 fn supports_color() -> bool {
     // Check NO_COLOR environment variable
@@ -73,4 +137,28 @@ mod tests {
         let result = init_logging(None, false);
         assert!(result.is_ok() || result.is_err()); // May already be initialized
     }
+
+    #[test]
+    fn test_divert_logs_sends_messages_to_sink() {
+        let captured = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured_clone = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(DivertLayer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            // Not diverted yet: nothing reaches the sink
+            tracing::warn!(file = "a.md", "Skipped file");
+
+            let _guard = divert_logs(Arc::new(move |level, message| {
+                captured_clone.lock().unwrap().push((level, message));
+            }));
+            tracing::warn!(file = "a.md", "Skipped file");
+        });
+        assert!(!diverted());
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(
+            *captured,
+            vec![(Level::WARN, "Skipped file file=\"a.md\"".to_string())]
+        );
+    }
 }
@@ -0,0 +1,243 @@
+//! Hot reload of config.yaml and prompt files.
+//!
+//! Long-lived processes keep a [`ConfigWatcher`] and call
+//! [`ConfigWatcher::reload`] between units of work. Files are compared by
+//! modification time, so no platform file-watching API is needed. A config
+//! file that fails to parse is reported and the previous configuration kept;
+//! prompts are read from disk each time they are used, so changes to them
+//! only need to be reported.
+
+use crate::config::AppConfig;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Files that changed since the last poll.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Changes {
+    /// Whether the config file was added, modified or removed
+    pub config: bool,
+
+    /// IDs of the prompts added, modified or removed, sorted
+    pub prompts: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        !self.config && self.prompts.is_empty()
+    }
+}
+
+/// Watches a workspace's config file and prompts for changes.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    config_path: PathBuf,
+    prompts_dir: PathBuf,
+    config_stamp: Option<SystemTime>,
+    prompt_stamps: BTreeMap<String, SystemTime>,
+}
+
+impl ConfigWatcher {
+    /// Start watching the files `config` was loaded from.
+    pub fn new(config: &AppConfig) -> Self {
+        let config_path = config.config_path();
+        let prompts_dir = config.guided_dir().join("prompts");
+        Self {
+            config_stamp: modified(&config_path),
+            prompt_stamps: prompt_stamps(&prompts_dir),
+            config_path,
+            prompts_dir,
+        }
+    }
+
+    /// The files changed since the watcher was created or last polled.
+    pub fn poll(&mut self) -> Changes {
+        let config_stamp = modified(&self.config_path);
+        let prompt_stamps = prompt_stamps(&self.prompts_dir);
+
+        let mut prompts: Vec<String> = prompt_stamps
+            .iter()
+            .filter(|(id, stamp)| self.prompt_stamps.get(*id) != Some(stamp))
+            .map(|(id, _)| id.clone())
+            .collect();
+        prompts.extend(
+            self.prompt_stamps
+                .keys()
+                .filter(|id| !prompt_stamps.contains_key(*id))
+                .cloned(),
+        );
+        prompts.sort();
+
+        let changes = Changes {
+            config: config_stamp != self.config_stamp,
+            prompts,
+        };
+        self.config_stamp = config_stamp;
+        self.prompt_stamps = prompt_stamps;
+        changes
+    }
+
+    /// Poll for changes and log them, returning the reloaded configuration
+    /// if the config file changed and still parses.
+    pub fn reload(&mut self, config: &AppConfig) -> Option<AppConfig> {
+        let changes = self.poll();
+        if !changes.prompts.is_empty() {
+            tracing::info!("Prompts changed: {}", changes.prompts.join(", "));
+        }
+        if !changes.config {
+            return None;
+        }
+
+        match config.reloaded() {
+            Ok(reloaded) => {
+                let fields = changed_fields(config, &reloaded);
+                if fields.is_empty() {
+                    tracing::info!("Reloaded {:?}: no settings changed", self.config_path);
+                } else {
+                    tracing::info!(
+                        "Reloaded {:?}: {} changed",
+                        self.config_path,
+                        fields.join(", ")
+                    );
+                }
+                Some(reloaded)
+            }
+            Err(e) => {
+                tracing::warn!("Keeping the previous configuration: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Settings that differ between two configurations, as dotted paths two
+/// levels deep (e.g., `memory.recall`, `llm.providers`).
+pub fn changed_fields(old: &AppConfig, new: &AppConfig) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut fields = Vec::new();
+    diff("", &old, &new, 2, &mut fields);
+    fields
+}
+
+fn diff(
+    prefix: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    depth: usize,
+    fields: &mut Vec<String>,
+) {
+    if old == new {
+        return;
+    }
+    match (old.as_object(), new.as_object()) {
+        (Some(old_fields), Some(new_fields)) if depth > 0 => {
+            let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                diff(
+                    &path,
+                    old_fields.get(key).unwrap_or(&serde_json::Value::Null),
+                    new_fields.get(key).unwrap_or(&serde_json::Value::Null),
+                    depth - 1,
+                    fields,
+                );
+            }
+        }
+        _ => fields.push(prefix.to_string()),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Modification time of each `.yml` prompt, by prompt ID.
+fn prompt_stamps(prompts_dir: &Path) -> BTreeMap<String, SystemTime> {
+    let Ok(entries) = std::fs::read_dir(prompts_dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            Some((id, modified(&path)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{File, FileTimes};
+    use std::time::Duration;
+
+    /// Write `contents` to `path` with a modification time `secs` after a
+    /// fixed point, so tests don't depend on the file system's time
+    /// resolution.
+    fn write(path: &Path, contents: &str, secs: u64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(time))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reload_picks_up_config_and_prompt_changes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let guided = temp.path().join(".guided");
+        write(&guided.join("config.yaml"), "memory:\n  recall: false\n", 0);
+        write(&guided.join("prompts/agent.ask.yml"), "id: agent.ask\n", 0);
+
+        let config = AppConfig {
+            workspace: temp.path().to_path_buf(),
+            ..AppConfig::default()
+        }
+        .reloaded()
+        .unwrap();
+        let mut watcher = ConfigWatcher::new(&config);
+        assert!(watcher.poll().is_empty());
+        assert!(watcher.reload(&config).is_none());
+
+        write(&guided.join("config.yaml"), "memory:\n  recall: true\n", 1);
+        write(
+            &guided.join("prompts/agent.brief.yml"),
+            "id: agent.brief\n",
+            1,
+        );
+        std::fs::remove_file(guided.join("prompts/agent.ask.yml")).unwrap();
+        let reloaded = watcher.reload(&config).unwrap();
+        assert!(reloaded.memory.recall);
+        assert_eq!(changed_fields(&config, &reloaded), vec!["memory.recall"]);
+        assert!(watcher.poll().is_empty());
+
+        write(&guided.join("prompts/agent.brief.yml"), "id: brief\n", 2);
+        assert_eq!(
+            watcher.poll(),
+            Changes {
+                config: false,
+                prompts: vec!["agent.brief".to_string()],
+            }
+        );
+
+        // A config that no longer parses keeps the previous one
+        write(&guided.join("config.yaml"), "memory: [", 3);
+        assert!(watcher.reload(&reloaded).is_none());
+    }
+}
@@ -0,0 +1,1163 @@
+//! Output renderers for LLM answers.
+//!
+//! Answers are markdown. [`renderer_for`] picks a [`Renderer`] for an
+//! [`OutputFormat`]: the terminal renderer wraps paragraphs and highlights
+//! code fences, the plain renderer strips formatting, and the HTML renderer
+//! emits a standalone page. JSON output is left to the commands, which embed
+//! the raw markdown.
+//!
+//! Streamed answers can be rendered block by block: [`complete_blocks_end`]
+//! tells how much of the text received so far is made of finished blocks.
+
+use crate::error::{AppError, AppResult};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+/// Format of a command's answer output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Markdown, pretty-printed on a terminal and raw otherwise
+    #[default]
+    Markdown,
+    /// Plain text without markdown formatting
+    Text,
+    /// A standalone HTML page
+    Html,
+    /// Structured JSON
+    Json,
+}
+
+impl OutputFormat {
+    /// Parse a format name, accepting common aliases.
+    pub fn parse(name: &str) -> AppResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "text" | "plain" | "txt" => Ok(OutputFormat::Text),
+            "html" => Ok(OutputFormat::Html),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(AppError::Config(format!(
+                "Unknown output format '{}'. Use markdown, text, html or json.",
+                name
+            ))),
+        }
+    }
+}
+
+/// Settings for [`renderer_for`].
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Use ANSI colors and styles (terminal renderer)
+    pub color: bool,
+
+    /// Column to wrap paragraphs at (terminal renderer)
+    pub width: usize,
+
+    /// Page title (HTML renderer)
+    pub title: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            color: false,
+            width: 80,
+            title: "guided".to_string(),
+        }
+    }
+}
+
+/// Turns a markdown answer into output.
+pub trait Renderer {
+    /// Render a markdown document. The result has no trailing newline.
+    fn render(&self, markdown: &str) -> String;
+}
+
+/// Renderer for an output format. JSON gets the markdown unchanged.
+pub fn renderer_for(format: OutputFormat, options: &RenderOptions) -> Box<dyn Renderer> {
+    match format {
+        OutputFormat::Markdown => Box::new(TerminalRenderer {
+            color: options.color,
+            width: options.width,
+        }),
+        OutputFormat::Text => Box::new(PlainRenderer),
+        OutputFormat::Html => Box::new(HtmlRenderer {
+            title: options.title.clone(),
+        }),
+        OutputFormat::Json => Box::new(MarkdownRenderer),
+    }
+}
+
+/// Byte length of the leading part of `markdown` made of finished blocks:
+/// up to the last blank line outside a code fence. Rendering that part on
+/// its own gives the same output as rendering it with the rest.
+pub fn complete_blocks_end(markdown: &str) -> usize {
+    let mut in_fence = false;
+    let mut offset = 0;
+    let mut end = 0;
+    for line in markdown.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+        let trimmed = line.trim();
+        if fence_marker(trimmed).is_some() {
+            in_fence = !in_fence;
+        } else if trimmed.is_empty() && !in_fence {
+            end = offset;
+        }
+    }
+    end
+}
+
+/// Passes markdown through unchanged.
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, markdown: &str) -> String {
+        markdown.trim_end().to_string()
+    }
+}
+
+/// Pretty-prints markdown for a terminal: styled headings and emphasis,
+/// wrapped paragraphs and lists, and syntax-highlighted code fences.
+pub struct TerminalRenderer {
+    pub color: bool,
+    pub width: usize,
+}
+
+impl TerminalRenderer {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color && !style.is_empty() {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Wrap inline text to the width, starting lines with the prefixes.
+    fn wrap(&self, text: &str, first_prefix: &str, prefix: &str) -> String {
+        let width = self.width.max(20);
+        let mut lines = Vec::new();
+        let mut line = first_prefix.to_string();
+        let mut line_width = visible_width(first_prefix);
+        let mut line_empty = true;
+
+        for word in styled_words(&parse_inline(text)) {
+            let word_width: usize = word.iter().map(|(t, _)| t.chars().count()).sum();
+            if !line_empty && line_width + 1 + word_width > width {
+                lines.push(std::mem::replace(&mut line, prefix.to_string()));
+                line_width = visible_width(prefix);
+                line_empty = true;
+            }
+            if !line_empty {
+                line.push(' ');
+                line_width += 1;
+            }
+            for (piece, style) in &word {
+                line.push_str(&self.paint(style, piece));
+            }
+            line_width += word_width;
+            line_empty = false;
+        }
+        lines.push(line);
+        lines.join("\n")
+    }
+
+    fn code(&self, lang: &str, lines: &[String]) -> String {
+        lines
+            .iter()
+            .map(|line| {
+                if self.color {
+                    highlight_line(line, lang)
+                } else {
+                    line.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn render(&self, markdown: &str) -> String {
+        let blocks = parse_blocks(markdown);
+        join_blocks(&blocks, |block| match block {
+            Block::Heading(_, text) => Some(self.paint(
+                &format!("{}{}", BOLD, CYAN),
+                &plain_inline(&parse_inline(text)),
+            )),
+            Block::Paragraph(text) => Some(self.wrap(text, "", "")),
+            Block::Item {
+                indent,
+                marker,
+                text,
+            } => {
+                let bullet = if marker == "-" {
+                    "•"
+                } else {
+                    marker.as_str()
+                };
+                let first = format!("{}{} ", " ".repeat(*indent), bullet);
+                let rest = " ".repeat(first.chars().count());
+                Some(self.wrap(text, &first, &rest))
+            }
+            Block::Quote(text) => {
+                let bar = self.paint(DIM, "│ ");
+                Some(self.wrap(text, &bar, &bar))
+            }
+            Block::Code { lang, lines } => Some(self.code(lang, lines)),
+            Block::Table(rows) => Some(rows.join("\n")),
+            Block::Rule => Some(self.paint(DIM, &"─".repeat(self.width.clamp(20, 80)))),
+        })
+    }
+}
+
+/// Narrowest column [`side_by_side`] lays out before stacking instead.
+pub const MIN_COLUMN_WIDTH: usize = 30;
+
+/// Lay out markdown answers as columns, each under its header, to fit
+/// `width`. When the columns would be narrower than [`MIN_COLUMN_WIDTH`] the
+/// answers are stacked instead. Columns are not colored, so they line up.
+pub fn side_by_side(answers: &[(String, String)], width: usize) -> String {
+    const SEPARATOR: &str = " │ ";
+    let count = answers.len().max(1);
+    let gaps = SEPARATOR.chars().count() * (count - 1);
+    let column_width = width.saturating_sub(gaps) / count;
+
+    if count == 1 || column_width < MIN_COLUMN_WIDTH {
+        let renderer = TerminalRenderer {
+            color: false,
+            width,
+        };
+        return answers
+            .iter()
+            .map(|(header, markdown)| {
+                let rule = "─".repeat(header.chars().count().min(width));
+                format!("{}\n{}\n{}", header, rule, renderer.render(markdown))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+    }
+
+    let renderer = TerminalRenderer {
+        color: false,
+        width: column_width,
+    };
+    let columns: Vec<Vec<String>> = answers
+        .iter()
+        .map(|(header, markdown)| {
+            let mut lines = hard_wrap(header, column_width);
+            lines.push("─".repeat(column_width));
+            for line in renderer.render(markdown).lines() {
+                lines.extend(hard_wrap(line, column_width));
+            }
+            lines
+        })
+        .collect();
+
+    let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+    (0..height)
+        .map(|row| {
+            let cells: Vec<String> = columns
+                .iter()
+                .map(|lines| {
+                    let cell = lines.get(row).map(String::as_str).unwrap_or("");
+                    let padding = column_width.saturating_sub(cell.chars().count());
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            cells.join(SEPARATOR).trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split a line into pieces of at most `width` chars, for code and other
+/// lines the renderer does not wrap.
+fn hard_wrap(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(width.max(1))
+        .map(|piece| piece.iter().collect())
+        .collect()
+}
+
+/// Width of text on a terminal, not counting ANSI escape sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            in_escape = c != 'm';
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Strips markdown formatting, keeping the text, list markers and code.
+pub struct PlainRenderer;
+
+impl Renderer for PlainRenderer {
+    fn render(&self, markdown: &str) -> String {
+        let blocks = parse_blocks(markdown);
+        join_blocks(&blocks, |block| match block {
+            Block::Heading(_, text) | Block::Paragraph(text) => {
+                Some(plain_inline(&parse_inline(text)))
+            }
+            Block::Item {
+                indent,
+                marker,
+                text,
+            } => Some(format!(
+                "{}{} {}",
+                " ".repeat(*indent),
+                marker,
+                plain_inline(&parse_inline(text))
+            )),
+            Block::Quote(text) => Some(format!("  {}", plain_inline(&parse_inline(text)))),
+            Block::Code { lines, .. } => Some(lines.join("\n")),
+            Block::Table(rows) => Some(
+                table_cells(rows)
+                    .iter()
+                    .map(|cells| {
+                        cells
+                            .iter()
+                            .map(|cell| plain_inline(&parse_inline(cell)))
+                            .collect::<Vec<_>>()
+                            .join("\t")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Block::Rule => None,
+        })
+    }
+}
+
+/// Emits a standalone HTML page.
+pub struct HtmlRenderer {
+    pub title: String,
+}
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; \
+padding: 0 1rem; line-height: 1.5; color: #1f2328; }
+pre { background: #f6f8fa; padding: 0.75rem 1rem; overflow-x: auto; border-radius: 6px; }
+code { font-family: ui-monospace, monospace; font-size: 0.9em; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid #d0d7de; color: #59636e; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 0.25rem 0.5rem; }";
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, markdown: &str) -> String {
+        let mut body = String::new();
+        // Open lists as (indent, tag); each has an open <li>
+        let mut lists: Vec<(usize, &str)> = Vec::new();
+
+        for block in parse_blocks(markdown) {
+            if let Block::Item {
+                indent,
+                marker,
+                text,
+            } = &block
+            {
+                let tag = if marker == "-" { "ul" } else { "ol" };
+                while lists.last().is_some_and(|(i, _)| i > indent) {
+                    let (_, open) = lists.pop().unwrap();
+                    body.push_str(&format!("</li></{}>\n", open));
+                }
+                match lists.last() {
+                    Some((i, open)) if i == indent && *open == tag => body.push_str("</li>\n"),
+                    Some((i, open)) if i == indent => {
+                        body.push_str(&format!("</li></{}>\n<{}>\n", open, tag));
+                        lists.pop();
+                        lists.push((*indent, tag));
+                    }
+                    _ => {
+                        body.push_str(&format!("<{}>\n", tag));
+                        lists.push((*indent, tag));
+                    }
+                }
+                body.push_str(&format!("<li>{}", html_inline(&parse_inline(text))));
+                continue;
+            }
+
+            while let Some((_, open)) = lists.pop() {
+                body.push_str(&format!("</li></{}>\n", open));
+            }
+            match block {
+                Block::Heading(level, text) => body.push_str(&format!(
+                    "<h{0}>{1}</h{0}>\n",
+                    level,
+                    html_inline(&parse_inline(&text))
+                )),
+                Block::Paragraph(text) => {
+                    body.push_str(&format!("<p>{}</p>\n", html_inline(&parse_inline(&text))))
+                }
+                Block::Quote(text) => body.push_str(&format!(
+                    "<blockquote><p>{}</p></blockquote>\n",
+                    html_inline(&parse_inline(&text))
+                )),
+                Block::Code { lang, lines } => {
+                    let class = if lang.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" class=\"language-{}\"", escape_html(&lang))
+                    };
+                    body.push_str(&format!(
+                        "<pre><code{}>{}</code></pre>\n",
+                        class,
+                        escape_html(&lines.join("\n"))
+                    ));
+                }
+                Block::Table(rows) => {
+                    body.push_str("<table>\n");
+                    for (i, cells) in table_cells(&rows).iter().enumerate() {
+                        let tag = if i == 0 { "th" } else { "td" };
+                        let cells: String = cells
+                            .iter()
+                            .map(|cell| {
+                                format!("<{0}>{1}</{0}>", tag, html_inline(&parse_inline(cell)))
+                            })
+                            .collect();
+                        body.push_str(&format!("<tr>{}</tr>\n", cells));
+                    }
+                    body.push_str("</table>\n");
+                }
+                Block::Rule => body.push_str("<hr>\n"),
+                Block::Item { .. } => unreachable!(),
+            }
+        }
+        while let Some((_, open)) = lists.pop() {
+            body.push_str(&format!("</li></{}>\n", open));
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>",
+            escape_html(&self.title),
+            HTML_STYLE,
+            body
+        )
+    }
+}
+
+/// A markdown block.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    /// A list item; `marker` is `-` for bullets or the number (`1.`)
+    Item {
+        indent: usize,
+        marker: String,
+        text: String,
+    },
+    Quote(String),
+    Code {
+        lang: String,
+        lines: Vec<String>,
+    },
+    Table(Vec<String>),
+    Rule,
+}
+
+/// Render blocks and separate them with blank lines, keeping consecutive
+/// list items together. Blocks rendered as `None` are left out.
+fn join_blocks(blocks: &[Block], mut render: impl FnMut(&Block) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&Block> = None;
+    for block in blocks {
+        let Some(rendered) = render(block) else {
+            continue;
+        };
+        if let Some(previous) = previous {
+            let tight = matches!((previous, block), (Block::Item { .. }, Block::Item { .. }));
+            out.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        out.push_str(&rendered);
+        previous = Some(block);
+    }
+    out
+}
+
+/// The opening of a code fence (`` ``` `` or `~~~`) and its info string.
+fn fence_marker(trimmed: &str) -> Option<&str> {
+    ["```", "~~~"]
+        .iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+        .map(|info| info.trim_start_matches(['`', '~']).trim())
+}
+
+/// Split markdown into blocks.
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    // Whether the last block still takes continuation lines
+    let mut open = false;
+    let mut lines = markdown.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if let Some(lang) = fence_marker(trimmed) {
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if fence_marker(line.trim()).is_some_and(|info| info.is_empty()) {
+                    break;
+                }
+                code.push(line.to_string());
+            }
+            blocks.push(Block::Code {
+                lang: lang.split_whitespace().next().unwrap_or("").to_lowercase(),
+                lines: code,
+            });
+            open = false;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            open = false;
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].chars().next().is_none_or(|c| c == ' ') {
+            let text = trimmed[hashes..].trim().trim_end_matches('#').trim_end();
+            blocks.push(Block::Heading(hashes, text.to_string()));
+            open = false;
+            continue;
+        }
+
+        let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.len() >= 3
+            && ["-", "*", "_"]
+                .iter()
+                .any(|c| compact.chars().all(|x| x.to_string() == *c))
+        {
+            blocks.push(Block::Rule);
+            open = false;
+            continue;
+        }
+
+        if trimmed.starts_with('|') {
+            match blocks.last_mut() {
+                Some(Block::Table(rows)) if open => rows.push(trimmed.to_string()),
+                _ => blocks.push(Block::Table(vec![trimmed.to_string()])),
+            }
+            open = true;
+            continue;
+        }
+
+        if let Some(quoted) = trimmed.strip_prefix('>') {
+            let quoted = quoted.trim();
+            match blocks.last_mut() {
+                Some(Block::Quote(text)) if open => {
+                    text.push(' ');
+                    text.push_str(quoted);
+                }
+                _ => blocks.push(Block::Quote(quoted.to_string())),
+            }
+            open = true;
+            continue;
+        }
+
+        if let Some((marker, text)) = list_item(trimmed) {
+            blocks.push(Block::Item {
+                indent: line.len() - line.trim_start().len(),
+                marker,
+                text: text.to_string(),
+            });
+            open = true;
+            continue;
+        }
+
+        match blocks.last_mut() {
+            Some(Block::Paragraph(text) | Block::Quote(text) | Block::Item { text, .. })
+                if open =>
+            {
+                text.push(' ');
+                text.push_str(trimmed);
+            }
+            _ => blocks.push(Block::Paragraph(trimmed.to_string())),
+        }
+        open = true;
+    }
+    blocks
+}
+
+/// The marker (`-` for bullets, `3.` for numbers) and text of a list item.
+fn list_item(trimmed: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = trimmed.strip_prefix(bullet) {
+            return Some(("-".to_string(), text.trim_start()));
+        }
+    }
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = &trimmed[digits..];
+    [". ", ") "]
+        .iter()
+        .find_map(|sep| rest.strip_prefix(sep))
+        .map(|text| (format!("{}.", &trimmed[..digits]), text.trim_start()))
+}
+
+/// Cells of table rows, without the header separator row.
+fn table_cells(rows: &[String]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| {
+            row.trim()
+                .trim_start_matches('|')
+                .trim_end_matches('|')
+                .split('|')
+                .map(|cell| cell.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| {
+            !cells
+                .iter()
+                .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':' | ' ')))
+        })
+        .collect()
+}
+
+/// An inline markdown span.
+#[derive(Debug, Clone, PartialEq)]
+enum Span {
+    Text(String),
+    Code(String),
+    Strong(String),
+    Emphasis(String),
+    Link { text: String, url: String },
+}
+
+/// Split inline markdown into spans. Nested formatting inside emphasis and
+/// links is flattened to its text.
+fn parse_inline(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    let find = |from: usize, pattern: &[char]| {
+        (from..chars.len().saturating_sub(pattern.len() - 1))
+            .find(|&j| chars[j..j + pattern.len()] == *pattern)
+    };
+    let collect = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+
+    while i < chars.len() {
+        let c = chars[i];
+        let mut span = None;
+
+        if c == '\\' && chars.get(i + 1).is_some_and(|n| n.is_ascii_punctuation()) {
+            plain.push(chars[i + 1]);
+            i += 2;
+            continue;
+        } else if c == '`' {
+            if let Some(end) = find(i + 1, &['`']) {
+                span = Some((Span::Code(collect(i + 1, end)), end + 1));
+            }
+        } else if (c == '*' || c == '_') && chars.get(i + 1) == Some(&c) {
+            if let Some(end) = find(i + 2, &[c, c]).filter(|&end| end > i + 2) {
+                let inner = plain_inline(&parse_inline(&collect(i + 2, end)));
+                span = Some((Span::Strong(inner), end + 2));
+            }
+        } else if (c == '*' || c == '_')
+            && chars.get(i + 1).is_some_and(|n| !n.is_whitespace())
+            // snake_case and 2*3 are not emphasis
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+        {
+            if let Some(end) = find(i + 1, &[c]).filter(|&end| end > i + 1) {
+                let inner = plain_inline(&parse_inline(&collect(i + 1, end)));
+                span = Some((Span::Emphasis(inner), end + 1));
+            }
+        } else if c == '[' {
+            if let Some(close) = find(i + 1, &[']', '(']) {
+                if let Some(end) = find(close + 2, &[')']) {
+                    let text = plain_inline(&parse_inline(&collect(i + 1, close)));
+                    let url = collect(close + 2, end).trim().to_string();
+                    span = Some((Span::Link { text, url }, end + 1));
+                }
+            }
+        }
+
+        match span {
+            Some((span, next)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::Text(std::mem::take(&mut plain)));
+                }
+                spans.push(span);
+                i = next;
+            }
+            None => {
+                plain.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::Text(plain));
+    }
+    spans
+}
+
+/// Inline spans as unformatted text.
+fn plain_inline(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) | Span::Code(text) | Span::Strong(text) | Span::Emphasis(text) => {
+                text.clone()
+            }
+            Span::Link { text, url } if text == url || text.is_empty() => url.clone(),
+            Span::Link { text, url } => format!("{} ({})", text, url),
+        })
+        .collect()
+}
+
+/// Inline spans as words of styled pieces, for wrapping.
+fn styled_words(spans: &[Span]) -> Vec<Vec<(String, String)>> {
+    let mut pieces: Vec<(String, String)> = Vec::new();
+    for span in spans {
+        match span {
+            Span::Text(text) => pieces.push((text.clone(), String::new())),
+            Span::Code(text) => pieces.push((text.clone(), YELLOW.to_string())),
+            Span::Strong(text) => pieces.push((text.clone(), BOLD.to_string())),
+            Span::Emphasis(text) => pieces.push((text.clone(), ITALIC.to_string())),
+            Span::Link { text, url } if text == url || text.is_empty() => {
+                pieces.push((url.clone(), format!("{}{}", UNDERLINE, BLUE)))
+            }
+            Span::Link { text, url } => {
+                pieces.push((text.clone(), UNDERLINE.to_string()));
+                pieces.push((format!(" ({})", url), DIM.to_string()));
+            }
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut word: Vec<(String, String)> = Vec::new();
+    for (text, style) in pieces {
+        for (i, part) in text.split(char::is_whitespace).enumerate() {
+            if i > 0 && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if !part.is_empty() {
+                word.push((part.to_string(), style.clone()));
+            }
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Inline spans as HTML.
+fn html_inline(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) => escape_html(text),
+            Span::Code(text) => format!("<code>{}</code>", escape_html(text)),
+            Span::Strong(text) => format!("<strong>{}</strong>", escape_html(text)),
+            Span::Emphasis(text) => format!("<em>{}</em>", escape_html(text)),
+            Span::Link { text, url } => {
+                let label = if text.is_empty() { url } else { text };
+                if url.to_lowercase().trim_start().starts_with("javascript:") {
+                    escape_html(label)
+                } else {
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(url),
+                        escape_html(label)
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Keywords highlighted in code fences of a language.
+fn keywords(lang: &str) -> &'static [&'static str] {
+    match lang {
+        "rust" | "rs" => &[
+            "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "false",
+            "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+            "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+            "unsafe", "use", "where", "while",
+        ],
+        "python" | "py" => &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in",
+            "is", "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while",
+            "with", "yield",
+        ],
+        "javascript" | "js" | "typescript" | "ts" | "jsx" | "tsx" => &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "function",
+            "if",
+            "import",
+            "in",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "of",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "while",
+        ],
+        "go" | "golang" => &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "false",
+            "for",
+            "func",
+            "go",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        "sh" | "bash" | "shell" | "zsh" | "console" => &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "while",
+        ],
+        "sql" => &[
+            "AND", "AS", "BY", "CREATE", "DELETE", "FROM", "GROUP", "INSERT", "INTO", "JOIN",
+            "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER", "SELECT", "SET", "TABLE", "UPDATE",
+            "VALUES", "WHERE",
+        ],
+        "java" | "c" | "cpp" | "c++" | "csharp" | "cs" | "kotlin" | "kt" => &[
+            "break",
+            "case",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "for",
+            "if",
+            "import",
+            "new",
+            "null",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "static",
+            "struct",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "void",
+            "while",
+        ],
+        _ => &[],
+    }
+}
+
+/// Line comment prefix of a language.
+fn comment_prefix(lang: &str) -> &'static str {
+    match lang {
+        "python" | "py" | "sh" | "bash" | "shell" | "zsh" | "console" | "yaml" | "yml" | "toml"
+        | "ruby" | "rb" | "dockerfile" | "makefile" => "#",
+        "sql" | "lua" | "haskell" | "hs" => "--",
+        _ => "//",
+    }
+}
+
+/// Color a line of code: keywords, strings, numbers and line comments.
+/// Languages without a keyword list are left as they are.
+fn highlight_line(line: &str, lang: &str) -> String {
+    let keywords = keywords(lang);
+    if keywords.is_empty() {
+        return line.to_string();
+    }
+    let comment = comment_prefix(lang);
+    let case_insensitive = lang == "sql";
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().collect();
+
+        if rest.starts_with(comment) && (i == 0 || comment != "#" || chars[i - 1].is_whitespace()) {
+            out.push_str(&format!("{}{}{}", DIM, rest, RESET));
+            break;
+        }
+
+        // Rust lifetimes ('a) are not strings
+        let quote = c == '"' || c == '`' || (c == '\'' && !matches!(lang, "rust" | "rs"));
+        if quote {
+            let mut end = i + 1;
+            while end < chars.len() && chars[end] != c {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            let end = (end + 1).min(chars.len());
+            let literal: String = chars[i..end].iter().collect();
+            out.push_str(&format!("{}{}{}", GREEN, literal, RESET));
+            i = end;
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let is_keyword = if case_insensitive {
+                keywords.iter().any(|k| k.eq_ignore_ascii_case(&word))
+            } else {
+                keywords.contains(&word.as_str())
+            };
+            if is_keyword {
+                out.push_str(&format!("{}{}{}", MAGENTA, word, RESET));
+            } else if c.is_ascii_digit() {
+                out.push_str(&format!("{}{}{}", YELLOW, word, RESET));
+            } else {
+                out.push_str(&word);
+            }
+            continue;
+        }
+
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWER: &str = "# Deploying\n\nRun the **deploy** script with `--env prod`, \
+        see [the guide](https://example.com/deploy).\n\n\
+        - Build the image\n- Push it\n  - to the registry\n\n\
+        ```rust\nlet x = \"hi\"; // greet\n```\n\n> Deploys are frozen on Fridays.\n";
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(OutputFormat::parse("md").unwrap(), OutputFormat::Markdown);
+        assert_eq!(OutputFormat::parse("TEXT").unwrap(), OutputFormat::Text);
+        assert_eq!(OutputFormat::parse("html").unwrap(), OutputFormat::Html);
+        assert!(OutputFormat::parse("pdf").is_err());
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let blocks = parse_blocks(ANSWER);
+        assert_eq!(blocks[0], Block::Heading(1, "Deploying".to_string()));
+        assert!(matches!(&blocks[1], Block::Paragraph(text) if text.starts_with("Run the")));
+        assert_eq!(
+            blocks[4],
+            Block::Item {
+                indent: 2,
+                marker: "-".to_string(),
+                text: "to the registry".to_string(),
+            }
+        );
+        assert_eq!(
+            blocks[5],
+            Block::Code {
+                lang: "rust".to_string(),
+                lines: vec!["let x = \"hi\"; // greet".to_string()],
+            }
+        );
+        assert_eq!(
+            blocks[6],
+            Block::Quote("Deploys are frozen on Fridays.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        assert_eq!(
+            parse_inline("a **b** `c` *d* [e](f) snake_case_name 2*3*4"),
+            vec![
+                Span::Text("a ".to_string()),
+                Span::Strong("b".to_string()),
+                Span::Text(" ".to_string()),
+                Span::Code("c".to_string()),
+                Span::Text(" ".to_string()),
+                Span::Emphasis("d".to_string()),
+                Span::Text(" ".to_string()),
+                Span::Link {
+                    text: "e".to_string(),
+                    url: "f".to_string(),
+                },
+                Span::Text(" snake_case_name 2*3*4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_renderer_strips_formatting() {
+        assert_eq!(
+            PlainRenderer.render(ANSWER),
+            "Deploying\n\n\
+             Run the deploy script with --env prod, see the guide (https://example.com/deploy).\n\n\
+             - Build the image\n- Push it\n  - to the registry\n\n\
+             let x = \"hi\"; // greet\n\n  Deploys are frozen on Fridays."
+        );
+    }
+
+    #[test]
+    fn test_terminal_renderer_wraps_and_highlights() {
+        let plain = TerminalRenderer {
+            color: false,
+            width: 30,
+        }
+        .render(ANSWER);
+        assert!(!plain.contains('\x1b'));
+        assert!(
+            plain.lines().all(|line| line.chars().count() <= 30),
+            "{}",
+            plain
+        );
+        assert!(plain.contains("• Build the image\n• Push it\n  • to the registry"));
+        assert!(plain.contains("│ Deploys are frozen on"));
+
+        let colored = TerminalRenderer {
+            color: true,
+            width: 80,
+        }
+        .render(ANSWER);
+        assert!(colored.contains(&format!("{}deploy{}", BOLD, RESET)));
+        assert!(colored.contains(&format!("{}let{}", MAGENTA, RESET)));
+        assert!(colored.contains(&format!("{}\"hi\"{}", GREEN, RESET)));
+        assert!(colored.contains(&format!("{}// greet{}", DIM, RESET)));
+
+        let colored = TerminalRenderer {
+            color: true,
+            width: 30,
+        }
+        .render(ANSWER);
+        assert!(colored.contains("Deploys are frozen on\n"));
+    }
+
+    #[test]
+    fn test_html_renderer_is_standalone_and_escaped() {
+        let html = HtmlRenderer {
+            title: "Q <1>".to_string(),
+        }
+        .render(&format!("{}\n<script>alert(1)</script>\n", ANSWER));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Q &lt;1&gt;</title>"));
+        assert!(html.contains("<h1>Deploying</h1>"));
+        assert!(html.contains("<strong>deploy</strong>"));
+        assert!(html.contains("<a href=\"https://example.com/deploy\">the guide</a>"));
+        assert!(html.contains(
+            "<ul>\n<li>Build the image</li>\n<li>Push it<ul>\n<li>to the registry</li></ul>\n</li></ul>"
+        ));
+        assert!(html.contains("<pre><code class=\"language-rust\">let x = &quot;hi&quot;;"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_complete_blocks_end() {
+        assert_eq!(complete_blocks_end("One paragraph"), 0);
+        let text = "First.\n\nSecond";
+        assert_eq!(&text[..complete_blocks_end(text)], "First.\n\n");
+        // A blank line inside a code fence does not end a block
+        let text = "Intro.\n\n```\na\n\nb\n";
+        assert_eq!(&text[..complete_blocks_end(text)], "Intro.\n\n");
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let answers = vec![
+            ("ollama:llama3".to_string(), "Use **cargo**.".to_string()),
+            (
+                "openai:gpt-4o".to_string(),
+                "Run the build.\n\n```\n0123456789012345678901234567890123456789\n```".to_string(),
+            ),
+        ];
+        let text = side_by_side(&answers, 80);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("ollama:llama3"));
+        assert!(lines[0].contains(" │ openai:gpt-4o"));
+        assert!(lines[2].starts_with("Use cargo."));
+        assert!(lines[2].ends_with("│ Run the build."));
+        // The long code line is split to fit its column
+        assert!(lines.iter().all(|line| line.chars().count() <= 80));
+        assert!(lines.iter().any(|line| line.ends_with("│ 89")));
+
+        // Too narrow for columns: answers are stacked
+        let text = side_by_side(&answers, 50);
+        assert!(text.starts_with("ollama:llama3\n─────────────\nUse cargo.\n\nopenai:gpt-4o"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Code review findings.
+//!
+//! This module defines the structured findings produced by `guided review`,
+//! parses them from LLM output, and converts them to SARIF for CI annotation.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Severity of a review finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Parse a severity label, accepting common aliases.
+    pub fn parse(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "error" | "critical" | "high" | "blocker" => Some(Severity::Error),
+            "warning" | "warn" | "medium" | "major" => Some(Severity::Warning),
+            "info" | "note" | "low" | "minor" | "suggestion" | "nit" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+
+    /// SARIF result level for this severity.
+    pub fn sarif_level(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "note",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A single review finding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewFinding {
+    /// How serious the issue is
+    pub severity: Severity,
+
+    /// File the finding applies to
+    pub file: String,
+
+    /// First line of the affected range (1-based, new file)
+    pub start_line: u32,
+
+    /// Last line of the affected range (inclusive)
+    pub end_line: u32,
+
+    /// Description of the issue
+    pub message: String,
+
+    /// Suggested fix, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl ReviewFinding {
+    /// Human-readable location (e.g., "src/main.rs:10-12").
+    pub fn location(&self) -> String {
+        if self.start_line == self.end_line {
+            format!("{}:{}", self.file, self.start_line)
+        } else {
+            format!("{}:{}-{}", self.file, self.start_line, self.end_line)
+        }
+    }
+}
+
+/// Loosely-typed finding as emitted by the model.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFinding {
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default, alias = "line", alias = "start_line")]
+    start_line: Option<u32>,
+    #[serde(default, alias = "end_line")]
+    end_line: Option<u32>,
+    #[serde(default, alias = "issue", alias = "description")]
+    message: Option<String>,
+    #[serde(default, alias = "fix")]
+    suggestion: Option<String>,
+}
+
+/// Parse findings from model output.
+///
+/// Accepts a JSON array, optionally wrapped in prose or code fences.
+/// Findings without a message are dropped; a missing file defaults to
+/// `default_file` and unknown severities default to warning.
+pub fn parse_findings(text: &str, default_file: &str) -> AppResult<Vec<ReviewFinding>> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        return Err(AppError::Serialization(
+            "Review output does not contain a JSON array of findings".to_string(),
+        ));
+    };
+
+    if end < start {
+        return Err(AppError::Serialization(
+            "Review output does not contain a JSON array of findings".to_string(),
+        ));
+    }
+
+    let raw: Vec<RawFinding> = serde_json::from_str(&text[start..=end])?;
+
+    let findings = raw
+        .into_iter()
+        .filter_map(|r| {
+            let message = r.message.filter(|m| !m.trim().is_empty())?;
+            let start_line = r.start_line.unwrap_or(1).max(1);
+            let end_line = r.end_line.unwrap_or(start_line).max(start_line);
+
+            Some(ReviewFinding {
+                severity: r
+                    .severity
+                    .as_deref()
+                    .and_then(Severity::parse)
+                    .unwrap_or(Severity::Warning),
+                file: r
+                    .file
+                    .filter(|f| !f.trim().is_empty())
+                    .unwrap_or_else(|| default_file.to_string()),
+                start_line,
+                end_line,
+                message: message.trim().to_string(),
+                suggestion: r.suggestion.filter(|s| !s.trim().is_empty()),
+            })
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+/// Convert findings to a SARIF 2.1.0 log.
+pub fn to_sarif(findings: &[ReviewFinding], tool_version: &str) -> serde_json::Value {
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|f| {
+            let mut text = f.message.clone();
+            if let Some(ref suggestion) = f.suggestion {
+                text.push_str("\n\nSuggestion: ");
+                text.push_str(suggestion);
+            }
+
+            serde_json::json!({
+                "ruleId": format!("guided-review/{}", f.severity),
+                "level": f.severity.sarif_level(),
+                "message": { "text": text },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": f.file },
+                        "region": {
+                            "startLine": f.start_line,
+                            "endLine": f.end_line
+                        }
+                    }
+                }]
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "guided-review",
+                    "version": tool_version,
+                    "informationUri": "https://github.com/guided-engineering/guided-agent"
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_findings_with_fences() {
+        let text = r#"Here are the findings:
+```json
+[
+  {"severity": "high", "file": "src/lib.rs", "startLine": 10, "endLine": 12,
+   "message": "Unchecked unwrap", "suggestion": "Propagate the error"},
+  {"severity": "nit", "line": 3, "message": "Typo in comment"},
+  {"severity": "warning", "message": ""}
+]
+```"#;
+
+        let findings = parse_findings(text, "src/main.rs").unwrap();
+        assert_eq!(findings.len(), 2);
+
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[0].location(), "src/lib.rs:10-12");
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("Propagate the error")
+        );
+
+        assert_eq!(findings[1].severity, Severity::Info);
+        assert_eq!(findings[1].file, "src/main.rs");
+        assert_eq!(findings[1].location(), "src/main.rs:3");
+    }
+
+    #[test]
+    fn test_parse_findings_empty() {
+        assert!(parse_findings("[]", "a.rs").unwrap().is_empty());
+        assert!(parse_findings("", "a.rs").unwrap().is_empty());
+        assert!(parse_findings("No issues found.", "a.rs").is_err());
+    }
+
+    #[test]
+    fn test_to_sarif() {
+        let findings = vec![ReviewFinding {
+            severity: Severity::Info,
+            file: "src/lib.rs".to_string(),
+            start_line: 4,
+            end_line: 4,
+            message: "Consider a doc comment".to_string(),
+            suggestion: None,
+        }];
+
+        let sarif = to_sarif(&findings, "0.1.0");
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["level"], "note");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/lib.rs"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Planning and applying patches to the workspace.
+//!
+//! Patches are applied in two phases: `plan_changes` computes every new file
+//! in memory (so a bad hunk fails before anything is touched), then
+//! `apply_changes` backs up the originals and writes all files, rolling back
+//! if any write fails.
+
+use crate::backup::create_backup;
+use crate::patch::{apply_file_patch, FilePatch};
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// What a change does to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// A fully computed change to one file.
+#[derive(Debug, Clone)]
+pub struct PlannedChange {
+    /// Path relative to the workspace
+    pub path: String,
+
+    /// Create, modify or delete
+    pub kind: ChangeKind,
+
+    /// Current contents (`None` for new files)
+    pub original: Option<String>,
+
+    /// Contents after the change (`None` for deletions)
+    pub updated: Option<String>,
+
+    /// Lines added
+    pub additions: usize,
+
+    /// Lines removed
+    pub deletions: usize,
+}
+
+/// Result of applying changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    /// Backup that can be restored to undo the changes
+    pub backup_id: String,
+
+    /// Files changed, relative to the workspace
+    pub files: Vec<String>,
+}
+
+/// Compute the result of each patch without touching the filesystem.
+pub fn plan_changes(workspace: &Path, patches: &[FilePatch]) -> AppResult<Vec<PlannedChange>> {
+    let mut seen = HashSet::new();
+    let mut changes = Vec::new();
+
+    for patch in patches {
+        if let (Some(old), Some(new)) = (&patch.old_path, &patch.new_path) {
+            if old != new {
+                return Err(AppError::Edit(format!(
+                    "Renames are not supported: {} -> {}",
+                    old, new
+                )));
+            }
+        }
+
+        let path = patch.path().to_string();
+        let target = resolve_path(workspace, &path)?;
+
+        if !seen.insert(path.clone()) {
+            return Err(AppError::Edit(format!(
+                "File appears more than once in patch: {}",
+                path
+            )));
+        }
+
+        let change = if patch.is_new() {
+            if target.exists() {
+                return Err(AppError::Edit(format!(
+                    "Cannot create {}: file already exists",
+                    path
+                )));
+            }
+            PlannedChange {
+                kind: ChangeKind::Create,
+                original: None,
+                updated: Some(apply_file_patch("", patch)?),
+                additions: patch.additions(),
+                deletions: patch.deletions(),
+                path,
+            }
+        } else {
+            let original = std::fs::read_to_string(&target)
+                .map_err(|e| AppError::Edit(format!("Cannot read {}: {}", path, e)))?;
+
+            if patch.is_deleted() {
+                PlannedChange {
+                    kind: ChangeKind::Delete,
+                    updated: None,
+                    additions: 0,
+                    deletions: original.lines().count(),
+                    original: Some(original),
+                    path,
+                }
+            } else {
+                let updated = apply_file_patch(&original, patch)?;
+                PlannedChange {
+                    kind: ChangeKind::Modify,
+                    original: Some(original),
+                    updated: Some(updated),
+                    additions: patch.additions(),
+                    deletions: patch.deletions(),
+                    path,
+                }
+            }
+        };
+
+        changes.push(change);
+    }
+
+    Ok(changes)
+}
+
+/// Back up originals and write all planned changes.
+///
+/// If any write fails, files already written are restored and the error is
+/// returned; the backup is kept either way.
+pub fn apply_changes(workspace: &Path, changes: &[PlannedChange]) -> AppResult<ApplyReport> {
+    let backup = create_backup(workspace, changes)?;
+
+    for (index, change) in changes.iter().enumerate() {
+        if let Err(e) = write_change(workspace, change) {
+            tracing::error!("Failed to apply {}: {}; rolling back", change.path, e);
+            for done in changes[..index].iter().rev() {
+                if let Err(rollback_err) = revert_change(workspace, done) {
+                    tracing::error!("Rollback of {} failed: {}", done.path, rollback_err);
+                }
+            }
+            return Err(AppError::Edit(format!(
+                "Failed to apply {}: {} (changes rolled back; backup {})",
+                change.path, e, backup.id
+            )));
+        }
+    }
+
+    tracing::info!("Applied {} file changes", changes.len());
+
+    Ok(ApplyReport {
+        backup_id: backup.id,
+        files: changes.iter().map(|c| c.path.clone()).collect(),
+    })
+}
+
+/// Write one change to disk.
+fn write_change(workspace: &Path, change: &PlannedChange) -> AppResult<()> {
+    let target = workspace.join(&change.path);
+    match change.updated {
+        Some(ref contents) => write_atomic(&target, contents),
+        None => Ok(std::fs::remove_file(&target)?),
+    }
+}
+
+/// Undo one change using its in-memory original.
+fn revert_change(workspace: &Path, change: &PlannedChange) -> AppResult<()> {
+    let target = workspace.join(&change.path);
+    match change.original {
+        Some(ref contents) => write_atomic(&target, contents),
+        None => Ok(std::fs::remove_file(&target)?),
+    }
+}
+
+/// Write via a temporary file in the same directory, then rename.
+fn write_atomic(target: &Path, contents: &str) -> AppResult<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::Edit(format!("Invalid file name: {:?}", target)))?;
+    let temp = target.with_file_name(format!(".{}.guided-tmp", file_name));
+
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, target).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })?;
+
+    Ok(())
+}
+
+/// Resolve a patch path inside the workspace, rejecting escapes.
+fn resolve_path(workspace: &Path, path: &str) -> AppResult<PathBuf> {
+    let relative = Path::new(path);
+
+    let escapes = path.is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(AppError::Edit(format!(
+            "Refusing to edit path outside the workspace: {}",
+            path
+        )));
+    }
+
+    if relative.starts_with(".git") || relative.starts_with(".guided/backups") {
+        return Err(AppError::Edit(format!(
+            "Refusing to edit protected path: {}",
+            path
+        )));
+    }
+
+    Ok(workspace.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{list_backups, restore_backup};
+    use crate::patch::parse_patch;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const PATCH: &str = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-pub fn one() -> u32 { 1 }\n+pub fn one() -> u32 { 2 }\n pub fn two() -> u32 { 2 }\n--- /dev/null\n+++ b/README.md\n@@ -0,0 +1 @@\n+# Demo\n";
+
+    fn workspace() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        fs::write(
+            temp.path().join("src/lib.rs"),
+            "pub fn one() -> u32 { 1 }\npub fn two() -> u32 { 2 }\n",
+        )
+        .unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_apply_and_restore() {
+        let temp = workspace();
+        let patches = parse_patch(PATCH).unwrap();
+
+        let changes = plan_changes(temp.path(), &patches).unwrap();
+        assert_eq!(changes[0].kind, ChangeKind::Modify);
+        assert_eq!(changes[1].kind, ChangeKind::Create);
+
+        let report = apply_changes(temp.path(), &changes).unwrap();
+        assert_eq!(report.files, vec!["src/lib.rs", "README.md"]);
+        assert!(fs::read_to_string(temp.path().join("src/lib.rs"))
+            .unwrap()
+            .contains("{ 2 }\npub fn two"));
+        assert!(temp.path().join("README.md").exists());
+
+        let backups = list_backups(temp.path()).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].id, report.backup_id);
+
+        restore_backup(temp.path(), &report.backup_id).unwrap();
+        assert!(fs::read_to_string(temp.path().join("src/lib.rs"))
+            .unwrap()
+            .starts_with("pub fn one() -> u32 { 1 }"));
+        assert!(!temp.path().join("README.md").exists());
+    }
+
+    #[test]
+    fn test_plan_fails_without_touching_files() {
+        let temp = workspace();
+        let bad = format!(
+            "{}--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-missing line\n+x\n",
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+new\n"
+        );
+        let patches = parse_patch(&bad).unwrap();
+
+        assert!(plan_changes(temp.path(), &patches).is_err());
+        assert!(!temp.path().join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_workspace() {
+        let temp = workspace();
+        for path in ["../escape.txt", "/etc/passwd", ".git/config"] {
+            let patch = FilePatch {
+                old_path: None,
+                new_path: Some(path.to_string()),
+                hunks: Vec::new(),
+            };
+            assert!(plan_changes(temp.path(), &[patch]).is_err(), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Backups of files touched by applied patches.
+//!
+//! Each application creates `.guided/backups/<id>/` containing a
+//! `manifest.json` and copies of the original files under `files/`.
+
+use crate::apply::{ChangeKind, PlannedChange};
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Manifest describing a backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// Backup identifier (timestamp-based directory name)
+    pub id: String,
+
+    /// When the backup was taken (RFC 3339)
+    pub created_at: String,
+
+    /// Files changed, relative to the workspace
+    pub entries: Vec<BackupEntry>,
+}
+
+/// A single file recorded in a backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    /// Path relative to the workspace
+    pub path: String,
+
+    /// What the patch did to the file
+    pub kind: ChangeKind,
+}
+
+/// Directory holding all backups for a workspace.
+pub fn backups_dir(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("backups")
+}
+
+/// Save the original contents of files about to change.
+pub fn create_backup(workspace: &Path, changes: &[PlannedChange]) -> AppResult<BackupManifest> {
+    let now = chrono::Utc::now();
+    let base_id = now.format("%Y%m%d-%H%M%S").to_string();
+
+    // Disambiguate backups created within the same second
+    let root = backups_dir(workspace);
+    let mut id = base_id.clone();
+    let mut suffix = 1;
+    while root.join(&id).exists() {
+        id = format!("{}-{}", base_id, suffix);
+        suffix += 1;
+    }
+
+    let dir = root.join(&id);
+    let files_dir = dir.join("files");
+    std::fs::create_dir_all(&files_dir)
+        .map_err(|e| AppError::Edit(format!("Failed to create backup {:?}: {}", dir, e)))?;
+
+    for change in changes {
+        if let Some(ref original) = change.original {
+            let backup_path = files_dir.join(&change.path);
+            if let Some(parent) = backup_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&backup_path, original)
+                .map_err(|e| AppError::Edit(format!("Failed to back up {}: {}", change.path, e)))?;
+        }
+    }
+
+    let manifest = BackupManifest {
+        id,
+        created_at: now.to_rfc3339(),
+        entries: changes
+            .iter()
+            .map(|c| BackupEntry {
+                path: c.path.clone(),
+                kind: c.kind,
+            })
+            .collect(),
+    };
+
+    let json = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(dir.join("manifest.json"), json)?;
+
+    tracing::info!("Created backup {} ({} files)", manifest.id, changes.len());
+
+    Ok(manifest)
+}
+
+/// List backups, newest first.
+pub fn list_backups(workspace: &Path) -> AppResult<Vec<BackupManifest>> {
+    let root = backups_dir(workspace);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut manifests = Vec::new();
+    for entry in std::fs::read_dir(&root)? {
+        let manifest_path = entry?.path().join("manifest.json");
+        if !manifest_path.exists() {
+            continue;
+        }
+
+        match std::fs::read_to_string(&manifest_path)
+            .map_err(AppError::from)
+            .and_then(|s| serde_json::from_str::<BackupManifest>(&s).map_err(AppError::from))
+        {
+            Ok(manifest) => manifests.push(manifest),
+            Err(e) => tracing::warn!("Skipping unreadable backup {:?}: {}", manifest_path, e),
+        }
+    }
+
+    manifests.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(manifests)
+}
+
+/// Restore files from a backup.
+///
+/// Modified and deleted files get their original contents back; files the
+/// patch created are removed.
+pub fn restore_backup(workspace: &Path, id: &str) -> AppResult<BackupManifest> {
+    let dir = backups_dir(workspace).join(id);
+    let manifest_path = dir.join("manifest.json");
+
+    if !manifest_path.exists() {
+        return Err(AppError::Edit(format!("Backup not found: {}", id)));
+    }
+
+    let manifest: BackupManifest = serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+
+    for entry in &manifest.entries {
+        let target = workspace.join(&entry.path);
+        match entry.kind {
+            ChangeKind::Create => {
+                if target.exists() {
+                    std::fs::remove_file(&target)?;
+                }
+            }
+            ChangeKind::Modify | ChangeKind::Delete => {
+                let original = std::fs::read(dir.join("files").join(&entry.path)).map_err(|e| {
+                    AppError::Edit(format!("Backup copy missing for {}: {}", entry.path, e))
+                })?;
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&target, original)?;
+            }
+        }
+    }
+
+    tracing::info!("Restored backup {} ({} files)", id, manifest.entries.len());
+
+    Ok(manifest)
+}
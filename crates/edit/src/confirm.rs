@@ -0,0 +1,25 @@
+//! Interactive confirmation before destructive actions.
+
+use guided_core::{AppError, AppResult};
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Ask a yes/no question on stderr and read the answer from stdin.
+///
+/// Defaults to "no". Fails when stdin is not a terminal, so scripts must
+/// opt in explicitly (e.g., with `--yes`) instead of hanging or guessing.
+pub fn confirm(question: &str) -> AppResult<bool> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err(AppError::Edit(
+            "Confirmation required but stdin is not a terminal; pass --yes to proceed".to_string(),
+        ));
+    }
+
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
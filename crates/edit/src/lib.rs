@@ -0,0 +1,21 @@
+//! Safe file editing for the Guided Agent CLI.
+//!
+//! This crate turns LLM-proposed changes into actions:
+//! - Unified diff parsing (with or without `diff --git` headers)
+//! - Tolerant hunk application (line offsets, trailing whitespace)
+//! - Colored patch previews
+//! - Atomic, all-or-nothing application with backups in `.guided/backups/`
+//! - Restoring a previous backup
+
+pub mod apply;
+pub mod backup;
+pub mod confirm;
+pub mod patch;
+pub mod preview;
+
+// Re-export main types
+pub use apply::{apply_changes, plan_changes, ApplyReport, ChangeKind, PlannedChange};
+pub use backup::{list_backups, restore_backup, BackupManifest};
+pub use confirm::confirm;
+pub use patch::{parse_patch, FilePatch, Hunk, HunkLine};
+pub use preview::render_preview;
@@ -0,0 +1,480 @@
+//! Unified diff parsing and hunk application.
+
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+
+/// A single line in a hunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "text", rename_all = "lowercase")]
+pub enum HunkLine {
+    /// Unchanged line (present in old and new)
+    Context(String),
+
+    /// Added line (new only)
+    Add(String),
+
+    /// Removed line (old only)
+    Remove(String),
+}
+
+/// A hunk of changes to one region of a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    /// First line in the old file (1-based; 0 for empty files)
+    pub old_start: usize,
+
+    /// First line in the new file (1-based)
+    pub new_start: usize,
+
+    /// Hunk lines in order
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines this hunk expects to find in the old file.
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(t) | HunkLine::Remove(t) => Some(t.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines this hunk produces in the new file.
+    pub fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(t) | HunkLine::Add(t) => Some(t.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// All changes to a single file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePatch {
+    /// Path before the change (`None` for new files)
+    pub old_path: Option<String>,
+
+    /// Path after the change (`None` for deleted files)
+    pub new_path: Option<String>,
+
+    /// Hunks in file order
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path this patch applies to.
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether this patch creates a new file.
+    pub fn is_new(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    /// Whether this patch deletes the file.
+    pub fn is_deleted(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    /// Number of added lines.
+    pub fn additions(&self) -> usize {
+        self.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .filter(|l| matches!(l, HunkLine::Add(_)))
+            .count()
+    }
+
+    /// Number of removed lines.
+    pub fn deletions(&self) -> usize {
+        self.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .filter(|l| matches!(l, HunkLine::Remove(_)))
+            .count()
+    }
+}
+
+/// Parse a unified diff into file patches.
+///
+/// Accepts `git diff` output as well as plain `---`/`+++` diffs, which is
+/// what models usually produce. Hunk line counts are used to tell content
+/// from headers but are not required to be exact.
+pub fn parse_patch(text: &str) -> AppResult<Vec<FilePatch>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut current: Option<FilePatch> = None;
+    let mut hunk: Option<Hunk> = None;
+    // Remaining (old, new) lines in the current hunk, when the header has counts
+    let mut remaining: Option<(usize, usize)> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let in_hunk = hunk.is_some() && remaining.is_none_or(|(o, n)| o > 0 || n > 0);
+
+        let is_file_header = line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "));
+        // git-style headers end a hunk even when its line counts are off
+        let is_git_header = is_file_header
+            && (line.starts_with("--- a/") || line.starts_with("--- /dev/null"))
+            && (lines[i + 1].starts_with("+++ b/") || lines[i + 1].starts_with("+++ /dev/null"));
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            flush(&mut current, &mut hunk, &mut patches);
+
+            let new = rest.rsplit_once(" b/").map(|(_, p)| p.to_string());
+            let old = rest
+                .split_once(" b/")
+                .map(|(a, _)| strip_prefix_path(a).to_string());
+            current = Some(FilePatch {
+                old_path: old,
+                new_path: new,
+                hunks: Vec::new(),
+            });
+            i += 1;
+            continue;
+        }
+
+        if is_file_header && (!in_hunk || is_git_header) {
+            // A header after hunks starts the next file
+            if hunk.is_some() || current.as_ref().is_some_and(|p| !p.hunks.is_empty()) {
+                flush(&mut current, &mut hunk, &mut patches);
+            }
+
+            let patch = current.get_or_insert_with(FilePatch::default);
+            patch.old_path = header_path(&line[4..]);
+            patch.new_path = header_path(&lines[i + 1][4..]);
+            remaining = None;
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let Some(patch) = current.as_mut() else {
+                return Err(AppError::Edit(format!(
+                    "Hunk without file header at line {}",
+                    i + 1
+                )));
+            };
+            if let Some(h) = hunk.take() {
+                patch.hunks.push(h);
+            }
+
+            let (old_start, old_count, new_start, new_count) =
+                parse_hunk_header(line).ok_or_else(|| {
+                    AppError::Edit(format!("Invalid hunk header at line {}: {}", i + 1, line))
+                })?;
+            remaining = match (old_count, new_count) {
+                (Some(o), Some(n)) => Some((o, n)),
+                _ => None,
+            };
+            hunk = Some(Hunk {
+                old_start,
+                new_start,
+                lines: Vec::new(),
+            });
+            i += 1;
+            continue;
+        }
+
+        if let Some(h) = hunk.as_mut() {
+            let parsed = if let Some(t) = line.strip_prefix('+') {
+                Some(HunkLine::Add(t.to_string()))
+            } else if let Some(t) = line.strip_prefix('-') {
+                Some(HunkLine::Remove(t.to_string()))
+            } else if let Some(t) = line.strip_prefix(' ') {
+                Some(HunkLine::Context(t.to_string()))
+            } else if line.is_empty() && in_hunk {
+                // Editors and models often strip the space on blank context lines
+                Some(HunkLine::Context(String::new()))
+            } else {
+                None
+            };
+
+            if let Some(parsed) = parsed {
+                if let Some((o, n)) = remaining.as_mut() {
+                    match parsed {
+                        HunkLine::Context(_) => {
+                            *o = o.saturating_sub(1);
+                            *n = n.saturating_sub(1);
+                        }
+                        HunkLine::Add(_) => *n = n.saturating_sub(1),
+                        HunkLine::Remove(_) => *o = o.saturating_sub(1),
+                    }
+                }
+                h.lines.push(parsed);
+                i += 1;
+                continue;
+            }
+        }
+
+        // "\ No newline at end of file", index lines, mode lines and prose
+        i += 1;
+    }
+
+    flush(&mut current, &mut hunk, &mut patches);
+
+    if patches.is_empty() {
+        return Err(AppError::Edit("No file changes found in patch".to_string()));
+    }
+
+    Ok(patches)
+}
+
+/// Move the in-progress hunk and file into the results.
+fn flush(current: &mut Option<FilePatch>, hunk: &mut Option<Hunk>, patches: &mut Vec<FilePatch>) {
+    if let Some(h) = hunk.take() {
+        if let Some(patch) = current.as_mut() {
+            patch.hunks.push(h);
+        }
+    }
+    if let Some(patch) = current.take() {
+        if !patch.hunks.is_empty() || patch.is_deleted() {
+            patches.push(patch);
+        }
+    }
+}
+
+/// Path from a `---`/`+++` header, or `None` for `/dev/null`.
+fn header_path(raw: &str) -> Option<String> {
+    // Drop trailing timestamps ("file\t2024-01-01 ...")
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" {
+        None
+    } else {
+        Some(strip_prefix_path(raw).to_string())
+    }
+}
+
+/// Strip the `a/` or `b/` prefix used by git.
+fn strip_prefix_path(path: &str) -> &str {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// Parse "@@ -a[,b] +c[,d] @@".
+///
+/// Counts are `None` when unknown; a bare "@@ @@" header yields line 0,
+/// so the hunk is located by content alone.
+fn parse_hunk_header(line: &str) -> Option<(usize, Option<usize>, usize, Option<usize>)> {
+    let inner = line.strip_prefix("@@")?;
+    let inner = &inner[..inner.find("@@").unwrap_or(inner.len())];
+
+    let mut old = None;
+    let mut new = None;
+    for part in inner.split_whitespace() {
+        if let Some(range) = part.strip_prefix('-') {
+            old = Some(parse_range(range)?);
+        } else if let Some(range) = part.strip_prefix('+') {
+            new = Some(parse_range(range)?);
+        }
+    }
+
+    let (old_start, old_count) = old.unwrap_or((0, None));
+    let (new_start, new_count) = new.unwrap_or((0, None));
+    Some((old_start, old_count, new_start, new_count))
+}
+
+fn parse_range(range: &str) -> Option<(usize, Option<usize>)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, Some(count.parse().ok()?))),
+        None => Some((range.parse().ok()?, Some(1))),
+    }
+}
+
+/// Apply a file patch to the original contents.
+///
+/// Each hunk is located near its stated position, searching outward when
+/// earlier edits or stale line numbers shift it, and falls back to matching
+/// with trailing whitespace ignored.
+pub fn apply_file_patch(original: &str, patch: &FilePatch) -> AppResult<String> {
+    let had_trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let mut offset: isize = 0;
+
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        let new = hunk.new_lines();
+
+        let expected = (hunk.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+        let position = find_hunk(&lines, &old, expected).ok_or_else(|| {
+            AppError::Edit(format!(
+                "Hunk {} does not apply to {} (expected near line {})",
+                index + 1,
+                patch.path(),
+                hunk.old_start
+            ))
+        })?;
+
+        lines.splice(
+            position..position + old.len(),
+            new.iter().map(|s| s.to_string()),
+        );
+        offset += new.len() as isize - old.len() as isize;
+        offset += position as isize - expected as isize;
+    }
+
+    let mut result = lines.join("\n");
+    if had_trailing_newline && !result.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Find where `needle` occurs in `lines`, preferring positions near `expected`.
+fn find_hunk(lines: &[String], needle: &[&str], expected: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(expected.min(lines.len()));
+    }
+    if needle.len() > lines.len() {
+        return None;
+    }
+
+    let last = lines.len() - needle.len();
+    let exact = |pos: usize| {
+        lines[pos..pos + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| a == b)
+    };
+    let loose = |pos: usize| {
+        lines[pos..pos + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| a.trim_end() == b.trim_end())
+    };
+
+    search_outward(expected.min(last), last, exact)
+        .or_else(|| search_outward(expected.min(last), last, loose))
+}
+
+/// Probe positions expected, expected±1, expected±2, ... within 0..=last.
+fn search_outward(expected: usize, last: usize, matches: impl Fn(usize) -> bool) -> Option<usize> {
+    for distance in 0..=last {
+        if let Some(pos) = expected.checked_sub(distance) {
+            if matches(pos) {
+                return Some(pos);
+            }
+        }
+        let pos = expected + distance;
+        if distance > 0 && pos <= last && matches(pos) {
+            return Some(pos);
+        }
+        if expected < distance && pos > last {
+            break;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+
+    #[test]
+    fn test_parse_ignores_surrounding_prose() {
+        let response = "Here is the fix:\n\n```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -2 +2 @@\n-    let x = 1;\n+    let x = 2;\n```\n\nThis sets x to 2.\n";
+
+        let patches = parse_patch(response).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_plain_diff() {
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,4 +1,4 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n     println!(\"{}\", x);\n }\n";
+
+        let patches = parse_patch(diff).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].path(), "src/main.rs");
+        assert_eq!(patches[0].additions(), 1);
+        assert_eq!(patches[0].deletions(), 1);
+    }
+
+    #[test]
+    fn test_parse_new_and_deleted_files() {
+        let diff = "diff --git a/new.txt b/new.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+
+        let patches = parse_patch(diff).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert!(patches[0].is_new());
+        assert_eq!(patches[0].path(), "new.txt");
+        assert!(patches[1].is_deleted());
+        assert_eq!(patches[1].path(), "old.txt");
+    }
+
+    #[test]
+    fn test_removed_line_that_looks_like_header() {
+        // "--- x" inside a hunk is a removed "-- x" line, not a new file
+        let diff = "--- a/notes.md\n+++ b/notes.md\n@@ -1,2 +1,2 @@\n--- x\n+++ y\n keep\n";
+
+        let patches = parse_patch(diff).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].hunks[0].old_lines(), vec!["-- x", "keep"]);
+        assert_eq!(patches[0].hunks[0].new_lines(), vec!["++ y", "keep"]);
+    }
+
+    #[test]
+    fn test_apply_with_offset() {
+        // Stated line is wrong; hunk should still be found
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -10,2 +10,2 @@\n-    let x = 1;\n+    let x = 2;\n     println!(\"{}\", x);\n";
+        let patches = parse_patch(diff).unwrap();
+
+        let updated = apply_file_patch(ORIGINAL, &patches[0]).unwrap();
+        assert_eq!(
+            updated,
+            "fn main() {\n    let x = 2;\n    println!(\"{}\", x);\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_new_file() {
+        let diff = "--- /dev/null\n+++ b/hello.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n";
+        let patches = parse_patch(diff).unwrap();
+
+        assert_eq!(apply_file_patch("", &patches[0]).unwrap(), "hello\nworld\n");
+    }
+
+    #[test]
+    fn test_apply_mismatch_fails() {
+        let diff =
+            "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -2 +2 @@\n-    let y = 1;\n+    let y = 2;\n";
+        let patches = parse_patch(diff).unwrap();
+
+        let err = apply_file_patch(ORIGINAL, &patches[0]).unwrap_err();
+        assert!(err.to_string().contains("does not apply"));
+    }
+
+    #[test]
+    fn test_apply_bare_hunk_header() {
+        let diff =
+            "--- a/src/main.rs\n+++ b/src/main.rs\n@@ @@\n     let x = 1;\n+    let y = x;\n";
+        let patches = parse_patch(diff).unwrap();
+
+        let updated = apply_file_patch(ORIGINAL, &patches[0]).unwrap();
+        assert!(updated.contains("    let x = 1;\n    let y = x;\n"));
+    }
+
+    #[test]
+    fn test_parse_empty_patch_fails() {
+        assert!(parse_patch("no diff here").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Patch previews for the terminal.
+
+use crate::patch::{FilePatch, HunkLine};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+
+/// Render patches as a unified diff, colored unless `color` is false.
+pub fn render_preview(patches: &[FilePatch], color: bool) -> String {
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut out = String::new();
+
+    for patch in patches {
+        let status = if patch.is_new() {
+            " (new file)"
+        } else if patch.is_deleted() {
+            " (deleted)"
+        } else {
+            ""
+        };
+        out.push_str(&paint(
+            BOLD,
+            &format!(
+                "{}{}  +{} -{}",
+                patch.path(),
+                status,
+                patch.additions(),
+                patch.deletions()
+            ),
+        ));
+        out.push('\n');
+
+        for hunk in &patch.hunks {
+            let old_count = hunk.old_lines().len();
+            let new_count = hunk.new_lines().len();
+            out.push_str(&paint(
+                CYAN,
+                &format!(
+                    "@@ -{},{} +{},{} @@",
+                    hunk.old_start, old_count, hunk.new_start, new_count
+                ),
+            ));
+            out.push('\n');
+
+            for line in &hunk.lines {
+                let rendered = match line {
+                    HunkLine::Context(t) => format!(" {}", t),
+                    HunkLine::Add(t) => paint(GREEN, &format!("+{}", t)),
+                    HunkLine::Remove(t) => paint(RED, &format!("-{}", t)),
+                };
+                out.push_str(&rendered);
+                out.push('\n');
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::parse_patch;
+
+    const PATCH: &str = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n";
+
+    #[test]
+    fn test_preview_plain() {
+        let patches = parse_patch(PATCH).unwrap();
+        let preview = render_preview(&patches, false);
+
+        assert!(preview.starts_with("a.txt  +1 -1\n@@ -1,1 +1,1 @@\n-old\n+new\n"));
+        assert!(!preview.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_preview_color() {
+        let patches = parse_patch(PATCH).unwrap();
+        let preview = render_preview(&patches, true);
+
+        assert!(preview.contains("\x1b[32m+new\x1b[0m"));
+        assert!(preview.contains("\x1b[31m-old\x1b[0m"));
+    }
+}
@@ -0,0 +1,117 @@
+//! A stand-in for an Ollama server.
+//!
+//! Answers every `/api/generate` request with the same text, streamed word
+//! by word when the request asks to stream, and keeps the prompts it was
+//! sent so tests can check what reached the LLM.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub struct FakeLlm {
+    /// Base URL to configure as the Ollama endpoint
+    pub endpoint: String,
+
+    prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeLlm {
+    /// Serve `answer` to every completion request.
+    pub async fn start(answer: &str) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+
+        let answer = answer.to_string();
+        let received = prompts.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let answer = answer.clone();
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let Some(body) = read_body(&mut socket).await else {
+                        return;
+                    };
+                    let request: serde_json::Value =
+                        serde_json::from_str(&body).unwrap_or_default();
+                    let model = request["model"].as_str().unwrap_or("fake").to_string();
+                    if let Some(prompt) = request["prompt"].as_str() {
+                        received.lock().unwrap().push(prompt.to_string());
+                    }
+
+                    let (content_type, body) = if request["stream"].as_bool() == Some(true) {
+                        ("application/x-ndjson", stream_body(&model, &answer))
+                    } else {
+                        ("application/json", done(&model, &answer).to_string())
+                    };
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        content_type,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        Self { endpoint, prompts }
+    }
+
+    /// Prompts received so far, in order.
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+/// Read one request and return its body.
+async fn read_body(socket: &mut tokio::net::TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.ok()?;
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(head_end) = text.find("\r\n\r\n") {
+            let length = text[..head_end]
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .and_then(|v| v.trim().parse::<usize>().ok())
+                })
+                .unwrap_or(0);
+            if request.len() >= head_end + 4 + length {
+                return Some(text[head_end + 4..].to_string());
+            }
+        }
+        if n == 0 {
+            return None;
+        }
+    }
+}
+
+/// The final object of a completion, with the whole answer when not
+/// streamed.
+fn done(model: &str, response: &str) -> serde_json::Value {
+    serde_json::json!({
+        "model": model,
+        "response": response,
+        "done": true,
+        "prompt_eval_count": 10,
+        "eval_count": 5,
+    })
+}
+
+/// `answer` as Ollama streams it: one object per word, then an empty final
+/// one.
+fn stream_body(model: &str, answer: &str) -> String {
+    let mut body = String::new();
+    for word in answer.split_inclusive(' ') {
+        let piece = serde_json::json!({ "model": model, "response": word, "done": false });
+        body.push_str(&piece.to_string());
+        body.push('\n');
+    }
+    body.push_str(&done(model, "").to_string());
+    body.push('\n');
+    body
+}
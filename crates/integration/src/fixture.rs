@@ -0,0 +1,160 @@
+//! Fixture workspaces.
+
+use guided_core::config::{LlmConfig, ProviderConfig};
+use guided_knowledge::{AskOptions, LearnOptions};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Files of the sample repository, relative to the workspace.
+const SAMPLE_REPO: &[(&str, &str)] = &[
+    (
+        "README.md",
+        "# Billing service\n\nThe billing service issues invoices and collects payments for \
+         every customer account.\n",
+    ),
+    (
+        "docs/deploys.md",
+        "# Deploys\n\nDeploys are frozen on Fridays. To roll back a deploy, run \
+         `make rollback` from the release branch.\n",
+    ),
+    (
+        "docs/oncall.md",
+        "# On-call\n\nThe on-call engineer answers pages within fifteen minutes and hands \
+         over every Monday at ten.\n",
+    ),
+    (
+        "docs/drafts/pricing.md",
+        "# Pricing (draft)\n\nNot decided yet.\n",
+    ),
+    (
+        "src/invoice.rs",
+        r#"/// An invoice for one billing period.
+pub struct Invoice {
+    pub total_cents: u64,
+}
+
+impl Invoice {
+    /// Total in whole currency units.
+    pub fn total(&self) -> f64 {
+        self.total_cents as f64 / 100.0
+    }
+}
+"#,
+    ),
+    (
+        "node_modules/left-pad/README.md",
+        "# left-pad\n\nPads strings on the left.\n",
+    ),
+    (".guided/ignore", "# Unfinished docs\ndrafts/\n"),
+    (
+        ".guided/prompts/agent.ask.kb.yml",
+        r#"id: agent.ask.kb
+title: "Ask with knowledge"
+apiVersion: "1.0"
+
+behavior:
+  tone: professional
+  style: concise
+
+context:
+  includeWorkspaceContext: false
+  includeKnowledgeBase: true
+
+template: |
+  {{#if knowledgeContext}}
+  # Relevant Knowledge
+
+  {{{knowledgeContext}}}
+  {{/if}}
+
+  # Question
+
+  {{prompt}}
+
+output:
+  format: markdown
+"#,
+    ),
+];
+
+/// A temporary workspace holding the sample repository.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    /// The sample repository (docs, code, a dependency directory, an
+    /// ignore file and a prompt), configured to use the LLM at
+    /// `llm_endpoint`.
+    pub fn sample_repo(llm_endpoint: &str) -> Self {
+        let dir = TempDir::new().unwrap();
+        let fixture = Self { dir };
+        for (path, contents) in SAMPLE_REPO {
+            fixture.write(path, contents);
+        }
+        fixture.write(
+            ".guided/config.yaml",
+            &format!(
+                r#"llm:
+  activeProvider: ollama
+  activeEmbeddingProvider: mock
+  providers:
+    ollama:
+      endpoint: {}
+      model: llama3
+"#,
+                llm_endpoint
+            ),
+        );
+        fixture
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Write a file of the workspace, creating its directories.
+    pub fn write(&self, relative: &str, contents: &str) -> PathBuf {
+        let path = self.path().join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// The workspace's `llm` config, read from `.guided/config.yaml`.
+    pub fn llm_config(&self) -> LlmConfig {
+        let contents = std::fs::read_to_string(self.path().join(".guided/config.yaml")).unwrap();
+        let config: serde_yaml::Value = serde_yaml::from_str(&contents).unwrap();
+        serde_yaml::from_value(config["llm"].clone()).unwrap()
+    }
+
+    pub fn provider_configs(&self) -> HashMap<String, ProviderConfig> {
+        self.llm_config().providers
+    }
+
+    /// Options to learn `paths` of the workspace into `base_name` with the
+    /// mock embedding provider.
+    pub fn learn_options(&self, base_name: &str, paths: &[&str]) -> LearnOptions {
+        LearnOptions {
+            base_name: base_name.to_string(),
+            paths: paths.iter().map(|p| self.path().join(p)).collect(),
+            provider: Some("mock".to_string()),
+            model: Some("trigram-v2".to_string()),
+            provider_configs: self.provider_configs(),
+            ..Default::default()
+        }
+    }
+
+    /// Options to ask `base_name` about `query`.
+    pub fn ask_options(&self, base_name: &str, query: &str) -> AskOptions {
+        AskOptions {
+            base_name: base_name.to_string(),
+            query: query.to_string(),
+            top_k: Some(3),
+            cache: true,
+            provider_configs: self.provider_configs(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! End-to-end tests of the guided crates working together.
+//!
+//! Each test builds a fixture workspace (a small repository with docs,
+//! code, prompts and a `.guided/config.yaml`), then runs learn, ask and
+//! stats through the library APIs the CLI calls, with the mock embedding
+//! provider and a fake LLM server, and checks the JSON the CLI would print.
+//! Nothing here needs the network or a model.
+
+#[cfg(test)]
+mod fake_llm;
+#[cfg(test)]
+mod fixture;
+#[cfg(test)]
+mod tests;
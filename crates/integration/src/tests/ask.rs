@@ -0,0 +1,94 @@
+//! Retrieval and RAG answers from a learned sample repository.
+
+use crate::fake_llm::FakeLlm;
+use crate::fixture::Fixture;
+use guided_knowledge::rag::{ask, AnswerEvent};
+use std::sync::Mutex;
+
+const ANSWER: &str = "Deploys are frozen on Fridays.";
+
+/// The sample repository, learned into the base `repo`.
+async fn learned_repo(llm: &FakeLlm) -> Fixture {
+    let fixture = Fixture::sample_repo(&llm.endpoint);
+    let options = fixture.learn_options("repo", &["README.md", "docs", "src"]);
+    guided_knowledge::learn(fixture.path(), &options, None)
+        .await
+        .unwrap();
+    fixture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ask_retrieves_the_relevant_chunk_first() {
+        let llm = FakeLlm::start(ANSWER).await;
+        let fixture = learned_repo(&llm).await;
+
+        let options = fixture.ask_options("repo", "When are deploys frozen?");
+        let result = guided_knowledge::ask(fixture.path(), options, None)
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        let chunks = json["chunks"].as_array().unwrap();
+        assert!(!chunks.is_empty());
+        assert!(chunks[0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("frozen on Fridays"));
+        assert_eq!(json["scores"].as_array().unwrap().len(), chunks.len());
+        assert!(llm.prompts().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rag_answer_cites_sources_and_is_cached() {
+        let llm = FakeLlm::start(ANSWER).await;
+        let fixture = learned_repo(&llm).await;
+        let options = || fixture.ask_options("repo", "When are deploys frozen?");
+
+        let response = ask::ask_rag(fixture.path(), options(), "ollama", None)
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["answer"], ANSWER);
+        assert!(json["sources"][0]["source"]
+            .as_str()
+            .unwrap()
+            .ends_with("deploys.md"));
+        assert!(json.get("cached").is_none());
+
+        // The retrieved text reached the LLM
+        let prompts = llm.prompts();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("Deploys are frozen on Fridays"));
+        assert!(prompts[0].contains("When are deploys frozen?"));
+
+        // Asking again is answered from the cache
+        let cached = ask::ask_rag(fixture.path(), options(), "ollama", None)
+            .await
+            .unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.answer, ANSWER);
+        assert_eq!(llm.prompts().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_streamed_answer_matches_the_full_answer() {
+        let llm = FakeLlm::start(ANSWER).await;
+        let fixture = learned_repo(&llm).await;
+
+        let mut options = fixture.ask_options("repo", "How do I roll back a deploy?");
+        options.cache = false;
+        let tokens = Mutex::new(String::new());
+        let response = ask::ask_rag_streaming(fixture.path(), options, "ollama", None, &|event| {
+            if let AnswerEvent::Token(token) = event {
+                tokens.lock().unwrap().push_str(&token);
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.answer, ANSWER);
+        assert_eq!(tokens.into_inner().unwrap(), ANSWER);
+    }
+}
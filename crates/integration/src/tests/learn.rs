@@ -0,0 +1,88 @@
+//! learn, dry runs and stats on the sample repository.
+
+use crate::fake_llm::FakeLlm;
+use crate::fixture::Fixture;
+
+/// What the sample repository's learn indexes: docs and code, without the
+/// ignored drafts and the dependency directory.
+const LEARNED: &[&str] = &["README.md", "docs", "src", "node_modules"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_learn_then_stats_report_the_same_sources() {
+        let llm = FakeLlm::start("unused").await;
+        let fixture = Fixture::sample_repo(&llm.endpoint);
+        let workspace = fixture.path();
+
+        let stats =
+            guided_knowledge::learn(workspace, &fixture.learn_options("repo", LEARNED), None)
+                .await
+                .unwrap();
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["sources_count"], 4);
+        assert_eq!(json["provider"], "mock");
+        assert_eq!(json["errors"], serde_json::json!([]));
+        assert!(stats.chunks_count >= 4);
+
+        let base = guided_knowledge::stats(workspace, "repo").await.unwrap();
+        let json = serde_json::to_value(&base).unwrap();
+        assert_eq!(json["base_name"], "repo");
+        assert_eq!(json["sources_count"], 4);
+        assert_eq!(json["chunks_count"], stats.chunks_count);
+        assert_eq!(json["model"], "trigram-v2");
+
+        // Learning the same files again records them again; stats counts
+        // each file once
+        guided_knowledge::learn(workspace, &fixture.learn_options("repo", LEARNED), None)
+            .await
+            .unwrap();
+        let base = guided_knowledge::stats(workspace, "repo").await.unwrap();
+        assert_eq!(base.sources_count, 4);
+
+        // No LLM is needed to learn
+        assert!(llm.prompts().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dry_run_explains_skipped_files() {
+        let llm = FakeLlm::start("unused").await;
+        let fixture = Fixture::sample_repo(&llm.endpoint);
+        let workspace = fixture.path();
+
+        let report = guided_knowledge::dry_run(
+            workspace,
+            &fixture.learn_options("repo", LEARNED),
+            None,
+            guided_knowledge::ProgressReporter::noop(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.sources_count, 4);
+
+        let json = serde_json::to_value(&report).unwrap();
+        let reasons: Vec<(String, String)> = json["excluded"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| {
+                let path = file["path"].as_str().unwrap();
+                let name = path.rsplit('/').next().unwrap().to_string();
+                (name, file["reason"].as_str().unwrap().to_string())
+            })
+            .collect();
+        assert!(reasons.contains(&(
+            "pricing.md".to_string(),
+            ".guided/ignore 'drafts/'".to_string()
+        )));
+        assert!(reasons.contains(&(
+            "README.md".to_string(),
+            "default pattern '/node_modules/'".to_string()
+        )));
+
+        // The dry run left no base behind
+        assert!(guided_knowledge::stats(workspace, "repo").await.is_err());
+    }
+}
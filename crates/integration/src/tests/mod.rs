@@ -0,0 +1,3 @@
+mod ask;
+mod learn;
+mod prompts;
@@ -0,0 +1,66 @@
+//! Workspace prompts filled with knowledge context, as `guided ask
+//! --knowledge-base` does.
+
+use crate::fake_llm::FakeLlm;
+use crate::fixture::Fixture;
+use guided_llm::{create_client_from_config, LlmRequest};
+use guided_prompt::{build_prompt, list_prompts, load_prompt};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prompt_with_knowledge_context_reaches_the_llm() {
+        let llm = FakeLlm::start("Run make rollback from the release branch.").await;
+        let fixture = Fixture::sample_repo(&llm.endpoint);
+        let workspace = fixture.path();
+        guided_knowledge::learn(workspace, &fixture.learn_options("repo", &["docs"]), None)
+            .await
+            .unwrap();
+
+        assert!(list_prompts(workspace)
+            .unwrap()
+            .contains(&"agent.ask.kb".to_string()));
+        let definition = load_prompt(workspace, "agent.ask.kb").unwrap();
+
+        let question = "How do I roll back a deploy?";
+        let retrieved =
+            guided_knowledge::ask(workspace, fixture.ask_options("repo", question), None)
+                .await
+                .unwrap();
+        let context: Vec<&str> = retrieved.chunks.iter().map(|c| c.text.as_str()).collect();
+        let variables = HashMap::from([("prompt".to_string(), question.to_string())]);
+        let built = build_prompt(
+            &definition,
+            variables,
+            workspace,
+            Some(context.join("\n\n")),
+        )
+        .unwrap();
+        assert_eq!(built.metadata.knowledge_base_used, None);
+
+        let llm_config = fixture.llm_config();
+        let client = create_client_from_config(
+            &llm_config.active_provider,
+            llm_config.providers.get(&llm_config.active_provider),
+            None,
+        )
+        .unwrap();
+        let response = client
+            .complete(&LlmRequest::new(built.user, "llama3"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.content,
+            "Run make rollback from the release branch."
+        );
+
+        let prompts = llm.prompts();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("# Relevant Knowledge"));
+        assert!(prompts[0].contains("make rollback"));
+        assert!(prompts[0].ends_with(&format!("{}\n", question)));
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * C interface to guided-knowledge, the local RAG core of guided.
+ *
+ * Build with `cargo build --release -p guided-knowledge-ffi` and link
+ * against libguided_knowledge_ffi (.so, .dylib, .dll or the static .a).
+ *
+ * Options are passed and results returned as JSON. Functions that fail
+ * return NULL; guided_knowledge_last_error() then describes the failure
+ * on the calling thread. Strings returned by the library are freed with
+ * guided_knowledge_string_free().
+ *
+ *     GuidedKnowledge *kb = guided_knowledge_open("/path/to/project", "docs");
+ *     char *stats = guided_knowledge_learn(kb, "{\"paths\": [\"docs\"]}");
+ *     char *result = guided_knowledge_ask(kb, "{\"query\": \"How are deploys run?\"}");
+ *     ...
+ *     guided_knowledge_string_free(result);
+ *     guided_knowledge_string_free(stats);
+ *     guided_knowledge_close(kb);
+ *
+ * A handle may be used from one thread at a time.
+ */
+
+#ifndef GUIDED_KNOWLEDGE_H
+#define GUIDED_KNOWLEDGE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An open knowledge base. */
+typedef struct GuidedKnowledge GuidedKnowledge;
+
+/*
+ * Open base `base_name` of the workspace at `workspace`. A base that was
+ * never learned opens too. Returns NULL on failure.
+ */
+GuidedKnowledge *guided_knowledge_open(const char *workspace, const char *base_name);
+
+/*
+ * Learn into the base. `options_json` is an object with the optional keys
+ * "paths", "urls", "include", "exclude" (arrays of strings), "namespace",
+ * "provider", "model" (strings) and "reset" (boolean). Returns the learn
+ * stats as JSON, or NULL on failure.
+ */
+char *guided_knowledge_learn(GuidedKnowledge *kb, const char *options_json);
+
+/*
+ * Retrieve the chunks most relevant to a query. `options_json` is an
+ * object with "query" (required), "top_k" and "candidates" (default: the
+ * base's retrieval settings, 5 chunks unless configured) and "namespace".
+ * Returns {"chunks": [...], "scores": [...]} as JSON, or NULL on failure.
+ */
+char *guided_knowledge_ask(const GuidedKnowledge *kb, const char *options_json);
+
+/* Statistics of the base as JSON, or NULL on failure. */
+char *guided_knowledge_stats(const GuidedKnowledge *kb);
+
+/*
+ * Message of the last failure on this thread, or NULL. Owned by the
+ * library; valid until the next call on this thread.
+ */
+const char *guided_knowledge_last_error(void);
+
+/* Free a string returned by the library. NULL is ignored. */
+void guided_knowledge_string_free(char *s);
+
+/* Close a handle. NULL is ignored. */
+void guided_knowledge_close(GuidedKnowledge *kb);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GUIDED_KNOWLEDGE_H */
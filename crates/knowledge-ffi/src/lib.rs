@@ -0,0 +1,393 @@
+//! C bindings for the knowledge core.
+//!
+//! Exposes learn, ask and stats on a [`KnowledgeBase`] to editor plugins
+//! and other non-Rust tools, which can then run local RAG in process rather
+//! than through the `guided` CLI. The C declarations are in
+//! `include/guided_knowledge.h`.
+//!
+//! Requests and results cross the boundary as JSON strings: options are
+//! parsed from snake_case objects and results are the serialized
+//! [`LearnStats`](guided_knowledge::LearnStats),
+//! [`AskResult`](guided_knowledge::AskResult) and
+//! [`BaseStats`](guided_knowledge::BaseStats). A function that fails returns
+//! NULL and leaves its message for `guided_knowledge_last_error` on the
+//! calling thread.
+//!
+//! Provider settings and API keys come from the workspace's
+//! `.guided/config.yaml` and the environment, as for the CLI.
+
+use guided_core::config::AppConfig;
+use guided_core::{AppError, AppResult};
+use guided_knowledge::{AskOptions, KnowledgeBase, LearnOptions};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open base with the runtime its calls block on.
+pub struct GuidedKnowledge {
+    runtime: tokio::runtime::Runtime,
+    base: KnowledgeBase,
+    config: AppConfig,
+}
+
+/// Options of `guided_knowledge_learn`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LearnRequest {
+    paths: Vec<PathBuf>,
+    urls: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    namespace: Option<String>,
+    reset: bool,
+    provider: Option<String>,
+    model: Option<String>,
+}
+
+/// Options of `guided_knowledge_ask`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AskRequest {
+    query: String,
+    top_k: Option<u32>,
+    candidates: Option<u32>,
+    namespace: Option<String>,
+}
+
+impl GuidedKnowledge {
+    fn open(workspace: &Path, base_name: &str) -> AppResult<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| AppError::Other(format!("Failed to start runtime: {}", e)))?;
+        let config = AppConfig {
+            workspace: workspace.to_path_buf(),
+            ..AppConfig::default()
+        }
+        .reloaded()?;
+        let base = runtime
+            .block_on(KnowledgeBase::open(workspace, base_name))?
+            .with_provider_configs(config.provider_configs());
+        Ok(Self {
+            runtime,
+            base,
+            config,
+        })
+    }
+
+    /// API key of the base's embedding provider, if one is configured.
+    fn api_key(&self, provider: Option<&str>) -> Option<String> {
+        let provider = provider.unwrap_or(&self.base.config().provider);
+        self.config.resolve_api_key(provider).ok().flatten()
+    }
+
+    fn learn(&mut self, request: &str) -> AppResult<String> {
+        let request: LearnRequest = parse(request)?;
+        let api_key = self.api_key(request.provider.as_deref());
+        let options = LearnOptions {
+            base_name: self.base.name().to_string(),
+            paths: request.paths,
+            urls: request.urls,
+            include: request.include,
+            exclude: request.exclude,
+            namespace: request.namespace,
+            reset: request.reset,
+            provider: request.provider,
+            model: request.model,
+            provider_configs: self.config.provider_configs(),
+            ..Default::default()
+        };
+        let stats = self
+            .runtime
+            .block_on(self.base.learn(&options, api_key.as_deref()))?;
+        to_json(&stats)
+    }
+
+    fn ask(&self, request: &str) -> AppResult<String> {
+        let request: AskRequest = parse(request)?;
+        if request.query.trim().is_empty() {
+            return Err(AppError::Knowledge("Ask needs a query".to_string()));
+        }
+        let options = AskOptions {
+            base_name: self.base.name().to_string(),
+            query: request.query,
+            top_k: request.top_k,
+            candidates: request.candidates,
+            namespace: request.namespace,
+            provider_configs: self.config.provider_configs(),
+            ..Default::default()
+        };
+        let result = self
+            .runtime
+            .block_on(self.base.ask(options, self.api_key(None).as_deref()))?;
+        to_json(&result)
+    }
+
+    fn stats(&self) -> AppResult<String> {
+        to_json(&self.runtime.block_on(self.base.stats())?)
+    }
+}
+
+fn parse<T: DeserializeOwned + Default>(json: &str) -> AppResult<T> {
+    if json.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(json).map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value).map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning errors and panics into NULL and a last error.
+fn guard<T>(f: impl FnOnce() -> AppResult<T>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            None
+        }
+        Err(_) => {
+            set_last_error("Internal error (panic) in guided-knowledge".to_string());
+            None
+        }
+    }
+}
+
+/// A UTF-8 string argument; NULL reads as empty.
+///
+/// # Safety
+/// `s` must be NULL or a NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> AppResult<&'a str> {
+    if s.is_null() {
+        return Ok("");
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| AppError::Other(format!("{} is not valid UTF-8", name)))
+}
+
+fn into_c_string(s: String) -> AppResult<*mut c_char> {
+    CString::new(s)
+        .map(CString::into_raw)
+        .map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+/// Open base `base_name` of the workspace at `workspace`. Returns NULL on
+/// failure. Close the handle with `guided_knowledge_close`.
+///
+/// # Safety
+/// Both arguments must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_open(
+    workspace: *const c_char,
+    base_name: *const c_char,
+) -> *mut GuidedKnowledge {
+    guard(|| {
+        let workspace = str_arg(workspace, "workspace")?;
+        let base_name = str_arg(base_name, "base_name")?;
+        if workspace.is_empty() || base_name.is_empty() {
+            return Err(AppError::Other(
+                "workspace and base_name are required".to_string(),
+            ));
+        }
+        GuidedKnowledge::open(Path::new(workspace), base_name)
+    })
+    .map_or(std::ptr::null_mut(), |handle| {
+        Box::into_raw(Box::new(handle))
+    })
+}
+
+/// Learn into the base. `options_json` holds `paths`, `urls`, `include`,
+/// `exclude`, `namespace`, `reset`, `provider` and `model`, all optional.
+/// Returns the learn stats as JSON, or NULL on failure.
+///
+/// # Safety
+/// `handle` must come from `guided_knowledge_open` and not be closed;
+/// `options_json` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_learn(
+    handle: *mut GuidedKnowledge,
+    options_json: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let handle = handle
+            .as_mut()
+            .ok_or_else(|| AppError::Other("handle is NULL".to_string()))?;
+        into_c_string(handle.learn(str_arg(options_json, "options_json")?)?)
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Retrieve the chunks most relevant to a query. `options_json` holds
+/// `query` (required), `top_k` and `candidates` (default: the base's
+/// `retrieval` settings) and `namespace`. Returns the chunks and scores as
+/// JSON, or NULL on failure.
+///
+/// # Safety
+/// `handle` must come from `guided_knowledge_open` and not be closed;
+/// `options_json` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_ask(
+    handle: *const GuidedKnowledge,
+    options_json: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let handle = handle
+            .as_ref()
+            .ok_or_else(|| AppError::Other("handle is NULL".to_string()))?;
+        into_c_string(handle.ask(str_arg(options_json, "options_json")?)?)
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Statistics of the base as JSON, or NULL on failure.
+///
+/// # Safety
+/// `handle` must come from `guided_knowledge_open` and not be closed.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_stats(handle: *const GuidedKnowledge) -> *mut c_char {
+    guard(|| {
+        let handle = handle
+            .as_ref()
+            .ok_or_else(|| AppError::Other("handle is NULL".to_string()))?;
+        into_c_string(handle.stats()?)
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Message of the last failure on this thread, or NULL. The string is
+/// owned by the library and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn guided_knowledge_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by the library. NULL is ignored.
+///
+/// # Safety
+/// `s` must come from this library and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Close a handle. NULL is ignored.
+///
+/// # Safety
+/// `handle` must come from `guided_knowledge_open` and not be closed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn guided_knowledge_close(handle: *mut GuidedKnowledge) {
+    if !handle.is_null() {
+        let handle = Box::from_raw(handle);
+        let GuidedKnowledge { runtime, base, .. } = *handle;
+        base.close();
+        drop(runtime);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    /// Take a returned string, freeing it.
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(
+            !s.is_null(),
+            "{:?}",
+            CStr::from_ptr(guided_knowledge_last_error())
+        );
+        let text = CStr::from_ptr(s).to_str().unwrap().to_string();
+        guided_knowledge_string_free(s);
+        text
+    }
+
+    #[test]
+    fn test_learn_ask_stats_through_c_api() {
+        let temp = TempDir::new().unwrap();
+        let docs = temp.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(
+            docs.join("deploys.md"),
+            "# Deploys\n\nDeploys are frozen during the last week of December.",
+        )
+        .unwrap();
+
+        unsafe {
+            let workspace = c(temp.path().to_str().unwrap());
+            let handle = guided_knowledge_open(workspace.as_ptr(), c("docs").as_ptr());
+            assert!(!handle.is_null());
+
+            let learn = serde_json::json!({
+                "paths": [docs],
+                "provider": "trigram",
+                "model": "trigram-v2",
+            });
+            let stats = take(guided_knowledge_learn(
+                handle,
+                c(&learn.to_string()).as_ptr(),
+            ));
+            let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+            assert_eq!(stats["sources_count"], 1, "{}", stats);
+            assert_eq!(stats["errors"], serde_json::json!([]), "{}", stats);
+
+            let ask = c(r#"{"query": "When are deploys frozen?", "top_k": 2}"#);
+            let result: serde_json::Value =
+                serde_json::from_str(&take(guided_knowledge_ask(handle, ask.as_ptr()))).unwrap();
+            let text = result["chunks"][0]["text"].as_str().unwrap();
+            assert!(text.contains("last week of December"));
+
+            let stats: serde_json::Value =
+                serde_json::from_str(&take(guided_knowledge_stats(handle))).unwrap();
+            assert_eq!(stats["base_name"], "docs");
+
+            guided_knowledge_close(handle);
+        }
+    }
+
+    #[test]
+    fn test_failures_set_last_error() {
+        let temp = TempDir::new().unwrap();
+        unsafe {
+            let workspace = c(temp.path().to_str().unwrap());
+            let handle = guided_knowledge_open(workspace.as_ptr(), c("empty").as_ptr());
+            assert!(!handle.is_null());
+
+            assert!(guided_knowledge_ask(handle, c(r#"{"top_k": 2}"#).as_ptr()).is_null());
+            let error = CStr::from_ptr(guided_knowledge_last_error())
+                .to_str()
+                .unwrap();
+            assert!(error.contains("needs a query"), "{}", error);
+
+            assert!(guided_knowledge_ask(handle, c("not json").as_ptr()).is_null());
+            assert!(guided_knowledge_stats(handle).is_null());
+            guided_knowledge_close(handle);
+
+            assert!(guided_knowledge_open(std::ptr::null(), c("docs").as_ptr()).is_null());
+        }
+    }
+}
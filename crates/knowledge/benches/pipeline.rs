@@ -0,0 +1,83 @@
+//! Criterion benchmarks of the knowledge pipeline: chunking, trigram
+//! embedding and LanceDB search, on the corpora `guided knowledge bench`
+//! generates.
+//!
+//! Run with `cargo bench -p guided-knowledge`; criterion compares each run
+//! with the last one saved under `target/criterion`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use guided_knowledge::bench::{self, code_corpus, markdown_corpus, paragraphs};
+use guided_knowledge::chunk::{ChunkConfig, ChunkPipeline};
+use guided_knowledge::embeddings::EmbeddingProvider;
+use guided_knowledge::vector_index::VectorIndex;
+use std::path::Path;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn chunking(c: &mut Criterion) {
+    let pipeline = ChunkPipeline::new(ChunkConfig::default());
+    let mut group = c.benchmark_group("chunk");
+    for (name, file, text) in [
+        ("markdown", "bench.md", markdown_corpus(1_000)),
+        ("code", "bench.rs", code_corpus(3_000)),
+    ] {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                pipeline
+                    .process("bench", &text, Some(Path::new(file)))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn embedding(c: &mut Criterion) {
+    let rt = runtime();
+    let provider = bench::provider();
+    let texts = paragraphs(256, 3);
+    let mut group = c.benchmark_group("embed");
+    group.throughput(Throughput::Elements(texts.len() as u64));
+    group.bench_function("trigram", |b| {
+        b.iter(|| rt.block_on(provider.embed_batch(&texts)).unwrap())
+    });
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let rt = runtime();
+    let provider = bench::provider();
+    let sizes = [1_000, 10_000, 50_000];
+    let texts = paragraphs(sizes[sizes.len() - 1], 3);
+    let embeddings = rt.block_on(provider.embed_batch(&texts)).unwrap();
+    let query = rt
+        .block_on(provider.embed_batch(&paragraphs(1, 4)))
+        .unwrap()
+        .remove(0);
+    let dir = std::env::temp_dir().join(format!("guided-criterion-{}", std::process::id()));
+
+    let mut group = c.benchmark_group("search");
+    for size in sizes {
+        let index = rt
+            .block_on(bench::build_index(
+                &dir.join(size.to_string()),
+                &texts[..size],
+                &embeddings[..size],
+            ))
+            .unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| rt.block_on(async { index.search_ids(&query, 5).unwrap() }))
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, chunking, embedding, search);
+criterion_main!(benches);
@@ -0,0 +1,334 @@
+//! A handle on one knowledge base.
+//!
+//! The free functions of this crate ([`crate::ask`], [`crate::stats`], ...)
+//! read the base's config, open its LanceDB index and set up an embedding
+//! provider on every call. Applications that query a base repeatedly, such
+//! as a long-running service, open a [`KnowledgeBase`] once instead: it holds
+//! the config, the index connection and the embedding provider between
+//! calls. [`KnowledgeBase::open_cached`] shares them process-wide instead,
+//! for callers that open a handle per query.
+//!
+//! ```no_run
+//! # async fn example(workspace: &std::path::Path) -> guided_core::AppResult<()> {
+//! use guided_knowledge::KnowledgeBase;
+//!
+//! let base = KnowledgeBase::open(workspace, "docs").await?;
+//! let stats = base.stats().await?;
+//! println!("{} chunks", stats.chunks_count);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::embeddings::EmbeddingEngine;
+use crate::encryption::Cipher;
+use crate::lancedb_index::LanceDbIndex;
+use crate::types::{
+    AskOptions, AskResult, BaseStats, ContentStats, KnowledgeBaseConfig, KnowledgeChunk,
+    KnowledgeSource, LearnOptions, LearnStats,
+};
+use crate::{config, encryption, progress, rag, vector_index, warm};
+use guided_core::config::ProviderConfig;
+use guided_core::{AppError, AppResult};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// An open knowledge base: its config, index connection and embedding
+/// provider, kept between calls.
+///
+/// The handle sees what it learns itself; after another process learns
+/// into the base, [`KnowledgeBase::reload`] picks up its changes.
+pub struct KnowledgeBase {
+    workspace: PathBuf,
+    name: String,
+    config: KnowledgeBaseConfig,
+    /// Unscoped index, `None` until the base is first learned
+    index: Option<LanceDbIndex>,
+    /// Cipher of an encrypted base, once its key was needed
+    cipher: OnceLock<Cipher>,
+    engine: Arc<EmbeddingEngine>,
+}
+
+impl KnowledgeBase {
+    /// Open a base of `workspace`. A base that was never learned opens too,
+    /// so it can be learned through the handle; asking it fails until then.
+    pub async fn open(workspace: &Path, name: &str) -> AppResult<Self> {
+        let config = config::load_config(workspace, name)?;
+        let index = open_index(workspace, name, &config).await?;
+        Ok(Self {
+            workspace: workspace.to_path_buf(),
+            name: name.to_string(),
+            config,
+            index,
+            cipher: OnceLock::new(),
+            engine: Arc::new(EmbeddingEngine::new(workspace.to_path_buf())),
+        })
+    }
+
+    /// Open a base with the index connection and embedding provider cached
+    /// for this process (see [`crate::warm`]), opening them only on first
+    /// use or after the base changed on disk.
+    pub async fn open_cached(
+        workspace: &Path,
+        name: &str,
+        provider_configs: &HashMap<String, ProviderConfig>,
+    ) -> AppResult<Self> {
+        let config = config::load_config(workspace, name)?;
+        let index = warm::index(workspace, name, &config)
+            .await?
+            .map(|index| index.with_title_weight(config.title_weight));
+        Ok(Self {
+            workspace: workspace.to_path_buf(),
+            name: name.to_string(),
+            config,
+            index,
+            cipher: OnceLock::new(),
+            engine: warm::engine(workspace, provider_configs),
+        })
+    }
+
+    /// Use these provider configs (endpoints, keys) for embeddings.
+    pub fn with_provider_configs(
+        mut self,
+        provider_configs: HashMap<String, ProviderConfig>,
+    ) -> Self {
+        self.engine = Arc::new(
+            EmbeddingEngine::new(self.workspace.clone()).with_provider_configs(provider_configs),
+        );
+        self
+    }
+
+    /// Name of the base.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Workspace the base belongs to.
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    /// Config of the base, as of the last open, learn or reload.
+    pub fn config(&self) -> &KnowledgeBaseConfig {
+        &self.config
+    }
+
+    /// Read the config again and reopen the index, to see changes made by
+    /// other processes. The embedding provider is set up again when the
+    /// base's provider or model changed.
+    pub async fn reload(&mut self) -> AppResult<()> {
+        let config = config::load_config(&self.workspace, &self.name)?;
+        if (&config.provider, &config.model) != (&self.config.provider, &self.config.model) {
+            self.engine.evict_provider(&self.name);
+        }
+        self.index = open_index(&self.workspace, &self.name, &config).await?;
+        self.cipher = OnceLock::new();
+        self.config = config;
+        Ok(())
+    }
+
+    /// Cipher of an encrypted base, read from its key on first use, so
+    /// operations that leave chunk text sealed work without the key.
+    fn cipher(&self) -> AppResult<Option<Cipher>> {
+        if let Some(cipher) = self.cipher.get() {
+            return Ok(Some(cipher.clone()));
+        }
+        let cipher = encryption::cipher_for(&self.config)?;
+        Ok(cipher.map(|cipher| self.cipher.get_or_init(|| cipher).clone()))
+    }
+
+    /// Learn into the base (see [`crate::learn`]); `options.base_name` is
+    /// replaced by the handle's base. The handle is reloaded afterwards,
+    /// also when learn fails part way, as it may have saved some sources.
+    pub async fn learn(
+        &mut self,
+        options: &LearnOptions,
+        api_key: Option<&str>,
+    ) -> AppResult<LearnStats> {
+        self.learn_with_progress(options, api_key, progress::ProgressReporter::noop())
+            .await
+    }
+
+    /// Learn with progress reporting.
+    pub async fn learn_with_progress(
+        &mut self,
+        options: &LearnOptions,
+        api_key: Option<&str>,
+        progress: progress::ProgressReporter,
+    ) -> AppResult<LearnStats> {
+        let options = LearnOptions {
+            base_name: self.name.clone(),
+            ..options.clone()
+        };
+        let result = crate::learn_with_progress(&self.workspace, &options, api_key, progress).await;
+        self.reload().await?;
+        result
+    }
+
+    /// Query the base and return relevant chunks (see [`crate::ask`]).
+    /// `options.base_name` is ignored and embeddings use the handle's
+    /// provider configs rather than `options.provider_configs`.
+    pub async fn ask(&self, options: AskOptions, api_key: Option<&str>) -> AppResult<AskResult> {
+        tracing::info!(
+            "Querying knowledge base '{}' with query: {}",
+            self.name,
+            options.query
+        );
+        let retrieval = self.retrieve(&options, api_key).await?;
+
+        let chunks: Vec<KnowledgeChunk> = retrieval
+            .chunks
+            .iter()
+            .map(|(chunk, _score)| chunk.clone())
+            .collect();
+        let scores: Vec<f32> = retrieval
+            .chunks
+            .iter()
+            .map(|(_chunk, score)| *score)
+            .collect();
+
+        if chunks.is_empty() {
+            tracing::info!(
+                "No relevant chunks found (all scores below {:.2} threshold)",
+                self.config.min_relevance()
+            );
+        } else {
+            tracing::info!(
+                "Retrieved {} relevant chunks (top score: {:.3}, lowest: {:.3})",
+                chunks.len(),
+                scores.first().unwrap_or(&0.0),
+                scores.last().unwrap_or(&0.0)
+            );
+        }
+
+        let highlights = chunks
+            .iter()
+            .map(|chunk| rag::highlight::match_ranges(&options.query, &chunk.text))
+            .collect();
+
+        Ok(AskResult {
+            chunks,
+            scores,
+            highlights,
+            flagged: retrieval.flagged,
+        })
+    }
+
+    /// The chunks `options` retrieve from the base, for [`Self::ask`] and
+    /// RAG answers alike (see [`rag::retrieval`]).
+    pub(crate) async fn retrieve(
+        &self,
+        options: &AskOptions,
+        api_key: Option<&str>,
+    ) -> AppResult<rag::retrieval::Retrieval> {
+        options.filters.validate()?;
+        let index = self.index.as_ref().ok_or_else(|| {
+            AppError::Knowledge(format!(
+                "Knowledge base '{}' has no index. Run 'guided knowledge learn' first.",
+                self.name
+            ))
+        })?;
+        crate::check_index_dimensions(&self.name, &self.config, index)?;
+        let namespace = options
+            .namespace
+            .as_deref()
+            .map(config::normalize_namespace)
+            .transpose()?;
+        let index = index
+            .clone()
+            .with_cipher(self.cipher()?)
+            .with_namespace(namespace.clone())
+            .with_filters(&options.filters);
+
+        rag::retrieval::retrieve(
+            &self.workspace,
+            &self.name,
+            &self.config,
+            index,
+            &self.engine,
+            options,
+            namespace.as_deref(),
+            api_key,
+        )
+        .await
+    }
+
+    /// Statistics of the base (see [`crate::stats`]).
+    pub async fn stats(&self) -> AppResult<BaseStats> {
+        let workspace = self.workspace.as_path();
+        let base_name = self.name.as_str();
+        tracing::info!("Getting stats for knowledge base '{}'", base_name);
+
+        let index = self.index.as_ref().ok_or_else(|| {
+            AppError::Knowledge(format!("Knowledge base '{}' does not exist", base_name))
+        })?;
+
+        use vector_index::VectorIndex;
+        let (_, chunks_count) = index.stats()?;
+
+        // Calculate directory size
+        let db_size_bytes =
+            crate::calculate_dir_size(&config::get_index_path(workspace, base_name));
+
+        // Read sources.jsonl to get last_learn_at
+        let source_manager = rag::SourceManager::new(workspace, base_name);
+        let sources = source_manager.list_sources().unwrap_or_default();
+
+        let last_learn_at = sources.iter().map(|s| s.indexed_at).max();
+
+        // Re-learned files are recorded again; only their latest record counts
+        let mut latest: HashMap<(Option<&str>, &str), &KnowledgeSource> = HashMap::new();
+        for source in &sources {
+            latest.insert((source.namespace.as_deref(), source.path.as_str()), source);
+        }
+        let sources_count = latest.len() as u32;
+        let mut content = ContentStats::default();
+        for source in latest.values() {
+            content.add_source(source);
+        }
+
+        tracing::debug!(
+            "Stats for '{}': {} sources, {} chunks, {} bytes, last_learn_at: {:?}",
+            base_name,
+            sources_count,
+            chunks_count,
+            db_size_bytes,
+            last_learn_at
+        );
+
+        Ok(BaseStats {
+            base_name: base_name.to_string(),
+            sources_count,
+            chunks_count,
+            db_size_bytes,
+            content,
+            provider: self.config.provider.clone(),
+            model: self.config.model.clone(),
+            last_learn_at,
+            scope: self.config.scope,
+            base_dir: config::get_base_dir(workspace, base_name),
+        })
+    }
+
+    /// Close the base, releasing its index connection and embedding
+    /// provider. Dropping the handle does the same.
+    pub fn close(self) {
+        self.engine.evict_provider(&self.name);
+    }
+}
+
+/// The index of a base with its title weight, if it was learned.
+async fn open_index(
+    workspace: &Path,
+    name: &str,
+    config: &KnowledgeBaseConfig,
+) -> AppResult<Option<LanceDbIndex>> {
+    let index_path = config::get_index_path(workspace, name);
+    if !index_path.exists() {
+        return Ok(None);
+    }
+    let index = LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
+        .await?
+        .with_title_weight(config.title_weight);
+    Ok(Some(index))
+}
@@ -0,0 +1,362 @@
+//! Performance profile of the knowledge pipeline.
+//!
+//! Times the stages learn and ask spend their time in, on generated corpora
+//! so runs compare across machines and commits: chunking markdown and code,
+//! embedding with the trigram provider, and searching LanceDB indexes of
+//! several sizes. `guided knowledge bench` runs a quick profile and prints
+//! it; `cargo bench -p guided-knowledge` runs the same stages under
+//! criterion, which keeps a baseline to catch regressions against.
+
+use crate::chunk::{ChunkConfig, ChunkPipeline};
+use crate::embeddings::providers::trigram::TrigramProvider;
+use crate::embeddings::EmbeddingProvider;
+use crate::lancedb_index::LanceDbIndex;
+use crate::types::{
+    BenchOptions, BenchReport, ChunkBench, EmbedBench, KnowledgeChunk, SearchBench,
+};
+use crate::vector_index::VectorIndex;
+use guided_core::{AppError, AppResult};
+use std::path::Path;
+use std::time::Instant;
+
+/// Embedding model and dimensions of the embedding and search stages.
+pub const MODEL: &str = "trigram-v2";
+pub const DIMENSIONS: usize = 384;
+
+/// Chunks retrieved per search.
+const TOP_K: usize = 5;
+
+/// Rows written to an index at once, as learn does.
+const UPSERT_BATCH: usize = 500;
+
+const WORDS: &[&str] = &[
+    "deploy",
+    "release",
+    "invoice",
+    "customer",
+    "payment",
+    "cluster",
+    "region",
+    "schema",
+    "migration",
+    "rollback",
+    "latency",
+    "cache",
+    "queue",
+    "worker",
+    "token",
+    "session",
+    "gateway",
+    "replica",
+    "backup",
+    "quota",
+    "tenant",
+    "index",
+    "search",
+    "report",
+    "alert",
+    "metric",
+    "budget",
+    "contract",
+    "ledger",
+    "refund",
+    "webhook",
+    "pipeline",
+    "shard",
+    "snapshot",
+    "failover",
+    "audit",
+    "policy",
+    "secret",
+    "rotation",
+    "timeout",
+];
+
+const FILLER: &[&str] = &[
+    "the", "a", "every", "each", "when", "before", "after", "with", "without", "for", "and", "is",
+    "runs", "stores", "checks", "sends", "keeps", "needs", "moves", "updates",
+];
+
+/// Deterministic word picker, so every run chunks and searches the same
+/// text.
+struct Words(u64);
+
+impl Words {
+    fn new(seed: u64) -> Self {
+        Self(
+            seed.wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407),
+        )
+    }
+
+    fn next(&mut self, words: &'static [&'static str]) -> &'static str {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        words[(self.0 >> 33) as usize % words.len()]
+    }
+
+    /// A sentence of `len` words, mixing topic and filler words.
+    fn sentence(&mut self, len: usize) -> String {
+        let mut sentence = String::new();
+        for i in 0..len {
+            let list = if i % 3 == 1 { WORDS } else { FILLER };
+            if i > 0 {
+                sentence.push(' ');
+            }
+            sentence.push_str(self.next(list));
+        }
+        sentence.push('.');
+        sentence
+    }
+}
+
+/// Markdown of `sections` sections, each with prose, a list and a code
+/// block (about 1 KB per section).
+pub fn markdown_corpus(sections: usize) -> String {
+    let mut words = Words::new(1);
+    let mut text = String::from("# Operations handbook\n\n");
+    for i in 0..sections {
+        let topic = words.next(WORDS);
+        text.push_str(&format!("## {} {}\n\n", topic, i));
+        for _ in 0..3 {
+            text.push_str(&words.sentence(14));
+            text.push(' ');
+        }
+        text.push_str("\n\n");
+        for _ in 0..3 {
+            text.push_str(&format!("- {}\n", words.sentence(8)));
+        }
+        text.push_str(&format!(
+            "\n```bash\nguided {} --{} {}\n```\n\n",
+            topic,
+            words.next(WORDS),
+            i
+        ));
+        text.push_str(&words.sentence(20));
+        text.push_str("\n\n");
+    }
+    text
+}
+
+/// Rust source of `functions` documented functions (about 300 bytes each).
+pub fn code_corpus(functions: usize) -> String {
+    let mut words = Words::new(2);
+    let mut text = String::from("//! Generated module for benchmarks.\n\n");
+    for i in 0..functions {
+        let name = words.next(WORDS);
+        text.push_str(&format!(
+            "/// {}\npub fn {}_{}(input: &[u64]) -> u64 {{\n    let mut total = 0;\n    \
+             for value in input {{\n        if value % {} == 0 {{\n            total += value;\n        \
+             }}\n    }}\n    total\n}}\n\n",
+            words.sentence(10),
+            name,
+            i,
+            i % 7 + 2
+        ));
+    }
+    text
+}
+
+/// `count` paragraphs of about chunk size, as embedded and indexed.
+pub fn paragraphs(count: usize, seed: u64) -> Vec<String> {
+    let mut words = Words::new(seed);
+    (0..count)
+        .map(|_| {
+            (0..10)
+                .map(|_| words.sentence(12))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// The trigram provider the embedding and search stages use.
+pub fn provider() -> TrigramProvider {
+    TrigramProvider::for_model(MODEL, DIMENSIONS)
+}
+
+/// Build an index of `texts` with their `embeddings` at `path`.
+pub async fn build_index(
+    path: &Path,
+    texts: &[String],
+    embeddings: &[Vec<f32>],
+) -> AppResult<LanceDbIndex> {
+    let mut index = LanceDbIndex::new(path, "chunks", DIMENSIONS).await?;
+    let chunks: Vec<KnowledgeChunk> = texts
+        .iter()
+        .zip(embeddings)
+        .enumerate()
+        .map(|(i, (text, embedding))| KnowledgeChunk {
+            id: format!("bench-{}", i),
+            source_id: format!("source-{}", i / 10),
+            position: (i % 10) as u32,
+            text: text.clone(),
+            embedding: Some(embedding.clone()),
+            title_embedding: None,
+            metadata: serde_json::json!({}),
+        })
+        .collect();
+    for batch in chunks.chunks(UPSERT_BATCH) {
+        index.upsert_chunks(batch)?;
+    }
+    Ok(index)
+}
+
+/// Run the profile: chunk the generated corpora, embed paragraphs, and
+/// search an index of each size in `options`.
+pub async fn bench(options: &BenchOptions) -> AppResult<BenchReport> {
+    let start = Instant::now();
+    tracing::info!("Benchmarking with {:?}", options);
+    if options.queries == 0 || options.index_sizes.contains(&0) {
+        return Err(AppError::Knowledge(
+            "Index sizes and the number of queries must be positive".to_string(),
+        ));
+    }
+
+    let pipeline = ChunkPipeline::new(ChunkConfig::default());
+    let chunking = [
+        ("markdown", "bench.md", markdown_corpus(options.sections)),
+        ("code", "bench.rs", code_corpus(options.sections * 3)),
+    ]
+    .into_iter()
+    .map(|(corpus, file, text)| {
+        let started = Instant::now();
+        let chunks = pipeline.process("bench", &text, Some(Path::new(file)))?;
+        let secs = started.elapsed().as_secs_f64();
+        Ok(ChunkBench {
+            corpus: corpus.to_string(),
+            bytes: text.len() as u64,
+            chunks: chunks.len() as u32,
+            secs,
+            mb_per_sec: text.len() as f64 / 1_000_000.0 / secs.max(f64::EPSILON),
+        })
+    })
+    .collect::<AppResult<Vec<_>>>()?;
+
+    // Embed as many paragraphs as the largest index holds
+    let largest = options.index_sizes.iter().copied().max().unwrap_or(0);
+    let texts = paragraphs(largest.max(options.queries), 3);
+    let provider = provider();
+    let started = Instant::now();
+    let embeddings = provider.embed_batch(&texts).await?;
+    let secs = started.elapsed().as_secs_f64();
+    let embedding = EmbedBench {
+        provider: provider.provider_name().to_string(),
+        model: MODEL.to_string(),
+        texts: texts.len() as u32,
+        secs,
+        texts_per_sec: texts.len() as f64 / secs.max(f64::EPSILON),
+    };
+
+    let queries = provider
+        .embed_batch(&paragraphs(options.queries, 4))
+        .await?;
+    let dir = std::env::temp_dir().join(format!("guided-bench-{}", uuid::Uuid::new_v4()));
+    let search = search_stage(&dir, options, &texts, &embeddings, &queries).await;
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("Failed to remove {:?}: {}", dir, e);
+        }
+    }
+
+    Ok(BenchReport {
+        chunking,
+        embedding,
+        search: search?,
+        duration_secs: start.elapsed().as_secs_f64(),
+    })
+}
+
+async fn search_stage(
+    dir: &Path,
+    options: &BenchOptions,
+    texts: &[String],
+    embeddings: &[Vec<f32>],
+    queries: &[Vec<f32>],
+) -> AppResult<Vec<SearchBench>> {
+    let mut results = Vec::new();
+    for &size in &options.index_sizes {
+        let started = Instant::now();
+        let index = build_index(
+            &dir.join(size.to_string()),
+            &texts[..size],
+            &embeddings[..size],
+        )
+        .await?;
+        let build_secs = started.elapsed().as_secs_f64();
+
+        let mut latencies = Vec::with_capacity(queries.len());
+        for query in queries {
+            let started = Instant::now();
+            index.search_ids(query, TOP_K)?;
+            latencies.push(started.elapsed().as_secs_f64() * 1000.0);
+        }
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        results.push(SearchBench {
+            index_size: size as u32,
+            queries: queries.len() as u32,
+            build_secs,
+            mean_ms: latencies.iter().sum::<f64>() / latencies.len() as f64,
+            p50_ms: percentile(&latencies, 0.50),
+            p95_ms: percentile(&latencies, 0.95),
+        });
+    }
+    Ok(results)
+}
+
+/// Value at `p` (0 to 1) of sorted `values`, nearest rank.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpora_are_deterministic_and_sized() {
+        assert_eq!(markdown_corpus(5), markdown_corpus(5));
+        assert!(markdown_corpus(100).len() > 50_000);
+        assert!(code_corpus(100).len() > 20_000);
+        assert_ne!(paragraphs(2, 1)[0], paragraphs(2, 1)[1]);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&values, 0.5), 10.0);
+        assert_eq!(percentile(&values, 0.95), 19.0);
+        assert_eq!(percentile(&[3.0], 0.95), 3.0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bench_reports_every_stage() {
+        let options = BenchOptions {
+            sections: 20,
+            index_sizes: vec![50, 200],
+            queries: 5,
+        };
+        let report = bench(&options).await.unwrap();
+
+        assert_eq!(report.chunking.len(), 2);
+        assert!(report.chunking.iter().all(|c| c.chunks > 0 && c.bytes > 0));
+        assert_eq!(report.embedding.texts, 200);
+        assert_eq!(report.embedding.provider, "trigram");
+        let sizes: Vec<u32> = report.search.iter().map(|s| s.index_size).collect();
+        assert_eq!(sizes, vec![50, 200]);
+        assert!(report
+            .search
+            .iter()
+            .all(|s| s.queries == 5 && s.p95_ms >= s.p50_ms));
+
+        let options = BenchOptions {
+            queries: 0,
+            ..options
+        };
+        assert!(bench(&options).await.is_err());
+    }
+}
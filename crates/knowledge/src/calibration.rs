@@ -0,0 +1,188 @@
+//! Relevance threshold calibration.
+//!
+//! How similar two unrelated texts look depends on the embedding model:
+//! trigram vectors of unrelated chunks score around 0.1, some neural models
+//! put nearly everything above 0.5. At the end of learn, the scores of
+//! random pairs of chunks from different sources approximate what an
+//! unrelated chunk scores against a question, and the thresholds of `ask`
+//! are taken from that distribution: chunks must beat the median unrelated
+//! pair to be retrieved, and answers are confident when the best chunk
+//! beats 95% of them.
+//!
+//! Pairs are drawn by a fixed-seed generator, so learning the same chunks
+//! again gives the same thresholds. They are drawn from a sample of at most
+//! [`SAMPLE_ROWS`] rows (see [`sample_rows`]), of which only the source and
+//! embedding are read, so calibrating costs the same for any base size.
+
+use crate::lancedb_index::cosine_similarity;
+use crate::types::{KnowledgeBaseConfig, Thresholds};
+use std::collections::BTreeSet;
+
+/// Chunks a base needs before its thresholds are calibrated; below this,
+/// the defaults apply.
+pub const MIN_CHUNKS: usize = 50;
+
+/// Pairs of chunks scored, at most.
+const SAMPLE_PAIRS: usize = 1000;
+
+/// Rows of the index the pairs are drawn from, at most.
+pub const SAMPLE_ROWS: usize = 2 * SAMPLE_PAIRS;
+
+/// Share of unrelated pairs a chunk must outscore to be retrieved.
+const RELEVANCE_PERCENTILE: f32 = 0.5;
+
+/// Share of unrelated pairs the best chunk must outscore for a confident
+/// answer.
+const CONFIDENCE_PERCENTILE: f32 = 0.95;
+
+/// Offsets of the index rows to calibrate from among `rows`: all of them
+/// up to [`SAMPLE_ROWS`], else that many distinct ones at random, in order.
+pub fn sample_rows(rows: usize) -> Vec<u64> {
+    if rows <= SAMPLE_ROWS {
+        return (0..rows as u64).collect();
+    }
+    let mut random = SplitMix64(0x5851_f42d_4c95_7f2d);
+    let mut offsets = BTreeSet::new();
+    while offsets.len() < SAMPLE_ROWS {
+        offsets.insert(random.below(rows) as u64);
+    }
+    offsets.into_iter().collect()
+}
+
+/// Thresholds for the chunks of a base, from the scores of random pairs of
+/// `embedded` chunks (source id and embedding, see [`sample_rows`]) of
+/// different sources. `None` when there are fewer than [`MIN_CHUNKS`]
+/// embedded chunks or they all come from one source.
+pub fn calibrate(
+    config: &KnowledgeBaseConfig,
+    embedded: &[(String, Vec<f32>)],
+) -> Option<Thresholds> {
+    if embedded.len() < MIN_CHUNKS {
+        return None;
+    }
+
+    let mut random = SplitMix64(0x9e37_79b9_7f4a_7c15);
+    let mut scores = Vec::with_capacity(SAMPLE_PAIRS);
+    // Bounded, so bases of one source (whose pairs are all skipped) end
+    for _ in 0..SAMPLE_PAIRS * 4 {
+        if scores.len() == SAMPLE_PAIRS {
+            break;
+        }
+        let a = &embedded[random.below(embedded.len())];
+        let b = &embedded[random.below(embedded.len())];
+        if a.0 != b.0 {
+            scores.push(cosine_similarity(&a.1, &b.1));
+        }
+    }
+    if scores.len() < MIN_CHUNKS {
+        return None;
+    }
+    scores.sort_by(f32::total_cmp);
+
+    let min_relevance = percentile(&scores, RELEVANCE_PERCENTILE).max(0.0);
+    let confidence = percentile(&scores, CONFIDENCE_PERCENTILE).max(min_relevance);
+    tracing::info!(
+        "Calibrated relevance thresholds for {}/{} from {} chunk pairs: min relevance {:.3}, confidence {:.3}",
+        config.provider,
+        config.model,
+        scores.len(),
+        min_relevance,
+        confidence
+    );
+    Some(Thresholds {
+        min_relevance,
+        confidence,
+        provider: config.provider.clone(),
+        model: config.model.clone(),
+        pairs: scores.len(),
+        calibrated_at: chrono::Utc::now(),
+    })
+}
+
+/// Score below which a share `p` of the sorted `scores` fall.
+fn percentile(scores: &[f32], p: f32) -> f32 {
+    let rank = ((scores.len() - 1) as f32 * p).round() as usize;
+    scores[rank.min(scores.len() - 1)]
+}
+
+/// SplitMix64, enough to pick sample pairs without a `rand` dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(source_id: &str, embedding: Vec<f32>) -> (String, Vec<f32>) {
+        (source_id.to_string(), embedding)
+    }
+
+    #[test]
+    fn test_calibrate_from_unrelated_pairs() {
+        let config = KnowledgeBaseConfig {
+            provider: "trigram".to_string(),
+            model: "trigram-v2".to_string(),
+            ..Default::default()
+        };
+        // Sources a and b point their chunks in different directions
+        let chunks: Vec<(String, Vec<f32>)> = (0..60)
+            .map(|i| {
+                let t = i as f32 / 60.0;
+                if i % 2 == 0 {
+                    chunk("a", vec![1.0, t, 0.0])
+                } else {
+                    chunk("b", vec![0.0, t, 1.0])
+                }
+            })
+            .collect();
+
+        let thresholds = calibrate(&config, &chunks).unwrap();
+        assert!(thresholds.min_relevance >= 0.0 && thresholds.min_relevance < 0.5);
+        assert!(thresholds.confidence >= thresholds.min_relevance);
+        assert_eq!(thresholds.model, "trigram-v2");
+        assert_eq!(
+            calibrate(&config, &chunks).unwrap().min_relevance,
+            thresholds.min_relevance
+        );
+
+        // Too few chunks, or a single source, keep the defaults
+        assert!(calibrate(&config, &chunks[..10]).is_none());
+        let one_source: Vec<(String, Vec<f32>)> = (0..60)
+            .map(|i| chunk("a", vec![1.0, i as f32, 0.0]))
+            .collect();
+        assert!(calibrate(&config, &one_source).is_none());
+    }
+
+    #[test]
+    fn test_sample_rows() {
+        assert_eq!(sample_rows(3), vec![0, 1, 2]);
+
+        let offsets = sample_rows(1_000_000);
+        assert_eq!(offsets.len(), SAMPLE_ROWS);
+        assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(offsets.iter().all(|&offset| offset < 1_000_000));
+        assert_eq!(offsets, sample_rows(1_000_000));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let scores = [0.1, 0.2, 0.3, 0.4, 0.5];
+        assert_eq!(percentile(&scores, 0.0), 0.1);
+        assert_eq!(percentile(&scores, 0.5), 0.3);
+        assert_eq!(percentile(&scores, 1.0), 0.5);
+    }
+}
@@ -0,0 +1,237 @@
+//! Learn checkpoints for resuming interrupted runs.
+//!
+//! A checkpoint has two files in the base directory:
+//! - `learn-checkpoint.json`: the file list discovered when the run started
+//! - `learn-checkpoint.jsonl`: one line per file that reached the index,
+//!   with the content hash it had at the time
+//!
+//! The list of done files is append-only so recording a batch costs the
+//! same whether it is the first or the five-thousandth. Both files are
+//! removed when a learn completes.
+
+use crate::config;
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// Files discovered at the start of a learn run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearnCheckpoint {
+    pub version: u32,
+
+    /// When the interrupted run started (RFC 3339)
+    pub started_at: String,
+
+    /// Every file the run intended to learn, in processing order
+    pub files: Vec<PathBuf>,
+}
+
+/// SHA-256 of a file's raw bytes.
+pub fn file_hash(path: &Path) -> AppResult<String> {
+    let bytes = std::fs::read(path)?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// A file that was indexed before the interruption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointEntry {
+    pub path: PathBuf,
+    pub content_hash: String,
+}
+
+/// Records progress of a running learn.
+#[derive(Debug, Clone)]
+pub struct CheckpointWriter {
+    manifest_path: PathBuf,
+    progress_path: PathBuf,
+}
+
+impl CheckpointWriter {
+    /// Start a fresh checkpoint for `files`, replacing any previous one.
+    pub fn start(workspace: &Path, base_name: &str, files: &[PathBuf]) -> AppResult<Self> {
+        let writer = Self::open(workspace, base_name);
+
+        let checkpoint = LearnCheckpoint {
+            version: CHECKPOINT_VERSION,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            files: files.to_vec(),
+        };
+        std::fs::write(&writer.manifest_path, serde_json::to_string(&checkpoint)?).map_err(
+            |e| {
+                AppError::Knowledge(format!(
+                    "Failed to write learn checkpoint {:?}: {}",
+                    writer.manifest_path, e
+                ))
+            },
+        )?;
+        std::fs::write(&writer.progress_path, "")?;
+
+        Ok(writer)
+    }
+
+    /// Continue appending to an existing checkpoint.
+    pub fn open(workspace: &Path, base_name: &str) -> Self {
+        Self {
+            manifest_path: config::get_checkpoint_path(workspace, base_name),
+            progress_path: config::get_checkpoint_progress_path(workspace, base_name),
+        }
+    }
+
+    /// Mark files as indexed.
+    pub fn record(&self, entries: &[CheckpointEntry]) -> AppResult<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.progress_path)?;
+
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Remove the checkpoint after a complete run.
+    pub fn finish(self) -> AppResult<()> {
+        remove(&self.manifest_path)?;
+        remove(&self.progress_path)
+    }
+}
+
+/// Plan for resuming an interrupted learn.
+#[derive(Debug, Clone)]
+pub struct ResumePlan {
+    pub checkpoint: LearnCheckpoint,
+
+    /// Files still to learn, in original order
+    pub remaining: Vec<PathBuf>,
+
+    /// Files skipped because they were indexed and are unchanged
+    pub skipped: usize,
+}
+
+/// Load the checkpoint of an interrupted learn, if there is one.
+///
+/// Files recorded as done are skipped unless their content changed since.
+/// A truncated last line (from a crash mid-append) is ignored.
+pub fn load_resume_plan(workspace: &Path, base_name: &str) -> AppResult<Option<ResumePlan>> {
+    let manifest_path = config::get_checkpoint_path(workspace, base_name);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let checkpoint: LearnCheckpoint =
+        serde_json::from_str(&std::fs::read_to_string(&manifest_path)?).map_err(|e| {
+            AppError::Knowledge(format!(
+                "Corrupt learn checkpoint {:?}: {}",
+                manifest_path, e
+            ))
+        })?;
+    if checkpoint.version != CHECKPOINT_VERSION {
+        return Err(AppError::Knowledge(format!(
+            "Unsupported learn checkpoint version {} in {:?}",
+            checkpoint.version, manifest_path
+        )));
+    }
+
+    let mut done: HashMap<PathBuf, String> = HashMap::new();
+    let progress_path = config::get_checkpoint_progress_path(workspace, base_name);
+    if progress_path.exists() {
+        let file = std::fs::File::open(&progress_path)?;
+        for line in std::io::BufReader::new(file).lines() {
+            match serde_json::from_str::<CheckpointEntry>(&line?) {
+                Ok(entry) => {
+                    done.insert(entry.path, entry.content_hash);
+                }
+                Err(e) => tracing::warn!("Ignoring unreadable checkpoint entry: {}", e),
+            }
+        }
+    }
+
+    let mut remaining = Vec::new();
+    let mut skipped = 0;
+    for path in &checkpoint.files {
+        let unchanged = done
+            .get(path)
+            .is_some_and(|hash| file_hash(path).is_ok_and(|current| current == *hash));
+
+        if unchanged {
+            skipped += 1;
+        } else {
+            remaining.push(path.clone());
+        }
+    }
+
+    Ok(Some(ResumePlan {
+        checkpoint,
+        remaining,
+        skipped,
+    }))
+}
+
+fn remove(path: &Path) -> AppResult<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resume_skips_unchanged_done_files() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(config::get_base_dir(temp.path(), "docs")).unwrap();
+
+        let files: Vec<PathBuf> = ["a.md", "b.md", "c.md"]
+            .iter()
+            .map(|name| {
+                let path = temp.path().join(name);
+                std::fs::write(&path, format!("content of {}", name)).unwrap();
+                path
+            })
+            .collect();
+
+        assert!(load_resume_plan(temp.path(), "docs").unwrap().is_none());
+
+        let writer = CheckpointWriter::start(temp.path(), "docs", &files).unwrap();
+        writer
+            .record(&[
+                CheckpointEntry {
+                    path: files[0].clone(),
+                    content_hash: file_hash(&files[0]).unwrap(),
+                },
+                CheckpointEntry {
+                    path: files[1].clone(),
+                    content_hash: file_hash(&files[1]).unwrap(),
+                },
+            ])
+            .unwrap();
+
+        // b.md changed after it was indexed, so it is learned again
+        std::fs::write(&files[1], "edited").unwrap();
+
+        let plan = load_resume_plan(temp.path(), "docs").unwrap().unwrap();
+        assert_eq!(plan.skipped, 1);
+        assert_eq!(plan.remaining, vec![files[1].clone(), files[2].clone()]);
+
+        CheckpointWriter::open(temp.path(), "docs")
+            .finish()
+            .unwrap();
+        assert!(load_resume_plan(temp.path(), "docs").unwrap().is_none());
+    }
+}
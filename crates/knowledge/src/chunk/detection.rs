@@ -44,6 +44,23 @@ pub enum Language {
 }
 
 impl Language {
+    /// Lowercase name, as serialized and used to tag fenced code blocks.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::TypeScript => "typescript",
+            Language::JavaScript => "javascript",
+            Language::Python => "python",
+            Language::Go => "go",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Java => "java",
+            Language::Ruby => "ruby",
+            Language::Php => "php",
+            Language::Unknown => "unknown",
+        }
+    }
+
     /// Get tree-sitter language for this language.
     pub fn tree_sitter_language(&self) -> Option<tree_sitter::Language> {
         match self {
@@ -0,0 +1,73 @@
+//! Byte offset to line number mapping.
+
+/// Line start offsets of a document, for mapping chunk byte ranges to lines.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the line starts of `text`.
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// 1-based line containing the byte at `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    /// 1-based inclusive line range of the text in `byte_range`, ignoring
+    /// leading and trailing whitespace (chunks are stored trimmed).
+    pub fn line_range(&self, byte_range: (usize, usize)) -> (usize, usize) {
+        let end = byte_range.1.min(self.text.len());
+        let start = byte_range.0.min(end);
+        let Some(slice) = self.text.get(start..end) else {
+            return (self.line_of(start), self.line_of(end.saturating_sub(1)));
+        };
+
+        let trimmed_start = start + (slice.len() - slice.trim_start().len());
+        let trimmed_end = start + slice.trim_end().len();
+        if trimmed_start >= trimmed_end {
+            let line = self.line_of(start);
+            return (line, line);
+        }
+        (self.line_of(trimmed_start), self.line_of(trimmed_end - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_of() {
+        let index = LineIndex::new("one\ntwo\n\nfour");
+        assert_eq!(index.line_of(0), 1);
+        assert_eq!(index.line_of(3), 1); // the newline ends line 1
+        assert_eq!(index.line_of(4), 2);
+        assert_eq!(index.line_of(8), 3);
+        assert_eq!(index.line_of(9), 4);
+    }
+
+    #[test]
+    fn test_line_range_ignores_surrounding_whitespace() {
+        let text = "one\ntwo\n\nfour\nfive\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_range((0, text.len())), (1, 5));
+        // "\n\nfour\n" covers only line 4 once trimmed
+        assert_eq!(index.line_range((7, 14)), (4, 4));
+        assert_eq!(index.line_range((8, 9)), (3, 3));
+    }
+
+    #[test]
+    fn test_line_range_out_of_bounds() {
+        let index = LineIndex::new("é\nb");
+        assert_eq!(index.line_range((0, 100)), (1, 2));
+        // Not on a char boundary
+        assert_eq!(index.line_range((1, 4)), (1, 2));
+    }
+}
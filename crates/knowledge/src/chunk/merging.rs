@@ -2,7 +2,8 @@
 
 use super::{Chunk, ChunkConfig};
 
-/// Merge consecutive small chunks to reach target size.
+/// Merge consecutive small chunks to reach target size, split oversized
+/// ones and overlap neighbours, as `config` enables.
 pub fn post_process_chunks(chunks: Vec<Chunk>, config: &ChunkConfig) -> Vec<Chunk> {
     if chunks.is_empty() {
         return chunks;
@@ -15,7 +16,8 @@ pub fn post_process_chunks(chunks: Vec<Chunk>, config: &ChunkConfig) -> Vec<Chun
         let mut current = chunks[i].clone();
 
         // Skip chunks that are too small (unless it's the last chunk)
-        if current.text.len() < config.min_chunk_size && i < chunks.len() - 1 {
+        if config.merge_small && current.text.len() < config.min_chunk_size && i < chunks.len() - 1
+        {
             i += 1;
             continue;
         }
@@ -29,7 +31,7 @@ pub fn post_process_chunks(chunks: Vec<Chunk>, config: &ChunkConfig) -> Vec<Chun
         }
 
         // Try to merge with next chunk if both are small
-        if i + 1 < chunks.len() {
+        if config.merge_small && i + 1 < chunks.len() {
             let next = &chunks[i + 1];
             if should_merge(&current, next, config) {
                 current = merge_two_chunks(current, next.clone());
@@ -43,6 +45,10 @@ pub fn post_process_chunks(chunks: Vec<Chunk>, config: &ChunkConfig) -> Vec<Chun
         i += 1;
     }
 
+    if config.inject_overlap && config.overlap > 0 {
+        inject_overlap(&mut processed, config.overlap);
+    }
+
     // Update positions
     for (pos, chunk) in processed.iter_mut().enumerate() {
         chunk.position = pos as u32;
@@ -83,17 +89,22 @@ fn split_oversized(chunk: Chunk, config: &ChunkConfig) -> Vec<Chunk> {
     let mut position = chunk.position;
 
     while start < text.len() {
-        let mut end = (start + config.target_chunk_size).min(text.len());
-        
-        // Find valid UTF-8 boundary
-        while end > start && !text.is_char_boundary(end) {
-            end -= 1;
-        }
+        let mut end = super::piece_end(text, start, config.target_chunk_size);
         
-        // Try to break at word boundary
+        // Try to break at a sentence end when snapping, else at a word
+        // boundary (past `start`, so the loop moves on)
         if end < text.len() {
-            if let Some(last_space) = text[start..end].rfind(|c: char| c.is_whitespace()) {
-                end = start + last_space;
+            let window = &text[start..end];
+            let sentence = if config.snap_to_sentences {
+                sentence_end(window)
+            } else {
+                None
+            };
+            let boundary = sentence
+                .or_else(|| window.rfind(|c: char| c.is_whitespace()))
+                .filter(|&i| i > 0);
+            if let Some(boundary) = boundary {
+                end = start + boundary;
             }
         }
 
@@ -118,6 +129,55 @@ fn split_oversized(chunk: Chunk, config: &ChunkConfig) -> Vec<Chunk> {
     result
 }
 
+/// End of the last sentence in `text`: just past a `.`, `!` or `?` that is
+/// followed by whitespace.
+fn sentence_end(text: &str) -> Option<usize> {
+    text.char_indices()
+        .zip(text.chars().skip(1))
+        .filter(|((_, c), next)| matches!(c, '.' | '!' | '?') && next.is_whitespace())
+        .map(|((i, c), _)| i + c.len_utf8())
+        .last()
+}
+
+/// Start each chunk with the end of the previous one: at most `overlap`
+/// bytes, from the first word that fits. Chunks of the fallback splitter
+/// already overlap and are left alone.
+fn inject_overlap(chunks: &mut [Chunk], overlap: usize) {
+    // Back to front, so each chunk takes the text of the previous one as
+    // split
+    for i in (1..chunks.len()).rev() {
+        if chunks[i].metadata.splitter_used == "fallback" {
+            continue;
+        }
+
+        let previous = &chunks[i - 1].text;
+        let mut start = previous.len().saturating_sub(overlap);
+        while !previous.is_char_boundary(start) {
+            start += 1;
+        }
+        if start > 0 {
+            start = previous[start..]
+                .find(|c: char| c.is_whitespace())
+                .map_or(previous.len(), |space| start + space);
+        }
+        let tail = previous[start..].trim().to_string();
+        if tail.is_empty() {
+            continue;
+        }
+
+        let previous_end = chunks[i - 1].metadata.byte_range.1;
+        let chunk = &mut chunks[i];
+        chunk.text = format!("{}\n{}", tail, chunk.text);
+        chunk.metadata.byte_range.0 = chunk
+            .metadata
+            .byte_range
+            .0
+            .min(previous_end.saturating_sub(tail.len()));
+        chunk.metadata.char_count = chunk.text.chars().count();
+        chunk.metadata.hash = super::metadata::calculate_hash(&chunk.text);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +243,73 @@ mod tests {
         // Should split into multiple chunks
         assert!(processed.len() > 1);
     }
+
+    #[test]
+    fn test_merge_disabled() {
+        let config = ChunkConfig {
+            min_chunk_size: 50,
+            merge_small: false,
+            ..Default::default()
+        };
+        let chunks = vec![
+            create_test_chunk("Tiny", 0),
+            create_test_chunk("Short text", 1),
+            create_test_chunk("Another short", 2),
+        ];
+
+        let processed = post_process_chunks(chunks, &config);
+
+        // Small chunks are kept as they are
+        let texts: Vec<&str> = processed.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["Tiny", "Short text", "Another short"]);
+    }
+
+    #[test]
+    fn test_snap_to_sentences() {
+        let config = ChunkConfig {
+            target_chunk_size: 60,
+            max_chunk_size: 80,
+            snap_to_sentences: true,
+            ..Default::default()
+        };
+        let text = "The cache holds entries. It evicts the oldest one first when full. \
+                    Sizes are in bytes.";
+        let chunks = vec![create_test_chunk(text, 0)];
+
+        let processed = post_process_chunks(chunks, &config);
+
+        let texts: Vec<&str> = processed.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "The cache holds entries.",
+                "It evicts the oldest one first when full.",
+                "Sizes are in bytes."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inject_overlap() {
+        let config = ChunkConfig {
+            min_chunk_size: 0,
+            overlap: 12,
+            merge_small: false,
+            inject_overlap: true,
+            ..Default::default()
+        };
+        let mut second = create_test_chunk("Second chunk.", 1);
+        second.metadata.byte_range = (21, 34);
+        let chunks = vec![create_test_chunk("The first chunk ends here.", 0), second];
+
+        let processed = post_process_chunks(chunks, &config);
+
+        assert_eq!(processed[0].text, "The first chunk ends here.");
+        assert_eq!(processed[1].text, "ends here.\nSecond chunk.");
+        assert_eq!(processed[1].metadata.byte_range, (16, 34));
+        assert_eq!(
+            processed[1].metadata.hash,
+            crate::chunk::metadata::calculate_hash(&processed[1].text)
+        );
+    }
 }
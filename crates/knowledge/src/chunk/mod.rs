@@ -7,13 +7,19 @@
 //! - Generates rich metadata
 
 mod detection;
+mod lines;
 mod merging;
 mod metadata;
 mod pipeline;
 pub mod splitters;
+pub mod symbols;
+pub mod titles;
 
-pub use detection::{ContentType, Language};
+pub use detection::{detect_content_type, ContentType, Language};
+pub use lines::LineIndex;
 pub use pipeline::{ChunkConfig, ChunkPipeline};
+pub use symbols::{extract_symbols, Symbol, SymbolKind};
+pub use titles::{chunk_sections, chunk_titles};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -106,3 +112,21 @@ impl Chunk {
         }
     }
 }
+
+/// End of a piece of `text` that starts at `start` and holds at most
+/// `max_len` bytes, on a character boundary. The piece always holds at least
+/// one character, so splitting loops move on even when `max_len` is shorter
+/// than the character at `start`.
+pub(crate) fn piece_end(text: &str, start: usize, max_len: usize) -> usize {
+    let mut end = (start + max_len).min(text.len());
+    while end > start && !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == start {
+        end = text[start..]
+            .chars()
+            .next()
+            .map_or(text.len(), |c| start + c.len_utf8());
+    }
+    end
+}
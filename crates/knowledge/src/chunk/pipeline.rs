@@ -2,6 +2,7 @@
 
 use super::{
     detection::{detect_content_type, ContentType},
+    lines::LineIndex,
     merging::post_process_chunks,
     splitters::{ChunkSplitter, CodeSplitter, FallbackSplitter, TextSplitter},
     Chunk,
@@ -29,6 +30,17 @@ pub struct ChunkConfig {
     
     /// Preserve code blocks in markdown
     pub preserve_code_blocks: bool,
+
+    /// Merge consecutive small chunks, dropping those under `min_chunk_size`
+    pub merge_small: bool,
+
+    /// Split oversized chunks at the end of a sentence rather than at any
+    /// whitespace
+    pub snap_to_sentences: bool,
+
+    /// Start each chunk with the end of the previous one, up to `overlap`
+    /// bytes (the fallback splitter overlaps chunks itself)
+    pub inject_overlap: bool,
 }
 
 impl Default for ChunkConfig {
@@ -40,10 +52,28 @@ impl Default for ChunkConfig {
             overlap: 200,
             respect_semantics: true,
             preserve_code_blocks: true,
+            merge_small: true,
+            snap_to_sentences: false,
+            inject_overlap: false,
         }
     }
 }
 
+impl ChunkConfig {
+    /// Names of the post-processing steps this configuration applies, as
+    /// recorded in chunk metadata.
+    pub fn post_processing_steps(&self) -> Vec<&'static str> {
+        [
+            (self.merge_small, "merge_small"),
+            (self.snap_to_sentences, "snap_to_sentences"),
+            (self.inject_overlap, "inject_overlap"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, step)| enabled.then_some(step))
+        .collect()
+    }
+}
+
 /// Hybrid chunking pipeline.
 pub struct ChunkPipeline {
     config: ChunkConfig,
@@ -78,7 +108,17 @@ impl ChunkPipeline {
         let chunks = splitter.split(source_id, text, &self.config)?;
 
         // 4. Post-process and merge
-        let processed = post_process_chunks(chunks, &self.config);
+        let mut processed = post_process_chunks(chunks, &self.config);
+
+        // 5. Map byte ranges to line ranges and record the post-processing
+        let lines = LineIndex::new(text);
+        let steps = serde_json::json!(self.config.post_processing_steps());
+        for chunk in &mut processed {
+            chunk.metadata.line_range = Some(lines.line_range(chunk.metadata.byte_range));
+            if let Some(custom) = chunk.metadata.custom.as_object_mut() {
+                custom.insert("post_processing".to_string(), steps.clone());
+            }
+        }
 
         tracing::info!(
             "Chunking complete: {} chunks created from {} bytes",
@@ -164,6 +204,61 @@ fn main() {}
         assert!(!chunks.is_empty());
     }
 
+    #[test]
+    fn test_pipeline_line_ranges() {
+        let pipeline = ChunkPipeline::new(ChunkConfig {
+            target_chunk_size: 200,
+            max_chunk_size: 400,
+            min_chunk_size: 20,
+            overlap: 0,
+            respect_semantics: true,
+            preserve_code_blocks: true,
+            ..Default::default()
+        });
+        let text: String = (1..=60)
+            .map(|i| format!("Line {} of the document.\n", i))
+            .collect();
+        let lines: Vec<&str> = text.lines().collect();
+
+        let chunks = pipeline.process("test-source", &text, None).unwrap();
+        assert!(chunks.len() > 1);
+
+        for chunk in &chunks {
+            let (start, end) = chunk.metadata.line_range.unwrap();
+            assert!(start <= end && end <= lines.len());
+            let first_line = chunk.text.lines().next().unwrap();
+            let last_line = chunk.text.lines().last().unwrap();
+            assert!(lines[start - 1].ends_with(first_line), "{:?}", chunk.text);
+            assert!(lines[end - 1].starts_with(last_line), "{:?}", chunk.text);
+        }
+        assert_eq!(chunks[0].metadata.line_range.unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_pipeline_records_post_processing() {
+        let text = "This is a test document. ".repeat(100);
+
+        let chunks = ChunkPipeline::new(ChunkConfig::default())
+            .process("test-source", &text, None)
+            .unwrap();
+        assert_eq!(
+            chunks[0].metadata.custom["post_processing"],
+            serde_json::json!(["merge_small"])
+        );
+
+        let chunks = ChunkPipeline::new(ChunkConfig {
+            merge_small: false,
+            snap_to_sentences: true,
+            ..Default::default()
+        })
+        .process("test-source", &text, None)
+        .unwrap();
+        assert_eq!(
+            chunks[0].metadata.custom["post_processing"],
+            serde_json::json!(["snap_to_sentences"])
+        );
+    }
+
     #[test]
     fn test_pipeline_utf8_safety() {
         let pipeline = ChunkPipeline::new(ChunkConfig::default());
@@ -194,6 +289,7 @@ fn main() {}
             overlap: 100,
             respect_semantics: true,
             preserve_code_blocks: true,
+            ..Default::default()
         });
 
         let text = "This is a sentence. ".repeat(1000);
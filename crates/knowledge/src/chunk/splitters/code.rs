@@ -135,12 +135,7 @@ fn split_large_node(
     let mut start = 0;
 
     while start < text.len() {
-        let mut end = (start + config.target_chunk_size).min(text.len());
-
-        // Find valid UTF-8 boundary
-        while end > start && !text.is_char_boundary(end) {
-            end -= 1;
-        }
+        let mut end = crate::chunk::piece_end(text, start, config.target_chunk_size);
 
         // Try to break at line boundary
         if end < text.len() {
@@ -220,6 +215,29 @@ fn another_function() {
         }
     }
 
+    #[test]
+    fn test_split_large_node_multibyte() {
+        // Pieces smaller than some of the characters still move forward
+        let code = "let nome = \"ação 🎮\";\n// 🚀🚀 é\n";
+        for target_chunk_size in [1, 2, 3, 5, 8] {
+            let config = ChunkConfig {
+                target_chunk_size,
+                ..Default::default()
+            };
+            let chunks =
+                split_large_node("test-source", code, 0, &config, &Language::Rust).unwrap();
+
+            // Pieces are trimmed, so compare without whitespace
+            let visible = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+            let joined: String = chunks.iter().map(|c| visible(&c.text)).collect();
+            assert_eq!(joined, visible(code));
+            for chunk in &chunks {
+                let (start, end) = chunk.metadata.byte_range;
+                assert!(code.is_char_boundary(start) && code.is_char_boundary(end));
+            }
+        }
+    }
+
     #[test]
     fn test_unsupported_language_fallback() {
         let splitter = CodeSplitter::new(Language::Unknown);
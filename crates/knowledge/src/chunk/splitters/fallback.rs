@@ -15,32 +15,32 @@ impl ChunkSplitter for FallbackSplitter {
 
         while start < text.len() {
             // Find end position respecting Unicode grapheme boundaries
-            let mut end = (start + config.target_chunk_size).min(text.len());
+            let mut end = crate::chunk::piece_end(text, start, config.target_chunk_size);
 
-            // Ensure we're at a grapheme boundary
-            while end > start && !text.is_char_boundary(end) {
-                end -= 1;
-            }
-
-            // Try to break at word boundary for better semantics
+            // Try to break at word boundary for better semantics; a space at
+            // `start` would leave nothing to chunk
             if end < text.len() {
-                if let Some(last_space) = text[start..end].rfind(|c: char| c.is_whitespace()) {
+                if let Some(last_space) = text[start..end]
+                    .rfind(|c: char| c.is_whitespace())
+                    .filter(|&i| i > 0)
+                {
                     end = start + last_space;
                 }
             }
 
             let chunk_text = text[start..end].trim().to_string();
 
-            // Skip empty chunks
+            // Skip pieces that are only whitespace
             if chunk_text.is_empty() {
-                break;
+                start = end;
+                continue;
             }
 
             // Skip chunks that are too small (unless it's the last chunk)
             if chunk_text.len() < config.min_chunk_size && end < text.len() {
                 // Try to extend to next word boundary
                 if let Some(next_space) = text[end..].find(|c: char| c.is_whitespace()) {
-                    end = end + next_space;
+                    end += next_space;
                 } else {
                     end = text.len();
                 }
@@ -53,7 +53,6 @@ impl ChunkSplitter for FallbackSplitter {
                         (start, end),
                     );
                     chunks.push(chunk);
-                    position += 1;
                 }
                 break;
             }
@@ -144,13 +143,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fallback_splitter_piece_starting_with_space() {
+        let splitter = FallbackSplitter;
+        let config = ChunkConfig {
+            target_chunk_size: 10,
+            overlap: 0,
+            min_chunk_size: 1,
+            ..Default::default()
+        };
+
+        // The second piece starts at the space; breaking there would leave
+        // it empty and drop the rest of the text
+        let text = "0123456789 abcdefghijklmnop";
+        let chunks = splitter.split("test-source", text, &config).unwrap();
+        assert_eq!(chunks.last().unwrap().text, "jklmnop");
+
+        // A target shorter than an emoji still makes progress
+        let config = ChunkConfig {
+            target_chunk_size: 2,
+            ..config
+        };
+        let chunks = splitter.split("test-source", "🎮🎮 çã", &config).unwrap();
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["🎮", "🎮", "ç", "ã"]);
+    }
+
     #[test]
     fn test_fallback_splitter_with_overlap() {
         let splitter = FallbackSplitter;
-        let mut config = ChunkConfig::default();
-        config.target_chunk_size = 100;
-        config.overlap = 20;
-        config.min_chunk_size = 10; // Lower min size for test
+        let config = ChunkConfig {
+            target_chunk_size: 100,
+            overlap: 20,
+            min_chunk_size: 10, // Lower min size for test
+            ..Default::default()
+        };
 
         let text = "word ".repeat(200);
         let chunks = splitter.split("test-source", &text, &config).unwrap();
@@ -161,11 +188,13 @@ mod tests {
     #[test]
     fn test_fallback_splitter_min_size() {
         let splitter = FallbackSplitter;
-        let mut config = ChunkConfig::default();
-        config.min_chunk_size = 50;
+        let config = ChunkConfig {
+            min_chunk_size: 50,
+            ..Default::default()
+        };
 
         let text = "Short. ";
-        let chunks = splitter.split("test-source", &text, &config).unwrap();
+        let chunks = splitter.split("test-source", text, &config).unwrap();
         
         // Should be empty or extended to meet min size
         for chunk in &chunks {
@@ -12,28 +12,25 @@ impl ChunkSplitter for TextSplitter {
         // Use text-splitter crate for semantic splitting
         let splitter = ExternalTextSplitter::new(config.target_chunk_size);
         
-        let raw_chunks: Vec<&str> = splitter.chunks(text).collect();
+        let raw_chunks: Vec<(usize, &str)> = splitter.chunk_indices(text).collect();
 
         let mut chunks = Vec::new();
-        let mut byte_offset = 0;
 
-        for (position, chunk_text) in raw_chunks.iter().enumerate() {
+        for (position, (byte_offset, chunk_text)) in raw_chunks.iter().enumerate() {
             if chunk_text.trim().is_empty() {
                 continue;
             }
 
-            let chunk_len = chunk_text.len();
             let chunk = Chunk::new(
                 source_id.to_string(),
                 position as u32,
                 chunk_text.to_string(),
-                (byte_offset, byte_offset + chunk_len),
+                (*byte_offset, byte_offset + chunk_text.len()),
                 ContentType::Text,
                 "text-splitter".to_string(),
             );
 
             chunks.push(chunk);
-            byte_offset += chunk_len;
         }
 
         tracing::debug!(
@@ -81,9 +78,11 @@ mod tests {
     #[test]
     fn test_text_splitter_with_overlap() {
         let splitter = TextSplitter;
-        let mut config = ChunkConfig::default();
-        config.target_chunk_size = 100;
-        config.overlap = 20;
+        let config = ChunkConfig {
+            target_chunk_size: 100,
+            overlap: 20,
+            ..Default::default()
+        };
 
         let text = "a".repeat(500);
         let chunks = splitter.split("test-source", &text, &config).unwrap();
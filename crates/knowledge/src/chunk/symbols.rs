@@ -0,0 +1,254 @@
+//! Symbol extraction using tree-sitter.
+//!
+//! Enumerates functions and methods in a source file, with their line
+//! ranges and signatures, for workflows that operate per symbol.
+
+use crate::chunk::detection::Language;
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+/// Kind of extracted symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    /// Free function
+    Function,
+
+    /// Function defined inside an impl block or class
+    Method,
+}
+
+/// A function or method found in a source file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    /// Symbol name
+    pub name: String,
+
+    /// Function or method
+    pub kind: SymbolKind,
+
+    /// Enclosing type or class name, for methods
+    pub parent: Option<String>,
+
+    /// First line of the definition (signature)
+    pub signature: String,
+
+    /// Line range in the file (1-based, inclusive)
+    pub line_range: (usize, usize),
+
+    /// Byte range in the file
+    pub byte_range: (usize, usize),
+}
+
+impl Symbol {
+    /// Qualified name (e.g., "Parser::parse" or "parse").
+    pub fn qualified_name(&self) -> String {
+        match self.parent {
+            Some(ref parent) => format!("{}::{}", parent, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Extract functions and methods from source text.
+///
+/// Returns an error if the language has no tree-sitter support.
+/// Test modules (`mod tests` in Rust) are skipped.
+pub fn extract_symbols(language: &Language, text: &str) -> AppResult<Vec<Symbol>> {
+    let ts_language = language.tree_sitter_language().ok_or_else(|| {
+        AppError::Knowledge(format!(
+            "Symbol extraction not supported for {:?}",
+            language
+        ))
+    })?;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&ts_language)
+        .map_err(|e| AppError::Other(format!("Failed to set parser language: {}", e)))?;
+
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| AppError::Other("Failed to parse code".into()))?;
+
+    let mut symbols = Vec::new();
+    collect_symbols(tree.root_node(), text, language, None, &mut symbols);
+
+    tracing::debug!(
+        "Extracted {} symbols ({:?}) from {} bytes",
+        symbols.len(),
+        language,
+        text.len()
+    );
+
+    Ok(symbols)
+}
+
+/// Recursively collect function-like nodes.
+fn collect_symbols(
+    node: Node,
+    text: &str,
+    language: &Language,
+    parent: Option<&str>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+
+    for child in node.children(&mut cursor) {
+        let kind = child.kind();
+
+        if is_function_node(language, kind) {
+            if let Some(name) = field_text(child, "name", text) {
+                let is_method = parent.is_some() || kind == "method_declaration";
+                let parent_name = parent.map(str::to_string).or_else(|| {
+                    // Go methods declare their receiver instead of nesting
+                    child
+                        .child_by_field_name("receiver")
+                        .and_then(|r| go_receiver_type(r, text))
+                });
+
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    kind: if is_method {
+                        SymbolKind::Method
+                    } else {
+                        SymbolKind::Function
+                    },
+                    parent: parent_name,
+                    signature: signature(&text[child.start_byte()..child.end_byte()]),
+                    line_range: (child.start_position().row + 1, child.end_position().row + 1),
+                    byte_range: (child.start_byte(), child.end_byte()),
+                });
+            }
+            continue;
+        }
+
+        match kind {
+            // Rust: skip `mod tests`, descend into other modules
+            "mod_item" if field_text(child, "name", text) != Some("tests") => {
+                collect_symbols(child, text, language, parent, symbols);
+            }
+            "impl_item" | "trait_item" => {
+                let type_name =
+                    field_text(child, "type", text).or_else(|| field_text(child, "name", text));
+                collect_symbols(child, text, language, type_name, symbols);
+            }
+            "class_declaration" | "class_definition" | "class" => {
+                let class_name = field_text(child, "name", text);
+                collect_symbols(child, text, language, class_name, symbols);
+            }
+            // Containers whose children may hold definitions
+            "declaration_list"
+            | "block"
+            | "class_body"
+            | "export_statement"
+            | "decorated_definition"
+            | "source_file"
+            | "program"
+            | "module" => {
+                collect_symbols(child, text, language, parent, symbols);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Node kinds that define functions for a language.
+fn is_function_node(language: &Language, kind: &str) -> bool {
+    match language {
+        Language::Rust => kind == "function_item" || kind == "function_signature_item",
+        Language::Python => kind == "function_definition",
+        Language::JavaScript | Language::TypeScript => matches!(
+            kind,
+            "function_declaration" | "generator_function_declaration" | "method_definition"
+        ),
+        Language::Go => kind == "function_declaration" || kind == "method_declaration",
+        _ => false,
+    }
+}
+
+/// Text of a named field on a node.
+fn field_text<'a>(node: Node, field: &str, text: &'a str) -> Option<&'a str> {
+    node.child_by_field_name(field)
+        .and_then(|n| text.get(n.start_byte()..n.end_byte()))
+}
+
+/// Extract the receiver type from a Go method receiver list (e.g., "(p *Parser)").
+fn go_receiver_type(receiver: Node, text: &str) -> Option<String> {
+    let raw = text.get(receiver.start_byte()..receiver.end_byte())?;
+    let inner = raw.trim_start_matches('(').trim_end_matches(')');
+    inner
+        .split_whitespace()
+        .last()
+        .map(|t| t.trim_start_matches('*').to_string())
+}
+
+/// First line of a definition, without a trailing opening brace.
+fn signature(node_text: &str) -> String {
+    let first_line = node_text.lines().next().unwrap_or_default().trim();
+    first_line.trim_end_matches('{').trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let code = r#"
+pub fn parse(input: &str) -> Vec<u8> {
+    input.bytes().collect()
+}
+
+struct Parser;
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse() {}
+}
+"#;
+
+        let symbols = extract_symbols(&Language::Rust, code).unwrap();
+        assert_eq!(symbols.len(), 2);
+
+        assert_eq!(symbols[0].name, "parse");
+        assert_eq!(symbols[0].kind, SymbolKind::Function);
+        assert_eq!(symbols[0].signature, "pub fn parse(input: &str) -> Vec<u8>");
+        assert_eq!(symbols[0].line_range, (2, 4));
+
+        assert_eq!(symbols[1].qualified_name(), "Parser::new");
+        assert_eq!(symbols[1].kind, SymbolKind::Method);
+    }
+
+    #[test]
+    fn test_extract_python_symbols() {
+        let code = "def top():\n    pass\n\nclass Greeter:\n    def greet(self, name):\n        return name\n";
+
+        let symbols = extract_symbols(&Language::Python, code).unwrap();
+        let names: Vec<String> = symbols.iter().map(|s| s.qualified_name()).collect();
+        assert_eq!(names, vec!["top", "Greeter::greet"]);
+    }
+
+    #[test]
+    fn test_extract_go_method_receiver() {
+        let code = "package main\n\nfunc (p *Parser) Parse() error {\n\treturn nil\n}\n";
+
+        let symbols = extract_symbols(&Language::Go, code).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].qualified_name(), "Parser::Parse");
+        assert_eq!(symbols[0].kind, SymbolKind::Method);
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(extract_symbols(&Language::Ruby, "def x; end").is_err());
+    }
+}
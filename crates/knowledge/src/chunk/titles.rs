@@ -0,0 +1,246 @@
+//! Chunk titles: where in its document a chunk sits.
+//!
+//! A title is the file name followed by the heading path at the start of
+//! the chunk (`guide.md > Chunking > Defaults`), or, for code, by the
+//! functions and methods the chunk defines (`pipeline.rs: ChunkPipeline::process`).
+//! Short queries naming a section or symbol match titles far better than
+//! the body text, so bases with a `title_weight` embed them separately.
+//!
+//! Chunks are placed by their line ranges, so titles are read from the
+//! original document even when the chunks were cut from cleaned text.
+//!
+//! Sections are the part of a title a chunk starts in: the heading path
+//! (`Chunking > Defaults`) or the innermost function or method around its
+//! first line. Overlapping chunks often start mid-section, so bases that
+//! overlap them keep the section to show beside the text in prompts.
+
+use crate::chunk::{extract_symbols, Chunk, ContentType, Symbol};
+
+/// Symbol names listed in a code chunk's title, at most.
+const MAX_TITLE_SYMBOLS: usize = 5;
+
+/// A markdown heading: 1-based line, level and text.
+struct Heading {
+    line: usize,
+    level: usize,
+    text: String,
+}
+
+/// Title of every chunk of `document`, in order. Chunks without a line
+/// range in `document` are titled with the file name alone.
+pub fn chunk_titles(document: &str, file_name: &str, chunks: &[Chunk]) -> Vec<String> {
+    let headings = markdown_headings(document);
+    let mut symbols: Option<Vec<Symbol>> = None;
+
+    chunks
+        .iter()
+        .map(|chunk| {
+            let Some((start, end)) = chunk.metadata.line_range else {
+                return file_name.to_string();
+            };
+            if let ContentType::Code { language } = &chunk.metadata.content_type {
+                let symbols = symbols
+                    .get_or_insert_with(|| extract_symbols(language, document).unwrap_or_default());
+                let names: Vec<String> = symbols
+                    .iter()
+                    .filter(|s| s.line_range.0 <= end && s.line_range.1 >= start)
+                    .take(MAX_TITLE_SYMBOLS)
+                    .map(Symbol::qualified_name)
+                    .collect();
+                if !names.is_empty() {
+                    return format!("{}: {}", file_name, names.join(", "));
+                }
+            }
+            heading_title(&headings, file_name, start, end)
+        })
+        .collect()
+}
+
+/// Section every chunk of `document` starts in, in order: the heading path
+/// at its first line or, for code, the innermost function or method around
+/// it. `None` for chunks outside any section or without a line range.
+pub fn chunk_sections(document: &str, chunks: &[Chunk]) -> Vec<Option<String>> {
+    let headings = markdown_headings(document);
+    let mut symbols: Option<Vec<Symbol>> = None;
+
+    chunks
+        .iter()
+        .map(|chunk| {
+            let (start, _) = chunk.metadata.line_range?;
+            if let ContentType::Code { language } = &chunk.metadata.content_type {
+                let symbols = symbols
+                    .get_or_insert_with(|| extract_symbols(language, document).unwrap_or_default());
+                return symbols
+                    .iter()
+                    .filter(|s| s.line_range.0 <= start && start <= s.line_range.1)
+                    .max_by_key(|s| s.line_range.0)
+                    .map(Symbol::qualified_name);
+            }
+            let path: Vec<&str> = heading_path(&headings, start)
+                .iter()
+                .map(|h| h.text.as_str())
+                .collect();
+            (!path.is_empty()).then(|| path.join(" > "))
+        })
+        .collect()
+}
+
+/// Headings enclosing `line`, outermost first.
+fn heading_path(headings: &[Heading], line: usize) -> Vec<&Heading> {
+    let mut path: Vec<&Heading> = Vec::new();
+    for heading in headings.iter().take_while(|h| h.line <= line) {
+        path.retain(|h| h.level < heading.level);
+        path.push(heading);
+    }
+    path
+}
+
+/// `file > heading path at start`, followed by the headings inside the chunk.
+fn heading_title(headings: &[Heading], file_name: &str, start: usize, end: usize) -> String {
+    let path = heading_path(headings, start);
+    let inner: Vec<&str> = headings
+        .iter()
+        .filter(|h| start < h.line && h.line <= end)
+        .map(|h| h.text.as_str())
+        .collect();
+
+    let mut title = std::iter::once(file_name)
+        .chain(path.iter().map(|h| h.text.as_str()))
+        .collect::<Vec<_>>()
+        .join(" > ");
+    if !inner.is_empty() {
+        title.push_str(&format!(" ({})", inner.join(", ")));
+    }
+    title
+}
+
+/// ATX headings (`# Title`) outside fenced code blocks.
+fn markdown_headings(document: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    for (n, line) in document.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && !line.starts_with(' ') {
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let text = trimmed[level..].trim().trim_end_matches('#').trim();
+            if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') && !text.is_empty() {
+                headings.push(Heading {
+                    line: n + 1,
+                    level,
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+    headings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{ChunkConfig, ChunkPipeline};
+    use std::path::Path;
+
+    fn chunk_at(document: &str, section: &str, content_type: ContentType) -> Chunk {
+        let start = document.find(section).unwrap();
+        let mut chunk = Chunk::new(
+            "s".to_string(),
+            0,
+            section.to_string(),
+            (start, start + section.len()),
+            content_type,
+            "test".to_string(),
+        );
+        let lines = crate::chunk::LineIndex::new(document);
+        chunk.metadata.line_range = Some(lines.line_range(chunk.metadata.byte_range));
+        chunk
+    }
+
+    #[test]
+    fn test_markdown_titles_follow_heading_path() {
+        let document = "# Guide\n\nIntro.\n\n## Chunking\n\nHow text is split.\n\n\
+                        ```sh\n# not a heading\n```\n\n### Defaults\n\nChunkConfig defaults.\n\n\
+                        ## Embeddings\n\nVectors.\n";
+        let chunks = vec![
+            chunk_at(document, "Intro.", ContentType::Markdown),
+            chunk_at(document, "ChunkConfig defaults.", ContentType::Markdown),
+            chunk_at(
+                document,
+                "How text is split.\n\n```sh\n# not a heading\n```\n\n### Defaults\n\nChunkConfig defaults.\n\n## Embeddings",
+                ContentType::Markdown,
+            ),
+        ];
+
+        let titles = chunk_titles(document, "guide.md", &chunks);
+        assert_eq!(titles[0], "guide.md > Guide");
+        assert_eq!(titles[1], "guide.md > Guide > Chunking > Defaults");
+        assert_eq!(
+            titles[2],
+            "guide.md > Guide > Chunking (Defaults, Embeddings)"
+        );
+
+        let mut unplaced = chunks[0].clone();
+        unplaced.metadata.line_range = None;
+        assert_eq!(
+            chunk_titles(document, "guide.md", &[unplaced]),
+            ["guide.md"]
+        );
+    }
+
+    #[test]
+    fn test_sections_at_chunk_start() {
+        let document = "# Guide\n\nIntro.\n\n## Chunking\n\nHow text is split.\n\n\
+                        ### Defaults\n\nChunkConfig defaults.\n\n## Embeddings\n\nVectors.\n";
+        let chunks = vec![
+            chunk_at(document, "Intro.", ContentType::Markdown),
+            chunk_at(
+                document,
+                "split.\n\n### Defaults\n\nChunkConfig",
+                ContentType::Markdown,
+            ),
+            chunk_at(document, "Vectors.", ContentType::Markdown),
+        ];
+
+        let sections = chunk_sections(document, &chunks);
+        assert_eq!(sections[0].as_deref(), Some("Guide"));
+        assert_eq!(sections[1].as_deref(), Some("Guide > Chunking"));
+        assert_eq!(sections[2].as_deref(), Some("Guide > Embeddings"));
+
+        let code = "impl Parser {\n    pub fn parse(&self) {\n        let a = 1;\n        let b = 2;\n    }\n}\n";
+        let language = crate::chunk::Language::Rust;
+        let chunks = vec![
+            chunk_at(
+                code,
+                "let b = 2;",
+                ContentType::Code {
+                    language: language.clone(),
+                },
+            ),
+            chunk_at(code, "impl Parser", ContentType::Code { language }),
+        ];
+        assert_eq!(
+            chunk_sections(code, &chunks),
+            [Some("Parser::parse".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_code_titles_name_symbols() {
+        let code = "pub struct Parser;\n\nimpl Parser {\n    pub fn new() -> Self {\n        Parser\n    }\n}\n\npub fn parse(input: &str) -> Vec<u8> {\n    input.bytes().collect()\n}\n";
+        let pipeline = ChunkPipeline::new(ChunkConfig::default());
+        let chunks = pipeline
+            .process("s", code, Some(Path::new("parser.rs")))
+            .unwrap();
+
+        let titles = chunk_titles(code, "parser.rs", &chunks);
+        assert!(
+            titles
+                .iter()
+                .any(|t| t.starts_with("parser.rs: ") && t.contains("Parser::new")),
+            "{:?}",
+            titles
+        );
+    }
+}
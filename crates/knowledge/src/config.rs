@@ -1,7 +1,13 @@
 //! Knowledge base configuration management.
+//!
+//! Bases are looked up in the workspace first (`.guided/knowledge/<base>`)
+//! and then in the global location (`~/.guided/knowledge/<base>`, or
+//! `$GUIDED_HOME/knowledge/<base>`). New bases are created in the workspace
+//! unless a global scope is requested.
 
-use crate::types::KnowledgeBaseConfig;
+use crate::types::{KnowledgeBaseConfig, KnowledgeScope};
 use guided_core::{AppError, AppResult};
+use std::borrow::Cow;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -9,7 +15,12 @@ use std::path::{Path, PathBuf};
 ///
 /// Loads from `.guided/knowledge/<base>/config.yaml` if it exists,
 /// otherwise creates a default config with the provided base name.
+///
+/// Fails on names that are not valid base names, and on bases whose
+/// directory is shared with another base (see [`check_isolated`]).
 pub fn load_config(workspace: &Path, base_name: &str) -> AppResult<KnowledgeBaseConfig> {
+    validate_stored_name(base_name)?;
+    check_isolated(workspace, base_name)?;
     let config_path = get_config_path(workspace, base_name);
 
     if config_path.exists() {
@@ -24,8 +35,9 @@ pub fn load_config(workspace: &Path, base_name: &str) -> AppResult<KnowledgeBase
             ))
         })?;
 
-        // Ensure name matches
+        // Ensure name and scope match where the base was found
         config.name = base_name.to_string();
+        config.scope = resolve_scope(workspace, base_name);
 
         tracing::debug!("Loaded knowledge base config for '{}'", base_name);
         Ok(config)
@@ -33,6 +45,7 @@ pub fn load_config(workspace: &Path, base_name: &str) -> AppResult<KnowledgeBase
         // Create default config
         let config = KnowledgeBaseConfig {
             name: base_name.to_string(),
+            scope: resolve_scope(workspace, base_name),
             ..Default::default()
         };
 
@@ -46,6 +59,7 @@ pub fn load_config(workspace: &Path, base_name: &str) -> AppResult<KnowledgeBase
 
 /// Save knowledge base configuration.
 pub fn save_config(workspace: &Path, config: &KnowledgeBaseConfig) -> AppResult<()> {
+    validate_stored_name(&config.name)?;
     let config_path = get_config_path(workspace, &config.name);
 
     // Ensure directory exists
@@ -71,16 +85,228 @@ pub fn save_config(workspace: &Path, config: &KnowledgeBaseConfig) -> AppResult<
 
 /// Get the path to a base's config file.
 pub fn get_config_path(workspace: &Path, base_name: &str) -> PathBuf {
-    workspace
-        .join(".guided")
-        .join("knowledge")
-        .join(base_name)
-        .join("config.yaml")
+    get_base_dir(workspace, base_name).join("config.yaml")
+}
+
+/// Get the knowledge directory of the workspace.
+pub fn workspace_knowledge_dir(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("knowledge")
+}
+
+/// Get the global knowledge directory shared by all workspaces.
+///
+/// `$GUIDED_HOME/knowledge` if set, otherwise `~/.guided/knowledge`.
+/// Returns `None` when no home directory can be determined.
+pub fn global_knowledge_dir() -> Option<PathBuf> {
+    if let Some(home) = std::env::var_os("GUIDED_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(home).join("knowledge"));
+    }
+
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|v| !v.is_empty())
+        .map(|home| PathBuf::from(home).join(".guided").join("knowledge"))
+}
+
+/// Find where an existing base is stored.
+///
+/// Workspace bases shadow global ones. Bases that exist in neither place
+/// resolve to the workspace.
+pub fn resolve_scope(workspace: &Path, base_name: &str) -> KnowledgeScope {
+    resolve_scope_in(workspace, global_knowledge_dir().as_deref(), base_name)
 }
 
-/// Get the base directory for a knowledge base.
+fn resolve_scope_in(
+    workspace: &Path,
+    global_dir: Option<&Path>,
+    base_name: &str,
+) -> KnowledgeScope {
+    let name = dir_name(base_name);
+    if workspace_knowledge_dir(workspace)
+        .join(name.as_ref())
+        .is_dir()
+    {
+        return KnowledgeScope::Workspace;
+    }
+
+    match global_dir {
+        Some(dir) if dir.join(name.as_ref()).is_dir() => KnowledgeScope::Global,
+        _ => KnowledgeScope::Workspace,
+    }
+}
+
+/// Get the base directory for a knowledge base, following the resolution order.
+///
+/// Always inside a knowledge directory: characters a base name cannot hold
+/// (see [`validate_base_name`]) are replaced, so `../../etc` cannot point
+/// elsewhere. Commands reject such names before they get here.
 pub fn get_base_dir(workspace: &Path, base_name: &str) -> PathBuf {
-    workspace.join(".guided").join("knowledge").join(base_name)
+    get_base_dir_in(workspace, global_knowledge_dir().as_deref(), base_name)
+}
+
+fn get_base_dir_in(workspace: &Path, global_dir: Option<&Path>, base_name: &str) -> PathBuf {
+    let name = dir_name(base_name);
+    match (
+        resolve_scope_in(workspace, global_dir, base_name),
+        global_dir,
+    ) {
+        (KnowledgeScope::Global, Some(dir)) => dir.join(name.as_ref()),
+        _ => workspace_knowledge_dir(workspace).join(name.as_ref()),
+    }
+}
+
+/// The directory name of a base: the name itself if valid, otherwise the
+/// name with every disallowed character (and a leading `.`) replaced by `_`.
+fn dir_name(base_name: &str) -> Cow<'_, str> {
+    if validate_stored_name(base_name).is_ok() {
+        return Cow::Borrowed(base_name);
+    }
+    let name: String = base_name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let allowed =
+                c.is_ascii_alphanumeric() || matches!(c, '-' | '_') || (c == '.' && i > 0);
+            if allowed {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        Cow::Borrowed("_")
+    } else {
+        Cow::Owned(name)
+    }
+}
+
+/// Check that no other base shares the directory, and so the index, of
+/// `base_name`.
+///
+/// Two bases share a directory when one is a symlink to the other (or to
+/// the same target). Learning into either would then overwrite the other's
+/// index and sources.
+pub fn check_isolated(workspace: &Path, base_name: &str) -> AppResult<()> {
+    let base_dir = get_base_dir(workspace, base_name);
+    match base_dir.parent() {
+        Some(knowledge_dir) => check_isolated_in(knowledge_dir, base_name, false),
+        None => Ok(()),
+    }
+}
+
+/// Check the bases of `knowledge_dir` against `base_name`. With
+/// `ignore_case`, bases whose names differ only by case also count as
+/// sharing a directory, as they do on case-insensitive filesystems.
+fn check_isolated_in(knowledge_dir: &Path, base_name: &str, ignore_case: bool) -> AppResult<()> {
+    let name = dir_name(base_name);
+    let real_dir = fs::canonicalize(knowledge_dir.join(name.as_ref())).ok();
+    let Ok(entries) = fs::read_dir(knowledge_dir) else {
+        return Ok(());
+    };
+
+    for entry in entries.flatten() {
+        let other = entry.file_name().to_string_lossy().into_owned();
+        if other == name.as_ref() || !entry.path().is_dir() {
+            continue;
+        }
+        let shared = (ignore_case && other.eq_ignore_ascii_case(&name))
+            || (real_dir.is_some() && fs::canonicalize(entry.path()).ok() == real_dir);
+        if shared {
+            return Err(AppError::Knowledge(format!(
+                "Knowledge bases '{}' and '{}' would share the index at {:?}; \
+                 give each base its own directory",
+                base_name,
+                other,
+                knowledge_dir.join(name.as_ref()).join("lance")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Create the directory of a base in the requested scope.
+///
+/// Existing bases are left where they are; asking for a global base whose
+/// name is already used in the workspace is an error, since the workspace
+/// base would shadow it.
+pub fn create_base_dir(
+    workspace: &Path,
+    base_name: &str,
+    scope: KnowledgeScope,
+) -> AppResult<PathBuf> {
+    create_base_dir_in(
+        workspace,
+        global_knowledge_dir().as_deref(),
+        base_name,
+        scope,
+    )
+}
+
+fn create_base_dir_in(
+    workspace: &Path,
+    global_dir: Option<&Path>,
+    base_name: &str,
+    scope: KnowledgeScope,
+) -> AppResult<PathBuf> {
+    validate_base_name(base_name)?;
+    let existing = resolve_scope_in(workspace, global_dir, base_name);
+    let exists = get_base_dir_in(workspace, global_dir, base_name).is_dir();
+
+    let dir = match scope {
+        KnowledgeScope::Workspace => {
+            if existing == KnowledgeScope::Global {
+                tracing::warn!(
+                    "Creating workspace knowledge base '{}'; it shadows the global base of the same name",
+                    base_name
+                );
+            }
+            workspace_knowledge_dir(workspace).join(base_name)
+        }
+        KnowledgeScope::Global => {
+            if exists && existing == KnowledgeScope::Workspace {
+                return Err(AppError::Knowledge(format!(
+                    "Knowledge base '{}' already exists in this workspace; \
+                     choose another name for the global base",
+                    base_name
+                )));
+            }
+            global_dir
+                .ok_or_else(|| {
+                    AppError::Knowledge(
+                        "Cannot locate the global knowledge directory; set HOME or GUIDED_HOME"
+                            .to_string(),
+                    )
+                })?
+                .join(base_name)
+        }
+    };
+
+    if let Some(knowledge_dir) = dir.parent() {
+        check_isolated_in(knowledge_dir, base_name, true)?;
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| {
+        AppError::Knowledge(format!(
+            "Failed to create knowledge base directory {:?}: {}",
+            dir, e
+        ))
+    })?;
+
+    Ok(dir)
+}
+
+/// Get the directory for downloaded embedding models used by a base.
+///
+/// Sits next to the knowledge directory the base lives in, so global bases
+/// share `~/.guided/models/` across workspaces.
+pub fn get_models_dir(workspace: &Path, base_name: &str) -> PathBuf {
+    let base_dir = get_base_dir(workspace, base_name);
+    base_dir
+        .parent()
+        .and_then(Path::parent)
+        .map(|root| root.join("models"))
+        .unwrap_or_else(|| workspace.join(".guided").join("models"))
 }
 
 /// Get the LanceDB index directory for a base.
@@ -93,16 +319,121 @@ pub fn get_sources_path(workspace: &Path, base_name: &str) -> PathBuf {
     get_base_dir(workspace, base_name).join("sources.jsonl")
 }
 
+/// Get the learn checkpoint path for a base (file list of an unfinished learn).
+pub fn get_checkpoint_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("learn-checkpoint.json")
+}
+
+/// Get the learn checkpoint progress path for a base (files already indexed).
+pub fn get_checkpoint_progress_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("learn-checkpoint.jsonl")
+}
+
+/// Get the answer cache directory for a base.
+pub fn get_answer_cache_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("answers")
+}
+
+/// Get the path of a base's last answer record (rated by `feedback --last`).
+pub fn get_last_answer_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("last-answer.json")
+}
+
+/// Get the feedback JSONL path for a base.
+pub fn get_feedback_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("feedback.jsonl")
+}
+
+/// Get the symbol index JSONL path for a base.
+pub fn get_symbols_path(workspace: &Path, base_name: &str) -> PathBuf {
+    get_base_dir(workspace, base_name).join("symbols.jsonl")
+}
+
 /// Get the stats JSON path for a base.
 pub fn get_stats_path(workspace: &Path, base_name: &str) -> PathBuf {
     get_base_dir(workspace, base_name).join("stats.json")
 }
 
+/// Check that `name` can name a new base directory.
+///
+/// Names use ASCII letters, digits, `-`, `_` and `.`, and do not start with
+/// a `.`.
+pub fn validate_base_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(AppError::Knowledge(format!(
+            "Invalid knowledge base name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Check the name of a base the crate reads or writes: a name users can
+/// give, or one with a leading `.`, which the crate keeps for its own
+/// throwaway bases (see [`crate::tune`]).
+fn validate_stored_name(name: &str) -> AppResult<()> {
+    validate_base_name(name.strip_prefix('.').unwrap_or(name)).map_err(|_| {
+        AppError::Knowledge(format!(
+            "Invalid knowledge base name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        ))
+    })
+}
+
+/// Check a namespace name and return it without surrounding slashes, so
+/// `docs/` and `docs` name the same namespace.
+///
+/// Names use ASCII letters, digits, `-`, `_`, `.` and `/` (`code/api`).
+pub fn normalize_namespace(namespace: &str) -> AppResult<String> {
+    let normalized = namespace.trim().trim_matches('/');
+    let valid = !normalized.is_empty()
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if !valid {
+        return Err(AppError::Knowledge(format!(
+            "Invalid namespace '{}': use letters, digits, '-', '_', '.' and '/'",
+            namespace
+        )));
+    }
+    Ok(normalized.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_normalize_namespace() {
+        assert_eq!(normalize_namespace("docs/").unwrap(), "docs");
+        assert_eq!(normalize_namespace("code/api").unwrap(), "code/api");
+        assert!(normalize_namespace("/").is_err());
+        assert!(normalize_namespace("it's").is_err());
+        assert!(normalize_namespace("two words").is_err());
+    }
+
+    #[test]
+    fn test_validate_base_name() {
+        assert!(validate_base_name("rust-docs_v2.1").is_ok());
+        assert!(validate_base_name("").is_err());
+        assert!(validate_base_name("..").is_err());
+        assert!(validate_base_name("../etc").is_err());
+        assert!(validate_base_name("a/b").is_err());
+        assert!(validate_base_name(".tune-docs-64-8").is_err());
+
+        // Throwaway bases of the crate are stored under a leading `.`
+        assert!(validate_stored_name(".tune-docs-64-8").is_ok());
+        assert!(validate_stored_name(".").is_err());
+        assert!(validate_stored_name("..").is_err());
+        assert!(validate_stored_name("../etc").is_err());
+    }
+
     #[test]
     fn test_load_default_config() {
         let temp = TempDir::new().unwrap();
@@ -116,9 +447,11 @@ mod tests {
     #[test]
     fn test_save_and_load_config() {
         let temp = TempDir::new().unwrap();
-        let mut config = KnowledgeBaseConfig::default();
-        config.name = "my-base".to_string();
-        config.chunk_size = 1024;
+        let config = KnowledgeBaseConfig {
+            name: "my-base".to_string(),
+            chunk_size: 1024,
+            ..Default::default()
+        };
 
         save_config(temp.path(), &config).unwrap();
 
@@ -126,4 +459,91 @@ mod tests {
         assert_eq!(loaded.name, "my-base");
         assert_eq!(loaded.chunk_size, 1024);
     }
+
+    #[test]
+    fn test_scope_resolution_order() {
+        let workspace = TempDir::new().unwrap();
+        let home = TempDir::new().unwrap();
+        let global = home.path().join("knowledge");
+        let local = workspace_knowledge_dir(workspace.path());
+
+        // Unknown bases resolve to the workspace
+        assert_eq!(
+            get_base_dir_in(workspace.path(), Some(&global), "docs"),
+            local.join("docs")
+        );
+
+        let dir = create_base_dir_in(
+            workspace.path(),
+            Some(&global),
+            "docs",
+            KnowledgeScope::Global,
+        )
+        .unwrap();
+        assert_eq!(dir, global.join("docs"));
+        assert_eq!(
+            resolve_scope_in(workspace.path(), Some(&global), "docs"),
+            KnowledgeScope::Global
+        );
+
+        // A workspace base with the same name shadows the global one
+        create_base_dir_in(
+            workspace.path(),
+            Some(&global),
+            "docs",
+            KnowledgeScope::Workspace,
+        )
+        .unwrap();
+        assert_eq!(
+            get_base_dir_in(workspace.path(), Some(&global), "docs"),
+            local.join("docs")
+        );
+        assert!(create_base_dir_in(
+            workspace.path(),
+            Some(&global),
+            "docs",
+            KnowledgeScope::Global
+        )
+        .is_err());
+
+        // Without a home directory only workspace bases are possible
+        assert!(create_base_dir_in(workspace.path(), None, "api", KnowledgeScope::Global).is_err());
+    }
+
+    #[test]
+    fn test_invalid_base_names_stay_inside_knowledge_dir() {
+        let temp = TempDir::new().unwrap();
+        let knowledge_dir = workspace_knowledge_dir(temp.path());
+
+        assert!(load_config(temp.path(), "../../etc").is_err());
+        assert!(
+            create_base_dir_in(temp.path(), None, "../docs", KnowledgeScope::Workspace).is_err()
+        );
+        assert_eq!(
+            get_index_path(temp.path(), "../../etc"),
+            knowledge_dir.join("_._.._etc").join("lance")
+        );
+        assert_eq!(get_base_dir(temp.path(), ""), knowledge_dir.join("_"));
+    }
+
+    #[test]
+    fn test_bases_sharing_a_directory_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        create_base_dir_in(temp.path(), None, "docs", KnowledgeScope::Workspace).unwrap();
+
+        // Names differing only by case share a directory on some filesystems
+        let err = create_base_dir_in(temp.path(), None, "Docs", KnowledgeScope::Workspace)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("would share the index"), "{}", err);
+
+        #[cfg(unix)]
+        {
+            let knowledge_dir = workspace_knowledge_dir(temp.path());
+            std::os::unix::fs::symlink(knowledge_dir.join("docs"), knowledge_dir.join("alias"))
+                .unwrap();
+            assert!(load_config(temp.path(), "alias").is_err());
+            assert!(load_config(temp.path(), "docs").is_err());
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! Confluence connector (REST API v1, Cloud and Data Center).
+
+use super::markdown::html_to_markdown;
+use super::{check_status, RemoteDocument, SourceConnector};
+use guided_core::{AppError, AppResult};
+use serde_json::Value;
+
+/// Pages requested per listing call.
+const PAGE_SIZE: usize = 50;
+
+/// Learns the pages of one or more Confluence spaces.
+pub struct ConfluenceConnector {
+    client: reqwest::Client,
+    base_url: String,
+    email: Option<String>,
+    token: String,
+    spaces: Vec<String>,
+}
+
+impl ConfluenceConnector {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: impl Into<String>,
+        email: Option<String>,
+        token: impl Into<String>,
+        spaces: Vec<String>,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            email,
+            token: token.into(),
+            spaces,
+        }
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> AppResult<Value> {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query);
+        let request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Confluence request failed: {}", e)))?;
+        check_status("Confluence", response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Invalid Confluence response: {}", e)))
+    }
+
+    /// Build a document from a content listing entry.
+    fn document(&self, result: &Value) -> Option<RemoteDocument> {
+        let id = result["id"].as_str()?.to_string();
+        let url = match result["_links"]["webui"].as_str() {
+            Some(webui) => format!("{}{}", self.base_url, webui),
+            None => format!("{}/pages/viewpage.action?pageId={}", self.base_url, id),
+        };
+        Some(RemoteDocument {
+            title: result["title"].as_str().unwrap_or(&id).to_string(),
+            version: result["version"]["number"].to_string(),
+            id,
+            url,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl SourceConnector for ConfluenceConnector {
+    fn kind(&self) -> &str {
+        "confluence"
+    }
+
+    async fn list_documents(&self) -> AppResult<Vec<RemoteDocument>> {
+        let mut documents = Vec::new();
+        for space in &self.spaces {
+            let mut start = 0;
+            loop {
+                let page = self
+                    .get(
+                        "/rest/api/content",
+                        &[
+                            ("spaceKey", space.clone()),
+                            ("type", "page".to_string()),
+                            ("expand", "version".to_string()),
+                            ("limit", PAGE_SIZE.to_string()),
+                            ("start", start.to_string()),
+                        ],
+                    )
+                    .await?;
+
+                let results = page["results"].as_array().cloned().unwrap_or_default();
+                start += results.len();
+                documents.extend(results.iter().filter_map(|r| self.document(r)));
+
+                if results.is_empty() || page["_links"]["next"].is_null() {
+                    break;
+                }
+            }
+        }
+        Ok(documents)
+    }
+
+    async fn fetch_markdown(&self, document: &RemoteDocument) -> AppResult<String> {
+        let page = self
+            .get(
+                &format!("/rest/api/content/{}", document.id),
+                &[("expand", "body.storage".to_string())],
+            )
+            .await?;
+        let storage = page["body"]["storage"]["value"].as_str().unwrap_or("");
+        Ok(html_to_markdown(storage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stub_http;
+
+    fn route(request: &str) -> String {
+        if request.starts_with("GET /wiki/rest/api/content?") {
+            assert!(request.contains("spaceKey=ENG"), "{}", request);
+            if request.contains("start=0") {
+                r#"{"results":[
+                    {"id":"1","title":"Deploys","version":{"number":4},"_links":{"webui":"/spaces/ENG/pages/1"}}
+                 ],"_links":{"next":"/rest/api/content?start=1"}}"#
+                    .to_string()
+            } else {
+                r#"{"results":[{"id":"2","title":"Oncall","version":{"number":1},"_links":{}}],"_links":{}}"#
+                    .to_string()
+            }
+        } else if request.starts_with("GET /wiki/rest/api/content/1?expand=body.storage") {
+            r#"{"body":{"storage":{"value":"<h2>Steps</h2><p>Ship it.</p>"}}}"#.to_string()
+        } else {
+            panic!("unexpected request: {}", request);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lists_pages_across_pagination_and_fetches_markdown() {
+        let base = format!("{}/wiki", stub_http::serve_json(route).await);
+        let connector = ConfluenceConnector::new(
+            reqwest::Client::new(),
+            format!("{}/", base),
+            Some("me@acme.com".to_string()),
+            "token",
+            vec!["ENG".to_string()],
+        );
+
+        let documents = connector.list_documents().await.unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                RemoteDocument {
+                    id: "1".to_string(),
+                    title: "Deploys".to_string(),
+                    url: format!("{}/spaces/ENG/pages/1", base),
+                    version: "4".to_string(),
+                },
+                RemoteDocument {
+                    id: "2".to_string(),
+                    title: "Oncall".to_string(),
+                    url: format!("{}/pages/viewpage.action?pageId=2", base),
+                    version: "1".to_string(),
+                },
+            ]
+        );
+
+        let markdown = connector.fetch_markdown(&documents[0]).await.unwrap();
+        assert_eq!(markdown, "## Steps\n\nShip it.");
+    }
+}
@@ -0,0 +1,457 @@
+//! Conversion of connector content to markdown.
+//!
+//! Confluence pages come as XHTML ("storage format"), Notion pages as JSON
+//! blocks and crawled web pages as HTML. All are reduced to the markdown the
+//! chunk pipeline expects: headings, paragraphs, lists, code blocks, links
+//! and table rows. Anything else keeps only its text; scripts, styles and
+//! page chrome (`<head>`, `<nav>`, `<footer>`) are dropped.
+
+use serde_json::Value;
+
+/// Elements whose content is not page text.
+const SKIPPED: &[&str] = &[
+    "script", "style", "head", "noscript", "template", "svg", "nav", "footer",
+];
+
+/// Convert HTML (or Confluence storage format) to markdown.
+pub fn html_to_markdown(html: &str) -> String {
+    let mut out = Converter::default();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            out.text(&after[..end], true);
+            rest = after.get(end + 3..).unwrap_or("");
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").map(|i| i + 3).unwrap_or(after.len());
+            rest = &after[end..];
+        } else if rest.starts_with('<') {
+            let end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            let tag = &rest[1..end.saturating_sub(1).max(1)];
+            out.tag(tag);
+            rest = &rest[end..];
+
+            // Script and style bodies may contain `<`; jump to their end tag
+            let name = tag_name(tag);
+            let opening = !tag.starts_with('/') && !tag.ends_with('/');
+            if opening && matches!(name.as_str(), "script" | "style") {
+                let close = format!("</{}", name);
+                let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+                rest = &rest[end..];
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            out.text(&decode_entities(&rest[..end]), false);
+            rest = &rest[end..];
+        }
+    }
+
+    out.finish()
+}
+
+/// Markdown writer driven by HTML tags.
+#[derive(Default)]
+struct Converter {
+    out: String,
+    /// Open lists: `None` for `<ul>`, the next item number for `<ol>`
+    lists: Vec<Option<usize>>,
+    /// Inside `<pre>` or a code macro: keep whitespace as it is
+    preformatted: bool,
+    /// Target of the open `<a>`, written after its text
+    link: Option<String>,
+    /// Inside a Confluence macro parameter, which is not page content
+    in_parameter: bool,
+    /// Cells written in the current table row
+    cells: usize,
+    /// Depth of open [`SKIPPED`] elements
+    skipped: usize,
+}
+
+impl Converter {
+    fn tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag_name(tag);
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+
+        if SKIPPED.contains(&name.as_str()) {
+            if closing {
+                self.skipped = self.skipped.saturating_sub(1);
+            } else if !self_closing {
+                self.skipped += 1;
+            }
+            return;
+        }
+        if self.skipped > 0 {
+            return;
+        }
+
+        match (name.as_str(), closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "div" | "blockquote", true) => {
+                self.block()
+            }
+            ("p" | "div", false) => self.block(),
+            ("blockquote", false) => {
+                self.block();
+                self.out.push_str("> ");
+            }
+            ("br", _) => self.line(),
+            ("hr", _) => {
+                self.block();
+                self.out.push_str("---\n\n");
+            }
+            ("ul", false) => {
+                self.line();
+                self.lists.push(None);
+            }
+            ("ol", false) => {
+                self.line();
+                self.lists.push(Some(1));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block();
+                }
+            }
+            ("li", false) => {
+                self.line();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        self.out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            ("li", true) => self.line(),
+            ("strong" | "b", _) => self.out.push_str("**"),
+            ("em" | "i", _) => self.out.push('_'),
+            ("code", _) if !self.preformatted => self.out.push('`'),
+            ("pre", false) | ("ac:plain-text-body", false) => {
+                self.block();
+                self.out.push_str("```\n");
+                self.preformatted = true;
+            }
+            ("pre", true) | ("ac:plain-text-body", true) => {
+                self.line();
+                self.out.push_str("```\n\n");
+                self.preformatted = false;
+            }
+            ("a", false) => {
+                self.link = attribute(tag, "href");
+                if self.link.is_some() {
+                    self.out.push('[');
+                }
+            }
+            ("a", true) => {
+                if let Some(href) = self.link.take() {
+                    self.out.push_str(&format!("]({})", href));
+                }
+            }
+            ("ac:parameter", _) => self.in_parameter = !closing,
+            ("tr", false) => {
+                self.line();
+                self.cells = 0;
+            }
+            ("tr", true) => {
+                if self.cells > 0 {
+                    self.out.push_str(" |");
+                }
+                self.line();
+            }
+            ("td" | "th", false) => {
+                self.out
+                    .push_str(if self.cells == 0 { "| " } else { " | " });
+                self.cells += 1;
+            }
+            ("table", true) => self.block(),
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str, raw: bool) {
+        if self.in_parameter || self.skipped > 0 {
+            return;
+        }
+        if self.preformatted || raw {
+            self.out.push_str(text);
+            return;
+        }
+
+        let starts_with_space = text.starts_with(char::is_whitespace);
+        let ends_with_space = text.ends_with(char::is_whitespace);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            if starts_with_space && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if starts_with_space && !self.at_line_start() && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+        self.out.push_str(&words.join(" "));
+        if ends_with_space {
+            self.out.push(' ');
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with("> ")
+    }
+
+    /// End the current line.
+    fn line(&mut self) {
+        trim_trailing_spaces(&mut self.out);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    /// End the current block with a blank line.
+    fn block(&mut self) {
+        self.line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(mut self) -> String {
+        trim_trailing_spaces(&mut self.out);
+        self.out.trim().to_string()
+    }
+}
+
+/// Lower-case element name of a tag's source (`/A href=...` is `a`).
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn trim_trailing_spaces(text: &mut String) {
+    let trimmed = text.trim_end_matches(' ').len();
+    text.truncate(trimmed);
+}
+
+/// Value of a quoted attribute in a tag's source (`a href="..."`).
+pub(crate) fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+    let quoted = &tag[start..];
+    let quote = quoted.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &quoted[1..];
+    let end = value.find(quote)?;
+    Some(decode_entities(&value[..end]))
+}
+
+/// Decode the character references that appear in page content.
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|&end| end <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Plain text of a Notion rich text array, with links kept.
+pub fn notion_rich_text(rich_text: &Value) -> String {
+    rich_text
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .map(|part| {
+                    let text = part["plain_text"].as_str().unwrap_or("");
+                    match part["href"].as_str() {
+                        Some(href) => format!("[{}]({})", text, href),
+                        None => text.to_string(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Markdown for one Notion block, indented by `depth` list levels.
+///
+/// Returns `None` for blocks without text content (images, embeds, ...).
+pub fn notion_block(block: &Value, depth: usize) -> Option<String> {
+    let kind = block["type"].as_str()?;
+    let content = &block[kind];
+    let text = notion_rich_text(&content["rich_text"]);
+    let indent = "  ".repeat(depth);
+
+    let markdown = match kind {
+        "paragraph" => text,
+        "heading_1" => format!("# {}", text),
+        "heading_2" => format!("## {}", text),
+        "heading_3" => format!("### {}", text),
+        "bulleted_list_item" | "toggle" => format!("{}- {}", indent, text),
+        "numbered_list_item" => format!("{}1. {}", indent, text),
+        "to_do" => {
+            let mark = if content["checked"].as_bool() == Some(true) {
+                "x"
+            } else {
+                " "
+            };
+            format!("{}- [{}] {}", indent, mark, text)
+        }
+        "quote" | "callout" => format!("> {}", text),
+        "code" => format!(
+            "```{}\n{}\n```",
+            content["language"].as_str().unwrap_or(""),
+            text
+        ),
+        "divider" => "---".to_string(),
+        "child_page" => format!("## {}", content["title"].as_str().unwrap_or("")),
+        _ => return None,
+    };
+    Some(markdown)
+}
+
+/// Join converted Notion blocks: list items stay on consecutive lines,
+/// other blocks are separated by a blank line.
+pub fn join_notion_blocks(blocks: &[String]) -> String {
+    let is_list_item = |b: &str| {
+        let b = b.trim_start();
+        b.starts_with("- ") || b.starts_with("1. ")
+    };
+
+    let mut out = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            let tight = is_list_item(&blocks[i - 1]) && is_list_item(block);
+            out.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        out.push_str(block);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = "<h1>Deploys</h1><p>We deploy <strong>daily</strong> &amp; roll back \
+                    <a href=\"https://wiki/rollback\">fast</a>.</p>\
+                    <ul><li>Build</li><li>Ship<ol><li>Canary</li><li>All</li></ol></li></ul>\
+                    <table><tr><th>Env</th><th>Owner</th></tr><tr><td>prod</td><td>ops</td></tr></table>";
+        assert_eq!(
+            html_to_markdown(html),
+            "# Deploys\n\n\
+             We deploy **daily** & roll back [fast](https://wiki/rollback).\n\n\
+             - Build\n- Ship\n  1. Canary\n  2. All\n\n\
+             | Env | Owner |\n| prod | ops |"
+        );
+    }
+
+    #[test]
+    fn test_confluence_code_macro_keeps_whitespace() {
+        let html = "<p>Run:</p><ac:structured-macro ac:name=\"code\">\
+                    <ac:parameter ac:name=\"language\">bash</ac:parameter>\
+                    <ac:plain-text-body><![CDATA[cargo build\n  --release]]></ac:plain-text-body>\
+                    </ac:structured-macro>";
+        assert_eq!(
+            html_to_markdown(html),
+            "Run:\n\n```\ncargo build\n  --release\n```"
+        );
+    }
+
+    #[test]
+    fn test_web_page_drops_scripts_and_chrome() {
+        let html = r#"<html><head><title>Guide</title><style>p { color: red }</style></head>
+            <body><nav><a href="/">Home</a></nav>
+            <script>if (a < b) { document.write("<p>x</p>"); }</script>
+            <h1>Install</h1><p>Run the <code>setup</code> script.<br/></p>
+            <svg viewBox="0 0 1 1"><text>logo</text></svg><img src="a.png"/>
+            <footer>Copyright</footer></body></html>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "# Install\n\nRun the `setup` script."
+        );
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#233;&#x41; & c"),
+            "a <b> éA & c"
+        );
+        assert_eq!(decode_entities("&unknown; &"), "&unknown; &");
+    }
+
+    #[test]
+    fn test_notion_blocks() {
+        let blocks: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"type": "heading_2", "heading_2": {"rich_text": [{"plain_text": "Setup"}]}},
+                {"type": "paragraph", "paragraph": {"rich_text": [
+                    {"plain_text": "See "},
+                    {"plain_text": "docs", "href": "https://example.com"}
+                ]}},
+                {"type": "bulleted_list_item", "bulleted_list_item": {"rich_text": [{"plain_text": "one"}]}},
+                {"type": "to_do", "to_do": {"rich_text": [{"plain_text": "two"}], "checked": true}},
+                {"type": "code", "code": {"rich_text": [{"plain_text": "ls"}], "language": "bash"}},
+                {"type": "image", "image": {}}
+            ]"#,
+        )
+        .unwrap();
+        let converted: Vec<String> = blocks.iter().filter_map(|b| notion_block(b, 0)).collect();
+        assert_eq!(
+            join_notion_blocks(&converted),
+            "## Setup\n\nSee [docs](https://example.com)\n\n- one\n- [x] two\n\n```bash\nls\n```"
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Connectors that learn documents from remote services.
+//!
+//! A connector is configured in `.guided/connectors/<name>.yaml`, where
+//! `type` selects the implementation and the other keys hold its endpoint,
+//! credentials and scope:
+//!
+//! ```yaml
+//! # .guided/connectors/confluence.yaml
+//! type: confluence
+//! baseUrl: https://acme.atlassian.net/wiki
+//! email: me@acme.com
+//! tokenEnv: CONFLUENCE_TOKEN
+//! spaces: [ENG, OPS]
+//! ```
+//!
+//! `guided knowledge learn <base> --connector confluence` lists the remote
+//! documents, converts the new and changed ones to markdown and learns them.
+//! Each source records the document's remote id and version, so the next
+//! run skips unchanged documents and drops the ones deleted remotely.
+
+pub mod confluence;
+pub mod markdown;
+pub mod notion;
+
+use crate::types::{InlineText, KnowledgeSource};
+use guided_core::{AppError, AppResult, CancellationToken};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Time allowed to connect to a remote service.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for one request, including reading the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A document listed by a connector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteDocument {
+    /// Id of the document in the remote service
+    pub id: String,
+
+    /// Document title
+    pub title: String,
+
+    /// Web URL of the document, stored as the source path
+    pub url: String,
+
+    /// Changes whenever the document is edited (version number, edit time)
+    pub version: String,
+}
+
+/// A remote document service that knowledge bases can learn from.
+#[async_trait::async_trait]
+pub trait SourceConnector: Send + Sync {
+    /// Connector type (`confluence`, `notion`)
+    fn kind(&self) -> &str;
+
+    /// List every document in scope, following pagination.
+    async fn list_documents(&self) -> AppResult<Vec<RemoteDocument>>;
+
+    /// Fetch a document's content as markdown.
+    async fn fetch_markdown(&self, document: &RemoteDocument) -> AppResult<String>;
+}
+
+/// Settings of a connector, as stored in `.guided/connectors/<name>.yaml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ConnectorConfig {
+    #[serde(rename_all = "camelCase")]
+    Confluence {
+        /// Site URL including the context path (`https://acme.atlassian.net/wiki`)
+        base_url: String,
+        /// Account email for Confluence Cloud API tokens; without it the
+        /// token is sent as a bearer token (Data Center personal tokens)
+        #[serde(default)]
+        email: Option<String>,
+        #[serde(flatten)]
+        token: TokenSource,
+        /// Space keys to learn
+        spaces: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Notion {
+        /// API URL (defaults to `https://api.notion.com`)
+        #[serde(default)]
+        base_url: Option<String>,
+        #[serde(flatten)]
+        token: TokenSource,
+    },
+}
+
+/// Where a connector's API token comes from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenSource {
+    /// The token itself (prefer `tokenEnv`, the file may be committed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// Environment variable holding the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+}
+
+impl TokenSource {
+    /// Resolve the token, preferring the environment variable when set.
+    pub fn resolve(&self, connector: &str) -> AppResult<String> {
+        if let Some(var) = &self.token_env {
+            match std::env::var(var) {
+                Ok(token) if !token.is_empty() => return Ok(token),
+                _ if self.token.is_none() => {
+                    return Err(AppError::Knowledge(format!(
+                        "Connector '{}' reads its token from ${}, which is not set",
+                        connector, var
+                    )))
+                }
+                _ => {}
+            }
+        }
+        self.token.clone().ok_or_else(|| {
+            AppError::Knowledge(format!(
+                "Connector '{}' has no token; set `token` or `tokenEnv`",
+                connector
+            ))
+        })
+    }
+}
+
+/// Directory holding connector configurations.
+pub fn connectors_dir(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("connectors")
+}
+
+/// Load the configuration of a connector by name.
+pub fn load_config(workspace: &Path, name: &str) -> AppResult<ConnectorConfig> {
+    let path = connectors_dir(workspace).join(format!("{}.yaml", name));
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        AppError::Knowledge(format!(
+            "Failed to read connector '{}' from {:?}: {}",
+            name, path, e
+        ))
+    })?;
+    serde_yaml::from_str(&content)
+        .map_err(|e| AppError::Knowledge(format!("Invalid connector config {:?}: {}", path, e)))
+}
+
+/// Create the connector configured under `name`.
+pub fn create_connector(workspace: &Path, name: &str) -> AppResult<Box<dyn SourceConnector>> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Knowledge(format!("Failed to create HTTP client: {}", e)))?;
+
+    Ok(match load_config(workspace, name)? {
+        ConnectorConfig::Confluence {
+            base_url,
+            email,
+            token,
+            spaces,
+        } => Box::new(confluence::ConfluenceConnector::new(
+            client,
+            base_url,
+            email,
+            token.resolve(name)?,
+            spaces,
+        )),
+        ConnectorConfig::Notion { base_url, token } => Box::new(notion::NotionConnector::new(
+            client,
+            base_url,
+            token.resolve(name)?,
+        )),
+    })
+}
+
+/// What a connector run learns and removes.
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// New and changed documents, converted to markdown
+    pub texts: Vec<InlineText>,
+
+    /// Sources to remove: earlier versions of changed documents and
+    /// documents that no longer exist remotely
+    pub stale: Vec<KnowledgeSource>,
+
+    /// Documents unchanged since they were learned
+    pub unchanged: u32,
+}
+
+/// Compare a connector's documents with the sources learned from it.
+///
+/// `sources` are the base's tracked sources; those with a remote id of this
+/// connector are matched to the listed documents by id and version.
+pub async fn plan_sync(
+    connector: &dyn SourceConnector,
+    name: &str,
+    sources: &[KnowledgeSource],
+    cancel: &CancellationToken,
+) -> AppResult<SyncPlan> {
+    let prefix = format!("{}:", name);
+    let mut learned: HashMap<&str, &KnowledgeSource> = sources
+        .iter()
+        .filter_map(|s| Some((s.remote_id.as_deref()?.strip_prefix(&prefix)?, s)))
+        .collect();
+
+    let documents = cancel
+        .run("connector sync", connector.list_documents())
+        .await?;
+    tracing::info!(
+        "Connector '{}' ({}) lists {} documents",
+        name,
+        connector.kind(),
+        documents.len()
+    );
+
+    let mut plan = SyncPlan::default();
+    for document in documents {
+        let previous = learned.remove(document.id.as_str());
+        if previous.is_some_and(|s| s.remote_version.as_deref() == Some(&document.version)) {
+            plan.unchanged += 1;
+            continue;
+        }
+
+        let markdown = cancel
+            .run("connector sync", connector.fetch_markdown(&document))
+            .await?;
+        if markdown.trim().is_empty() {
+            tracing::debug!("Skipping empty document '{}'", document.title);
+            continue;
+        }
+        plan.stale.extend(previous.cloned());
+        plan.texts.push(InlineText {
+            text: format!("# {}\n\n{}", document.title, markdown),
+            title: document.title,
+            tags: Vec::new(),
+            url: Some(document.url),
+            remote_id: Some(format!("{}{}", prefix, document.id)),
+            remote_version: Some(document.version),
+            ..Default::default()
+        });
+    }
+
+    // Whatever was not listed again was deleted (or moved out of scope)
+    plan.stale.extend(learned.into_values().cloned());
+    Ok(plan)
+}
+
+/// Turn an HTTP error status into an error naming the connector.
+async fn check_status(
+    connector: &str,
+    response: reqwest::Response,
+) -> AppResult<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let hint = match status.as_u16() {
+        401 | 403 => " (check the connector's token and its access)",
+        _ => "",
+    };
+    Err(AppError::Knowledge(format!(
+        "{} request failed with {}{}: {}",
+        connector,
+        status,
+        hint,
+        body.chars().take(200).collect::<String>()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Vec<RemoteDocument>);
+
+    #[async_trait::async_trait]
+    impl SourceConnector for Fixed {
+        fn kind(&self) -> &str {
+            "fixed"
+        }
+
+        async fn list_documents(&self) -> AppResult<Vec<RemoteDocument>> {
+            Ok(self.0.clone())
+        }
+
+        async fn fetch_markdown(&self, document: &RemoteDocument) -> AppResult<String> {
+            Ok(format!("Body of {}", document.id))
+        }
+    }
+
+    fn document(id: &str, version: &str) -> RemoteDocument {
+        RemoteDocument {
+            id: id.to_string(),
+            title: format!("Page {}", id),
+            url: format!("https://wiki/{}", id),
+            version: version.to_string(),
+        }
+    }
+
+    fn learned(id: &str, version: &str) -> KnowledgeSource {
+        KnowledgeSource {
+            source_id: format!("source-{}", id),
+            path: format!("https://wiki/{}", id),
+            remote_id: Some(format!("wiki:{}", id)),
+            remote_version: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_sync_learns_new_and_changed_documents() {
+        let connector = Fixed(vec![
+            document("1", "3"),
+            document("2", "7"),
+            document("4", "1"),
+        ]);
+        let sources = vec![learned("1", "3"), learned("2", "6"), learned("3", "1")];
+
+        let plan = plan_sync(&connector, "wiki", &sources, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(plan.unchanged, 1);
+        let ids: Vec<_> = plan
+            .texts
+            .iter()
+            .map(|t| t.remote_id.clone().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["wiki:2", "wiki:4"]);
+        assert_eq!(plan.texts[0].title, "Page 2");
+        assert_eq!(plan.texts[0].url.as_deref(), Some("https://wiki/2"));
+        assert_eq!(plan.texts[0].text, "# Page 2\n\nBody of 2");
+
+        let mut stale: Vec<_> = plan.stale.iter().map(|s| s.source_id.as_str()).collect();
+        stale.sort();
+        assert_eq!(stale, vec!["source-2", "source-3"]);
+    }
+
+    #[test]
+    fn test_connector_config_from_yaml() {
+        let config: ConnectorConfig = serde_yaml::from_str(
+            "type: confluence\nbaseUrl: https://acme.atlassian.net/wiki\n\
+             email: me@acme.com\ntokenEnv: CONFLUENCE_TOKEN\nspaces: [ENG]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ConnectorConfig::Confluence {
+                base_url: "https://acme.atlassian.net/wiki".to_string(),
+                email: Some("me@acme.com".to_string()),
+                token: TokenSource {
+                    token: None,
+                    token_env: Some("CONFLUENCE_TOKEN".to_string()),
+                },
+                spaces: vec!["ENG".to_string()],
+            }
+        );
+
+        let config: ConnectorConfig =
+            serde_yaml::from_str("type: notion\ntoken: secret\n").unwrap();
+        let ConnectorConfig::Notion { base_url, token } = config else {
+            panic!("expected a notion connector");
+        };
+        assert_eq!(base_url, None);
+        assert_eq!(token.resolve("notion").unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_token_from_missing_env_var() {
+        let token = TokenSource {
+            token: None,
+            token_env: Some("GUIDED_TEST_UNSET_CONNECTOR_TOKEN".to_string()),
+        };
+        let err = token.resolve("wiki").unwrap_err().to_string();
+        assert!(
+            err.contains("$GUIDED_TEST_UNSET_CONNECTOR_TOKEN"),
+            "{}",
+            err
+        );
+    }
+}
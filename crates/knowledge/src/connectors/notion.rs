@@ -0,0 +1,220 @@
+//! Notion connector (public API, integration tokens).
+
+use super::markdown::{join_notion_blocks, notion_block, notion_rich_text};
+use super::{check_status, RemoteDocument, SourceConnector};
+use guided_core::{AppError, AppResult};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+
+/// API URL used when the config sets none.
+const DEFAULT_BASE_URL: &str = "https://api.notion.com";
+
+/// API version sent with every request.
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// Results requested per call (the API maximum).
+const PAGE_SIZE: u32 = 100;
+
+/// Learns every page shared with a Notion integration.
+pub struct NotionConnector {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl NotionConnector {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.into(),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> AppResult<Value> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header("Notion-Version", NOTION_VERSION)
+            .send()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Notion request failed: {}", e)))?;
+        check_status("Notion", response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Invalid Notion response: {}", e)))
+    }
+
+    /// Convert the children of a block (or page), descending into nested
+    /// blocks. Child pages are listed as documents of their own.
+    fn children<'a>(
+        &'a self,
+        block_id: &'a str,
+        depth: usize,
+        out: &'a mut Vec<String>,
+    ) -> Pin<Box<dyn Future<Output = AppResult<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut query = vec![("page_size", PAGE_SIZE.to_string())];
+                query.extend(cursor.take().map(|c| ("start_cursor", c)));
+                let url = format!("{}/v1/blocks/{}/children", self.base_url, block_id);
+                let page = self.send(self.client.get(url).query(&query)).await?;
+
+                for block in page["results"].as_array().into_iter().flatten() {
+                    out.extend(notion_block(block, depth));
+
+                    let kind = block["type"].as_str().unwrap_or("");
+                    if block["has_children"].as_bool() == Some(true) && kind != "child_page" {
+                        let nested = matches!(
+                            kind,
+                            "bulleted_list_item" | "numbered_list_item" | "to_do" | "toggle"
+                        );
+                        let id = block["id"].as_str().unwrap_or_default();
+                        self.children(id, depth + usize::from(nested), out).await?;
+                    }
+                }
+
+                match page["next_cursor"].as_str() {
+                    Some(next) if page["has_more"].as_bool() == Some(true) => {
+                        cursor = Some(next.to_string())
+                    }
+                    _ => return Ok(()),
+                }
+            }
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl SourceConnector for NotionConnector {
+    fn kind(&self) -> &str {
+        "notion"
+    }
+
+    async fn list_documents(&self) -> AppResult<Vec<RemoteDocument>> {
+        let mut documents = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut body = json!({
+                "filter": {"property": "object", "value": "page"},
+                "page_size": PAGE_SIZE,
+            });
+            if let Some(cursor) = cursor.take() {
+                body["start_cursor"] = Value::String(cursor);
+            }
+            let url = format!("{}/v1/search", self.base_url);
+            let page = self.send(self.client.post(url).json(&body)).await?;
+
+            documents.extend(
+                page["results"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|r| r["archived"].as_bool() != Some(true))
+                    .filter_map(document),
+            );
+
+            match page["next_cursor"].as_str() {
+                Some(next) if page["has_more"].as_bool() == Some(true) => {
+                    cursor = Some(next.to_string())
+                }
+                _ => return Ok(documents),
+            }
+        }
+    }
+
+    async fn fetch_markdown(&self, document: &RemoteDocument) -> AppResult<String> {
+        let mut blocks = Vec::new();
+        self.children(&document.id, 0, &mut blocks).await?;
+        Ok(join_notion_blocks(&blocks))
+    }
+}
+
+/// Build a document from a search result page.
+fn document(page: &Value) -> Option<RemoteDocument> {
+    let id = page["id"].as_str()?.to_string();
+    let title = page["properties"]
+        .as_object()
+        .into_iter()
+        .flat_map(|properties| properties.values())
+        .find(|property| property["type"] == "title")
+        .map(|property| notion_rich_text(&property["title"]))
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    Some(RemoteDocument {
+        url: page["url"].as_str().unwrap_or_default().to_string(),
+        version: page["last_edited_time"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        id,
+        title,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stub_http;
+
+    fn route(request: &str) -> String {
+        if request.starts_with("POST /v1/search") {
+            if request.contains("\"start_cursor\":\"p2\"") {
+                r#"{"results":[
+                    {"id":"b","url":"https://notion.so/b","last_edited_time":"2026-01-02T00:00:00.000Z",
+                     "properties":{"title":{"type":"title","title":[]}}},
+                    {"id":"c","archived":true,"properties":{}}
+                 ],"has_more":false,"next_cursor":null}"#
+                    .to_string()
+            } else {
+                r#"{"results":[
+                    {"id":"a","url":"https://notion.so/a","last_edited_time":"2026-01-01T00:00:00.000Z",
+                     "properties":{"Name":{"type":"title","title":[{"plain_text":"Runbook"}]}}}
+                 ],"has_more":true,"next_cursor":"p2"}"#
+                    .to_string()
+            }
+        } else if request.starts_with("GET /v1/blocks/a/children?page_size=100 ") {
+            r#"{"results":[
+                {"id":"h","type":"heading_1","heading_1":{"rich_text":[{"plain_text":"Restart"}]}},
+                {"id":"l","type":"bulleted_list_item","has_children":true,
+                 "bulleted_list_item":{"rich_text":[{"plain_text":"Drain"}]}}
+             ],"has_more":true,"next_cursor":"c2"}"#
+                .to_string()
+        } else if request.starts_with("GET /v1/blocks/a/children?page_size=100&start_cursor=c2") {
+            r#"{"results":[
+                {"id":"p","type":"paragraph","paragraph":{"rich_text":[{"plain_text":"Done."}]}}
+             ],"has_more":false,"next_cursor":null}"#
+                .to_string()
+        } else if request.starts_with("GET /v1/blocks/l/children") {
+            r#"{"results":[
+                {"id":"n","type":"bulleted_list_item","bulleted_list_item":{"rich_text":[{"plain_text":"Wait"}]}}
+             ],"has_more":false}"#
+                .to_string()
+        } else {
+            panic!("unexpected request: {}", request);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lists_pages_and_converts_nested_blocks() {
+        let base = stub_http::serve_json(route).await;
+        let connector = NotionConnector::new(reqwest::Client::new(), Some(base), "secret");
+
+        let documents = connector.list_documents().await.unwrap();
+        let titles: Vec<_> = documents.iter().map(|d| d.title.as_str()).collect();
+        assert_eq!(titles, vec!["Runbook", "Untitled"]);
+        assert_eq!(documents[0].url, "https://notion.so/a");
+        assert_eq!(documents[0].version, "2026-01-01T00:00:00.000Z");
+
+        let markdown = connector.fetch_markdown(&documents[0]).await.unwrap();
+        assert_eq!(markdown, "# Restart\n\n- Drain\n  - Wait\n\nDone.");
+    }
+}
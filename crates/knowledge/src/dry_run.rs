@@ -0,0 +1,181 @@
+//! Dry runs of `learn`.
+//!
+//! A dry run discovers, parses and chunks the files (and inline texts) a
+//! learn would, without embedding them or touching the base: no config,
+//! checkpoint, index or sources are written. It reports what the learn
+//! would index, the files discovery skips and why, and how long embedding
+//! would take, timed by embedding a few of the chunks. The only thing it may
+//! leave behind is an embedding model downloaded to time the provider.
+
+use crate::embeddings::{self, EmbeddingConfig};
+use crate::types::{
+    ContentStats, DryRunReport, KnowledgeSource, LearnError, LearnOptions, LearnStage, SourceType,
+};
+use crate::{config, metadata, paths, progress};
+use guided_core::{AppError, AppResult};
+use std::path::Path;
+use std::time::Instant;
+
+/// Chunks embedded to time the provider.
+const SAMPLE_CHUNKS: usize = 16;
+
+/// Providers that embed on this machine, at no API cost.
+const LOCAL_PROVIDERS: &[&str] = &["trigram", "mock", "fastembed", "ollama"];
+
+/// Report what `learn` would do with `options`, without embedding or
+/// writing anything.
+///
+/// URLs, feeds and connectors are not fetched, so they are rejected;
+/// `reset`, `resume` and `migrate` only matter to a real learn.
+pub async fn dry_run(
+    workspace: &Path,
+    options: &LearnOptions,
+    api_key: Option<&str>,
+    progress: progress::ProgressReporter,
+) -> AppResult<DryRunReport> {
+    let start = Instant::now();
+    tracing::info!("Dry run of learn for base '{}'", options.base_name);
+
+    if !options.urls.is_empty() || !options.feeds.is_empty() || options.connector.is_some() {
+        return Err(AppError::Knowledge(
+            "A dry run covers local files and inline text only; drop the URLs, feeds and connector"
+                .to_string(),
+        ));
+    }
+
+    let mut config = config::load_config(workspace, &options.base_name)?;
+    if let Some(provider) = &options.provider {
+        config.provider = provider.clone();
+    }
+    if let Some(model) = &options.model {
+        config.model = model.clone();
+    }
+    config.post_processing = options.post_processing.apply(config.post_processing);
+
+    let options = &crate::with_config_patterns(options, &config);
+
+    // Discover files as learn does, remembering what is skipped
+    let (files, excluded) = crate::discover_files(workspace, options)?;
+
+    let mut sources_count = 0u32;
+    let mut bytes_processed = 0u64;
+    let mut content = ContentStats::default();
+    let mut errors = Vec::new();
+    let mut sample = Vec::new();
+    let mut count = |source: KnowledgeSource, chunks: Vec<crate::chunk::Chunk>| {
+        let source = KnowledgeSource {
+            chunk_count: chunks.len() as u32,
+            token_count: Some(
+                chunks
+                    .iter()
+                    .map(|c| guided_llm::estimate_tokens(&c.text) as u64)
+                    .sum(),
+            ),
+            ..source
+        };
+        content.add_source(&source);
+        sources_count += 1;
+        bytes_processed += source.byte_count;
+        let room = SAMPLE_CHUNKS.saturating_sub(sample.len());
+        sample.extend(chunks.into_iter().take(room).map(|c| c.text));
+    };
+
+    let total_files = files.len() as u64;
+    for (idx, path) in files.iter().enumerate() {
+        if options.cancel.is_cancelled() {
+            break;
+        }
+        progress.parse((idx + 1) as u64, Some(total_files), &paths::normalize(path));
+        match crate::parse_and_chunk_file(workspace, &config, options, path, &progress).await {
+            Ok((source_id, chunks, byte_count)) => count(
+                KnowledgeSource {
+                    source_id,
+                    path: paths::normalize(path),
+                    source_type: SourceType::File,
+                    content_type: Some(metadata::detect_file_type(path).as_str().to_string()),
+                    byte_count,
+                    ..Default::default()
+                },
+                chunks,
+            ),
+            Err(e) => errors.push(LearnError {
+                source: paths::normalize(path),
+                stage: LearnStage::Parse,
+                message: e.to_string(),
+            }),
+        }
+    }
+    for inline in &options.texts {
+        let (source_id, chunks, byte_count) = crate::chunk_inline_text(&config, inline, &progress)?;
+        count(
+            KnowledgeSource {
+                source_id,
+                path: inline.title.clone(),
+                source_type: SourceType::Text,
+                content_type: Some(crate::inline_file_type(inline).as_str().to_string()),
+                byte_count,
+                ..Default::default()
+            },
+            chunks,
+        );
+    }
+
+    let chunks_count: u32 = content.chunks_by_type.values().sum();
+    let (embed_secs_estimate, embed_estimate_error) =
+        match time_embedding(workspace, &config, options, api_key, &sample).await {
+            Ok(secs_per_chunk) => (Some(secs_per_chunk * chunks_count as f64), None),
+            Err(e) => {
+                tracing::warn!("Could not time the embedding provider: {}", e);
+                (None, Some(e.to_string()))
+            }
+        };
+
+    let report = DryRunReport {
+        sources_count,
+        chunks_count,
+        bytes_processed,
+        content,
+        excluded,
+        errors,
+        local_provider: LOCAL_PROVIDERS.contains(&config.provider.as_str()),
+        provider: config.provider,
+        model: config.model,
+        embed_secs_estimate,
+        embed_estimate_error,
+        duration_secs: start.elapsed().as_secs_f64(),
+    };
+    tracing::info!(
+        "Dry run done: {} sources, {} chunks, {} excluded, {} failed",
+        report.sources_count,
+        report.chunks_count,
+        report.excluded.len(),
+        report.errors.len()
+    );
+    Ok(report)
+}
+
+/// Seconds the provider takes per chunk, timed on `sample`. A first call
+/// loads the model and opens the connection, so it is not timed.
+async fn time_embedding(
+    workspace: &Path,
+    config: &crate::types::KnowledgeBaseConfig,
+    options: &LearnOptions,
+    api_key: Option<&str>,
+    sample: &[String],
+) -> AppResult<f64> {
+    if sample.is_empty() {
+        return Ok(0.0);
+    }
+
+    let mut embedding_config = EmbeddingConfig::from_base(workspace, config)
+        .with_provider_settings(&options.provider_configs);
+    if let Some(dimensions) = embeddings::model_dimensions(&config.provider, &config.model) {
+        embedding_config.dimensions = dimensions;
+    }
+    let provider = embeddings::create_provider(&embedding_config, api_key).await?;
+    provider.embed_batch(&sample[..1]).await?;
+
+    let start = Instant::now();
+    provider.embed_batch(sample).await?;
+    Ok(start.elapsed().as_secs_f64() / sample.len() as f64)
+}
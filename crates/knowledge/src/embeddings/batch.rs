@@ -0,0 +1,117 @@
+//! Request batching for embedding providers.
+//!
+//! Splits a list of texts into provider-sized batches by item count and
+//! estimated token count, so providers with a native batch endpoint receive
+//! as much work per request as they accept.
+
+use std::ops::Range;
+
+/// Per-request limits a provider accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLimits {
+    /// Maximum texts per request
+    pub max_items: usize,
+
+    /// Maximum estimated tokens per request (all inputs combined)
+    pub max_tokens: Option<usize>,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_items: 100,
+            max_tokens: None,
+        }
+    }
+}
+
+impl BatchLimits {
+    /// Cap the item count, e.g. by a base's configured `batch_size`.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = self.max_items.min(max_items).max(1);
+        self
+    }
+}
+
+/// Rough token estimate (about four characters per token).
+///
+/// Only used to keep requests under provider limits, so erring slightly
+/// high is fine.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4).max(1)
+}
+
+/// Split `texts` into consecutive index ranges that respect `limits`.
+///
+/// A text that alone exceeds `max_tokens` gets a batch of its own; the
+/// provider decides whether to truncate or reject it.
+pub fn plan_batches(texts: &[String], limits: BatchLimits) -> Vec<Range<usize>> {
+    let max_items = limits.max_items.max(1);
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+
+    for (i, text) in texts.iter().enumerate() {
+        let text_tokens = estimate_tokens(text);
+        let full = i - start >= max_items
+            || limits
+                .max_tokens
+                .is_some_and(|max| i > start && tokens + text_tokens > max);
+
+        if full {
+            batches.push(start..i);
+            start = i;
+            tokens = 0;
+        }
+        tokens += text_tokens;
+    }
+
+    if start < texts.len() {
+        batches.push(start..texts.len());
+    }
+
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lens: &[usize]) -> Vec<String> {
+        lens.iter().map(|n| "x".repeat(*n)).collect()
+    }
+
+    #[test]
+    fn test_plan_batches_by_items() {
+        let limits = BatchLimits {
+            max_items: 2,
+            max_tokens: None,
+        };
+        assert_eq!(
+            plan_batches(&texts(&[4, 4, 4, 4, 4]), limits),
+            vec![0..2, 2..4, 4..5]
+        );
+        assert!(plan_batches(&[], limits).is_empty());
+    }
+
+    #[test]
+    fn test_plan_batches_by_tokens() {
+        let limits = BatchLimits {
+            max_items: 10,
+            max_tokens: Some(10),
+        };
+        // 4 + 4 tokens fit; the third would make 12; the 40-token text is alone
+        assert_eq!(
+            plan_batches(&texts(&[16, 16, 16, 160, 4]), limits),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+    }
+
+    #[test]
+    fn test_with_max_items() {
+        let limits = BatchLimits::default().with_max_items(16);
+        assert_eq!(limits.max_items, 16);
+        assert_eq!(BatchLimits::default().with_max_items(0).max_items, 1);
+        assert_eq!(BatchLimits::default().with_max_items(500).max_items, 100);
+    }
+}
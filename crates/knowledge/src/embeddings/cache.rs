@@ -0,0 +1,175 @@
+//! Disk-backed embedding cache.
+//!
+//! Maps (provider, model, dimensions, content hash) to a vector so that
+//! re-learning unchanged chunks, or the same text in several files or
+//! bases, does not call the provider again. The cache is shared by every
+//! base in a workspace and lives under `.guided/cache/embeddings/`:
+//!
+//! ```text
+//! .guided/cache/embeddings/<provider>/<model>-<dims>/<hash[..2]>/<hash>.f32
+//! ```
+//!
+//! Each file holds the raw little-endian `f32` components.
+
+use crate::metadata::generate_content_hash;
+use guided_core::{AppError, AppResult};
+use std::path::{Path, PathBuf};
+
+/// Embedding cache for one provider/model/dimension combination.
+#[derive(Debug, Clone)]
+pub struct EmbeddingCache {
+    dir: PathBuf,
+    dimensions: usize,
+}
+
+impl EmbeddingCache {
+    pub fn new(workspace: &Path, provider: &str, model: &str, dimensions: usize) -> Self {
+        Self {
+            dir: cache_root(workspace).join(sanitize(provider)).join(format!(
+                "{}-{}",
+                sanitize(model),
+                dimensions
+            )),
+            dimensions,
+        }
+    }
+
+    /// Cache key for a text.
+    pub fn key(text: &str) -> String {
+        generate_content_hash(text)
+    }
+
+    /// Look up a vector by key.
+    ///
+    /// Missing, unreadable or wrongly sized entries are treated as misses.
+    pub fn get(&self, key: &str) -> Option<Vec<f32>> {
+        let bytes = std::fs::read(self.entry_path(key)).ok()?;
+        if bytes.len() != self.dimensions * 4 {
+            tracing::debug!("Ignoring malformed embedding cache entry {}", key);
+            return None;
+        }
+
+        Some(
+            bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        )
+    }
+
+    /// Store a vector under a key.
+    pub fn put(&self, key: &str, embedding: &[f32]) -> AppResult<()> {
+        if embedding.len() != self.dimensions {
+            return Err(AppError::Knowledge(format!(
+                "Refusing to cache {}-dimensional embedding in {}-dimensional cache",
+                embedding.len(),
+                self.dimensions
+            )));
+        }
+
+        let path = self.entry_path(key);
+        let parent = path.parent().unwrap_or(&self.dir);
+        std::fs::create_dir_all(parent).map_err(|e| {
+            AppError::Knowledge(format!(
+                "Failed to create embedding cache directory {:?}: {}",
+                parent, e
+            ))
+        })?;
+
+        let bytes: Vec<u8> = embedding.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        // Write then rename so concurrent learners never read a partial entry
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)?;
+
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let shard = key.get(..2).unwrap_or("00");
+        self.dir.join(shard).join(format!("{}.f32", key))
+    }
+}
+
+/// Root of the workspace embedding cache.
+pub fn cache_root(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("cache").join("embeddings")
+}
+
+/// Delete the whole workspace embedding cache.
+///
+/// Returns the number of cached vectors removed.
+pub fn clear_cache(workspace: &Path) -> AppResult<u64> {
+    let root = cache_root(workspace);
+    if !root.exists() {
+        return Ok(0);
+    }
+
+    let entries = walkdir::WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "f32"))
+        .count() as u64;
+
+    std::fs::remove_dir_all(&root).map_err(|e| {
+        AppError::Knowledge(format!("Failed to clear embedding cache {:?}: {}", root, e))
+    })?;
+
+    Ok(entries)
+}
+
+/// Make a provider or model name safe as a single path component.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_put_get_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let cache = EmbeddingCache::new(temp.path(), "ollama", "nomic-embed-text:latest", 3);
+
+        let key = EmbeddingCache::key("hello world");
+        assert!(cache.get(&key).is_none());
+
+        cache.put(&key, &[0.5, -1.0, 2.25]).unwrap();
+        assert_eq!(cache.get(&key), Some(vec![0.5, -1.0, 2.25]));
+
+        // Model and dimensions are part of the key space
+        let other = EmbeddingCache::new(temp.path(), "ollama", "nomic-embed-text:latest", 4);
+        assert!(other.get(&key).is_none());
+        assert!(cache.put(&key, &[1.0]).is_err());
+
+        assert!(cache_root(temp.path())
+            .join("ollama")
+            .join("nomic-embed-text_latest-3")
+            .exists());
+    }
+
+    #[test]
+    fn test_clear_cache() {
+        let temp = TempDir::new().unwrap();
+        let cache = EmbeddingCache::new(temp.path(), "ollama", "m", 2);
+        cache.put(&EmbeddingCache::key("a"), &[1.0, 2.0]).unwrap();
+        cache.put(&EmbeddingCache::key("b"), &[3.0, 4.0]).unwrap();
+
+        assert_eq!(clear_cache(temp.path()).unwrap(), 2);
+        assert!(cache.get(&EmbeddingCache::key("a")).is_none());
+        assert_eq!(clear_cache(temp.path()).unwrap(), 0);
+    }
+}
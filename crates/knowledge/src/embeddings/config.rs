@@ -1,7 +1,9 @@
 //! Embedding configuration types and management.
 
+use guided_core::config::ProviderConfig;
 use guided_core::{AppError, AppResult};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
@@ -42,7 +44,7 @@ impl Default for EmbeddingConfig {
     fn default() -> Self {
         Self {
             provider: "trigram".to_string(),
-            model: "trigram-v1".to_string(),
+            model: "trigram-v2".to_string(),
             dimensions: 384,
             normalize: true,
             batch_size: 100,
@@ -69,23 +71,57 @@ impl EmbeddingConfig {
         })?;
 
         // Parse full KnowledgeBaseConfig and extract embedding settings
-        let base_config: crate::types::KnowledgeBaseConfig =
-            serde_yaml::from_str(&content).map_err(|e| {
+        let mut base_config: crate::types::KnowledgeBaseConfig = serde_yaml::from_str(&content)
+            .map_err(|e| {
                 AppError::Knowledge(format!(
                     "Failed to parse config at {:?}: {}",
                     config_path, e
                 ))
             })?;
 
-        // Convert to EmbeddingConfig
-        Ok(Self {
+        base_config.name = base_name.to_string();
+
+        Ok(Self::from_base(workspace, &base_config))
+    }
+
+    /// Extract the embedding settings of a base config.
+    pub fn from_base(workspace: &Path, base_config: &crate::types::KnowledgeBaseConfig) -> Self {
+        Self {
             provider: base_config.provider.clone(),
             model: base_config.model.clone(),
             dimensions: base_config.embedding_dim as usize,
             normalize: true,
-            batch_size: 100,
-            provider_config: serde_json::json!({}),
-        })
+            batch_size: base_config.embedding_batch_size.max(1) as usize,
+            provider_config: serde_json::json!({
+                "modelsDir": crate::config::get_models_dir(workspace, &base_config.name),
+            }),
+        }
+    }
+
+    /// Add the endpoint and timeout configured for this provider in
+    /// `llm.providers` to `provider_config` (as `endpoint` and `timeoutSecs`).
+    ///
+    /// These are not saved with the base, so each machine can reach the
+    /// provider at its own address.
+    pub fn with_provider_settings(
+        mut self,
+        provider_configs: &HashMap<String, ProviderConfig>,
+    ) -> Self {
+        let Some(provider_config) = provider_configs.get(&self.provider) else {
+            return self;
+        };
+        if !self.provider_config.is_object() {
+            self.provider_config = serde_json::json!({});
+        }
+        if let Some(settings) = self.provider_config.as_object_mut() {
+            if let Some(endpoint) = provider_config.endpoint() {
+                settings.insert("endpoint".to_string(), endpoint.into());
+            }
+            if let Some(timeout) = provider_config.timeout_secs() {
+                settings.insert("timeoutSecs".to_string(), timeout.into());
+            }
+        }
+        self
     }
 
     /// Save embedding config to base config.yaml
@@ -116,6 +152,7 @@ impl EmbeddingConfig {
         base_config.provider = self.provider.clone();
         base_config.model = self.model.clone();
         base_config.embedding_dim = self.dimensions as u32;
+        base_config.embedding_batch_size = self.batch_size as u32;
 
         let yaml = serde_yaml::to_string(&base_config)
             .map_err(|e| AppError::Knowledge(format!("Failed to serialize config: {}", e)))?;
@@ -167,7 +204,7 @@ mod tests {
     fn test_default_config() {
         let config = EmbeddingConfig::default();
         assert_eq!(config.provider, "trigram");
-        assert_eq!(config.model, "trigram-v1");
+        assert_eq!(config.model, "trigram-v2");
         assert_eq!(config.dimensions, 384);
         assert!(config.normalize);
         assert_eq!(config.batch_size, 100);
@@ -193,11 +230,40 @@ mod tests {
         assert_eq!(loaded.dimensions, 1536);
     }
 
+    #[test]
+    fn test_with_provider_settings() {
+        let providers = HashMap::from([(
+            "ollama".to_string(),
+            ProviderConfig::Ollama {
+                endpoint: "http://gpu-box:11434".to_string(),
+                model: "llama3".to_string(),
+                embedding_model: Some("nomic-embed-text".to_string()),
+                timeout: Some(90),
+            },
+        )]);
+        let config = EmbeddingConfig {
+            provider: "ollama".to_string(),
+            model: "nomic-embed-text".to_string(),
+            dimensions: 768,
+            provider_config: serde_json::json!({"modelsDir": "/tmp/models"}),
+            ..Default::default()
+        }
+        .with_provider_settings(&providers);
+
+        assert_eq!(config.provider_config["endpoint"], "http://gpu-box:11434");
+        assert_eq!(config.provider_config["timeoutSecs"], 90);
+        assert_eq!(config.provider_config["modelsDir"], "/tmp/models");
+
+        // Other providers' settings are not applied
+        let trigram = EmbeddingConfig::default().with_provider_settings(&providers);
+        assert_eq!(trigram.provider_config, serde_json::json!({}));
+    }
+
     #[test]
     fn test_validate_consistency_success() {
         let config1 = EmbeddingConfig {
             provider: "trigram".to_string(),
-            model: "trigram-v1".to_string(),
+            model: "trigram-v2".to_string(),
             dimensions: 384,
             normalize: true,
             batch_size: 100,
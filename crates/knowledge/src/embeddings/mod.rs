@@ -2,23 +2,48 @@
 //!
 //! Provides provider-agnostic embedding generation with per-base configuration.
 
+pub mod batch;
+pub mod cache;
 pub mod config;
 pub mod provider;
 pub mod providers;
 
+pub use batch::BatchLimits;
+pub use cache::EmbeddingCache;
 pub use config::EmbeddingConfig;
-pub use provider::{create_provider, EmbeddingProvider};
+pub use provider::{
+    create_provider, detect_dimensions, model_dimensions, EmbedNotice, EmbedObserver,
+    EmbeddingProvider, EmbeddingUsage, DEFAULT_FASTEMBED_MODEL, FASTEMBED_AVAILABLE,
+};
 
 use crate::chunk::Chunk;
+use crate::progress::ProgressReporter;
+use guided_core::config::ProviderConfig;
 use guided_core::AppResult;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// A base's provider, created once however many tasks ask for it at the
+/// same time.
+type ProviderCell = Arc<OnceCell<CachedProvider>>;
+
+/// A provider and the config it was created from.
+struct CachedProvider {
+    config: EmbeddingConfig,
+    provider: Arc<dyn EmbeddingProvider>,
+}
 
 /// Central embedding engine that manages providers per knowledge base.
 pub struct EmbeddingEngine {
     workspace: PathBuf,
-    providers: Arc<RwLock<HashMap<String, Arc<dyn EmbeddingProvider>>>>,
+    /// Held only to find or replace a base's cell, never across an await
+    providers: Arc<Mutex<HashMap<String, ProviderCell>>>,
+    provider_configs: HashMap<String, ProviderConfig>,
+    use_cache: bool,
+    /// What the provider calls of this engine consumed
+    usage: Mutex<EmbeddingUsage>,
 }
 
 impl EmbeddingEngine {
@@ -26,44 +51,88 @@ impl EmbeddingEngine {
     pub fn new(workspace: PathBuf) -> Self {
         Self {
             workspace,
-            providers: Arc::new(RwLock::new(HashMap::new())),
+            providers: Arc::new(Mutex::new(HashMap::new())),
+            provider_configs: HashMap::new(),
+            use_cache: true,
+            usage: Mutex::new(EmbeddingUsage::default()),
         }
     }
 
+    /// Reach providers at the endpoint and with the timeout configured in
+    /// `llm.providers` (see [`EmbeddingConfig::with_provider_settings`]).
+    pub fn with_provider_configs(
+        mut self,
+        provider_configs: HashMap<String, ProviderConfig>,
+    ) -> Self {
+        self.provider_configs = provider_configs;
+        self
+    }
+
+    /// Always call the provider, bypassing the disk embedding cache.
+    pub fn without_cache(mut self) -> Self {
+        self.use_cache = false;
+        self
+    }
+
     /// Get or create provider for a knowledge base.
+    ///
+    /// Concurrent callers for the same base share one creation. A provider
+    /// created from a config that has since changed on disk (e.g., a
+    /// migration to another model) is evicted and created again.
     async fn get_provider(
         &self,
         base_name: &str,
         api_key: Option<&str>,
     ) -> AppResult<Arc<dyn EmbeddingProvider>> {
-        // Check cache first
-        {
-            let providers = self.providers.read().unwrap();
-            if let Some(provider) = providers.get(base_name) {
-                return Ok(Arc::clone(provider));
+        let config = EmbeddingConfig::load(&self.workspace, base_name)?
+            .with_provider_settings(&self.provider_configs);
+
+        let cell = {
+            let mut providers = self.providers.lock().unwrap();
+            let stale = providers
+                .get(base_name)
+                .and_then(|cell| cell.get())
+                .is_some_and(|cached| cached.config != config);
+            if stale {
+                tracing::info!(
+                    "Embedding config of base '{}' changed; recreating its provider",
+                    base_name
+                );
+                providers.remove(base_name);
             }
-        }
-
-        // Load config and create provider
-        let config = EmbeddingConfig::load(&self.workspace, base_name)?;
-
-        tracing::debug!(
-            "Creating embedding provider for base '{}': provider={}, model={}, dimensions={}",
-            base_name,
-            config.provider,
-            config.model,
-            config.dimensions
-        );
+            Arc::clone(providers.entry(base_name.to_string()).or_default())
+        };
 
-        let provider = provider::create_provider(&config, api_key).await?;
+        let cached = cell
+            .get_or_try_init(|| async {
+                tracing::debug!(
+                    "Creating embedding provider for base '{}': provider={}, model={}, dimensions={}",
+                    base_name,
+                    config.provider,
+                    config.model,
+                    config.dimensions
+                );
+                let provider = provider::create_provider(&config, api_key).await?;
+                AppResult::Ok(CachedProvider {
+                    config: config.clone(),
+                    provider,
+                })
+            })
+            .await?;
+        Ok(Arc::clone(&cached.provider))
+    }
 
-        // Cache it
-        {
-            let mut providers = self.providers.write().unwrap();
-            providers.insert(base_name.to_string(), Arc::clone(&provider));
-        }
+    /// Drop the cached provider of a base, so the next call creates it
+    /// from the base's current config.
+    pub fn evict_provider(&self, base_name: &str) {
+        self.providers.lock().unwrap().remove(base_name);
+    }
 
-        Ok(provider)
+    /// What the provider calls made through this engine consumed so far.
+    ///
+    /// Texts served from the disk cache cost nothing and are not counted.
+    pub fn usage(&self) -> EmbeddingUsage {
+        *self.usage.lock().unwrap()
     }
 
     /// Embed multiple texts for a knowledge base.
@@ -72,22 +141,154 @@ impl EmbeddingEngine {
         base_name: &str,
         texts: &[String],
         api_key: Option<&str>,
+    ) -> AppResult<Vec<Vec<f32>>> {
+        self.embed_texts_with_progress(base_name, texts, api_key, &ProgressReporter::noop())
+            .await
+    }
+
+    /// Embed multiple texts, reporting progress after each provider batch,
+    /// within batches for providers that report it, and before retries.
+    ///
+    /// Vectors are served from the disk cache where possible, and identical
+    /// texts are embedded once. The remaining texts are split by the
+    /// provider's batch limits, further capped by the base's `batch_size`.
+    pub async fn embed_texts_with_progress(
+        &self,
+        base_name: &str,
+        texts: &[String],
+        api_key: Option<&str>,
+        progress: &ProgressReporter,
     ) -> AppResult<Vec<Vec<f32>>> {
         if texts.is_empty() {
             return Ok(Vec::new());
         }
 
         let provider = self.get_provider(base_name, api_key).await?;
+        let config = EmbeddingConfig::load(&self.workspace, base_name)?;
+        let cache = (self.use_cache && provider.cacheable()).then(|| {
+            EmbeddingCache::new(
+                &self.workspace,
+                provider.provider_name(),
+                provider.model_name(),
+                provider.dimensions(),
+            )
+        });
+
+        // Resolve cache hits and collapse duplicates; `pending` holds the
+        // distinct texts still to embed with every position they fill
+        let mut embeddings: Vec<Option<Vec<f32>>> = vec![None; texts.len()];
+        let mut pending: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut pending_index: HashMap<&str, usize> = HashMap::new();
+        let mut cached = 0;
+
+        for (i, text) in texts.iter().enumerate() {
+            if let Some(&p) = pending_index.get(text.as_str()) {
+                pending[p].1.push(i);
+                continue;
+            }
+
+            let key = EmbeddingCache::key(text);
+            if let Some(vector) = cache.as_ref().and_then(|c| c.get(&key)) {
+                embeddings[i] = Some(vector);
+                cached += 1;
+                continue;
+            }
+
+            pending_index.insert(text, pending.len());
+            pending.push((key, vec![i]));
+        }
+
+        let unique: Vec<String> = pending
+            .iter()
+            .map(|(_, positions)| texts[positions[0]].clone())
+            .collect();
+        let limits = provider.batch_limits().with_max_items(config.batch_size);
+        let batches = batch::plan_batches(&unique, limits);
 
         tracing::info!(
-            "Embedding {} texts for base '{}' using provider '{}' (model: {})",
+            "Embedding {} texts for base '{}' using provider '{}' (model: {}): {} cached, {} to embed in {} batches",
             texts.len(),
             base_name,
             provider.provider_name(),
-            provider.model_name()
+            provider.model_name(),
+            cached,
+            unique.len(),
+            batches.len()
         );
 
-        let embeddings = provider.embed_batch(texts).await?;
+        let mut filled = texts.len() - pending.iter().map(|(_, p)| p.len()).sum::<usize>();
+        for (i, range) in batches.iter().enumerate() {
+            // Providers making several requests per batch report as they go
+            let before = filled;
+            let reported = Mutex::new(EmbeddingUsage::default());
+            let observer = |notice: EmbedNotice| match notice {
+                EmbedNotice::Embedded(done) => progress.embed_batch(
+                    i + 1,
+                    batches.len(),
+                    (before + done).min(texts.len()) as u64,
+                    texts.len() as u64,
+                    provider.model_name(),
+                ),
+                EmbedNotice::Retrying {
+                    attempt,
+                    max_attempts,
+                    delay,
+                    reason,
+                } => progress.embed_retry(attempt, max_attempts, delay, &reason),
+                EmbedNotice::Usage(usage) => reported.lock().unwrap().add(&usage),
+            };
+            crate::faults::check(&self.workspace, crate::faults::FaultPoint::Embed)?;
+            let batch = provider
+                .embed_batch_observed(&unique[range.clone()], &observer)
+                .await?;
+
+            // Providers that do not report usage made one request
+            let mut usage = reported.into_inner().unwrap();
+            usage.requests = usage.requests.max(1);
+            usage.texts = range.len() as u64;
+            usage.characters = unique[range.clone()]
+                .iter()
+                .map(|text| text.chars().count() as u64)
+                .sum();
+            self.usage.lock().unwrap().add(&usage);
+            if batch.len() != range.len() {
+                return Err(guided_core::AppError::Knowledge(format!(
+                    "Embedding provider returned {} vectors for {} texts",
+                    batch.len(),
+                    range.len()
+                )));
+            }
+
+            for ((key, positions), vector) in pending[range.clone()].iter().zip(batch) {
+                if let Some(ref cache) = cache {
+                    if let Err(e) = cache.put(key, &vector) {
+                        tracing::warn!("Failed to cache embedding: {}", e);
+                    }
+                }
+                for &position in positions {
+                    embeddings[position] = Some(vector.clone());
+                }
+                filled += positions.len();
+            }
+
+            progress.embed_batch(
+                i + 1,
+                batches.len(),
+                filled as u64,
+                texts.len() as u64,
+                provider.model_name(),
+            );
+        }
+
+        if batches.is_empty() {
+            progress.embed(
+                filled as u64,
+                Some(texts.len() as u64),
+                provider.model_name(),
+            );
+        }
+
+        let embeddings: Vec<Vec<f32>> = embeddings.into_iter().flatten().collect();
 
         tracing::debug!(
             "Generated {} embeddings of dimension {}",
@@ -104,9 +305,22 @@ impl EmbeddingEngine {
         base_name: &str,
         chunks: &[Chunk],
         api_key: Option<&str>,
+    ) -> AppResult<Vec<Vec<f32>>> {
+        self.embed_chunks_with_progress(base_name, chunks, api_key, &ProgressReporter::noop())
+            .await
+    }
+
+    /// Embed chunks, reporting progress after each provider batch.
+    pub async fn embed_chunks_with_progress(
+        &self,
+        base_name: &str,
+        chunks: &[Chunk],
+        api_key: Option<&str>,
+        progress: &ProgressReporter,
     ) -> AppResult<Vec<Vec<f32>>> {
         let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
-        self.embed_texts(base_name, &texts, api_key).await
+        self.embed_texts_with_progress(base_name, &texts, api_key, progress)
+            .await
     }
 
     /// Validate that a base's config is consistent with existing index.
@@ -190,7 +404,251 @@ mod tests {
             .unwrap();
 
         // Verify provider is cached
-        let providers = engine.providers.read().unwrap();
+        let providers = engine.providers.lock().unwrap();
         assert!(providers.contains_key("test-base"));
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_provider_is_created_once_and_recreated_on_config_change() {
+        let temp = TempDir::new().unwrap();
+        let engine = Arc::new(EmbeddingEngine::new(temp.path().to_path_buf()));
+        EmbeddingConfig::default()
+            .save(temp.path(), "test-base")
+            .unwrap();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let engine = Arc::clone(&engine);
+                tokio::spawn(async move { engine.get_provider("test-base", None).await.unwrap() })
+            })
+            .collect();
+        let mut providers = Vec::new();
+        for task in tasks {
+            providers.push(task.await.unwrap());
+        }
+        assert!(providers.iter().all(|p| Arc::ptr_eq(p, &providers[0])));
+
+        // A changed config evicts the provider
+        let config = EmbeddingConfig {
+            model: "trigram-v1".to_string(),
+            ..EmbeddingConfig::default()
+        };
+        config.save(temp.path(), "test-base").unwrap();
+        let provider = engine.get_provider("test-base", None).await.unwrap();
+        assert!(!Arc::ptr_eq(&provider, &providers[0]));
+        assert_eq!(provider.model_name(), "trigram-v1");
+        let again = engine.get_provider("test-base", None).await.unwrap();
+        assert!(Arc::ptr_eq(&provider, &again));
+
+        engine.evict_provider("test-base");
+        let evicted = engine.get_provider("test-base", None).await.unwrap();
+        assert!(!Arc::ptr_eq(&provider, &evicted));
+    }
+
+    impl EmbeddingEngine {
+        /// Use `provider` for `base_name`, as if created from its config.
+        fn cache_provider(&self, base_name: &str, provider: Arc<dyn EmbeddingProvider>) {
+            let config = EmbeddingConfig::load(&self.workspace, base_name)
+                .unwrap()
+                .with_provider_settings(&self.provider_configs);
+            let cell = OnceCell::new_with(Some(CachedProvider { config, provider }));
+            self.providers
+                .lock()
+                .unwrap()
+                .insert(base_name.to_string(), Arc::new(cell));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_texts_reports_batches() {
+        let temp = TempDir::new().unwrap();
+        let engine = EmbeddingEngine::new(temp.path().to_path_buf());
+
+        let config = EmbeddingConfig {
+            batch_size: 2,
+            ..EmbeddingConfig::default()
+        };
+        config.save(temp.path(), "test-base").unwrap();
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let reporter = ProgressReporter::new(Arc::new(move |event| {
+            events_clone.lock().unwrap().push(event);
+        }));
+
+        let texts: Vec<String> = (0..5).map(|i| format!("text {}", i)).collect();
+        let embeddings = engine
+            .embed_texts_with_progress("test-base", &texts, None, &reporter)
+            .await
+            .unwrap();
+        assert_eq!(embeddings.len(), 5);
+
+        let events = events.lock().unwrap();
+        let progress: Vec<u64> = events.iter().map(|e| e.current).collect();
+        assert_eq!(progress, vec![2, 4, 5]);
+        assert_eq!(events[2].message, "batch 3/3 model=trigram-v2");
+    }
+
+    /// Provider that records every text it is asked to embed.
+    #[derive(Debug, Default)]
+    struct CountingProvider {
+        seen: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        fn provider_name(&self) -> &str {
+            "counting"
+        }
+
+        fn model_name(&self) -> &str {
+            "counting-v1"
+        }
+
+        fn dimensions(&self) -> usize {
+            2
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+            self.seen.lock().unwrap().extend(texts.iter().cloned());
+            Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+        }
+    }
+
+    /// Provider that embeds one text at a time, retrying the first once.
+    #[derive(Debug)]
+    struct SteppingProvider;
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for SteppingProvider {
+        fn provider_name(&self) -> &str {
+            "stepping"
+        }
+
+        fn model_name(&self) -> &str {
+            "stepping-v1"
+        }
+
+        fn dimensions(&self) -> usize {
+            1
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+            self.embed_batch_observed(texts, &|_| {}).await
+        }
+
+        async fn embed_batch_observed(
+            &self,
+            texts: &[String],
+            observer: &EmbedObserver<'_>,
+        ) -> AppResult<Vec<Vec<f32>>> {
+            observer(EmbedNotice::Retrying {
+                attempt: 1,
+                max_attempts: 3,
+                delay: std::time::Duration::from_millis(250),
+                reason: "Rate limited".to_string(),
+            });
+            let mut embeddings = Vec::new();
+            for (i, text) in texts.iter().enumerate() {
+                embeddings.push(vec![text.len() as f32]);
+                observer(EmbedNotice::Usage(EmbeddingUsage {
+                    requests: 1,
+                    tokens: 2,
+                    ..Default::default()
+                }));
+                observer(EmbedNotice::Embedded(i + 1));
+            }
+            Ok(embeddings)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_texts_reports_progress_within_batches() {
+        let temp = TempDir::new().unwrap();
+        let engine = EmbeddingEngine::new(temp.path().to_path_buf()).without_cache();
+        engine.cache_provider("test-base", Arc::new(SteppingProvider));
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let reporter = ProgressReporter::new(Arc::new(move |event| {
+            events_clone.lock().unwrap().push(event);
+        }));
+
+        let texts: Vec<String> = (0..3).map(|i| format!("text {}", i)).collect();
+        engine
+            .embed_texts_with_progress("test-base", &texts, None, &reporter)
+            .await
+            .unwrap();
+
+        let events = events.lock().unwrap();
+        let phases: Vec<(&str, u64)> = events
+            .iter()
+            .map(|e| (e.phase.as_str(), e.current))
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                ("retry", 1),
+                ("embed", 1),
+                ("embed", 2),
+                ("embed", 3),
+                ("embed", 3)
+            ]
+        );
+        assert_eq!(
+            events[0].message,
+            "Rate limited; retrying in 250ms (attempt 1/3)"
+        );
+        assert_eq!(events[1].total, Some(3));
+
+        // The provider's own request and token counts are kept
+        assert_eq!(
+            engine.usage(),
+            EmbeddingUsage {
+                requests: 3,
+                texts: 3,
+                characters: 18,
+                tokens: 6,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embed_texts_uses_cache_and_dedup() {
+        let temp = TempDir::new().unwrap();
+        let provider = Arc::new(CountingProvider::default());
+        let engine = EmbeddingEngine::new(temp.path().to_path_buf());
+        engine.cache_provider("test-base", provider.clone());
+
+        let texts: Vec<String> = ["a", "bb", "a"].iter().map(|t| t.to_string()).collect();
+        let first = engine.embed_texts("test-base", &texts, None).await.unwrap();
+        assert_eq!(first, vec![vec![1.0, 1.0], vec![2.0, 1.0], vec![1.0, 1.0]]);
+        assert_eq!(*provider.seen.lock().unwrap(), vec!["a", "bb"]);
+
+        // Second run only embeds the new text
+        let texts: Vec<String> = ["bb", "ccc", "a"].iter().map(|t| t.to_string()).collect();
+        let second = engine.embed_texts("test-base", &texts, None).await.unwrap();
+        assert_eq!(second, vec![vec![2.0, 1.0], vec![3.0, 1.0], vec![1.0, 1.0]]);
+        assert_eq!(*provider.seen.lock().unwrap(), vec!["a", "bb", "ccc"]);
+
+        // Usage counts one request per batch and only the texts sent
+        assert_eq!(
+            engine.usage(),
+            EmbeddingUsage {
+                requests: 2,
+                texts: 3,
+                characters: 6,
+                tokens: 0,
+            }
+        );
+
+        // Bypassing the cache calls the provider again
+        let uncached = EmbeddingEngine::new(temp.path().to_path_buf()).without_cache();
+        uncached.cache_provider("test-base", provider.clone());
+        uncached
+            .embed_texts("test-base", &texts[..1], None)
+            .await
+            .unwrap();
+        assert_eq!(provider.seen.lock().unwrap().len(), 4);
+    }
 }
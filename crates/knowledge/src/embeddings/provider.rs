@@ -1,8 +1,64 @@
 //! Embedding provider trait and factory.
 
+use crate::embeddings::batch::BatchLimits;
 use crate::embeddings::config::EmbeddingConfig;
 use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// What a provider reports while it embeds one batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbedNotice {
+    /// This many texts of the batch are embedded so far
+    Embedded(usize),
+
+    /// A request failed or was rate limited and is retried after `delay`
+    Retrying {
+        /// Number of the retry, from 1
+        attempt: u32,
+        /// Retries allowed
+        max_attempts: u32,
+        /// Wait before the retry
+        delay: Duration,
+        /// Why the request is retried
+        reason: String,
+    },
+
+    /// Requests sent, and the tokens billed for them when the provider
+    /// reports tokens
+    Usage(EmbeddingUsage),
+}
+
+/// What embedding calls consumed, for metering cloud spend.
+///
+/// Providers report `requests` and `tokens` through [`EmbedNotice::Usage`];
+/// `EmbeddingEngine` counts `texts` and `characters` itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingUsage {
+    /// Requests sent to the provider
+    pub requests: u64,
+    /// Texts embedded (cache hits and duplicates excluded)
+    pub texts: u64,
+    /// Characters of the texts embedded
+    pub characters: u64,
+    /// Tokens embedded, 0 when the provider does not report them
+    pub tokens: u64,
+}
+
+impl EmbeddingUsage {
+    /// Add another usage to this one.
+    pub fn add(&mut self, other: &EmbeddingUsage) {
+        self.requests += other.requests;
+        self.texts += other.texts;
+        self.characters += other.characters;
+        self.tokens += other.tokens;
+    }
+}
+
+/// Receives the notices of a batch being embedded.
+pub type EmbedObserver<'a> = dyn Fn(EmbedNotice) + Send + Sync + 'a;
 
 /// Trait for embedding providers.
 #[async_trait::async_trait]
@@ -19,6 +75,34 @@ pub trait EmbeddingProvider: Send + Sync + std::fmt::Debug {
     /// Generate embeddings for multiple texts in a batch.
     async fn embed_batch(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
 
+    /// Like [`EmbeddingProvider::embed_batch`], telling `observer` about
+    /// progress within the batch and retries.
+    ///
+    /// Providers that make several requests per batch, or retry them,
+    /// should override this, and may report their usage. The default
+    /// reports nothing; the engine then counts one request per batch.
+    async fn embed_batch_observed(
+        &self,
+        texts: &[String],
+        observer: &EmbedObserver<'_>,
+    ) -> AppResult<Vec<Vec<f32>>> {
+        let _ = observer;
+        self.embed_batch(texts).await
+    }
+
+    /// Largest batch a single `embed_batch` call should receive.
+    ///
+    /// `EmbeddingEngine` splits work to fit; providers with a native batch
+    /// endpoint should report its item and token limits.
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits::default()
+    }
+
+    /// Whether vectors should be stored in the disk embedding cache.
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     /// Generate embedding for a single text (convenience method).
     async fn embed(&self, text: &str) -> AppResult<Vec<f32>> {
         let mut results = self.embed_batch(&[text.to_string()]).await?;
@@ -28,6 +112,45 @@ pub trait EmbeddingProvider: Send + Sync + std::fmt::Debug {
     }
 }
 
+/// Whether this build includes the local `fastembed` provider.
+pub const FASTEMBED_AVAILABLE: bool = cfg!(feature = "fastembed");
+
+/// Model used by the `fastembed` provider when none is configured.
+pub const DEFAULT_FASTEMBED_MODEL: &str = "all-MiniLM-L6-v2";
+
+/// Native dimensions of a provider's model, when known without loading it.
+///
+/// Used to size the index of a new base.
+pub fn model_dimensions(provider: &str, model: &str) -> Option<usize> {
+    match provider {
+        #[cfg(feature = "fastembed")]
+        "fastembed" => super::providers::fastembed::model_dimensions(model),
+        _ => {
+            let _ = model;
+            None
+        }
+    }
+}
+
+/// Native dimensions of the configured provider's model.
+///
+/// Known models are looked up without loading them, and Ollama is asked with
+/// a test embedding. Trigram vectors take whatever size the base configures.
+pub async fn detect_dimensions(
+    config: &EmbeddingConfig,
+    api_key: Option<&str>,
+) -> AppResult<usize> {
+    if let Some(dimensions) = model_dimensions(&config.provider, &config.model) {
+        return Ok(dimensions);
+    }
+
+    match config.provider.as_str() {
+        "trigram" | "mock" => Ok(config.dimensions),
+        "ollama" => super::providers::ollama::OllamaProvider::detect_dimensions(config).await,
+        _ => Ok(create_provider(config, api_key).await?.dimensions()),
+    }
+}
+
 /// Create an embedding provider based on configuration.
 pub async fn create_provider(
     config: &EmbeddingConfig,
@@ -36,7 +159,10 @@ pub async fn create_provider(
     match config.provider.as_str() {
         "trigram" | "mock" => {
             // Accept both "trigram" and "mock" (for backward compatibility)
-            let provider = super::providers::trigram::TrigramProvider::new(config.dimensions);
+            let provider = super::providers::trigram::TrigramProvider::for_model(
+                &config.model,
+                config.dimensions,
+            );
             Ok(Arc::new(provider))
         }
 
@@ -45,6 +171,19 @@ pub async fn create_provider(
             Ok(Arc::new(provider))
         }
 
+        #[cfg(feature = "fastembed")]
+        "fastembed" => {
+            let provider = super::providers::fastembed::FastEmbedProvider::new(config).await?;
+            Ok(Arc::new(provider))
+        }
+
+        #[cfg(not(feature = "fastembed"))]
+        "fastembed" => Err(AppError::Knowledge(
+            "This build of guided does not include the fastembed provider. Rebuild with \
+             `cargo install --path crates/cli --features fastembed`, or use 'trigram' or 'ollama'."
+                .to_string(),
+        )),
+
         "openai" => Err(AppError::Knowledge(
             "OpenAI provider not yet implemented. Use 'trigram' or 'ollama' provider.".to_string(),
         )),
@@ -54,7 +193,7 @@ pub async fn create_provider(
         )),
 
         _ => Err(AppError::Knowledge(format!(
-            "Unknown embedding provider: '{}'. Supported providers: trigram, ollama, openai, gguf",
+            "Unknown embedding provider: '{}'. Supported providers: trigram, fastembed, ollama, openai, gguf",
             config.provider
         ))),
     }
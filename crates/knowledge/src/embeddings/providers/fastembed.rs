@@ -0,0 +1,192 @@
+//! Local neural embedding provider using fastembed (ONNX Runtime).
+//!
+//! Runs sentence-transformer models such as all-MiniLM-L6-v2 in-process: no
+//! daemon, no API key. The model is downloaded from Hugging Face on first use
+//! into the models directory (`.guided/models/`, or `~/.guided/models/` for
+//! global bases) and loaded from there afterwards.
+//!
+//! Only built with the `fastembed` cargo feature.
+
+use crate::embeddings::batch::BatchLimits;
+use crate::embeddings::config::EmbeddingConfig;
+use crate::embeddings::provider::EmbeddingProvider;
+use fastembed::{EmbeddingModel, ModelInfo, TextEmbedding, TextInitOptions};
+use guided_core::{AppError, AppResult};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Texts per ONNX inference call.
+const MAX_BATCH_ITEMS: usize = 64;
+
+/// Local fastembed embedding provider.
+pub struct FastEmbedProvider {
+    model: Arc<Mutex<TextEmbedding>>,
+    model_name: String,
+    dimensions: usize,
+}
+
+impl std::fmt::Debug for FastEmbedProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FastEmbedProvider")
+            .field("model_name", &self.model_name)
+            .field("dimensions", &self.dimensions)
+            .finish()
+    }
+}
+
+impl FastEmbedProvider {
+    /// Load the configured model, downloading it on first use.
+    pub async fn new(config: &EmbeddingConfig) -> AppResult<Self> {
+        let info = model_info(&config.model)?;
+        if info.dim != config.dimensions {
+            return Err(AppError::Knowledge(format!(
+                "fastembed model '{}' produces {}-dimensional embeddings but the base expects {}",
+                config.model, info.dim, config.dimensions
+            )));
+        }
+
+        let models_dir = config
+            .provider_config
+            .get("modelsDir")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(".guided").join("models"));
+        std::fs::create_dir_all(&models_dir).map_err(|e| {
+            AppError::Knowledge(format!(
+                "Failed to create models directory {:?}: {}",
+                models_dir, e
+            ))
+        })?;
+
+        tracing::info!(
+            "Loading fastembed model '{}' from {:?} (downloaded on first use)",
+            info.model_code,
+            models_dir
+        );
+
+        let options = TextInitOptions::new(info.model.clone())
+            .with_cache_dir(models_dir)
+            .with_show_download_progress(false);
+        let model = tokio::task::spawn_blocking(move || TextEmbedding::try_new(options))
+            .await
+            .map_err(|e| AppError::Knowledge(format!("fastembed task failed: {}", e)))?
+            .map_err(|e| {
+                AppError::Knowledge(format!(
+                    "Failed to load fastembed model '{}': {}",
+                    info.model_code, e
+                ))
+            })?;
+
+        Ok(Self {
+            model: Arc::new(Mutex::new(model)),
+            model_name: config.model.clone(),
+            dimensions: info.dim,
+        })
+    }
+}
+
+/// Look up a supported model by enum name (`AllMiniLML6V2`), Hugging Face
+/// code (`Qdrant/all-MiniLM-L6-v2-onnx`) or short name (`all-MiniLM-L6-v2`).
+fn model_info(name: &str) -> AppResult<ModelInfo<EmbeddingModel>> {
+    let models = TextEmbedding::list_supported_models();
+
+    let short_name = |info: &ModelInfo<EmbeddingModel>| {
+        let code = info
+            .model_code
+            .rsplit('/')
+            .next()
+            .unwrap_or(&info.model_code);
+        code.strip_suffix("-onnx").unwrap_or(code).to_string()
+    };
+
+    models
+        .iter()
+        .find(|info| format!("{:?}", info.model).eq_ignore_ascii_case(name))
+        .or_else(|| {
+            models
+                .iter()
+                .find(|info| info.model_code.eq_ignore_ascii_case(name))
+        })
+        .or_else(|| {
+            models
+                .iter()
+                .find(|info| short_name(info).eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .ok_or_else(|| {
+            let mut names: Vec<String> = models.iter().map(short_name).collect();
+            names.sort();
+            names.dedup();
+            AppError::Knowledge(format!(
+                "Unknown fastembed model '{}'. Supported models: {}",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+/// Embedding dimensions of a fastembed model, if the name is known.
+pub fn model_dimensions(name: &str) -> Option<usize> {
+    model_info(name).ok().map(|info| info.dim)
+}
+
+#[async_trait::async_trait]
+impl EmbeddingProvider for FastEmbedProvider {
+    fn provider_name(&self) -> &str {
+        "fastembed"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits::default().with_max_items(MAX_BATCH_ITEMS)
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let model = Arc::clone(&self.model);
+        let texts = texts.to_vec();
+        let embeddings = tokio::task::spawn_blocking(move || {
+            let mut model = model
+                .lock()
+                .map_err(|_| AppError::Knowledge("fastembed model lock poisoned".to_string()))?;
+            model
+                .embed(&texts, Some(MAX_BATCH_ITEMS))
+                .map_err(|e| AppError::Knowledge(format!("fastembed inference failed: {}", e)))
+        })
+        .await
+        .map_err(|e| AppError::Knowledge(format!("fastembed task failed: {}", e)))??;
+
+        if let Some(bad) = embeddings.iter().find(|e| e.len() != self.dimensions) {
+            return Err(AppError::Knowledge(format!(
+                "Unexpected embedding dimensions: got {}, expected {}",
+                bad.len(),
+                self.dimensions
+            )));
+        }
+
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_lookup() {
+        assert_eq!(model_dimensions("all-MiniLM-L6-v2"), Some(384));
+        assert_eq!(model_dimensions("AllMiniLML6V2"), Some(384));
+        assert_eq!(model_dimensions("Qdrant/all-MiniLM-L6-v2-onnx"), Some(384));
+        assert!(model_info("not-a-model").is_err());
+    }
+}
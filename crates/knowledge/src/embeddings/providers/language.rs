@@ -0,0 +1,316 @@
+//! Language-aware word normalization for the trigram provider.
+//!
+//! Text is lowercased, accents are folded (`ação` → `acao`) and it is split
+//! into words. The language is guessed from stopword hits, then that
+//! language's stopwords are dropped and a light stemmer strips plural
+//! endings, so `aplicações` and `aplicação` or `jugadores` and `jugador`
+//! land on the same term.
+//!
+//! Queries are often too short to detect. Their words are checked against
+//! the stopwords of every supported language and contribute the stem of
+//! each language, so they still line up with the terms of the documents.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Languages with stopword lists and stemming rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    Portuguese,
+    Spanish,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::Portuguese,
+        Language::Spanish,
+        Language::French,
+    ];
+
+    /// Stopwords of the language, ASCII-folded.
+    pub fn stopwords(self) -> &'static HashSet<&'static str> {
+        static SETS: OnceLock<[HashSet<&'static str>; 4]> = OnceLock::new();
+        let sets = SETS.get_or_init(|| {
+            [ENGLISH, PORTUGUESE, SPANISH, FRENCH]
+                .map(|words| words.iter().copied().collect::<HashSet<_>>())
+        });
+        &sets[self as usize]
+    }
+}
+
+const ENGLISH: &[&str] = &[
+    "the", "is", "at", "which", "on", "a", "an", "as", "are", "was", "were", "for", "to", "of",
+    "in", "and", "or", "but", "with", "by", "from", "this", "that", "be", "have", "has", "had",
+    "it", "its", "their", "they", "them", "not", "can", "will", "you", "your", "we", "our", "all",
+    "any", "been", "there", "these", "those", "than", "then", "into", "about", "what", "when",
+    "how", "who", "also", "more", "some", "such", "only", "other", "would", "should", "could",
+];
+
+const PORTUGUESE: &[&str] = &[
+    "de", "a", "o", "que", "e", "do", "da", "em", "um", "para", "com", "nao", "uma", "os", "no",
+    "se", "na", "por", "mais", "as", "dos", "como", "mas", "ao", "ele", "das", "seu", "sua", "ou",
+    "quando", "muito", "nos", "ja", "eu", "tambem", "so", "pelo", "pela", "ate", "isso", "ela",
+    "entre", "depois", "sem", "mesmo", "aos", "seus", "quem", "nas", "esse", "eles", "essa", "num",
+    "nem", "suas", "meu", "minha", "numa", "pelos", "elas", "qual", "lhe", "deles", "essas",
+    "esses", "pelas", "este", "dele", "isto", "esta", "estes", "estas", "aquele", "aquela", "sao",
+    "foi", "ser", "tem", "sobre", "onde", "pode",
+];
+
+const SPANISH: &[&str] = &[
+    "de", "la", "que", "el", "en", "y", "a", "los", "del", "se", "las", "por", "un", "para", "con",
+    "no", "una", "su", "al", "lo", "como", "mas", "pero", "sus", "le", "ya", "o", "este", "porque",
+    "esta", "entre", "cuando", "muy", "sin", "sobre", "tambien", "me", "hasta", "hay", "donde",
+    "quien", "desde", "todo", "nos", "durante", "todos", "uno", "les", "ni", "contra", "otros",
+    "ese", "eso", "ante", "ellos", "e", "esto", "mi", "antes", "algunos", "que", "unos", "yo",
+    "otro", "otras", "otra", "el", "tanto", "esa", "estos", "mucho", "quienes", "nada", "muchos",
+    "cual", "es", "son", "fue", "ser", "tiene", "puede",
+];
+
+const FRENCH: &[&str] = &[
+    "le", "la", "les", "de", "des", "du", "un", "une", "et", "a", "au", "aux", "en", "dans",
+    "pour", "par", "sur", "avec", "ce", "ces", "cet", "cette", "qui", "que", "quoi", "dont", "ou",
+    "ne", "pas", "plus", "se", "sa", "son", "ses", "leur", "leurs", "il", "elle", "ils", "elles",
+    "nous", "vous", "je", "tu", "on", "est", "sont", "etre", "avoir", "ont", "mais", "comme",
+    "tout", "tous", "aussi", "meme", "sans", "sous", "entre", "lui", "y", "si", "peut", "fait",
+    "cela", "ca", "deux", "tres",
+];
+
+/// Lowercase, fold accents to ASCII and split into alphanumeric words.
+pub fn words(text: &str) -> Vec<String> {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        fold_char(c, &mut folded);
+    }
+
+    folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Map accented Latin letters to their ASCII base letters.
+///
+/// Other letters and digits (CJK, Cyrillic, ...) are kept as they are; any
+/// other character becomes a word separator.
+fn fold_char(c: char, out: &mut String) {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'ç' | 'ć' | 'č' => "c",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' => "i",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'š' | 'ś' => "s",
+        'ž' | 'ź' | 'ż' => "z",
+        'ł' => "l",
+        'œ' => "oe",
+        'æ' => "ae",
+        'ß' => "ss",
+        c if c.is_alphanumeric() || c.is_ascii() => {
+            out.push(c);
+            return;
+        }
+        _ => " ",
+    };
+    out.push_str(folded);
+}
+
+/// Guess the language of a list of folded words from stopword hits.
+///
+/// Returns `None` when no language has at least two hits or two languages
+/// tie, which is typical for short queries.
+pub fn detect(words: &[String]) -> Option<Language> {
+    let mut best: Option<(Language, usize)> = None;
+    let mut tied = false;
+
+    for language in Language::ALL {
+        let stopwords = language.stopwords();
+        let hits = words
+            .iter()
+            .filter(|w| stopwords.contains(w.as_str()))
+            .count();
+        match best {
+            Some((_, top)) if hits == top => tied = true,
+            Some((_, top)) if hits < top => {}
+            _ => {
+                best = Some((language, hits));
+                tied = false;
+            }
+        }
+    }
+
+    match best {
+        Some((language, hits)) if hits >= 2 && !tied => Some(language),
+        _ => None,
+    }
+}
+
+/// Whether a word is a stopword of the language, or of any language if unknown.
+pub fn is_stopword(word: &str, language: Option<Language>) -> bool {
+    match language {
+        Some(language) => language.stopwords().contains(word),
+        None => Language::ALL
+            .iter()
+            .any(|language| language.stopwords().contains(word)),
+    }
+}
+
+/// Strip plural endings so singular and plural forms share a stem.
+///
+/// Only endings that leave at least three characters are removed.
+pub fn stem(word: &str, language: Language) -> String {
+    let replace = |suffix: &str, replacement: &str| {
+        word.strip_suffix(suffix)
+            .filter(|stem| stem.len() >= 3)
+            .map(|stem| format!("{}{}", stem, replacement))
+    };
+
+    let rules: &[(&str, &str)] = match language {
+        Language::English => &[
+            ("ies", "y"),
+            ("sses", "ss"),
+            ("xes", "x"),
+            ("ches", "ch"),
+            ("shes", "sh"),
+        ],
+        Language::Portuguese => &[
+            ("oes", "ao"),
+            ("aes", "ao"),
+            ("ais", "al"),
+            ("eis", "el"),
+            ("ns", "m"),
+            ("res", "r"),
+            ("zes", "z"),
+        ],
+        Language::Spanish => &[
+            ("ces", "z"),
+            ("ones", "on"),
+            ("res", "r"),
+            ("les", "l"),
+            ("des", "d"),
+        ],
+        Language::French => &[("eaux", "eau"), ("aux", "al")],
+    };
+
+    rules
+        .iter()
+        .find_map(|(suffix, replacement)| replace(suffix, replacement))
+        .or_else(|| {
+            let plural = (word.ends_with('s')
+                && !word.ends_with("ss")
+                && !word.ends_with("us")
+                && !word.ends_with("is"))
+                || (language == Language::French && word.ends_with('x'));
+            if plural {
+                replace(&word[word.len() - 1..], "")
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| word.to_string())
+}
+
+/// Normalized, stopword-free, stemmed terms of a text with their weights.
+///
+/// Words of undetected language contribute the stem of every supported
+/// language, splitting their weight between the distinct stems.
+pub fn terms(text: &str) -> Vec<(String, f32)> {
+    let words = words(text);
+    let language = detect(&words);
+
+    let mut terms = Vec::new();
+    for word in words
+        .iter()
+        .filter(|w| w.chars().count() > 2 && !is_stopword(w, language))
+    {
+        match language {
+            Some(language) => terms.push((stem(word, language), 1.0)),
+            None => {
+                let mut stems: Vec<String> = Language::ALL.iter().map(|l| stem(word, *l)).collect();
+                stems.sort();
+                stems.dedup();
+                let weight = 1.0 / stems.len() as f32;
+                terms.extend(stems.into_iter().map(|stem| (stem, weight)));
+            }
+        }
+    }
+
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folding_and_detection() {
+        let words = words("A aplicação não gerencia os jogos do usuário!");
+        assert_eq!(
+            words,
+            vec![
+                "a",
+                "aplicacao",
+                "nao",
+                "gerencia",
+                "os",
+                "jogos",
+                "do",
+                "usuario"
+            ]
+        );
+        assert_eq!(detect(&words), Some(Language::Portuguese));
+
+        assert_eq!(
+            detect(&self::words("El jugador de la liga y los equipos")),
+            Some(Language::Spanish)
+        );
+        assert_eq!(
+            detect(&self::words("Les joueurs et la ville dans le nord")),
+            Some(Language::French)
+        );
+        assert_eq!(
+            detect(&self::words("The players of the league and their teams")),
+            Some(Language::English)
+        );
+        assert_eq!(detect(&self::words("gerenciar jogos")), None);
+    }
+
+    #[test]
+    fn test_inflections_share_terms() {
+        let texts = |text: &str| terms(text).into_iter().map(|(t, _)| t).collect::<Vec<_>>();
+
+        // Document in Portuguese, short query with no detectable language
+        let doc = texts("As aplicações gerenciam os jogos e as coleções do usuário");
+        for query_term in texts("jogo aplicação coleção") {
+            assert!(doc.contains(&query_term), "{} not in {:?}", query_term, doc);
+        }
+
+        let doc = texts("Los jugadores de los equipos nacionales");
+        assert!(doc.contains(&"jugador".to_string()));
+        assert!(doc.contains(&"equipo".to_string()));
+        assert!(doc.contains(&"nacional".to_string()));
+        assert!(!doc.contains(&"los".to_string()));
+
+        // An undetected query still produces the Spanish stem, with part of the weight
+        let query = terms("jugadores");
+        assert!(query.iter().any(|(t, w)| t == "jugador" && *w < 1.0));
+
+        let doc = texts("Les journaux et les bateaux dans les villes");
+        assert!(doc.contains(&"journal".to_string()));
+        assert!(doc.contains(&"bateau".to_string()));
+        assert!(doc.contains(&"ville".to_string()));
+
+        assert_eq!(stem("status", Language::English), "status");
+        assert_eq!(stem("class", Language::English), "class");
+        assert_eq!(stem("libraries", Language::English), "library");
+
+        // Non-Latin words are kept
+        assert_eq!(words("東京 タワー"), vec!["東京", "タワー"]);
+    }
+}
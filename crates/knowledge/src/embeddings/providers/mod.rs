@@ -1,4 +1,7 @@
 //! Provider implementations.
 
+#[cfg(feature = "fastembed")]
+pub mod fastembed;
+pub mod language;
 pub mod ollama;
 pub mod trigram;
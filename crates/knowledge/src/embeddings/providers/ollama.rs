@@ -6,15 +6,21 @@
 //! - Neural semantic embeddings (768-dim by default)
 //! - Local-first (no API costs, privacy-preserving)
 //! - Multilingual support (100+ languages)
-//! - Batch embedding support
-//! - Automatic retry with exponential backoff
+//! - Native batch embedding via `/api/embed`, falling back to one request
+//!   per text on servers that predate it
+//! - Automatic retry with exponential backoff, reported to the observer of
+//!   `embed_batch_observed` along with rate limit waits
+//! - Shared per-provider rate limiting (`llm.rateLimits.ollama`)
+//! - Server address and timeout from `llm.providers.ollama` (`endpoint`,
+//!   `timeout`), passed in `provider_config`; `OLLAMA_URL` is used when no
+//!   endpoint is configured
 //!
 //! # Example
 //! ```no_run
-//! use guided_agent_knowledge::embeddings::{EmbeddingConfig, EmbeddingProvider};
-//! use guided_agent_knowledge::embeddings::providers::ollama::OllamaProvider;
+//! use guided_knowledge::embeddings::{EmbeddingConfig, EmbeddingProvider};
+//! use guided_knowledge::embeddings::providers::ollama::OllamaProvider;
 //!
-//! # tokio_test::block_on(async {
+//! # async fn example() {
 //! let config = EmbeddingConfig {
 //!     provider: "ollama".to_string(),
 //!     model: "nomic-embed-text".to_string(),
@@ -25,15 +31,19 @@
 //! let provider = OllamaProvider::new(config).await.unwrap();
 //! let embedding = provider.embed("Hello world").await.unwrap();
 //! assert_eq!(embedding.len(), 768);
-//! # });
+//! # }
 //! ```
 
-use crate::embeddings::EmbeddingProvider;
+use crate::embeddings::batch::BatchLimits;
 use crate::embeddings::EmbeddingConfig;
+use crate::embeddings::{EmbedNotice, EmbedObserver, EmbeddingProvider, EmbeddingUsage};
 use crate::AppError;
 use async_trait::async_trait;
-use reqwest::{Client, StatusCode};
+use guided_llm::rate_limit::{self, CallError, RateLimiter};
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, instrument, warn};
@@ -41,6 +51,10 @@ use tracing::{debug, error, instrument, warn};
 /// Ollama API endpoint for embeddings
 const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
 const EMBEDDING_ENDPOINT: &str = "/api/embeddings";
+const BATCH_EMBEDDING_ENDPOINT: &str = "/api/embed";
+
+/// Maximum texts per batch request (keeps requests well under the timeout)
+const MAX_BATCH_ITEMS: usize = 64;
 
 /// Maximum retry attempts for failed requests
 const MAX_RETRIES: u32 = 3;
@@ -48,9 +62,19 @@ const MAX_RETRIES: u32 = 3;
 /// Initial backoff duration in milliseconds
 const INITIAL_BACKOFF_MS: u64 = 100;
 
-/// Request timeout in seconds
+/// Request timeout in seconds, unless configured
 const REQUEST_TIMEOUT_SECS: u64 = 30;
 
+/// Idle connections kept open per host between batches
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// How long an idle pooled connection is kept
+const POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// TCP keep-alive interval, so idle pooled connections aren't dropped by
+/// firewalls or proxies between batches
+const TCP_KEEPALIVE_SECS: u64 = 60;
+
 /// Ollama embedding provider using local API
 #[derive(Debug, Clone)]
 pub struct OllamaProvider {
@@ -62,6 +86,10 @@ pub struct OllamaProvider {
     model: String,
     /// Expected embedding dimensions
     dimensions: usize,
+    /// Limiter shared with the Ollama LLM client
+    limiter: Arc<RateLimiter>,
+    /// Whether the server has the batch endpoint (cleared on first 404)
+    batch_supported: Arc<AtomicBool>,
 }
 
 /// Request payload for Ollama embeddings API
@@ -80,6 +108,25 @@ struct EmbeddingResponse {
     embedding: Vec<f32>,
 }
 
+/// Request payload for Ollama batch embeddings API
+#[derive(Debug, Clone, Serialize)]
+struct BatchEmbeddingRequest<'a> {
+    /// Model name to use
+    model: &'a str,
+    /// Texts to embed
+    input: &'a [String],
+}
+
+/// Response from Ollama batch embeddings API
+#[derive(Debug, Clone, Deserialize)]
+struct BatchEmbeddingResponse {
+    /// One embedding vector per input, in order
+    embeddings: Vec<Vec<f32>>,
+    /// Tokens embedded, when the server reports them
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+}
+
 /// Error response from Ollama API
 #[derive(Debug, Clone, Deserialize)]
 struct ErrorResponse {
@@ -99,48 +146,90 @@ impl OllamaProvider {
     /// # Errors
     /// * `AppError::LLM` - If Ollama is not reachable or model is invalid
     pub async fn new(config: EmbeddingConfig) -> Result<Self, AppError> {
+        let provider = Self::build(&config)?;
+
+        // Verify Ollama is running and model is available
+        provider.verify_connection().await?;
+
+        Ok(provider)
+    }
+
+    /// Ask Ollama for the native dimensions of the configured model.
+    ///
+    /// Unlike [`OllamaProvider::new`], `config.dimensions` is not checked.
+    pub async fn detect_dimensions(config: &EmbeddingConfig) -> Result<usize, AppError> {
+        Self::build(config)?.probe().await
+    }
+
+    fn build(config: &EmbeddingConfig) -> Result<Self, AppError> {
+        let settings = &config.provider_config;
+        let timeout_secs = settings
+            .get("timeoutSecs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(REQUEST_TIMEOUT_SECS);
+        // Ollama speaks HTTP/1.1, so throughput comes from reusing
+        // connections across the many batch requests of a learn run
         let client = Client::builder()
-            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .timeout(Duration::from_secs(timeout_secs))
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECS))
+            .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE_SECS))
+            .tcp_nodelay(true)
             .build()
             .map_err(|e| {
                 AppError::Llm(format!("Failed to create HTTP client for Ollama: {}", e))
             })?;
 
-        let base_url = std::env::var("OLLAMA_URL").unwrap_or_else(|_| DEFAULT_OLLAMA_URL.to_string());
+        // Configured endpoint, then OLLAMA_URL, then the local default
+        let base_url = settings
+            .get("endpoint")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| std::env::var("OLLAMA_URL").ok())
+            .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
 
         let provider = Self {
             client: Arc::new(client),
             base_url,
             model: config.model.clone(),
             dimensions: config.dimensions,
+            limiter: rate_limit::shared_limiter("ollama"),
+            batch_supported: Arc::new(AtomicBool::new(true)),
         };
 
-        // Verify Ollama is running and model is available
-        provider.verify_connection().await?;
-
         Ok(provider)
     }
 
     /// Verify Ollama connection and model availability
     #[instrument(skip(self), fields(model = %self.model))]
     async fn verify_connection(&self) -> Result<(), AppError> {
+        let dimensions = self.probe().await?;
+        if dimensions != self.dimensions {
+            return Err(AppError::Llm(format!(
+                "Ollama model '{}' returned {} dimensions, expected {}. If the knowledge base \
+                 was built with another model, re-embed it with `guided knowledge learn <base> --migrate`",
+                self.model, dimensions, self.dimensions
+            )));
+        }
+        debug!("Ollama connection verified, model '{}' ready", self.model);
+        Ok(())
+    }
+
+    /// Embed a test text and return the length of the vector
+    async fn probe(&self) -> Result<usize, AppError> {
         debug!("Verifying Ollama connection at {}", self.base_url);
 
         // Test with a simple embedding request
         let test_text = "test connection";
-        match self.embed_with_retries(test_text, MAX_RETRIES).await {
-            Ok(embedding) => {
-                if embedding.len() != self.dimensions {
-                    return Err(AppError::Llm(format!(
-                        "Ollama model '{}' returned {} dimensions, expected {}",
-                        self.model,
-                        embedding.len(),
-                        self.dimensions
-                    )));
-                }
-                debug!("Ollama connection verified, model '{}' ready", self.model);
-                Ok(())
-            }
+        let quiet = |_: EmbedNotice| {};
+        match Self::with_retries(MAX_RETRIES, &quiet, || {
+            self.request_embedding(test_text, &quiet)
+        })
+        .await
+        {
+            Ok(embedding) => Ok(embedding.len()),
             Err(e) => {
                 error!("Failed to connect to Ollama: {}", e);
                 Err(AppError::Llm(format!(
@@ -152,17 +241,35 @@ impl OllamaProvider {
     }
 
     /// Embed single text with retry logic
-    #[instrument(skip(self, text), fields(text_len = text.len(), model = %self.model))]
-    async fn embed_with_retries(&self, text: &str, retries: u32) -> Result<Vec<f32>, AppError> {
+    #[instrument(skip(self, text, observer), fields(text_len = text.len(), model = %self.model))]
+    async fn embed_with_retries(
+        &self,
+        text: &str,
+        retries: u32,
+        observer: &EmbedObserver<'_>,
+    ) -> Result<Vec<f32>, AppError> {
+        Self::with_retries(retries, observer, || self.embed_single(text, observer)).await
+    }
+
+    /// Run a request with retry and exponential backoff, telling `observer`
+    /// before each retry
+    async fn with_retries<T, F, Fut>(
+        retries: u32,
+        observer: &EmbedObserver<'_>,
+        mut op: F,
+    ) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
         let mut attempt = 0;
         let mut last_error = None;
 
         while attempt < retries {
-            match self.embed_single(text).await {
-                Ok(embedding) => return Ok(embedding),
+            match op().await {
+                Ok(value) => return Ok(value),
                 Err(e) => {
                     attempt += 1;
-                    last_error = Some(e);
 
                     if attempt < retries {
                         let backoff_ms = INITIAL_BACKOFF_MS * 2_u64.pow(attempt);
@@ -170,8 +277,15 @@ impl OllamaProvider {
                             "Embedding failed (attempt {}/{}), retrying in {}ms",
                             attempt, retries, backoff_ms
                         );
+                        observer(EmbedNotice::Retrying {
+                            attempt,
+                            max_attempts: retries - 1,
+                            delay: Duration::from_millis(backoff_ms),
+                            reason: format!("Embedding request failed: {}", e),
+                        });
                         tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
                     }
+                    last_error = Some(e);
                 }
             }
         }
@@ -179,62 +293,203 @@ impl OllamaProvider {
         Err(last_error.unwrap_or_else(|| AppError::Llm("Unknown embedding error".to_string())))
     }
 
-    /// Embed single text (no retries)
-    #[instrument(skip(self, text), fields(text_len = text.len()))]
-    async fn embed_single(&self, text: &str) -> Result<Vec<f32>, AppError> {
-        let url = format!("{}{}", self.base_url, EMBEDDING_ENDPOINT);
-
-        let request = EmbeddingRequest {
-            model: self.model.clone(),
-            prompt: text.to_string(),
-        };
+    /// POST a JSON body under the shared rate limiter
+    ///
+    /// 429s are retried inside the limiter, telling `observer`; any other
+    /// response is returned for the caller to check.
+    async fn post<T: Serialize + ?Sized>(
+        &self,
+        endpoint: &str,
+        body: &T,
+        observer: &EmbedObserver<'_>,
+    ) -> Result<reqwest::Response, AppError> {
+        let url = format!("{}{}", self.base_url, endpoint);
 
         debug!("Sending embedding request to {}", url);
 
+        let on_retry = |attempt, max_attempts, delay| {
+            observer(EmbedNotice::Retrying {
+                attempt,
+                max_attempts,
+                delay,
+                reason: "Rate limited by Ollama".to_string(),
+            })
+        };
         let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| AppError::Llm(format!("Failed to send request to Ollama: {}", e)))?;
+            .limiter
+            .call_observed(
+                || async {
+                    let response = self
+                        .client
+                        .post(&url)
+                        .json(body)
+                        .send()
+                        .await
+                        .map_err(|e| {
+                            AppError::Llm(format!("Failed to send request to Ollama: {}", e))
+                        })?;
+
+                    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        return Err(CallError::RateLimited {
+                            retry_after: rate_limit::retry_after(response.headers()),
+                        });
+                    }
 
+                    Ok(response)
+                },
+                on_retry,
+            )
+            .await?;
+
+        observer(EmbedNotice::Usage(EmbeddingUsage {
+            requests: 1,
+            ..Default::default()
+        }));
+        Ok(response)
+    }
+
+    /// Turn an error status into `AppError::Llm` with Ollama's message
+    async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, AppError> {
         let status = response.status();
 
-        if !status.is_success() {
-            // Try to parse error response
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-
-            if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
-                return Err(AppError::Llm(format!(
-                    "Ollama API error ({}): {}",
-                    status, error_response.error
-                )));
-            }
+        if status.is_success() {
+            return Ok(response);
+        }
 
+        // Try to parse error response
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
             return Err(AppError::Llm(format!(
                 "Ollama API error ({}): {}",
-                status, error_text
+                status, error_response.error
             )));
         }
 
-        let response_body: EmbeddingResponse = response
+        Err(AppError::Llm(format!(
+            "Ollama API error ({}): {}",
+            status, error_text
+        )))
+    }
+
+    /// Embed several texts in one request (no retries)
+    ///
+    /// Returns `None` if the server predates the batch endpoint.
+    #[instrument(skip(self, texts, observer), fields(batch_size = texts.len()))]
+    async fn embed_batch_native(
+        &self,
+        texts: &[String],
+        observer: &EmbedObserver<'_>,
+    ) -> Result<Option<Vec<Vec<f32>>>, AppError> {
+        let request = BatchEmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        };
+
+        let response = self
+            .post(BATCH_EMBEDDING_ENDPOINT, &request, observer)
+            .await?;
+
+        // Old servers answer a plain-text 404; a JSON error means the
+        // endpoint exists but the request failed (e.g. unknown model)
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            let error_text = response.text().await.unwrap_or_default();
+            return match serde_json::from_str::<ErrorResponse>(&error_text) {
+                Ok(error_response) => Err(AppError::Llm(format!(
+                    "Ollama API error (404 Not Found): {}",
+                    error_response.error
+                ))),
+                Err(_) => Ok(None),
+            };
+        }
+
+        let response_body: BatchEmbeddingResponse = Self::check_status(response)
+            .await?
             .json()
             .await
             .map_err(|e| AppError::Llm(format!("Failed to parse Ollama response: {}", e)))?;
 
-        if response_body.embedding.len() != self.dimensions {
+        if response_body.embeddings.len() != texts.len() {
+            return Err(AppError::Llm(format!(
+                "Ollama returned {} embeddings for {} texts",
+                response_body.embeddings.len(),
+                texts.len()
+            )));
+        }
+
+        if let Some(bad) = response_body
+            .embeddings
+            .iter()
+            .find(|e| e.len() != self.dimensions)
+        {
             return Err(AppError::Llm(format!(
                 "Unexpected embedding dimensions: got {}, expected {}",
-                response_body.embedding.len(),
+                bad.len(),
                 self.dimensions
             )));
         }
 
-        debug!("Successfully generated {} dimensional embedding", response_body.embedding.len());
+        if let Some(tokens) = response_body.prompt_eval_count {
+            observer(EmbedNotice::Usage(EmbeddingUsage {
+                tokens,
+                ..Default::default()
+            }));
+        }
+
+        debug!(
+            "Successfully generated {} embeddings in one request",
+            texts.len()
+        );
+
+        Ok(Some(response_body.embeddings))
+    }
+
+    /// Embed single text (no retries)
+    #[instrument(skip(self, text, observer), fields(text_len = text.len()))]
+    async fn embed_single(
+        &self,
+        text: &str,
+        observer: &EmbedObserver<'_>,
+    ) -> Result<Vec<f32>, AppError> {
+        let embedding = self.request_embedding(text, observer).await?;
+
+        if embedding.len() != self.dimensions {
+            return Err(AppError::Llm(format!(
+                "Unexpected embedding dimensions: got {}, expected {}",
+                embedding.len(),
+                self.dimensions
+            )));
+        }
+
+        debug!(
+            "Successfully generated {} dimensional embedding",
+            embedding.len()
+        );
+
+        Ok(embedding)
+    }
+
+    /// Request a single embedding, whatever its dimensions
+    async fn request_embedding(
+        &self,
+        text: &str,
+        observer: &EmbedObserver<'_>,
+    ) -> Result<Vec<f32>, AppError> {
+        let request = EmbeddingRequest {
+            model: self.model.clone(),
+            prompt: text.to_string(),
+        };
+
+        let response = self.post(EMBEDDING_ENDPOINT, &request, observer).await?;
+        let response = Self::check_status(response).await?;
+
+        let response_body: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Llm(format!("Failed to parse Ollama response: {}", e)))?;
 
         Ok(response_body.embedding)
     }
@@ -248,35 +503,82 @@ impl EmbeddingProvider for OllamaProvider {
             return Err(AppError::Llm("Cannot embed empty text".to_string()));
         }
 
-        self.embed_with_retries(text, MAX_RETRIES).await
+        self.embed_with_retries(text, MAX_RETRIES, &|_| {}).await
     }
 
-    #[instrument(skip(self, texts), fields(batch_size = texts.len(), provider = "ollama", model = %self.model))]
     async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AppError> {
+        self.embed_batch_observed(texts, &|_| {}).await
+    }
+
+    #[instrument(skip(self, texts, observer), fields(batch_size = texts.len(), provider = "ollama", model = %self.model))]
+    async fn embed_batch_observed(
+        &self,
+        texts: &[String],
+        observer: &EmbedObserver<'_>,
+    ) -> Result<Vec<Vec<f32>>, AppError> {
         if texts.is_empty() {
             return Ok(vec![]);
         }
 
         debug!("Embedding batch of {} texts", texts.len());
 
-        // Ollama doesn't support batch API, so we embed sequentially
-        // TODO: Consider concurrent requests with semaphore for rate limiting
-        let mut embeddings = Vec::with_capacity(texts.len());
+        // Empty texts get zero vectors without a request
+        let mut embeddings = vec![vec![0.0; self.dimensions]; texts.len()];
+        let (indices, inputs): (Vec<usize>, Vec<String>) = texts
+            .iter()
+            .enumerate()
+            .filter(|(i, text)| {
+                let empty = text.trim().is_empty();
+                if empty {
+                    warn!("Skipping empty text at index {}", i);
+                }
+                !empty
+            })
+            .map(|(i, text)| (i, text.clone()))
+            .unzip();
+
+        if inputs.is_empty() {
+            return Ok(embeddings);
+        }
 
-        for (i, text) in texts.iter().enumerate() {
-            if text.trim().is_empty() {
-                warn!("Skipping empty text at index {}", i);
-                embeddings.push(vec![0.0; self.dimensions]);
-                continue;
+        if self.batch_supported.load(Ordering::Relaxed) {
+            match Self::with_retries(MAX_RETRIES, observer, || {
+                self.embed_batch_native(&inputs, observer)
+            })
+            .await?
+            {
+                Some(vectors) => {
+                    for (i, vector) in indices.into_iter().zip(vectors) {
+                        embeddings[i] = vector;
+                    }
+                    return Ok(embeddings);
+                }
+                None => {
+                    warn!(
+                        "Ollama at {} has no batch embedding endpoint; embedding one text per request",
+                        self.base_url
+                    );
+                    self.batch_supported.store(false, Ordering::Relaxed);
+                }
             }
+        }
 
-            let embedding = self.embed(text).await?;
-            embeddings.push(embedding);
+        // One request per text: report each, so long batches show movement
+        for (done, (i, text)) in indices.into_iter().zip(&inputs).enumerate() {
+            embeddings[i] = self.embed_with_retries(text, MAX_RETRIES, observer).await?;
+            observer(EmbedNotice::Embedded(done + 1));
         }
 
         Ok(embeddings)
     }
 
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits {
+            max_items: MAX_BATCH_ITEMS,
+            max_tokens: None,
+        }
+    }
+
     fn dimensions(&self) -> usize {
         self.dimensions
     }
@@ -388,6 +690,20 @@ mod tests {
         assert_eq!(provider.model_name(), "nomic-embed-text");
     }
 
+    #[test]
+    fn test_build_uses_configured_endpoint() {
+        let config = EmbeddingConfig {
+            provider_config: serde_json::json!({
+                "endpoint": "http://gpu-box:11434/",
+                "timeoutSecs": 120,
+            }),
+            ..create_test_config()
+        };
+
+        let provider = OllamaProvider::build(&config).unwrap();
+        assert_eq!(provider.base_url, "http://gpu-box:11434");
+    }
+
     /// Helper to check if Ollama is running
     async fn is_ollama_running() -> bool {
         let client = Client::builder()
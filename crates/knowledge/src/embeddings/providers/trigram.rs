@@ -1,8 +1,16 @@
 //! Trigram embedding provider using character trigram-based content-aware embeddings.
 
+use super::language;
 use crate::embeddings::provider::EmbeddingProvider;
 use guided_core::AppResult;
 
+/// Original model: English stopwords only, words split on whitespace.
+pub const LEGACY_MODEL: &str = "trigram-v1";
+
+/// Language-aware model: accent folding, per-language stopwords and plural
+/// stemming (see [`super::language`]).
+pub const DEFAULT_MODEL: &str = "trigram-v2";
+
 /// Trigram-based embedding provider for local, offline operation.
 ///
 /// Generates deterministic embeddings based on text content using
@@ -12,19 +20,33 @@ use guided_core::AppResult;
 #[derive(Debug)]
 pub struct TrigramProvider {
     dimensions: usize,
+    language_aware: bool,
 }
 
 impl TrigramProvider {
     /// Create a new trigram provider with specified dimensions.
+    ///
+    /// Uses the legacy `trigram-v1` model; see [`TrigramProvider::for_model`].
     pub fn new(dimensions: usize) -> Self {
-        Self { dimensions }
+        Self {
+            dimensions,
+            language_aware: false,
+        }
     }
 
-    /// Generate a trigram-based embedding for text.
-    fn generate_trigram_embedding(&self, text: &str) -> AppResult<Vec<f32>> {
-        let mut embedding = vec![0.0; self.dimensions];
+    /// Create a provider for a model name.
+    ///
+    /// `trigram-v1` keeps the vectors of bases built before language support;
+    /// every other name selects the language-aware model.
+    pub fn for_model(model: &str, dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            language_aware: model != LEGACY_MODEL,
+        }
+    }
 
-        // Use text properties to create content-aware embeddings
+    /// Weighted terms of a text for the legacy model.
+    fn legacy_terms(text: &str) -> Vec<(String, f32)> {
         let lower = text.to_lowercase();
 
         // Filter stop words for better discrimination
@@ -37,15 +59,28 @@ impl TrigramProvider {
         .copied()
         .collect();
 
-        let words: Vec<&str> = lower
+        lower
             .split_whitespace()
             .filter(|w| !stop_words.contains(w) && w.len() > 2)
-            .collect();
+            .map(|w| (w.to_string(), 1.0))
+            .collect()
+    }
 
-        // Build word frequency map
-        let mut word_freq = std::collections::HashMap::new();
-        for word in &words {
-            *word_freq.entry(*word).or_insert(0) += 1;
+    /// Generate a trigram-based embedding for text.
+    fn generate_trigram_embedding(&self, text: &str) -> AppResult<Vec<f32>> {
+        let mut embedding = vec![0.0; self.dimensions];
+
+        let terms = if self.language_aware {
+            language::terms(text)
+        } else {
+            Self::legacy_terms(text)
+        };
+
+        // Build term frequency map
+        let mut word_freq: std::collections::HashMap<String, f32> =
+            std::collections::HashMap::new();
+        for (term, weight) in terms {
+            *word_freq.entry(term).or_insert(0.0) += weight;
         }
 
         // Map each unique word to multiple dimensions based on character trigrams
@@ -65,7 +100,7 @@ impl TrigramProvider {
                     .fold(0u64, |acc, b| acc.wrapping_mul(37).wrapping_add(b as u64));
 
                 let dim_idx = (trigram_hash as usize) % self.dimensions;
-                embedding[dim_idx] += (*freq as f32).sqrt(); // sqrt scale for better distribution
+                embedding[dim_idx] += freq.sqrt(); // sqrt scale for better distribution
             }
 
             // Also encode whole word
@@ -73,7 +108,7 @@ impl TrigramProvider {
                 .bytes()
                 .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
             let base_dim = (word_hash as usize) % self.dimensions;
-            embedding[base_dim] += *freq as f32;
+            embedding[base_dim] += *freq;
         }
 
         // Normalize to unit vector
@@ -95,7 +130,11 @@ impl EmbeddingProvider for TrigramProvider {
     }
 
     fn model_name(&self) -> &str {
-        "trigram-v1"
+        if self.language_aware {
+            DEFAULT_MODEL
+        } else {
+            LEGACY_MODEL
+        }
     }
 
     fn dimensions(&self) -> usize {
@@ -108,6 +147,11 @@ impl EmbeddingProvider for TrigramProvider {
             .map(|text| self.generate_trigram_embedding(text))
             .collect()
     }
+
+    fn cacheable(&self) -> bool {
+        // Computing a trigram vector is cheaper than reading it from disk
+        false
+    }
 }
 
 #[cfg(test)]
@@ -202,4 +246,32 @@ mod tests {
         let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
         assert!((norm - 1.0).abs() < 0.001);
     }
+
+    #[tokio::test]
+    async fn test_language_aware_model_matches_inflections() {
+        let legacy = TrigramProvider::for_model(LEGACY_MODEL, 384);
+        let provider = TrigramProvider::for_model(DEFAULT_MODEL, 384);
+        assert_eq!(legacy.model_name(), "trigram-v1");
+        assert_eq!(provider.model_name(), "trigram-v2");
+
+        let doc = "O aplicativo gerencia as coleções de jogos dos usuários e suas listas.";
+        let query = "coleção de jogo";
+
+        let similarity = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+
+        let legacy_score = similarity(
+            &legacy.embed(doc).await.unwrap(),
+            &legacy.embed(query).await.unwrap(),
+        );
+        let score = similarity(
+            &provider.embed(doc).await.unwrap(),
+            &provider.embed(query).await.unwrap(),
+        );
+        assert!(
+            score > legacy_score,
+            "v2 {} should beat v1 {}",
+            score,
+            legacy_score
+        );
+    }
 }
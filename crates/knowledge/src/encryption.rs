@@ -0,0 +1,279 @@
+//! Encryption at rest for knowledge bases.
+//!
+//! Bases learned with `--encrypt` store chunk text and metadata in LanceDB
+//! sealed with ChaCha20-Poly1305. Embedding vectors stay in the clear since
+//! search runs over them, and so do `sources.jsonl` and the embedding cache.
+//!
+//! The key is 32 bytes written as 64 hex characters (`openssl rand -hex 32`).
+//! It is read from an environment variable (`GUIDED_KNOWLEDGE_KEY` unless the
+//! base configures another), or, when that is unset, from the output of the
+//! base's `key_command`, e.g. `secret-tool lookup guided knowledge` to use the
+//! OS keyring.
+
+use crate::types::{EncryptionConfig, KnowledgeBaseConfig};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use guided_core::{AppError, AppResult};
+
+/// Environment variable read for the key by default.
+pub const DEFAULT_KEY_ENV: &str = "GUIDED_KNOWLEDGE_KEY";
+
+/// Marks encrypted column values.
+const PREFIX: &str = "enc:v1:";
+
+/// Encrypted into `key_check` so a wrong key is detected before any query.
+const KEY_CHECK_PLAINTEXT: &str = "guided-knowledge-key-check";
+
+const NONCE_LEN: usize = 12;
+
+/// Seals and opens column values of an encrypted base.
+#[derive(Clone)]
+pub struct Cipher {
+    aead: ChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cipher { .. }")
+    }
+}
+
+impl Cipher {
+    /// Build a cipher from a 64-character hex key.
+    pub fn from_hex(key_hex: &str) -> AppResult<Self> {
+        let key = decode_hex(key_hex.trim())
+            .filter(|k| k.len() == 32)
+            .ok_or_else(|| {
+                AppError::Knowledge(
+                    "Knowledge base key must be 32 bytes written as 64 hex characters \
+                 (generate one with `openssl rand -hex 32`)"
+                        .to_string(),
+                )
+            })?;
+
+        Ok(Self {
+            aead: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// Encrypt a value with a fresh random nonce.
+    pub fn encrypt(&self, plaintext: &str) -> AppResult<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aead
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| {
+                AppError::Knowledge("Failed to encrypt knowledge base data".to_string())
+            })?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", PREFIX, BASE64.encode(sealed)))
+    }
+
+    /// Decrypt a value produced by [`Cipher::encrypt`].
+    pub fn decrypt(&self, value: &str) -> AppResult<String> {
+        let invalid = || {
+            AppError::Knowledge(
+                "Failed to decrypt knowledge base data (wrong key or corrupted data)".to_string(),
+            )
+        };
+
+        let sealed = value
+            .strip_prefix(PREFIX)
+            .and_then(|encoded| BASE64.decode(encoded).ok())
+            .filter(|sealed| sealed.len() >= NONCE_LEN)
+            .ok_or_else(invalid)?;
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+        let plaintext = self
+            .aead
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+
+    /// Whether a stored value is encrypted.
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(PREFIX)
+    }
+}
+
+/// Turn on encryption for a base that has no encrypted data yet.
+///
+/// Records a key check value so later runs can tell a wrong key apart.
+pub fn enable(config: &mut KnowledgeBaseConfig) -> AppResult<Cipher> {
+    let mut encryption = config.encryption.clone().unwrap_or_default();
+    let cipher = Cipher::from_hex(&load_key(&encryption)?)?;
+    encryption.key_check = cipher.encrypt(KEY_CHECK_PLAINTEXT)?;
+    config.encryption = Some(encryption);
+
+    tracing::info!("Enabled encryption for knowledge base '{}'", config.name);
+    Ok(cipher)
+}
+
+/// Get the cipher of an encrypted base, or `None` for a plaintext base.
+///
+/// Fails if the key is missing or is not the one the base was encrypted with.
+pub fn cipher_for(config: &KnowledgeBaseConfig) -> AppResult<Option<Cipher>> {
+    let Some(encryption) = &config.encryption else {
+        return Ok(None);
+    };
+
+    let cipher = Cipher::from_hex(&load_key(encryption)?)?;
+    if cipher.decrypt(&encryption.key_check).ok().as_deref() != Some(KEY_CHECK_PLAINTEXT) {
+        return Err(AppError::Knowledge(format!(
+            "Wrong key for encrypted knowledge base '{}'",
+            config.name
+        )));
+    }
+
+    Ok(Some(cipher))
+}
+
+/// Read the key from the environment, falling back to `key_command`.
+fn load_key(encryption: &EncryptionConfig) -> AppResult<String> {
+    if let Some(key) = std::env::var(&encryption.key_env)
+        .ok()
+        .filter(|k| !k.trim().is_empty())
+    {
+        return Ok(key);
+    }
+
+    let Some(command) = &encryption.key_command else {
+        return Err(AppError::Knowledge(format!(
+            "Knowledge base is encrypted but {} is not set",
+            encryption.key_env
+        )));
+    };
+
+    let output = if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .args(["/C", command])
+            .output()
+    } else {
+        std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()
+    }
+    .map_err(|e| AppError::Knowledge(format!("Failed to run key command: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Knowledge(format!(
+            "Key command failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const OTHER_KEY: &str = "ff0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let cipher = Cipher::from_hex(KEY).unwrap();
+
+        let sealed = cipher.encrypt("internal roadmap").unwrap();
+        assert!(Cipher::is_encrypted(&sealed));
+        assert!(!sealed.contains("roadmap"));
+        assert_ne!(sealed, cipher.encrypt("internal roadmap").unwrap());
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "internal roadmap");
+
+        let other = Cipher::from_hex(OTHER_KEY).unwrap();
+        assert!(other.decrypt(&sealed).is_err());
+
+        assert!(Cipher::from_hex("abcd").is_err());
+        assert!(Cipher::from_hex(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn test_key_check_detects_wrong_key() {
+        let mut config = KnowledgeBaseConfig {
+            name: "secret".to_string(),
+            encryption: Some(EncryptionConfig {
+                key_env: "GUIDED_TEST_UNSET_KEY".to_string(),
+                key_command: Some(format!("echo {}", KEY)),
+                key_check: String::new(),
+            }),
+            ..Default::default()
+        };
+
+        enable(&mut config).unwrap();
+        assert!(cipher_for(&config).unwrap().is_some());
+
+        config.encryption.as_mut().unwrap().key_command = Some(format!("echo {}", OTHER_KEY));
+        assert!(cipher_for(&config).is_err());
+
+        config.encryption.as_mut().unwrap().key_command = None;
+        assert!(cipher_for(&config).is_err());
+
+        config.encryption = None;
+        assert!(cipher_for(&config).unwrap().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_index_stores_ciphertext() {
+        use crate::lancedb_index::LanceDbIndex;
+        use crate::types::KnowledgeChunk;
+        use crate::vector_index::VectorIndex;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let cipher = Cipher::from_hex(KEY).unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2)
+            .await
+            .unwrap()
+            .with_cipher(Some(cipher));
+
+        index
+            .upsert_chunks(&[KnowledgeChunk {
+                id: "c1".to_string(),
+                source_id: "s1".to_string(),
+                position: 0,
+                text: "quarterly revenue forecast".to_string(),
+                embedding: Some(vec![1.0, 0.0]),
+                title_embedding: None,
+                metadata: serde_json::json!({"source_path": "finance/q3.md"}),
+            }])
+            .unwrap();
+
+        let results = index.search(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].0.text, "quarterly revenue forecast");
+        assert_eq!(results[0].0.metadata["source_path"], "finance/q3.md");
+
+        // Without the key the rows cannot be read back
+        let plain = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        assert!(plain.search(&[1.0, 0.0], 1).unwrap().is_empty());
+
+        for entry in walkdir::WalkDir::new(temp.path())
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_file() {
+                let bytes = std::fs::read(entry.path()).unwrap();
+                let contents = String::from_utf8_lossy(&bytes);
+                assert!(!contents.contains("revenue"));
+                assert!(!contents.contains("finance/q3.md"));
+            }
+        }
+    }
+}
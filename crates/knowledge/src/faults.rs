@@ -0,0 +1,165 @@
+//! Fault injection for resilience tests of the learn pipeline.
+//!
+//! Learn calls [`check`] at the points where the embedding provider or the
+//! index can fail. In builds with the `fault-injection` feature (and in this
+//! crate's tests), [`inject`] makes those calls fail for one workspace, so
+//! tests can assert that learn reports partial failures, keeps
+//! `sources.jsonl` consistent with the index and can be resumed. Without the
+//! feature, [`check`] does nothing.
+
+use guided_core::AppResult;
+use std::path::Path;
+
+/// A point of the learn pipeline where a fault can be injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultPoint {
+    /// A request of the embedding engine to its provider, per batch
+    Embed,
+    /// An insert of chunks into the index
+    IndexWrite,
+    /// Recording a learned source in `sources.jsonl`
+    TrackSource,
+}
+
+impl FaultPoint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FaultPoint::Embed => "embed",
+            FaultPoint::IndexWrite => "index write",
+            FaultPoint::TrackSource => "track source",
+        }
+    }
+}
+
+/// Fail if a fault was injected at `point` for `workspace` and is due.
+pub(crate) fn check(workspace: &Path, point: FaultPoint) -> AppResult<()> {
+    #[cfg(any(test, feature = "fault-injection"))]
+    injection::check(workspace, point)?;
+    #[cfg(not(any(test, feature = "fault-injection")))]
+    let _ = (workspace, point);
+    Ok(())
+}
+
+#[cfg(any(test, feature = "fault-injection"))]
+pub use injection::{inject, Fault, FaultGuard};
+
+#[cfg(any(test, feature = "fault-injection"))]
+mod injection {
+    use super::FaultPoint;
+    use guided_core::{AppError, AppResult};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, OnceLock};
+
+    /// When a fault at a point fires.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Fault {
+        pub point: FaultPoint,
+        /// Calls that pass before the first failure
+        pub after: usize,
+        /// Calls that fail from then on; `None` fails every later call
+        pub times: Option<usize>,
+    }
+
+    impl Fault {
+        /// Fail every call at `point`.
+        pub fn new(point: FaultPoint) -> Self {
+            Self {
+                point,
+                after: 0,
+                times: None,
+            }
+        }
+
+        /// Let the first `calls` calls pass.
+        pub fn after(mut self, calls: usize) -> Self {
+            self.after = calls;
+            self
+        }
+
+        /// Fail only once, then pass again (e.g., a transient outage).
+        pub fn once(mut self) -> Self {
+            self.times = Some(1);
+            self
+        }
+    }
+
+    /// A fault with the calls seen so far.
+    struct Armed {
+        fault: Fault,
+        calls: usize,
+    }
+
+    type Faults = HashMap<(PathBuf, FaultPoint), Armed>;
+
+    fn faults() -> &'static Mutex<Faults> {
+        static FAULTS: OnceLock<Mutex<Faults>> = OnceLock::new();
+        FAULTS.get_or_init(Default::default)
+    }
+
+    /// Removes its fault when dropped.
+    #[must_use = "the fault is removed when the guard is dropped"]
+    pub struct FaultGuard {
+        key: (PathBuf, FaultPoint),
+    }
+
+    impl FaultGuard {
+        /// Calls seen at the fault's point so far, passed or failed.
+        pub fn calls(&self) -> usize {
+            faults()
+                .lock()
+                .unwrap()
+                .get(&self.key)
+                .map_or(0, |armed| armed.calls)
+        }
+    }
+
+    impl Drop for FaultGuard {
+        fn drop(&mut self) {
+            if let Ok(mut faults) = faults().lock() {
+                faults.remove(&self.key);
+            }
+        }
+    }
+
+    /// Inject `fault` into learns of `workspace` until the guard is dropped,
+    /// replacing an earlier fault at the same point.
+    pub fn inject(workspace: &Path, fault: Fault) -> FaultGuard {
+        let key = (workspace.to_path_buf(), fault.point);
+        faults()
+            .lock()
+            .unwrap()
+            .insert(key.clone(), Armed { fault, calls: 0 });
+        FaultGuard { key }
+    }
+
+    pub(super) fn check(workspace: &Path, point: FaultPoint) -> AppResult<()> {
+        let mut faults = faults().lock().unwrap();
+        let Some(armed) = faults.get_mut(&(workspace.to_path_buf(), point)) else {
+            return Ok(());
+        };
+
+        // Index of this call, from 0
+        let call = armed.calls;
+        armed.calls += 1;
+        let due = call >= armed.fault.after
+            && armed
+                .fault
+                .times
+                .is_none_or(|times| call < armed.fault.after + times);
+        if !due {
+            return Ok(());
+        }
+
+        tracing::warn!(
+            "Injected fault at {} (call {})",
+            point.as_str(),
+            armed.calls
+        );
+        Err(AppError::Knowledge(format!(
+            "Injected fault at {} (call {})",
+            point.as_str(),
+            armed.calls
+        )))
+    }
+}
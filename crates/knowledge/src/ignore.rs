@@ -0,0 +1,258 @@
+//! Ignore files for learn's file discovery.
+//!
+//! `learn` reads `ignore` files in gitignore syntax: `~/.guided/ignore`
+//! (or `$GUIDED_HOME/ignore`) for excludes shared by every workspace, then
+//! the workspace's `.guided/ignore`. Patterns are matched against paths
+//! relative to the workspace (relative to the learned directory for paths
+//! outside it).
+//!
+//! Which files under a learned directory are skipped, strongest first:
+//!
+//! 1. `exclude` patterns, from `--exclude` and the base config.
+//! 2. Ignore files, where the last matching line wins. A `!pattern` line
+//!    re-includes files the built-in excludes skip (`!vendor/`), but a file
+//!    under an ignored directory cannot be re-included, as in git.
+//! 3. The built-in excludes (`/node_modules/`, `/target/`, `.lock`, ...),
+//!    and images and recordings unless learned with `--images` or
+//!    `--transcribe`.
+//! 4. `include` patterns, from `--include` and the base config: when there
+//!    are any, files matching none of them are skipped.
+//!
+//! Files passed to `learn` directly are always learned.
+
+use guided_core::{AppError, AppResult};
+use std::path::{Path, PathBuf};
+
+/// Matching as gitignore does: `*` and `?` stay within a path segment.
+const MATCH: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Rules of the ignore files that apply to a workspace, in order.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// The line as written, for reporting
+    line: String,
+
+    /// File the line is from (`.guided/ignore`)
+    origin: String,
+
+    pattern: glob::Pattern,
+
+    /// `!pattern`: re-include what matches
+    negated: bool,
+
+    /// `pattern/`: only matches directories
+    dir_only: bool,
+
+    /// No `/` in the pattern: matches the name of any path segment
+    any_segment: bool,
+}
+
+/// What the ignore rules say about a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreMatch<'a> {
+    /// Ignored by this line of this file
+    Ignored { line: &'a str, origin: &'a str },
+
+    /// Re-included by a `!` line
+    Included,
+}
+
+impl IgnoreRules {
+    /// Load the global ignore file, then the workspace's. Missing files
+    /// add no rules.
+    pub fn load(workspace: &Path) -> AppResult<Self> {
+        let mut rules = Self::default();
+        if let Some(path) = global_ignore_path() {
+            rules.add_file(&path, "~/.guided/ignore")?;
+        }
+        rules.add_file(&workspace_ignore_path(workspace), ".guided/ignore")?;
+        Ok(rules)
+    }
+
+    /// Parse ignore rules in gitignore syntax.
+    pub fn parse(content: &str, origin: &str) -> Self {
+        let mut rules = Self::default();
+        rules.add(content, origin);
+        rules
+    }
+
+    fn add_file(&mut self, path: &Path, origin: &str) -> AppResult<()> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
+        self.add(&content, origin);
+        tracing::debug!("Loaded ignore rules from {:?}", path);
+        Ok(())
+    }
+
+    fn add(&mut self, content: &str, origin: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match IgnoreRule::parse(line, origin) {
+                Some(rule) => self.rules.push(rule),
+                None => tracing::warn!("Skipping invalid pattern '{}' in {}", line, origin),
+            }
+        }
+    }
+
+    /// Check a file's path, relative to where the rules apply and with `/`
+    /// separators. `None` when no line matches it or its directories.
+    pub fn check(&self, relative_path: &str) -> Option<IgnoreMatch<'_>> {
+        let segments: Vec<&str> = relative_path.split('/').filter(|s| !s.is_empty()).collect();
+        let mut result = None;
+        for end in 1..=segments.len() {
+            let is_dir = end < segments.len();
+            let path = segments[..end].join("/");
+            let name = segments[end - 1];
+            let Some(rule) = self
+                .rules
+                .iter()
+                .rev()
+                .find(|rule| rule.matches(&path, name, is_dir))
+            else {
+                continue;
+            };
+            if rule.negated {
+                result = Some(IgnoreMatch::Included);
+            } else {
+                // Nothing below an ignored directory comes back
+                return Some(IgnoreMatch::Ignored {
+                    line: &rule.line,
+                    origin: &rule.origin,
+                });
+            }
+        }
+        result
+    }
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, origin: &str) -> Option<Self> {
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let any_segment = !pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            line: line.to_string(),
+            origin: origin.to_string(),
+            pattern: glob::Pattern::new(pattern).ok()?,
+            negated,
+            dir_only,
+            any_segment,
+        })
+    }
+
+    fn matches(&self, path: &str, name: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.any_segment {
+            self.pattern.matches_with(name, MATCH)
+        } else {
+            self.pattern.matches_with(path, MATCH)
+        }
+    }
+}
+
+/// Path of `path` as ignore rules see it: relative to the workspace, or to
+/// the learned directory `root` when outside the workspace.
+pub fn relative_path(workspace: &Path, root: &Path, path: &Path) -> String {
+    let absolute = |p: &Path| match std::env::current_dir() {
+        Ok(cwd) if p.is_relative() => cwd.join(p),
+        _ => p.to_path_buf(),
+    };
+    let path = absolute(path);
+    path.strip_prefix(absolute(workspace))
+        .or_else(|_| path.strip_prefix(absolute(root)))
+        .map(crate::paths::normalize)
+        .unwrap_or_else(|_| crate::paths::normalize(&path))
+}
+
+/// The workspace's ignore file.
+pub fn workspace_ignore_path(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("ignore")
+}
+
+/// The ignore file shared by all workspaces: `$GUIDED_HOME/ignore` if set,
+/// otherwise `~/.guided/ignore`.
+pub fn global_ignore_path() -> Option<PathBuf> {
+    crate::config::global_knowledge_dir()
+        .and_then(|dir| dir.parent().map(|home| home.join("ignore")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(rules: &IgnoreRules, path: &str) -> bool {
+        matches!(rules.check(path), Some(IgnoreMatch::Ignored { .. }))
+    }
+
+    #[test]
+    fn test_name_patterns_match_any_segment() {
+        let rules = IgnoreRules::parse("# generated\n*.snap\nfixtures/\n", ".guided/ignore");
+        assert!(ignored(&rules, "tests/__snapshots__/a.snap"));
+        assert!(ignored(&rules, "src/fixtures/big.json"));
+        assert!(!ignored(&rules, "fixtures"));
+        assert!(rules.check("src/main.rs").is_none());
+    }
+
+    #[test]
+    fn test_patterns_with_slash_are_anchored() {
+        let rules = IgnoreRules::parse("/docs/drafts\napi/**/internal.md\n", ".guided/ignore");
+        assert!(ignored(&rules, "docs/drafts/wip.md"));
+        assert!(!ignored(&rules, "guide/docs/drafts/wip.md"));
+        assert!(ignored(&rules, "api/v1/users/internal.md"));
+        assert!(ignored(&rules, "api/internal.md"));
+        assert!(!ignored(&rules, "api/v1/public.md"));
+    }
+
+    #[test]
+    fn test_last_matching_line_wins() {
+        let rules = IgnoreRules::parse("*.md\n!README.md\n", ".guided/ignore");
+        assert!(ignored(&rules, "docs/guide.md"));
+        assert_eq!(rules.check("docs/README.md"), Some(IgnoreMatch::Included));
+
+        let rules = IgnoreRules::parse("!vendor/\n", ".guided/ignore");
+        assert_eq!(
+            rules.check("vendor/lib/mod.go"),
+            Some(IgnoreMatch::Included)
+        );
+    }
+
+    #[test]
+    fn test_ignored_directory_cannot_be_reincluded() {
+        let rules = IgnoreRules::parse("build/\n!build/keep.md\n", ".guided/ignore");
+        assert_eq!(
+            rules.check("build/keep.md"),
+            Some(IgnoreMatch::Ignored {
+                line: "build/",
+                origin: ".guided/ignore"
+            })
+        );
+    }
+}
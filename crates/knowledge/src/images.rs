@@ -0,0 +1,305 @@
+//! Text descriptions of images, so diagrams and screenshots can be learned.
+//!
+//! Images are only learned with `learn --images`:
+//!
+//! - `tesseract` runs OCR on PNG and JPEG files (the `tesseract` command
+//!   must be installed)
+//! - `ollama` asks a local vision model (`llava` by default) to describe
+//!   them, at the endpoint configured for `llm.providers.ollama`
+//!
+//! SVG diagrams carry their labels as text, which is read directly with
+//! either reader. The description is learned under the image's path, and
+//! its chunks point back to the image with `image_path`.
+
+use crate::connectors::markdown::decode_entities;
+use base64::Engine;
+use guided_core::config::ProviderConfig;
+use guided_core::{AppError, AppResult, CancellationToken};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Vision model used when `--vision-model` is not given.
+pub const DEFAULT_VISION_MODEL: &str = "llava";
+
+/// Ollama address used when none is configured and `OLLAMA_URL` is unset.
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Time allowed for a vision model to describe one image, unless the
+/// Ollama provider configures a timeout.
+const VISION_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Instructions given to the vision model.
+const DESCRIBE_PROMPT: &str = "Describe this image for a documentation search index. \
+If it is a diagram, name every component and explain how they are connected. \
+Transcribe all visible text exactly. Answer in plain text.";
+
+/// How image files are turned into text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageReader {
+    /// OCR with the `tesseract` command, in the given languages
+    /// (`eng+deu`; tesseract's default when unset)
+    Tesseract { languages: Option<String> },
+
+    /// A vision model served by Ollama
+    Ollama { model: String },
+}
+
+impl ImageReader {
+    /// Name of the reader (`tesseract`, `ollama`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageReader::Tesseract { .. } => "tesseract",
+            ImageReader::Ollama { .. } => "ollama",
+        }
+    }
+
+    /// Describe an image file as text.
+    ///
+    /// SVG labels are read from the file; other images go to the reader.
+    pub async fn describe(
+        &self,
+        path: &Path,
+        provider_configs: &HashMap<String, ProviderConfig>,
+        cancel: &CancellationToken,
+    ) -> AppResult<String> {
+        if is_svg(path) {
+            let svg = std::fs::read_to_string(path)
+                .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
+            return Ok(svg_text(&svg));
+        }
+
+        match self {
+            ImageReader::Tesseract { languages } => {
+                cancel
+                    .run("learn", run_tesseract(path, languages.as_deref()))
+                    .await
+            }
+            ImageReader::Ollama { model } => {
+                cancel
+                    .run("learn", ask_vision_model(path, model, provider_configs))
+                    .await
+            }
+        }
+    }
+}
+
+/// Whether a path is an image that `learn --images` can read.
+pub fn is_image(path: &Path) -> bool {
+    matches!(
+        crate::metadata::detect_file_type(path),
+        crate::metadata::FileType::Image
+    )
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+async fn run_tesseract(path: &Path, languages: Option<&str>) -> AppResult<String> {
+    let mut command = tokio::process::Command::new("tesseract");
+    command.arg(path).arg("stdout").kill_on_drop(true);
+    if let Some(languages) = languages {
+        command.arg("-l").arg(languages);
+    }
+
+    let output = command.output().await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::Knowledge(
+                "tesseract is not installed; install it or use --images ollama".to_string(),
+            )
+        } else {
+            AppError::Knowledge(format!("Failed to run tesseract: {}", e))
+        }
+    })?;
+    if !output.status.success() {
+        return Err(AppError::Knowledge(format!(
+            "tesseract failed on {:?}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn ask_vision_model(
+    path: &Path,
+    model: &str,
+    provider_configs: &HashMap<String, ProviderConfig>,
+) -> AppResult<String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
+
+    // Configured endpoint, then OLLAMA_URL, then the local default
+    let ollama = provider_configs.get("ollama");
+    let base_url = ollama
+        .and_then(|config| config.endpoint())
+        .map(str::to_string)
+        .or_else(|| std::env::var("OLLAMA_URL").ok())
+        .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string());
+    let timeout = ollama
+        .and_then(|config| config.timeout_secs())
+        .map_or(VISION_TIMEOUT, Duration::from_secs);
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AppError::Knowledge(format!("Failed to create HTTP client: {}", e)))?;
+    let response = client
+        .post(format!("{}/api/generate", base_url.trim_end_matches('/')))
+        .json(&serde_json::json!({
+            "model": model,
+            "prompt": DESCRIBE_PROMPT,
+            "images": [base64::engine::general_purpose::STANDARD.encode(&bytes)],
+            "stream": false,
+        }))
+        .send()
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Vision model request failed: {}", e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let hint = if status.as_u16() == 404 {
+            format!(" (pull the model with `ollama pull {}`)", model)
+        } else {
+            String::new()
+        };
+        return Err(AppError::Knowledge(format!(
+            "Vision model '{}' failed with {}{}: {}",
+            model,
+            status,
+            hint,
+            body.chars().take(200).collect::<String>()
+        )));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Invalid vision model response: {}", e)))?;
+    Ok(body["response"].as_str().unwrap_or("").trim().to_string())
+}
+
+/// The labels of an SVG drawing: its `<title>`, `<desc>` and `<text>`
+/// elements, one per line.
+pub fn svg_text(svg: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        let tag_end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = &rest[start + 1..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if !matches!(name, "title" | "desc" | "text") || tag.ends_with('/') {
+            continue;
+        }
+
+        // Labels split into <tspan>s are joined back into one line
+        let close = format!("</{}>", name);
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        let label = strip_tags(&rest[..end]);
+        let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !label.is_empty() && !lines.contains(&label) {
+            lines.push(label);
+        }
+        rest = &rest[end + close.len()..];
+    }
+    lines.join("\n")
+}
+
+/// Text of markup with the tags removed and character references decoded.
+fn strip_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stub_http::{self, Reply};
+
+    #[test]
+    fn test_svg_text_reads_labels() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <title>Checkout flow</title>
+            <rect x="0" y="0" width="80" height="40"/>
+            <text x="10" y="20">API &amp; gateway</text>
+            <text x="10" y="80"><tspan>Payments</tspan><tspan x="10" dy="12">service</tspan></text>
+            <text x="90" y="20">API &amp; gateway</text>
+            <text/>
+        </svg>"#;
+        assert_eq!(
+            svg_text(svg),
+            "Checkout flow\nAPI & gateway\nPayments service"
+        );
+    }
+
+    #[test]
+    fn test_is_image() {
+        assert!(is_image(Path::new("docs/arch/overview.PNG")));
+        assert!(is_image(Path::new("flow.svg")));
+        assert!(!is_image(Path::new("notes.md")));
+    }
+
+    #[tokio::test]
+    async fn test_vision_model_describes_png() {
+        let server = stub_http::serve(|request| {
+            assert!(request.starts_with("POST /api/generate"), "{}", request);
+            assert!(request.contains("\"model\":\"llava\""), "{}", request);
+            assert!(request.contains("\"images\":[\"iVBORw0K\"]"), "{}", request);
+            Reply::ok(
+                "application/json",
+                r#"{"response":" Three boxes: web, api and db. ","done":true}"#,
+            )
+        })
+        .await;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("arch.png");
+        std::fs::write(&path, b"\x89PNG\r\n").unwrap();
+
+        let mut provider_configs = HashMap::new();
+        provider_configs.insert(
+            "ollama".to_string(),
+            ProviderConfig::Ollama {
+                endpoint: server,
+                model: "llama3".to_string(),
+                embedding_model: None,
+                timeout: None,
+            },
+        );
+        let reader = ImageReader::Ollama {
+            model: "llava".to_string(),
+        };
+        let text = reader
+            .describe(&path, &provider_configs, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(text, "Three boxes: web, api and db.");
+    }
+}
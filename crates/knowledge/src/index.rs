@@ -21,11 +21,15 @@ pub fn init_index(db_path: &Path) -> AppResult<Connection> {
         r#"
         CREATE TABLE IF NOT EXISTS sources (
             id TEXT PRIMARY KEY,
-            path TEXT,
-            url TEXT,
-            content_type TEXT NOT NULL,
-            learned_at TEXT NOT NULL,
-            size_bytes INTEGER NOT NULL
+            path TEXT NOT NULL,
+            source_type TEXT NOT NULL,
+            content_type TEXT,
+            indexed_at TEXT NOT NULL,
+            chunk_count INTEGER NOT NULL,
+            byte_count INTEGER NOT NULL,
+            added_tags TEXT NOT NULL,
+            removed_tags TEXT NOT NULL,
+            description TEXT
         );
 
         CREATE TABLE IF NOT EXISTS chunks (
@@ -49,19 +53,25 @@ pub fn init_index(db_path: &Path) -> AppResult<Connection> {
 
 /// Insert a source into the index.
 pub fn insert_source(conn: &Connection, source: &KnowledgeSource) -> AppResult<()> {
+    let tags_json = |tags: &[String]| {
+        serde_json::to_string(tags)
+            .map_err(|e| AppError::Knowledge(format!("Failed to serialize tags: {}", e)))
+    };
+
     conn.execute(
-        "INSERT OR REPLACE INTO sources (id, path, url, content_type, learned_at, size_bytes) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT OR REPLACE INTO sources (id, path, source_type, content_type, indexed_at, chunk_count, byte_count, added_tags, removed_tags, description) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         params![
-            source.id,
-            source
-                .path
-                .as_ref()
-                .map(|p| p.to_string_lossy().to_string()),
-            source.url,
+            source.source_id,
+            source.path,
+            source.source_type.as_str(),
             source.content_type,
-            source.learned_at.to_rfc3339(),
-            source.size_bytes as i64,
+            source.indexed_at.to_rfc3339(),
+            source.chunk_count as i64,
+            source.byte_count as i64,
+            tags_json(&source.added_tags)?,
+            tags_json(&source.removed_tags)?,
+            source.description,
         ],
     )
     .map_err(|e| AppError::Knowledge(format!("Failed to insert source: {}", e)))?;
@@ -229,6 +239,7 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::SourceType;
     use chrono::Utc;
     use tempfile::NamedTempFile;
 
@@ -256,12 +267,14 @@ mod tests {
 
         // Insert source
         let source = KnowledgeSource {
-            id: "source1".to_string(),
-            path: None,
-            url: None,
-            content_type: "text".to_string(),
-            learned_at: Utc::now(),
-            size_bytes: 100,
+            source_id: "source1".to_string(),
+            path: "notes.txt".to_string(),
+            source_type: SourceType::File,
+            content_type: Some("text".to_string()),
+            indexed_at: Utc::now(),
+            chunk_count: 1,
+            byte_count: 100,
+            ..Default::default()
         };
         insert_source(&conn, &source).unwrap();
 
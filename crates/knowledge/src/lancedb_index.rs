@@ -1,25 +1,125 @@
 //! LanceDB-backed vector index implementation.
-
+//!
+//! The layout of the chunks table is versioned ([`SCHEMA_VERSION`], stored in
+//! the table's schema metadata). Tables written by older versions are
+//! rewritten to the current layout when they are opened:
+//!
+//! 1. `id, source_id, position, text, embedding, metadata`: metadata only as
+//!    a JSON blob.
+//! 2. Structured metadata columns (source path, file type, language, tags,
+//!    timestamps), filled from top-level metadata keys only, so chunks
+//!    written by `learn` (which nests them under `custom`) had them empty.
+//! 3. Structured columns filled from top-level or `custom` metadata.
+//! 4. Structured columns are null when a field is absent, and plaintext rows
+//!    no longer repeat their values in the metadata blob; they are put back
+//!    into the chunk's metadata when it is read.
+//! 5. A `namespace` column (see [`LanceDbIndex::with_namespace`]), null for
+//!    chunks outside any namespace. It is stored in the clear, also for
+//!    encrypted bases, so searches and resets can filter on it.
+//! 6. A `title_embedding` column (see [`LanceDbIndex::with_title_weight`]).
+//!    Chunks without a title store their body embedding in it, so blended
+//!    scores fall back to the body score.
+
+use crate::encryption::Cipher;
+use crate::rag::SearchFilters;
 use crate::types::KnowledgeChunk;
 use crate::vector_index::VectorIndex;
+use arrow_array::builder::{
+    FixedSizeListBuilder, Float32Builder, Int64Builder, ListBuilder, StringBuilder, UInt32Builder,
+    UInt64Builder,
+};
 use arrow_array::{
-    Array, FixedSizeListArray, Int64Array, ListArray, RecordBatch, RecordBatchIterator,
+    Array, ArrayRef, FixedSizeListArray, Int64Array, ListArray, RecordBatch, RecordBatchIterator,
     StringArray, UInt32Array, UInt64Array,
 };
-use arrow_buffer::OffsetBuffer;
 use arrow_schema::{DataType, Field, Schema};
 use guided_core::{AppError, AppResult};
 use lancedb::query::{ExecutableQuery, QueryBase};
 use lancedb::Table;
-use std::collections::HashSet;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
+/// Current layout version of the chunks table.
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// Schema metadata key holding the layout version.
+const SCHEMA_VERSION_KEY: &str = "guided.schema_version";
+
+/// Ids per query in [`VectorIndex::fetch_chunks`], to keep predicates short.
+const FETCH_GROUP_SIZE: usize = 256;
+
+/// Search results with at least this many rows are converted to chunks on
+/// all cores; fewer convert faster on the calling thread.
+const PARALLEL_MIN_ROWS: usize = 64;
+
+/// Type of a structured metadata column.
+#[derive(Debug, Clone, Copy)]
+enum FieldKind {
+    Str,
+    U64,
+    I64,
+    Tags,
+}
+
+impl FieldKind {
+    /// Whether a metadata value can be stored in a column of this kind.
+    fn accepts(self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldKind::Str => value.is_string(),
+            FieldKind::U64 => value.is_u64(),
+            FieldKind::I64 => value.is_i64(),
+            FieldKind::Tags => value
+                .as_array()
+                .is_some_and(|tags| tags.iter().all(|t| t.is_string())),
+        }
+    }
+}
+
+/// Structured metadata columns, in schema order (Phase 5.5.1).
+const STRUCTURED_FIELDS: &[(&str, FieldKind)] = &[
+    ("source_path", FieldKind::Str),
+    ("file_name", FieldKind::Str),
+    ("file_type", FieldKind::Str),
+    ("language", FieldKind::Str),
+    ("file_size_bytes", FieldKind::U64),
+    ("file_line_count", FieldKind::U64),
+    ("file_modified_at", FieldKind::I64), // Unix timestamp
+    ("content_hash", FieldKind::Str),
+    ("tags", FieldKind::Tags),
+    ("created_at", FieldKind::I64), // Unix timestamp
+    ("updated_at", FieldKind::I64), // Unix timestamp
+];
+
+/// A chunk as stored, with text and metadata already sealed for encrypted bases.
+struct StoredRow {
+    id: String,
+    source_id: String,
+    position: u32,
+    text: String,
+    embedding: Vec<f32>,
+    title_embedding: Vec<f32>,
+    namespace: Option<String>,
+    metadata: String,
+}
+
 /// LanceDB-backed vector index for knowledge chunks.
+///
+/// Clones share the connection and table, so a clone can be scoped with
+/// the `with_*` methods without reopening the index.
+#[derive(Clone)]
 pub struct LanceDbIndex {
+    conn: lancedb::Connection,
+    table_name: String,
     table: Table,
     embedding_dim: usize,
     source_ids: HashSet<String>,
+    cipher: Option<Cipher>,
+    namespace: Option<String>,
+    sources: Option<Vec<String>>,
+    column_filter: Option<String>,
+    title_weight: f32,
 }
 
 impl LanceDbIndex {
@@ -29,6 +129,9 @@ impl LanceDbIndex {
     /// * `db_path` - Directory path for the LanceDB database
     /// * `table_name` - Name of the table (typically "chunks")
     /// * `embedding_dim` - Dimension of embedding vectors (e.g., 384)
+    ///
+    /// An existing table keeps the dimensions it was created with; compare
+    /// [`LanceDbIndex::dimensions`] against the provider's before using it.
     pub async fn new(db_path: &Path, table_name: &str, embedding_dim: usize) -> AppResult<Self> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
@@ -51,38 +154,695 @@ impl LanceDbIndex {
             .await
             .map_err(|e| AppError::Knowledge(format!("Failed to list tables: {}", e)))?;
 
-        let table = if table_names.contains(&table_name.to_string()) {
+        let (table, embedding_dim) = if table_names.contains(&table_name.to_string()) {
             // Open existing table
-            conn.open_table(table_name)
+            let table = conn
+                .open_table(table_name)
                 .execute()
                 .await
-                .map_err(|e| AppError::Knowledge(format!("Failed to open table: {}", e)))?
+                .map_err(|e| AppError::Knowledge(format!("Failed to open table: {}", e)))?;
+            let (stored_dim, version) = Self::read_layout(&table).await?;
+            if stored_dim != embedding_dim {
+                tracing::debug!(
+                    "Index at {:?} stores {}-dimensional embeddings (requested {})",
+                    db_path,
+                    stored_dim,
+                    embedding_dim
+                );
+            }
+
+            if version > SCHEMA_VERSION {
+                return Err(AppError::Knowledge(format!(
+                    "Index at {:?} uses schema version {}, newer than this build supports ({}); \
+                     upgrade guided",
+                    db_path, version, SCHEMA_VERSION
+                )));
+            }
+            let table = if version < SCHEMA_VERSION {
+                Self::upgrade(&conn, table_name, &table, version, stored_dim).await?
+            } else {
+                table
+            };
+            (table, stored_dim)
         } else {
             // Create new table with schema
-            let schema = Self::create_schema(embedding_dim);
-            let empty_batch = RecordBatch::new_empty(schema.clone());
-
-            conn.create_table(
-                table_name,
-                RecordBatchIterator::new(vec![Ok(empty_batch)], schema),
+            (
+                Self::create_table(&conn, table_name, embedding_dim).await?,
+                embedding_dim,
             )
-            .execute()
-            .await
-            .map_err(|e| AppError::Knowledge(format!("Failed to create table: {}", e)))?
         };
 
         tracing::debug!("Initialized LanceDB index at {:?}", db_path);
 
         Ok(Self {
+            conn,
+            table_name: table_name.to_string(),
             table,
             embedding_dim,
             source_ids: HashSet::new(),
+            cipher: None,
+            namespace: None,
+            sources: None,
+            column_filter: None,
+            title_weight: 0.0,
         })
     }
 
+    /// Whether the database at `db_path` has a table named `table_name`,
+    /// without creating either.
+    pub async fn has_table(db_path: &Path, table_name: &str) -> AppResult<bool> {
+        if !db_path.exists() {
+            return Ok(false);
+        }
+        let conn = lancedb::connect(&db_path.to_string_lossy())
+            .execute()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to connect to LanceDB: {}", e)))?;
+        let table_names = conn
+            .table_names()
+            .execute()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to list tables: {}", e)))?;
+        Ok(table_names.iter().any(|name| name == table_name))
+    }
+
+    /// Dimensions of the embedding vectors stored in the index.
+    pub fn dimensions(&self) -> usize {
+        self.embedding_dim
+    }
+
+    /// Drop all chunks and recreate the table for a new embedding dimension.
+    pub async fn recreate(&mut self, embedding_dim: usize) -> AppResult<()> {
+        self.conn
+            .drop_table(&self.table_name, &[])
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to drop table: {}", e)))?;
+        self.table = Self::create_table(&self.conn, &self.table_name, embedding_dim).await?;
+        self.embedding_dim = embedding_dim;
+        self.source_ids.clear();
+
+        tracing::info!(
+            "Recreated LanceDB index for {}-dimensional embeddings",
+            embedding_dim
+        );
+        Ok(())
+    }
+
+    /// Read every chunk in the index (e.g., to re-embed them).
+    pub async fn all_chunks(&self) -> AppResult<Vec<KnowledgeChunk>> {
+        self.query_chunks(None).await
+    }
+
+    /// Number of rows in the index, across namespaces.
+    pub async fn row_count(&self) -> AppResult<usize> {
+        self.table
+            .count_rows(None)
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to count rows: {}", e)))
+    }
+
+    /// Source id and embedding of the rows at `offsets`, reading only those
+    /// two columns (e.g., to calibrate thresholds from a sample).
+    pub async fn embeddings_at(&self, offsets: Vec<u64>) -> AppResult<Vec<(String, Vec<f32>)>> {
+        use futures::TryStreamExt;
+        use lancedb::query::Select;
+
+        if offsets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let batches = self
+            .table
+            .take_offsets(offsets)
+            .select(Select::columns(&["source_id", "embedding"]))
+            .execute()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to read embeddings: {}", e)))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to collect embeddings: {}", e)))?;
+
+        let mut embedded = Vec::new();
+        for batch in &batches {
+            let source_ids = batch
+                .column_by_name("source_id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| AppError::Knowledge("Invalid source_id column".to_string()))?;
+            let embeddings = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+                .ok_or_else(|| AppError::Knowledge("Invalid embedding column".to_string()))?;
+            for row_idx in 0..batch.num_rows() {
+                embedded.push((
+                    source_ids.value(row_idx).to_string(),
+                    vector_at(embeddings, row_idx)?,
+                ));
+            }
+        }
+        Ok(embedded)
+    }
+
+    /// All chunks learned from the file at `source_path` (as stored in the
+    /// chunk metadata).
+    ///
+    /// Plaintext bases filter on the `source_path` column; encrypted bases
+    /// keep metadata sealed, so every chunk is read and filtered after
+    /// decryption.
+    pub async fn chunks_for_source_path(
+        &self,
+        source_path: &str,
+    ) -> AppResult<Vec<KnowledgeChunk>> {
+        if self.cipher.is_some() {
+            let mut chunks = self.query_chunks(None).await?;
+            chunks.retain(|chunk| {
+                chunk
+                    .metadata_values("source_path")
+                    .any(|v| v.as_str() == Some(source_path))
+            });
+            return Ok(chunks);
+        }
+
+        let predicate = format!("source_path = '{}'", source_path.replace('\'', "''"));
+        self.query_chunks(Some(predicate)).await
+    }
+
+    /// Chunks learned from the file at `source_path` in the index's
+    /// namespace, scored against `query_embedding` as searches score them,
+    /// best first.
+    pub async fn score_source_path(
+        &self,
+        source_path: &str,
+        query_embedding: &[f32],
+    ) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
+        let mut chunks = self.chunks_for_source_path(source_path).await?;
+        if let Some(namespace) = &self.namespace {
+            chunks.retain(|chunk| {
+                chunk
+                    .metadata_values("namespace")
+                    .any(|v| v.as_str() == Some(namespace.as_str()))
+            });
+        }
+
+        let mut scored: Vec<(KnowledgeChunk, f32)> = chunks
+            .into_iter()
+            .filter_map(|chunk| {
+                let embedding = chunk.embedding.as_deref()?;
+                let title = chunk.title_embedding.as_deref().unwrap_or(embedding);
+                let score = self.score(query_embedding, embedding, title);
+                Some((chunk, score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored)
+    }
+
+    /// The chunks with these ids in the index's namespace, scored against
+    /// `query_embedding` as searches score them, best first.
+    pub async fn score_chunk_ids(
+        &self,
+        ids: &[String],
+        query_embedding: &[f32],
+    ) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<String> = ids
+            .iter()
+            .map(|id| format!("'{}'", id.replace('\'', "''")))
+            .collect();
+        let mut predicate = format!("id IN ({})", ids.join(", "));
+        if let Some(namespace) = self.namespace_predicate() {
+            predicate = format!("{} AND {}", predicate, namespace);
+        }
+
+        let mut scored: Vec<(KnowledgeChunk, f32)> = self
+            .query_chunks(Some(predicate))
+            .await?
+            .into_iter()
+            .filter_map(|chunk| {
+                let embedding = chunk.embedding.as_deref()?;
+                let title = chunk.title_embedding.as_deref().unwrap_or(embedding);
+                let score = self.score(query_embedding, embedding, title);
+                Some((chunk, score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored)
+    }
+
+    /// Overwrite stored chunks with the same ids, keeping one row per chunk.
+    ///
+    /// `upsert_chunks` only appends; this is for rewriting the metadata of
+    /// chunks that are already indexed.
+    pub async fn update_chunks(&self, chunks: &[KnowledgeChunk]) -> AppResult<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self.chunks_to_batch(chunks)?;
+        let schema = batch.schema();
+        let mut merge = self.table.merge_insert(&["id"]);
+        merge.when_matched_update_all(None);
+        merge
+            .execute(Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema)))
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to update chunks: {}", e)))?;
+
+        tracing::debug!("Updated {} chunks in LanceDB", chunks.len());
+        Ok(())
+    }
+
+    /// Delete every chunk of the given sources.
+    pub async fn delete_sources(&mut self, source_ids: &[String]) -> AppResult<()> {
+        if source_ids.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<String> = source_ids
+            .iter()
+            .map(|id| format!("'{}'", id.replace('\'', "''")))
+            .collect();
+        let predicate = format!("source_id IN ({})", ids.join(", "));
+        self.table
+            .delete(&predicate)
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to delete chunks: {}", e)))?;
+        for id in source_ids {
+            self.source_ids.remove(id);
+        }
+
+        tracing::debug!(
+            "Deleted the chunks of {} sources from LanceDB",
+            source_ids.len()
+        );
+        Ok(())
+    }
+
+    /// Convert chunks to a single RecordBatch, appending each chunk to the
+    /// column builders in turn.
+    fn chunks_to_batch(&self, chunks: &[KnowledgeChunk]) -> AppResult<RecordBatch> {
+        let mut builder = BatchBuilder::new(self.embedding_dim, chunks.len());
+        for chunk in chunks {
+            let embedding = chunk
+                .embedding
+                .as_ref()
+                .ok_or_else(|| AppError::Knowledge("Chunk missing embedding".to_string()))?;
+
+            // The namespace lives in its own column, not in the metadata blob
+            let mut metadata = chunk.metadata.clone();
+            let namespace = match metadata.as_object_mut().and_then(|m| m.remove("namespace")) {
+                Some(serde_json::Value::String(namespace)) => Some(namespace),
+                _ => self.namespace.clone(),
+            };
+
+            // Encrypted bases keep the structured fields in the sealed blob
+            let (structured, metadata) = if self.cipher.is_some() {
+                let metadata_json = serde_json::to_string(&metadata).map_err(|e| {
+                    AppError::Knowledge(format!("Failed to serialize metadata: {}", e))
+                })?;
+                (Vec::new(), self.seal(&metadata_json)?)
+            } else {
+                (take_structured(&mut metadata), metadata.to_string())
+            };
+
+            builder.append(
+                Row {
+                    id: &chunk.id,
+                    source_id: &chunk.source_id,
+                    position: chunk.position,
+                    text: &self.seal(&chunk.text)?,
+                    embedding,
+                    title_embedding: chunk.title_embedding.as_deref().unwrap_or(embedding),
+                    namespace: namespace.as_deref(),
+                    metadata: &metadata,
+                },
+                &structured,
+            )?;
+        }
+        builder.finish()
+    }
+
+    /// Read every chunk matching `predicate` (all chunks when `None`).
+    async fn query_chunks(&self, predicate: Option<String>) -> AppResult<Vec<KnowledgeChunk>> {
+        use futures::TryStreamExt;
+
+        let mut query = self.table.query();
+        if let Some(predicate) = predicate {
+            query = query.only_if(predicate);
+        }
+        let batches = query
+            .execute()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to read chunks: {}", e)))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to collect chunks: {}", e)))?;
+
+        let mut chunks = Vec::new();
+        for batch in &batches {
+            for row_idx in 0..batch.num_rows() {
+                chunks.push(self.batch_to_chunk(batch, row_idx)?);
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Nearest neighbours by body embedding, and by title embedding when
+    /// titles are weighted, loading only `columns` when given. Rows found by
+    /// both queries appear twice.
+    fn nearest_batches(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        columns: Option<&[&str]>,
+    ) -> AppResult<Vec<RecordBatch>> {
+        let mut batches = self.nearest_batches_by(query_embedding, top_k, columns, "embedding")?;
+        if self.title_weight > 0.0 {
+            batches.extend(self.nearest_batches_by(
+                query_embedding,
+                top_k,
+                columns,
+                "title_embedding",
+            )?);
+        }
+        Ok(batches)
+    }
+
+    /// Run a nearest-neighbour query on one vector column.
+    fn nearest_batches_by(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        columns: Option<&[&str]>,
+        vector_column: &str,
+    ) -> AppResult<Vec<RecordBatch>> {
+        use futures::TryStreamExt;
+        use lancedb::query::Select;
+
+        if query_embedding.len() != self.embedding_dim {
+            return Err(AppError::Knowledge(format!(
+                "Query embedding dimension mismatch: expected {}, got {}",
+                self.embedding_dim,
+                query_embedding.len()
+            )));
+        }
+
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                let mut query = self
+                    .table
+                    .query()
+                    .nearest_to(query_embedding.to_vec())
+                    .map_err(|e| AppError::Knowledge(format!("Failed to create query: {}", e)))?
+                    .column(vector_column)
+                    .limit(top_k);
+                if let Some(predicate) = self.search_predicate() {
+                    query = query.only_if(predicate);
+                }
+                if let Some(columns) = columns {
+                    query = query.select(Select::columns(columns));
+                }
+
+                query
+                    .execute()
+                    .await
+                    .map_err(|e| AppError::Knowledge(format!("Failed to execute search: {}", e)))?
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .map_err(|e| AppError::Knowledge(format!("Failed to collect results: {}", e)))
+            })
+        })
+    }
+
+    /// Create an empty chunks table.
+    async fn create_table(
+        conn: &lancedb::Connection,
+        table_name: &str,
+        embedding_dim: usize,
+    ) -> AppResult<Table> {
+        let schema = Self::create_schema(embedding_dim);
+        let empty_batch = RecordBatch::new_empty(schema.clone());
+
+        conn.create_table(
+            table_name,
+            RecordBatchIterator::new(vec![Ok(empty_batch)], schema),
+        )
+        .execute()
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Failed to create table: {}", e)))
+    }
+
+    /// Read the embedding dimension and layout version of an existing table.
+    ///
+    /// Tables from before versioning are told apart by their columns.
+    async fn read_layout(table: &Table) -> AppResult<(usize, u32)> {
+        let schema = table
+            .schema()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to read table schema: {}", e)))?;
+
+        let embedding_dim = match schema.field_with_name("embedding").map(|f| f.data_type()) {
+            Ok(DataType::FixedSizeList(_, size)) => *size as usize,
+            _ => {
+                return Err(AppError::Knowledge(
+                    "Index has no fixed-size embedding column".to_string(),
+                ))
+            }
+        };
+
+        let version = match schema.metadata().get(SCHEMA_VERSION_KEY) {
+            Some(version) => version.parse().map_err(|_| {
+                AppError::Knowledge(format!("Invalid index schema version '{}'", version))
+            })?,
+            None if schema.field_with_name("source_path").is_ok() => 2,
+            None => 1,
+        };
+
+        Ok((embedding_dim, version))
+    }
+
+    /// Rewrite a table from an older layout version to the current one.
+    ///
+    /// Stored values are carried over as they are, so encrypted bases are
+    /// upgraded without their key. The rewrite is a single overwrite commit:
+    /// if it fails, the old table is left untouched.
+    async fn upgrade(
+        conn: &lancedb::Connection,
+        table_name: &str,
+        table: &Table,
+        version: u32,
+        embedding_dim: usize,
+    ) -> AppResult<Table> {
+        use futures::TryStreamExt;
+        use lancedb::database::CreateTableMode;
+
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to read chunks: {}", e)))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| AppError::Knowledge(format!("Failed to collect chunks: {}", e)))?;
+
+        let mut rows = Vec::new();
+        for batch in &batches {
+            rows.extend(Self::read_stored_rows(batch, version)?);
+        }
+
+        tracing::info!(
+            "Upgrading index table '{}' from schema version {} to {} ({} chunks)",
+            table_name,
+            version,
+            SCHEMA_VERSION,
+            rows.len()
+        );
+
+        let schema = Self::create_schema(embedding_dim);
+        let batch = Self::build_batch(embedding_dim, &rows)?;
+        conn.create_table(
+            table_name,
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+        )
+        .mode(CreateTableMode::Overwrite)
+        .execute()
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Failed to upgrade index table: {}", e)))
+    }
+
+    /// Read the columns common to every layout version, by name.
+    ///
+    /// From version 4 on, structured column values are merged back into
+    /// plaintext metadata so the rows can be rewritten in any layout.
+    fn read_stored_rows(batch: &RecordBatch, version: u32) -> AppResult<Vec<StoredRow>> {
+        let strings = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| AppError::Knowledge(format!("Invalid {} column", name)))
+        };
+        let ids = strings("id")?;
+        let source_ids = strings("source_id")?;
+        let texts = strings("text")?;
+        let metadata = strings("metadata")?;
+        let positions = batch
+            .column_by_name("position")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>())
+            .ok_or_else(|| AppError::Knowledge("Invalid position column".to_string()))?;
+        let embeddings = batch
+            .column_by_name("embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            .ok_or_else(|| AppError::Knowledge("Invalid embedding column".to_string()))?;
+        // Only in version 6 and later
+        let title_embeddings = batch
+            .column_by_name("title_embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>());
+        // Only in version 5 and later
+        let namespaces = batch
+            .column_by_name("namespace")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
+        (0..batch.num_rows())
+            .map(|row_idx| {
+                let embedding = vector_at(embeddings, row_idx)?;
+                let title_embedding = match title_embeddings {
+                    Some(column) => vector_at(column, row_idx)?,
+                    None => embedding.clone(),
+                };
+
+                let mut stored_metadata = metadata.value(row_idx).to_string();
+                if version >= 4 && !Cipher::is_encrypted(&stored_metadata) {
+                    if let Ok(mut value) = serde_json::from_str(&stored_metadata) {
+                        restore_structured(batch, row_idx, &mut value)?;
+                        stored_metadata = value.to_string();
+                    }
+                }
+
+                Ok(StoredRow {
+                    id: ids.value(row_idx).to_string(),
+                    source_id: source_ids.value(row_idx).to_string(),
+                    position: positions.value(row_idx),
+                    text: texts.value(row_idx).to_string(),
+                    embedding,
+                    title_embedding,
+                    namespace: namespaces
+                        .filter(|c| !c.is_null(row_idx))
+                        .map(|c| c.value(row_idx).to_string()),
+                    metadata: stored_metadata,
+                })
+            })
+            .collect()
+    }
+
+    /// Encrypt chunk text and metadata with `cipher` (see [`crate::encryption`]).
+    ///
+    /// Structured metadata columns are left null for encrypted chunks so file
+    /// paths and names are not stored in the clear.
+    pub fn with_cipher(mut self, cipher: Option<Cipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Scope the index to a namespace of the base.
+    ///
+    /// Searches only return chunks of the namespace, [`VectorIndex::reset`]
+    /// only removes them, and chunks written without a `namespace` metadata
+    /// key are stored in it. `None` leaves the index unscoped.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// Restrict searches to the chunks of these sources (by source id).
+    /// `None` searches every source.
+    pub fn with_sources(mut self, source_ids: Option<Vec<String>>) -> Self {
+        self.sources = source_ids;
+        self
+    }
+
+    /// Restrict searches to the chunks whose source path and content kind
+    /// pass `filters` (see [`SearchFilters::column_predicate`]). Encrypted
+    /// bases keep metadata out of the columns, so their chunks are filtered
+    /// after retrieval instead.
+    pub fn with_filters(mut self, filters: &SearchFilters) -> Self {
+        self.column_filter = filters.column_predicate();
+        self
+    }
+
+    /// Blend title similarity into search scores with weight `title_weight`
+    /// (0 to 1): `(1 - w) * body + w * title`. Searches also look up
+    /// nearest titles, so chunks whose title matches are found even when
+    /// their body does not. 0 (the default) scores bodies only.
+    pub fn with_title_weight(mut self, title_weight: f32) -> Self {
+        self.title_weight = title_weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Similarity of a chunk to the query, blending in its title.
+    fn score(&self, query_embedding: &[f32], embedding: &[f32], title_embedding: &[f32]) -> f32 {
+        let body = cosine_similarity(query_embedding, embedding);
+        if self.title_weight == 0.0 {
+            return body;
+        }
+        let title = cosine_similarity(query_embedding, title_embedding);
+        (1.0 - self.title_weight) * body + self.title_weight * title
+    }
+
+    /// Filter matching the chunks of the index's namespace, if scoped.
+    fn namespace_predicate(&self) -> Option<String> {
+        self.namespace
+            .as_ref()
+            .map(|namespace| format!("namespace = '{}'", namespace.replace('\'', "''")))
+    }
+
+    /// Filter for searches: the namespace, the sources and the column
+    /// filters, if restricted.
+    fn search_predicate(&self) -> Option<String> {
+        let sources = self.sources.as_ref().map(|source_ids| {
+            format!(
+                "source_id IN ({})",
+                source_ids
+                    .iter()
+                    .map(|id| format!("'{}'", id.replace('\'', "''")))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        });
+        let columns = self.column_filter.clone().filter(|_| self.cipher.is_none());
+        let conditions: Vec<String> = [self.namespace_predicate(), sources, columns]
+            .into_iter()
+            .flatten()
+            .collect();
+        (!conditions.is_empty()).then(|| conditions.join(" AND "))
+    }
+
+    /// Encrypt a column value if the index has a cipher.
+    fn seal(&self, value: &str) -> AppResult<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(value),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypt a column value written by [`LanceDbIndex::seal`].
+    fn open(&self, value: &str) -> AppResult<String> {
+        if !Cipher::is_encrypted(value) {
+            return Ok(value.to_string());
+        }
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(value),
+            None => Err(AppError::Knowledge(
+                "Chunk is encrypted but no key was provided".to_string(),
+            )),
+        }
+    }
+
     /// Create Arrow schema for chunks table with structured metadata (Phase 5.5.1).
     fn create_schema(embedding_dim: usize) -> Arc<Schema> {
-        Arc::new(Schema::new(vec![
+        let metadata =
+            HashMap::from([(SCHEMA_VERSION_KEY.to_string(), SCHEMA_VERSION.to_string())]);
+
+        let mut fields = vec![
             // Core fields
             Field::new("id", DataType::Utf8, false),
             Field::new("source_id", DataType::Utf8, false),
@@ -96,238 +856,173 @@ impl LanceDbIndex {
                 ),
                 false,
             ),
-            // Structured metadata fields (Phase 5.5.1)
-            Field::new("source_path", DataType::Utf8, true),
-            Field::new("file_name", DataType::Utf8, true),
-            Field::new("file_type", DataType::Utf8, true),
-            Field::new("language", DataType::Utf8, true),
-            Field::new("file_size_bytes", DataType::UInt64, true),
-            Field::new("file_line_count", DataType::UInt64, true),
-            Field::new("file_modified_at", DataType::Int64, true), // Unix timestamp
-            Field::new("content_hash", DataType::Utf8, true),
             Field::new(
-                "tags",
-                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
-                true,
+                "title_embedding",
+                DataType::FixedSizeList(
+                    Arc::new(Field::new("item", DataType::Float32, true)),
+                    embedding_dim as i32,
+                ),
+                false,
             ),
-            Field::new("created_at", DataType::Int64, true), // Unix timestamp
-            Field::new("updated_at", DataType::Int64, true), // Unix timestamp
-            // Legacy metadata field for backward compatibility
-            Field::new("metadata", DataType::Utf8, false),
-        ]))
-    }
+            Field::new("namespace", DataType::Utf8, true),
+        ];
+
+        // Structured metadata fields
+        fields.extend(STRUCTURED_FIELDS.iter().map(|(name, kind)| {
+            let data_type = match kind {
+                FieldKind::Str => DataType::Utf8,
+                FieldKind::U64 => DataType::UInt64,
+                FieldKind::I64 => DataType::Int64,
+                FieldKind::Tags => {
+                    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)))
+                }
+            };
+            Field::new(*name, data_type, true)
+        }));
 
-    /// Convert KnowledgeChunk to Arrow RecordBatch.
-    fn chunk_to_batch(&self, chunk: &KnowledgeChunk) -> AppResult<RecordBatch> {
-        let schema = Self::create_schema(self.embedding_dim);
+        // Legacy metadata field for backward compatibility
+        fields.push(Field::new("metadata", DataType::Utf8, false));
 
-        let embedding = chunk
-            .embedding
-            .as_ref()
-            .ok_or_else(|| AppError::Knowledge("Chunk missing embedding".to_string()))?;
+        Arc::new(Schema::new_with_metadata(fields, metadata))
+    }
 
-        if embedding.len() != self.embedding_dim {
-            return Err(AppError::Knowledge(format!(
-                "Embedding dimension mismatch: expected {}, got {}",
-                self.embedding_dim,
-                embedding.len()
-            )));
+    /// Build a RecordBatch in the current layout from stored rows.
+    ///
+    /// Structured fields are moved from plaintext metadata into their
+    /// columns; encrypted rows keep them in the sealed blob and leave the
+    /// columns null.
+    fn build_batch(embedding_dim: usize, rows: &[StoredRow]) -> AppResult<RecordBatch> {
+        let mut builder = BatchBuilder::new(embedding_dim, rows.len());
+        for row in rows {
+            let parsed = if Cipher::is_encrypted(&row.metadata) {
+                None
+            } else {
+                serde_json::from_str::<serde_json::Value>(&row.metadata).ok()
+            };
+            let (structured, metadata) = match parsed {
+                Some(mut metadata) => (take_structured(&mut metadata), metadata.to_string()),
+                None => (Vec::new(), row.metadata.clone()),
+            };
+            builder.append(
+                Row {
+                    id: &row.id,
+                    source_id: &row.source_id,
+                    position: row.position,
+                    text: &row.text,
+                    embedding: &row.embedding,
+                    title_embedding: &row.title_embedding,
+                    namespace: row.namespace.as_deref(),
+                    metadata: &metadata,
+                },
+                &structured,
+            )?;
         }
+        builder.finish()
+    }
 
-        let metadata_json = serde_json::to_string(&chunk.metadata)
-            .map_err(|e| AppError::Knowledge(format!("Failed to serialize metadata: {}", e)))?;
+    /// Convert a row of a search result and score it against the query;
+    /// `None` if the row cannot be read, which is logged.
+    fn scored_row(
+        &self,
+        batch: &RecordBatch,
+        batch_idx: usize,
+        row_idx: usize,
+        query_embedding: &[f32],
+    ) -> Option<(KnowledgeChunk, f32)> {
+        let chunk = match self.batch_to_chunk(batch, row_idx) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to convert row {} of batch {} to chunk: {}",
+                    row_idx,
+                    batch_idx,
+                    e
+                );
+                return None;
+            }
+        };
 
-        // Create core arrays
-        let id_array = StringArray::from(vec![chunk.id.as_str()]);
-        let source_id_array = StringArray::from(vec![chunk.source_id.as_str()]);
-        let position_array = UInt32Array::from(vec![chunk.position]);
-        let text_array = StringArray::from(vec![chunk.text.as_str()]);
+        let score = match &chunk.embedding {
+            Some(embedding) => {
+                let title = chunk.title_embedding.as_deref().unwrap_or(embedding);
+                self.score(query_embedding, embedding, title)
+            }
+            None => {
+                tracing::warn!("Chunk {} has no embedding - score will be 0.0", chunk.id);
+                0.0
+            }
+        };
 
-        // Create embedding as FixedSizeListArray
-        let embedding_values = arrow_array::Float32Array::from(embedding.clone());
-        let embedding_array = FixedSizeListArray::new(
-            Arc::new(Field::new("item", DataType::Float32, true)),
-            self.embedding_dim as i32,
-            Arc::new(embedding_values),
-            None,
+        // Per-row logs cost more than the conversion on large top-k values
+        #[cfg(feature = "row-tracing")]
+        tracing::trace!(
+            "Row {} of batch {}: chunk '{}' score {:.4}",
+            row_idx,
+            batch_idx,
+            chunk.text.chars().take(50).collect::<String>(),
+            score
         );
 
-        // Extract structured metadata from chunk.metadata JSON
-        let source_path = chunk
-            .metadata
-            .get("source_path")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        let file_name = chunk
-            .metadata
-            .get("file_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        let file_type = chunk
-            .metadata
-            .get("file_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        let language = chunk
-            .metadata
-            .get("language")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        let file_size_bytes = chunk
-            .metadata
-            .get("file_size_bytes")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let file_line_count = chunk
-            .metadata
-            .get("file_line_count")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-        let file_modified_at = chunk
-            .metadata
-            .get("file_modified_at")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0);
-        let content_hash = chunk
-            .metadata
-            .get("content_hash")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        let created_at = chunk
-            .metadata
-            .get("created_at")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0);
-        let updated_at = chunk
-            .metadata
-            .get("updated_at")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0);
-
-        // Create structured metadata arrays
-        let source_path_array = StringArray::from(vec![source_path]);
-        let file_name_array = StringArray::from(vec![file_name]);
-        let file_type_array = StringArray::from(vec![file_type]);
-        let language_array = StringArray::from(vec![language]);
-        let file_size_bytes_array = UInt64Array::from(vec![file_size_bytes]);
-        let file_line_count_array = UInt64Array::from(vec![file_line_count]);
-        let file_modified_at_array = Int64Array::from(vec![file_modified_at]);
-        let content_hash_array = StringArray::from(vec![content_hash]);
-        let created_at_array = Int64Array::from(vec![created_at]);
-        let updated_at_array = Int64Array::from(vec![updated_at]);
-
-        // Create tags array (List of strings)
-        let tags: Vec<Option<&str>> = chunk
-            .metadata
-            .get("tags")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .map(|v| v.as_str())
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
-        let tags_values = StringArray::from(tags);
-        let tags_count = tags_values.len();
-        let tags_offsets = vec![0_i32, tags_count as i32];
-        let tags_array = ListArray::try_new(
-            Arc::new(Field::new("item", DataType::Utf8, true)),
-            OffsetBuffer::new(tags_offsets.into()),
-            Arc::new(tags_values),
-            None,
-        )
-        .map_err(|e| AppError::Knowledge(format!("Failed to create tags array: {}", e)))?;
-
-        // Legacy metadata field
-        let metadata_array = StringArray::from(vec![metadata_json.as_str()]);
-
-        RecordBatch::try_new(
-            schema,
-            vec![
-                // Core fields
-                Arc::new(id_array),
-                Arc::new(source_id_array),
-                Arc::new(position_array),
-                Arc::new(text_array),
-                Arc::new(embedding_array),
-                // Structured metadata
-                Arc::new(source_path_array),
-                Arc::new(file_name_array),
-                Arc::new(file_type_array),
-                Arc::new(language_array),
-                Arc::new(file_size_bytes_array),
-                Arc::new(file_line_count_array),
-                Arc::new(file_modified_at_array),
-                Arc::new(content_hash_array),
-                Arc::new(tags_array),
-                Arc::new(created_at_array),
-                Arc::new(updated_at_array),
-                // Legacy metadata
-                Arc::new(metadata_array),
-            ],
-        )
-        .map_err(|e| AppError::Knowledge(format!("Failed to create RecordBatch: {}", e)))
+        Some((chunk, score))
     }
 
     /// Convert Arrow RecordBatch row to KnowledgeChunk.
     fn batch_to_chunk(&self, batch: &RecordBatch, row_idx: usize) -> AppResult<KnowledgeChunk> {
-        let id = batch
-            .column(0)
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| AppError::Knowledge("Invalid id column".to_string()))?
-            .value(row_idx)
-            .to_string();
+        let strings = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| AppError::Knowledge(format!("Invalid {} column", name)))
+        };
 
-        let source_id = batch
-            .column(1)
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| AppError::Knowledge("Invalid source_id column".to_string()))?
-            .value(row_idx)
-            .to_string();
+        let id = strings("id")?.value(row_idx).to_string();
+        let source_id = strings("source_id")?.value(row_idx).to_string();
 
         let position = batch
-            .column(2)
-            .as_any()
-            .downcast_ref::<UInt32Array>()
+            .column_by_name("position")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>())
             .ok_or_else(|| AppError::Knowledge("Invalid position column".to_string()))?
             .value(row_idx);
 
-        let text = batch
-            .column(3)
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| AppError::Knowledge("Invalid text column".to_string()))?
-            .value(row_idx)
-            .to_string();
+        let text = self.open(strings("text")?.value(row_idx))?;
 
         let embedding_list = batch
-            .column(4)
-            .as_any()
-            .downcast_ref::<FixedSizeListArray>()
+            .column_by_name("embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
             .ok_or_else(|| AppError::Knowledge("Invalid embedding column".to_string()))?;
+        let embedding = vector_at(embedding_list, row_idx)?;
+
+        // Untitled chunks store their body embedding as title
+        let title_embedding = match batch
+            .column_by_name("title_embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+        {
+            Some(column) => Some(vector_at(column, row_idx)?).filter(|t| *t != embedding),
+            None => None,
+        };
 
-        let embedding_array_ref = embedding_list.value(row_idx);
-        let embedding_values = embedding_array_ref
-            .as_any()
-            .downcast_ref::<arrow_array::Float32Array>()
-            .ok_or_else(|| AppError::Knowledge("Invalid embedding values".to_string()))?;
-
-        let embedding: Vec<f32> = (0..embedding_values.len())
-            .map(|i| embedding_values.value(i))
-            .collect();
+        // Legacy metadata blob, plus the structured columns moved out of it
+        let stored_metadata = strings("metadata")?.value(row_idx);
+        let encrypted = Cipher::is_encrypted(stored_metadata);
+        let metadata_json = self.open(stored_metadata)?;
 
-        // Read legacy metadata field (now at column 16)
-        let metadata_json = batch
-            .column(16)
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| AppError::Knowledge("Invalid metadata column".to_string()))?
-            .value(row_idx);
-
-        let metadata: serde_json::Value = serde_json::from_str(metadata_json)
+        let mut metadata: serde_json::Value = serde_json::from_str(&metadata_json)
             .map_err(|e| AppError::Knowledge(format!("Failed to parse metadata: {}", e)))?;
+        if !encrypted {
+            restore_structured(batch, row_idx, &mut metadata)?;
+        }
+        if let Some(namespaces) = batch
+            .column_by_name("namespace")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .filter(|c| !c.is_null(row_idx))
+        {
+            if let Some(metadata) = metadata.as_object_mut() {
+                metadata.insert(
+                    "namespace".to_string(),
+                    serde_json::json!(namespaces.value(row_idx)),
+                );
+            }
+        }
 
         Ok(KnowledgeChunk {
             id,
@@ -335,6 +1030,7 @@ impl LanceDbIndex {
             position,
             text,
             embedding: Some(embedding),
+            title_embedding,
             metadata,
         })
     }
@@ -345,7 +1041,7 @@ impl VectorIndex for LanceDbIndex {
         // Track source ID
         self.source_ids.insert(chunk.source_id.clone());
 
-        let batch = self.chunk_to_batch(chunk)?;
+        let batch = self.chunks_to_batch(std::slice::from_ref(chunk))?;
 
         // Use blocking runtime for sync trait
         tokio::task::block_in_place(|| {
@@ -376,20 +1072,7 @@ impl VectorIndex for LanceDbIndex {
         }
 
         // Convert all chunks to a single RecordBatch
-        let batches: Vec<RecordBatch> = chunks
-            .iter()
-            .map(|chunk| self.chunk_to_batch(chunk))
-            .collect::<AppResult<Vec<_>>>()?;
-
-        // Combine batches if needed
-        let combined_batch = if batches.len() == 1 {
-            batches.into_iter().next().unwrap()
-        } else {
-            // Concatenate multiple batches
-            let schema = batches[0].schema();
-            arrow_select::concat::concat_batches(&schema, &batches)
-                .map_err(|e| AppError::Knowledge(format!("Failed to concat batches: {}", e)))?
-        };
+        let combined_batch = self.chunks_to_batch(chunks)?;
 
         // Single insert operation for all chunks
         tokio::task::block_in_place(|| {
@@ -415,68 +1098,40 @@ impl VectorIndex for LanceDbIndex {
         query_embedding: &[f32],
         top_k: usize,
     ) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
-        if query_embedding.len() != self.embedding_dim {
-            return Err(AppError::Knowledge(format!(
-                "Query embedding dimension mismatch: expected {}, got {}",
-                self.embedding_dim,
-                query_embedding.len()
-            )));
-        }
-
-        let query_vec = query_embedding.to_vec();
-        let batches = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                use futures::TryStreamExt;
+        let batches = self.nearest_batches(query_embedding, top_k, None)?;
 
-                self.table
-                    .query()
-                    .nearest_to(query_vec.clone())
-                    .map_err(|e| AppError::Knowledge(format!("Failed to create query: {}", e)))?
-                    .limit(top_k)
-                    .execute()
-                    .await
-                    .map_err(|e| AppError::Knowledge(format!("Failed to execute search: {}", e)))?
-                    .try_collect::<Vec<_>>()
-                    .await
-                    .map_err(|e| AppError::Knowledge(format!("Failed to collect results: {}", e)))
-            })
-        })?;
-
-        let mut chunks_with_scores = Vec::new();
-
-        // Process batches
-        tracing::debug!("Processing {} batches from LanceDB", batches.len());
-        for (batch_idx, batch) in batches.iter().enumerate() {
-            tracing::debug!("Batch {} has {} rows", batch_idx, batch.num_rows());
-            for row_idx in 0..batch.num_rows() {
-                tracing::debug!("Processing row {} of batch {}", row_idx, batch_idx);
-                let chunk = match self.batch_to_chunk(batch, row_idx) {
-                    Ok(c) => {
-                        tracing::debug!("Successfully converted row {} to chunk", row_idx);
-                        c
-                    },
-                    Err(e) => {
-                        tracing::warn!("Failed to convert batch row {} to chunk: {}", row_idx, e);
-                        continue;
-                    }
-                };
-
-                // Calculate cosine similarity score
-                let score = if let Some(embedding) = &chunk.embedding {
-                    cosine_similarity(query_embedding, embedding)
-                } else {
-                    tracing::warn!("Chunk has no embedding - score will be 0.0");
-                    0.0
-                };
+        // Rows across all batches, converted (and decrypted) in parallel
+        // when there are enough of them to be worth it
+        let rows: Vec<(usize, usize)> = batches
+            .iter()
+            .enumerate()
+            .flat_map(|(batch_idx, batch)| (0..batch.num_rows()).map(move |row| (batch_idx, row)))
+            .collect();
+        tracing::debug!(
+            "Converting {} rows from {} LanceDB batches",
+            rows.len(),
+            batches.len()
+        );
+        let convert = |&(batch_idx, row_idx): &(usize, usize)| {
+            self.scored_row(&batches[batch_idx], batch_idx, row_idx, query_embedding)
+        };
+        let converted: Vec<Option<(KnowledgeChunk, f32)>> = if rows.len() >= PARALLEL_MIN_ROWS {
+            rows.par_iter().map(convert).collect()
+        } else {
+            rows.iter().map(convert).collect()
+        };
 
-                tracing::debug!("Chunk '{}' score: {:.4}", chunk.text.chars().take(50).collect::<String>(), score);
+        let mut chunks_with_scores = Vec::with_capacity(converted.len());
+        let mut seen = HashSet::with_capacity(converted.len());
+        for (chunk, score) in converted.into_iter().flatten() {
+            if seen.insert(chunk.id.clone()) {
                 chunks_with_scores.push((chunk, score));
             }
         }
 
-        // Sort by score descending
-        chunks_with_scores
-            .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort by score descending, ties by ID so results are reproducible
+        chunks_with_scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+        chunks_with_scores.truncate(top_k);
 
         tracing::debug!(
             "Retrieved {} chunks (requested top-{})",
@@ -487,6 +1142,86 @@ impl VectorIndex for LanceDbIndex {
         Ok(chunks_with_scores)
     }
 
+    fn search_ids(&self, query_embedding: &[f32], top_k: usize) -> AppResult<Vec<(String, f32)>> {
+        let batches = self.nearest_batches(
+            query_embedding,
+            top_k,
+            Some(&["id", "embedding", "title_embedding"]),
+        )?;
+
+        let mut ids_with_scores = Vec::new();
+        let mut seen = HashSet::new();
+        for batch in &batches {
+            let ids = batch
+                .column_by_name("id")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| AppError::Knowledge("Invalid id column".to_string()))?;
+            let embeddings = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+                .ok_or_else(|| AppError::Knowledge("Invalid embedding column".to_string()))?;
+            let title_embeddings = batch
+                .column_by_name("title_embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+                .ok_or_else(|| AppError::Knowledge("Invalid title_embedding column".to_string()))?;
+
+            for row_idx in 0..batch.num_rows() {
+                let id = ids.value(row_idx);
+                if !seen.insert(id.to_string()) {
+                    continue;
+                }
+                let embedding = vector_at(embeddings, row_idx)?;
+                let title_embedding = vector_at(title_embeddings, row_idx)?;
+                let score = self.score(query_embedding, &embedding, &title_embedding);
+                ids_with_scores.push((id.to_string(), score));
+            }
+        }
+
+        ids_with_scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ids_with_scores.truncate(top_k);
+        Ok(ids_with_scores)
+    }
+
+    fn fetch_chunks(&self, ids: &[String]) -> AppResult<Vec<KnowledgeChunk>> {
+        use futures::TryStreamExt;
+
+        let mut by_id = HashMap::with_capacity(ids.len());
+        for group in ids.chunks(FETCH_GROUP_SIZE) {
+            let predicate = format!(
+                "id IN ({})",
+                group
+                    .iter()
+                    .map(|id| format!("'{}'", id.replace('\'', "''")))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let batches = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    self.table
+                        .query()
+                        .only_if(predicate)
+                        .execute()
+                        .await
+                        .map_err(|e| AppError::Knowledge(format!("Failed to fetch chunks: {}", e)))?
+                        .try_collect::<Vec<_>>()
+                        .await
+                        .map_err(|e| {
+                            AppError::Knowledge(format!("Failed to collect chunks: {}", e))
+                        })
+                })
+            })?;
+
+            for batch in &batches {
+                for row_idx in 0..batch.num_rows() {
+                    let chunk = self.batch_to_chunk(batch, row_idx)?;
+                    by_id.insert(chunk.id.clone(), chunk);
+                }
+            }
+        }
+
+        Ok(ids.iter().filter_map(|id| by_id.remove(id)).collect())
+    }
+
     fn stats(&self) -> AppResult<(u32, u32)> {
         let count = tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
@@ -509,11 +1244,13 @@ impl VectorIndex for LanceDbIndex {
             tokio::runtime::Handle::current().block_on(async {
                 // LanceDB doesn't have a direct drop table method in the public API
                 // We'll delete all rows instead
-                let count = self.table.count_rows(None).await.unwrap_or(0);
+                let predicate = self.namespace_predicate();
+                let count = self.table.count_rows(predicate.clone()).await.unwrap_or(0);
 
                 if count > 0 {
-                    // Delete all rows by creating a predicate that matches everything
-                    self.table.delete("id IS NOT NULL").await.map_err(|e| {
+                    // Delete all rows (of the namespace) with a predicate
+                    let predicate = predicate.unwrap_or_else(|| "id IS NOT NULL".to_string());
+                    self.table.delete(&predicate).await.map_err(|e| {
                         AppError::Knowledge(format!("Failed to reset index: {}", e))
                     })?;
                 }
@@ -523,7 +1260,10 @@ impl VectorIndex for LanceDbIndex {
         })?;
 
         self.source_ids.clear();
-        tracing::info!("Reset LanceDB index");
+        match &self.namespace {
+            Some(namespace) => tracing::info!("Reset namespace '{}' of LanceDB index", namespace),
+            None => tracing::info!("Reset LanceDB index"),
+        }
 
         Ok(())
     }
@@ -534,8 +1274,255 @@ impl VectorIndex for LanceDbIndex {
     }
 }
 
+/// One row of a RecordBatch, borrowed from a chunk or a stored row. Text
+/// and metadata are already sealed for encrypted bases.
+struct Row<'a> {
+    id: &'a str,
+    source_id: &'a str,
+    position: u32,
+    text: &'a str,
+    embedding: &'a [f32],
+    title_embedding: &'a [f32],
+    namespace: Option<&'a str>,
+    metadata: &'a str,
+}
+
+/// Builder of one structured metadata column.
+enum ColumnBuilder {
+    Str(StringBuilder),
+    U64(UInt64Builder),
+    I64(Int64Builder),
+    Tags(ListBuilder<StringBuilder>),
+}
+
+impl ColumnBuilder {
+    fn new(kind: &FieldKind, capacity: usize) -> Self {
+        match kind {
+            FieldKind::Str => Self::Str(StringBuilder::with_capacity(capacity, capacity * 16)),
+            FieldKind::U64 => Self::U64(UInt64Builder::with_capacity(capacity)),
+            FieldKind::I64 => Self::I64(Int64Builder::with_capacity(capacity)),
+            FieldKind::Tags => {
+                Self::Tags(ListBuilder::with_capacity(StringBuilder::new(), capacity))
+            }
+        }
+    }
+
+    /// Append a value, or null when it is missing or of the wrong type.
+    fn append(&mut self, value: Option<&serde_json::Value>) {
+        match self {
+            Self::Str(builder) => builder.append_option(value.and_then(|v| v.as_str())),
+            Self::U64(builder) => builder.append_option(value.and_then(|v| v.as_u64())),
+            Self::I64(builder) => builder.append_option(value.and_then(|v| v.as_i64())),
+            Self::Tags(builder) => match value.and_then(|v| v.as_array()) {
+                Some(tags) => {
+                    for tag in tags {
+                        builder.values().append_option(tag.as_str());
+                    }
+                    builder.append(true);
+                }
+                None => builder.append_null(),
+            },
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Str(builder) => Arc::new(builder.finish()),
+            Self::U64(builder) => Arc::new(builder.finish()),
+            Self::I64(builder) => Arc::new(builder.finish()),
+            Self::Tags(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+/// Columnar builder of a multi-row RecordBatch in the current layout.
+///
+/// Rows are appended straight into one Arrow builder per column, so a batch
+/// allocates its arrays once however many chunks it holds.
+struct BatchBuilder {
+    embedding_dim: usize,
+    ids: StringBuilder,
+    source_ids: StringBuilder,
+    positions: UInt32Builder,
+    texts: StringBuilder,
+    embeddings: FixedSizeListBuilder<Float32Builder>,
+    title_embeddings: FixedSizeListBuilder<Float32Builder>,
+    namespaces: StringBuilder,
+    structured: Vec<ColumnBuilder>,
+    metadata: StringBuilder,
+}
+
+impl BatchBuilder {
+    fn new(embedding_dim: usize, capacity: usize) -> Self {
+        let vectors = || {
+            FixedSizeListBuilder::with_capacity(
+                Float32Builder::with_capacity(capacity * embedding_dim),
+                embedding_dim as i32,
+                capacity,
+            )
+        };
+        Self {
+            embedding_dim,
+            ids: StringBuilder::with_capacity(capacity, capacity * 36),
+            source_ids: StringBuilder::with_capacity(capacity, capacity * 36),
+            positions: UInt32Builder::with_capacity(capacity),
+            texts: StringBuilder::with_capacity(capacity, capacity * 1024),
+            embeddings: vectors(),
+            title_embeddings: vectors(),
+            namespaces: StringBuilder::with_capacity(capacity, 0),
+            structured: STRUCTURED_FIELDS
+                .iter()
+                .map(|(_, kind)| ColumnBuilder::new(kind, capacity))
+                .collect(),
+            metadata: StringBuilder::with_capacity(capacity, capacity * 256),
+        }
+    }
+
+    /// Append a row with the values of its structured columns, in
+    /// [`STRUCTURED_FIELDS`] order; missing values are null.
+    fn append(&mut self, row: Row<'_>, structured: &[Option<serde_json::Value>]) -> AppResult<()> {
+        if let Some(len) = [row.embedding.len(), row.title_embedding.len()]
+            .into_iter()
+            .find(|&len| len != self.embedding_dim)
+        {
+            return Err(AppError::Knowledge(format!(
+                "Embedding dimension mismatch: expected {}, got {}",
+                self.embedding_dim, len
+            )));
+        }
+
+        self.ids.append_value(row.id);
+        self.source_ids.append_value(row.source_id);
+        self.positions.append_value(row.position);
+        self.texts.append_value(row.text);
+        self.embeddings.values().append_slice(row.embedding);
+        self.embeddings.append(true);
+        self.title_embeddings
+            .values()
+            .append_slice(row.title_embedding);
+        self.title_embeddings.append(true);
+        self.namespaces.append_option(row.namespace);
+        for (i, column) in self.structured.iter_mut().enumerate() {
+            column.append(structured.get(i).and_then(|v| v.as_ref()));
+        }
+        self.metadata.append_value(row.metadata);
+        Ok(())
+    }
+
+    /// The rows appended so far, as one batch. Leaves the builder empty.
+    fn finish(&mut self) -> AppResult<RecordBatch> {
+        let mut columns: Vec<ArrayRef> = vec![
+            // Core fields
+            Arc::new(self.ids.finish()),
+            Arc::new(self.source_ids.finish()),
+            Arc::new(self.positions.finish()),
+            Arc::new(self.texts.finish()),
+            Arc::new(self.embeddings.finish()),
+            Arc::new(self.title_embeddings.finish()),
+            Arc::new(self.namespaces.finish()),
+        ];
+        // Structured metadata
+        columns.extend(self.structured.iter_mut().map(|column| column.finish()));
+        // Legacy metadata
+        columns.push(Arc::new(self.metadata.finish()));
+
+        RecordBatch::try_new(LanceDbIndex::create_schema(self.embedding_dim), columns)
+            .map_err(|e| AppError::Knowledge(format!("Failed to create RecordBatch: {}", e)))
+    }
+}
+
+/// The object holding a chunk's file metadata: `custom` for chunks written
+/// by `learn`, otherwise the metadata itself.
+fn file_fields_mut(
+    metadata: &mut serde_json::Value,
+) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    if metadata.get("custom").is_some_and(|c| c.is_object()) {
+        metadata.get_mut("custom")?.as_object_mut()
+    } else {
+        metadata.as_object_mut()
+    }
+}
+
+/// Move the structured fields out of a chunk's metadata, one value per
+/// column. Values of the wrong type stay in the metadata.
+fn take_structured(metadata: &mut serde_json::Value) -> Vec<Option<serde_json::Value>> {
+    let mut values = vec![None; STRUCTURED_FIELDS.len()];
+    if let Some(fields) = file_fields_mut(metadata) {
+        for (value, (name, kind)) in values.iter_mut().zip(STRUCTURED_FIELDS) {
+            if fields.get(*name).is_some_and(|v| kind.accepts(v)) {
+                *value = fields.remove(*name);
+            }
+        }
+    }
+    values
+}
+
+/// Put a row's structured column values back into its chunk metadata.
+fn restore_structured(
+    batch: &RecordBatch,
+    row_idx: usize,
+    metadata: &mut serde_json::Value,
+) -> AppResult<()> {
+    let Some(fields) = file_fields_mut(metadata) else {
+        return Ok(());
+    };
+
+    for (name, kind) in STRUCTURED_FIELDS {
+        let Some(column) = batch.column_by_name(name) else {
+            continue;
+        };
+        if column.is_null(row_idx) {
+            continue;
+        }
+
+        let invalid = || AppError::Knowledge(format!("Invalid {} column", name));
+        let value = match kind {
+            FieldKind::Str => serde_json::json!(column
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(invalid)?
+                .value(row_idx)),
+            FieldKind::U64 => serde_json::json!(column
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .ok_or_else(invalid)?
+                .value(row_idx)),
+            FieldKind::I64 => serde_json::json!(column
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .ok_or_else(invalid)?
+                .value(row_idx)),
+            FieldKind::Tags => {
+                let tags = column
+                    .as_any()
+                    .downcast_ref::<ListArray>()
+                    .ok_or_else(invalid)?
+                    .value(row_idx);
+                let tags = tags
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .ok_or_else(invalid)?;
+                serde_json::json!(tags.iter().flatten().collect::<Vec<_>>())
+            }
+        };
+        fields.entry(name.to_string()).or_insert(value);
+    }
+
+    Ok(())
+}
+
+/// The vector in row `row_idx` of an embedding column.
+fn vector_at(column: &FixedSizeListArray, row_idx: usize) -> AppResult<Vec<f32>> {
+    let values = column.value(row_idx);
+    let values = values
+        .as_any()
+        .downcast_ref::<arrow_array::Float32Array>()
+        .ok_or_else(|| AppError::Knowledge("Invalid embedding values".to_string()))?;
+    Ok(values.values().to_vec())
+}
+
 /// Calculate cosine similarity between two vectors.
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         return 0.0;
     }
@@ -550,3 +1537,528 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 
     dot_product / (norm_a * norm_b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::mix::ContentKind;
+
+    fn chunk(id: &str, embedding: Vec<f32>) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: id.to_string(),
+            source_id: "s1".to_string(),
+            position: 0,
+            text: format!("text of {}", id),
+            embedding: Some(embedding),
+            title_embedding: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_existing_table_keeps_its_dimensions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 3).await.unwrap();
+        index
+            .upsert_chunks(&[
+                chunk("a", vec![1.0, 0.0, 0.0]),
+                chunk("b", vec![0.0, 1.0, 0.0]),
+            ])
+            .unwrap();
+
+        // Reopening with another size reports what is stored
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        assert_eq!(index.dimensions(), 3);
+
+        let mut chunks = index.all_chunks().await.unwrap();
+        chunks.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].text, "text of b");
+
+        index.recreate(2).await.unwrap();
+        assert_eq!(index.dimensions(), 2);
+        assert_eq!(index.stats().unwrap().1, 0);
+        index.upsert_chunks(&[chunk("a", vec![0.5, 0.5])]).unwrap();
+
+        let index = LanceDbIndex::new(temp.path(), "chunks", 3).await.unwrap();
+        assert_eq!(index.dimensions(), 2);
+        assert_eq!(index.search(&[1.0, 0.0], 1).unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_title_weight_blends_title_similarity() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        let titled = KnowledgeChunk {
+            title_embedding: Some(vec![0.0, 1.0]),
+            ..chunk("titled", vec![1.0, 0.0])
+        };
+        index
+            .upsert_chunks(&[titled, chunk("untitled", vec![0.8, 0.6])])
+            .unwrap();
+
+        // Bodies only: the untitled chunk is nearer
+        let query = [0.0, 1.0];
+        let ids = index.search_ids(&query, 1).unwrap();
+        assert_eq!(ids[0].0, "untitled");
+
+        // The title is found even though its body is not among the top 1
+        let index = LanceDbIndex::new(temp.path(), "chunks", 2)
+            .await
+            .unwrap()
+            .with_title_weight(0.8);
+        let ids = index.search_ids(&query, 1).unwrap();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(ids[0].0, "titled");
+        assert!((ids[0].1 - 0.8).abs() < 1e-6, "{:?}", ids);
+
+        let results = index.search(&query, 2).unwrap();
+        assert_eq!(results[0].0.id, "titled");
+        assert_eq!(results[0].0.title_embedding, Some(vec![0.0, 1.0]));
+        assert!((results[1].1 - 0.6).abs() < 1e-6, "{:?}", results[1].1);
+        assert_eq!(results[1].0.title_embedding, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_ids_and_fetch_chunks() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        index
+            .upsert_chunks(&[
+                chunk("a", vec![1.0, 0.0]),
+                chunk("b", vec![0.6, 0.8]),
+                chunk("it's", vec![0.0, 1.0]),
+            ])
+            .unwrap();
+
+        let full = index.search(&[1.0, 0.0], 3).unwrap();
+        let ids = index.search_ids(&[1.0, 0.0], 3).unwrap();
+        assert_eq!(
+            ids,
+            full.iter()
+                .map(|(chunk, score)| (chunk.id.clone(), *score))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(ids[0].0, "a");
+
+        let wanted = ["it's", "missing", "a"].map(String::from);
+        let chunks = index.fetch_chunks(&wanted).unwrap();
+        assert_eq!(
+            chunks.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+            vec!["it's", "a"]
+        );
+        assert_eq!(chunks[0].text, "text of it's");
+        assert!(index.fetch_chunks(&[]).unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_embeddings_at_reads_sampled_rows() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        index
+            .upsert_chunks(&[
+                chunk("a", vec![1.0, 0.0]),
+                chunk("b", vec![0.6, 0.8]),
+                chunk("c", vec![0.0, 1.0]),
+            ])
+            .unwrap();
+        assert_eq!(index.row_count().await.unwrap(), 3);
+
+        let mut sampled = index.embeddings_at(vec![0, 2]).await.unwrap();
+        sampled.sort_by(|a, b| a.1.iter().partial_cmp(b.1.iter()).unwrap());
+        assert_eq!(
+            sampled,
+            vec![
+                ("s1".to_string(), vec![0.0, 1.0]),
+                ("s1".to_string(), vec![1.0, 0.0])
+            ]
+        );
+        assert!(index.embeddings_at(Vec::new()).await.unwrap().is_empty());
+    }
+
+    /// Read a string column of every row straight from the table.
+    async fn column_values(index: &LanceDbIndex, column: &str) -> Vec<String> {
+        use futures::TryStreamExt;
+
+        let batches = index
+            .table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch
+                    .column_by_name(column)
+                    .unwrap()
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap()
+                    .clone();
+                (0..values.len())
+                    .map(|i| values.value(i).to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_legacy_table_is_upgraded_on_open() {
+        let temp = tempfile::TempDir::new().unwrap();
+
+        // Version 1 layout: metadata only in the JSON column
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("source_id", DataType::Utf8, false),
+            Field::new("position", DataType::UInt32, false),
+            Field::new("text", DataType::Utf8, false),
+            Field::new(
+                "embedding",
+                DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 2),
+                false,
+            ),
+            Field::new("metadata", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["c1"])),
+                Arc::new(StringArray::from(vec!["s1"])),
+                Arc::new(UInt32Array::from(vec![0])),
+                Arc::new(StringArray::from(vec!["legacy chunk"])),
+                Arc::new(FixedSizeListArray::new(
+                    Arc::new(Field::new("item", DataType::Float32, true)),
+                    2,
+                    Arc::new(arrow_array::Float32Array::from(vec![1.0, 0.0])),
+                    None,
+                )),
+                Arc::new(StringArray::from(vec![
+                    r#"{"custom":{"source_path":"docs/a.md","language":"markdown"}}"#,
+                ])),
+            ],
+        )
+        .unwrap();
+        let conn = lancedb::connect(&temp.path().to_string_lossy())
+            .execute()
+            .await
+            .unwrap();
+        conn.create_table("chunks", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        assert_eq!(
+            LanceDbIndex::read_layout(&index.table).await.unwrap(),
+            (2, SCHEMA_VERSION)
+        );
+        assert_eq!(
+            column_values(&index, "source_path").await,
+            vec!["docs/a.md"]
+        );
+        assert_eq!(column_values(&index, "language").await, vec!["markdown"]);
+        assert_eq!(
+            column_values(&index, "metadata").await,
+            vec![r#"{"custom":{}}"#]
+        );
+
+        let results = index.search(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].0.text, "legacy chunk");
+        assert_eq!(results[0].0.metadata["custom"]["source_path"], "docs/a.md");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_structured_fields_round_trip_through_columns() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+
+        let mut learned = chunk("a", vec![1.0, 0.0]);
+        learned.metadata = serde_json::json!({
+            "content_type": "markdown",
+            "created_at": "2026-01-01T00:00:00Z",
+            "custom": {
+                "source_path": "docs/a.md",
+                "file_size_bytes": 42,
+                "file_modified_at": 1_700_000_000,
+                "tags": ["guide"],
+                "language": 7,
+            },
+        });
+        let mut bare = chunk("b", vec![0.0, 1.0]);
+        bare.metadata = serde_json::json!({"file_type": "rs", "note": "kept"});
+        index
+            .upsert_chunks(&[learned.clone(), bare.clone()])
+            .unwrap();
+
+        // Moved into columns; values of the wrong type stay in the blob
+        let mut blobs = column_values(&index, "metadata").await;
+        blobs.sort();
+        assert_eq!(
+            blobs,
+            vec![
+                r#"{"content_type":"markdown","created_at":"2026-01-01T00:00:00Z","custom":{"language":7}}"#,
+                r#"{"note":"kept"}"#,
+            ]
+        );
+
+        let mut chunks = index.all_chunks().await.unwrap();
+        chunks.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(chunks[0].metadata, learned.metadata);
+        assert_eq!(chunks[1].metadata, bare.metadata);
+    }
+
+    #[test]
+    fn test_batch_builder_builds_one_multi_row_batch() {
+        let structured = |tags: Option<serde_json::Value>| {
+            let mut values = vec![None; STRUCTURED_FIELDS.len()];
+            values[0] = Some(serde_json::json!("docs/a.md"));
+            values[8] = tags;
+            values
+        };
+        let mut builder = BatchBuilder::new(2, 3);
+        for (i, tags) in [
+            Some(serde_json::json!(["guide", "ops"])),
+            None,
+            Some(serde_json::json!(["faq"])),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let id = format!("c{}", i);
+            builder
+                .append(
+                    Row {
+                        id: &id,
+                        source_id: "s1",
+                        position: i as u32,
+                        text: "text",
+                        embedding: &[1.0, 0.0],
+                        title_embedding: &[0.0, 1.0],
+                        namespace: (i == 1).then_some("team"),
+                        metadata: "{}",
+                    },
+                    &structured(tags),
+                )
+                .unwrap();
+        }
+        let batch = builder.finish().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.schema(), LanceDbIndex::create_schema(2));
+
+        let tags = batch
+            .column_by_name("tags")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(tags.value_offsets(), &[0, 2, 2, 3]);
+        assert!(tags.is_null(1));
+        let values = tags.value(2);
+        let values = values.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(values.value(0), "faq");
+        let namespaces = batch
+            .column_by_name("namespace")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert!(namespaces.is_null(0) && namespaces.value(1) == "team");
+
+        // Finishing empties the builder for the next batch
+        assert_eq!(builder.finish().unwrap().num_rows(), 0);
+        let wrong = Row {
+            id: "x",
+            source_id: "s1",
+            position: 0,
+            text: "text",
+            embedding: &[1.0],
+            title_embedding: &[1.0],
+            namespace: None,
+            metadata: "{}",
+        };
+        assert!(builder.append(wrong, &[]).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespace_scopes_search_and_reset() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let open = |namespace: Option<&str>| {
+            let path = temp.path().to_path_buf();
+            let namespace = namespace.map(str::to_string);
+            async move {
+                LanceDbIndex::new(&path, "chunks", 2)
+                    .await
+                    .unwrap()
+                    .with_namespace(namespace)
+            }
+        };
+
+        open(Some("docs"))
+            .await
+            .upsert_chunks(&[chunk("d1", vec![1.0, 0.0]), chunk("d2", vec![0.9, 0.1])])
+            .unwrap();
+        open(Some("code"))
+            .await
+            .upsert_chunks(&[chunk("c1", vec![1.0, 0.0])])
+            .unwrap();
+        // A chunk read from another namespace keeps its own
+        let mut moved = chunk("t1", vec![1.0, 0.0]);
+        moved.metadata = serde_json::json!({"namespace": "tickets"});
+        open(None).await.upsert_chunks(&[moved]).unwrap();
+
+        let ids = |results: Vec<(KnowledgeChunk, f32)>| {
+            let mut ids: Vec<String> = results.into_iter().map(|(c, _)| c.id).collect();
+            ids.sort();
+            ids
+        };
+        let docs = open(Some("docs")).await;
+        assert_eq!(ids(docs.search(&[1.0, 0.0], 10).unwrap()), vec!["d1", "d2"]);
+        assert_eq!(docs.search_ids(&[1.0, 0.0], 10).unwrap().len(), 2);
+        let all = open(None).await;
+        assert_eq!(all.search(&[1.0, 0.0], 10).unwrap().len(), 4);
+        let tickets = open(Some("tickets")).await.search(&[1.0, 0.0], 10).unwrap();
+        assert_eq!(tickets[0].0.metadata["namespace"], "tickets");
+        assert_eq!(column_values(&all, "metadata").await, vec!["{}"; 4]);
+
+        let mut code = open(Some("code")).await;
+        code.reset().unwrap();
+        assert!(code.search(&[1.0, 0.0], 10).unwrap().is_empty());
+        assert_eq!(open(None).await.stats().unwrap().1, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sources_restrict_search() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(!LanceDbIndex::has_table(temp.path(), "chunks")
+            .await
+            .unwrap());
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        assert!(LanceDbIndex::has_table(temp.path(), "chunks")
+            .await
+            .unwrap());
+        let mut other = chunk("b", vec![1.0, 0.0]);
+        other.source_id = "s2".to_string();
+        index
+            .upsert_chunks(&[chunk("a", vec![0.5, 0.5]), other])
+            .unwrap();
+
+        let index = index.with_sources(Some(vec!["s1".to_string()]));
+        let results = index.search(&[1.0, 0.0], 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, "a");
+        let index = index.with_namespace(Some("docs".to_string()));
+        assert!(index.search_ids(&[1.0, 0.0], 10).unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_path_filters_restrict_search() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = LanceDbIndex::new(temp.path(), "chunks", 2).await.unwrap();
+        let at = |id: &str, path: &str| {
+            let mut chunk = chunk(id, vec![1.0, 0.0]);
+            let file_type = if path.ends_with(".rs") {
+                "code"
+            } else {
+                "markdown"
+            };
+            chunk.metadata = serde_json::json!({
+                "custom": {"source_path": path, "file_type": file_type}
+            });
+            chunk
+        };
+        index
+            .upsert_chunks(&[
+                at("lib", "./crates/knowledge/src/lib.rs"),
+                at("readme", "crates/knowledge/README.md"),
+                at("cli", "crates/cli/src/main.rs"),
+                at("quote", "docs/it's.md"),
+            ])
+            .unwrap();
+
+        let mut index = index.with_filters(&SearchFilters::new());
+        let mut found = |filters: SearchFilters| {
+            index.column_filter = filters.column_predicate();
+            let mut ids: Vec<String> = index
+                .search_ids(&[1.0, 0.0], 10)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            found(SearchFilters::new().with_path_prefix("crates/knowledge/".to_string())),
+            vec!["lib", "readme"]
+        );
+        assert_eq!(
+            found(SearchFilters::new().with_path_glob("crates/knowledge/**".to_string())),
+            vec!["lib", "readme"]
+        );
+        assert_eq!(
+            found(SearchFilters::new().with_path_prefix("docs/it's".to_string())),
+            vec!["quote"]
+        );
+        assert_eq!(
+            found(SearchFilters::new().with_content(ContentKind::Code)),
+            vec!["cli", "lib"]
+        );
+        assert_eq!(
+            found(
+                SearchFilters::new()
+                    .with_content(ContentKind::Docs)
+                    .with_path_prefix("crates/".to_string())
+            ),
+            vec!["readme"]
+        );
+        assert_eq!(found(SearchFilters::new()).len(), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_chunks_for_source_path() {
+        let key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        for cipher in [None, Some(Cipher::from_hex(key).unwrap())] {
+            let temp = tempfile::TempDir::new().unwrap();
+            let mut index = LanceDbIndex::new(temp.path(), "chunks", 2)
+                .await
+                .unwrap()
+                .with_cipher(cipher);
+
+            let learned = |id: &str, path: &str| {
+                let mut chunk = chunk(id, vec![1.0, 0.0]);
+                chunk.metadata = serde_json::json!({"custom": {"source_path": path}});
+                chunk
+            };
+            index
+                .upsert_chunks(&[
+                    learned("a1", "docs/a.md"),
+                    learned("b1", "docs/b.md"),
+                    learned("a2", "docs/a.md"),
+                    learned("q", "docs/it's.md"),
+                ])
+                .unwrap();
+
+            let mut ids: Vec<String> = index
+                .chunks_for_source_path("docs/a.md")
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            ids.sort();
+            assert_eq!(ids, vec!["a1", "a2"]);
+
+            let quoted = index.chunks_for_source_path("docs/it's.md").await.unwrap();
+            assert_eq!(quoted.len(), 1);
+            assert!(index
+                .chunks_for_source_path("docs/c.md")
+                .await
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
@@ -2,30 +2,64 @@
 //!
 //! Provides local-first RAG using LanceDB vector index.
 
+pub mod base;
+pub mod bench;
+pub mod calibration;
+pub mod checkpoint;
 pub mod chunk;
 pub mod chunker; // Deprecated: use chunk module instead
 pub mod config;
+pub mod connectors;
+pub mod dry_run;
 pub mod embeddings;
+pub mod encryption;
+pub mod faults;
+pub mod guardrails;
+pub mod ignore;
+pub mod images;
 pub mod lancedb_index;
+pub mod memory;
 pub mod metadata;
 pub mod parser;
+pub mod paths;
 pub mod progress;
 pub mod rag;
+pub mod summaries;
+pub mod symbol_index;
+pub mod transcripts;
+pub mod tune;
 pub mod types;
 pub mod vector_index;
+pub mod warm;
+pub mod web;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export commonly used types
+pub use base::KnowledgeBase;
+pub use bench::bench;
+pub use dry_run::dry_run;
+pub use images::ImageReader;
 pub use progress::{ProgressEvent, ProgressReporter};
 pub use rag::{RagResponse, RagSourceRef};
+pub use summaries::summarize;
+pub use transcripts::Transcriber;
+pub use tune::tune;
 pub use types::{
-    AskOptions, AskResult, BaseStats, KnowledgeBaseConfig, KnowledgeChunk, KnowledgeSource,
-    LearnOptions, LearnStats,
+    AskOptions, AskResult, BaseStats, BenchOptions, BenchReport, ChunkBench, ChunkSetting,
+    ContentStats, CrawlOptions, DiscoveryOptions, DryRunReport, EmbedBench, EncryptionConfig,
+    EvalCase, ExcludedFile, FeedSubscription, GuardrailsConfig, InjectionFilter, InlineText,
+    KnowledgeBaseConfig, KnowledgeChunk, KnowledgeScope, KnowledgeSource, LearnError, LearnOptions,
+    LearnStage, LearnStats, MergeOptions, MergeStats, PostProcessing, PostProcessingOverrides,
+    RefreshOptions, RetrievalConfig, SearchBench, SourceType, SummarizeOptions, SummarizeStats,
+    TagOptions, TagResult, TuneOptions, TuneResult, TuneScore,
 };
 
+use embeddings::EmbeddingUsage;
 use guided_core::{AppError, AppResult};
+use guided_llm::{UsageEntry, UsageLedger};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
@@ -36,6 +70,8 @@ use walkdir::WalkDir;
 /// Range: -1.0 to 1.0, where 1.0 is perfect match, 0.0 is orthogonal, -1.0 is opposite.
 /// Note: 0.08 is suitable for trigram embeddings (lower semantic accuracy);
 /// production systems with neural embeddings should use 0.3-0.5.
+/// Learn calibrates a threshold for bases large enough to measure (see
+/// [`calibration`]); this is the default for the others.
 const MIN_RELEVANCE_SCORE: f32 = 0.08;
 
 /// Learn from sources and populate the knowledge base.
@@ -58,8 +94,52 @@ pub async fn learn_with_progress(
 
     tracing::info!("Starting learn operation for base '{}'", options.base_name);
 
+    // Validated once; everything below reads the namespace from `options`
+    let options = &LearnOptions {
+        namespace: options
+            .namespace
+            .as_deref()
+            .map(config::normalize_namespace)
+            .transpose()?,
+        ..options.clone()
+    };
+
+    if options.resume && options.reset {
+        return Err(AppError::Knowledge(
+            "Cannot resume a learn and reset the base at the same time".to_string(),
+        ));
+    }
+
+    // Fail on invalid URLs, empty inline text or a missing connector before
+    // any work is done
+    let start_urls = web::parse_urls(&options.urls)?;
+    for inline in &options.texts {
+        validate_inline_text(inline)?;
+    }
+    web::parse_urls(&options.feeds)?;
+    let connector = options
+        .connector
+        .as_deref()
+        .map(|name| connectors::create_connector(workspace, name))
+        .transpose()?;
+
+    // Place a new base in the requested scope; existing bases stay put
+    if let Some(scope) = options.scope {
+        config::create_base_dir(workspace, &options.base_name, scope)?;
+    }
+
     // Load or create config
     let mut config = config::load_config(workspace, &options.base_name)?;
+    let options = &with_config_patterns(options, &config);
+    tracing::info!(
+        "Knowledge base '{}' is stored in {} scope at {:?}",
+        options.base_name,
+        config.scope,
+        config::get_base_dir(workspace, &options.base_name)
+    );
+
+    // The index holds vectors of the provider and model the base was built with
+    let built_with = (config.provider.clone(), config.model.clone());
 
     // Override provider/model if specified in options
     if let Some(provider) = &options.provider {
@@ -67,92 +147,488 @@ pub async fn learn_with_progress(
         tracing::info!("Using provider from options: {}", provider);
     }
     if let Some(model) = &options.model {
-        config.model = model.clone();
-        tracing::info!("Using model from options: {}", model);
+        // A trigram base keeps the model it was built with, so its vectors
+        // stay comparable; switching models needs --reset
+        let has_sources = !options.reset
+            && rag::SourceManager::new(workspace, &options.base_name)
+                .list_sources()
+                .is_ok_and(|sources| !sources.is_empty());
+        let trigram_upgrade = config.provider == "trigram"
+            && config.model.starts_with("trigram-")
+            && config.model != *model;
+        if has_sources && trigram_upgrade {
+            tracing::warn!(
+                "Knowledge base '{}' was built with {}; keeping it instead of {} (use --reset to switch)",
+                options.base_name,
+                config.model,
+                model
+            );
+        } else {
+            config.model = model.clone();
+            tracing::info!("Using model from options: {}", model);
+        }
     }
-
-    // Save config (creates base directory if needed)
-    config::save_config(workspace, &config)?;
+    config.post_processing = options.post_processing.apply(config.post_processing);
 
     // Initialize LanceDB index
     let index_path = config::get_index_path(workspace, &options.base_name);
     let mut index =
         lancedb_index::LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
-            .await?;
+            .await?
+            .with_namespace(options.namespace.clone());
 
     // Initialize source manager
     let source_manager = rag::SourceManager::new(workspace, &options.base_name);
+    let mut symbols = symbol_index::SymbolIndex::load(workspace, &options.base_name)?;
 
-    // Reset if requested
+    // Reset if requested (only the namespace, if one is given)
     if options.reset {
-        tracing::info!("Resetting knowledge base");
         use vector_index::VectorIndex;
         index.reset()?;
-        source_manager.clear_sources()?;
+        symbols.clear(options.namespace.as_deref());
+        match &options.namespace {
+            Some(namespace) => {
+                tracing::info!("Resetting namespace '{}'", namespace);
+                source_manager.clear_namespace(namespace)?;
+            }
+            None => {
+                tracing::info!("Resetting knowledge base");
+                source_manager.clear_sources()?;
+            }
+        }
     }
 
-    let mut sources_count = 0u32;
-    let mut chunks_count = 0u32;
-    let mut bytes_processed = 0u64;
+    // Tag edits and descriptions made with `tag` survive re-learning
+    let curated: HashMap<String, KnowledgeSource> = source_manager
+        .latest_by_path()?
+        .into_iter()
+        .filter(|(_, source)| source.is_curated())
+        .collect();
 
-    // Phase 1: Discover files
-    let mut all_files = Vec::new();
-    for path in &options.paths {
-        if path.is_file() {
-            all_files.push(path.clone());
-        } else if path.is_dir() {
-            for entry in WalkDir::new(path).follow_links(false).into_iter().filter_map(|e| e.ok()) {
-                let entry_path = entry.path();
-                if entry_path.is_file() && should_include(entry_path, &options) {
-                    all_files.push(entry_path.to_path_buf());
-                }
-            }
+    // Sources of this namespace: earlier versions of re-learned files and
+    // texts are replaced, and connector documents and feed entries are
+    // compared with them
+    let learned: Vec<KnowledgeSource> = source_manager
+        .list_sources()?
+        .into_iter()
+        .filter(|source| source.namespace == options.namespace)
+        .collect();
+    let mut earlier: HashMap<&str, Vec<&KnowledgeSource>> = HashMap::new();
+    for source in learned.iter().filter(|source| source.remote_id.is_none()) {
+        earlier
+            .entry(source.path.as_str())
+            .or_default()
+            .push(source);
+    }
+    let mut relearned: HashSet<String> = HashSet::new();
+    let mut unchanged_files = 0u32;
+
+    // Encryption can only be turned on before any plaintext chunk is stored
+    if options.encrypt && config.encryption.is_none() {
+        use vector_index::VectorIndex;
+        let (_, existing_chunks) = index.stats()?;
+        if existing_chunks > 0 {
+            return Err(AppError::Knowledge(format!(
+                "Knowledge base '{}' already has unencrypted chunks; re-learn it with --encrypt --reset",
+                options.base_name
+            )));
         }
+        encryption::enable(&mut config)?;
+        config::save_config(workspace, &config)?;
     }
+    let mut index = index.with_cipher(encryption::cipher_for(&config)?);
+
+    // Size the index for the provider, before the new provider is recorded
+    let migrate =
+        check_dimensions(workspace, options, &mut config, &built_with, &mut index).await?;
+
+    // One engine for the whole run, so the provider (and its pooled HTTP
+    // connections) is created once. It loads the provider lazily, after the
+    // config below is saved.
+    let engine = embeddings::EmbeddingEngine::new(workspace.to_path_buf())
+        .with_provider_configs(options.provider_configs.clone());
+
+    if migrate {
+        let previous = config::load_config(workspace, &options.base_name)?;
+        config::save_config(workspace, &config)?;
+        if let Err(e) = migrate_embeddings(workspace, &engine, options, &mut index, &progress).await
+        {
+            config::save_config(workspace, &previous)?;
+            return Err(e);
+        }
+    }
+
+    // Subscribe to the feeds (moving them to this namespace if they were
+    // learned into another one); their entries are learned below
+    for url in &options.feeds {
+        match config.feeds.iter_mut().find(|feed| feed.url == *url) {
+            Some(feed) => feed.namespace = options.namespace.clone(),
+            None => config.feeds.push(FeedSubscription {
+                url: url.clone(),
+                namespace: options.namespace.clone(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    // Save config (creates base directory if needed)
+    config::save_config(workspace, &config)?;
+
+    let mut sources_count = 0u32;
+    let mut chunks_count = 0u32;
+    let mut bytes_processed = 0u64;
+    let mut content = ContentStats::default();
+    let mut errors = Vec::new();
+
+    // Phase 1: Discover files, or take the unfinished ones from the checkpoint
+    let (all_files, checkpoint) = if options.resume {
+        let plan =
+            checkpoint::load_resume_plan(workspace, &options.base_name)?.ok_or_else(|| {
+                AppError::Knowledge(format!(
+                    "No interrupted learn to resume for knowledge base '{}'",
+                    options.base_name
+                ))
+            })?;
+        tracing::info!(
+            "Resuming learn started at {}: {} files already indexed, {} remaining",
+            plan.checkpoint.started_at,
+            plan.skipped,
+            plan.remaining.len()
+        );
+        let writer = checkpoint::CheckpointWriter::open(workspace, &options.base_name);
+        (plan.remaining, writer)
+    } else {
+        let (all_files, _) = discover_files(workspace, options)?;
+        let writer =
+            checkpoint::CheckpointWriter::start(workspace, &options.base_name, &all_files)?;
+        (all_files, writer)
+    };
     
     let total_files = all_files.len() as u64;
     tracing::info!("Discovered {} files to process", total_files);
     
-    // Phase 2: Process files with batch optimization
-    const BATCH_SIZE: usize = 10; // Process 10 files before embedding batch
-    let mut pending_chunks: Vec<(String, Vec<chunk::Chunk>, PathBuf, u64)> = Vec::new();
+    // Phase 2: Process files with batch optimization, embedding once the
+    // pending chunks hold BATCH_BYTES of text
+    let mut pending_chunks: Vec<(KnowledgeSource, Vec<chunk::Chunk>)> = Vec::new();
+    let mut pending_bytes = 0usize;
+    let mut pending_entries: Vec<checkpoint::CheckpointEntry> = Vec::new();
+    let mut files_read = 0u64;
+    let mut cancelled = false;
     
     for (idx, path) in all_files.iter().enumerate() {
         let current = (idx + 1) as u64;
+        let last = idx == all_files.len() - 1;
+
+        // Stop between files; parsed-but-unembedded files are dropped so
+        // sources.jsonl only lists what reached the index
+        if options.cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
         
-        progress.parse(current, Some(total_files), &path.to_string_lossy());
-        
-        // Parse and chunk file (fast operations)
-        match parse_and_chunk_file(workspace, &config, path, &progress).await {
-            Ok((source_id, chunks, byte_count)) => {
-                pending_chunks.push((source_id.clone(), chunks, path.clone(), byte_count));
-                
-                // Process batch when full or at end
-                if pending_chunks.len() >= BATCH_SIZE || idx == all_files.len() - 1 {
-                    let batch_result = process_batch(
-                        workspace,
-                        &options.base_name,
-                        &mut index,
-                        &config,
-                        &source_manager,
-                        &mut pending_chunks,
-                        &progress,
-                    ).await;
-                    
-                    match batch_result {
-                        Ok((batch_sources, batch_chunks, batch_bytes)) => {
-                            sources_count += batch_sources;
-                            chunks_count += batch_chunks;
-                            bytes_processed += batch_bytes;
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to process batch: {}", e);
-                        }
+        progress.parse(current, Some(total_files), &paths::normalize(path));
+        files_read = current;
+
+        let content_hash = checkpoint::file_hash(path)
+            .map_err(|e| tracing::warn!("Failed to hash {:?}: {}", path, e))
+            .ok();
+
+        // Incremental learns skip files whose latest version is unchanged
+        let unchanged = options.incremental
+            && content_hash.is_some()
+            && earlier
+                .get(paths::normalize(path).as_str())
+                .and_then(|versions| versions.last())
+                .is_some_and(|source| source.content_hash == content_hash);
+        let parsed = if unchanged {
+            unchanged_files += 1;
+            None
+        } else {
+            // Parse and chunk file (fast operations)
+            Some(parse_and_chunk_file(workspace, &config, options, path, &progress).await)
+        };
+
+        match parsed {
+            None => {}
+            Some(Ok((source_id, chunks, byte_count))) => {
+                let source = KnowledgeSource {
+                    source_id,
+                    path: paths::normalize(path),
+                    source_type: SourceType::File,
+                    content_type: Some(metadata::detect_file_type(path).as_str().to_string()),
+                    byte_count,
+                    namespace: options.namespace.clone(),
+                    content_hash: content_hash.clone(),
+                    ..Default::default()
+                };
+                pending_bytes += text_bytes(&chunks);
+                pending_chunks.push((source, chunks));
+                if let Some(content_hash) = content_hash {
+                    pending_entries.push(checkpoint::CheckpointEntry {
+                        path: path.clone(),
+                        content_hash,
+                    });
+                }
+            }
+            Some(Err(e)) => {
+                tracing::warn!("Failed to parse/chunk file {:?}: {}", path, e);
+                errors.push(LearnError {
+                    source: paths::normalize(path),
+                    stage: LearnStage::Parse,
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        // Process batch when full or at end
+        if !pending_chunks.is_empty() && (pending_bytes >= BATCH_BYTES || last) {
+            pending_bytes = 0;
+            let batch_paths: Vec<String> = pending_chunks
+                .iter()
+                .map(|(source, _)| source.path.clone())
+                .collect();
+            let batch_result = process_batch(
+                workspace,
+                &engine,
+                options,
+                &mut index,
+                &source_manager,
+                &mut symbols,
+                &curated,
+                &mut pending_chunks,
+                &progress,
+            )
+            .await;
+
+            match batch_result {
+                Ok((batch_sources, batch_chunks, batch_bytes, batch_content)) => {
+                    sources_count += batch_sources;
+                    chunks_count += batch_chunks;
+                    bytes_processed += batch_bytes;
+                    content.merge(&batch_content);
+                    relearned.extend(batch_paths);
+
+                    if let Err(e) = checkpoint.record(&pending_entries) {
+                        tracing::warn!("Failed to update learn checkpoint: {}", e);
                     }
+                    pending_entries.clear();
+                }
+                Err(AppError::Cancelled(_)) => {
+                    cancelled = true;
+                    break;
+                }
+                Err(e) => {
+                    // Skip the batch's files instead of retrying them with the next batch
+                    tracing::warn!("Failed to process batch: {}", e);
+                    errors.extend(pending_chunks.drain(..).map(|(source, _)| LearnError {
+                        source: source.path,
+                        stage: LearnStage::Embed,
+                        message: e.to_string(),
+                    }));
+                    pending_entries.clear();
                 }
             }
+        }
+    }
+
+    // Phase 3: Web pages of the given URLs (whole sites when crawling)
+    let mut web_texts = Vec::new();
+    if !cancelled && !start_urls.is_empty() {
+        let crawl = options.crawl.as_ref();
+        match web::fetch_pages(&start_urls, crawl, &options.cancel, &progress).await {
+            Ok(pages) => {
+                web_texts = pages
+                    .into_iter()
+                    .map(|page| InlineText {
+                        title: page.title,
+                        text: page.markdown,
+                        url: Some(page.url),
+                        ..Default::default()
+                    })
+                    .collect();
+            }
+            Err(AppError::Cancelled(_)) => cancelled = true,
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Phase 4: Documents of the connector that are new or changed since
+    // the last sync
+    let mut sync = connectors::SyncPlan::default();
+    if let (Some(connector), Some(name), false) = (&connector, &options.connector, cancelled) {
+        match connectors::plan_sync(connector.as_ref(), name, &learned, &options.cancel).await {
+            Ok(plan) => sync = plan,
+            Err(AppError::Cancelled(_)) => cancelled = true,
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Phase 5: Entries of the feeds that are new or changed since the last
+    // sync. A feed that cannot be read is skipped, so one broken feed does
+    // not hold back the others.
+    let mut synced_feeds = Vec::new();
+    let feeds: Vec<&FeedSubscription> = config
+        .feeds
+        .iter()
+        .filter(|feed| options.feeds.contains(&feed.url))
+        .collect();
+    for (idx, feed) in feeds.iter().enumerate() {
+        if cancelled {
+            break;
+        }
+        progress.fetch(idx as u64 + 1, Some(feeds.len() as u64), &feed.url);
+        match web::feed::plan_sync(feed, &learned, &options.cancel).await {
+            Ok((plan, synced)) => {
+                sync.texts.extend(plan.texts);
+                sync.stale.extend(plan.stale);
+                sync.unchanged += plan.unchanged;
+                synced_feeds.push(synced);
+            }
+            Err(AppError::Cancelled(_)) => cancelled = true,
             Err(e) => {
-                tracing::warn!("Failed to parse/chunk file {:?}: {}", path, e);
+                tracing::warn!("Skipping feed {}: {}", feed.url, e);
+                errors.push(LearnError {
+                    source: feed.url.clone(),
+                    stage: LearnStage::Feed,
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+
+    // Phase 6: Text given inline or fetched from the web, the connector or
+    // the feeds, one source per text. Like files, a text that fails is
+    // recorded and skipped; connector documents and feed entries that fail
+    // keep their earlier version and are synced again next time.
+    let texts: Vec<&InlineText> = options
+        .texts
+        .iter()
+        .chain(&web_texts)
+        .chain(&sync.texts)
+        .collect();
+    let mut failed_remote_ids = HashSet::new();
+    if !cancelled && !texts.is_empty() {
+        let count = texts.len();
+        for (idx, inline) in texts.into_iter().enumerate() {
+            let path = inline.url.clone().unwrap_or_else(|| inline.title.clone());
+            match chunk_inline_text(&config, inline, &progress) {
+                Ok((source_id, chunks, byte_count)) => {
+                    let source = KnowledgeSource {
+                        source_id,
+                        path,
+                        source_type: inline.source_type.unwrap_or(if inline.url.is_some() {
+                            SourceType::Url
+                        } else {
+                            SourceType::Text
+                        }),
+                        content_type: Some(inline_file_type(inline).as_str().to_string()),
+                        byte_count,
+                        namespace: options.namespace.clone(),
+                        added_tags: inline.tags.clone(),
+                        remote_id: inline.remote_id.clone(),
+                        remote_version: inline.remote_version.clone(),
+                        ..Default::default()
+                    };
+                    pending_bytes += text_bytes(&chunks);
+                    pending_chunks.push((source, chunks));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to chunk {}: {}", path, e);
+                    failed_remote_ids.extend(inline.remote_id.clone());
+                    errors.push(LearnError {
+                        source: path,
+                        stage: LearnStage::Parse,
+                        message: e.to_string(),
+                    });
+                }
+            }
+            let last = idx + 1 == count;
+            if pending_chunks.is_empty() || (pending_bytes < BATCH_BYTES && !last) {
+                continue;
+            }
+
+            pending_bytes = 0;
+            let batch_paths: Vec<String> = pending_chunks
+                .iter()
+                .map(|(source, _)| source.path.clone())
+                .collect();
+            match process_batch(
+                workspace,
+                &engine,
+                options,
+                &mut index,
+                &source_manager,
+                &mut symbols,
+                &curated,
+                &mut pending_chunks,
+                &progress,
+            )
+            .await
+            {
+                Ok((batch_sources, batch_chunks, batch_bytes, batch_content)) => {
+                    sources_count += batch_sources;
+                    chunks_count += batch_chunks;
+                    bytes_processed += batch_bytes;
+                    content.merge(&batch_content);
+                    relearned.extend(batch_paths);
+                }
+                Err(AppError::Cancelled(_)) => {
+                    cancelled = true;
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to process batch: {}", e);
+                    for (source, _) in pending_chunks.drain(..) {
+                        failed_remote_ids.extend(source.remote_id);
+                        errors.push(LearnError {
+                            source: source.path,
+                            stage: LearnStage::Embed,
+                            message: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Drop earlier versions of changed documents and entries, and deleted
+    // documents, once their replacements are indexed
+    sync.stale.retain(|source| {
+        !source
+            .remote_id
+            .as_ref()
+            .is_some_and(|id| failed_remote_ids.contains(id))
+    });
+    let removed_count = if cancelled {
+        0
+    } else {
+        sync.stale.len() as u32
+    };
+    let mut stale: Vec<KnowledgeSource> = if cancelled { Vec::new() } else { sync.stale };
+
+    // Re-learned files and texts replace their earlier versions, even when
+    // the learn was cancelled after their batch
+    stale.extend(
+        relearned
+            .iter()
+            .filter_map(|path| earlier.get(path.as_str()))
+            .flatten()
+            .map(|source| (*source).clone()),
+    );
+    remove_sources(&mut index, &source_manager, &stale).await?;
+    let stale_ids: Vec<String> = stale.iter().map(|s| s.source_id.clone()).collect();
+    symbols.remove_sources(&stale_ids);
+
+    // A feed counts as synced once its entries are indexed; until then the
+    // next sync downloads it again
+    if !cancelled {
+        for synced in synced_feeds {
+            let prefix = format!("feed:{}#", synced.url);
+            if failed_remote_ids.iter().any(|id| id.starts_with(&prefix)) {
+                continue;
+            }
+            if let Some(feed) = config.feeds.iter_mut().find(|feed| feed.url == synced.url) {
+                *feed = synced;
             }
         }
     }
@@ -160,18 +636,66 @@ pub async fn learn_with_progress(
     // Flush index
     use vector_index::VectorIndex;
     index.flush()?;
+    if config.encryption.is_none() {
+        symbols.save()?;
+    }
+
+    // Calibrate relevance thresholds against the chunks as learned now
+    let changed = chunks_count > 0 || removed_count > 0;
+    if !cancelled && (changed || config.calibrated_thresholds().is_none()) {
+        let sample = calibration::sample_rows(index.row_count().await?);
+        config.thresholds = calibration::calibrate(&config, &index.embeddings_at(sample).await?);
+    }
 
     // Save config
     config::save_config(workspace, &config)?;
 
+    // Asks in this process reopen the index to see what was learned
+    warm::forget(workspace, &options.base_name);
+
+    // Meter the embedding calls, including those of a cancelled learn
+    let embedding_usage = engine.usage();
+    if embedding_usage.requests > 0 {
+        UsageLedger::record(
+            workspace,
+            &UsageEntry::embedding(
+                "learn",
+                &config.provider,
+                &config.model,
+                embedding_usage.requests,
+                embedding_usage.tokens,
+                embedding_usage.texts,
+                embedding_usage.characters,
+            ),
+        );
+    }
+
+    if cancelled {
+        tracing::warn!(
+            "Learn cancelled after reading {} of {} files; {} sources ({} chunks) were saved",
+            files_read,
+            total_files,
+            sources_count,
+            chunks_count
+        );
+        return Err(AppError::Cancelled(format!(
+            "learn stopped after reading {} of {} files; {} sources ({} chunks) were saved to '{}'. \
+             Run `guided knowledge learn {} --resume` to continue",
+            files_read, total_files, sources_count, chunks_count, options.base_name, options.base_name
+        )));
+    }
+
+    checkpoint.finish()?;
+
     let duration = start.elapsed();
 
     tracing::info!(
-        "Learn operation completed: {} sources, {} chunks, {} bytes in {:.2}s",
+        "Learn operation completed: {} sources, {} chunks, {} bytes in {:.2}s ({} failed)",
         sources_count,
         chunks_count,
         bytes_processed,
-        duration.as_secs_f64()
+        duration.as_secs_f64(),
+        errors.len()
     );
 
     Ok(LearnStats {
@@ -179,27 +703,444 @@ pub async fn learn_with_progress(
         chunks_count,
         bytes_processed,
         duration_secs: duration.as_secs_f64(),
+        unchanged_count: sync.unchanged + unchanged_files,
+        removed_count,
+        content,
+        provider: config.provider,
+        model: config.model,
+        embedding_usage,
+        errors,
     })
 }
 
+/// Delete the chunks and records of `stale` sources.
+/// Returns the number of sources removed.
+async fn remove_sources(
+    index: &mut lancedb_index::LanceDbIndex,
+    source_manager: &rag::SourceManager,
+    stale: &[KnowledgeSource],
+) -> AppResult<u32> {
+    if stale.is_empty() {
+        return Ok(0);
+    }
+
+    let ids: Vec<String> = stale.iter().map(|s| s.source_id.clone()).collect();
+    index.delete_sources(&ids).await?;
+
+    let remaining: Vec<KnowledgeSource> = source_manager
+        .list_sources()?
+        .into_iter()
+        .filter(|source| !ids.contains(&source.source_id))
+        .collect();
+    source_manager.replace_sources(&remaining)?;
+
+    tracing::info!("Removed {} outdated sources", ids.len());
+    Ok(ids.len() as u32)
+}
+
+/// Make sure the index can hold the vectors of the configured provider.
+///
+/// Sets `config.embedding_dim` to the provider's dimensions. An empty index
+/// is recreated at that size; for one with chunks, a mismatch fails with a
+/// migration hint unless `--migrate` was given. Returns `true` when the
+/// existing chunks must be re-embedded (`--migrate` after a dimension or
+/// provider change).
+async fn check_dimensions(
+    workspace: &Path,
+    options: &LearnOptions,
+    config: &mut KnowledgeBaseConfig,
+    built_with: &(String, String),
+    index: &mut lancedb_index::LanceDbIndex,
+) -> AppResult<bool> {
+    use vector_index::VectorIndex;
+
+    let (_, existing_chunks) = index.stats()?;
+    let model_changed = config.provider != built_with.0 || config.model != built_with.1;
+    if existing_chunks > 0 && !model_changed && index.dimensions() == config.embedding_dim as usize
+    {
+        return Ok(false);
+    }
+
+    let embedding_config = embeddings::EmbeddingConfig::from_base(workspace, config)
+        .with_provider_settings(&options.provider_configs);
+    let dimensions = embeddings::detect_dimensions(&embedding_config, None).await?;
+    if dimensions == index.dimensions() {
+        config.embedding_dim = dimensions as u32;
+        if existing_chunks > 0 && model_changed {
+            if options.migrate {
+                return Ok(true);
+            }
+            tracing::warn!(
+                "Knowledge base '{}' was embedded with {}/{}; chunks embedded with {}/{} will not rank consistently with them. \
+                 Re-embed the existing chunks with --migrate",
+                options.base_name,
+                built_with.0,
+                built_with.1,
+                config.provider,
+                config.model
+            );
+        }
+        return Ok(false);
+    }
+
+    if existing_chunks == 0 {
+        index.recreate(dimensions).await?;
+        config.embedding_dim = dimensions as u32;
+        return Ok(false);
+    }
+
+    if !options.migrate {
+        return Err(dimension_mismatch(
+            &options.base_name,
+            index.dimensions(),
+            &format!("{}/{}", config.provider, config.model),
+            dimensions,
+        ));
+    }
+
+    config.embedding_dim = dimensions as u32;
+    Ok(true)
+}
+
+/// Error for a base whose index was built for other embedding dimensions.
+pub(crate) fn dimension_mismatch(
+    base_name: &str,
+    index_dimensions: usize,
+    embedder: &str,
+    dimensions: usize,
+) -> AppError {
+    AppError::Knowledge(format!(
+        "Knowledge base '{}' stores {}-dimensional embeddings, but {} produces {}. \
+         Re-embed the existing chunks with `guided knowledge learn {} --migrate`, \
+         or rebuild the base with `--reset`",
+        base_name, index_dimensions, embedder, dimensions, base_name
+    ))
+}
+
+/// Fail early when a base's config no longer matches its index.
+pub(crate) fn check_index_dimensions(
+    base_name: &str,
+    config: &KnowledgeBaseConfig,
+    index: &lancedb_index::LanceDbIndex,
+) -> AppResult<()> {
+    if index.dimensions() != config.embedding_dim as usize {
+        return Err(dimension_mismatch(
+            base_name,
+            index.dimensions(),
+            &format!("{}/{}", config.provider, config.model),
+            config.embedding_dim as usize,
+        ));
+    }
+    Ok(())
+}
+
+/// Re-embed every chunk with the base's (saved) provider and rebuild the
+/// index at its dimensions.
+async fn migrate_embeddings(
+    workspace: &Path,
+    engine: &embeddings::EmbeddingEngine,
+    options: &LearnOptions,
+    index: &mut lancedb_index::LanceDbIndex,
+    progress: &progress::ProgressReporter,
+) -> AppResult<()> {
+    use vector_index::VectorIndex;
+    const UPSERT_BATCH: usize = 500;
+    let base_name = &options.base_name;
+
+    let chunks = index.all_chunks().await?;
+    let dimensions = embeddings::EmbeddingConfig::load(workspace, base_name)?.dimensions;
+    tracing::info!(
+        "Migrating {} chunks of '{}' from {} to {} dimensions",
+        chunks.len(),
+        base_name,
+        index.dimensions(),
+        dimensions
+    );
+
+    // The old index stays intact until every chunk has its new vector
+    let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+    let embeddings = options
+        .cancel
+        .run(
+            "learn",
+            engine.embed_texts_with_progress(base_name, &texts, None, progress),
+        )
+        .await?;
+    let title_embeddings = embed_titles(
+        engine,
+        options,
+        chunks
+            .iter()
+            .map(|c| c.metadata["custom"]["title"].as_str()),
+    )
+    .await?;
+
+    let migrated: Vec<KnowledgeChunk> = chunks
+        .into_iter()
+        .zip(embeddings)
+        .zip(title_embeddings)
+        .map(|((chunk, embedding), title_embedding)| KnowledgeChunk {
+            embedding: Some(embedding),
+            title_embedding,
+            ..chunk
+        })
+        .collect();
+
+    index.recreate(dimensions).await?;
+    for batch in migrated.chunks(UPSERT_BATCH) {
+        index.upsert_chunks(batch)?;
+    }
+
+    tracing::info!("Migrated {} chunks of '{}'", migrated.len(), base_name);
+    Ok(())
+}
+
 /// Parse and chunk a file (no embedding yet).
 /// Returns (source_id, chunks, byte_count).
 async fn parse_and_chunk_file(
-    workspace: &Path,
+    _workspace: &Path,
     config: &KnowledgeBaseConfig,
+    options: &LearnOptions,
     path: &Path,
     progress: &progress::ProgressReporter,
 ) -> AppResult<(String, Vec<chunk::Chunk>, u64)> {
+    if images::is_image(path) {
+        return describe_and_chunk_image(config, options, path, progress).await;
+    }
+    if transcripts::is_media(path) {
+        return transcribe_and_chunk_media(config, options, path, progress).await;
+    }
+
     // Parse file
-    let text = parser::parse_file(path)?;
+    let parsed = parser::parse_file_with_lines(path)?;
+    let text = &parsed.text;
     let size_bytes = text.len() as u64;
 
     // Extract rich metadata using Phase 5.5.1 metadata module
-    let file_metadata = metadata::extract_metadata(path, &text);
+    let file_metadata = metadata::extract_metadata(path, text);
 
     // Create source
     let source_id = uuid::Uuid::new_v4().to_string();
 
+    let mut chunks = chunk_document(config, &source_id, text, path, &file_metadata)?;
+
+    // Line ranges refer to the cleaned text; point them at the file
+    for chunk_item in &mut chunks {
+        chunk_item.metadata.line_range = chunk_item
+            .metadata
+            .line_range
+            .and_then(|range| parsed.source_line_range(range));
+    }
+    let index_symbols = config.encryption.is_none()
+        && chunks
+            .iter()
+            .any(|c| matches!(c.metadata.content_type, chunk::ContentType::Code { .. }));
+    // Cleaning drops the heading markers; titles, sections and definitions
+    // read the file as written
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
+    add_titles(config, &source, &file_metadata.file_name, &mut chunks);
+    if index_symbols {
+        symbol_index::mark_definitions(&source, &mut chunks);
+    }
+
+    let chunks_count = chunks.len() as u32;
+    progress.chunk(1, Some(1), chunks_count);
+
+    Ok((source_id, chunks, size_bytes))
+}
+
+/// Describe an image as text and chunk the description (no embedding yet).
+/// Returns (source_id, chunks, byte_count).
+async fn describe_and_chunk_image(
+    config: &KnowledgeBaseConfig,
+    options: &LearnOptions,
+    path: &Path,
+    progress: &progress::ProgressReporter,
+) -> AppResult<(String, Vec<chunk::Chunk>, u64)> {
+    let image_path = paths::normalize(path);
+    let reader = options.images.as_ref().ok_or_else(|| {
+        AppError::Knowledge(format!(
+            "{} is an image; learn it with --images tesseract or --images ollama",
+            image_path
+        ))
+    })?;
+
+    let description = reader
+        .describe(path, &options.provider_configs, &options.cancel)
+        .await?;
+    if description.trim().is_empty() {
+        return Err(AppError::Knowledge(format!(
+            "No text found in image {}",
+            image_path
+        )));
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| image_path.clone());
+    let text = format!(
+        "# {}
+
+{}",
+        file_name,
+        description.trim()
+    );
+    let file_metadata = metadata::extract_metadata(path, &text);
+
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let mut chunks = chunk_document(config, &source_id, &text, path, &file_metadata)?;
+    add_titles(config, &text, &file_metadata.file_name, &mut chunks);
+
+    // The description has no lines in the image; point at the image instead
+    for chunk_item in &mut chunks {
+        chunk_item.metadata.line_range = None;
+        if let Some(custom) = chunk_item.metadata.custom.as_object_mut() {
+            custom.insert("image_path".to_string(), serde_json::json!(image_path));
+            custom.insert("image_reader".to_string(), serde_json::json!(reader.name()));
+        }
+    }
+
+    progress.chunk(1, Some(1), chunks.len() as u32);
+
+    Ok((source_id, chunks, text.len() as u64))
+}
+
+/// Transcribe a recording and chunk the transcript (no embedding yet).
+/// Returns (source_id, chunks, byte_count).
+async fn transcribe_and_chunk_media(
+    config: &KnowledgeBaseConfig,
+    options: &LearnOptions,
+    path: &Path,
+    progress: &progress::ProgressReporter,
+) -> AppResult<(String, Vec<chunk::Chunk>, u64)> {
+    let media_path = paths::normalize(path);
+    let transcriber = options.transcribe.as_ref().ok_or_else(|| {
+        AppError::Knowledge(format!(
+            "{} is a recording; learn it with --transcribe",
+            media_path
+        ))
+    })?;
+
+    let segments = transcriber.transcribe(path, &options.cancel).await?;
+    if segments.is_empty() {
+        return Err(AppError::Knowledge(format!(
+            "No speech found in recording {}",
+            media_path
+        )));
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| media_path.clone());
+    let (text, times) = transcripts::transcript_text(&file_name, &segments);
+    let file_metadata = metadata::extract_metadata(path, &text);
+
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let mut chunks = chunk_document(config, &source_id, &text, path, &file_metadata)?;
+    add_titles(config, &text, &file_metadata.file_name, &mut chunks);
+
+    // Transcript lines have no place in the recording; cite times instead
+    for chunk_item in &mut chunks {
+        let time_range = chunk_item
+            .metadata
+            .line_range
+            .take()
+            .and_then(|lines| transcripts::time_range(&times, lines));
+        if let Some(custom) = chunk_item.metadata.custom.as_object_mut() {
+            custom.insert("media_path".to_string(), serde_json::json!(media_path));
+            if let Some((start, end)) = time_range {
+                custom.insert("time_range".to_string(), serde_json::json!([start, end]));
+            }
+        }
+    }
+
+    progress.chunk(1, Some(1), chunks.len() as u32);
+
+    Ok((source_id, chunks, text.len() as u64))
+}
+
+/// Fail on inline text without a title or without text.
+fn validate_inline_text(inline: &InlineText) -> AppResult<()> {
+    if inline.title.trim().is_empty() {
+        return Err(AppError::Knowledge("Inline text needs a title".to_string()));
+    }
+    if inline.text.trim().is_empty() {
+        return Err(AppError::Knowledge(format!(
+            "No text to learn for '{}'",
+            inline.title
+        )));
+    }
+    Ok(())
+}
+
+/// Chunk text given inline (no embedding yet).
+/// Returns (source_id, chunks, byte_count).
+fn chunk_inline_text(
+    config: &KnowledgeBaseConfig,
+    inline: &InlineText,
+    progress: &progress::ProgressReporter,
+) -> AppResult<(String, Vec<chunk::Chunk>, u64)> {
+    validate_inline_text(inline)?;
+
+    // The URL (or else the title) stands in for the file path; there is no
+    // file to stat
+    let path = Path::new(inline.url.as_deref().unwrap_or(&inline.title));
+    let mut file_metadata = metadata::extract_metadata(path, &inline.text);
+    file_metadata.file_type = inline_file_type(inline);
+    file_metadata.file_size_bytes = inline.text.len() as u64;
+    if inline.url.is_some() {
+        file_metadata.file_name = inline.title.clone();
+    }
+
+    if let Some(published_at) = inline.published_at {
+        file_metadata.file_modified_at = published_at;
+    }
+
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let mut chunks = chunk_document(config, &source_id, &inline.text, path, &file_metadata)?;
+    add_titles(config, &inline.text, &file_metadata.file_name, &mut chunks);
+
+    // Feed entries are dated by their publication
+    if let Some(published_at) = inline.published_at {
+        for chunk_item in &mut chunks {
+            if let Some(custom) = chunk_item.metadata.custom.as_object_mut() {
+                custom.insert(
+                    "published_at".to_string(),
+                    serde_json::json!(published_at.to_rfc3339()),
+                );
+            }
+        }
+    }
+
+    progress.chunk(1, Some(1), chunks.len() as u32);
+
+    Ok((source_id, chunks, inline.text.len() as u64))
+}
+
+/// File type of inline text: markdown for documents fetched from the web or
+/// a connector, else from the title's extension if it has one, plain text
+/// otherwise.
+fn inline_file_type(inline: &InlineText) -> metadata::FileType {
+    if inline.url.is_some() {
+        return metadata::FileType::Markdown;
+    }
+    match metadata::detect_file_type(Path::new(&inline.title)) {
+        metadata::FileType::Unknown => metadata::FileType::Text,
+        file_type => file_type,
+    }
+}
+
+/// Split a document into chunks carrying its file metadata.
+fn chunk_document(
+    config: &KnowledgeBaseConfig,
+    source_id: &str,
+    text: &str,
+    path: &Path,
+    file_metadata: &metadata::Metadata,
+) -> AppResult<Vec<chunk::Chunk>> {
     // Use new hybrid chunking pipeline
     let chunk_config = chunk::ChunkConfig {
         target_chunk_size: config.chunk_size as usize,
@@ -208,10 +1149,13 @@ async fn parse_and_chunk_file(
         overlap: config.chunk_overlap as usize,
         respect_semantics: true,
         preserve_code_blocks: true,
+        merge_small: config.post_processing.merge_small,
+        snap_to_sentences: config.post_processing.snap_to_sentences,
+        inject_overlap: config.post_processing.inject_overlap,
     };
     
     let pipeline = chunk::ChunkPipeline::new(chunk_config);
-    let mut chunks = pipeline.process(&source_id, &text, Some(path))?;
+    let mut chunks = pipeline.process(source_id, text, Some(path))?;
 
     // Enrich chunks with rich metadata from Phase 5.5.1
     for chunk_item in &mut chunks {
@@ -239,89 +1183,416 @@ async fn parse_and_chunk_file(
         chunk_item.metadata.custom = serde_json::Value::Object(custom_map);
     }
 
-    let chunks_count = chunks.len() as u32;
-    progress.chunk(1, Some(1), chunks_count);
+    Ok(chunks)
+}
+
+/// Store the title of each chunk (see [`chunk::titles`]) when the base
+/// weights titles, and the section it starts in. `source` is the text the
+/// chunks' line ranges refer to.
+fn add_titles(
+    config: &KnowledgeBaseConfig,
+    source: &str,
+    file_name: &str,
+    chunks: &mut [chunk::Chunk],
+) {
+    // Titles are only needed (and embedded) when the base weights them
+    if config.title_weight > 0.0 {
+        let titles = chunk::chunk_titles(source, file_name, chunks);
+        for (chunk_item, title) in chunks.iter_mut().zip(titles) {
+            if let Some(custom) = chunk_item.metadata.custom.as_object_mut() {
+                custom.insert("title".to_string(), serde_json::json!(title));
+            }
+        }
+    }
+
+    // Prompts introduce each chunk by its heading path or function, which
+    // overlapping chunks starting mid-section would not show otherwise
+    let sections = chunk::chunk_sections(source, chunks);
+    for (chunk_item, section) in chunks.iter_mut().zip(sections) {
+        if let (Some(custom), Some(section)) = (chunk_item.metadata.custom.as_object_mut(), section)
+        {
+            custom.insert("section".to_string(), serde_json::json!(section));
+        }
+    }
+}
+
+/// Embed the `title` of every chunk that has one, in chunk order.
+async fn embed_titles<'a>(
+    engine: &embeddings::EmbeddingEngine,
+    options: &LearnOptions,
+    titles: impl Iterator<Item = Option<&'a str>>,
+) -> AppResult<Vec<Option<Vec<f32>>>> {
+    let titles: Vec<Option<&str>> = titles.collect();
+    let texts: Vec<String> = titles.iter().flatten().map(|t| t.to_string()).collect();
+    if texts.is_empty() {
+        return Ok(vec![None; titles.len()]);
+    }
+
+    let mut embeddings = options
+        .cancel
+        .run(
+            "learn",
+            engine.embed_texts(&options.base_name, &texts, None),
+        )
+        .await?
+        .into_iter();
+    Ok(titles
+        .iter()
+        .map(|title| title.and_then(|_| embeddings.next()))
+        .collect())
+}
+
+/// Text of the chunks learn holds before embedding them, in bytes. Bounds
+/// the memory of a batch whatever the size of its files.
+const BATCH_BYTES: usize = 4 * 1024 * 1024;
 
-    Ok((source_id, chunks, size_bytes))
+/// Chunks written to the index per insert.
+const INSERT_BATCH: usize = 500;
+
+fn text_bytes(chunks: &[chunk::Chunk]) -> usize {
+    chunks.iter().map(|c| c.text.len()).sum()
 }
 
-/// Process a batch of files: embed all chunks at once and insert in batch.
+/// Process a batch of files: embed all chunks at once and insert them in
+/// sub-batches. Chunks are moved out of `pending`, which keeps the sources
+/// until they are tracked so a failed batch can report its files.
+#[allow(clippy::too_many_arguments)]
 async fn process_batch(
     workspace: &Path,
-    base_name: &str,
-    index: &mut dyn vector_index::VectorIndex,
-    config: &KnowledgeBaseConfig,
+    engine: &embeddings::EmbeddingEngine,
+    options: &LearnOptions,
+    index: &mut lancedb_index::LanceDbIndex,
     source_manager: &rag::SourceManager,
-    pending: &mut Vec<(String, Vec<chunk::Chunk>, PathBuf, u64)>,
+    symbols: &mut symbol_index::SymbolIndex,
+    curated: &HashMap<String, KnowledgeSource>,
+    pending: &mut Vec<(KnowledgeSource, Vec<chunk::Chunk>)>,
     progress: &progress::ProgressReporter,
-) -> AppResult<(u32, u32, u64)> {
+) -> AppResult<(u32, u32, u64, ContentStats)> {
     if pending.is_empty() {
-        return Ok((0, 0, 0));
+        return Ok((0, 0, 0, ContentStats::default()));
     }
 
-    // Collect all chunks from all files in batch
-    let mut all_chunks = Vec::new();
-    let mut chunk_to_source: Vec<usize> = Vec::new(); // Maps chunk index to source index
-    
-    for (idx, (_source_id, chunks, _path, _bytes)) in pending.iter().enumerate() {
-        for _ in chunks {
-            chunk_to_source.push(idx);
+    // Re-learned sources keep their curated tags and description; inline
+    // texts bring their own tags
+    for (source, chunks) in pending.iter_mut() {
+        if !source.is_curated() {
+            if let Some(previous) = curated.get(&source.path) {
+                source.inherit_curation(previous);
+            }
         }
-        all_chunks.extend(chunks.clone());
+        if source.is_curated() {
+            for chunk_item in chunks.iter_mut() {
+                if let Some(custom) = chunk_item.metadata.custom.as_object_mut() {
+                    source.apply_curation(custom);
+                }
+            }
+        }
+    }
+
+    // Move all chunks of the batch into one list, counting them and taking
+    // their definitions per source first
+    let mut all_chunks = Vec::new();
+    let mut definitions = Vec::with_capacity(pending.len());
+    for (source, chunks) in pending.iter_mut() {
+        definitions.push(symbol_index::definitions(source, chunks));
+        source.chunk_count = chunks.len() as u32;
+        source.token_count = Some(
+            chunks
+                .iter()
+                .map(|c| guided_llm::estimate_tokens(&c.text) as u64)
+                .sum(),
+        );
+        all_chunks.append(chunks);
     }
 
     let total_chunks = all_chunks.len();
     
-    // Batch embedding - single call for all chunks
-    let engine = crate::embeddings::EmbeddingEngine::new(workspace.to_path_buf());
-    let embeddings = engine.embed_chunks(base_name, &all_chunks, None).await?;
-    progress.embed(total_chunks as u64, Some(total_chunks as u64), &config.model);
-
-    // Batch insert - collect all KnowledgeChunks first
-    let mut knowledge_chunks = Vec::new();
-    for (chunk_item, embedding) in all_chunks.into_iter().zip(embeddings) {
-        let knowledge_chunk = KnowledgeChunk {
-            id: chunk_item.id,
-            source_id: chunk_item.source_id,
-            position: chunk_item.position,
-            text: chunk_item.text,
-            embedding: Some(embedding),
-            metadata: serde_json::to_value(&chunk_item.metadata)?,
-        };
-        knowledge_chunks.push(knowledge_chunk);
+    // Batch embedding - split into provider-sized requests
+    // Nothing has been written yet, so an interrupted embed leaves no trace
+    let embeddings = options
+        .cancel
+        .run(
+            "learn",
+            engine.embed_chunks_with_progress(&options.base_name, &all_chunks, None, progress),
+        )
+        .await?;
+
+    let title_embeddings = embed_titles(
+        engine,
+        options,
+        all_chunks
+            .iter()
+            .map(|c| c.metadata.custom.get("title").and_then(|t| t.as_str())),
+    )
+    .await?;
+
+    // Nothing of a batch that fails from here on is kept: its chunks and
+    // source records are rolled back, so no chunk is left without a source
+    let source_ids: Vec<String> = pending.iter().map(|(s, _)| s.source_id.clone()).collect();
+    let written = write_batch(
+        workspace,
+        index,
+        source_manager,
+        symbols,
+        pending,
+        definitions,
+        all_chunks.into_iter().zip(embeddings).zip(title_embeddings),
+        total_chunks,
+        progress,
+    );
+    if let Err(e) = written {
+        rollback_batch(index, source_manager, &source_ids).await;
+        return Err(e);
     }
 
-    // Batch upsert
-    index.upsert_chunks(&knowledge_chunks)?;
-    progress.index(total_chunks as u64, Some(total_chunks as u64));
-
-    // Track sources
+    // Count the tracked sources
     let mut sources_count = 0u32;
     let mut chunks_count = 0u32;
     let mut bytes_processed = 0u64;
-    
-    for (source_id, chunks, path, byte_count) in pending.drain(..) {
-        let source = KnowledgeSource {
-            source_id,
-            path: path.to_string_lossy().to_string(),
-            source_type: "file".to_string(),
-            indexed_at: chrono::Utc::now(),
-            chunk_count: chunks.len() as u32,
-            byte_count,
-        };
-        source_manager.track_source(&source)?;
-        
+    let mut content = ContentStats::default();
+
+    for (source, _) in pending.drain(..) {
+        content.add_source(&source);
+
         sources_count += 1;
-        chunks_count += chunks.len() as u32;
-        bytes_processed += byte_count;
+        chunks_count += source.chunk_count;
+        bytes_processed += source.byte_count;
+    }
+
+    Ok((sources_count, chunks_count, bytes_processed, content))
+}
+
+/// Insert a batch's embedded chunks, then record its sources and symbols.
+///
+/// Chunks are inserted INSERT_BATCH at a time, converting each sub-batch as
+/// it is written so only one is held as KnowledgeChunks. `pending` keeps
+/// its sources, so a failure can report and roll back all of them.
+#[allow(clippy::too_many_arguments)]
+fn write_batch(
+    workspace: &Path,
+    index: &mut lancedb_index::LanceDbIndex,
+    source_manager: &rag::SourceManager,
+    symbols: &mut symbol_index::SymbolIndex,
+    pending: &mut [(KnowledgeSource, Vec<chunk::Chunk>)],
+    definitions: Vec<Vec<symbol_index::SymbolEntry>>,
+    mut rows: impl Iterator<Item = ((chunk::Chunk, Vec<f32>), Option<Vec<f32>>)>,
+    total_chunks: usize,
+    progress: &progress::ProgressReporter,
+) -> AppResult<()> {
+    use vector_index::VectorIndex;
+
+    let mut knowledge_chunks = Vec::with_capacity(INSERT_BATCH.min(total_chunks));
+    let mut indexed = 0usize;
+    while indexed < total_chunks {
+        knowledge_chunks.clear();
+        for ((chunk_item, embedding), title_embedding) in rows.by_ref().take(INSERT_BATCH) {
+            knowledge_chunks.push(KnowledgeChunk {
+                id: chunk_item.id,
+                source_id: chunk_item.source_id,
+                position: chunk_item.position,
+                text: chunk_item.text,
+                embedding: Some(embedding),
+                title_embedding,
+                metadata: serde_json::to_value(&chunk_item.metadata)?,
+            });
+        }
+        if knowledge_chunks.is_empty() {
+            break;
+        }
+        faults::check(workspace, faults::FaultPoint::IndexWrite)?;
+        index.upsert_chunks(&knowledge_chunks)?;
+        indexed += knowledge_chunks.len();
+        progress.index(indexed as u64, Some(total_chunks as u64));
+    }
+
+    for (source, _) in pending.iter_mut() {
+        source.indexed_at = chrono::Utc::now();
+        faults::check(workspace, faults::FaultPoint::TrackSource)?;
+        source_manager.track_source(source)?;
+    }
+
+    // Last, as it replaces the definitions of earlier versions
+    for ((source, _), definitions) in pending.iter().zip(definitions) {
+        symbols.replace(source, definitions);
+    }
+    Ok(())
+}
+
+/// Undo whatever a failed [`write_batch`] wrote for `source_ids`.
+///
+/// Failures are logged: the batch already failed, and its error is the one
+/// reported.
+async fn rollback_batch(
+    index: &mut lancedb_index::LanceDbIndex,
+    source_manager: &rag::SourceManager,
+    source_ids: &[String],
+) {
+    tracing::warn!(
+        "Rolling back a failed batch of {} sources",
+        source_ids.len()
+    );
+    if let Err(e) = index.delete_sources(source_ids).await {
+        tracing::error!("Failed to roll back the chunks of a failed batch: {}", e);
+    }
+    let tracked = source_manager.list_sources().map(|sources| {
+        sources
+            .into_iter()
+            .filter(|source| !source_ids.contains(&source.source_id))
+            .collect::<Vec<_>>()
+    });
+    if let Err(e) = tracked.and_then(|remaining| source_manager.replace_sources(&remaining)) {
+        tracing::error!("Failed to roll back the sources of a failed batch: {}", e);
+    }
+}
+
+/// `options` with the include and exclude patterns of the base's config
+/// added to its own.
+fn with_config_patterns(options: &LearnOptions, config: &KnowledgeBaseConfig) -> LearnOptions {
+    LearnOptions {
+        include: config
+            .include
+            .iter()
+            .chain(&options.include)
+            .cloned()
+            .collect(),
+        exclude: config
+            .exclude
+            .iter()
+            .chain(&options.exclude)
+            .cloned()
+            .collect(),
+        ..options.clone()
+    }
+}
+
+/// Files under `options.paths` that learn reads, and the files discovery
+/// skips with why (see [`ignore`] for the order of the rules). Files given
+/// directly are always read.
+fn discover_files(
+    workspace: &Path,
+    options: &LearnOptions,
+) -> AppResult<(Vec<PathBuf>, Vec<ExcludedFile>)> {
+    let ignore_rules = ignore::IgnoreRules::load(workspace)?;
+    let discovery = &options.discovery;
+    let mut files = Vec::new();
+    let mut excluded = Vec::new();
+    // Where each directory and file reached through links was first seen
+    let mut visited: HashMap<_, PathBuf> = HashMap::new();
+    for path in &options.paths {
+        if path.is_file() {
+            files.push(path.clone());
+        } else if path.is_dir() {
+            let mut walker = WalkDir::new(path)
+                .follow_links(discovery.follow_links)
+                .same_file_system(discovery.same_file_system);
+            if let Some(depth) = discovery.max_depth {
+                walker = walker.max_depth(depth + 1);
+            }
+            let mut entries = walker.into_iter();
+            while let Some(entry) = entries.next() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        tracing::warn!("Skipping part of {:?}: {}", path, e);
+                        continue;
+                    }
+                };
+                let entry_path = entry.path();
+
+                if discovery.follow_links {
+                    // A directory or file reached again through another link
+                    // (or a link cycle) is learned once
+                    let Some(id) = file_id(entry_path) else {
+                        continue;
+                    };
+                    if let Some(first) = visited.get(&id) {
+                        if entry.file_type().is_dir() {
+                            tracing::debug!(
+                                "Skipping {:?}, already walked as {:?}",
+                                entry_path,
+                                first
+                            );
+                            entries.skip_current_dir();
+                        } else {
+                            excluded.push(ExcludedFile {
+                                path: paths::normalize(entry_path),
+                                reason: format!("same file as {}", paths::normalize(first)),
+                            });
+                        }
+                        continue;
+                    }
+                    visited.insert(id, entry_path.to_path_buf());
+                } else if entry.path_is_symlink() && entry_path.is_dir() {
+                    excluded.push(ExcludedFile {
+                        path: paths::normalize(entry_path),
+                        reason: "link to a directory (no --follow-links)".to_string(),
+                    });
+                    continue;
+                }
+                if !entry_path.is_file() {
+                    continue;
+                }
+                let relative = ignore::relative_path(workspace, path, entry_path);
+                match exclusion_reason(entry_path, &relative, options, &ignore_rules) {
+                    Some(reason) => {
+                        tracing::debug!("Excluding file ({}): {:?}", reason, entry_path);
+                        excluded.push(ExcludedFile {
+                            path: paths::normalize(entry_path),
+                            reason,
+                        });
+                    }
+                    None => files.push(entry_path.to_path_buf()),
+                }
+            }
+        } else {
+            excluded.push(ExcludedFile {
+                path: paths::normalize(path),
+                reason: "not found".to_string(),
+            });
+        }
     }
+    Ok((files, excluded))
+}
+
+/// Identity of a file or directory, the same through every link to it.
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
 
-    Ok((sources_count, chunks_count, bytes_processed))
+/// Identity of a file or directory, the same through every link to it.
+#[cfg(not(unix))]
+fn file_id(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok()
 }
 
-/// Check if a file should be included based on patterns.
-fn should_include(path: &Path, options: &LearnOptions) -> bool {
-    let path_str = path.to_string_lossy();
+/// Why discovery skips `path` under a learned directory, or `None` when it
+/// is learned. `relative` is the path the ignore rules match.
+fn exclusion_reason(
+    path: &Path,
+    relative: &str,
+    options: &LearnOptions,
+    ignore_rules: &ignore::IgnoreRules,
+) -> Option<String> {
+    let path_str = paths::normalize(path);
+
+    // Explicit excludes win over everything
+    for pattern in &options.exclude {
+        if paths::contains_pattern(&path_str, pattern) {
+            return Some(format!("exclude pattern '{}'", pattern));
+        }
+    }
+
+    // Ignore files; a `!` line lifts the built-in excludes
+    let reincluded = match ignore_rules.check(relative) {
+        Some(ignore::IgnoreMatch::Ignored { line, origin }) => {
+            return Some(format!("{} '{}'", origin, line));
+        }
+        Some(ignore::IgnoreMatch::Included) => true,
+        None => false,
+    };
 
     // Default exclusions (always applied)
     const DEFAULT_EXCLUDES: &[&str] = &[
@@ -351,121 +1622,125 @@ fn should_include(path: &Path, options: &LearnOptions) -> bool {
         ".cache",
     ];
 
-    // Check default exclusions
+    // Check default exclusions on the relative path only, so a workspace
+    // under `/tmp/.tmpXXXX`, `~/.cache` or `build/` is still learned
+    let relative = format!("/{}", relative.trim_start_matches('/'));
     for pattern in DEFAULT_EXCLUDES {
-        if path_str.contains(pattern) {
-            tracing::debug!("Excluding file (default pattern '{}'): {:?}", pattern, path);
-            return false;
+        if !reincluded && relative.contains(pattern) {
+            return Some(format!("default pattern '{}'", pattern));
         }
     }
 
-    // Check user-provided excludes
-    for pattern in &options.exclude {
-        if path_str.contains(pattern) {
-            tracing::debug!("Excluding file (user pattern '{}'): {:?}", pattern, path);
-            return false;
-        }
+    // Images are only read with --images
+    if options.images.is_none() && images::is_image(path) {
+        return Some("image (no --images)".to_string());
+    }
+
+    // Recordings are only read with --transcribe
+    if options.transcribe.is_none() && transcripts::is_media(path) {
+        return Some("recording (no --transcribe)".to_string());
     }
 
     // If includes are specified, must match at least one
-    if !options.include.is_empty() {
-        for pattern in &options.include {
-            if path_str.contains(pattern) {
-                return true;
-            }
-        }
-        tracing::debug!("Excluding file (no include match): {:?}", path);
-        return false;
+    if !options.include.is_empty()
+        && !options
+            .include
+            .iter()
+            .any(|pattern| paths::contains_pattern(&path_str, pattern))
+    {
+        return Some("no include pattern matches".to_string());
     }
 
-    true
+    None
 }
 
 /// Query the knowledge base and return relevant chunks.
+///
+/// Opens the base for this one query; see [`KnowledgeBase`] to query a
+/// base repeatedly.
 pub async fn ask(
     workspace: &Path,
     options: AskOptions,
     api_key: Option<&str>,
 ) -> AppResult<AskResult> {
-    tracing::info!(
-        "Querying knowledge base '{}' with query: {}",
-        options.base_name,
-        options.query
-    );
+    KnowledgeBase::open_cached(workspace, &options.base_name, &options.provider_configs)
+        .await?
+        .ask(options, api_key)
+        .await
+}
 
-    // Load config
-    let config = config::load_config(workspace, &options.base_name)?;
+/// Learn the new and changed entries of the feeds a base subscribes to.
+pub async fn refresh(workspace: &Path, options: &RefreshOptions) -> AppResult<LearnStats> {
+    refresh_with_progress(workspace, options, progress::ProgressReporter::noop()).await
+}
 
-    // Check if index exists
-    let index_path = config::get_index_path(workspace, &options.base_name);
-    if !index_path.exists() {
+/// Refresh with progress reporting.
+pub async fn refresh_with_progress(
+    workspace: &Path,
+    options: &RefreshOptions,
+    progress: progress::ProgressReporter,
+) -> AppResult<LearnStats> {
+    let start = Instant::now();
+    tracing::info!("Refreshing knowledge base '{}'", options.base_name);
+
+    let config = config::load_config(workspace, &options.base_name)?;
+    if config.feeds.is_empty() {
         return Err(AppError::Knowledge(format!(
-            "Knowledge base '{}' has no index. Run 'guided knowledge learn' first.",
-            options.base_name
+            "Knowledge base '{}' has no feeds; subscribe with `guided knowledge learn {} --feed <url>`",
+            options.base_name, options.base_name
         )));
     }
 
-    // Initialize LanceDB index
-    let index =
-        lancedb_index::LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
-            .await?;
-
-    // Generate query embedding using EmbeddingEngine
-    let engine = crate::embeddings::EmbeddingEngine::new(workspace.to_path_buf());
-    let query_embeddings = engine.embed_texts(&options.base_name, &[options.query.clone()], api_key).await?;
-    let query_embedding = query_embeddings.into_iter().next().ok_or_else(|| {
-        AppError::Knowledge("Failed to generate query embedding".to_string())
-    })?;
-
-    // Retrieve top-k chunks
-    use vector_index::VectorIndex;
-    let results = index.search(&query_embedding, options.top_k as usize)?;
-
-    // Debug: log scores before filtering
-    if !results.is_empty() {
-        let all_scores: Vec<f32> = results.iter().map(|(_, s)| *s).collect();
-        tracing::debug!(
-            "Retrieved {} chunks before filtering - scores: {:?}",
-            results.len(),
-            all_scores
-        );
+    // Each feed is learned into the namespace it was subscribed in
+    let mut by_namespace: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    for feed in &config.feeds {
+        by_namespace
+            .entry(feed.namespace.clone())
+            .or_default()
+            .push(feed.url.clone());
     }
 
-    // Apply relevance cutoff - filter out chunks with low similarity
-    let filtered_results: Vec<_> = results
-        .into_iter()
-        .filter(|(_chunk, score)| *score >= MIN_RELEVANCE_SCORE)
-        .collect();
-
-    let chunks: Vec<KnowledgeChunk> = filtered_results
-        .iter()
-        .map(|(chunk, _score)| chunk.clone())
-        .collect();
-    let scores: Vec<f32> = filtered_results
-        .iter()
-        .map(|(_chunk, score)| *score)
-        .collect();
-
-    if chunks.is_empty() {
-        tracing::info!(
-            "No relevant chunks found (all scores below {:.2} threshold)",
-            MIN_RELEVANCE_SCORE
-        );
-    } else {
-        tracing::info!(
-            "Retrieved {} relevant chunks (top score: {:.3}, lowest: {:.3})",
-            chunks.len(),
-            scores.first().unwrap_or(&0.0),
-            scores.last().unwrap_or(&0.0)
-        );
+    let mut total = LearnStats {
+        sources_count: 0,
+        chunks_count: 0,
+        bytes_processed: 0,
+        unchanged_count: 0,
+        removed_count: 0,
+        content: ContentStats::default(),
+        provider: config.provider.clone(),
+        model: config.model.clone(),
+        embedding_usage: EmbeddingUsage::default(),
+        errors: Vec::new(),
+        duration_secs: 0.0,
+    };
+    for (namespace, feeds) in by_namespace {
+        let learn_options = LearnOptions {
+            base_name: options.base_name.clone(),
+            feeds,
+            namespace,
+            provider_configs: options.provider_configs.clone(),
+            cancel: options.cancel.clone(),
+            ..Default::default()
+        };
+        let stats = learn_with_progress(workspace, &learn_options, None, progress.clone()).await?;
+        total.sources_count += stats.sources_count;
+        total.chunks_count += stats.chunks_count;
+        total.bytes_processed += stats.bytes_processed;
+        total.unchanged_count += stats.unchanged_count;
+        total.removed_count += stats.removed_count;
+        total.content.merge(&stats.content);
+        total.embedding_usage.add(&stats.embedding_usage);
+        total.errors.extend(stats.errors);
     }
 
-    Ok(AskResult { chunks, scores })
+    total.duration_secs = start.elapsed().as_secs_f64();
+    Ok(total)
 }
 
-/// Clean (reset) a knowledge base.
-pub async fn clean(workspace: &Path, base_name: &str) -> AppResult<()> {
+/// Clean (reset) a knowledge base, or only one namespace of it.
+pub async fn clean(workspace: &Path, base_name: &str, namespace: Option<&str>) -> AppResult<()> {
     tracing::info!("Cleaning knowledge base '{}'", base_name);
+    let namespace = namespace.map(config::normalize_namespace).transpose()?;
 
     let config = config::load_config(workspace, base_name)?;
     let index_path = config::get_index_path(workspace, base_name);
@@ -479,68 +1754,457 @@ pub async fn clean(workspace: &Path, base_name: &str) -> AppResult<()> {
 
     let mut index =
         lancedb_index::LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
-            .await?;
+            .await?
+            .with_namespace(namespace.clone());
 
     use vector_index::VectorIndex;
     index.reset()?;
 
+    // Summaries of the removed sources go too
+    if lancedb_index::LanceDbIndex::has_table(&index_path, summaries::SUMMARIES_TABLE).await? {
+        lancedb_index::LanceDbIndex::new(
+            &index_path,
+            summaries::SUMMARIES_TABLE,
+            config.embedding_dim as usize,
+        )
+        .await?
+        .with_namespace(namespace.clone())
+        .reset()?;
+    }
+    rag::AnswerCache::new(workspace, base_name).clear()?;
+    let mut symbols = symbol_index::SymbolIndex::load(workspace, base_name)?;
+    symbols.clear(namespace.as_deref());
+    symbols.save()?;
+    warm::forget(workspace, base_name);
+
     // Clear source tracking
     let source_manager = rag::SourceManager::new(workspace, base_name);
+    if let Some(namespace) = &namespace {
+        source_manager.clear_namespace(namespace)?;
+        tracing::info!(
+            "Namespace '{}' of knowledge base '{}' cleaned",
+            namespace,
+            base_name
+        );
+        return Ok(());
+    }
     source_manager.clear_sources()?;
 
     tracing::info!("Knowledge base '{}' cleaned (index and sources.jsonl cleared)", base_name);
     Ok(())
 }
 
-/// Get statistics for a knowledge base.
-pub async fn stats(workspace: &Path, base_name: &str) -> AppResult<BaseStats> {
-    tracing::info!("Getting stats for knowledge base '{}'", base_name);
+/// Copy a base, with its index, sources and settings, to `new_name`.
+///
+/// The copy is created in `scope`, or in the scope of the original when
+/// unset, and is independent of it from then on (e.g., to re-learn with
+/// another chunk size). Cached answers are left behind. Returns the
+/// directory of the copy.
+pub fn clone_base(
+    workspace: &Path,
+    base_name: &str,
+    new_name: &str,
+    scope: Option<KnowledgeScope>,
+) -> AppResult<std::path::PathBuf> {
+    tracing::info!("Cloning knowledge base '{}' to '{}'", base_name, new_name);
+    let from = check_new_base_name(workspace, base_name, new_name)?;
+
+    let scope = scope.unwrap_or_else(|| config::resolve_scope(workspace, base_name));
+    let parent = match scope {
+        KnowledgeScope::Workspace => config::workspace_knowledge_dir(workspace),
+        KnowledgeScope::Global => config::global_knowledge_dir().ok_or_else(|| {
+            AppError::Knowledge(
+                "Cannot locate the global knowledge directory; set HOME or GUIDED_HOME".to_string(),
+            )
+        })?,
+    };
+    let to = parent.join(new_name);
+
+    // Copied next to the destination first, so a failed copy leaves no base
+    let partial = parent.join(format!(".{}.partial", new_name));
+    let _ = std::fs::remove_dir_all(&partial);
+    if let Err(e) = copy_base_dir(&from, &partial) {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &to).map_err(|e| {
+        let _ = std::fs::remove_dir_all(&partial);
+        AppError::Knowledge(format!("Failed to create {:?}: {}", to, e))
+    })?;
 
-    let config = config::load_config(workspace, base_name)?;
-    let index_path = config::get_index_path(workspace, base_name);
+    rename_in_config(workspace, new_name)?;
+    tracing::info!("Cloned knowledge base '{}' to {:?}", base_name, to);
+    Ok(to)
+}
 
-    if !index_path.exists() {
+/// Rename a base, keeping it in its scope. Returns its new directory.
+pub fn rename_base(
+    workspace: &Path,
+    base_name: &str,
+    new_name: &str,
+) -> AppResult<std::path::PathBuf> {
+    tracing::info!("Renaming knowledge base '{}' to '{}'", base_name, new_name);
+    let from = check_new_base_name(workspace, base_name, new_name)?;
+
+    let to = from.with_file_name(new_name);
+    std::fs::rename(&from, &to).map_err(|e| {
+        AppError::Knowledge(format!("Failed to rename {:?} to {:?}: {}", from, to, e))
+    })?;
+
+    rename_in_config(workspace, new_name)?;
+    tracing::info!("Renamed knowledge base '{}' to '{}'", base_name, new_name);
+    Ok(to)
+}
+
+/// Check that `base_name` exists and `new_name` is free in every scope,
+/// returning the directory of `base_name`.
+fn check_new_base_name(
+    workspace: &Path,
+    base_name: &str,
+    new_name: &str,
+) -> AppResult<std::path::PathBuf> {
+    let from = config::get_base_dir(workspace, base_name);
+    if !from.is_dir() {
         return Err(AppError::Knowledge(format!(
             "Knowledge base '{}' does not exist",
             base_name
         )));
     }
+    config::validate_base_name(new_name)?;
+
+    // A base of the new name in either scope would shadow or be shadowed
+    let taken = config::workspace_knowledge_dir(workspace)
+        .join(new_name)
+        .exists()
+        || config::global_knowledge_dir().is_some_and(|dir| dir.join(new_name).exists());
+    if taken {
+        return Err(AppError::Knowledge(format!(
+            "Knowledge base '{}' already exists",
+            new_name
+        )));
+    }
+    Ok(from)
+}
+
+/// Copy a base directory, leaving out its answer cache (`answers/` and
+/// `last-answer.json`, see [`config::get_answer_cache_path`]).
+fn copy_base_dir(from: &Path, to: &Path) -> AppResult<()> {
+    const SKIPPED: &[&str] = &["answers", "last-answer.json"];
+    for entry in WalkDir::new(from).into_iter().filter_entry(|entry| {
+        let relative = entry.path().strip_prefix(from).unwrap_or(entry.path());
+        !SKIPPED.iter().any(|skip| relative == Path::new(skip))
+    }) {
+        let entry =
+            entry.map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", from, e)))?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        let result = if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)
+        } else {
+            std::fs::copy(entry.path(), &target).map(|_| ())
+        };
+        result.map_err(|e| {
+            AppError::Knowledge(format!("Failed to copy {:?}: {}", entry.path(), e))
+        })?;
+    }
+    Ok(())
+}
+
+/// Record a base's new name in its config file, if it has one.
+fn rename_in_config(workspace: &Path, base_name: &str) -> AppResult<()> {
+    if config::get_config_path(workspace, base_name).exists() {
+        // Loading sets the name from the directory
+        let config = config::load_config(workspace, base_name)?;
+        config::save_config(workspace, &config)?;
+    }
+    Ok(())
+}
+
+/// Add or remove tags and set the description, weight and pin of a learned
+/// source.
+///
+/// The edits are stored on the source record, so they survive re-learning,
+/// and applied to the metadata of the source's chunks, where search filters
+/// and ranking see them.
+pub async fn tag(workspace: &Path, options: &TagOptions) -> AppResult<TagResult> {
+    tracing::info!(
+        "Tagging '{}' in knowledge base '{}'",
+        options.path,
+        options.base_name
+    );
+
+    let config = config::load_config(workspace, &options.base_name)?;
+    let index_path = config::get_index_path(workspace, &options.base_name);
+
+    if !index_path.exists() {
+        return Err(AppError::Knowledge(format!(
+            "Knowledge base '{}' does not exist",
+            options.base_name
+        )));
+    }
+
+    if let Some(weight) = options.weight {
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(AppError::Knowledge(format!(
+                "Source weight must be a positive number, got {}",
+                weight
+            )));
+        }
+    }
+
+    let source_manager = rag::SourceManager::new(workspace, &options.base_name);
+    let mut source = source_manager.find_source(&options.path)?;
+
+    for tag in &options.add {
+        source.removed_tags.retain(|t| t != tag);
+        if !source.added_tags.contains(tag) {
+            source.added_tags.push(tag.clone());
+        }
+    }
+    for tag in &options.remove {
+        source.added_tags.retain(|t| t != tag);
+        if !source.removed_tags.contains(tag) {
+            source.removed_tags.push(tag.clone());
+        }
+    }
+    if let Some(description) = &options.description {
+        source.description = Some(description.trim().to_string()).filter(|d| !d.is_empty());
+    }
+    if let Some(weight) = options.weight {
+        source.weight = Some(weight).filter(|w| *w != 1.0);
+    }
+    if let Some(pinned) = options.pinned {
+        source.pinned = pinned;
+    }
 
     let index =
         lancedb_index::LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
-            .await?;
+            .await?
+            .with_cipher(encryption::cipher_for(&config)?);
+    let mut chunks = index.chunks_for_source_path(&source.path).await?;
+    for chunk in &mut chunks {
+        if let Some(custom) = chunk
+            .metadata
+            .get_mut("custom")
+            .and_then(|custom| custom.as_object_mut())
+        {
+            source.apply_curation(custom);
+        }
+    }
+    index.update_chunks(&chunks).await?;
+
+    // Rewrite every record of this path, so the latest one carries the edits
+    let sources: Vec<KnowledgeSource> = source_manager
+        .list_sources()?
+        .into_iter()
+        .map(|mut record| {
+            if record.path == source.path {
+                record.inherit_curation(&source);
+            }
+            record
+        })
+        .collect();
+    source_manager.replace_sources(&sources)?;
+
+    let tags = source.curate_tags(metadata::derive_tags(Path::new(&source.path)));
+    tracing::info!("Tagged {} chunks of '{}'", chunks.len(), source.path);
+
+    Ok(TagResult {
+        chunks_updated: chunks.len() as u32,
+        source,
+        tags,
+    })
+}
 
+/// Copy the chunks and sources of other bases into `options.target`.
+///
+/// Chunks keep their vectors, so every source base must be embedded with the
+/// target's provider, model and dimensions. A missing target is created with
+/// the settings of the first source base. A source whose content the target
+/// already holds in the same namespace (same content hash, from the target
+/// itself or an earlier source base) is skipped. Summaries are not copied;
+/// run `summarize` on the target afterwards.
+pub async fn merge(workspace: &Path, options: &MergeOptions) -> AppResult<MergeStats> {
     use vector_index::VectorIndex;
-    let (sources_count, chunks_count) = index.stats()?;
+    const UPSERT_BATCH: usize = 500;
+    let start = Instant::now();
+
+    tracing::info!(
+        "Merging {} into knowledge base '{}'",
+        options.sources.join(", "),
+        options.target
+    );
+    if options.sources.is_empty() {
+        return Err(AppError::Knowledge(
+            "Name at least one knowledge base to merge".to_string(),
+        ));
+    }
+    if options.sources.contains(&options.target) {
+        return Err(AppError::Knowledge(format!(
+            "Cannot merge knowledge base '{}' into itself",
+            options.target
+        )));
+    }
 
-    // Calculate directory size
-    let db_size_bytes = calculate_dir_size(&index_path);
+    let mut bases = Vec::with_capacity(options.sources.len());
+    for name in &options.sources {
+        if !config::get_index_path(workspace, name).exists() {
+            return Err(AppError::Knowledge(format!(
+                "Knowledge base '{}' does not exist",
+                name
+            )));
+        }
+        bases.push(config::load_config(workspace, name)?);
+    }
 
-    // Read sources.jsonl to get last_learn_at
-    let source_manager = rag::SourceManager::new(workspace, base_name);
-    let sources = source_manager.list_sources().unwrap_or_default();
-    
-    let last_learn_at = sources
+    // A missing target is created in the image of the first base
+    let created = !config::get_config_path(workspace, &options.target).exists();
+    let mut target_config = if created {
+        KnowledgeBaseConfig {
+            name: options.target.clone(),
+            feeds: Vec::new(),
+            ..bases[0].clone()
+        }
+    } else {
+        config::load_config(workspace, &options.target)?
+    };
+    for base in &bases {
+        check_mergeable(&target_config, base)?;
+    }
+    if created {
+        if let Some(scope) = options.scope {
+            config::create_base_dir(workspace, &options.target, scope)?;
+        }
+        config::save_config(workspace, &target_config)?;
+        target_config = config::load_config(workspace, &options.target)?;
+    }
+
+    let mut target = lancedb_index::LanceDbIndex::new(
+        &config::get_index_path(workspace, &options.target),
+        "chunks",
+        target_config.embedding_dim as usize,
+    )
+    .await?
+    .with_cipher(encryption::cipher_for(&target_config)?);
+    check_index_dimensions(&options.target, &target_config, &target)?;
+
+    // Content already in the target, by namespace and content hash
+    let mut held: HashSet<(Option<String>, String)> = target
+        .all_chunks()
+        .await?
         .iter()
-        .map(|s| s.indexed_at)
-        .max();
+        .filter_map(content_key)
+        .collect();
 
-    tracing::debug!(
-        "Stats for '{}': {} sources, {} chunks, {} bytes, last_learn_at: {:?}",
-        base_name,
-        sources_count,
-        chunks_count,
-        db_size_bytes,
-        last_learn_at
-    );
+    let target_sources = rag::SourceManager::new(workspace, &options.target);
+    let mut records = target_sources.list_sources()?;
+    let mut stats = MergeStats {
+        created,
+        ..Default::default()
+    };
 
-    Ok(BaseStats {
-        base_name: base_name.to_string(),
-        sources_count,
-        chunks_count,
-        db_size_bytes,
-        last_learn_at,
-    })
+    for base in &bases {
+        let index = lancedb_index::LanceDbIndex::new(
+            &config::get_index_path(workspace, &base.name),
+            "chunks",
+            base.embedding_dim as usize,
+        )
+        .await?
+        .with_cipher(encryption::cipher_for(base)?);
+        check_index_dimensions(&base.name, base, &index)?;
+
+        let mut by_source: BTreeMap<String, Vec<KnowledgeChunk>> = BTreeMap::new();
+        for chunk in index.all_chunks().await? {
+            by_source
+                .entry(chunk.source_id.clone())
+                .or_default()
+                .push(chunk);
+        }
+
+        let mut copied = HashSet::new();
+        let mut chunks = Vec::new();
+        for (source_id, source_chunks) in by_source {
+            // Chunks of one source share its content hash
+            if let Some(key) = source_chunks.iter().find_map(content_key) {
+                if !held.insert(key) {
+                    stats.duplicate_sources += 1;
+                    stats.duplicate_chunks += source_chunks.len() as u32;
+                    continue;
+                }
+            }
+            stats.sources_added += 1;
+            stats.chunks_added += source_chunks.len() as u32;
+            copied.insert(source_id);
+            chunks.extend(source_chunks);
+        }
+
+        for batch in chunks.chunks(UPSERT_BATCH) {
+            target.upsert_chunks(batch)?;
+        }
+        records.extend(
+            rag::SourceManager::new(workspace, &base.name)
+                .list_sources()?
+                .into_iter()
+                .filter(|source| copied.contains(&source.source_id)),
+        );
+        tracing::info!(
+            "Copied {} chunks of {} sources from '{}'",
+            chunks.len(),
+            copied.len(),
+            base.name
+        );
+    }
+
+    target.flush()?;
+    target_sources.replace_sources(&records)?;
+    rag::AnswerCache::new(workspace, &options.target).clear()?;
+
+    stats.duration_secs = start.elapsed().as_secs_f64();
+    Ok(stats)
+}
+
+/// Fail unless the chunks of `source` can be searched alongside `target`'s.
+fn check_mergeable(target: &KnowledgeBaseConfig, source: &KnowledgeBaseConfig) -> AppResult<()> {
+    if source.provider != target.provider
+        || source.model != target.model
+        || source.embedding_dim != target.embedding_dim
+    {
+        return Err(AppError::Knowledge(format!(
+            "Cannot merge '{}' into '{}': it is embedded with {}/{} ({} dimensions), \
+             '{}' with {}/{} ({} dimensions). Re-embed it with \
+             `guided knowledge learn {} --provider {} --model {} --migrate` first",
+            source.name,
+            target.name,
+            source.provider,
+            source.model,
+            source.embedding_dim,
+            target.name,
+            target.provider,
+            target.model,
+            target.embedding_dim,
+            source.name,
+            target.provider,
+            target.model
+        )));
+    }
+    Ok(())
+}
+
+/// Namespace and content hash of the file a chunk was learned from.
+fn content_key(chunk: &KnowledgeChunk) -> Option<(Option<String>, String)> {
+    let hash = chunk
+        .metadata_values("content_hash")
+        .find_map(|v| v.as_str())?
+        .to_string();
+    let namespace = chunk.metadata["namespace"].as_str().map(str::to_string);
+    Some((namespace, hash))
+}
+
+/// Get statistics for a knowledge base.
+pub async fn stats(workspace: &Path, base_name: &str) -> AppResult<BaseStats> {
+    KnowledgeBase::open(workspace, base_name)
+        .await?
+        .stats()
+        .await
 }
 
 /// Calculate total size of a directory recursively.
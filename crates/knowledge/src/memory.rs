@@ -0,0 +1,353 @@
+//! Conversation memory: facts distilled from past sessions.
+//!
+//! After an `ask`, the LLM can be asked which facts of the exchange are
+//! worth keeping (the project's conventions, the user's preferences,
+//! decisions made). Each is stored per workspace in a small LanceDB index
+//! under `.guided/memory/`, embedded with the local trigram provider so
+//! recall needs no network, and the ones relevant to a new prompt can be
+//! added to it. `guided memory list` and `guided memory forget` show and
+//! remove what is kept.
+
+use crate::embeddings::providers::trigram::TrigramProvider;
+use crate::embeddings::EmbeddingProvider;
+use crate::lancedb_index::LanceDbIndex;
+use crate::types::KnowledgeChunk;
+use crate::vector_index::VectorIndex;
+use chrono::{DateTime, Utc};
+use guided_core::{AppError, AppResult};
+use guided_llm::{LlmClient, LlmRequest};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Embedding model and dimensions of the memory index.
+const MODEL: &str = "trigram-v2";
+const DIMENSIONS: usize = 384;
+
+/// Similarity above which a new memory repeats a stored one and is not
+/// kept again.
+const DUPLICATE_SCORE: f32 = 0.92;
+
+/// Similarity a memory needs to a prompt to be recalled for it.
+const MIN_RECALL_SCORE: f32 = 0.15;
+
+/// Most facts kept from one exchange.
+const MAX_FACTS: usize = 5;
+
+/// Reply of the distillation prompt when nothing is worth keeping.
+const NOTHING: &str = "NONE";
+
+/// A remembered fact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Memory {
+    /// Unique identifier; `forget` takes any unambiguous prefix
+    pub id: String,
+
+    /// The fact, one sentence
+    pub text: String,
+
+    /// When it was remembered
+    pub created_at: DateTime<Utc>,
+
+    /// Where it came from (e.g., the question it was distilled from)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+impl Memory {
+    fn from_chunk(chunk: &KnowledgeChunk) -> Self {
+        let field = |name: &str| {
+            chunk
+                .metadata_values(name)
+                .find_map(|v| v.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            id: chunk.id.clone(),
+            text: chunk.text.clone(),
+            created_at: field("created_at")
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_default(),
+            origin: field("origin"),
+        }
+    }
+
+    fn to_chunk(&self, embedding: Vec<f32>) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: self.id.clone(),
+            source_id: self.id.clone(),
+            position: 0,
+            text: self.text.clone(),
+            embedding: Some(embedding),
+            title_embedding: None,
+            metadata: serde_json::json!({
+                "created_at": self.created_at.to_rfc3339(),
+                "origin": self.origin,
+            }),
+        }
+    }
+}
+
+/// The memories of a workspace.
+pub struct MemoryStore {
+    index: LanceDbIndex,
+    provider: TrigramProvider,
+}
+
+impl MemoryStore {
+    /// Open the workspace's memory index, creating it if needed.
+    pub async fn open(workspace: &Path) -> AppResult<Self> {
+        Ok(Self {
+            index: LanceDbIndex::new(&index_path(workspace), "memories", DIMENSIONS).await?,
+            provider: TrigramProvider::for_model(MODEL, DIMENSIONS),
+        })
+    }
+
+    /// Every memory, newest first.
+    pub async fn list(&self) -> AppResult<Vec<Memory>> {
+        let mut memories: Vec<Memory> = self
+            .index
+            .all_chunks()
+            .await?
+            .iter()
+            .map(Memory::from_chunk)
+            .collect();
+        memories.sort_by_key(|memory| std::cmp::Reverse(memory.created_at));
+        Ok(memories)
+    }
+
+    /// Remember `text`, unless a stored memory already says the same.
+    ///
+    /// Returns the new memory, or `None` for a repeat.
+    pub async fn remember(
+        &mut self,
+        text: &str,
+        origin: Option<&str>,
+    ) -> AppResult<Option<Memory>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let embedding = self.embed(text).await?;
+        if let Some((existing, score)) = self.index.search(&embedding, 1)?.into_iter().next() {
+            if score >= DUPLICATE_SCORE {
+                tracing::debug!("Already remembered as {}: {}", existing.id, text);
+                return Ok(None);
+            }
+        }
+
+        let memory = Memory {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            text: text.to_string(),
+            created_at: Utc::now(),
+            origin: origin.map(str::to_string),
+        };
+        self.index.upsert_chunk(&memory.to_chunk(embedding))?;
+        self.index.flush()?;
+        tracing::info!("Remembered {}: {}", memory.id, memory.text);
+        Ok(Some(memory))
+    }
+
+    /// The memories most similar to `query`, best first, at most `limit`.
+    pub async fn recall(&self, query: &str, limit: usize) -> AppResult<Vec<(Memory, f32)>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let embedding = self.embed(query).await?;
+        Ok(self
+            .index
+            .search(&embedding, limit)?
+            .into_iter()
+            .filter(|(_, score)| *score >= MIN_RECALL_SCORE)
+            .map(|(chunk, score)| (Memory::from_chunk(&chunk), score))
+            .collect())
+    }
+
+    /// Forget the memory whose id starts with `prefix`.
+    pub async fn forget(&mut self, prefix: &str) -> AppResult<Memory> {
+        let matching: Vec<Memory> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|memory| !prefix.is_empty() && memory.id.starts_with(prefix))
+            .collect();
+        let memory = match matching.len() {
+            0 => {
+                return Err(AppError::Knowledge(format!(
+                    "No memory with id '{}'; see `guided memory list`",
+                    prefix
+                )))
+            }
+            1 => matching.into_iter().next().unwrap(),
+            n => {
+                return Err(AppError::Knowledge(format!(
+                    "{} memories have ids starting with '{}'; give more of the id",
+                    n, prefix
+                )))
+            }
+        };
+        self.index
+            .delete_sources(std::slice::from_ref(&memory.id))
+            .await?;
+        Ok(memory)
+    }
+
+    /// Forget every memory, returning how many there were.
+    pub async fn clear(&mut self) -> AppResult<usize> {
+        let count = self.list().await?.len();
+        self.index.reset()?;
+        Ok(count)
+    }
+
+    async fn embed(&self, text: &str) -> AppResult<Vec<f32>> {
+        self.provider
+            .embed_batch(&[text.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::Knowledge("Failed to embed memory".to_string()))
+    }
+}
+
+/// Directory of a workspace's memory index.
+pub fn index_path(workspace: &Path) -> PathBuf {
+    workspace.join(".guided").join("memory")
+}
+
+/// Prompt section listing `memories`, to add to a new prompt.
+pub fn context(memories: &[(Memory, f32)]) -> String {
+    memories
+        .iter()
+        .map(|(memory, _)| format!("- {}", memory.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The request asking the LLM which facts of an exchange to remember.
+pub fn distill_request(question: &str, answer: &str, model: &str) -> LlmRequest {
+    let system = format!(
+        "You keep notes for a coding assistant. From the exchange below, list the \
+         facts worth remembering in later sessions: the project's conventions and \
+         architecture, the user's preferences, decisions that were made. Skip \
+         anything only relevant to this question, and general knowledge. Write \
+         each fact as one self-contained sentence on its own line starting with \
+         \"- \", at most {}. If nothing is worth keeping, reply {}.",
+        MAX_FACTS, NOTHING
+    );
+    LlmRequest::new(
+        format!("Question:\n{}\n\nAnswer:\n{}", question, answer),
+        model,
+    )
+    .with_system(system)
+    .with_temperature(0.0)
+    .with_max_tokens(300)
+}
+
+/// The facts of a distillation reply.
+pub fn parse_facts(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let fact = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))?
+                .trim();
+            (!fact.is_empty() && fact != NOTHING).then(|| fact.to_string())
+        })
+        .take(MAX_FACTS)
+        .collect()
+}
+
+/// Ask `client` which facts of an exchange to remember, and remember them.
+///
+/// Returns the memories added (repeats of stored ones are skipped).
+pub async fn distill(
+    store: &mut MemoryStore,
+    client: &dyn LlmClient,
+    model: &str,
+    question: &str,
+    answer: &str,
+) -> AppResult<Vec<Memory>> {
+    let reply = client
+        .complete(&distill_request(question, answer, model))
+        .await?;
+    let mut added = Vec::new();
+    for fact in parse_facts(&reply.content) {
+        if let Some(memory) = store.remember(&fact, Some(question)).await? {
+            added.push(memory);
+        }
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_facts() {
+        let reply = "Here is what to keep:\n- The API uses snake_case JSON.\n* Deploys go \
+                     through make release.\n-\nNONE";
+        assert_eq!(
+            parse_facts(reply),
+            vec![
+                "The API uses snake_case JSON.",
+                "Deploys go through make release."
+            ]
+        );
+        assert!(parse_facts("NONE").is_empty());
+        assert!(parse_facts("- NONE").is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remember_recall_and_forget() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut store = MemoryStore::open(temp.path()).await.unwrap();
+
+        let tabs = store
+            .remember(
+                "The user prefers tabs over spaces in Go code.",
+                Some("style?"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        store
+            .remember(
+                "Invoices are generated by the billing worker every night.",
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        // Repeats are not kept twice
+        assert!(store
+            .remember("The user prefers tabs over spaces in Go code.", None)
+            .await
+            .unwrap()
+            .is_none());
+
+        let listed = store.list().await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.contains(&tabs));
+
+        let recalled = store
+            .recall("when are invoices generated", 1)
+            .await
+            .unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert!(recalled[0].0.text.starts_with("Invoices"));
+
+        let forgotten = store.forget(&tabs.id[..8]).await.unwrap();
+        assert_eq!(forgotten.id, tabs.id);
+        assert!(store.forget(&tabs.id).await.is_err());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+
+        // Memories persist across opens
+        let mut store = MemoryStore::open(temp.path()).await.unwrap();
+        assert_eq!(store.clear().await.unwrap(), 1);
+        assert!(store.list().await.unwrap().is_empty());
+    }
+}
@@ -23,6 +23,12 @@ pub fn detect_file_type(path: &Path) -> FileType {
         "xml" => FileType::Xml,
         "txt" => FileType::Text,
 
+        // Diagrams and screenshots
+        "png" | "jpg" | "jpeg" | "svg" => FileType::Image,
+
+        // Meeting recordings
+        "mp3" | "mp4" | "m4a" | "wav" => FileType::Media,
+
         // Programming languages
         "rs" => FileType::Code("rust".to_string()),
         "ts" => FileType::Code("typescript".to_string()),
@@ -47,7 +53,7 @@ pub fn detect_file_type(path: &Path) -> FileType {
 }
 
 /// Detect language from path and content
-pub fn detect_language(path: &Path, content: &str, file_type: &FileType) -> Option<Language> {
+pub fn detect_language(_path: &Path, content: &str, file_type: &FileType) -> Option<Language> {
     // For code files, derive language from file type
     if let FileType::Code(lang) = file_type {
         return match lang.as_str() {
@@ -112,23 +118,19 @@ fn detect_natural_language(content: &str) -> Option<Language> {
 pub fn derive_tags(path: &Path) -> Vec<String> {
     let mut tags = Vec::new();
 
-    // Extract directory names as tags
-    for component in path.components() {
-        if let std::path::Component::Normal(dir) = component {
-            if let Some(dir_str) = dir.to_str() {
-                // Skip common root directories
-                if matches!(dir_str, "." | ".." | "/" | "src" | "lib" | "target" | "node_modules") {
-                    continue;
-                }
-
-                // Add directory as tag
-                tags.push(dir_str.to_lowercase());
-            }
+    // Extract directory names as tags, with either separator
+    let path_str = crate::paths::normalize(path).to_lowercase();
+    for dir_str in crate::paths::segments(&path_str) {
+        // Skip common root directories
+        if matches!(dir_str, "src" | "lib" | "target" | "node_modules") {
+            continue;
         }
+
+        // Add directory as tag
+        tags.push(dir_str.to_string());
     }
 
     // Add special tags based on path patterns
-    let path_str = path.to_string_lossy().to_lowercase();
 
     if path_str.contains("test") || path_str.contains("spec") {
         tags.push("test".to_string());
@@ -240,6 +242,16 @@ mod tests {
         assert!(tags.contains(&"utils".to_string()));
     }
 
+    #[test]
+    fn test_derive_tags_windows_path() {
+        let path = PathBuf::from(r"C:\Work\Docs\API\guide.md");
+        let tags = derive_tags(&path);
+
+        assert!(tags.contains(&"docs".to_string()));
+        assert!(tags.contains(&"api".to_string()));
+        assert!(!tags.iter().any(|t| t.contains('\\') || t.contains(':')));
+    }
+
     #[test]
     fn test_derive_tags_dedup() {
         let path = PathBuf::from("docs/api/docs/test.md");
@@ -50,7 +50,7 @@ pub fn extract_metadata(path: &Path, content: &str) -> Metadata {
     let content_hash = generate_content_hash(content);
 
     Metadata {
-        source_path: path.to_string_lossy().to_string(),
+        source_path: crate::paths::normalize(path),
         file_name,
         file_type,
         language,
@@ -30,6 +30,10 @@ pub enum FileType {
     Json,
     Yaml,
     Xml,
+    /// Diagram or screenshot, learned through its text description
+    Image,
+    /// Audio or video recording, learned through its transcript
+    Media,
     Unknown,
 }
 
@@ -58,6 +62,8 @@ impl FileType {
             FileType::Json => "json",
             FileType::Yaml => "yaml",
             FileType::Xml => "xml",
+            FileType::Image => "image",
+            FileType::Media => "media",
             FileType::Unknown => "unknown",
         }
     }
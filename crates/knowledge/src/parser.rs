@@ -40,22 +40,57 @@ impl ContentType {
     }
 }
 
+/// Text extracted from a source file.
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// Cleaned text
+    pub text: String,
+
+    /// Line number in the file (1-based) of each line of `text`, or `None`
+    /// when cleaning does not keep lines (HTML)
+    pub source_lines: Option<Vec<usize>>,
+}
+
+impl ParsedFile {
+    /// Map a 1-based line range of the cleaned text back to the file.
+    pub fn source_line_range(&self, (start, end): (usize, usize)) -> Option<(usize, usize)> {
+        let lines = self.source_lines.as_ref()?;
+        let line = |n: usize| lines.get(n.checked_sub(1)?).copied();
+        Some((line(start)?, line(end)?))
+    }
+}
+
 /// Parse a source file and extract clean text.
 pub fn parse_file(path: &Path) -> AppResult<String> {
+    parse_file_with_lines(path).map(|parsed| parsed.text)
+}
+
+/// Parse a source file, keeping track of where each cleaned line came from.
+pub fn parse_file_with_lines(path: &Path) -> AppResult<ParsedFile> {
     let content_type = ContentType::from_path(path);
 
     let raw = fs::read_to_string(path)
         .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
 
-    let cleaned = match content_type {
-        ContentType::Markdown => clean_markdown(&raw),
-        ContentType::Html => clean_html(&raw),
-        ContentType::Code => clean_code(&raw),
-        ContentType::PlainText => raw,
+    let (text, source_lines) = match content_type {
+        ContentType::Markdown => {
+            let (text, lines) = clean_markdown(&raw);
+            (text, Some(lines))
+        }
+        ContentType::Html => (clean_html(&raw), None),
+        ContentType::Code => {
+            let (text, lines) = clean_code(&raw);
+            (text, Some(lines))
+        }
+        ContentType::PlainText => {
+            let lines = (1..=raw.lines().count()).collect();
+            (raw, Some(lines))
+        }
         ContentType::Unknown => {
             // Try to read as text, skip if binary
             if is_likely_text(&raw) {
-                raw
+                let lines = (1..=raw.lines().count()).collect();
+                (raw, Some(lines))
             } else {
                 tracing::warn!("Skipping likely binary file: {:?}", path);
                 return Err(AppError::Knowledge("Binary file not supported".to_string()));
@@ -63,14 +98,17 @@ pub fn parse_file(path: &Path) -> AppResult<String> {
         }
     };
 
-    Ok(cleaned)
+    Ok(ParsedFile { text, source_lines })
 }
 
 /// Clean markdown by removing excess formatting.
-fn clean_markdown(text: &str) -> String {
+///
+/// Returns the cleaned text and the file line of each of its lines.
+fn clean_markdown(text: &str) -> (String, Vec<usize>) {
     let mut result = String::with_capacity(text.len());
+    let mut lines = Vec::new();
 
-    for line in text.lines() {
+    for (n, line) in text.lines().enumerate() {
         // Remove markdown headers
         let trimmed = line.trim_start_matches('#').trim();
 
@@ -83,10 +121,11 @@ fn clean_markdown(text: &str) -> String {
         if !trimmed.is_empty() {
             result.push_str(trimmed);
             result.push('\n');
+            lines.push(n + 1);
         }
     }
 
-    result.trim().to_string()
+    (result.trim().to_string(), lines)
 }
 
 /// Clean HTML by stripping tags (simple approach).
@@ -129,10 +168,13 @@ fn clean_html(text: &str) -> String {
 }
 
 /// Clean code by removing excess whitespace and comments (simple approach).
-fn clean_code(text: &str) -> String {
+///
+/// Returns the cleaned text and the file line of each of its lines.
+fn clean_code(text: &str) -> (String, Vec<usize>) {
     let mut result = String::with_capacity(text.len());
+    let mut lines = Vec::new();
 
-    for line in text.lines() {
+    for (n, line) in text.lines().enumerate() {
         let trimmed = line.trim();
 
         // Skip single-line comments (basic detection)
@@ -143,10 +185,11 @@ fn clean_code(text: &str) -> String {
         if !trimmed.is_empty() {
             result.push_str(trimmed);
             result.push('\n');
+            lines.push(n + 1);
         }
     }
 
-    result.trim().to_string()
+    (result.trim().to_string(), lines)
 }
 
 /// Check if text is likely UTF-8 text (not binary).
@@ -178,11 +221,29 @@ mod tests {
     #[test]
     fn test_clean_markdown() {
         let input = "# Header\n\nSome text\n\n```rust\ncode\n```\n\nMore text";
-        let output = clean_markdown(input);
+        let (output, lines) = clean_markdown(input);
         assert!(output.contains("Header"));
         assert!(output.contains("Some text"));
         assert!(output.contains("More text"));
         assert!(!output.contains("```"));
+        assert_eq!(output.lines().count(), lines.len());
+        assert_eq!(lines, vec![1, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_source_line_range() {
+        let parsed = ParsedFile {
+            text: "a\nb\nc".to_string(),
+            source_lines: Some(vec![2, 5, 9]),
+        };
+        assert_eq!(parsed.source_line_range((1, 3)), Some((2, 9)));
+        assert_eq!(parsed.source_line_range((2, 4)), None);
+
+        let html = ParsedFile {
+            text: "a".to_string(),
+            source_lines: None,
+        };
+        assert_eq!(html.source_line_range((1, 1)), None);
     }
 
     #[test]
@@ -195,8 +256,9 @@ mod tests {
     #[test]
     fn test_clean_code() {
         let input = "// Comment\nfn main() {\n    println!(\"hello\");\n}";
-        let output = clean_code(input);
+        let (output, lines) = clean_code(input);
         assert!(!output.contains("// Comment"));
         assert!(output.contains("fn main()"));
+        assert_eq!(lines, vec![2, 3, 4]);
     }
 }
@@ -0,0 +1,105 @@
+//! Path normalization for the knowledge pipeline.
+//!
+//! Discovery, metadata and source display work on paths as `/`-separated
+//! strings, so exclude patterns such as `/node_modules/`, stored source
+//! paths and tags behave the same on Windows. Stored paths may come from
+//! another platform (global bases are shared), so splitting accepts either
+//! separator.
+
+use std::path::Path;
+
+/// A path as a string with `/` separators.
+///
+/// Backslashes are only converted on Windows, where they are separators;
+/// elsewhere they are valid file name characters.
+pub fn normalize(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(windows) {
+        normalize_separators(&path)
+    } else {
+        path.into_owned()
+    }
+}
+
+/// Replace `\` separators with `/`.
+pub fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Last component of a stored path, whichever separator it uses.
+pub fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Named components of a stored path, whichever separator it uses.
+///
+/// Skips empty, `.` and `..` components and Windows drive prefixes (`C:`).
+pub fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+        .filter(|s| !is_drive_prefix(s))
+}
+
+/// Whether a component is a Windows drive prefix such as `C:`.
+fn is_drive_prefix(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Whether a normalized path contains `pattern`, normalized the same way.
+pub fn contains_pattern(normalized_path: &str, pattern: &str) -> bool {
+    normalized_path.contains(&normalize(Path::new(pattern)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_separators() {
+        assert_eq!(
+            normalize_separators(r"C:\work\docs\guide.md"),
+            "C:/work/docs/guide.md"
+        );
+        assert_eq!(normalize_separators("docs/guide.md"), "docs/guide.md");
+    }
+
+    #[test]
+    fn test_file_name_accepts_either_separator() {
+        assert_eq!(file_name(r"C:\work\docs\guide.md"), "guide.md");
+        assert_eq!(file_name("/work/docs/guide.md"), "guide.md");
+        assert_eq!(file_name(r"docs/sub\mixed.md"), "mixed.md");
+        assert_eq!(file_name("guide.md"), "guide.md");
+    }
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(
+            segments(r"C:\work\.\docs\..\api\x.md").collect::<Vec<_>>(),
+            vec!["work", "docs", "api", "x.md"]
+        );
+        assert_eq!(
+            segments("./docs/api/").collect::<Vec<_>>(),
+            vec!["docs", "api"]
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_normalize_windows_path() {
+        assert_eq!(
+            normalize(Path::new(r"C:\work\node_modules\x.js")),
+            "C:/work/node_modules/x.js"
+        );
+        assert!(contains_pattern(
+            &normalize(Path::new(r"C:\work\node_modules\x.js")),
+            r"\node_modules\"
+        ));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_normalize_keeps_backslashes_elsewhere() {
+        assert_eq!(normalize(Path::new(r"docs/a\b.md")), r"docs/a\b.md");
+    }
+}
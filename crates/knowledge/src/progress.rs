@@ -4,12 +4,13 @@
 //! indexing, embedding, and chunking.
 
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Progress event emitted during knowledge operations.
 #[derive(Debug, Clone)]
 pub struct ProgressEvent {
-    /// Phase of the operation: "discover", "parse", "chunk", "embed", "index"
+    /// Phase of the operation: "discover", "fetch", "parse", "chunk", "embed",
+    /// "index", or "retry" when a provider request is retried
     pub phase: String,
     
     /// Current progress (files processed, chunks created, etc.)
@@ -127,6 +128,16 @@ impl ProgressReporter {
         ));
     }
     
+    /// Emit fetch phase event (web pages).
+    pub fn fetch(&self, current: u64, total: Option<u64>, url: &str) {
+        self.emit(ProgressEvent::new(
+            "fetch",
+            current,
+            total,
+            format!("fetching {}", url),
+        ));
+    }
+
     /// Emit parsing phase event.
     pub fn parse(&self, current: u64, total: Option<u64>, file: &str) {
         self.emit(ProgressEvent::new(
@@ -157,6 +168,39 @@ impl ProgressReporter {
         ));
     }
     
+    /// Emit embedding progress after a provider batch completes.
+    pub fn embed_batch(
+        &self,
+        batch: usize,
+        batches: usize,
+        embedded: u64,
+        total: u64,
+        model: &str,
+    ) {
+        self.emit(ProgressEvent::new(
+            "embed",
+            embedded,
+            Some(total),
+            format!("batch {}/{} model={}", batch, batches, model),
+        ));
+    }
+
+    /// Emit a notice that an embedding request is retried after `delay`.
+    pub fn embed_retry(&self, attempt: u32, max_attempts: u32, delay: Duration, reason: &str) {
+        self.emit(ProgressEvent::new(
+            "retry",
+            attempt as u64,
+            None,
+            format!(
+                "{}; retrying in {}ms (attempt {}/{})",
+                reason,
+                delay.as_millis(),
+                attempt,
+                max_attempts
+            ),
+        ));
+    }
+
     /// Emit indexing phase event.
     pub fn index(&self, current: u64, total: Option<u64>) {
         self.emit(ProgressEvent::new(
@@ -199,6 +243,26 @@ mod tests {
         assert_eq!(captured[0].current, 3);
     }
 
+    #[test]
+    fn test_embed_retry_event() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let reporter = ProgressReporter::new(Arc::new(move |event| {
+            events_clone.lock().unwrap().push(event);
+        }));
+
+        reporter.embed_retry(2, 3, Duration::from_millis(400), "Rate limited by Ollama");
+
+        let captured = events.lock().unwrap();
+        assert_eq!(captured[0].phase, "retry");
+        assert_eq!(captured[0].current, 2);
+        assert_eq!(captured[0].total, None);
+        assert_eq!(
+            captured[0].message,
+            "Rate limited by Ollama; retrying in 400ms (attempt 2/3)"
+        );
+    }
+
     #[test]
     fn test_noop_reporter() {
         let reporter = ProgressReporter::noop();
@@ -3,22 +3,32 @@
 //! Retrieves relevant chunks and generates natural language answers via LLM.
 
 use crate::chunk::ChunkMetadata;
-use crate::rag::search::{detect_query_filters, SearchFilters};
-use crate::rag::types::{RagResponse, RagSourceRef, CONFIDENCE_THRESHOLD};
+use crate::rag::cache::AnswerCache;
+use crate::rag::context::build_context;
+use crate::rag::extractive;
+use crate::rag::feedback::{self, AnswerRecord};
+use crate::rag::highlight;
+use crate::rag::types::{AnswerEvent, AnswerObserver, RagBestOf, RagResponse, RagSourceRef};
+use crate::rag::verify::{self, Verification};
 use crate::types::{AskOptions, KnowledgeChunk};
-use crate::{config, lancedb_index, vector_index::VectorIndex};
+use crate::KnowledgeBase;
+use futures::StreamExt;
+use guided_core::config::ProviderConfig;
 use guided_core::{AppError, AppResult};
-use guided_llm::LlmRequest;
+use guided_llm::{JudgeMode, LlmRequest};
 use std::collections::HashMap;
 use std::path::Path;
-
-/// Minimum cosine similarity score for a chunk to be considered relevant.
-/// Note: 0.08 is suitable for trigram embeddings (lower semantic accuracy);
-/// production systems with neural embeddings should use 0.3-0.5.
-const MIN_RELEVANCE_SCORE: f32 = 0.08;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Maximum snippet length for source references.
-const MAX_SNIPPET_LENGTH: usize = 150;
+pub(crate) const MAX_SNIPPET_LENGTH: usize = 150;
+
+/// Sentences quoted in an extractive answer.
+const EXTRACTIVE_SENTENCES: usize = 3;
+
+/// Model answers are requested from when the provider has no configured
+/// chat model.
+const DEFAULT_ANSWER_MODEL: &str = "llama3";
 
 /// Ask a question and generate a natural language answer using RAG.
 ///
@@ -26,7 +36,8 @@ const MAX_SNIPPET_LENGTH: usize = 150;
 /// 1. Retrieves relevant chunks from the vector index
 /// 2. Checks confidence levels
 /// 3. Builds context for the LLM
-/// 4. Generates answer via LLM synthesis
+/// 4. Generates answer via LLM synthesis (or, with `extractive`, quotes the
+///    sentences that best match the query)
 /// 5. Maps chunks to human-readable source references
 pub async fn ask_rag(
     workspace: &Path,
@@ -34,69 +45,63 @@ pub async fn ask_rag(
     llm_provider: &str,
     api_key: Option<&str>,
 ) -> AppResult<RagResponse> {
+    answer_query(workspace, options, llm_provider, api_key, None).await
+}
+
+/// Like [`ask_rag`], streaming the answer to `observer` as the LLM writes it.
+///
+/// The cited sources are reported once retrieved, before the answer. Cached
+/// and best-of-N answers arrive in one piece.
+pub async fn ask_rag_streaming(
+    workspace: &Path,
+    options: AskOptions,
+    llm_provider: &str,
+    api_key: Option<&str>,
+    observer: &AnswerObserver<'_>,
+) -> AppResult<RagResponse> {
+    answer_query(workspace, options, llm_provider, api_key, Some(observer)).await
+}
+
+async fn answer_query(
+    workspace: &Path,
+    options: AskOptions,
+    llm_provider: &str,
+    api_key: Option<&str>,
+    observer: Option<&AnswerObserver<'_>>,
+) -> AppResult<RagResponse> {
+    let emit = |event| {
+        if let Some(observer) = observer {
+            observer(event);
+        }
+    };
     tracing::info!(
         "RAG answering for knowledge base '{}' with query: {}",
         options.base_name,
         options.query
     );
 
-    // Load config
-    let config = config::load_config(workspace, &options.base_name)?;
-
-    // Check if index exists
-    let index_path = config::get_index_path(workspace, &options.base_name);
-    if !index_path.exists() {
-        return Err(AppError::Knowledge(format!(
-            "Knowledge base '{}' has no index. Run 'guided knowledge learn' first.",
-            options.base_name
-        )));
-    }
-
-    // Initialize LanceDB index
-    let index =
-        lancedb_index::LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize)
-            .await?;
-
-    // Generate query embedding using EmbeddingEngine
-    let engine = crate::embeddings::EmbeddingEngine::new(workspace.to_path_buf());
-    let query_embeddings = engine.embed_texts(&options.base_name, &[options.query.clone()], api_key).await?;
-    let query_embedding = query_embeddings.into_iter().next().ok_or_else(|| {
-        AppError::Knowledge("Failed to generate query embedding".to_string())
-    })?;
-
-    // Retrieve top-k chunks
-    let results = index.search(&query_embedding, options.top_k as usize)?;
-
-    tracing::debug!(
-        "Retrieved {} chunks before filtering",
-        results.len()
-    );
-
-    // Detect query intent and apply automatic filters
-    let auto_filters = detect_query_filters(&options.query);
-    
-    // Apply relevance cutoff
-    let mut filtered_results: Vec<_> = results
-        .into_iter()
-        .filter(|(_chunk, score)| *score >= MIN_RELEVANCE_SCORE)
-        .collect();
-
-    // Apply automatic metadata filters if detected
-    if auto_filters.has_filters() {
-        tracing::debug!(
-            "Applying automatic filters: file_types={:?}, languages={:?}",
-            auto_filters.file_types,
-            auto_filters.languages
-        );
-        filtered_results = auto_filters.apply(filtered_results);
+    // The chunks `ask` returns for the same options
+    let base = KnowledgeBase::open_cached(workspace, &options.base_name, &options.provider_configs)
+        .await?;
+    let config = base.config();
+    let retrieval = base.retrieve(&options, api_key).await?;
+    let filtered_results = retrieval.chunks;
+    let overview = retrieval.overview;
+
+    // Remember what was retrieved, for `feedback --last`
+    let record = AnswerRecord::new(&options.query, options.namespace.clone(), &filtered_results);
+    if let Err(e) = feedback::record_answer(workspace, &options.base_name, &record) {
+        tracing::warn!("Failed to record answer for feedback: {}", e);
     }
 
     if filtered_results.is_empty() {
         tracing::info!(
             "No relevant chunks found (all scores below {:.2} threshold or filtered out)",
-            MIN_RELEVANCE_SCORE
+            config.min_relevance()
         );
-        return Ok(RagResponse::no_information(&options.query));
+        let response = RagResponse::no_information(&options.query);
+        emit(AnswerEvent::Token(response.answer.clone()));
+        return Ok(response);
     }
 
     let chunks: Vec<KnowledgeChunk> = filtered_results
@@ -109,7 +114,8 @@ pub async fn ask_rag(
         .collect();
 
     let max_score = scores.first().copied().unwrap_or(0.0);
-    let low_confidence = max_score < CONFIDENCE_THRESHOLD;
+    let confidence_threshold = config.confidence_threshold();
+    let low_confidence = max_score < confidence_threshold;
 
     tracing::info!(
         "Retrieved {} relevant chunks (max score: {:.3}, low_confidence: {})",
@@ -118,87 +124,144 @@ pub async fn ask_rag(
         low_confidence
     );
 
+    // Map chunks to source references
+    let sources = map_chunks_to_sources(&chunks, &options.query, workspace);
+    emit(AnswerEvent::Sources(sources.clone()));
+
+    // Without an LLM, quote the sentences that best match the query
+    if options.extractive {
+        let answer = extractive_answer(&options.query, &chunks);
+        emit(AnswerEvent::Token(answer.clone()));
+        let mut response = RagResponse::new(answer, sources, max_score)
+            .with_confidence_threshold(confidence_threshold);
+        response.extractive = true;
+        return Ok(response);
+    }
+
     // Build context for LLM
-    let context = build_context(&chunks)?;
+    let mut context = build_context(&chunks);
+    if !overview.is_empty() {
+        context = format!(
+            "{}\n\n---\n\n{}",
+            crate::summaries::overview_context(&overview),
+            context
+        );
+    }
 
-    // Generate answer via LLM
-    let answer = generate_answer(
-        llm_provider,
-        api_key,
+    let llm_config = options.provider_configs.get(llm_provider);
+    let request = answer_request(
+        answer_model(llm_config),
         &options.query,
         &context,
         low_confidence,
-    )
-    .await?;
+        options.style.as_deref(),
+    );
 
-    // Map chunks to source references
-    let sources = map_chunks_to_sources(&chunks);
+    // Serve a cached answer written from the same chunks with the same
+    // request. Best-of answers are not cached: their candidates are what
+    // the caller wants to see.
+    let cache = (options.cache && options.best_of.is_none() && config.encryption.is_none())
+        .then(|| AnswerCache::new(workspace, &options.base_name));
+    let cache_key = AnswerCache::key(&options.query, llm_provider, &request, &chunks, &overview);
+    if let Some(cached) = cache.as_ref().and_then(|cache| cache.get(&cache_key)) {
+        tracing::info!("Answer served from cache");
+        emit(AnswerEvent::Token(cached.answer.clone()));
+        let mut response = cached.with_confidence_threshold(confidence_threshold);
+        if options.verify && response.verification.is_none() {
+            let verification = verify_answer(
+                &options,
+                llm_provider,
+                api_key,
+                &response.answer,
+                &context,
+                chunks.len(),
+            )
+            .await?;
+            response.verification = Some(verification);
+        }
+        return Ok(response);
+    }
 
-    Ok(RagResponse::new(answer, sources, max_score))
+    // Generate answer via LLM
+    let judge = if options.synthesize {
+        JudgeMode::Synthesize
+    } else {
+        JudgeMode::Select
+    };
+    let best_of = options.best_of.map(|n| (n as usize, judge));
+    let (answer, best_of) = options
+        .cancel
+        .run(
+            "knowledge ask",
+            generate_answer(
+                llm_provider,
+                llm_config,
+                api_key,
+                &request,
+                best_of,
+                observer,
+            ),
+        )
+        .await?;
+
+    let mut response = RagResponse::new(answer, sources, max_score)
+        .with_confidence_threshold(confidence_threshold);
+    response.best_of = best_of;
+    if options.verify {
+        let verification = verify_answer(
+            &options,
+            llm_provider,
+            api_key,
+            &response.answer,
+            &context,
+            chunks.len(),
+        )
+        .await?;
+        response.verification = Some(verification);
+    }
+    if let Some(cache) = &cache {
+        if let Err(e) = cache.put(&cache_key, &options.query, &response) {
+            tracing::warn!("Failed to cache answer: {}", e);
+        }
+    }
+    Ok(response)
 }
 
-/// Build context string from chunks for LLM prompt.
-fn build_context(chunks: &[KnowledgeChunk]) -> AppResult<String> {
-    let context_parts: Vec<String> = chunks
-        .iter()
-        .enumerate()
-        .map(|(i, chunk)| {
-            // Extract metadata from chunk
-            let metadata_info = if let Ok(metadata) = serde_json::from_value::<ChunkMetadata>(chunk.metadata.clone()) {
-                if let Some(custom) = metadata.custom.as_object() {
-                    let mut info = String::new();
-                    
-                    // Source path
-                    if let Some(source_path) = custom.get("source_path").and_then(|v| v.as_str()) {
-                        info.push_str(&format!("File: {}\n", source_path));
-                    }
-                    
-                    // File size
-                    if let Some(file_size) = custom.get("file_size_bytes").and_then(|v| v.as_u64()) {
-                        info.push_str(&format!("Size: {} bytes\n", file_size));
-                    }
-                    
-                    // Line count
-                    if let Some(line_count) = custom.get("file_line_count").and_then(|v| v.as_u64()) {
-                        info.push_str(&format!("Lines: {}\n", line_count));
-                    }
-                    
-                    info
-                } else {
-                    String::new()
-                }
-            } else {
-                String::new()
-            };
-
+/// Answer `query` with the sentences of `chunks` that best match it, one
+/// per line with the file it comes from.
+fn extractive_answer(query: &str, chunks: &[KnowledgeChunk]) -> String {
+    extractive::select(query, chunks, EXTRACTIVE_SENTENCES)
+        .into_iter()
+        .map(|extract| {
             format!(
-                "[Document {}]\n{}Content:\n{}",
-                i + 1,
-                metadata_info,
-                chunk.text
+                "- {} ({})",
+                extract.sentence,
+                extract_source_name(&chunks[extract.chunk])
             )
         })
-        .collect();
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    Ok(context_parts.join("\n\n---\n\n"))
+/// Chat model of the answering provider's config, e.g. the model pulled on
+/// a remote Ollama host.
+fn answer_model(provider_config: Option<&ProviderConfig>) -> &str {
+    provider_config
+        .and_then(ProviderConfig::model)
+        .unwrap_or(DEFAULT_ANSWER_MODEL)
 }
 
-/// Generate answer by calling LLM with RAG prompt.
-async fn generate_answer(
-    provider: &str,
-    api_key: Option<&str>,
+/// Build the LLM request answering `query` from `context` with `model`, in
+/// `style` if given.
+fn answer_request(
+    model: &str,
     query: &str,
     context: &str,
     low_confidence: bool,
-) -> AppResult<String> {
-    tracing::debug!("Generating answer with LLM (provider: {}, low_confidence: {})", provider, low_confidence);
-
-    // Create LLM client
-    let client = guided_llm::create_client(provider, None, api_key)
-        .map_err(|e| AppError::Knowledge(format!("Failed to create LLM client: {}", e)))?;
-
+    style: Option<&str>,
+) -> LlmRequest {
     // Build system prompt
-    let system_prompt = build_system_prompt(low_confidence);
+    let system_prompt = build_system_prompt(low_confidence, style);
 
     // Build user prompt
     let user_prompt = format!(
@@ -207,22 +270,103 @@ async fn generate_answer(
     );
 
     // Create request
-    let request = LlmRequest::new(user_prompt, "llama3")
+    LlmRequest::new(user_prompt, model)
         .with_system(system_prompt)
         .with_temperature(0.1) // Very low temperature to reduce hallucination
-        .with_max_tokens(1000);
+        .with_max_tokens(1000)
+}
+
+/// Generate answer by calling LLM with RAG prompt, best of `best_of`
+/// samples if given, streamed to `observer` if given.
+async fn generate_answer(
+    provider: &str,
+    provider_config: Option<&ProviderConfig>,
+    api_key: Option<&str>,
+    request: &LlmRequest,
+    best_of: Option<(usize, JudgeMode)>,
+    observer: Option<&AnswerObserver<'_>>,
+) -> AppResult<(String, Option<RagBestOf>)> {
+    tracing::debug!("Generating answer with LLM (provider: {})", provider);
+
+    // Create LLM client
+    let client = guided_llm::create_client_from_config(provider, provider_config, api_key)
+        .map_err(|e| AppError::Knowledge(format!("Failed to create LLM client: {}", e)))?;
+
+    if let Some((n, judge)) = best_of {
+        // Low-temperature samples barely differ; the judge guards faithfulness
+        let best = guided_llm::best_of(client.as_ref(), request, n, judge)
+            .await
+            .map_err(|e| AppError::Knowledge(format!("LLM request failed: {}", e)))?;
+        if let Some(observer) = observer {
+            observer(AnswerEvent::Token(best.answer.clone()));
+        }
+        return Ok((best.answer.clone(), Some(RagBestOf::from(&best))));
+    }
+
+    if let Some(observer) = observer {
+        let mut stream = client
+            .stream(&request.clone().with_streaming())
+            .await
+            .map_err(|e| AppError::Knowledge(format!("LLM request failed: {}", e)))?;
+        let mut answer = String::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk =
+                chunk.map_err(|e| AppError::Knowledge(format!("LLM request failed: {}", e)))?;
+            if !chunk.content.is_empty() {
+                answer.push_str(&chunk.content);
+                observer(AnswerEvent::Token(chunk.content));
+            }
+        }
+        return Ok((answer, None));
+    }
 
     // Send request
     let response = client
-        .complete(&request)
+        .complete(request)
         .await
         .map_err(|e| AppError::Knowledge(format!("LLM request failed: {}", e)))?;
 
-    Ok(response.content)
+    Ok((response.content, None))
+}
+
+/// Check the claims of `answer` against the `documents` numbered chunks of
+/// `context`, with the provider that wrote it.
+async fn verify_answer(
+    options: &AskOptions,
+    llm_provider: &str,
+    api_key: Option<&str>,
+    answer: &str,
+    context: &str,
+    documents: usize,
+) -> AppResult<Verification> {
+    let llm_config = options.provider_configs.get(llm_provider);
+    let client = guided_llm::create_client_from_config(llm_provider, llm_config, api_key)
+        .map_err(|e| AppError::Knowledge(format!("Failed to create LLM client: {}", e)))?;
+    let verification = options
+        .cancel
+        .run(
+            "knowledge verify",
+            verify::verify(
+                client.as_ref(),
+                answer_model(llm_config),
+                &options.query,
+                answer,
+                context,
+                documents,
+            ),
+        )
+        .await?;
+    tracing::info!(
+        "Verified answer: {} claims, {} unsupported, grounded: {}",
+        verification.claims.len(),
+        verification.unsupported().count(),
+        verification.grounded
+    );
+    Ok(verification)
 }
 
 /// Build system prompt for RAG answering.
-fn build_system_prompt(low_confidence: bool) -> String {
+fn build_system_prompt(low_confidence: bool, style: Option<&str>) -> String {
     let mut prompt = String::from(
         "You are a knowledge assistant with access to the user's document collection.\n\n"
     );
@@ -245,15 +389,26 @@ fn build_system_prompt(low_confidence: bool) -> String {
          - Do not use phrases like \"Based on the provided information\", \"According to the context\", \"De acordo com o Documento X\"\n\
          - Do not mention technical terms like \"chunks\", \"embeddings\", \"documents\", \"Document 1\", or \"RAG\"\n\
          - Answer naturally as if you had read the original documents\n\
-         - Simply state facts without saying where they came from\n\
-         - Be concise and factual\n"
+         - Simply state facts without saying where they came from\n"
     );
 
+    // The requested form replaces the default concise answer
+    match style.map(str::trim).filter(|style| !style.is_empty()) {
+        Some(style) => prompt.push_str(&format!("- Be factual\n\nAnswer format:\n{}\n", style)),
+        None => prompt.push_str("- Be concise and factual\n"),
+    }
+
     prompt
 }
 
 /// Map chunks to human-readable source references.
-fn map_chunks_to_sources(chunks: &[KnowledgeChunk]) -> Vec<RagSourceRef> {
+///
+/// Relative source paths are resolved against `workspace`.
+fn map_chunks_to_sources(
+    chunks: &[KnowledgeChunk],
+    query: &str,
+    workspace: &Path,
+) -> Vec<RagSourceRef> {
     // Deduplicate by (source, location)
     let mut seen = HashMap::new();
     let mut sources = Vec::new();
@@ -263,13 +418,21 @@ fn map_chunks_to_sources(chunks: &[KnowledgeChunk]) -> Vec<RagSourceRef> {
         let location = extract_location(chunk);
         let key = (source.clone(), location.clone());
 
-        if !seen.contains_key(&key) {
-            seen.insert(key, true);
-
+        if seen.insert(key, true).is_none() {
+            let metadata = serde_json::from_value::<ChunkMetadata>(chunk.metadata.clone()).ok();
+            let snippet = truncate_snippet(&chunk.text, MAX_SNIPPET_LENGTH);
             sources.push(RagSourceRef {
                 source,
                 location,
-                snippet: truncate_snippet(&chunk.text, MAX_SNIPPET_LENGTH),
+                highlights: highlight::match_ranges(query, &snippet),
+                snippet,
+                path: metadata
+                    .as_ref()
+                    .and_then(|m| m.custom.get("source_path"))
+                    .and_then(|v| v.as_str())
+                    .map(|p| absolute_path(workspace, p)),
+                time_range: metadata.as_ref().and_then(time_range),
+                line_range: metadata.and_then(|m| m.line_range),
             });
         }
     }
@@ -277,6 +440,16 @@ fn map_chunks_to_sources(chunks: &[KnowledgeChunk]) -> Vec<RagSourceRef> {
     sources
 }
 
+/// Resolve a learned source path against the workspace, without touching
+/// the filesystem.
+pub(crate) fn absolute_path(workspace: &Path, path: &str) -> String {
+    let joined = workspace.join(path);
+    std::path::absolute(&joined)
+        .unwrap_or(joined)
+        .to_string_lossy()
+        .to_string()
+}
+
 /// Extract human-readable source name from source_id or chunk metadata.
 fn extract_source_name(chunk: &KnowledgeChunk) -> String {
     // Try to get source from metadata first
@@ -285,20 +458,17 @@ fn extract_source_name(chunk: &KnowledgeChunk) -> String {
             if let Some(source_path) = custom.get("source_path") {
                 if let Some(path_str) = source_path.as_str() {
                     // Extract filename from path
-                    if let Some(filename) = path_str.rsplit('/').next() {
-                        return filename.to_string();
-                    }
+                    return crate::paths::file_name(path_str).to_string();
                 }
             }
         }
     }
 
     // Fallback: try to parse source_id as path
-    if let Some(filename) = chunk.source_id.rsplit('/').next() {
-        // Check if it looks like a filename (has extension)
-        if filename.contains('.') {
-            return filename.to_string();
-        }
+    let filename = crate::paths::file_name(&chunk.source_id);
+    // Check if it looks like a filename (has extension)
+    if filename.contains('.') {
+        return filename.to_string();
     }
 
     // Ultimate fallback: truncate UUID
@@ -313,6 +483,10 @@ fn extract_source_name(chunk: &KnowledgeChunk) -> String {
 fn extract_location(chunk: &KnowledgeChunk) -> String {
     // Try to parse metadata
     if let Ok(metadata) = serde_json::from_value::<ChunkMetadata>(chunk.metadata.clone()) {
+        if let Some((start, end)) = time_range(&metadata) {
+            return crate::transcripts::format_time_range(start, end);
+        }
+
         if let Some((start, end)) = metadata.line_range {
             return format!("lines {}-{}", start, end);
         }
@@ -326,18 +500,34 @@ fn extract_location(chunk: &KnowledgeChunk) -> String {
     format!("position {}", chunk.position)
 }
 
-/// Truncate snippet to maximum length.
-fn truncate_snippet(text: &str, max_len: usize) -> String {
-    if text.len() <= max_len {
-        text.to_string()
-    } else {
-        // Find a good break point (word boundary)
-        let truncated = &text[..max_len];
-        if let Some(last_space) = truncated.rfind(char::is_whitespace) {
-            format!("{}...", &truncated[..last_space])
-        } else {
-            format!("{}...", truncated)
+/// Seconds into the recording a transcript chunk covers.
+fn time_range(metadata: &ChunkMetadata) -> Option<(f64, f64)> {
+    let range = metadata.custom.get("time_range")?.as_array()?;
+    Some((range.first()?.as_f64()?, range.get(1)?.as_f64()?))
+}
+
+/// Truncate snippet to at most `max_chars` characters, without splitting a
+/// grapheme (an accented letter or an emoji sequence).
+pub(crate) fn truncate_snippet(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut end = 0;
+    let mut chars = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        chars += grapheme.chars().count();
+        if chars > max_chars {
+            break;
         }
+        end = i + grapheme.len();
+    }
+
+    // Find a good break point (word boundary)
+    let truncated = &text[..end];
+    match truncated.rfind(char::is_whitespace) {
+        Some(last_space) if last_space > 0 => format!("{}...", &truncated[..last_space]),
+        _ => format!("{}...", truncated),
     }
 }
 
@@ -345,6 +535,24 @@ fn truncate_snippet(text: &str, max_len: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_answers_use_the_configured_model() {
+        let ollama = ProviderConfig::Ollama {
+            endpoint: "http://gpu-box:11434".to_string(),
+            model: "mistral".to_string(),
+            embedding_model: None,
+            timeout: None,
+        };
+        let request = answer_request(answer_model(Some(&ollama)), "Why?", "", false, None);
+        assert_eq!(request.model, "mistral");
+
+        let mock = ProviderConfig::Mock {
+            script: "replies.yaml".to_string(),
+        };
+        assert_eq!(answer_model(Some(&mock)), DEFAULT_ANSWER_MODEL);
+        assert_eq!(answer_model(None), DEFAULT_ANSWER_MODEL);
+    }
+
     #[test]
     fn test_extract_source_name() {
         use crate::chunk::ChunkMetadata;
@@ -372,6 +580,7 @@ mod tests {
             position: 0,
             text: "test".to_string(),
             embedding: None,
+            title_embedding: None,
             metadata: serde_json::to_value(&metadata).unwrap(),
         };
         
@@ -384,6 +593,7 @@ mod tests {
             position: 0,
             text: "test".to_string(),
             embedding: None,
+            title_embedding: None,
             metadata: serde_json::json!({}),
         };
         
@@ -391,48 +601,126 @@ mod tests {
     }
 
     #[test]
-    fn test_truncate_snippet() {
-        let short = "Short text";
-        assert_eq!(truncate_snippet(short, 100), "Short text");
-
-        let long = "This is a very long text that needs to be truncated at some point";
-        let result = truncate_snippet(long, 30);
-        assert!(result.len() <= 33); // 30 + "..."
-        assert!(result.ends_with("..."));
-    }
-
-    #[test]
-    fn test_build_context() {
+    fn test_sources_keep_path_and_line_range() {
+        let metadata = ChunkMetadata {
+            content_type: crate::chunk::ContentType::Text,
+            language: None,
+            byte_range: (0, 100),
+            line_range: Some((12, 34)),
+            char_count: 100,
+            token_count: None,
+            hash: "test".to_string(),
+            created_at: chrono::Utc::now(),
+            splitter_used: "test".to_string(),
+            custom: serde_json::json!({"source_path": "docs/guide.md"}),
+        };
         let chunks = vec![
             KnowledgeChunk {
                 id: "1".to_string(),
-                source_id: "test.md".to_string(),
+                source_id: "uuid-1".to_string(),
                 position: 0,
-                text: "First chunk".to_string(),
+                text: "evidence".to_string(),
                 embedding: None,
-                metadata: serde_json::json!({}),
+                title_embedding: None,
+                metadata: serde_json::to_value(&metadata).unwrap(),
             },
             KnowledgeChunk {
                 id: "2".to_string(),
-                source_id: "test.md".to_string(),
-                position: 1,
-                text: "Second chunk".to_string(),
+                source_id: "uuid-2".to_string(),
+                position: 0,
+                text: "no metadata".to_string(),
                 embedding: None,
+                title_embedding: None,
                 metadata: serde_json::json!({}),
             },
         ];
 
-        let context = build_context(&chunks).unwrap();
-        assert!(context.contains("First chunk"));
-        assert!(context.contains("Second chunk"));
-        assert!(context.contains("[Document 1]"));
-        assert!(context.contains("[Document 2]"));
-        assert!(context.contains("---"));
+        let workspace = std::env::temp_dir();
+        let sources = map_chunks_to_sources(&chunks, "Which evidence is there?", &workspace);
+
+        assert_eq!(sources[0].source, "guide.md");
+        assert_eq!(sources[0].location, "lines 12-34");
+        assert_eq!(
+            sources[0].path.as_deref().map(Path::new),
+            Some(workspace.join("docs/guide.md").as_path())
+        );
+        assert_eq!(sources[0].line_range, Some((12, 34)));
+        assert_eq!(sources[0].highlights, vec![(0, 8)]);
+        assert_eq!(sources[1].path, None);
+        assert_eq!(sources[1].line_range, None);
+        assert!(sources[1].highlights.is_empty());
+    }
+
+    #[test]
+    fn test_transcript_sources_cite_time_range() {
+        let metadata = ChunkMetadata {
+            content_type: crate::chunk::ContentType::Text,
+            language: None,
+            byte_range: (0, 100),
+            line_range: None,
+            char_count: 100,
+            token_count: None,
+            hash: "test".to_string(),
+            created_at: chrono::Utc::now(),
+            splitter_used: "test".to_string(),
+            custom: serde_json::json!({
+                "source_path": "meetings/meeting-2024-03.mp4",
+                "media_path": "meetings/meeting-2024-03.mp4",
+                "time_range": [750.0, 845.4],
+            }),
+        };
+        let chunks = vec![KnowledgeChunk {
+            id: "1".to_string(),
+            source_id: "uuid-1".to_string(),
+            position: 0,
+            text: "[12:30] The billing migration ships on April 2nd.".to_string(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::to_value(&metadata).unwrap(),
+        }];
+
+        let sources = map_chunks_to_sources(&chunks, "", &std::env::temp_dir());
+
+        assert_eq!(sources[0].source, "meeting-2024-03.mp4");
+        assert_eq!(sources[0].location, "12:30–14:05");
+        assert_eq!(sources[0].time_range, Some((750.0, 845.4)));
+        assert_eq!(sources[0].line_range, None);
+    }
+
+    #[test]
+    fn test_truncate_snippet() {
+        let short = "Short text";
+        assert_eq!(truncate_snippet(short, 100), "Short text");
+
+        let long = "This is a very long text that needs to be truncated at some point";
+        let result = truncate_snippet(long, 30);
+        assert!(result.len() <= 33); // 30 + "..."
+        assert!(result.ends_with("..."));
+    }
+
+    #[test]
+    fn test_truncate_snippet_multibyte() {
+        // Cutting "ação" after 30 bytes would land inside "ç"
+        let accented = "A configuração da aplicação não gerencia a sessão do usuário";
+        assert_eq!(
+            truncate_snippet(accented, 30),
+            "A configuração da aplicação..."
+        );
+
+        // Emoji and combining accents are kept whole
+        assert_eq!(truncate_snippet("🚀🚀🚀🚀", 2), "🚀🚀...");
+        assert_eq!(truncate_snippet("👩‍💻👩‍💻", 4), "👩‍💻...");
+        assert_eq!(truncate_snippet("cafe\u{301} noir", 4), "caf...");
+
+        for max in 0..40 {
+            let result = truncate_snippet(accented, max);
+            assert!(result.trim_end_matches("...").chars().count() <= max);
+        }
     }
 
     #[test]
     fn test_build_system_prompt_normal() {
-        let prompt = build_system_prompt(false);
+        let prompt = build_system_prompt(false, None);
         assert!(prompt.contains("knowledge assistant"));
         assert!(prompt.contains("Do not mention"));
         assert!(!prompt.contains("may not directly answer"));
@@ -440,8 +728,19 @@ mod tests {
 
     #[test]
     fn test_build_system_prompt_low_confidence() {
-        let prompt = build_system_prompt(true);
+        let prompt = build_system_prompt(true, None);
         assert!(prompt.contains("may not directly answer"));
         assert!(prompt.contains("Be cautious"));
     }
+
+    #[test]
+    fn test_build_system_prompt_with_style() {
+        let prompt = build_system_prompt(false, Some("Answer with numbered steps."));
+        assert!(prompt.ends_with("- Be factual\n\nAnswer format:\nAnswer with numbered steps.\n"));
+        assert!(!prompt.contains("Be concise"));
+        assert_eq!(
+            build_system_prompt(false, Some("  ")),
+            build_system_prompt(false, None)
+        );
+    }
 }
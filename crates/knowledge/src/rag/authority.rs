@@ -0,0 +1,137 @@
+//! Authoritative and pinned sources.
+//!
+//! `guided knowledge tag --weight 1.5` marks a source as authoritative: the
+//! scores of its chunks are multiplied by the weight, so the canonical
+//! architecture doc outranks meeting notes using the same words. `--pin`
+//! includes a source in the answer to every query that filters on one of
+//! its tags, whether or not its chunks rank in the top-k.
+
+use crate::lancedb_index::LanceDbIndex;
+use crate::rag::{SearchFilters, SourceManager};
+use crate::types::{KnowledgeChunk, KnowledgeSource};
+use guided_core::AppResult;
+use std::path::Path;
+
+/// The weighted and pinned sources of a base, as they bear on one query.
+#[derive(Debug, Default)]
+pub struct Authority {
+    /// Whether any source has a weight
+    weighted: bool,
+
+    /// Pinned sources with a tag the query filters on
+    pinned: Vec<KnowledgeSource>,
+}
+
+impl Authority {
+    /// Read the curated sources of `base_name` that apply to a query with
+    /// `filters`.
+    pub fn load(workspace: &Path, base_name: &str, filters: &SearchFilters) -> AppResult<Self> {
+        let sources = SourceManager::new(workspace, base_name).latest_by_path()?;
+        let mut authority = Self {
+            weighted: sources.values().any(|source| source.weight.is_some()),
+            pinned: Vec::new(),
+        };
+        if let Some(tags) = &filters.tags {
+            authority.pinned = sources
+                .into_values()
+                .filter(|source| source.pinned && has_tag(source, tags))
+                .collect();
+            authority.pinned.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        Ok(authority)
+    }
+
+    /// Whether weights reorder chunks after scoring, so a wider pool of
+    /// candidates should be retrieved for them to pick from.
+    pub fn widens_search(&self) -> bool {
+        self.weighted
+    }
+
+    /// Multiply the scores of weighted sources' chunks by their weight, best
+    /// first.
+    pub fn weigh(&self, mut results: Vec<(KnowledgeChunk, f32)>) -> Vec<(KnowledgeChunk, f32)> {
+        if !self.weighted {
+            return results;
+        }
+        for (chunk, score) in results.iter_mut() {
+            if let Some(weight) = chunk.metadata_values("weight").find_map(|v| v.as_f64()) {
+                *score *= weight as f32;
+            }
+        }
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        results
+    }
+
+    /// Add the best `per_source` chunks of each pinned source that pass
+    /// `filters` to `results`, best first.
+    pub async fn include_pinned(
+        &self,
+        index: &LanceDbIndex,
+        query_embedding: &[f32],
+        filters: &SearchFilters,
+        per_source: usize,
+        mut results: Vec<(KnowledgeChunk, f32)>,
+    ) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
+        if self.pinned.is_empty() {
+            return Ok(results);
+        }
+        for source in &self.pinned {
+            let chunks = index
+                .score_source_path(&source.path, query_embedding)
+                .await?;
+            let chunks = self.weigh(filters.apply(chunks));
+            for (chunk, score) in chunks.into_iter().take(per_source) {
+                if !results.iter().any(|(kept, _)| kept.id == chunk.id) {
+                    results.push((chunk, score));
+                }
+            }
+        }
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(results)
+    }
+}
+
+/// Whether `source` carries one of `tags` (ignoring case).
+fn has_tag(source: &KnowledgeSource, tags: &[String]) -> bool {
+    source
+        .curate_tags(crate::metadata::derive_tags(Path::new(&source.path)))
+        .iter()
+        .any(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &str, weight: Option<f32>) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: id.to_string(),
+            source_id: id.to_string(),
+            position: 0,
+            text: String::new(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::json!({"custom": {"weight": weight}}),
+        }
+    }
+
+    #[test]
+    fn test_weigh_ranks_authoritative_sources_first() {
+        let results = vec![
+            (chunk("notes", None), 0.8),
+            (chunk("design", Some(1.5)), 0.6),
+        ];
+
+        let authority = Authority {
+            weighted: true,
+            ..Default::default()
+        };
+        let ranked = authority.weigh(results.clone());
+        assert_eq!(ranked[0].0.id, "design");
+        assert!((ranked[0].1 - 0.9).abs() < 1e-6);
+        assert_eq!(ranked[1].1, 0.8);
+
+        // Bases without weights keep their order
+        assert_eq!(Authority::default().weigh(results)[0].0.id, "notes");
+    }
+}
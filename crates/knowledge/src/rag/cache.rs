@@ -0,0 +1,291 @@
+//! Cache of RAG answers.
+//!
+//! An answer is keyed by the normalized question, the LLM provider, the
+//! request sent to it (model, prompts and generation parameters) and the
+//! chunks it was written from: each chunk's text hash and the content hash
+//! of its source file. Learning a changed file gives its chunks a new
+//! content hash, so every answer that drew on the file misses the cache
+//! while answers from other files are still served. Entries live under the
+//! base directory:
+//!
+//! ```text
+//! <base>/answers/<key>.json
+//! ```
+//!
+//! Encrypted bases are not cached, since answers would be stored in the clear.
+
+use crate::metadata::generate_content_hash;
+use crate::rag::types::{RagResponse, RagSourceRef};
+use crate::types::KnowledgeChunk;
+use chrono::{DateTime, Utc};
+use guided_core::{AppError, AppResult};
+use guided_llm::LlmRequest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Answers kept per base; the oldest are removed beyond this.
+const MAX_CACHED_ANSWERS: usize = 500;
+
+/// A cached answer.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    query: String,
+    answer: String,
+    sources: Vec<RagSourceRef>,
+    max_score: f32,
+    created_at: DateTime<Utc>,
+}
+
+/// Answer cache of one knowledge base.
+#[derive(Debug, Clone)]
+pub struct AnswerCache {
+    dir: PathBuf,
+}
+
+impl AnswerCache {
+    pub fn new(workspace: &Path, base_name: &str) -> Self {
+        Self {
+            dir: crate::config::get_answer_cache_path(workspace, base_name),
+        }
+    }
+
+    /// Cache key of an answer to `query` by `provider`, asked for with
+    /// `request` and written from `chunks` (in any order). `context` holds
+    /// anything else given to the LLM, such as hierarchical overviews.
+    ///
+    /// The request covers the model, the system and user prompts and the
+    /// generation parameters, so changing any of them misses the cache.
+    pub fn key(
+        query: &str,
+        provider: &str,
+        request: &LlmRequest,
+        chunks: &[KnowledgeChunk],
+        context: &[KnowledgeChunk],
+    ) -> String {
+        let hash = |kind: &str, chunk: &KnowledgeChunk| {
+            format!(
+                "{}:{}:{}",
+                kind,
+                generate_content_hash(&chunk.text),
+                chunk.metadata["custom"]["content_hash"]
+                    .as_str()
+                    .unwrap_or("")
+            )
+        };
+        let mut hashes: Vec<String> = chunks
+            .iter()
+            .map(|chunk| hash("chunk", chunk))
+            .chain(context.iter().map(|chunk| hash("context", chunk)))
+            .collect();
+        hashes.sort();
+
+        // Questions that normalize alike share an answer, so the prompt is
+        // compared with the question normalized too
+        let normalized = normalize_query(query);
+        let mut compared = request.clone();
+        compared.prompt = compared.prompt.replacen(query, &normalized, 1);
+        let compared = serde_json::to_string(&compared).unwrap_or_default();
+
+        let key = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            normalized,
+            provider,
+            request.model,
+            generate_content_hash(&compared),
+            hashes.join("\n")
+        );
+        generate_content_hash(&key)
+    }
+
+    /// Look up an answer. Unreadable entries are treated as misses.
+    pub fn get(&self, key: &str) -> Option<RagResponse> {
+        let json = std::fs::read_to_string(self.entry_path(key)).ok()?;
+        let entry: Entry = serde_json::from_str(&json)
+            .inspect_err(|e| tracing::debug!("Ignoring malformed cached answer {}: {}", key, e))
+            .ok()?;
+        let mut response = RagResponse::new(entry.answer, entry.sources, entry.max_score);
+        response.cached = true;
+        Some(response)
+    }
+
+    /// Store an answer, removing the oldest beyond [`MAX_CACHED_ANSWERS`].
+    pub fn put(&self, key: &str, query: &str, response: &RagResponse) -> AppResult<()> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            AppError::Knowledge(format!(
+                "Failed to create answer cache directory {:?}: {}",
+                self.dir, e
+            ))
+        })?;
+
+        let entry = Entry {
+            query: query.to_string(),
+            answer: response.answer.clone(),
+            sources: response.sources.clone(),
+            max_score: response.max_score,
+            created_at: Utc::now(),
+        };
+        let json = serde_json::to_string(&entry)
+            .map_err(|e| AppError::Knowledge(format!("Failed to serialize answer: {}", e)))?;
+
+        // Write then rename so concurrent askers never read a partial entry
+        let path = self.entry_path(key);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.prune()
+    }
+
+    /// Delete every cached answer. Returns the number removed.
+    pub fn clear(&self) -> AppResult<u64> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+        let count = self.entries()?.len() as u64;
+        std::fs::remove_dir_all(&self.dir).map_err(|e| {
+            AppError::Knowledge(format!(
+                "Failed to clear answer cache {:?}: {}",
+                self.dir, e
+            ))
+        })?;
+        Ok(count)
+    }
+
+    fn prune(&self) -> AppResult<()> {
+        let mut entries = self.entries()?;
+        if entries.len() <= MAX_CACHED_ANSWERS {
+            return Ok(());
+        }
+        entries.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+        for (path, _) in entries.into_iter().skip(MAX_CACHED_ANSWERS) {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    /// Cached answer files with their modification times.
+    fn entries(&self) -> AppResult<Vec<(PathBuf, std::time::SystemTime)>> {
+        Ok(std::fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| {
+                let modified = p.metadata().and_then(|m| m.modified()).ok()?;
+                Some((p, modified))
+            })
+            .collect())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// A question as compared by the cache: lowercase, single spaces, without
+/// trailing punctuation.
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['?', '!', '.'])
+        .trim_end()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chunk(text: &str, content_hash: &str) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: text.to_string(),
+            source_id: "s1".to_string(),
+            position: 0,
+            text: text.to_string(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::json!({"custom": {"content_hash": content_hash}}),
+        }
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("  How do I   deploy?? "),
+            normalize_query("how do i deploy")
+        );
+    }
+
+    fn request(query: &str) -> LlmRequest {
+        LlmRequest::new(format!("User question:\n{}", query), "llama3")
+            .with_system("Answer from the documents.")
+            .with_temperature(0.1)
+    }
+
+    #[test]
+    fn test_key_follows_chunks_and_sources() {
+        let a = chunk("Deploys run on Fridays.", "h1");
+        let b = chunk("Rollbacks use the previous tag.", "h2");
+        let query = "How to deploy?";
+        let key = |query: &str, provider: &str, chunks: &[KnowledgeChunk], context| {
+            AnswerCache::key(query, provider, &request(query), chunks, context)
+        };
+        let expected = key(query, "ollama", &[a.clone(), b.clone()], &[]);
+
+        assert_eq!(
+            expected,
+            key("how to deploy", "ollama", &[b.clone(), a.clone()], &[])
+        );
+        assert_ne!(expected, key(query, "openai", &[a.clone(), b.clone()], &[]));
+        // The same chunk text from an edited file
+        let edited = chunk("Rollbacks use the previous tag.", "h3");
+        assert_ne!(expected, key(query, "ollama", &[a.clone(), edited], &[]));
+        assert_ne!(
+            expected,
+            key(
+                query,
+                "ollama",
+                std::slice::from_ref(&a),
+                std::slice::from_ref(&b)
+            )
+        );
+    }
+
+    #[test]
+    fn test_key_follows_the_request() {
+        let chunks = [chunk("Deploys run on Fridays.", "h1")];
+        let query = "How to deploy?";
+        let key = |request: &LlmRequest| AnswerCache::key(query, "ollama", request, &chunks, &[]);
+        let expected = key(&request(query));
+
+        let mut other_model = request(query);
+        other_model.model = "mistral".to_string();
+        assert_ne!(expected, key(&other_model));
+        // Another system prompt, such as another answer style
+        assert_ne!(
+            expected,
+            key(&request(query).with_system("Answer in steps."))
+        );
+        assert_ne!(expected, key(&request(query).with_temperature(0.7)));
+        assert_ne!(expected, key(&request(query).with_max_tokens(200)));
+    }
+
+    #[test]
+    fn test_put_get_clear() {
+        let temp = TempDir::new().unwrap();
+        let cache = AnswerCache::new(temp.path(), "docs");
+        assert!(cache.get("k").is_none());
+
+        let response = RagResponse::new("Fridays.".to_string(), Vec::new(), 0.8);
+        cache.put("k", "When?", &response).unwrap();
+        let cached = cache.get("k").unwrap();
+        assert_eq!(cached.answer, "Fridays.");
+        assert_eq!(cached.max_score, 0.8);
+        assert!(cached.cached);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.get("k").is_none());
+    }
+}
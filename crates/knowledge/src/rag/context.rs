@@ -0,0 +1,214 @@
+//! Context given to the LLM from retrieved chunks.
+//!
+//! Each chunk is introduced by where it comes from (file, heading path or
+//! function, lines) and formatted by what it holds: code goes in a fenced
+//! block tagged with its language, so the model reads it as code rather than
+//! prose; markdown and other text are kept as they are, headings and lists
+//! included.
+
+use crate::chunk::{ChunkMetadata, ContentType, Language};
+use crate::types::KnowledgeChunk;
+
+/// Separator between chunks.
+const SEPARATOR: &str = "\n\n---\n\n";
+
+/// Build the context of an LLM prompt from `chunks`, numbered in order.
+pub fn build_context(chunks: &[KnowledgeChunk]) -> String {
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| format_chunk(i + 1, chunk))
+        .collect::<Vec<_>>()
+        .join(SEPARATOR)
+}
+
+/// One chunk: a header line, then its text.
+fn format_chunk(number: usize, chunk: &KnowledgeChunk) -> String {
+    // Chunks learned without metadata get a plain header
+    let metadata = serde_json::from_value::<ChunkMetadata>(chunk.metadata.clone()).ok();
+    let mut header = format!("[Document {}]", number);
+    let Some(metadata) = metadata else {
+        return format!("{}\n\n{}", header, chunk.text.trim_end());
+    };
+
+    if let Some(path) = custom_str(&metadata, "source_path") {
+        header.push_str(&format!(" {}", path));
+    }
+    if let Some(section) = section(&metadata) {
+        header.push_str(&format!(" > {}", section));
+    }
+    if let Some((start, end)) = metadata.line_range {
+        header.push_str(&format!(" (lines {}-{})", start, end));
+    }
+
+    match fence_tag(&metadata) {
+        Some(tag) => format!("{}\n\n{}", header, fenced(&chunk.text, tag)),
+        None => format!("{}\n\n{}", header, chunk.text.trim_end()),
+    }
+}
+
+/// Heading path or function a chunk starts in: its `section`, or for
+/// chunks learned before sections were recorded, its `title` without the
+/// file name.
+fn section(metadata: &ChunkMetadata) -> Option<&str> {
+    if let Some(section) = custom_str(metadata, "section") {
+        return Some(section);
+    }
+    let title = custom_str(metadata, "title")?;
+    let file_name = custom_str(metadata, "file_name").unwrap_or_default();
+    title
+        .strip_prefix(file_name)
+        .and_then(|rest| rest.strip_prefix(" > ").or_else(|| rest.strip_prefix(": ")))
+        .filter(|rest| !rest.is_empty())
+}
+
+/// Language tag of a code chunk's fence (empty when the language is not
+/// known), or `None` for prose.
+fn fence_tag(metadata: &ChunkMetadata) -> Option<&str> {
+    let file_language = custom_str(metadata, "language").filter(|l| *l != "unknown");
+    match &metadata.content_type {
+        ContentType::Code { language } if *language != Language::Unknown => Some(language.as_str()),
+        ContentType::Code { .. } => Some(file_language.unwrap_or("")),
+        // Code files the chunker split as text still read as code
+        _ if custom_str(metadata, "file_type") == Some("code") => Some(file_language.unwrap_or("")),
+        _ => None,
+    }
+}
+
+/// `text` in a fenced code block, the fence longer than any run of
+/// backticks inside it.
+fn fenced(text: &str, tag: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, tag, text.trim_end(), fence)
+}
+
+fn custom_str<'a>(metadata: &'a ChunkMetadata, key: &str) -> Option<&'a str> {
+    metadata.custom.get(key).and_then(|value| value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+
+    fn knowledge_chunk(
+        text: &str,
+        content_type: ContentType,
+        custom: serde_json::Value,
+        line_range: Option<(usize, usize)>,
+    ) -> KnowledgeChunk {
+        let mut chunk = Chunk::new(
+            "source".to_string(),
+            0,
+            text.to_string(),
+            (0, text.len()),
+            content_type,
+            "text-splitter".to_string(),
+        );
+        chunk.metadata.custom = custom;
+        chunk.metadata.line_range = line_range;
+        KnowledgeChunk {
+            id: chunk.id.clone(),
+            source_id: chunk.source_id.clone(),
+            position: chunk.position,
+            text: chunk.text.clone(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::to_value(&chunk.metadata).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_build_context_numbers_chunks() {
+        let plain = |text: &str| KnowledgeChunk {
+            id: text.to_string(),
+            source_id: "test.md".to_string(),
+            position: 0,
+            text: text.to_string(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::json!({}),
+        };
+
+        let context = build_context(&[plain("First chunk"), plain("Second chunk")]);
+        assert_eq!(
+            context,
+            "[Document 1]\n\nFirst chunk\n\n---\n\n[Document 2]\n\nSecond chunk"
+        );
+    }
+
+    #[test]
+    fn test_markdown_keeps_structure_under_its_heading_path() {
+        let text = "## Eviction\n\n- the oldest entry is evicted first\n";
+        let chunk = knowledge_chunk(
+            text,
+            ContentType::Markdown,
+            serde_json::json!({"source_path": "docs/guide.md", "section": "Cache > Eviction"}),
+            Some((12, 14)),
+        );
+
+        let context = build_context(&[chunk]);
+        assert_eq!(
+            context,
+            "[Document 1] docs/guide.md > Cache > Eviction (lines 12-14)\n\n\
+             ## Eviction\n\n- the oldest entry is evicted first"
+        );
+    }
+
+    #[test]
+    fn test_title_stands_in_for_a_missing_section() {
+        let chunk = knowledge_chunk(
+            "Entries expire after an hour.",
+            ContentType::Markdown,
+            serde_json::json!({
+                "source_path": "docs/guide.md",
+                "file_name": "guide.md",
+                "title": "guide.md > Cache > Expiry"
+            }),
+            None,
+        );
+        assert!(
+            build_context(&[chunk]).starts_with("[Document 1] docs/guide.md > Cache > Expiry\n")
+        );
+
+        // A title naming only the file adds nothing to the path
+        let chunk = knowledge_chunk(
+            "Entries expire after an hour.",
+            ContentType::Markdown,
+            serde_json::json!({
+                "source_path": "docs/guide.md",
+                "file_name": "guide.md",
+                "title": "guide.md"
+            }),
+            None,
+        );
+        assert!(build_context(&[chunk]).starts_with("[Document 1] docs/guide.md\n"));
+    }
+
+    #[test]
+    fn test_code_is_fenced_with_its_language() {
+        let chunk = knowledge_chunk(
+            "fn evict(&mut self) {}\n",
+            ContentType::Code {
+                language: Language::Rust,
+            },
+            serde_json::json!({"source_path": "src/cache.rs", "section": "Cache::evict"}),
+            Some((40, 40)),
+        );
+        assert_eq!(
+            build_context(&[chunk]),
+            "[Document 1] src/cache.rs > Cache::evict (lines 40-40)\n\n\
+             ```rust\nfn evict(&mut self) {}\n```"
+        );
+
+        // Code files split as text, with backticks in them
+        let chunk = knowledge_chunk(
+            "puts `date` # ```",
+            ContentType::Text,
+            serde_json::json!({"file_type": "code", "language": "ruby"}),
+            None,
+        );
+        assert!(build_context(&[chunk]).ends_with("````ruby\nputs `date` # ```\n````"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Maximal Marginal Relevance (MMR) diversification of retrieval results.
+//!
+//! Plain top-k retrieval often returns several near-identical chunks from one
+//! document. MMR picks chunks one at a time, trading each candidate's
+//! relevance against its similarity to the chunks already picked, using the
+//! stored embeddings.
+
+use crate::lancedb_index::cosine_similarity;
+use crate::types::KnowledgeChunk;
+use guided_core::{AppError, AppResult};
+
+/// Candidates retrieved per requested chunk when diversifying.
+pub const CANDIDATE_FACTOR: usize = 4;
+
+/// Number of candidates to retrieve for `top_k` results.
+///
+/// Fails if `diversity` is outside `0.0..=1.0`.
+pub fn candidate_count(top_k: usize, diversity: Option<f32>) -> AppResult<usize> {
+    match diversity {
+        None => Ok(top_k),
+        Some(d) if (0.0..=1.0).contains(&d) => Ok(top_k.saturating_mul(CANDIDATE_FACTOR)),
+        Some(d) => Err(AppError::Knowledge(format!(
+            "Diversity must be between 0.0 and 1.0, got {}",
+            d
+        ))),
+    }
+}
+
+/// Number of candidates to retrieve for `top_k` results: `candidates` when
+/// configured (never fewer than `top_k`), otherwise [`candidate_count`],
+/// widened as for diversity when results are filtered or re-ranked.
+///
+/// Fails if `diversity` is outside `0.0..=1.0`.
+pub fn pool_size(
+    top_k: usize,
+    candidates: Option<usize>,
+    diversity: Option<f32>,
+    widens: bool,
+) -> AppResult<usize> {
+    let count = candidate_count(top_k, diversity)?;
+    Ok(match candidates {
+        Some(candidates) => candidates.max(top_k),
+        None if widens => count.max(top_k.saturating_mul(CANDIDATE_FACTOR)),
+        None => count,
+    })
+}
+
+/// Pick up to `top_k` results by Maximal Marginal Relevance.
+///
+/// `results` must be sorted by descending relevance. `diversity` is the
+/// weight given to redundancy: 0.0 keeps the relevance order, 1.0 picks the
+/// most relevant chunk and then the ones least similar to what was picked.
+/// Each result keeps its relevance score; chunks without an embedding are
+/// treated as dissimilar to everything.
+pub fn select_diverse(
+    results: Vec<(KnowledgeChunk, f32)>,
+    top_k: usize,
+    diversity: f32,
+) -> Vec<(KnowledgeChunk, f32)> {
+    let lambda = 1.0 - diversity;
+    let mut candidates: Vec<Option<(KnowledgeChunk, f32)>> =
+        results.into_iter().map(Some).collect();
+    // Highest similarity of each candidate to the chunks picked so far
+    let mut redundancy = vec![0.0f32; candidates.len()];
+    let mut selected = Vec::with_capacity(top_k.min(candidates.len()));
+
+    while selected.len() < top_k {
+        let best = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.as_ref().map(|(_, score)| (i, *score)))
+            .map(|(i, score)| (i, lambda * score - (1.0 - lambda) * redundancy[i]))
+            .fold(None, |best: Option<(usize, f32)>, (i, mmr)| match best {
+                Some((_, best_mmr)) if best_mmr >= mmr => best,
+                _ => Some((i, mmr)),
+            });
+        let Some((chunk, score)) = best.and_then(|(i, _)| candidates[i].take()) else {
+            break;
+        };
+
+        if let Some(picked_embedding) = &chunk.embedding {
+            for (i, candidate) in candidates.iter().enumerate() {
+                if let Some(embedding) = candidate.as_ref().and_then(|(c, _)| c.embedding.as_ref())
+                {
+                    let similarity = cosine_similarity(picked_embedding, embedding);
+                    redundancy[i] = redundancy[i].max(similarity);
+                }
+            }
+        }
+        selected.push((chunk, score));
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, embedding: Vec<f32>, score: f32) -> (KnowledgeChunk, f32) {
+        (
+            KnowledgeChunk {
+                id: id.to_string(),
+                source_id: id.to_string(),
+                position: 0,
+                text: id.to_string(),
+                embedding: Some(embedding),
+                title_embedding: None,
+                metadata: serde_json::json!({}),
+            },
+            score,
+        )
+    }
+
+    fn ids(results: &[(KnowledgeChunk, f32)]) -> Vec<&str> {
+        results.iter().map(|(c, _)| c.id.as_str()).collect()
+    }
+
+    fn near_duplicates() -> Vec<(KnowledgeChunk, f32)> {
+        vec![
+            result("a1", vec![1.0, 0.0], 0.95),
+            result("a2", vec![0.99, 0.01], 0.94),
+            result("a3", vec![0.98, 0.02], 0.93),
+            result("b", vec![0.0, 1.0], 0.80),
+        ]
+    }
+
+    #[test]
+    fn test_zero_diversity_keeps_relevance_order() {
+        let selected = select_diverse(near_duplicates(), 3, 0.0);
+        assert_eq!(ids(&selected), vec!["a1", "a2", "a3"]);
+    }
+
+    #[test]
+    fn test_diversity_skips_near_duplicates() {
+        let selected = select_diverse(near_duplicates(), 2, 0.5);
+        assert_eq!(ids(&selected), vec!["a1", "b"]);
+        // Relevance scores are kept
+        assert_eq!(selected[1].1, 0.80);
+    }
+
+    #[test]
+    fn test_selects_at_most_available() {
+        assert_eq!(select_diverse(near_duplicates(), 10, 0.3).len(), 4);
+        assert!(select_diverse(Vec::new(), 3, 0.3).is_empty());
+    }
+
+    #[test]
+    fn test_candidate_count() {
+        assert_eq!(candidate_count(5, None).unwrap(), 5);
+        assert_eq!(candidate_count(5, Some(0.3)).unwrap(), 5 * CANDIDATE_FACTOR);
+        assert!(candidate_count(5, Some(1.5)).is_err());
+        assert!(candidate_count(5, Some(f32::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_pool_size() {
+        assert_eq!(pool_size(5, None, None, false).unwrap(), 5);
+        assert_eq!(
+            pool_size(5, None, None, true).unwrap(),
+            5 * CANDIDATE_FACTOR
+        );
+        assert_eq!(pool_size(5, Some(50), Some(0.3), true).unwrap(), 50);
+        assert_eq!(pool_size(8, Some(3), None, false).unwrap(), 8);
+        assert!(pool_size(5, Some(50), Some(1.5), false).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Extractive answers, for machines without an LLM.
+//!
+//! Instead of having a model write an answer from the retrieved chunks, the
+//! sentences of the chunks are scored by the query terms they share
+//! (normalized as the trigram provider does it), discounted by the rank of
+//! their chunk, and the best few are returned in reading order.
+
+use crate::embeddings::providers::language;
+use crate::types::KnowledgeChunk;
+use std::collections::{HashMap, HashSet};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Sentences shorter than this many words (headings, list stubs) are not
+/// picked.
+const MIN_WORDS: usize = 3;
+
+/// A sentence picked for an extractive answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extract {
+    /// Index of the chunk the sentence comes from
+    pub chunk: usize,
+
+    /// The sentence, trimmed and on one line
+    pub sentence: String,
+}
+
+/// The `count` sentences of `chunks` (best first) that best match `query`,
+/// in the order of the chunks and of the sentences within them.
+///
+/// When no sentence shares a term with the query, the first sentences of
+/// the best chunk are returned.
+pub fn select(query: &str, chunks: &[KnowledgeChunk], count: usize) -> Vec<Extract> {
+    let mut query_terms: HashMap<String, f32> = HashMap::new();
+    for (term, weight) in language::terms(query) {
+        let entry = query_terms.entry(term).or_insert(0.0);
+        *entry = entry.max(weight);
+    }
+
+    // (chunk, sentence number, score, sentence)
+    let mut candidates = Vec::new();
+    for (rank, chunk) in chunks.iter().enumerate() {
+        for (position, sentence) in sentences(&chunk.text).into_iter().enumerate() {
+            let terms: HashSet<String> = language::terms(&sentence)
+                .into_iter()
+                .map(|(term, _)| term)
+                .collect();
+            let overlap: f32 = query_terms
+                .iter()
+                .filter(|(term, _)| terms.contains(*term))
+                .map(|(_, weight)| weight)
+                .sum();
+            let score = overlap / (1.0 + 0.1 * rank as f32);
+            candidates.push((rank, position, score, sentence));
+        }
+    }
+
+    let mut picked: Vec<_> = candidates
+        .iter()
+        .filter(|(_, _, score, _)| *score > 0.0)
+        .collect();
+    if picked.is_empty() {
+        picked = candidates.iter().filter(|(rank, ..)| *rank == 0).collect();
+    } else {
+        picked.sort_by(|a, b| b.2.total_cmp(&a.2));
+    }
+    picked.truncate(count);
+    picked.sort_by_key(|(rank, position, ..)| (*rank, *position));
+
+    picked
+        .into_iter()
+        .map(|(rank, _, _, sentence)| Extract {
+            chunk: *rank,
+            sentence: sentence.clone(),
+        })
+        .collect()
+}
+
+/// Sentences of `text` worth quoting: on one line, without heading or list
+/// markers, and at least [`MIN_WORDS`] words long.
+fn sentences(text: &str) -> Vec<String> {
+    text.unicode_sentences()
+        .map(|sentence| {
+            let sentence = sentence
+                .trim()
+                .trim_start_matches('#')
+                .trim_start_matches(['-', '*', '>'])
+                .trim();
+            sentence.split_whitespace().collect::<Vec<_>>().join(" ")
+        })
+        .filter(|sentence| sentence.unicode_words().count() >= MIN_WORDS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(text: &str) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: text.to_string(),
+            source_id: String::new(),
+            position: 0,
+            text: text.to_string(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_picks_matching_sentences_in_reading_order() {
+        let chunks = [
+            chunk(
+                "# Deploys\n\nWe ship every week. Deploys are frozen on Fridays. \
+                 To roll back a deploy, run make rollback.",
+            ),
+            chunk("The on-call engineer answers pages. Nobody deploys on Fridays after five."),
+        ];
+
+        let extracts = select("When are deploys frozen?", &chunks, 2);
+        assert_eq!(
+            extracts,
+            vec![
+                Extract {
+                    chunk: 0,
+                    sentence: "Deploys are frozen on Fridays.".to_string()
+                },
+                Extract {
+                    chunk: 0,
+                    sentence: "To roll back a deploy, run make rollback.".to_string()
+                },
+            ]
+        );
+
+        let extracts = select("friday deploys", &chunks, 3);
+        assert_eq!(extracts.len(), 3);
+        assert_eq!(extracts[2].chunk, 1);
+    }
+
+    #[test]
+    fn test_falls_back_to_the_best_chunk() {
+        let chunks = [chunk("Invoices go out monthly. Payments take three days.")];
+        let extracts = select("kubernetes", &chunks, 1);
+        assert_eq!(extracts[0].sentence, "Invoices go out monthly.");
+        assert!(select("kubernetes", &[], 3).is_empty());
+    }
+}
@@ -0,0 +1,408 @@
+//! Feedback on RAG answers.
+//!
+//! Every `ask` records its question and retrieved chunks as the base's last
+//! answer (`last-answer.json`). `guided knowledge feedback <base> --last` rates
+//! that answer, appending the question, chunk ids, sources, scores and
+//! verdict to `feedback.jsonl` in the base directory. The report built from
+//! those entries names the sources that keep showing up in unhelpful answers
+//! and suggests retrieval settings to try.
+
+use crate::types::KnowledgeChunk;
+use chrono::{DateTime, Utc};
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Unhelpful answers a source must appear in to be reported.
+const MIN_UNHELPFUL_COUNT: u32 = 2;
+
+/// Share of a source's rated answers that must be unhelpful to report it.
+const MIN_UNHELPFUL_SHARE: f32 = 0.75;
+
+/// Unhelpful answers needed before settings are suggested.
+const MIN_RATINGS_FOR_SUGGESTIONS: usize = 3;
+
+/// Question and retrieval of an answer, as recorded for feedback.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerRecord {
+    /// The question asked
+    pub query: String,
+
+    /// Namespace searched, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Ids of the chunks the answer was written from, best first
+    pub chunk_ids: Vec<String>,
+
+    /// Source path of each chunk
+    pub sources: Vec<String>,
+
+    /// Similarity score of each chunk
+    pub scores: Vec<f32>,
+
+    /// When the question was asked
+    pub asked_at: DateTime<Utc>,
+}
+
+impl AnswerRecord {
+    /// Record of an answer to `query` written from the scored `chunks`.
+    pub fn new(query: &str, namespace: Option<String>, chunks: &[(KnowledgeChunk, f32)]) -> Self {
+        Self {
+            query: query.to_string(),
+            namespace,
+            chunk_ids: chunks.iter().map(|(chunk, _)| chunk.id.clone()).collect(),
+            sources: chunks.iter().map(|(chunk, _)| source_path(chunk)).collect(),
+            scores: chunks.iter().map(|(_, score)| *score).collect(),
+            asked_at: Utc::now(),
+        }
+    }
+
+    /// Best retrieval score, 0 when nothing was retrieved.
+    fn top_score(&self) -> f32 {
+        self.scores.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// Whether an answer helped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Helpful,
+    NotHelpful,
+}
+
+/// A rated answer, one line of `feedback.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackEntry {
+    #[serde(flatten)]
+    pub answer: AnswerRecord,
+
+    pub verdict: Verdict,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    pub rated_at: DateTime<Utc>,
+}
+
+/// How often a source appeared in rated answers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFeedback {
+    pub source: String,
+    pub helpful: u32,
+    pub not_helpful: u32,
+}
+
+/// Summary of a base's feedback.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackReport {
+    pub helpful: u32,
+    pub not_helpful: u32,
+
+    /// Sources found mostly in unhelpful answers, worst first
+    pub unhelpful_sources: Vec<SourceFeedback>,
+
+    /// Settings to try, in plain sentences
+    pub suggestions: Vec<String>,
+}
+
+/// Remember `record` as the last answer of a base.
+pub fn record_answer(workspace: &Path, base_name: &str, record: &AnswerRecord) -> AppResult<()> {
+    let path = crate::config::get_last_answer_path(workspace, base_name);
+    let json = serde_json::to_string_pretty(record)
+        .map_err(|e| AppError::Knowledge(format!("Failed to serialize answer record: {}", e)))?;
+    std::fs::write(&path, json)
+        .map_err(|e| AppError::Knowledge(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Rate the last answer of a base.
+pub fn rate_last_answer(
+    workspace: &Path,
+    base_name: &str,
+    verdict: Verdict,
+    note: Option<String>,
+) -> AppResult<FeedbackEntry> {
+    let path = crate::config::get_last_answer_path(workspace, base_name);
+    let json = std::fs::read_to_string(&path).map_err(|_| {
+        AppError::Knowledge(format!(
+            "No answer to rate in knowledge base '{}'. Run 'guided knowledge ask' first.",
+            base_name
+        ))
+    })?;
+    let answer: AnswerRecord = serde_json::from_str(&json)
+        .map_err(|e| AppError::Knowledge(format!("Invalid {}: {}", path.display(), e)))?;
+
+    let entry = FeedbackEntry {
+        answer,
+        verdict,
+        note: note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        rated_at: Utc::now(),
+    };
+    let line = serde_json::to_string(&entry)
+        .map_err(|e| AppError::Knowledge(format!("Failed to serialize feedback: {}", e)))?;
+
+    let feedback_path = crate::config::get_feedback_path(workspace, base_name);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&feedback_path)
+        .map_err(|e| AppError::Knowledge(format!("Failed to open feedback.jsonl: {}", e)))?;
+    writeln!(file, "{}", line)
+        .map_err(|e| AppError::Knowledge(format!("Failed to write feedback.jsonl: {}", e)))?;
+
+    Ok(entry)
+}
+
+/// All rated answers of a base, oldest first. Malformed lines are skipped.
+pub fn list_feedback(workspace: &Path, base_name: &str) -> AppResult<Vec<FeedbackEntry>> {
+    let path = crate::config::get_feedback_path(workspace, base_name);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::Knowledge(format!("Failed to read feedback.jsonl: {}", e)))?;
+
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(n, line)| {
+            serde_json::from_str(line)
+                .inspect_err(|e| tracing::warn!("Skipping feedback.jsonl line {}: {}", n + 1, e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Report on the rated answers of a base.
+pub fn feedback_report(workspace: &Path, base_name: &str) -> AppResult<FeedbackReport> {
+    let config = crate::config::load_config(workspace, base_name)?;
+    let entries = list_feedback(workspace, base_name)?;
+    Ok(build_report(&entries, config.chunk_size))
+}
+
+fn build_report(entries: &[FeedbackEntry], chunk_size: u32) -> FeedbackReport {
+    let (helpful, unhelpful): (Vec<&FeedbackEntry>, Vec<&FeedbackEntry>) = entries
+        .iter()
+        .partition(|entry| entry.verdict == Verdict::Helpful);
+
+    // Count each source once per answer
+    let mut by_source: BTreeMap<&str, SourceFeedback> = BTreeMap::new();
+    for entry in entries {
+        let mut sources: Vec<&str> = entry.answer.sources.iter().map(String::as_str).collect();
+        sources.sort();
+        sources.dedup();
+        for source in sources {
+            let counts = by_source.entry(source).or_insert_with(|| SourceFeedback {
+                source: source.to_string(),
+                helpful: 0,
+                not_helpful: 0,
+            });
+            match entry.verdict {
+                Verdict::Helpful => counts.helpful += 1,
+                Verdict::NotHelpful => counts.not_helpful += 1,
+            }
+        }
+    }
+    let mut unhelpful_sources: Vec<SourceFeedback> = by_source
+        .into_values()
+        .filter(|s| {
+            let share = s.not_helpful as f32 / (s.helpful + s.not_helpful) as f32;
+            s.not_helpful >= MIN_UNHELPFUL_COUNT && share >= MIN_UNHELPFUL_SHARE
+        })
+        .collect();
+    unhelpful_sources.sort_by(|a, b| {
+        b.not_helpful
+            .cmp(&a.not_helpful)
+            .then(a.helpful.cmp(&b.helpful))
+    });
+
+    let mut suggestions = Vec::new();
+    if unhelpful.len() >= MIN_RATINGS_FOR_SUGGESTIONS {
+        suggestions.extend(threshold_suggestion(&helpful, &unhelpful));
+        suggestions.extend(chunk_size_suggestion(&unhelpful, chunk_size));
+        let empty = unhelpful
+            .iter()
+            .filter(|e| e.answer.chunk_ids.is_empty())
+            .count();
+        if empty * 2 >= unhelpful.len() {
+            suggestions.push(format!(
+                "{} of {} unhelpful answers found nothing relevant: the base may be missing \
+                 those topics; learn the documents that cover them.",
+                empty,
+                unhelpful.len()
+            ));
+        }
+    }
+
+    FeedbackReport {
+        helpful: helpful.len() as u32,
+        not_helpful: unhelpful.len() as u32,
+        unhelpful_sources,
+        suggestions,
+    }
+}
+
+/// Suggest a relevance threshold between the top scores of unhelpful and
+/// helpful answers, when unhelpful answers matched clearly worse.
+fn threshold_suggestion(
+    helpful: &[&FeedbackEntry],
+    unhelpful: &[&FeedbackEntry],
+) -> Option<String> {
+    let top_scores = |entries: &[&FeedbackEntry]| -> Vec<f32> {
+        entries
+            .iter()
+            .filter(|e| !e.answer.scores.is_empty())
+            .map(|e| e.answer.top_score())
+            .collect()
+    };
+    let helpful_median = median(top_scores(helpful))?;
+    let unhelpful_median = median(top_scores(unhelpful))?;
+    if unhelpful_median + 0.05 > helpful_median {
+        return None;
+    }
+    Some(format!(
+        "Unhelpful answers matched weakly (median top score {:.2}, against {:.2} for helpful \
+         ones); a relevance threshold near {:.2} would drop most of them.",
+        unhelpful_median,
+        helpful_median,
+        (unhelpful_median + helpful_median) / 2.0
+    ))
+}
+
+/// Suggest larger chunks when unhelpful answers are stitched together from
+/// several pieces of the same files.
+fn chunk_size_suggestion(unhelpful: &[&FeedbackEntry], chunk_size: u32) -> Option<String> {
+    let (chunks, sources) = unhelpful
+        .iter()
+        .filter(|e| !e.answer.sources.is_empty())
+        .fold((0usize, 0usize), |(chunks, sources), e| {
+            let mut distinct = e.answer.sources.clone();
+            distinct.sort();
+            distinct.dedup();
+            (chunks + e.answer.sources.len(), sources + distinct.len())
+        });
+    if sources == 0 || (chunks as f32 / sources as f32) < 2.0 {
+        return None;
+    }
+    Some(format!(
+        "Unhelpful answers drew {:.1} chunks per file on average, so passages are split \
+         apart; try `chunk_size: {}` (now {}) in the base's config.yaml and re-learn with \
+         --reset.",
+        chunks as f32 / sources as f32,
+        chunk_size * 2,
+        chunk_size
+    ))
+}
+
+fn median(mut values: Vec<f32>) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Path of a chunk's source file, or its source id.
+fn source_path(chunk: &KnowledgeChunk) -> String {
+    chunk.metadata["custom"]["source_path"]
+        .as_str()
+        .unwrap_or(&chunk.source_id)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(verdict: Verdict, sources: &[&str], scores: &[f32]) -> FeedbackEntry {
+        FeedbackEntry {
+            answer: AnswerRecord {
+                query: "q".to_string(),
+                namespace: None,
+                chunk_ids: (0..sources.len()).map(|i| format!("c{}", i)).collect(),
+                sources: sources.iter().map(|s| s.to_string()).collect(),
+                scores: scores.to_vec(),
+                asked_at: Utc::now(),
+            },
+            verdict,
+            note: None,
+            rated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_report_names_unhelpful_sources() {
+        let entries = vec![
+            entry(Verdict::NotHelpful, &["old.md", "old.md"], &[0.2, 0.1]),
+            entry(Verdict::NotHelpful, &["old.md", "faq.md"], &[0.2, 0.1]),
+            entry(Verdict::NotHelpful, &["old.md"], &[0.15]),
+            entry(Verdict::Helpful, &["faq.md"], &[0.6]),
+            entry(Verdict::Helpful, &["guide.md"], &[0.4]),
+        ];
+
+        let report = build_report(&entries, 512);
+        assert_eq!((report.helpful, report.not_helpful), (2, 3));
+        assert_eq!(
+            report.unhelpful_sources,
+            vec![SourceFeedback {
+                source: "old.md".to_string(),
+                helpful: 0,
+                not_helpful: 3,
+            }]
+        );
+        assert_eq!(report.suggestions.len(), 1);
+        assert!(
+            report.suggestions[0].contains("threshold near 0.35"),
+            "{:?}",
+            report.suggestions
+        );
+    }
+
+    #[test]
+    fn test_report_suggests_larger_chunks() {
+        let entries: Vec<FeedbackEntry> = (0..3)
+            .map(|_| {
+                entry(
+                    Verdict::NotHelpful,
+                    &["a.md", "a.md", "a.md"],
+                    &[0.5, 0.4, 0.3],
+                )
+            })
+            .collect();
+
+        let report = build_report(&entries, 400);
+        assert!(
+            report
+                .suggestions
+                .iter()
+                .any(|s| s.contains("chunk_size: 800")),
+            "{:?}",
+            report.suggestions
+        );
+        assert!(build_report(&entries[..2], 400).suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_entries_roundtrip_as_json_lines() {
+        let mut rated = entry(Verdict::NotHelpful, &["a.md"], &[0.4]);
+        rated.note = Some("outdated".to_string());
+        let line = serde_json::to_string(&rated).unwrap();
+        assert!(line.contains(r#""verdict":"not-helpful""#), "{}", line);
+        assert!(line.contains(r#""chunkIds":["c0"]"#), "{}", line);
+        assert_eq!(serde_json::from_str::<FeedbackEntry>(&line).unwrap(), rated);
+    }
+}
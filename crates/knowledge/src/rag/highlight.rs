@@ -0,0 +1,112 @@
+//! Marking the query's terms in retrieved text.
+//!
+//! Retrieval is by embedding similarity, so a snippet does not have to share
+//! a word with the question. To show why a chunk was picked anyway, the
+//! words of a snippet are matched after the fact against the query's terms,
+//! normalized as the trigram provider does it: lowercased, accents folded,
+//! stopwords dropped and plurals stemmed, so `deployments` marks `deploy`.
+
+use crate::embeddings::providers::language::{self, Language};
+use std::collections::HashSet;
+
+/// Byte ranges of the words in `text` that match a term of `query`, in
+/// order.
+pub fn match_ranges(query: &str, text: &str) -> Vec<(usize, usize)> {
+    let terms: HashSet<String> = language::terms(query)
+        .into_iter()
+        .map(|(term, _)| term)
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                if word_matches(&text[s..i], &terms) {
+                    ranges.push((s, i));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// Whether a word of the text, in any supported language, has a stem
+/// among `terms`.
+fn word_matches(word: &str, terms: &HashSet<String>) -> bool {
+    language::words(word).iter().any(|folded| {
+        terms.contains(folded)
+            || Language::ALL
+                .iter()
+                .any(|&lang| terms.contains(&language::stem(folded, lang)))
+    })
+}
+
+/// Wrap each of `ranges` of `text` in `open` and `close` (ANSI codes,
+/// `**` for markdown, ...).
+pub fn mark(text: &str, ranges: &[(usize, usize)], open: &str, close: &str) -> String {
+    let mut marked = String::with_capacity(text.len() + ranges.len() * (open.len() + close.len()));
+    let mut last = 0;
+    for &(start, end) in ranges {
+        if start < last || end > text.len() {
+            continue;
+        }
+        marked.push_str(&text[last..start]);
+        marked.push_str(open);
+        marked.push_str(&text[start..end]);
+        marked.push_str(close);
+        last = end;
+    }
+    marked.push_str(&text[last..]);
+    marked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marked(query: &str, text: &str) -> String {
+        mark(text, &match_ranges(query, text), "[", "]")
+    }
+
+    #[test]
+    fn test_marks_query_terms_and_their_inflections() {
+        assert_eq!(
+            marked(
+                "How are deployments rolled back?",
+                "Each deployment can be rolled back."
+            ),
+            "Each [deployment] can be [rolled] [back]."
+        );
+    }
+
+    #[test]
+    fn test_ignores_stopwords_and_folds_accents() {
+        assert_eq!(
+            marked(
+                "the configuração of the app",
+                "The configuracao is in the app."
+            ),
+            "The [configuracao] is in the [app]."
+        );
+        assert!(match_ranges("the of and", "the end of it").is_empty());
+    }
+
+    #[test]
+    fn test_ranges_are_byte_offsets() {
+        let text = "Ação: rotate the signing key";
+        let ranges = match_ranges("signing keys", text);
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(&text[ranges[0].0..ranges[0].1], "signing");
+        assert_eq!(&text[ranges[1].0..ranges[1].1], "key");
+    }
+}
@@ -0,0 +1,108 @@
+//! File and line anchors for retrieved chunks.
+//!
+//! Editor extensions showing "semantic search in workspace" results need
+//! where each match is, not an answer: [`locate`] turns the chunks of an
+//! [`AskResult`](crate::AskResult) into file paths and line ranges they can
+//! jump to.
+
+use crate::chunk::ChunkMetadata;
+use crate::rag::ask::{absolute_path, truncate_snippet, MAX_SNIPPET_LENGTH};
+use crate::types::KnowledgeChunk;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A retrieved chunk's place in a workspace file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    /// Absolute path of the file
+    pub path: String,
+
+    /// First line of the chunk (1-based)
+    pub start_line: usize,
+
+    /// Last line of the chunk (inclusive)
+    pub end_line: usize,
+
+    /// Relevance score of the chunk
+    pub score: f32,
+
+    /// Start of the chunk's text
+    pub snippet: String,
+}
+
+/// Locations of `chunks` (with their `scores`), best first. Chunks that do
+/// not come from a file of the workspace, such as web pages and inline
+/// text, or that have no line range are left out.
+pub fn locate(workspace: &Path, chunks: &[KnowledgeChunk], scores: &[f32]) -> Vec<Location> {
+    chunks
+        .iter()
+        .zip(scores)
+        .filter_map(|(chunk, &score)| {
+            let metadata = serde_json::from_value::<ChunkMetadata>(chunk.metadata.clone()).ok()?;
+            let (start_line, end_line) = metadata.line_range?;
+            let source_path = metadata.custom.get("source_path")?.as_str()?;
+            let path = absolute_path(workspace, source_path);
+            if !Path::new(&path).is_file() {
+                return None;
+            }
+            Some(Location {
+                path,
+                start_line,
+                end_line,
+                score,
+                snippet: truncate_snippet(chunk.text.trim(), MAX_SNIPPET_LENGTH),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chunk(source_path: &str, line_range: Option<(usize, usize)>, text: &str) -> KnowledgeChunk {
+        let metadata = ChunkMetadata {
+            content_type: crate::chunk::ContentType::Text,
+            language: None,
+            byte_range: (0, text.len()),
+            line_range,
+            char_count: text.len(),
+            token_count: None,
+            hash: "test".to_string(),
+            created_at: chrono::Utc::now(),
+            splitter_used: "test".to_string(),
+            custom: serde_json::json!({ "source_path": source_path }),
+        };
+        KnowledgeChunk {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_id: "source".to_string(),
+            position: 0,
+            text: text.to_string(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::to_value(&metadata).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_locate_files_with_lines() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("src")).unwrap();
+        std::fs::write(temp.path().join("src/lib.rs"), "fn parse() {}\n").unwrap();
+
+        let chunks = vec![
+            chunk("src/lib.rs", Some((12, 20)), "\n  fn parse() {}"),
+            chunk("https://example.com/guide", Some((1, 3)), "A web page"),
+            chunk("src/lib.rs", None, "No lines"),
+        ];
+        let locations = locate(temp.path(), &chunks, &[0.9, 0.8, 0.7]);
+
+        assert_eq!(locations.len(), 1);
+        assert!(locations[0].path.ends_with("lib.rs"));
+        assert!(Path::new(&locations[0].path).is_absolute());
+        assert_eq!((locations[0].start_line, locations[0].end_line), (12, 20));
+        assert_eq!(locations[0].score, 0.9);
+        assert_eq!(locations[0].snippet, "fn parse() {}");
+    }
+}
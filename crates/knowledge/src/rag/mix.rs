@@ -0,0 +1,167 @@
+//! Mixed retrieval of code and docs.
+//!
+//! Code and prose land in different regions of most embedding spaces, so in
+//! a base holding both, one kind often crowds the other out of the top k
+//! whatever the question. A [`Mix`] such as `code:3,docs:2` retrieves each
+//! kind with its own query, restricted to that kind, and interleaves the
+//! results.
+
+use crate::types::KnowledgeChunk;
+use serde::{Deserialize, Serialize};
+
+/// Kind of content a search is restricted to, by the `file_type` of chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    /// Source code files
+    Code,
+    /// Everything else: markdown, text, web pages, PDFs, transcripts...
+    Docs,
+}
+
+impl ContentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Code => "code",
+            ContentKind::Docs => "docs",
+        }
+    }
+
+    /// LanceDB filter on the `file_type` column for this kind.
+    pub fn predicate(&self) -> &'static str {
+        match self {
+            ContentKind::Code => "file_type = 'code'",
+            ContentKind::Docs => "(file_type IS NULL OR file_type <> 'code')",
+        }
+    }
+
+    /// Whether a chunk is of this kind.
+    pub fn matches(&self, chunk: &KnowledgeChunk) -> bool {
+        let is_code = chunk
+            .metadata_values("file_type")
+            .find_map(|v| v.as_str())
+            .is_some_and(|file_type| file_type == "code");
+        is_code == (*self == ContentKind::Code)
+    }
+}
+
+impl std::fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ContentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "code" => Ok(ContentKind::Code),
+            "docs" => Ok(ContentKind::Docs),
+            other => Err(format!(
+                "Unknown content kind '{}' (expected 'code' or 'docs')",
+                other
+            )),
+        }
+    }
+}
+
+/// Chunks to retrieve of each kind, in the order results are interleaved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mix {
+    parts: Vec<(ContentKind, usize)>,
+}
+
+impl Mix {
+    /// Each kind with its number of chunks.
+    pub fn parts(&self) -> &[(ContentKind, usize)] {
+        &self.parts
+    }
+
+    /// Chunks retrieved in all.
+    pub fn total(&self) -> usize {
+        self.parts.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl std::fmt::Display for Mix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .parts
+            .iter()
+            .map(|(kind, count)| format!("{}:{}", kind, count))
+            .collect();
+        f.write_str(&parts.join(","))
+    }
+}
+
+impl std::str::FromStr for Mix {
+    type Err = String;
+
+    /// Parse `KIND:COUNT,...`, e.g. `code:3,docs:2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid mix '{}' (expected KIND:COUNT,..., e.g. code:3,docs:2)",
+                s
+            )
+        };
+        let mut parts: Vec<(ContentKind, usize)> = Vec::new();
+        for part in s.split(',') {
+            let (kind, count) = part.split_once(':').ok_or_else(invalid)?;
+            let kind: ContentKind = kind.parse()?;
+            let count: usize = count.trim().parse().map_err(|_| invalid())?;
+            if count == 0 {
+                return Err(format!("Invalid mix '{}': counts must be above 0", s));
+            }
+            if parts.iter().any(|(k, _)| *k == kind) {
+                return Err(format!("Invalid mix '{}': {} is given twice", s, kind));
+            }
+            parts.push((kind, count));
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// Take results from each list in turn, best first, until all are used up.
+pub fn interleave<T>(lists: Vec<Vec<T>>) -> Vec<T> {
+    let total = lists.iter().map(Vec::len).sum();
+    let mut iters: Vec<_> = lists.into_iter().map(Vec::into_iter).collect();
+    let mut interleaved = Vec::with_capacity(total);
+    while interleaved.len() < total {
+        for iter in &mut iters {
+            interleaved.extend(iter.next());
+        }
+    }
+    interleaved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mix() {
+        let mix: Mix = "code:3, docs:2".parse().unwrap();
+        assert_eq!(
+            mix.parts(),
+            &[(ContentKind::Code, 3), (ContentKind::Docs, 2)]
+        );
+        assert_eq!(mix.total(), 5);
+        assert_eq!(mix.to_string(), "code:3,docs:2");
+
+        assert!("code".parse::<Mix>().is_err());
+        assert!("code:0".parse::<Mix>().is_err());
+        assert!("code:1,code:2".parse::<Mix>().is_err());
+        assert!("tests:2".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn test_interleave() {
+        assert_eq!(
+            interleave(vec![vec!["c1", "c2", "c3"], vec!["d1"]]),
+            vec!["c1", "d1", "c2", "c3"]
+        );
+        assert!(interleave::<u8>(vec![vec![], vec![]]).is_empty());
+    }
+}
@@ -3,10 +3,26 @@
 //! Provides natural language answering over knowledge bases using LLM synthesis.
 
 pub mod ask;
+pub mod authority;
+pub mod cache;
+pub mod context;
+pub mod diversity;
+pub mod extractive;
+pub mod feedback;
+pub mod highlight;
+pub mod locate;
+pub mod mix;
+pub mod recency;
+pub mod retrieval;
 pub mod search;
 pub mod sources;
 pub mod types;
+pub mod verify;
 
-pub use search::{detect_query_filters, SearchFilters};
+pub use cache::AnswerCache;
+pub use locate::Location;
+pub use mix::{ContentKind, Mix};
+pub use search::{detect_query_filters, Boost, SearchFilters};
 pub use sources::SourceManager;
-pub use types::{RagResponse, RagSourceRef};
+pub use types::{AnswerEvent, AnswerObserver, RagBestOf, RagResponse, RagSourceRef};
+pub use verify::{ClaimCheck, Verification};
@@ -0,0 +1,95 @@
+//! Recency boost of retrieval results.
+//!
+//! Questions about current behavior should find the document describing it,
+//! not a stale design doc with similar wording. With `recency` set in a
+//! base's config, each chunk's similarity is multiplied by
+//! `(1 - weight) + weight * 0.5^(age / half_life)`, its age counted from
+//! the `file_modified_at` of its source, and results are ranked again.
+
+use crate::types::{KnowledgeChunk, RecencyConfig};
+use chrono::{DateTime, Utc};
+
+/// Boost `results` by the age of their sources at `now`, best first.
+///
+/// Chunks without a modification time count as one half-life old.
+pub fn boost(
+    mut results: Vec<(KnowledgeChunk, f32)>,
+    config: &RecencyConfig,
+    now: DateTime<Utc>,
+) -> Vec<(KnowledgeChunk, f32)> {
+    for (chunk, score) in results.iter_mut() {
+        let modified_at = chunk
+            .metadata_values("file_modified_at")
+            .find_map(|v| v.as_i64());
+        *score *= factor(config, modified_at, now);
+    }
+    results.sort_by(|a, b| b.1.total_cmp(&a.1));
+    results
+}
+
+/// Score multiplier of a source modified at `modified_at` (Unix seconds).
+fn factor(config: &RecencyConfig, modified_at: Option<i64>, now: DateTime<Utc>) -> f32 {
+    let weight = config.weight.clamp(0.0, 1.0);
+    let decay = match modified_at {
+        Some(modified_at) => {
+            let age_days = (now.timestamp() - modified_at).max(0) as f32 / 86_400.0;
+            0.5f32.powf(age_days / config.half_life_days.max(f32::EPSILON))
+        }
+        None => 0.5,
+    };
+    (1.0 - weight) + weight * decay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &str, modified_at: Option<i64>) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: id.to_string(),
+            source_id: id.to_string(),
+            position: 0,
+            text: String::new(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::json!({"custom": {"file_modified_at": modified_at}}),
+        }
+    }
+
+    #[test]
+    fn test_factor_halves_the_boost_every_half_life() {
+        let config = RecencyConfig {
+            half_life_days: 30.0,
+            weight: 0.4,
+        };
+        let now = Utc::now();
+        let days_ago = |days: i64| Some(now.timestamp() - days * 86_400);
+
+        assert!((factor(&config, days_ago(0), now) - 1.0).abs() < 1e-6);
+        assert!((factor(&config, days_ago(30), now) - 0.8).abs() < 1e-6);
+        assert!((factor(&config, days_ago(60), now) - 0.7).abs() < 1e-6);
+        assert!((factor(&config, None, now) - 0.8).abs() < 1e-6);
+        // Clocks disagreeing by a little do not boost above 1
+        assert!((factor(&config, days_ago(-1), now) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_boost_ranks_newer_sources_first() {
+        let now = Utc::now();
+        let year_ago = now.timestamp() - 365 * 86_400;
+        let results = vec![
+            (chunk("stale", Some(year_ago)), 0.82),
+            (chunk("current", Some(now.timestamp())), 0.78),
+        ];
+
+        let ranked = boost(results.clone(), &RecencyConfig::default(), now);
+        assert_eq!(ranked[0].0.id, "current");
+        assert_eq!(ranked[0].1, 0.78);
+
+        let off = RecencyConfig {
+            weight: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(boost(results, &off, now)[0].0.id, "stale");
+    }
+}
@@ -0,0 +1,214 @@
+//! Retrieval of the chunks a query is answered from.
+//!
+//! Shared by [`crate::ask`] (and [`crate::KnowledgeBase::ask`]), which return
+//! the chunks, and [`crate::rag::ask::ask_rag`], which answers from them, so both
+//! see the same chunks for the same query.
+
+use crate::embeddings::EmbeddingEngine;
+use crate::lancedb_index::LanceDbIndex;
+use crate::rag::authority::Authority;
+use crate::rag::diversity;
+use crate::rag::mix;
+use crate::rag::recency;
+use crate::rag::search::{detect_query_filters, SearchFilters};
+use crate::symbol_index::SymbolIndex;
+use crate::types::{AskOptions, KnowledgeBaseConfig, KnowledgeChunk};
+use crate::vector_index::VectorIndex;
+use crate::{config, guardrails, summaries};
+use guided_core::{AppError, AppResult};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Chunks retrieved for a query.
+#[derive(Debug, Default)]
+pub(crate) struct Retrieval {
+    /// Chunks with their scores, in rank order, past the guardrails
+    pub chunks: Vec<(KnowledgeChunk, f32)>,
+
+    /// IDs of chunks that looked like prompt injection
+    pub flagged: Vec<String>,
+
+    /// Summaries of the sources a hierarchical query picked
+    pub overview: Vec<KnowledgeChunk>,
+}
+
+/// Embed `options.query` and retrieve its chunks from `index`, already
+/// scoped to the namespace and filters of `options`: the top-k (or each
+/// kind's chunks of a mix, interleaved), the pinned sources' and the
+/// definitions of the symbols the query names, past the guardrails.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn retrieve(
+    workspace: &Path,
+    base_name: &str,
+    config: &KnowledgeBaseConfig,
+    index: LanceDbIndex,
+    engine: &EmbeddingEngine,
+    options: &AskOptions,
+    namespace: Option<&str>,
+    api_key: Option<&str>,
+) -> AppResult<Retrieval> {
+    let query_embeddings = options
+        .cancel
+        .run(
+            "knowledge query",
+            engine.embed_texts(base_name, std::slice::from_ref(&options.query), api_key),
+        )
+        .await?;
+    let query_embedding = query_embeddings
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::Knowledge("Failed to generate query embedding".to_string()))?;
+
+    // Hierarchical: only search the sources whose summaries match, and keep
+    // those summaries as an overview
+    let (index, overview) = if options.hierarchical {
+        match summaries::select_sources(
+            &config::get_index_path(workspace, base_name),
+            base_name,
+            config,
+            namespace.map(str::to_string),
+            &query_embedding,
+        )
+        .await?
+        {
+            Some((source_ids, summaries)) => (index.with_sources(Some(source_ids)), summaries),
+            None => (index, Vec::new()),
+        }
+    } else {
+        (index, Vec::new())
+    };
+
+    // Retrieve the top-k chunks or, with a mix, each kind's chunks from its
+    // own search, interleaved
+    let authority = Authority::load(workspace, base_name, &options.filters)?;
+    let depth = config
+        .retrieval
+        .with_overrides(options.top_k, options.candidates);
+    let candidates = depth.candidates.map(|n| n as usize);
+    let (index, top_k, results) = match &options.mix {
+        None => {
+            let top_k = depth.top_k as usize;
+            let results = search(
+                &index,
+                &query_embedding,
+                top_k,
+                candidates,
+                &options.filters,
+                options,
+                config,
+                &authority,
+            )?;
+            (index, top_k, results)
+        }
+        Some(mix) => {
+            let mut index = index;
+            let mut parts = Vec::new();
+            for &(kind, count) in mix.parts() {
+                let filters = options.filters.clone().with_content(kind);
+                index = index.with_filters(&filters);
+                parts.push(search(
+                    &index,
+                    &query_embedding,
+                    count,
+                    candidates,
+                    &filters,
+                    options,
+                    config,
+                    &authority,
+                )?);
+            }
+            let index = index.with_filters(&options.filters);
+            (index, mix.total(), mix::interleave(parts))
+        }
+    };
+    let results = authority
+        .include_pinned(&index, &query_embedding, &options.filters, top_k, results)
+        .await?;
+
+    // Definitions of the symbols the query names, whatever their similarity
+    let results = SymbolIndex::load(workspace, base_name)?
+        .include_definitions(
+            &index,
+            &options.query,
+            &query_embedding,
+            namespace,
+            &options.filters,
+            results,
+        )
+        .await?;
+
+    // Filter prompt injection and cap the text put into prompts
+    let guarded = guardrails::guard(results, &config.guardrails);
+    Ok(Retrieval {
+        chunks: guarded.chunks,
+        flagged: guarded.flagged,
+        overview,
+    })
+}
+
+/// The `top_k` chunks of `index` most relevant to the query. A wider pool
+/// to diversify, filter or boost from (by field, recency or source weight)
+/// is scored without loading its text; only the chunks over the relevance
+/// cutoff are loaded.
+#[allow(clippy::too_many_arguments)]
+fn search(
+    index: &LanceDbIndex,
+    query_embedding: &[f32],
+    top_k: usize,
+    candidates: Option<usize>,
+    filters: &SearchFilters,
+    options: &AskOptions,
+    config: &KnowledgeBaseConfig,
+    authority: &Authority,
+) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
+    let widens = filters.widens_search() || config.recency.is_some() || authority.widens_search();
+    let candidates = diversity::pool_size(top_k, candidates, options.diversity, widens)?;
+    let results = index.search_ids(query_embedding, candidates)?;
+
+    tracing::debug!("Retrieved {} chunks before filtering", results.len());
+
+    // Detect query intent and apply automatic filters
+    let auto_filters = detect_query_filters(&options.query);
+
+    // Apply relevance cutoff, then load only the chunks that pass it
+    let relevant: Vec<_> = results
+        .into_iter()
+        .filter(|(_id, score)| *score >= config.min_relevance())
+        .collect();
+    let ids: Vec<String> = relevant.iter().map(|(id, _score)| id.clone()).collect();
+    let scores: HashMap<String, f32> = relevant.into_iter().collect();
+    let mut results: Vec<_> = index
+        .fetch_chunks(&ids)?
+        .into_iter()
+        .map(|chunk| {
+            let score = scores[&chunk.id];
+            (chunk, score)
+        })
+        .collect();
+
+    // Apply the caller's filters, then automatic metadata filters if
+    // detected (unless a mix already picked the kind of content)
+    if filters.has_filters() {
+        results = filters.apply(results);
+    }
+    if auto_filters.has_filters() && filters.content.is_none() {
+        tracing::debug!(
+            "Applying automatic filters: file_types={:?}, languages={:?}",
+            auto_filters.file_types,
+            auto_filters.languages
+        );
+        results = auto_filters.apply(results);
+    }
+
+    if let Some(recency) = &config.recency {
+        results = recency::boost(results, recency, chrono::Utc::now());
+    }
+    results = authority.weigh(results);
+
+    if let Some(diversity) = options.diversity {
+        results = diversity::select_diverse(results, top_k, diversity);
+    } else {
+        results.truncate(top_k);
+    }
+    Ok(results)
+}
@@ -3,9 +3,10 @@
 //! Provides metadata-based filtering for vector similarity search to improve
 //! retrieval quality and relevance.
 
+use crate::rag::mix::ContentKind;
 use crate::types::KnowledgeChunk;
 use chrono::{DateTime, Utc};
-use guided_core::AppResult;
+use guided_core::{AppError, AppResult};
 use serde::{Deserialize, Serialize};
 
 /// Options for filtered vector search
@@ -26,6 +27,29 @@ pub struct SearchFilters {
     /// Only include documents modified after this timestamp
     pub modified_after: Option<DateTime<Utc>>,
 
+    /// Only include documents whose source path starts with this (e.g.,
+    /// "crates/knowledge/"), as learned, ignoring a leading "./"
+    pub path_prefix: Option<String>,
+
+    /// Only include documents whose source path matches this glob (e.g.,
+    /// "crates/knowledge/**/*.rs"); `*` stays within a directory, `**`
+    /// spans directories
+    pub path_glob: Option<String>,
+
+    /// Only include code, or only everything but code
+    pub content: Option<ContentKind>,
+
+    /// Exclude documents with any of these tags
+    pub exclude_tags: Option<Vec<String>>,
+
+    /// Exclude documents whose source path starts with or matches the glob
+    /// of any of these (e.g., ["docs/drafts/", "**/*.test.ts"])
+    pub exclude_paths: Option<Vec<String>>,
+
+    /// Multiply the scores of matching documents, then rank again
+    #[serde(default)]
+    pub boosts: Vec<Boost>,
+
     /// Minimum relevance score (0.0 to 1.0)
     pub min_score: Option<f32>,
 
@@ -69,6 +93,42 @@ impl SearchFilters {
         self
     }
 
+    /// Filter by source path prefix
+    pub fn with_path_prefix(mut self, path_prefix: String) -> Self {
+        self.path_prefix = Some(path_prefix);
+        self
+    }
+
+    /// Filter by source path glob
+    pub fn with_path_glob(mut self, path_glob: String) -> Self {
+        self.path_glob = Some(path_glob);
+        self
+    }
+
+    /// Filter by content kind
+    pub fn with_content(mut self, content: ContentKind) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    /// Exclude tags
+    pub fn with_exclude_tags(mut self, exclude_tags: Vec<String>) -> Self {
+        self.exclude_tags = Some(exclude_tags);
+        self
+    }
+
+    /// Exclude source paths (prefixes or globs)
+    pub fn with_exclude_paths(mut self, exclude_paths: Vec<String>) -> Self {
+        self.exclude_paths = Some(exclude_paths);
+        self
+    }
+
+    /// Boost scores by metadata field
+    pub fn with_boosts(mut self, boosts: Vec<Boost>) -> Self {
+        self.boosts = boosts;
+        self
+    }
+
     /// Set minimum relevance score
     pub fn with_min_score(mut self, min_score: f32) -> Self {
         self.min_score = Some(min_score);
@@ -88,9 +148,118 @@ impl SearchFilters {
             || self.tags.is_some()
             || self.created_after.is_some()
             || self.modified_after.is_some()
+            || self.path_prefix.is_some()
+            || self.path_glob.is_some()
+            || self.content.is_some()
+            || self.exclude_tags.is_some()
+            || self.exclude_paths.is_some()
+            || !self.boosts.is_empty()
             || self.min_score.is_some()
     }
 
+    /// Whether the filters drop or reorder chunks after scoring, so a
+    /// wider pool of candidates should be retrieved for them to pick from.
+    pub fn widens_search(&self) -> bool {
+        self.tags.is_some()
+            || self.exclude_tags.is_some()
+            || self.exclude_paths.is_some()
+            || !self.boosts.is_empty()
+    }
+
+    /// Check that the path glob is valid.
+    pub fn validate(&self) -> AppResult<()> {
+        for glob in self
+            .path_glob
+            .iter()
+            .chain(self.exclude_paths.iter().flatten())
+        {
+            glob::Pattern::new(glob)
+                .map_err(|e| AppError::Knowledge(format!("Invalid path glob '{}': {}", glob, e)))?;
+        }
+        Ok(())
+    }
+
+    /// LanceDB filter on the `source_path` column for the path filters, so
+    /// searches only score matching chunks. Globs are pushed down by the
+    /// literal part before their first wildcard, and excluded paths only
+    /// when they have no wildcard; [`SearchFilters::apply`] matches them
+    /// exactly.
+    pub fn path_predicate(&self) -> Option<String> {
+        let glob_prefix = self
+            .path_glob
+            .as_deref()
+            .map(|glob| &glob[..glob.find(['*', '?', '[']).unwrap_or(glob.len())]);
+        let starts_with = |prefix: &str| {
+            let prefix = prefix.replace('\'', "''");
+            format!(
+                "(starts_with(source_path, '{0}') OR starts_with(source_path, './{0}'))",
+                prefix
+            )
+        };
+        let mut conditions: Vec<String> = [self.path_prefix.as_deref(), glob_prefix]
+            .into_iter()
+            .flatten()
+            .map(relative)
+            .filter(|prefix| !prefix.is_empty())
+            .map(starts_with)
+            .collect();
+        // Chunks without a source path are never excluded
+        conditions.extend(
+            self.exclude_paths
+                .iter()
+                .flatten()
+                .map(|path| relative(path))
+                .filter(|path| !path.is_empty() && !path.contains(['*', '?', '[']))
+                .map(|path| format!("(source_path IS NULL OR NOT {})", starts_with(path))),
+        );
+        (!conditions.is_empty()).then(|| conditions.join(" AND "))
+    }
+
+    /// LanceDB filter on the columns the filters can be checked against
+    /// before scoring: the source path (see [`SearchFilters::path_predicate`])
+    /// and the content kind.
+    pub fn column_predicate(&self) -> Option<String> {
+        let conditions: Vec<String> = [
+            self.path_predicate(),
+            self.content.map(|content| content.predicate().to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!conditions.is_empty()).then(|| conditions.join(" AND "))
+    }
+
+    /// Whether a source path passes the path filters.
+    pub fn matches_path(&self, path: &str) -> bool {
+        let path = crate::paths::normalize_separators(path);
+        let path = relative(&path);
+        if let Some(prefix) = &self.path_prefix {
+            if !path.starts_with(relative(prefix)) {
+                return false;
+            }
+        }
+        if let Some(glob) = &self.path_glob {
+            let matched = glob::Pattern::new(relative(glob))
+                .is_ok_and(|pattern| pattern.matches_with(path, PATH_MATCH));
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether a source path is under or matches one of the excluded paths.
+    pub fn excludes_path(&self, path: &str) -> bool {
+        let path = crate::paths::normalize_separators(path);
+        let path = relative(&path);
+        self.exclude_paths.iter().flatten().any(|excluded| {
+            let excluded = relative(excluded);
+            path.starts_with(excluded)
+                || glob::Pattern::new(excluded)
+                    .is_ok_and(|pattern| pattern.matches_with(path, PATH_MATCH))
+        })
+    }
+
     /// Apply filters to a list of chunks with scores
     pub fn apply(&self, chunks: Vec<(KnowledgeChunk, f32)>) -> Vec<(KnowledgeChunk, f32)> {
         let mut filtered = chunks;
@@ -104,9 +273,8 @@ impl SearchFilters {
         if let Some(file_types) = &self.file_types {
             filtered.retain(|(chunk, _)| {
                 chunk
-                    .metadata
-                    .get("file_type")
-                    .and_then(|v| v.as_str())
+                    .metadata_values("file_type")
+                    .find_map(|v| v.as_str())
                     .map(|ft| file_types.iter().any(|t| ft.contains(t)))
                     .unwrap_or(false)
             });
@@ -116,9 +284,8 @@ impl SearchFilters {
         if let Some(languages) = &self.languages {
             filtered.retain(|(chunk, _)| {
                 chunk
-                    .metadata
-                    .get("language")
-                    .and_then(|v| v.as_str())
+                    .metadata_values("language")
+                    .find_map(|v| v.as_str())
                     .map(|lang| languages.iter().any(|l| lang.eq_ignore_ascii_case(l)))
                     .unwrap_or(false)
             });
@@ -128,9 +295,8 @@ impl SearchFilters {
         if let Some(tags) = &self.tags {
             filtered.retain(|(chunk, _)| {
                 chunk
-                    .metadata
-                    .get("tags")
-                    .and_then(|v| v.as_array())
+                    .metadata_values("tags")
+                    .find_map(|v| v.as_array())
                     .map(|chunk_tags| {
                         chunk_tags.iter().any(|ct| {
                             ct.as_str()
@@ -147,9 +313,8 @@ impl SearchFilters {
             let created_after_ts = created_after.timestamp();
             filtered.retain(|(chunk, _)| {
                 chunk
-                    .metadata
-                    .get("created_at")
-                    .and_then(|v| v.as_i64())
+                    .metadata_values("created_at")
+                    .find_map(|v| v.as_i64())
                     .map(|ts| ts >= created_after_ts)
                     .unwrap_or(false)
             });
@@ -160,14 +325,63 @@ impl SearchFilters {
             let modified_after_ts = modified_after.timestamp();
             filtered.retain(|(chunk, _)| {
                 chunk
-                    .metadata
-                    .get("file_modified_at")
-                    .and_then(|v| v.as_i64())
+                    .metadata_values("file_modified_at")
+                    .find_map(|v| v.as_i64())
                     .map(|ts| ts >= modified_after_ts)
                     .unwrap_or(false)
             });
         }
 
+        // Filter by content kind
+        if let Some(content) = self.content {
+            filtered.retain(|(chunk, _)| content.matches(chunk));
+        }
+
+        // Filter by source path
+        if self.path_prefix.is_some() || self.path_glob.is_some() {
+            filtered.retain(|(chunk, _)| {
+                chunk
+                    .metadata_values("source_path")
+                    .find_map(|v| v.as_str())
+                    .is_some_and(|path| self.matches_path(path))
+            });
+        }
+
+        // Drop excluded tags and paths
+        if let Some(exclude_tags) = &self.exclude_tags {
+            filtered.retain(|(chunk, _)| {
+                !chunk
+                    .metadata_values("tags")
+                    .find_map(|v| v.as_array())
+                    .is_some_and(|chunk_tags| {
+                        chunk_tags
+                            .iter()
+                            .filter_map(|t| t.as_str())
+                            .any(|tag| exclude_tags.iter().any(|t| tag.eq_ignore_ascii_case(t)))
+                    })
+            });
+        }
+        if self.exclude_paths.is_some() {
+            filtered.retain(|(chunk, _)| {
+                !chunk
+                    .metadata_values("source_path")
+                    .find_map(|v| v.as_str())
+                    .is_some_and(|path| self.excludes_path(path))
+            });
+        }
+
+        // Boost, then rank again
+        if !self.boosts.is_empty() {
+            for (chunk, score) in filtered.iter_mut() {
+                for boost in &self.boosts {
+                    if boost.matches(chunk) {
+                        *score *= boost.factor;
+                    }
+                }
+            }
+            filtered.sort_by(|a, b| b.1.total_cmp(&a.1));
+        }
+
         // Apply max results limit
         if let Some(max_results) = self.max_results {
             filtered.truncate(max_results);
@@ -177,6 +391,87 @@ impl SearchFilters {
     }
 }
 
+/// Score multiplier for chunks whose metadata field has a value, such as
+/// `file_type:markdown=1.2` to rank markdown above code.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Boost {
+    /// Metadata field (e.g., "file_type", "language", "tags")
+    pub field: String,
+
+    /// Value the field must have, ignoring case; for list fields such as
+    /// tags, a value the list must contain
+    pub value: String,
+
+    /// Factor the score is multiplied by (above 1 to boost, below to bury)
+    pub factor: f32,
+}
+
+impl Boost {
+    /// Whether the chunk's field has the boosted value.
+    pub fn matches(&self, chunk: &KnowledgeChunk) -> bool {
+        let is_value = |v: &serde_json::Value| {
+            v.as_str()
+                .is_some_and(|s| s.eq_ignore_ascii_case(&self.value))
+        };
+        chunk
+            .metadata_values(&self.field)
+            .any(|v| match v.as_array() {
+                Some(values) => values.iter().any(is_value),
+                None => is_value(v),
+            })
+    }
+}
+
+impl std::fmt::Display for Boost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}={}", self.field, self.value, self.factor)
+    }
+}
+
+impl std::str::FromStr for Boost {
+    type Err = String;
+
+    /// Parse `FIELD:VALUE=FACTOR`, e.g. `file_type:markdown=1.2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid boost '{}' (expected FIELD:VALUE=FACTOR, e.g. file_type:markdown=1.2)",
+                s
+            )
+        };
+        let (target, factor) = s.rsplit_once('=').ok_or_else(invalid)?;
+        let (field, value) = target.split_once(':').ok_or_else(invalid)?;
+        let factor: f32 = factor.trim().parse().map_err(|_| invalid())?;
+        if field.trim().is_empty() || value.trim().is_empty() {
+            return Err(invalid());
+        }
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(format!("Invalid boost '{}': the factor must be above 0", s));
+        }
+        Ok(Self {
+            field: field.trim().to_string(),
+            value: value.trim().to_string(),
+            factor,
+        })
+    }
+}
+
+/// Path globs match as in ignore files: `*` and `?` stay within a directory.
+const PATH_MATCH: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A path without its leading "./".
+fn relative(path: &str) -> &str {
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+    path
+}
+
 /// Detect query intent and generate default filters
 pub fn detect_query_filters(query: &str) -> SearchFilters {
     let query_lower = query.to_lowercase();
@@ -215,6 +510,7 @@ mod tests {
             position: 0,
             text: "test".to_string(),
             embedding: Some(vec![0.0; 384]),
+            title_embedding: None,
             metadata: json!({
                 "file_type": file_type,
                 "language": language,
@@ -299,6 +595,151 @@ mod tests {
         assert_eq!(filtered[0].1, 0.9);
     }
 
+    #[test]
+    fn test_filter_reads_learned_file_metadata() {
+        // `learn` nests file metadata under `custom`, next to the chunk's
+        // own top-level fields
+        let mut learned = create_test_chunk("code", "rust", vec![]);
+        learned.metadata = json!({
+            "language": null,
+            "custom": { "file_type": "code", "language": "rust", "tags": ["api"] },
+        });
+        let chunks = vec![
+            (learned, 0.9),
+            (create_test_chunk("markdown", "english", vec![]), 0.8),
+        ];
+
+        let filters = SearchFilters::new()
+            .with_file_types(vec!["code".to_string()])
+            .with_languages(vec!["rust".to_string()])
+            .with_tags(vec!["api".to_string()]);
+        let filtered = filters.apply(chunks);
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].1, 0.9);
+    }
+
+    #[test]
+    fn test_filter_by_source_path() {
+        let at = |path: &str| {
+            let mut chunk = create_test_chunk("code", "rust", vec![]);
+            chunk.metadata = json!({ "custom": { "source_path": path } });
+            (chunk, 0.9)
+        };
+        let chunks = vec![
+            at("./crates/knowledge/src/lib.rs"),
+            at("crates/knowledge/README.md"),
+            at("crates/cli/src/main.rs"),
+        ];
+
+        let filters = SearchFilters::new().with_path_prefix("crates/knowledge/".to_string());
+        assert!(filters.has_filters());
+        assert_eq!(filters.apply(chunks.clone()).len(), 2);
+
+        let filters = SearchFilters::new().with_path_glob("crates/*/src/*.rs".to_string());
+        assert_eq!(filters.apply(chunks.clone()).len(), 2);
+        let filters = SearchFilters::new().with_path_glob("crates/*.rs".to_string());
+        assert!(filters.apply(chunks.clone()).is_empty());
+        let filters = SearchFilters::new().with_path_glob("crates/**/*.rs".to_string());
+        assert_eq!(filters.apply(chunks).len(), 2);
+
+        assert!(SearchFilters::new()
+            .with_path_glob("crates/[".to_string())
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_path_predicate_uses_literal_prefix() {
+        assert_eq!(SearchFilters::new().path_predicate(), None);
+        assert_eq!(
+            SearchFilters::new()
+                .with_path_glob("./docs/it's/**".to_string())
+                .path_predicate()
+                .unwrap(),
+            "(starts_with(source_path, 'docs/it''s/') OR starts_with(source_path, './docs/it''s/'))"
+        );
+        // Nothing to push down before a leading wildcard
+        assert_eq!(
+            SearchFilters::new()
+                .with_path_glob("**/*.rs".to_string())
+                .path_predicate(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_exclusions_drop_tags_and_paths() {
+        let at = |path: &str, tags: Vec<&str>| {
+            let mut chunk = create_test_chunk("markdown", "english", vec![]);
+            chunk.metadata = json!({ "custom": { "source_path": path, "tags": tags } });
+            (chunk, 0.9)
+        };
+        let chunks = vec![
+            at("docs/guide.md", vec!["Guide"]),
+            at("docs/drafts/pricing.md", vec![]),
+            at("./src/app.test.ts", vec![]),
+            at("notes.md", vec!["draft", "ops"]),
+        ];
+
+        let filters = SearchFilters::new()
+            .with_exclude_tags(vec!["DRAFT".to_string()])
+            .with_exclude_paths(vec!["docs/drafts/".to_string(), "**/*.test.ts".to_string()]);
+        assert!(filters.widens_search());
+        let kept = filters.apply(chunks);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].0.metadata["custom"]["source_path"], "docs/guide.md");
+
+        // Literal paths are pushed down; globs are matched after retrieval
+        assert_eq!(
+            filters.path_predicate().unwrap(),
+            "(source_path IS NULL OR NOT (starts_with(source_path, 'docs/drafts/') \
+             OR starts_with(source_path, './docs/drafts/')))"
+        );
+    }
+
+    #[test]
+    fn test_boosts_rerank_matching_chunks() {
+        let chunks = vec![
+            (create_test_chunk("code", "rust", vec![]), 0.8),
+            (
+                create_test_chunk("markdown", "english", vec!["runbook"]),
+                0.7,
+            ),
+            (create_test_chunk("markdown", "english", vec![]), 0.6),
+        ];
+
+        let filters = SearchFilters::new().with_boosts(vec![
+            "file_type:Markdown=1.2".parse().unwrap(),
+            "tags:runbook=1.5".parse().unwrap(),
+        ]);
+        let ranked = filters.apply(chunks);
+        let scores: Vec<f32> = ranked.iter().map(|(_, score)| *score).collect();
+        assert!((scores[0] - 0.7 * 1.2 * 1.5).abs() < 1e-6, "{:?}", scores);
+        assert_eq!(scores[1], 0.8);
+        assert!((scores[2] - 0.6 * 1.2).abs() < 1e-6, "{:?}", scores);
+    }
+
+    #[test]
+    fn test_parse_boost() {
+        let boost: Boost = " language : rust = 0.5".parse().unwrap();
+        assert_eq!(boost.field, "language");
+        assert_eq!(boost.value, "rust");
+        assert_eq!(boost.factor, 0.5);
+        assert_eq!(boost.to_string(), "language:rust=0.5");
+
+        let invalid = [
+            "markdown=1.2",
+            "file_type:markdown",
+            "file_type:=2",
+            "a:b=0",
+            "a:b=x",
+        ];
+        for invalid in invalid {
+            assert!(invalid.parse::<Boost>().is_err(), "{}", invalid);
+        }
+    }
+
     #[test]
     fn test_filter_max_results() {
         let chunks = vec![
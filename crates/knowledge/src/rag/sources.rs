@@ -4,6 +4,7 @@
 
 use crate::types::KnowledgeSource;
 use guided_core::{AppError, AppResult};
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
@@ -25,11 +26,7 @@ impl SourceManager {
 
     /// Get path to sources.jsonl file.
     fn sources_path(&self) -> PathBuf {
-        self.workspace
-            .join(".guided")
-            .join("knowledge")
-            .join(&self.base_name)
-            .join("sources.jsonl")
+        crate::config::get_sources_path(&self.workspace, &self.base_name)
     }
 
     /// Track a new source by appending to sources.jsonl.
@@ -104,6 +101,80 @@ impl SourceManager {
         Ok(sources)
     }
 
+    /// The most recent record of each tracked path.
+    pub fn latest_by_path(&self) -> AppResult<HashMap<String, KnowledgeSource>> {
+        Ok(self
+            .list_sources()?
+            .into_iter()
+            .map(|source| (source.path.clone(), source))
+            .collect())
+    }
+
+    /// The most recent record of the source learned from `path`.
+    ///
+    /// `path` is matched against the stored paths as given, then as a path
+    /// suffix (`guide.md` matches `docs/guide.md`) when that is unambiguous.
+    pub fn find_source(&self, path: &str) -> AppResult<KnowledgeSource> {
+        let wanted = crate::paths::normalize_separators(path);
+        let wanted = wanted.trim_start_matches("./");
+        let mut latest = self.latest_by_path()?;
+
+        if let Some(key) = latest
+            .keys()
+            .find(|stored| stored.trim_start_matches("./") == wanted)
+            .cloned()
+        {
+            return Ok(latest.remove(&key).unwrap());
+        }
+
+        let suffix = format!("/{}", wanted.trim_start_matches('/'));
+        let mut matches: Vec<KnowledgeSource> = latest
+            .into_values()
+            .filter(|source| crate::paths::normalize_separators(&source.path).ends_with(&suffix))
+            .collect();
+        match matches.len() {
+            0 => Err(AppError::Knowledge(format!(
+                "No source matching '{}' in knowledge base '{}'",
+                path, self.base_name
+            ))),
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let mut paths: Vec<&str> = matches.iter().map(|s| s.path.as_str()).collect();
+                paths.sort();
+                Err(AppError::Knowledge(format!(
+                    "'{}' matches several sources, give a longer path: {}",
+                    path,
+                    paths.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Replace sources.jsonl with `sources`.
+    ///
+    /// Written to a temporary file first, so an interrupted rewrite leaves
+    /// the previous list intact.
+    pub fn replace_sources(&self, sources: &[KnowledgeSource]) -> AppResult<()> {
+        let sources_path = self.sources_path();
+        let temp_path = sources_path.with_extension("jsonl.tmp");
+
+        let mut content = String::new();
+        for source in sources {
+            let json_line = serde_json::to_string(source)
+                .map_err(|e| AppError::Knowledge(format!("Failed to serialize source: {}", e)))?;
+            content.push_str(&json_line);
+            content.push('\n');
+        }
+
+        std::fs::write(&temp_path, content)
+            .map_err(|e| AppError::Knowledge(format!("Failed to write sources.jsonl: {}", e)))?;
+        std::fs::rename(&temp_path, &sources_path)
+            .map_err(|e| AppError::Knowledge(format!("Failed to replace sources.jsonl: {}", e)))?;
+
+        tracing::debug!("Rewrote sources.jsonl with {} sources", sources.len());
+        Ok(())
+    }
+
     /// Clear all tracked sources.
     pub fn clear_sources(&self) -> AppResult<()> {
         let sources_path = self.sources_path();
@@ -117,11 +188,25 @@ impl SourceManager {
 
         Ok(())
     }
+
+    /// Clear the tracked sources of one namespace, keeping the others.
+    pub fn clear_namespace(&self, namespace: &str) -> AppResult<()> {
+        let sources: Vec<KnowledgeSource> = self
+            .list_sources()?
+            .into_iter()
+            .filter(|source| source.namespace.as_deref() != Some(namespace))
+            .collect();
+        self.replace_sources(&sources)?;
+
+        tracing::debug!("Cleared namespace '{}' from sources.jsonl", namespace);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::SourceType;
     use tempfile::TempDir;
 
     #[test]
@@ -132,10 +217,12 @@ mod tests {
         let source = KnowledgeSource {
             source_id: "test-id".to_string(),
             path: "test.md".to_string(),
-            source_type: "file".to_string(),
+            source_type: SourceType::File,
+            content_type: None,
             indexed_at: chrono::Utc::now(),
             chunk_count: 10,
             byte_count: 1024,
+            ..Default::default()
         };
 
         manager.track_source(&source).unwrap();
@@ -152,19 +239,23 @@ mod tests {
         let source1 = KnowledgeSource {
             source_id: "id1".to_string(),
             path: "test1.md".to_string(),
-            source_type: "file".to_string(),
+            source_type: SourceType::File,
+            content_type: None,
             indexed_at: chrono::Utc::now(),
             chunk_count: 5,
             byte_count: 512,
+            ..Default::default()
         };
 
         let source2 = KnowledgeSource {
             source_id: "id2".to_string(),
             path: "test2.md".to_string(),
-            source_type: "file".to_string(),
+            source_type: SourceType::File,
+            content_type: None,
             indexed_at: chrono::Utc::now(),
             chunk_count: 8,
             byte_count: 1024,
+            ..Default::default()
         };
 
         manager.track_source(&source1).unwrap();
@@ -184,10 +275,12 @@ mod tests {
         let source = KnowledgeSource {
             source_id: "test-id".to_string(),
             path: "test.md".to_string(),
-            source_type: "file".to_string(),
+            source_type: SourceType::File,
+            content_type: None,
             indexed_at: chrono::Utc::now(),
             chunk_count: 10,
             byte_count: 1024,
+            ..Default::default()
         };
 
         manager.track_source(&source).unwrap();
@@ -215,10 +308,12 @@ mod tests {
             let source = KnowledgeSource {
                 source_id: format!("id{}", i),
                 path: format!("test{}.md", i),
-                source_type: "file".to_string(),
+                source_type: SourceType::File,
+                content_type: None,
                 indexed_at: chrono::Utc::now(),
                 chunk_count: i as u32,
                 byte_count: (i * 100) as u64,
+                ..Default::default()
             };
             manager.track_source(&source).unwrap();
         }
@@ -226,4 +321,156 @@ mod tests {
         let sources = manager.list_sources().unwrap();
         assert_eq!(sources.len(), 5);
     }
+
+    #[test]
+    fn test_replace_sources_and_latest_by_path() {
+        let temp = TempDir::new().unwrap();
+        let manager = SourceManager::new(temp.path(), "testbase");
+
+        let source = |id: &str, path: &str| KnowledgeSource {
+            source_id: id.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        manager.track_source(&source("old", "a.md")).unwrap();
+        manager.track_source(&source("b", "b.md")).unwrap();
+        manager.track_source(&source("new", "a.md")).unwrap();
+
+        let latest = manager.latest_by_path().unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest["a.md"].source_id, "new");
+
+        let tagged = KnowledgeSource {
+            added_tags: vec!["billing".to_string()],
+            ..source("new", "a.md")
+        };
+        manager
+            .replace_sources(std::slice::from_ref(&tagged))
+            .unwrap();
+        assert_eq!(manager.list_sources().unwrap(), vec![tagged]);
+    }
+
+    #[test]
+    fn test_clear_namespace() {
+        let temp = TempDir::new().unwrap();
+        let manager = SourceManager::new(temp.path(), "testbase");
+
+        let source = |path: &str, namespace: Option<&str>| KnowledgeSource {
+            source_id: path.to_string(),
+            path: path.to_string(),
+            namespace: namespace.map(str::to_string),
+            ..Default::default()
+        };
+        manager.track_source(&source("a.md", Some("docs"))).unwrap();
+        manager.track_source(&source("b.rs", Some("code"))).unwrap();
+        manager.track_source(&source("c.md", None)).unwrap();
+
+        manager.clear_namespace("docs").unwrap();
+        let paths: Vec<String> = manager
+            .list_sources()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.path)
+            .collect();
+        assert_eq!(paths, vec!["b.rs", "c.md"]);
+    }
+
+    #[test]
+    fn test_find_source_by_path_or_suffix() {
+        let temp = TempDir::new().unwrap();
+        let manager = SourceManager::new(temp.path(), "testbase");
+
+        for path in [
+            "./docs/api/guide.md",
+            "docs/intro/guide.md",
+            "docs/api/auth.md",
+        ] {
+            manager
+                .track_source(&KnowledgeSource {
+                    source_id: path.to_string(),
+                    path: path.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            manager.find_source("docs/api/guide.md").unwrap().path,
+            "./docs/api/guide.md"
+        );
+        assert_eq!(
+            manager.find_source("auth.md").unwrap().path,
+            "docs/api/auth.md"
+        );
+        assert_eq!(
+            manager.find_source("intro/guide.md").unwrap().path,
+            "docs/intro/guide.md"
+        );
+
+        let ambiguous = manager.find_source("guide.md").unwrap_err().to_string();
+        assert!(ambiguous.contains("several sources"));
+        assert!(manager.find_source("missing.md").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_keeps_all_fields() {
+        let temp = TempDir::new().unwrap();
+        let manager = SourceManager::new(temp.path(), "testbase");
+
+        let source = KnowledgeSource {
+            source_id: "id".to_string(),
+            path: "https://example.com/guide".to_string(),
+            source_type: SourceType::Url,
+            content_type: Some("html".to_string()),
+            indexed_at: chrono::Utc::now(),
+            chunk_count: 3,
+            byte_count: 2048,
+            token_count: Some(512),
+            namespace: Some("docs".to_string()),
+            added_tags: vec!["billing".to_string()],
+            removed_tags: vec!["guide".to_string()],
+            description: Some("Billing guide".to_string()),
+            weight: Some(1.5),
+            pinned: true,
+            remote_id: Some("confluence:42".to_string()),
+            remote_version: Some("7".to_string()),
+            content_hash: Some("abc123".to_string()),
+        };
+        manager.track_source(&source).unwrap();
+
+        assert_eq!(manager.list_sources().unwrap(), vec![source]);
+    }
+
+    #[test]
+    fn test_list_sources_reads_older_records() {
+        let temp = TempDir::new().unwrap();
+        let manager = SourceManager::new(temp.path(), "testbase");
+        let sources_path = manager.sources_path();
+        std::fs::create_dir_all(sources_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &sources_path,
+            concat!(
+                r#"{"source_id":"a","path":"docs/a.md","source_type":"file","indexed_at":"2025-01-02T03:04:05Z","chunk_count":4,"byte_count":100}"#,
+                "\n",
+                r#"{"id":"b","path":null,"url":"https://example.com","content_type":"html","learned_at":"2025-01-02T03:04:05Z","size_bytes":50}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let sources = manager.list_sources().unwrap();
+        assert_eq!(sources.len(), 2);
+
+        assert_eq!(sources[0].source_id, "a");
+        assert_eq!(sources[0].source_type, SourceType::File);
+        assert_eq!(sources[0].content_type, None);
+        assert_eq!(sources[0].chunk_count, 4);
+
+        assert_eq!(sources[1].source_id, "b");
+        assert_eq!(sources[1].path, "https://example.com");
+        assert_eq!(sources[1].source_type, SourceType::Url);
+        assert_eq!(sources[1].content_type.as_deref(), Some("html"));
+        assert_eq!(sources[1].chunk_count, 0);
+        assert_eq!(sources[1].byte_count, 50);
+    }
 }
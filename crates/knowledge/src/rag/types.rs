@@ -1,22 +1,41 @@
 //! RAG response types.
 
+use super::verify::Verification;
 use serde::{Deserialize, Serialize};
 
 /// A single source reference used to answer a query.
 ///
 /// This is the user-facing representation of where information came from.
-/// Internal details like chunk IDs, scores, and byte offsets are hidden.
+/// Internal details like chunk IDs, scores, and byte offsets are hidden;
+/// the file path and line range are kept for editor integrations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RagSourceRef {
     /// Source file or document name (e.g., "gamedex.md", "playstore.html")
     pub source: String,
 
     /// Human-readable location within the source
-    /// Examples: "lines 12-34", "developer section", "page 2"
+    /// Examples: "lines 12-34", "12:30–14:05", "page 2"
     pub location: String,
 
     /// Short snippet showing the relevant evidence (truncated if needed)
     pub snippet: String,
+
+    /// Byte ranges of `snippet` holding words of the query, to show why the
+    /// chunk was retrieved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<(usize, usize)>,
+
+    /// Absolute path of the source file, when it came from a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// 1-based inclusive line range of the evidence, when known
+    #[serde(default, rename = "lineRange", skip_serializing_if = "Option::is_none")]
+    pub line_range: Option<(usize, usize)>,
+
+    /// Seconds into the recording the evidence was spoken, for transcripts
+    #[serde(default, rename = "timeRange", skip_serializing_if = "Option::is_none")]
+    pub time_range: Option<(f64, f64)>,
 }
 
 /// Response from a RAG answering query.
@@ -40,6 +59,53 @@ pub struct RagResponse {
     /// Used to trigger cautious answering behavior
     #[serde(skip_serializing)]
     pub low_confidence: bool,
+
+    /// Whether the answer was served from the base's answer cache
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+
+    /// The sampled candidates, when the answer was chosen best-of-N
+    #[serde(default, rename = "bestOf", skip_serializing_if = "Option::is_none")]
+    pub best_of: Option<RagBestOf>,
+
+    /// Whether the answer quotes the retrieved chunks instead of being
+    /// written by the LLM
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub extractive: bool,
+
+    /// How well the retrieved chunks support each claim of the answer,
+    /// when it was verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+}
+
+/// Candidate answers of a best-of-N answer, kept for inspection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RagBestOf {
+    /// 1-based number of the candidate used as the answer; `None` when the
+    /// judge synthesized it
+    pub chosen: Option<usize>,
+
+    /// The judge's reply
+    pub judge: String,
+
+    /// Every sampled answer
+    pub candidates: Vec<String>,
+
+    /// Tokens used by the samples and the judge together
+    pub total_tokens: u32,
+}
+
+impl From<&guided_llm::BestOf> for RagBestOf {
+    fn from(best: &guided_llm::BestOf) -> Self {
+        Self {
+            chosen: best.chosen.map(|i| i + 1),
+            judge: best.judge.content.clone(),
+            candidates: best.candidates.iter().map(|c| c.content.clone()).collect(),
+            total_tokens: best.usage.total_tokens,
+        }
+    }
 }
 
 impl RagResponse {
@@ -52,9 +118,20 @@ impl RagResponse {
             sources,
             max_score,
             low_confidence,
+            cached: false,
+            best_of: None,
+            extractive: false,
+            verification: None,
         }
     }
 
+    /// The response judged against a base's own confidence threshold
+    /// rather than [`CONFIDENCE_THRESHOLD`].
+    pub fn with_confidence_threshold(mut self, threshold: f32) -> Self {
+        self.low_confidence = self.max_score < threshold;
+        self
+    }
+
     /// Create a "no information" response when no relevant chunks are found.
     pub fn no_information(query: &str) -> Self {
         Self {
@@ -65,10 +142,28 @@ impl RagResponse {
             sources: Vec::new(),
             max_score: 0.0,
             low_confidence: true,
+            cached: false,
+            best_of: None,
+            extractive: false,
+            verification: None,
         }
     }
 }
 
+/// What [`ask_rag_streaming`](super::ask::ask_rag_streaming) reports while
+/// it answers.
+#[derive(Debug, Clone)]
+pub enum AnswerEvent {
+    /// The sources the answer will cite, once they are retrieved
+    Sources(Vec<RagSourceRef>),
+
+    /// The next piece of the answer text
+    Token(String),
+}
+
+/// Receiver of [`AnswerEvent`]s.
+pub type AnswerObserver<'a> = dyn Fn(AnswerEvent) + Send + Sync + 'a;
+
 /// Minimum score for high-confidence answering.
 /// Scores below this trigger cautious/uncertain language in the LLM prompt.
 pub const CONFIDENCE_THRESHOLD: f32 = 0.30;
@@ -83,6 +178,10 @@ mod tests {
             source: "test.md".to_string(),
             location: "lines 1-10".to_string(),
             snippet: "Test content".to_string(),
+            highlights: Vec::new(),
+            path: None,
+            line_range: None,
+            time_range: None,
         }];
 
         let response = RagResponse::new("Test answer".to_string(), sources, 0.85);
@@ -99,6 +198,10 @@ mod tests {
             source: "test.md".to_string(),
             location: "lines 1-10".to_string(),
             snippet: "Test content".to_string(),
+            highlights: Vec::new(),
+            path: None,
+            line_range: None,
+            time_range: None,
         }];
 
         let response = RagResponse::new("Test answer".to_string(), sources, 0.25);
@@ -124,6 +227,10 @@ mod tests {
             source: "test.md".to_string(),
             location: "lines 1-10".to_string(),
             snippet: "Test snippet".to_string(),
+            highlights: Vec::new(),
+            path: Some("docs/test.md".to_string()),
+            line_range: Some((1, 10)),
+            time_range: None,
         };
 
         let json = serde_json::to_string(&source_ref).unwrap();
@@ -132,5 +239,8 @@ mod tests {
         assert_eq!(deserialized.source, source_ref.source);
         assert_eq!(deserialized.location, source_ref.location);
         assert_eq!(deserialized.snippet, source_ref.snippet);
+        assert_eq!(deserialized.path, source_ref.path);
+        assert_eq!(deserialized.line_range, Some((1, 10)));
+        assert!(json.contains(r#""lineRange":[1,10]"#));
     }
 }
@@ -0,0 +1,181 @@
+//! Verification of answers against their sources.
+//!
+//! Low temperature and a strict prompt keep most answers faithful, but a
+//! model can still slip in a specific (a number, a name, a default) that no
+//! retrieved chunk states. Verification is a second LLM call that splits the
+//! answer into claims and scores how well the numbered documents of the
+//! context support each one; an answer is grounded when every claim is
+//! supported.
+
+use guided_core::{AppError, AppResult};
+use guided_llm::{LlmClient, LlmRequest};
+use serde::{Deserialize, Serialize};
+
+/// Support score from which a claim counts as supported.
+pub const SUPPORTED_SCORE: f32 = 0.5;
+
+/// Claims of an answer with how well the sources support them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verification {
+    /// Each claim of the answer, in order
+    pub claims: Vec<ClaimCheck>,
+
+    /// Whether the answer has claims and the sources support all of them
+    pub grounded: bool,
+}
+
+/// One claim of an answer and its support.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaimCheck {
+    /// The statement, as the verifier restated it
+    pub claim: String,
+
+    /// How well the sources support it, from 0 (not at all, or
+    /// contradicted) to 1 (stated outright)
+    pub support: f32,
+
+    /// 1-based numbers of the context documents supporting it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub documents: Vec<usize>,
+}
+
+impl ClaimCheck {
+    pub fn is_supported(&self) -> bool {
+        self.support >= SUPPORTED_SCORE
+    }
+}
+
+impl Verification {
+    fn new(claims: Vec<ClaimCheck>) -> Self {
+        let grounded = !claims.is_empty() && claims.iter().all(ClaimCheck::is_supported);
+        Self { claims, grounded }
+    }
+
+    /// Claims the sources do not support.
+    pub fn unsupported(&self) -> impl Iterator<Item = &ClaimCheck> {
+        self.claims.iter().filter(|claim| !claim.is_supported())
+    }
+}
+
+/// Check each claim of `answer` against `context`, the numbered documents
+/// the answer was written from, asking `model`.
+pub async fn verify(
+    client: &dyn LlmClient,
+    model: &str,
+    query: &str,
+    answer: &str,
+    context: &str,
+    documents: usize,
+) -> AppResult<Verification> {
+    let response = client
+        .complete(&verification_request(model, query, answer, context))
+        .await
+        .map_err(|e| AppError::Knowledge(format!("Verification request failed: {}", e)))?;
+    let verification = parse_verification(&response.content, documents);
+    if verification.claims.is_empty() {
+        tracing::warn!("Verifier reply had no claims: {}", response.content);
+    }
+    Ok(verification)
+}
+
+/// The LLM request checking `answer` against `context`.
+fn verification_request(model: &str, query: &str, answer: &str, context: &str) -> LlmRequest {
+    let system = "You check answers against their sources. Split the answer into its \
+         factual claims and, for each, judge how well the documents support it. Reply with \
+         one line per claim and nothing else, as:\n\
+         CLAIM: <the claim> | SUPPORT: <0.0 to 1.0> | DOCUMENTS: <document numbers, or none>\n\
+         SUPPORT is 1.0 when a document states the claim, 0.5 when the documents only \
+         imply it, and 0.0 when they do not mention it or contradict it. Judge only \
+         against the documents, not your own knowledge.";
+    let prompt = format!(
+        "Question:\n{}\n\nAnswer to check:\n{}\n\nDocuments:\n{}",
+        query,
+        answer.trim(),
+        context
+    );
+    LlmRequest::new(prompt, model)
+        .with_system(system)
+        .with_temperature(0.0)
+        .with_max_tokens(1000)
+}
+
+/// Claims of a verifier reply. Lines not in the requested form are skipped;
+/// document numbers above `documents` are dropped.
+fn parse_verification(reply: &str, documents: usize) -> Verification {
+    let claims = reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim();
+            let mut claim = None;
+            let mut support = None;
+            let mut cited = Vec::new();
+            for field in line.split('|') {
+                let (key, value) = field.split_once(':')?;
+                let value = value.trim();
+                match key.trim().to_lowercase().as_str() {
+                    "claim" => claim = Some(value.to_string()).filter(|c| !c.is_empty()),
+                    "support" => support = parse_support(value),
+                    "documents" => {
+                        cited = value
+                            .split(|c: char| !c.is_ascii_digit())
+                            .filter_map(|n| n.parse::<usize>().ok())
+                            .filter(|n| (1..=documents).contains(n))
+                            .collect()
+                    }
+                    _ => {}
+                }
+            }
+            Some(ClaimCheck {
+                claim: claim?,
+                support: support?,
+                documents: cited,
+            })
+        })
+        .collect();
+    Verification::new(claims)
+}
+
+/// A support score, given as a number or in words.
+fn parse_support(value: &str) -> Option<f32> {
+    let value = value.to_lowercase();
+    match value.trim_end_matches('.') {
+        "yes" | "supported" => Some(1.0),
+        "partial" | "partly" | "implied" => Some(0.5),
+        "no" | "unsupported" | "contradicted" => Some(0.0),
+        number => number.parse::<f32>().ok().map(|n| n.clamp(0.0, 1.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verification() {
+        let reply = "Here are the claims:\n\
+            CLAIM: Tokens rotate every 90 days | SUPPORT: 1.0 | DOCUMENTS: 1\n\
+            - CLAIM: Rotation is automatic | SUPPORT: partial | DOCUMENTS: 1, 7\n\
+            CLAIM: Admins get an email | SUPPORT: 0 | DOCUMENTS: none\n\
+            CLAIM: | SUPPORT: 1.0 | DOCUMENTS: 2";
+        let verification = parse_verification(reply, 2);
+
+        assert_eq!(verification.claims.len(), 3);
+        assert_eq!(verification.claims[0].claim, "Tokens rotate every 90 days");
+        assert_eq!(verification.claims[0].documents, vec![1]);
+        assert_eq!(verification.claims[1].support, 0.5);
+        assert_eq!(verification.claims[1].documents, vec![1]);
+        assert!(!verification.grounded);
+        let unsupported: Vec<_> = verification
+            .unsupported()
+            .map(|c| c.claim.as_str())
+            .collect();
+        assert_eq!(unsupported, vec!["Admins get an email"]);
+    }
+
+    #[test]
+    fn test_grounded_needs_claims() {
+        let grounded = parse_verification("CLAIM: A | SUPPORT: 0.8 | DOCUMENTS: 1", 1);
+        assert!(grounded.grounded);
+        assert!(!parse_verification("I cannot tell.", 1).grounded);
+    }
+}
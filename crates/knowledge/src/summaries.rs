@@ -0,0 +1,517 @@
+//! Source and directory summaries for hierarchical retrieval.
+//!
+//! `summarize` asks the LLM for a short summary of every learned source,
+//! then of every directory holding more than one summarized entry, written
+//! from the summaries below it. Summaries are embedded into a `summaries`
+//! table next to the chunks. `ask --hierarchical` searches them first and
+//! retrieves chunks only from the sources of the best matches, so broad
+//! questions ("what does this repo do?") get an overview instead of a few
+//! unrelated chunks.
+//!
+//! Summaries are keyed by path and hash their input: summarizing again only
+//! calls the LLM for sources and directories whose content changed.
+
+use crate::types::{KnowledgeBaseConfig, KnowledgeChunk, SummarizeOptions, SummarizeStats};
+use crate::vector_index::VectorIndex;
+use crate::{config, encryption, lancedb_index::LanceDbIndex, metadata};
+use guided_core::{AppError, AppResult};
+use guided_llm::{LlmClient, LlmRequest};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Table of the summaries, in the base's LanceDB database.
+pub const SUMMARIES_TABLE: &str = "summaries";
+
+/// Characters of source text or child summaries given to the LLM, at most.
+const MAX_SUMMARY_INPUT: usize = 6000;
+
+/// Summaries whose sources a hierarchical search retrieves from, at most.
+const SUMMARY_TOP_K: usize = 5;
+
+/// A summary to store, before it is embedded.
+struct Summary {
+    level: &'static str,
+    path: String,
+    namespace: Option<String>,
+    source_ids: Vec<String>,
+    content_hash: String,
+    text: String,
+    embedding: Option<Vec<f32>>,
+}
+
+impl Summary {
+    fn into_chunk(self) -> KnowledgeChunk {
+        let id = summary_id(self.level, self.namespace.as_deref(), &self.path);
+        let mut metadata = serde_json::json!({
+            "custom": {
+                "summary_level": self.level,
+                "source_path": self.path,
+                "source_ids": self.source_ids,
+                "content_hash": self.content_hash,
+            }
+        });
+        if let Some(namespace) = self.namespace {
+            metadata["namespace"] = serde_json::json!(namespace);
+        }
+
+        KnowledgeChunk {
+            id,
+            source_id: self.path,
+            position: 0,
+            text: self.text,
+            embedding: self.embedding,
+            title_embedding: None,
+            metadata,
+        }
+    }
+}
+
+/// The learned sources of one namespace: path -> (source ids, text).
+type SourceTexts = BTreeMap<String, (Vec<String>, String)>;
+
+/// Summarize the sources and directories of a knowledge base.
+pub async fn summarize(
+    workspace: &Path,
+    options: &SummarizeOptions,
+    api_key: Option<&str>,
+) -> AppResult<SummarizeStats> {
+    tracing::info!("Summarizing knowledge base '{}'", options.base_name);
+
+    let config = config::load_config(workspace, &options.base_name)?;
+    let index_path = config::get_index_path(workspace, &options.base_name);
+    if !index_path.exists() {
+        return Err(AppError::Knowledge(format!(
+            "Knowledge base '{}' has no index. Run 'guided knowledge learn' first.",
+            options.base_name
+        )));
+    }
+
+    let dimensions = config.embedding_dim as usize;
+    let cipher = encryption::cipher_for(&config)?;
+    let chunks_index = LanceDbIndex::new(&index_path, "chunks", dimensions)
+        .await?
+        .with_cipher(cipher.clone());
+    crate::check_index_dimensions(&options.base_name, &config, &chunks_index)?;
+    let chunks = chunks_index.all_chunks().await?;
+
+    let mut summaries_index = LanceDbIndex::new(&index_path, SUMMARIES_TABLE, dimensions)
+        .await?
+        .with_cipher(cipher);
+    if summaries_index.dimensions() != dimensions {
+        summaries_index.recreate(dimensions).await?;
+    }
+    let previous: HashMap<String, KnowledgeChunk> = summaries_index
+        .all_chunks()
+        .await?
+        .into_iter()
+        .map(|chunk| (chunk.id.clone(), chunk))
+        .collect();
+
+    let llm_config = options.provider_configs.get(&options.llm_provider);
+    let client = guided_llm::create_client_from_config(&options.llm_provider, llm_config, api_key)
+        .map_err(|e| AppError::Knowledge(format!("Failed to create LLM client: {}", e)))?;
+    let mut writer = Writer {
+        client: client.as_ref(),
+        options,
+        previous,
+        stats: SummarizeStats::default(),
+    };
+
+    let mut summaries = Vec::new();
+    for (namespace, sources) in group_sources(&chunks) {
+        summaries.extend(writer.summarize_namespace(namespace, sources).await?);
+    }
+
+    // Embed the new summaries; reused ones keep their embeddings
+    let texts: Vec<String> = summaries
+        .iter()
+        .filter(|s| s.embedding.is_none())
+        .map(|s| s.text.clone())
+        .collect();
+    if !texts.is_empty() {
+        let engine = crate::embeddings::EmbeddingEngine::new(workspace.to_path_buf())
+            .with_provider_configs(options.provider_configs.clone());
+        let embeddings = options
+            .cancel
+            .run(
+                "knowledge summarize",
+                engine.embed_texts(&options.base_name, &texts, api_key),
+            )
+            .await?;
+        let mut embeddings = embeddings.into_iter();
+        for summary in summaries.iter_mut().filter(|s| s.embedding.is_none()) {
+            summary.embedding = embeddings.next();
+        }
+    }
+
+    let chunks: Vec<KnowledgeChunk> = summaries.into_iter().map(Summary::into_chunk).collect();
+    summaries_index.reset()?;
+    summaries_index.upsert_chunks(&chunks)?;
+
+    let stats = writer.stats;
+    tracing::info!(
+        "Summarized {} sources and {} directories ({} unchanged)",
+        stats.sources_summarized,
+        stats.directories_summarized,
+        stats.summaries_reused
+    );
+    Ok(stats)
+}
+
+/// Learned sources by namespace, their chunk texts joined in order.
+fn group_sources(chunks: &[KnowledgeChunk]) -> BTreeMap<Option<String>, SourceTexts> {
+    let mut by_source: BTreeMap<(Option<String>, String), Vec<&KnowledgeChunk>> = BTreeMap::new();
+    for chunk in chunks {
+        let namespace = chunk.metadata["namespace"].as_str().map(str::to_string);
+        let path = chunk.metadata["custom"]["source_path"]
+            .as_str()
+            .unwrap_or(&chunk.source_id)
+            .to_string();
+        by_source.entry((namespace, path)).or_default().push(chunk);
+    }
+
+    let mut grouped: BTreeMap<Option<String>, SourceTexts> = BTreeMap::new();
+    for ((namespace, path), mut chunks) in by_source {
+        chunks.sort_by_key(|c| (c.source_id.clone(), c.position));
+        let mut source_ids: Vec<String> = chunks.iter().map(|c| c.source_id.clone()).collect();
+        source_ids.dedup();
+        let text = chunks
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        grouped
+            .entry(namespace)
+            .or_default()
+            .insert(path, (source_ids, text));
+    }
+    grouped
+}
+
+/// Writes summaries with the LLM, reusing unchanged ones.
+struct Writer<'a> {
+    client: &'a dyn LlmClient,
+    options: &'a SummarizeOptions,
+    previous: HashMap<String, KnowledgeChunk>,
+    stats: SummarizeStats,
+}
+
+impl Writer<'_> {
+    /// Summaries of the sources of a namespace and of their directories,
+    /// deepest directories first.
+    async fn summarize_namespace(
+        &mut self,
+        namespace: Option<String>,
+        sources: SourceTexts,
+    ) -> AppResult<Vec<Summary>> {
+        let mut summaries = Vec::new();
+        // What each directory contributes to its parent: (path, summary, source ids)
+        let mut entries: BTreeMap<PathBuf, Vec<(String, String, Vec<String>)>> = BTreeMap::new();
+
+        for (path, (source_ids, text)) in sources {
+            self.options.cancel.check("knowledge summarize")?;
+            let summary = self
+                .summary("source", &path, &namespace, source_ids, &text)
+                .await?;
+            entries.entry(parent_dir(&path)).or_default().push((
+                path,
+                summary.text.clone(),
+                summary.source_ids.clone(),
+            ));
+            summaries.push(summary);
+        }
+
+        let root = entries
+            .keys()
+            .cloned()
+            .reduce(|root, dir| common_ancestor(&root, &dir))
+            .unwrap_or_default();
+        let mut dirs: BTreeSet<PathBuf> = BTreeSet::new();
+        for dir in entries.keys() {
+            dirs.extend(
+                dir.ancestors()
+                    .take_while(|d| d.starts_with(&root))
+                    .map(Path::to_path_buf),
+            );
+        }
+        let mut dirs: Vec<PathBuf> = dirs.into_iter().collect();
+        dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+
+        for dir in dirs {
+            let children = entries.remove(&dir).unwrap_or_default();
+            let entry = match children.len() {
+                0 => continue,
+                // A directory with one entry says nothing its entry does not
+                1 => children.into_iter().next().unwrap(),
+                _ => {
+                    self.options.cancel.check("knowledge summarize")?;
+                    let label = dir_label(&dir);
+                    let text = children
+                        .iter()
+                        .map(|(path, summary, _)| format!("{}:\n{}", path, summary))
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    let mut source_ids: Vec<String> =
+                        children.into_iter().flat_map(|(_, _, ids)| ids).collect();
+                    source_ids.sort();
+                    source_ids.dedup();
+                    let summary = self
+                        .summary("directory", &label, &namespace, source_ids, &text)
+                        .await?;
+                    let entry = (label, summary.text.clone(), summary.source_ids.clone());
+                    summaries.push(summary);
+                    entry
+                }
+            };
+            if dir != root {
+                if let Some(parent) = dir.parent() {
+                    entries.entry(parent.to_path_buf()).or_default().push(entry);
+                }
+            }
+        }
+
+        Ok(summaries)
+    }
+
+    /// Summary of one source or directory: the stored one if `content` is
+    /// unchanged, otherwise a new one from the LLM.
+    async fn summary(
+        &mut self,
+        level: &'static str,
+        path: &str,
+        namespace: &Option<String>,
+        source_ids: Vec<String>,
+        content: &str,
+    ) -> AppResult<Summary> {
+        let content = truncate(content, MAX_SUMMARY_INPUT);
+        let mut summary = Summary {
+            level,
+            path: path.to_string(),
+            namespace: namespace.clone(),
+            source_ids,
+            content_hash: metadata::generate_content_hash(content),
+            text: String::new(),
+            embedding: None,
+        };
+
+        let id = summary_id(level, namespace.as_deref(), path);
+        if let Some(stored) = self.previous.remove(&id).filter(|stored| {
+            stored.metadata["custom"]["content_hash"] == summary.content_hash.as_str()
+        }) {
+            summary.text = stored.text;
+            summary.embedding = stored.embedding;
+            self.stats.summaries_reused += 1;
+            return Ok(summary);
+        }
+
+        tracing::debug!("Summarizing {} {}", level, path);
+        summary.text = self
+            .options
+            .cancel
+            .run(
+                "knowledge summarize",
+                write_summary(self.client, &self.options.llm_model, level, path, content),
+            )
+            .await?;
+        match level {
+            "source" => self.stats.sources_summarized += 1,
+            _ => self.stats.directories_summarized += 1,
+        }
+        Ok(summary)
+    }
+}
+
+/// Ask the LLM to summarize a source's text or a directory's summaries.
+async fn write_summary(
+    client: &dyn LlmClient,
+    model: &str,
+    level: &str,
+    path: &str,
+    content: &str,
+) -> AppResult<String> {
+    let (kind, input) = match level {
+        "source" => ("file", "Content"),
+        _ => ("directory", "Summaries of its files and subdirectories"),
+    };
+    let system = format!(
+        "You summarize a {} for a search index. Write 3 to 5 plain sentences on what it \
+         contains and what it is for, naming its main topics, components and terms. \
+         Do not add a preamble, headings or lists, and do not invent anything.",
+        kind
+    );
+    let request = LlmRequest::new(format!("Path: {}\n\n{}:\n{}", path, input, content), model)
+        .with_system(system)
+        .with_temperature(0.1)
+        .with_max_tokens(300);
+
+    let response = client
+        .complete(&request)
+        .await
+        .map_err(|e| AppError::Knowledge(format!("LLM request failed: {}", e)))?;
+    Ok(response.content.trim().to_string())
+}
+
+/// Sources whose summaries are closest to the query, with those summaries
+/// (best first). `None` when no summary is relevant.
+///
+/// Fails when the base has not been summarized.
+pub(crate) async fn select_sources(
+    index_path: &Path,
+    base_name: &str,
+    config: &KnowledgeBaseConfig,
+    namespace: Option<String>,
+    query_embedding: &[f32],
+) -> AppResult<Option<(Vec<String>, Vec<KnowledgeChunk>)>> {
+    if !LanceDbIndex::has_table(index_path, SUMMARIES_TABLE).await? {
+        return Err(AppError::Knowledge(format!(
+            "Knowledge base '{}' has no summaries. Run 'guided knowledge summarize {}' first.",
+            base_name, base_name
+        )));
+    }
+
+    let index = LanceDbIndex::new(index_path, SUMMARIES_TABLE, config.embedding_dim as usize)
+        .await?
+        .with_cipher(encryption::cipher_for(config)?)
+        .with_namespace(namespace);
+    let summaries: Vec<KnowledgeChunk> = index
+        .search(query_embedding, SUMMARY_TOP_K)?
+        .into_iter()
+        .filter(|(_summary, score)| *score >= crate::MIN_RELEVANCE_SCORE)
+        .map(|(summary, _score)| summary)
+        .collect();
+    if summaries.is_empty() {
+        return Ok(None);
+    }
+
+    let mut source_ids: Vec<String> = summaries
+        .iter()
+        .filter_map(|s| s.metadata["custom"]["source_ids"].as_array())
+        .flatten()
+        .filter_map(|id| id.as_str().map(str::to_string))
+        .collect();
+    source_ids.sort();
+    source_ids.dedup();
+    tracing::debug!(
+        "Hierarchical search: {} summaries select {} sources",
+        summaries.len(),
+        source_ids.len()
+    );
+    Ok(Some((source_ids, summaries)))
+}
+
+/// Context block of the selected summaries, for the LLM prompt.
+pub(crate) fn overview_context(summaries: &[KnowledgeChunk]) -> String {
+    summaries
+        .iter()
+        .map(|summary| {
+            format!(
+                "[Overview of {}]\n{}",
+                summary.metadata["custom"]["source_path"]
+                    .as_str()
+                    .unwrap_or(&summary.source_id),
+                summary.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Id of the summary of a source or directory.
+fn summary_id(level: &str, namespace: Option<&str>, path: &str) -> String {
+    format!(
+        "summary-{}",
+        metadata::generate_content_hash(&format!(
+            "{}\0{}\0{}",
+            level,
+            namespace.unwrap_or(""),
+            path
+        ))
+    )
+}
+
+/// Directory of a source path (empty for bare names).
+fn parent_dir(path: &str) -> PathBuf {
+    Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Deepest directory containing both `a` and `b`.
+fn common_ancestor(a: &Path, b: &Path) -> PathBuf {
+    a.ancestors()
+        .find(|dir| b.starts_with(dir))
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// A directory path as shown in summaries (`.` for the empty path).
+fn dir_label(dir: &Path) -> String {
+    match dir.to_string_lossy() {
+        label if label.is_empty() => ".".to_string(),
+        label => label.into_owned(),
+    }
+}
+
+/// At most `max_chars` characters of `text`.
+fn truncate(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_ancestor() {
+        assert_eq!(
+            common_ancestor(Path::new("/repo/src/rag"), Path::new("/repo/docs")),
+            PathBuf::from("/repo")
+        );
+        assert_eq!(
+            common_ancestor(Path::new("src"), Path::new("docs")),
+            PathBuf::new()
+        );
+        assert_eq!(dir_label(Path::new("")), ".");
+    }
+
+    #[test]
+    fn test_group_sources_joins_chunks_in_order() {
+        let chunk = |source_id: &str, position: u32, path: &str, namespace: Option<&str>| {
+            let mut metadata = serde_json::json!({"custom": {"source_path": path}});
+            if let Some(namespace) = namespace {
+                metadata["namespace"] = serde_json::json!(namespace);
+            }
+            KnowledgeChunk {
+                id: format!("{}-{}", source_id, position),
+                source_id: source_id.to_string(),
+                position,
+                text: format!("part {}", position),
+                embedding: None,
+                title_embedding: None,
+                metadata,
+            }
+        };
+        let chunks = vec![
+            chunk("a", 1, "docs/a.md", None),
+            chunk("a", 0, "docs/a.md", None),
+            chunk("b", 0, "docs/b.md", Some("team")),
+        ];
+
+        let grouped = group_sources(&chunks);
+        assert_eq!(
+            grouped[&None]["docs/a.md"],
+            (vec!["a".to_string()], "part 0\n\npart 1".to_string())
+        );
+        assert_eq!(grouped[&Some("team".to_string())].len(), 1);
+    }
+
+    #[test]
+    fn test_truncate_keeps_char_boundaries() {
+        assert_eq!(truncate("héllo", 2), "hé");
+        assert_eq!(truncate("hi", 10), "hi");
+    }
+}
@@ -0,0 +1,409 @@
+//! Symbol index: where each function and method of a base is defined.
+//!
+//! Learn extracts the functions and methods of every code file with
+//! tree-sitter (see [`crate::chunk::symbols`]), records each in the metadata
+//! of the chunk its definition starts in (`defines`), and keeps one entry
+//! per definition in `symbols.jsonl`, replacing a file's entries whenever
+//! it is learned again. `guided knowledge symbol` looks names up there, and
+//! `ask` adds the definition chunk of every symbol the query names, so
+//! exact-name questions do not depend on vector similarity.
+//!
+//! Encrypted bases have no symbol index: names and signatures would be
+//! stored in the clear.
+
+use crate::chunk::{extract_symbols, Chunk, ContentType, SymbolKind};
+use crate::lancedb_index::LanceDbIndex;
+use crate::rag::SearchFilters;
+use crate::types::{KnowledgeChunk, KnowledgeSource};
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Metadata key of the definitions a chunk holds.
+pub const DEFINES_KEY: &str = "defines";
+
+/// Definition chunks added to a query's results, at most.
+const MAX_MENTIONED: usize = 3;
+
+/// A function or method definition, as recorded in chunk metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Definition {
+    /// Symbol name
+    pub name: String,
+
+    /// Name with its enclosing type (e.g., "Parser::parse")
+    pub qualified_name: String,
+
+    /// Function or method
+    pub kind: SymbolKind,
+
+    /// First line of the definition
+    pub signature: String,
+
+    /// Line range in the file (1-based, inclusive)
+    pub line_range: (usize, usize),
+}
+
+/// A definition in the symbol index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolEntry {
+    #[serde(flatten)]
+    pub definition: Definition,
+
+    /// Path of the source, as tracked in sources.jsonl
+    pub path: String,
+
+    /// Namespace the source was learned into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Source the definition was learned from
+    pub source_id: String,
+
+    /// Chunk the definition starts in
+    pub chunk_id: String,
+}
+
+/// Record in the metadata of each code chunk the functions and methods whose
+/// definition starts in it. `document` is the text the chunks' line ranges
+/// refer to. A definition split over several chunks is recorded in the
+/// first chunk holding all of it, else in the first holding its first line.
+pub fn mark_definitions(document: &str, chunks: &mut [Chunk]) {
+    let Some(language) = chunks.iter().find_map(|chunk| {
+        match (&chunk.metadata.content_type, &chunk.metadata.language) {
+            (ContentType::Code { .. }, Some(language)) => Some(language.clone()),
+            _ => None,
+        }
+    }) else {
+        return;
+    };
+    let Ok(symbols) = extract_symbols(&language, document) else {
+        return;
+    };
+
+    for symbol in symbols {
+        let (start, end) = symbol.line_range;
+        let holds = |chunk: &Chunk, line: usize| {
+            chunk
+                .metadata
+                .line_range
+                .is_some_and(|(first, last)| first <= line && line <= last)
+        };
+        let Some(chunk) = chunks
+            .iter()
+            .position(|chunk| holds(chunk, start) && holds(chunk, end))
+            .or_else(|| chunks.iter().position(|chunk| holds(chunk, start)))
+        else {
+            continue;
+        };
+
+        let definition = Definition {
+            qualified_name: symbol.qualified_name(),
+            name: symbol.name,
+            kind: symbol.kind,
+            signature: symbol.signature,
+            line_range: symbol.line_range,
+        };
+        let Some(custom) = chunks[chunk].metadata.custom.as_object_mut() else {
+            continue;
+        };
+        let defines = custom
+            .entry(DEFINES_KEY)
+            .or_insert_with(|| serde_json::json!([]));
+        if let (Some(defines), Ok(definition)) =
+            (defines.as_array_mut(), serde_json::to_value(definition))
+        {
+            defines.push(definition);
+        }
+    }
+}
+
+/// The definitions recorded in `chunks` by [`mark_definitions`], as index
+/// entries of `source`.
+pub fn definitions(source: &KnowledgeSource, chunks: &[Chunk]) -> Vec<SymbolEntry> {
+    chunks
+        .iter()
+        .flat_map(|chunk| {
+            let defines = chunk
+                .metadata
+                .custom
+                .get(DEFINES_KEY)
+                .cloned()
+                .unwrap_or_default();
+            serde_json::from_value::<Vec<Definition>>(defines)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|definition| SymbolEntry {
+                    definition,
+                    path: source.path.clone(),
+                    namespace: source.namespace.clone(),
+                    source_id: source.source_id.clone(),
+                    chunk_id: chunk.id.clone(),
+                })
+        })
+        .collect()
+}
+
+/// The symbol index of a knowledge base.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    path: PathBuf,
+    entries: Vec<SymbolEntry>,
+}
+
+impl SymbolIndex {
+    /// Load the symbol index of a base; empty if it has none yet.
+    pub fn load(workspace: &Path, base_name: &str) -> AppResult<Self> {
+        let path = crate::config::get_symbols_path(workspace, base_name);
+        let mut entries = Vec::new();
+        if path.exists() {
+            let file = std::fs::File::open(&path)?;
+            for (line_num, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = serde_json::from_str(&line).map_err(|e| {
+                    AppError::Knowledge(format!(
+                        "Failed to parse line {} in {:?}: {}",
+                        line_num + 1,
+                        path,
+                        e
+                    ))
+                })?;
+                entries.push(entry);
+            }
+        }
+        Ok(Self { path, entries })
+    }
+
+    /// Write the index back to the base.
+    pub fn save(&self) -> AppResult<()> {
+        if self.entries.is_empty() && !self.path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&self.path)?);
+        for entry in &self.entries {
+            let line =
+                serde_json::to_string(entry).map_err(|e| AppError::Serialization(e.to_string()))?;
+            writeln!(file, "{}", line)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replace the definitions of `source`'s path and namespace with
+    /// `entries`.
+    pub fn replace(&mut self, source: &KnowledgeSource, entries: Vec<SymbolEntry>) {
+        self.entries
+            .retain(|entry| entry.path != source.path || entry.namespace != source.namespace);
+        self.entries.extend(entries);
+    }
+
+    /// Drop the definitions of these sources (by source id).
+    pub fn remove_sources(&mut self, source_ids: &[String]) {
+        self.entries
+            .retain(|entry| !source_ids.contains(&entry.source_id));
+    }
+
+    /// Drop every definition, or those of one namespace.
+    pub fn clear(&mut self, namespace: Option<&str>) {
+        match namespace {
+            Some(namespace) => self
+                .entries
+                .retain(|entry| entry.namespace.as_deref() != Some(namespace)),
+            None => self.entries.clear(),
+        }
+    }
+
+    /// Definitions named `name`, plainly or qualified (`parse` or
+    /// `Parser::parse`), by path and line.
+    pub fn lookup(&self, name: &str) -> Vec<&SymbolEntry> {
+        let mut found: Vec<&SymbolEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry.definition.name == name || entry.definition.qualified_name == name
+            })
+            .collect();
+        found.sort_by(|a, b| {
+            (&a.path, a.definition.line_range).cmp(&(&b.path, b.definition.line_range))
+        });
+        found
+    }
+
+    /// Definitions of the symbols `query` names, in the order it names them.
+    ///
+    /// Only words that read as code count: qualified (`Parser::parse`),
+    /// snake_case or camelCase names, and any name in backticks or followed
+    /// by `(`. Plain words such as "parse" in "how do I parse a file" are
+    /// left to the vector search.
+    pub fn mentioned_in(&self, query: &str, namespace: Option<&str>) -> Vec<&SymbolEntry> {
+        let mut found: Vec<&SymbolEntry> = Vec::new();
+        for word in code_words(query) {
+            for entry in self.lookup(word) {
+                let in_namespace = namespace.is_none() || entry.namespace.as_deref() == namespace;
+                if in_namespace && !found.contains(&entry) {
+                    found.push(entry);
+                }
+            }
+        }
+        found
+    }
+
+    /// Add the definition chunks of the symbols `query` names to `results`,
+    /// ahead of the search results; those that are already there move to
+    /// the front. Chunks that fail `filters` are left out.
+    pub async fn include_definitions(
+        &self,
+        index: &LanceDbIndex,
+        query: &str,
+        query_embedding: &[f32],
+        namespace: Option<&str>,
+        filters: &SearchFilters,
+        results: Vec<(KnowledgeChunk, f32)>,
+    ) -> AppResult<Vec<(KnowledgeChunk, f32)>> {
+        let mut chunk_ids: Vec<String> = Vec::new();
+        for entry in self.mentioned_in(query, namespace) {
+            if !chunk_ids.contains(&entry.chunk_id) {
+                chunk_ids.push(entry.chunk_id.clone());
+            }
+        }
+        chunk_ids.truncate(MAX_MENTIONED);
+        if chunk_ids.is_empty() {
+            return Ok(results);
+        }
+
+        let mut definitions =
+            filters.apply(index.score_chunk_ids(&chunk_ids, query_embedding).await?);
+        definitions.sort_by_key(|(chunk, _)| chunk_ids.iter().position(|id| *id == chunk.id));
+        tracing::debug!(
+            "Query names {} symbols; adding {} definition chunks",
+            chunk_ids.len(),
+            definitions.len()
+        );
+
+        let added: HashSet<String> = definitions
+            .iter()
+            .map(|(chunk, _)| chunk.id.clone())
+            .collect();
+        definitions.extend(
+            results
+                .into_iter()
+                .filter(|(chunk, _)| !added.contains(&chunk.id)),
+        );
+        Ok(definitions)
+    }
+}
+
+/// Words of `query` that read as code names (see
+/// [`SymbolIndex::mentioned_in`]).
+fn code_words(query: &str) -> Vec<&str> {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == ':';
+    let mut words = Vec::new();
+    let mut rest = query;
+    while let Some(start) = rest.find(is_name_char) {
+        let end = rest[start..]
+            .find(|c: char| !is_name_char(c))
+            .map_or(rest.len(), |len| start + len);
+        let word = rest[start..end].trim_matches(':');
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+
+        let qualified = word.contains("::");
+        let snake = word.contains('_') && word.chars().any(char::is_alphabetic);
+        let camel =
+            word.chars().skip(1).any(char::is_uppercase) && word.chars().any(char::is_lowercase);
+        let quoted = before == Some('`') && after == Some('`');
+        let called = after == Some('(');
+        if !word.is_empty() && (qualified || snake || camel || quoted || called) {
+            words.push(word);
+        }
+        rest = &rest[end..];
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, qualified_name: &str, path: &str, chunk_id: &str) -> SymbolEntry {
+        SymbolEntry {
+            definition: Definition {
+                name: name.to_string(),
+                qualified_name: qualified_name.to_string(),
+                kind: SymbolKind::Function,
+                signature: format!("fn {}()", name),
+                line_range: (1, 3),
+            },
+            path: path.to_string(),
+            namespace: None,
+            source_id: path.to_string(),
+            chunk_id: chunk_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_code_words() {
+        assert_eq!(
+            code_words("Where is Parser::parse defined, and what calls load_config or `run`?"),
+            vec!["Parser::parse", "load_config", "run"]
+        );
+        assert_eq!(
+            code_words("Does chunkText() use ChunkPipeline?"),
+            vec!["chunkText", "ChunkPipeline"]
+        );
+        // Capitalized and plain words are prose
+        assert!(code_words("How do I parse a file? Parser errors, v2").is_empty());
+    }
+
+    #[test]
+    fn test_lookup_and_mentions() {
+        let mut index = SymbolIndex::default();
+        let source = KnowledgeSource {
+            source_id: "a".to_string(),
+            path: "src/parser.rs".to_string(),
+            ..Default::default()
+        };
+        index.replace(
+            &source,
+            vec![
+                entry("parse", "Parser::parse", "src/parser.rs", "c1"),
+                entry("parse_file", "parse_file", "src/parser.rs", "c2"),
+            ],
+        );
+
+        assert_eq!(index.lookup("Parser::parse")[0].chunk_id, "c1");
+        assert_eq!(index.lookup("parse")[0].chunk_id, "c1");
+        assert!(index.lookup("Parse").is_empty());
+
+        let mentioned: Vec<&str> = index
+            .mentioned_in("what does parse_file do before `parse`?", None)
+            .iter()
+            .map(|entry| entry.chunk_id.as_str())
+            .collect();
+        assert_eq!(mentioned, vec!["c2", "c1"]);
+        assert!(index
+            .mentioned_in("how do I parse a file?", None)
+            .is_empty());
+        assert!(index.mentioned_in("parse_file", Some("docs")).is_empty());
+
+        // Learning the file again replaces its entries
+        index.replace(
+            &source,
+            vec![entry("parse", "Parser::parse", "src/parser.rs", "c3")],
+        );
+        assert!(index.lookup("parse_file").is_empty());
+        assert_eq!(index.lookup("parse")[0].chunk_id, "c3");
+    }
+}
@@ -0,0 +1,90 @@
+//! Tests for the RAG answer cache.
+
+use crate::tests::fixtures;
+use crate::types::AskOptions;
+use guided_core::config::ProviderConfig;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for Ollama that counts the answers it writes.
+    async fn llm(calls: Arc<AtomicUsize>) -> HashMap<String, ProviderConfig> {
+        fixtures::ollama(move |_request| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            "Deploys are frozen on Fridays.".to_string()
+        })
+        .await
+    }
+
+    async fn learn(workspace: &Path, policy: &str) {
+        fixtures::learn_file(workspace, "team", "deploys.md", policy).await;
+    }
+
+    async fn ask(
+        workspace: &Path,
+        query: &str,
+        cache: bool,
+        provider_configs: &HashMap<String, ProviderConfig>,
+    ) -> crate::RagResponse {
+        let options = AskOptions {
+            base_name: "team".to_string(),
+            query: query.to_string(),
+            top_k: Some(3),
+            cache,
+            provider_configs: provider_configs.clone(),
+            ..Default::default()
+        };
+        crate::rag::ask::ask_rag(workspace, options, "ollama", None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_answers_are_cached_until_sources_change() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace, "# Deploys\n\nDeploys are frozen on Fridays.\n").await;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let providers = llm(calls.clone()).await;
+
+        let first = ask(workspace, "When are deploys frozen?", true, &providers).await;
+        assert!(!first.cached);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let second = ask(workspace, "when are deploys   frozen", true, &providers).await;
+        assert!(second.cached);
+        assert_eq!(second.answer, first.answer);
+        assert_eq!(second.sources.len(), first.sources.len());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Without the cache the LLM is asked again
+        assert!(
+            !ask(workspace, "When are deploys frozen?", false, &providers)
+                .await
+                .cached
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // A changed source invalidates the answers it contributed to
+        learn(
+            workspace,
+            "# Deploys\n\nDeploys are frozen on Fridays and holidays.\n",
+        )
+        .await;
+        assert!(
+            !ask(workspace, "When are deploys frozen?", true, &providers)
+                .await
+                .cached
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        crate::clean(workspace, "team", None).await.unwrap();
+        assert!(!crate::config::get_answer_cache_path(workspace, "team").exists());
+    }
+}
@@ -0,0 +1,133 @@
+//! Tests for streaming RAG answers.
+
+use crate::rag::AnswerEvent;
+use crate::tests::fixtures;
+use crate::tests::stub_http::{self, Reply};
+use crate::types::AskOptions;
+use guided_core::config::ProviderConfig;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for Ollama streaming its answer in three pieces.
+    async fn llm() -> HashMap<String, ProviderConfig> {
+        let endpoint = stub_http::serve(|_request| {
+            Reply::ok(
+                "application/x-ndjson",
+                concat!(
+                    r#"{"model":"llama3","response":"Deploys are ","done":false}"#,
+                    "\n",
+                    r#"{"model":"llama3","response":"frozen on Fridays.","done":false}"#,
+                    "\n",
+                    r#"{"model":"llama3","response":"","done":true}"#,
+                    "\n",
+                ),
+            )
+        })
+        .await;
+        fixtures::ollama_at(endpoint)
+    }
+
+    async fn learn(workspace: &Path) {
+        let policy = "# Deploys\n\nDeploys are frozen on Fridays.\n";
+        fixtures::learn_file(workspace, "team", "deploys.md", policy).await;
+    }
+
+    async fn ask(
+        workspace: &Path,
+        provider_configs: &HashMap<String, ProviderConfig>,
+    ) -> (crate::RagResponse, Vec<AnswerEvent>) {
+        let options = AskOptions {
+            base_name: "team".to_string(),
+            query: "When are deploys frozen?".to_string(),
+            top_k: Some(3),
+            cache: true,
+            provider_configs: provider_configs.clone(),
+            ..Default::default()
+        };
+        let events = Mutex::new(Vec::new());
+        let response =
+            crate::rag::ask::ask_rag_streaming(workspace, options, "ollama", None, &|event| {
+                events.lock().unwrap().push(event)
+            })
+            .await
+            .unwrap();
+        (response, events.into_inner().unwrap())
+    }
+
+    fn tokens(events: &[AnswerEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                AnswerEvent::Token(token) => Some(token.as_str()),
+                AnswerEvent::Sources(_) => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_answer_streams_sources_then_tokens() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+        let providers = llm().await;
+
+        let (response, events) = ask(workspace, &providers).await;
+        assert_eq!(response.answer, "Deploys are frozen on Fridays.");
+        match &events[0] {
+            AnswerEvent::Sources(sources) => {
+                assert_eq!(sources.len(), response.sources.len());
+                assert_eq!(sources[0].source, "deploys.md");
+            }
+            other => panic!("expected sources first, got {:?}", other),
+        }
+        assert_eq!(tokens(&events), vec!["Deploys are ", "frozen on Fridays."]);
+
+        // A cached answer arrives in one piece
+        let (cached, events) = ask(workspace, &providers).await;
+        assert!(cached.cached);
+        assert_eq!(tokens(&events), vec!["Deploys are frozen on Fridays."]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_extractive_answer_does_not_call_the_llm() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+
+        // Nothing listens on the discard port, so calling the LLM would fail
+        let provider_configs = fixtures::ollama_at("http://127.0.0.1:9".to_string());
+        let options = AskOptions {
+            base_name: "team".to_string(),
+            query: "When are deploys frozen?".to_string(),
+            top_k: Some(3),
+            cache: true,
+            extractive: true,
+            provider_configs,
+            ..Default::default()
+        };
+        let events = Mutex::new(Vec::new());
+        let response =
+            crate::rag::ask::ask_rag_streaming(workspace, options, "ollama", None, &|event| {
+                events.lock().unwrap().push(event)
+            })
+            .await
+            .unwrap();
+
+        assert!(response.extractive && !response.cached);
+        assert_eq!(
+            response.answer,
+            "- Deploys are frozen on Fridays. (deploys.md)"
+        );
+        assert_eq!(response.sources[0].source, "deploys.md");
+        assert_eq!(
+            tokens(&events.into_inner().unwrap()),
+            vec![response.answer.as_str()]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Tests for the `KnowledgeBase` handle.
+
+use crate::tests::fixtures::learn_text;
+use crate::types::AskOptions;
+use crate::KnowledgeBase;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask_options(query: &str) -> AskOptions {
+        AskOptions {
+            query: query.to_string(),
+            top_k: Some(3),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_handle_learns_and_asks_without_reopening() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+
+        let mut base = KnowledgeBase::open(workspace, "notes").await.unwrap();
+        let err = base.ask(ask_options("invoices"), None).await.unwrap_err();
+        assert!(err.to_string().contains("has no index"), "{}", err);
+
+        base.learn(
+            &learn_text(
+                "notes",
+                "billing.md",
+                "Invoices are exported every Monday at noon.",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(base.config().provider, "trigram");
+
+        // The same handle answers repeatedly
+        for _ in 0..2 {
+            let result = base
+                .ask(ask_options("When are invoices exported?"), None)
+                .await
+                .unwrap();
+            assert!(result.chunks[0].text.contains("every Monday"));
+        }
+        let stats = base.stats().await.unwrap();
+        assert_eq!(stats.sources_count, 1);
+        assert_eq!(stats.base_name, "notes");
+
+        // Learned elsewhere, seen after a reload
+        crate::learn(
+            workspace,
+            &learn_text(
+                "notes",
+                "deploys.md",
+                "Deploys are frozen during the holidays.",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+        base.reload().await.unwrap();
+        assert_eq!(base.stats().await.unwrap().sources_count, 2);
+        base.close();
+    }
+}
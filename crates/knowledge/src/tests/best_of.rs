@@ -0,0 +1,88 @@
+//! Tests for best-of-N RAG answers.
+
+use crate::tests::fixtures;
+use crate::types::{AskOptions, KnowledgeBaseConfig};
+use guided_core::config::ProviderConfig;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for Ollama that answers differently on each sample and
+    /// picks the second candidate when asked to judge.
+    async fn llm(requests: Arc<Mutex<Vec<String>>>) -> HashMap<String, ProviderConfig> {
+        fixtures::ollama(move |request| {
+            let mut requests = requests.lock().unwrap();
+            requests.push(request.to_string());
+            if request.contains("You judge answers") {
+                "Best: 2".to_string()
+            } else {
+                format!("Tokens rotate every {} days.", 30 * requests.len())
+            }
+        })
+        .await
+    }
+
+    async fn learn(workspace: &Path) {
+        crate::config::save_config(
+            workspace,
+            &KnowledgeBaseConfig {
+                name: "ops".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let tokens = "# Tokens\n\nAccess tokens rotate every 90 days.\n";
+        fixtures::learn_file(workspace, "ops", "tokens.md", tokens).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_best_of_keeps_judged_candidate() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let providers = llm(requests.clone()).await;
+
+        let options = |best_of| AskOptions {
+            base_name: "ops".to_string(),
+            query: "How often do access tokens rotate?".to_string(),
+            top_k: Some(3),
+            cache: true,
+            best_of,
+            provider_configs: providers.clone(),
+            ..Default::default()
+        };
+        let response = crate::rag::ask::ask_rag(workspace, options(Some(3)), "ollama", None)
+            .await
+            .unwrap();
+
+        let best_of = response.best_of.expect("best-of candidates");
+        assert_eq!(best_of.candidates.len(), 3);
+        assert_eq!(best_of.chosen, Some(2));
+        assert_eq!(best_of.judge, "Best: 2");
+        assert_eq!(response.answer, best_of.candidates[1]);
+        assert!(!response.cached);
+
+        // Three samples, then the judge, whose prompt holds the retrieved context
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 4);
+            assert!(requests[3].contains("rotate every 90 days"));
+            assert!(requests[3].contains("Candidate 3"));
+        }
+
+        // Best-of answers are not cached, so a plain ask goes to the LLM
+        let response = crate::rag::ask::ask_rag(workspace, options(None), "ollama", None)
+            .await
+            .unwrap();
+        assert!(!response.cached);
+        assert!(response.best_of.is_none());
+        assert_eq!(requests.lock().unwrap().len(), 5);
+    }
+}
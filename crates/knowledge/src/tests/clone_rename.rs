@@ -0,0 +1,108 @@
+//! Tests for cloning and renaming knowledge bases.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::AskOptions;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask_options(base: &str) -> AskOptions {
+        AskOptions {
+            base_name: base.to_string(),
+            query: "when does the office open".to_string(),
+            top_k: Some(5),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_clone_and_rename_keep_index_and_sources() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let notes = workspace.join("office.md");
+        std::fs::write(
+            &notes,
+            "# Office\n\nThe office opens at nine every weekday.\n",
+        )
+        .unwrap();
+        crate::learn(workspace, &learn_options("docs", vec![notes.clone()]), None)
+            .await
+            .unwrap();
+        let chunks = crate::stats(workspace, "docs").await.unwrap().chunks_count;
+
+        let copy = crate::clone_base(workspace, "docs", "docs-small", None).unwrap();
+        assert_eq!(
+            copy,
+            crate::config::workspace_knowledge_dir(workspace).join("docs-small")
+        );
+        let config = std::fs::read_to_string(copy.join("config.yaml")).unwrap();
+        assert!(config.contains("name: docs-small"), "{}", config);
+        assert_eq!(
+            crate::stats(workspace, "docs-small")
+                .await
+                .unwrap()
+                .chunks_count,
+            chunks
+        );
+        let result = crate::ask(workspace, ask_options("docs-small"), None)
+            .await
+            .unwrap();
+        assert!(result.chunks[0].text.contains("opens at nine"));
+
+        // The copy is independent of the original
+        crate::clean(workspace, "docs-small", None).await.unwrap();
+        assert_eq!(
+            crate::stats(workspace, "docs").await.unwrap().chunks_count,
+            chunks
+        );
+
+        crate::rename_base(workspace, "docs", "handbook").unwrap();
+        assert!(crate::stats(workspace, "docs").await.is_err());
+        assert_eq!(
+            crate::stats(workspace, "handbook")
+                .await
+                .unwrap()
+                .chunks_count,
+            chunks
+        );
+        let sources = crate::rag::SourceManager::new(workspace, "handbook")
+            .list_sources()
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(
+            crate::config::load_config(workspace, "handbook")
+                .unwrap()
+                .model,
+            "trigram-v2"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_clone_and_rename_refuse_unsafe_names() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let notes = workspace.join("office.md");
+        std::fs::write(&notes, "# Office\n\nThe office opens at nine.\n").unwrap();
+        crate::learn(workspace, &learn_options("docs", vec![notes.clone()]), None)
+            .await
+            .unwrap();
+        crate::clone_base(workspace, "docs", "copy", None).unwrap();
+
+        let err = crate::clone_base(workspace, "docs", "copy", None).unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        let err = crate::rename_base(workspace, "docs", "../outside").unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid knowledge base name"),
+            "{}",
+            err
+        );
+        let err = crate::rename_base(workspace, "missing", "other").unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+
+        // Failed attempts leave both bases as they were
+        assert!(crate::config::get_index_path(workspace, "docs").exists());
+        assert!(crate::config::get_index_path(workspace, "copy").exists());
+    }
+}
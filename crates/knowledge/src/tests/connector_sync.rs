@@ -0,0 +1,150 @@
+//! Tests for learning from a connector and syncing it incrementally.
+
+use crate::tests::fixtures;
+use crate::types::{LearnOptions, SourceType};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the stub workspace has been edited: page "a" changed and
+    /// page "b" deleted.
+    static EDITED: AtomicBool = AtomicBool::new(false);
+
+    fn route(request: &str) -> String {
+        let edited = EDITED.load(Ordering::SeqCst);
+        if request.starts_with("POST /v1/search") {
+            let (a_version, b) = if edited {
+                ("2026-02-01T00:00:00.000Z", "")
+            } else {
+                (
+                    "2026-01-01T00:00:00.000Z",
+                    r#",{"id":"b","url":"https://notion.so/b","last_edited_time":"2026-01-01T00:00:00.000Z",
+                        "properties":{"title":{"type":"title","title":[{"plain_text":"Oncall"}]}}}"#,
+                )
+            };
+            format!(
+                r#"{{"results":[{{"id":"a","url":"https://notion.so/a","last_edited_time":"{}",
+                    "properties":{{"title":{{"type":"title","title":[{{"plain_text":"Runbook"}}]}}}}}}{}],
+                   "has_more":false}}"#,
+                a_version, b
+            )
+        } else if request.starts_with("GET /v1/blocks/a/children") {
+            let text = if edited {
+                "Restart the billing service with a rolling deploy."
+            } else {
+                "Restart the billing service by hand."
+            };
+            format!(
+                r#"{{"results":[{{"id":"p","type":"paragraph","paragraph":{{"rich_text":[{{"plain_text":"{}"}}]}}}}],"has_more":false}}"#,
+                text
+            )
+        } else if request.starts_with("GET /v1/blocks/b/children") {
+            r#"{"results":[{"id":"q","type":"paragraph","paragraph":{"rich_text":[{"plain_text":"Page the primary first."}]}}],"has_more":false}"#
+                .to_string()
+        } else {
+            panic!("unexpected request: {}", request);
+        }
+    }
+
+    fn learn_options() -> LearnOptions {
+        LearnOptions {
+            connector: Some("notion".to_string()),
+            ..fixtures::learn_options("wiki", Vec::new())
+        }
+    }
+
+    async fn chunk_texts(workspace: &Path) -> Vec<String> {
+        let index_path = crate::config::get_index_path(workspace, "wiki");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        let mut texts: Vec<String> = index
+            .all_chunks()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        texts.sort();
+        texts
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connector_sync_learns_changes_and_drops_deleted_documents() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let server = crate::tests::stub_http::serve_json(route).await;
+        let connectors = crate::connectors::connectors_dir(workspace);
+        std::fs::create_dir_all(&connectors).unwrap();
+        std::fs::write(
+            connectors.join("notion.yaml"),
+            format!("type: notion\nbaseUrl: {}\ntoken: secret\n", server),
+        )
+        .unwrap();
+
+        let stats = crate::learn(workspace, &learn_options(), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 2);
+        assert_eq!(stats.removed_count, 0);
+
+        let sources = crate::rag::SourceManager::new(workspace, "wiki")
+            .list_sources()
+            .unwrap();
+        let runbook = sources
+            .iter()
+            .find(|s| s.path == "https://notion.so/a")
+            .unwrap();
+        assert_eq!(runbook.source_type, SourceType::Url);
+        assert_eq!(runbook.remote_id.as_deref(), Some("notion:a"));
+        assert_eq!(runbook.content_type.as_deref(), Some("markdown"));
+
+        // Nothing changed remotely
+        let stats = crate::learn(workspace, &learn_options(), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 0);
+        assert_eq!(stats.unchanged_count, 2);
+
+        // Page "a" edited, page "b" deleted
+        EDITED.store(true, Ordering::SeqCst);
+        let stats = crate::learn(workspace, &learn_options(), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 1);
+        assert_eq!(stats.unchanged_count, 0);
+        assert_eq!(stats.removed_count, 2);
+
+        let sources = crate::rag::SourceManager::new(workspace, "wiki")
+            .list_sources()
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(
+            sources[0].remote_version.as_deref(),
+            Some("2026-02-01T00:00:00.000Z")
+        );
+
+        let texts = chunk_texts(workspace).await;
+        assert_eq!(texts.len(), 1, "{:?}", texts);
+        assert!(texts[0].contains("rolling deploy"), "{:?}", texts);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_missing_connector_config_fails_before_learning() {
+        let temp = TempDir::new().unwrap();
+        let err = crate::learn(temp.path(), &learn_options(), None)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Failed to read connector 'notion'"),
+            "{}",
+            err
+        );
+        assert!(!crate::config::get_index_path(temp.path(), "wiki").exists());
+    }
+}
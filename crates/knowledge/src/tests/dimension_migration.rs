@@ -0,0 +1,84 @@
+//! Tests for embedding dimension guardrails and `--migrate`.
+
+use crate::tests::fixtures;
+use crate::types::{AskOptions, LearnOptions};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learn_options(docs: &Path, migrate: bool) -> LearnOptions {
+        LearnOptions {
+            migrate,
+            ..fixtures::learn_options("docs", vec![docs.to_path_buf()])
+        }
+    }
+
+    fn ask_options(query: &str) -> AskOptions {
+        AskOptions {
+            base_name: "docs".to_string(),
+            query: query.to_string(),
+            top_k: Some(3),
+            ..Default::default()
+        }
+    }
+
+    /// Change the dimensions in the base config, as a provider switch would.
+    fn set_embedding_dim(workspace: &Path, dimensions: u32) {
+        let mut config = crate::config::load_config(workspace, "docs").unwrap();
+        config.embedding_dim = dimensions;
+        crate::config::save_config(workspace, &config).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dimension_mismatch_requires_migrate() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("lancedb.md");
+        std::fs::write(
+            &docs,
+            "# Storage\n\nLanceDB stores the chunk vectors of every knowledge base on disk.\n",
+        )
+        .unwrap();
+
+        let stats = crate::learn(workspace, &learn_options(&docs, false), None)
+            .await
+            .unwrap();
+        assert!(stats.chunks_count > 0);
+
+        set_embedding_dim(workspace, 256);
+
+        // Both learn and ask stop before touching the index
+        let err = crate::ask(workspace, ask_options("vector storage"), None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--migrate"), "{}", err);
+        let err = crate::learn(workspace, &learn_options(&docs, false), None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("trigram/trigram-v2 produces 256"), "{}", err);
+        assert!(err.contains("--migrate"), "{}", err);
+
+        // Migrating re-embeds the chunks at the new size
+        let migrate = LearnOptions {
+            paths: Vec::new(),
+            ..learn_options(&docs, true)
+        };
+        crate::learn(workspace, &migrate, None).await.unwrap();
+
+        let index_path = crate::config::get_index_path(workspace, "docs");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        assert_eq!(index.dimensions(), 256);
+
+        let result = crate::ask(workspace, ask_options("LanceDB chunk vectors"), None)
+            .await
+            .unwrap();
+        assert!(result.chunks[0].text.contains("LanceDB"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Tests for `learn --dry-run`.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::{InlineText, LearnStage};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dry_run_reports_sources_exclusions_and_failures() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        std::fs::create_dir_all(docs.join("drafts")).unwrap();
+        std::fs::create_dir_all(docs.join("node_modules/pkg")).unwrap();
+        std::fs::write(
+            docs.join("guide.md"),
+            "# Guide\n\nThe office opens at nine every weekday and closes at six.\n",
+        )
+        .unwrap();
+        std::fs::write(docs.join("drafts/wip.md"), "# Draft\n\nNot ready.\n").unwrap();
+        std::fs::write(docs.join("node_modules/pkg/readme.md"), "# Package\n").unwrap();
+        std::fs::write(docs.join("broken.md"), b"# Broken\n\n\xff\xfe\n").unwrap();
+
+        let mut options = learn_options("docs", vec![docs.clone(), workspace.join("missing")]);
+        options.exclude = vec!["drafts/".to_string()];
+        options.texts = vec![InlineText {
+            title: "release-notes".to_string(),
+            text: "Version 2 adds weekend opening hours.".to_string(),
+            ..Default::default()
+        }];
+
+        let report = crate::dry_run(workspace, &options, None, crate::ProgressReporter::noop())
+            .await
+            .unwrap();
+
+        assert_eq!(report.sources_count, 2);
+        assert_eq!(report.chunks_count, 2);
+        assert!(report.bytes_processed > 0);
+        assert!(report.content.tokens_estimate > 0);
+        assert_eq!(report.provider, "trigram");
+        assert!(report.local_provider);
+        assert!(report.embed_secs_estimate.is_some());
+        assert!(report.embed_estimate_error.is_none());
+
+        let reason = |name: &str| {
+            report
+                .excluded
+                .iter()
+                .find(|file| file.path.ends_with(name))
+                .map(|file| file.reason.as_str())
+        };
+        assert_eq!(reason("drafts/wip.md"), Some("exclude pattern 'drafts/'"));
+        assert_eq!(
+            reason("node_modules/pkg/readme.md"),
+            Some("default pattern '/node_modules/'")
+        );
+        assert_eq!(reason("missing"), Some("not found"));
+        assert_eq!(reason("guide.md"), None);
+
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].source.ends_with("broken.md"));
+        assert_eq!(report.errors[0].stage, LearnStage::Parse);
+
+        // Nothing of the base was written
+        assert!(!crate::config::get_base_dir(workspace, "docs").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dry_run_rejects_remote_sources() {
+        let temp = TempDir::new().unwrap();
+        let mut options = learn_options("docs", Vec::new());
+        options.urls = vec!["https://example.com/docs".to_string()];
+
+        let result =
+            crate::dry_run(temp.path(), &options, None, crate::ProgressReporter::noop()).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Resilience tests of learn: faults injected into the embedding provider
+//! and the index must never leave chunks without a source record.
+
+use crate::faults::{self, Fault, FaultPoint};
+use crate::rag::SourceManager;
+use crate::tests::fixtures::learn_options;
+use crate::types::{InlineText, LearnOptions, LearnStage};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(workspace: &Path) -> Vec<PathBuf> {
+        let hours = workspace.join("hours.md");
+        std::fs::write(&hours, "The office opens at nine every weekday.\n").unwrap();
+        let parking = workspace.join("parking.md");
+        std::fs::write(
+            &parking,
+            "Visitors park on the second level of the garage.\n",
+        )
+        .unwrap();
+        vec![hours, parking]
+    }
+
+    /// Sources recorded in sources.jsonl and chunks in the index.
+    async fn recorded(workspace: &Path) -> (usize, u32, u32) {
+        let sources = SourceManager::new(workspace, "docs")
+            .list_sources()
+            .unwrap();
+        let source_chunks = sources.iter().map(|s| s.chunk_count).sum();
+        let stats = crate::stats(workspace, "docs").await.unwrap();
+        (sources.len(), source_chunks, stats.chunks_count)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_embedding_reports_the_batch_and_writes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = write_files(workspace);
+
+        let fault = faults::inject(workspace, Fault::new(FaultPoint::Embed));
+        let stats = crate::learn(workspace, &learn_options("docs", files.clone()), None)
+            .await
+            .unwrap();
+        assert!(fault.calls() > 0);
+        drop(fault);
+
+        assert_eq!((stats.sources_count, stats.chunks_count), (0, 0));
+        assert_eq!(stats.errors.len(), 2);
+        assert!(stats.errors.iter().all(|e| e.stage == LearnStage::Embed));
+        assert!(stats.errors[0].message.contains("Injected fault at embed"));
+        assert_eq!(recorded(workspace).await, (0, 0, 0));
+
+        // Learning again once the provider is back picks both files up
+        let stats = crate::learn(workspace, &learn_options("docs", files), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 2);
+        assert!(stats.errors.is_empty());
+        let (sources, source_chunks, index_chunks) = recorded(workspace).await;
+        assert_eq!(sources, 2);
+        assert_eq!(source_chunks, index_chunks);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_source_tracking_rolls_back_indexed_chunks() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = write_files(workspace);
+
+        // The chunks are inserted and the first source is recorded before
+        // the second fails
+        let _fault = faults::inject(workspace, Fault::new(FaultPoint::TrackSource).after(1));
+        let stats = crate::learn(workspace, &learn_options("docs", files), None)
+            .await
+            .unwrap();
+
+        assert_eq!(stats.sources_count, 0);
+        assert_eq!(stats.errors.len(), 2);
+        assert!(stats.errors[1]
+            .message
+            .contains("Injected fault at track source"));
+        assert_eq!(recorded(workspace).await, (0, 0, 0));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_inline_batch_keeps_the_learned_files() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = write_files(workspace);
+        let options = LearnOptions {
+            texts: vec![InlineText {
+                title: "lunch.md".to_string(),
+                text: "Lunch is served in the cafeteria from noon.".to_string(),
+                ..Default::default()
+            }],
+            ..learn_options("docs", files)
+        };
+
+        // The files' batch is written; the inline text's batch fails and is
+        // reported without losing the files
+        let fault = faults::inject(workspace, Fault::new(FaultPoint::IndexWrite).after(1));
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(fault.calls(), 2);
+        drop(fault);
+        assert_eq!(stats.sources_count, 2);
+        assert_eq!(stats.errors.len(), 1);
+        assert_eq!(stats.errors[0].source, "lunch.md");
+        assert_eq!(stats.errors[0].stage, LearnStage::Embed);
+        assert!(stats.errors[0]
+            .message
+            .contains("Injected fault at index write"));
+
+        let (sources, source_chunks, index_chunks) = recorded(workspace).await;
+        assert_eq!(sources, 2);
+        assert_eq!(source_chunks, index_chunks);
+
+        // Learning the text again once the index is back picks it up
+        let retried = crate::learn(
+            workspace,
+            &LearnOptions {
+                paths: Vec::new(),
+                ..options
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(retried.sources_count, 1);
+        let (sources, source_chunks, index_chunks) = recorded(workspace).await;
+        assert_eq!(sources, 3);
+        assert_eq!(source_chunks, index_chunks);
+    }
+}
@@ -0,0 +1,56 @@
+//! Tests for rating answers (`feedback --last`) and the feedback report.
+
+use crate::rag::feedback::{self, Verdict};
+use crate::tests::fixtures;
+use crate::types::AskOptions;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn learn(workspace: &Path) {
+        let api = "# API\n\nClients call the v1 endpoint for invoices.\n";
+        fixtures::learn_file(workspace, "api", "api.md", api).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_last_answer_is_rated_and_reported() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+
+        let err = feedback::rate_last_answer(workspace, "api", Verdict::Helpful, None).unwrap_err();
+        assert!(err.to_string().contains("guided knowledge ask"), "{}", err);
+
+        let options = AskOptions {
+            base_name: "api".to_string(),
+            query: "Which endpoint do clients call for invoices?".to_string(),
+            top_k: Some(3),
+            provider_configs: fixtures::ollama(|_request| "Use the v1 endpoint.".to_string()).await,
+            ..Default::default()
+        };
+        crate::rag::ask::ask_rag(workspace, options, "ollama", None)
+            .await
+            .unwrap();
+
+        let entry = feedback::rate_last_answer(
+            workspace,
+            "api",
+            Verdict::NotHelpful,
+            Some("v1 is deprecated ".to_string()),
+        )
+        .unwrap();
+        assert_eq!(entry.note.as_deref(), Some("v1 is deprecated"));
+        assert!(!entry.answer.chunk_ids.is_empty());
+        assert!(entry.answer.sources[0].ends_with("api.md"));
+
+        let entries = feedback::list_feedback(workspace, "api").unwrap();
+        assert_eq!(entries, vec![entry]);
+        let report = feedback::feedback_report(workspace, "api").unwrap();
+        assert_eq!((report.helpful, report.not_helpful), (0, 1));
+        // A single rating says nothing about a source yet
+        assert!(report.unhelpful_sources.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! Tests for feed subscriptions (`learn --feed`, `refresh`).
+
+use crate::tests::fixtures;
+use crate::tests::stub_http::{self, Reply};
+use crate::types::{LearnOptions, RefreshOptions, SourceType};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the stub feed has published its second release.
+    static RELEASED: AtomicBool = AtomicBool::new(false);
+
+    const ITEM_1: &str = r#"<item>
+      <title>v1.0</title>
+      <link>https://acme.dev/releases/1.0</link>
+      <guid>1.0</guid>
+      <pubDate>Mon, 05 Oct 2026 09:00:00 GMT</pubDate>
+      <description>First stable release with CSV import.</description>
+    </item>"#;
+
+    const ITEM_2: &str = r#"<item>
+      <title>v1.1</title>
+      <link>https://acme.dev/releases/1.1</link>
+      <guid>1.1</guid>
+      <pubDate>Mon, 12 Oct 2026 09:00:00 GMT</pubDate>
+      <description>Adds parquet export and drops Python 3.8.</description>
+    </item>"#;
+
+    fn feed(_request: &str) -> Reply {
+        let items = if RELEASED.load(Ordering::SeqCst) {
+            format!("{}{}", ITEM_2, ITEM_1)
+        } else {
+            ITEM_1.to_string()
+        };
+        Reply::ok(
+            "application/rss+xml",
+            format!(
+                "<rss version=\"2.0\"><channel><title>Acme</title>{}</channel></rss>",
+                items
+            ),
+        )
+    }
+
+    fn learn_options(feed: String) -> LearnOptions {
+        LearnOptions {
+            feeds: vec![feed],
+            namespace: Some("upstream".to_string()),
+            ..fixtures::learn_options("releases", Vec::new())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_refresh_learns_only_new_entries() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let server = stub_http::serve(feed).await;
+        let url = format!("{}/releases.xml", server);
+
+        let stats = crate::learn(workspace, &learn_options(url.clone()), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 1);
+
+        let config = crate::config::load_config(workspace, "releases").unwrap();
+        assert_eq!(config.feeds.len(), 1);
+        assert_eq!(config.feeds[0].url, url);
+        assert_eq!(config.feeds[0].namespace.as_deref(), Some("upstream"));
+        assert!(config.feeds[0].last_synced_at.is_some());
+
+        RELEASED.store(true, Ordering::SeqCst);
+        let options = RefreshOptions {
+            base_name: "releases".to_string(),
+            ..Default::default()
+        };
+        let stats = crate::refresh(workspace, &options).await.unwrap();
+        assert_eq!(stats.sources_count, 1);
+        assert_eq!(stats.unchanged_count, 1);
+
+        let sources = crate::rag::SourceManager::new(workspace, "releases")
+            .list_sources()
+            .unwrap();
+        let mut paths: Vec<&str> = sources.iter().map(|s| s.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "https://acme.dev/releases/1.0",
+                "https://acme.dev/releases/1.1"
+            ]
+        );
+        assert!(sources.iter().all(|s| s.source_type == SourceType::Feed));
+        assert!(sources
+            .iter()
+            .all(|s| s.namespace.as_deref() == Some("upstream")));
+
+        let index_path = crate::config::get_index_path(workspace, "releases");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        let chunks = index.all_chunks().await.unwrap();
+        let release = chunks
+            .iter()
+            .find(|c| c.text.contains("parquet export"))
+            .unwrap();
+        assert!(release.text.contains("_Published 2026-10-12_"));
+        assert_eq!(
+            release.metadata["custom"]["published_at"],
+            "2026-10-12T09:00:00+00:00"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_refresh_without_feeds_fails() {
+        let temp = TempDir::new().unwrap();
+        let options = RefreshOptions {
+            base_name: "releases".to_string(),
+            ..Default::default()
+        };
+        let err = crate::refresh(temp.path(), &options).await.unwrap_err();
+        assert!(err.to_string().contains("has no feeds"), "{}", err);
+    }
+}
@@ -0,0 +1,71 @@
+//! Learn options and LLM stand-ins shared by the knowledge tests.
+
+use crate::tests::stub_http::{self, Reply};
+use crate::types::{InlineText, LearnOptions};
+use guided_core::config::ProviderConfig;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Options to learn `paths` into `base` with the trigram embedder, which
+/// needs no model download or network.
+pub(crate) fn learn_options(base: &str, paths: Vec<PathBuf>) -> LearnOptions {
+    LearnOptions {
+        base_name: base.to_string(),
+        paths,
+        provider: Some("trigram".to_string()),
+        model: Some("trigram-v2".to_string()),
+        ..Default::default()
+    }
+}
+
+/// [`learn_options`] for one inline text instead of paths.
+pub(crate) fn learn_text(base: &str, title: &str, text: &str) -> LearnOptions {
+    LearnOptions {
+        texts: vec![InlineText {
+            title: title.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }],
+        ..learn_options(base, Vec::new())
+    }
+}
+
+/// Write `text` to `file` in `workspace` and learn it into `base`.
+pub(crate) async fn learn_file(workspace: &Path, base: &str, file: &str, text: &str) {
+    let path = workspace.join(file);
+    std::fs::write(&path, text).unwrap();
+    crate::learn(workspace, &learn_options(base, vec![path]), None)
+        .await
+        .unwrap();
+}
+
+/// Provider configs with Ollama (model llama3) at `endpoint`.
+pub(crate) fn ollama_at(endpoint: String) -> HashMap<String, ProviderConfig> {
+    let mut provider_configs = HashMap::new();
+    provider_configs.insert(
+        "ollama".to_string(),
+        ProviderConfig::Ollama {
+            endpoint,
+            model: "llama3".to_string(),
+            embedding_model: None,
+            timeout: None,
+        },
+    );
+    provider_configs
+}
+
+/// Provider configs with a stand-in for Ollama that answers each request
+/// (head and body) with the text `respond` gives for it.
+pub(crate) async fn ollama(
+    respond: impl Fn(&str) -> String + Send + Sync + 'static,
+) -> HashMap<String, ProviderConfig> {
+    let endpoint = stub_http::serve(move |request| {
+        Reply::ok(
+            "application/json",
+            serde_json::json!({"model": "llama3", "response": respond(request), "done": true})
+                .to_string(),
+        )
+    })
+    .await;
+    ollama_at(endpoint)
+}
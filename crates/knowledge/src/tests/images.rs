@@ -0,0 +1,96 @@
+//! Tests for learning diagrams and screenshots (`--images`).
+
+use crate::images::ImageReader;
+use crate::tests::fixtures;
+use crate::tests::stub_http::{self, Reply};
+use crate::types::LearnOptions;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vision_model(_request: &str) -> Reply {
+        Reply::ok(
+            "application/json",
+            r#"{"response":"Deployment diagram: the load balancer sends traffic to two web nodes backed by Postgres.","done":true}"#,
+        )
+    }
+
+    fn write_docs(dir: &Path) {
+        std::fs::create_dir_all(dir.join("arch")).unwrap();
+        std::fs::write(dir.join("guide.md"), "# Guide\n\nRead the diagrams.\n").unwrap();
+        std::fs::write(dir.join("arch/deploy.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        std::fs::write(
+            dir.join("arch/queue.svg"),
+            r#"<svg><text x="1" y="1">Order queue</text><text x="1" y="9">Invoice worker</text></svg>"#,
+        )
+        .unwrap();
+    }
+
+    fn learn_options(docs: PathBuf, images: Option<ImageReader>, endpoint: String) -> LearnOptions {
+        LearnOptions {
+            images,
+            provider_configs: fixtures::ollama_at(endpoint),
+            ..fixtures::learn_options("docs", vec![docs])
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_images_are_learned_through_descriptions() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        write_docs(&docs);
+        let server = stub_http::serve(vision_model).await;
+
+        let reader = ImageReader::Ollama {
+            model: "llava".to_string(),
+        };
+        let options = learn_options(docs, Some(reader), server);
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(stats.sources_count, 3);
+
+        let sources = crate::rag::SourceManager::new(workspace, "docs")
+            .list_sources()
+            .unwrap();
+        let png = sources
+            .iter()
+            .find(|s| s.path.ends_with("arch/deploy.png"))
+            .unwrap();
+        assert_eq!(png.content_type.as_deref(), Some("image"));
+
+        let index_path = crate::config::get_index_path(workspace, "docs");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        let chunks = index.all_chunks().await.unwrap();
+
+        let diagram = chunks
+            .iter()
+            .find(|c| c.text.contains("load balancer"))
+            .unwrap();
+        assert!(diagram.text.starts_with("# deploy.png"), "{}", diagram.text);
+        assert_eq!(diagram.metadata["custom"]["image_path"], png.path.as_str());
+        assert_eq!(diagram.metadata["custom"]["image_reader"], "ollama");
+
+        let svg = chunks
+            .iter()
+            .find(|c| c.text.contains("Invoice worker"))
+            .unwrap();
+        assert!(svg.text.contains("Order queue"), "{}", svg.text);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_images_are_skipped_without_a_reader() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        write_docs(&docs);
+
+        let options = learn_options(docs, None, "http://127.0.0.1:9".to_string());
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(stats.sources_count, 1);
+    }
+}
@@ -0,0 +1,87 @@
+//! Tests for learning text given inline (`--stdin`, `--text`).
+
+use crate::tests::fixtures;
+use crate::types::{AskOptions, InlineText, LearnOptions, SourceType};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learn_options(texts: Vec<InlineText>) -> LearnOptions {
+        LearnOptions {
+            texts,
+            ..fixtures::learn_options("notes", Vec::new())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_inline_text_becomes_a_tagged_source() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+
+        let texts = vec![
+            InlineText {
+                title: "release-notes".to_string(),
+                text: "Version 2.4 adds invoice exports and fixes login timeouts.".to_string(),
+                tags: vec!["release".to_string()],
+                ..Default::default()
+            },
+            InlineText {
+                title: "meeting.md".to_string(),
+                text: "# Planning\n\nWe agreed to ship invoice exports in March.".to_string(),
+                tags: Vec::new(),
+                ..Default::default()
+            },
+        ];
+        let stats = crate::learn(workspace, &learn_options(texts), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_count, 2);
+
+        let sources = crate::rag::SourceManager::new(workspace, "notes")
+            .list_sources()
+            .unwrap();
+        assert!(sources.iter().all(|s| s.source_type == SourceType::Text));
+        assert_eq!(sources[0].path, "release-notes");
+        assert_eq!(sources[0].content_type.as_deref(), Some("text"));
+        assert_eq!(sources[0].added_tags, vec!["release"]);
+        assert_eq!(sources[1].content_type.as_deref(), Some("markdown"));
+
+        let result = crate::ask(
+            workspace,
+            AskOptions {
+                base_name: "notes".to_string(),
+                query: "invoice exports release".to_string(),
+                top_k: Some(5),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let notes = result
+            .chunks
+            .iter()
+            .find(|c| c.metadata["custom"]["source_path"] == "release-notes")
+            .unwrap();
+        let tags = notes.metadata["custom"]["tags"].as_array().unwrap();
+        assert!(tags.contains(&serde_json::json!("release")));
+        assert_eq!(notes.metadata["custom"]["file_size_bytes"], 58);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_empty_inline_text_is_rejected() {
+        let temp = TempDir::new().unwrap();
+        let texts = vec![InlineText {
+            title: "empty".to_string(),
+            text: " \n".to_string(),
+            tags: Vec::new(),
+            ..Default::default()
+        }];
+        let err = crate::learn(temp.path(), &learn_options(texts), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No text to learn"), "{}", err);
+    }
+}
@@ -0,0 +1,117 @@
+//! Tests for the size and token accounting of learn and stats, and the
+//! batches learn embeds and inserts chunks in.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::LearnStage;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_learn_and_stats_count_tokens_and_chunks_by_type() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let guide = workspace.join("guide.md");
+        std::fs::write(
+            &guide,
+            "# Guide\n\nThe office opens at nine every weekday and closes at six.\n",
+        )
+        .unwrap();
+        let code = workspace.join("hours.rs");
+        std::fs::write(
+            &code,
+            "/// Opening hour of the office.\npub fn opening_hour() -> u32 {\n    9\n}\n",
+        )
+        .unwrap();
+
+        let learned = crate::learn(
+            workspace,
+            &learn_options("docs", vec![guide.clone(), code]),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(learned.provider, "trigram");
+        assert_eq!(learned.model, "trigram-v2");
+        let by_type = &learned.content.chunks_by_type;
+        assert!(by_type["markdown"] > 0, "{:?}", by_type);
+        assert!(by_type["code"] > 0, "{:?}", by_type);
+        assert_eq!(by_type.values().sum::<u32>(), learned.chunks_count);
+        assert!(learned.content.tokens_estimate > 0);
+        assert!(learned.content.avg_chunk_tokens() > 0);
+
+        let stats = crate::stats(workspace, "docs").await.unwrap();
+        assert_eq!(stats.sources_count, 2);
+        assert_eq!(stats.content, learned.content);
+        assert_eq!(stats.provider, "trigram");
+        assert_eq!(stats.model, "trigram-v2");
+
+        // A re-learned file is counted once, from its latest record
+        std::fs::write(
+            &guide,
+            "# Guide\n\nThe office opens at ten on Mondays and at nine on other weekdays.\n",
+        )
+        .unwrap();
+        crate::learn(workspace, &learn_options("docs", vec![guide]), None)
+            .await
+            .unwrap();
+        let stats = crate::stats(workspace, "docs").await.unwrap();
+        assert_eq!(stats.sources_count, 2);
+        assert_eq!(stats.content.chunks_by_type, learned.content.chunks_by_type);
+        assert!(stats.content.tokens_estimate > learned.content.tokens_estimate);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_large_batch_is_inserted_in_sub_batches() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let handbook = workspace.join("handbook.md");
+        std::fs::write(&handbook, crate::bench::markdown_corpus(1_200)).unwrap();
+        let notes = workspace.join("notes.md");
+        std::fs::write(&notes, "# Notes\n\nThe office opens at nine.\n").unwrap();
+
+        let learned = crate::learn(
+            workspace,
+            &learn_options("docs", vec![handbook, notes]),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(learned.chunks_count > 500, "{}", learned.chunks_count);
+
+        let stats = crate::stats(workspace, "docs").await.unwrap();
+        assert_eq!(stats.sources_count, 2);
+        assert_eq!(stats.chunks_count, learned.chunks_count);
+        let sources = crate::rag::SourceManager::new(workspace, "docs")
+            .list_sources()
+            .unwrap();
+        let chunk_counts: u32 = sources.iter().map(|s| s.chunk_count).sum();
+        assert_eq!(chunk_counts, learned.chunks_count);
+        assert!(sources.iter().all(|s| s.token_count.unwrap_or(0) > 0));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_learn_reports_files_it_could_not_read() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let guide = workspace.join("guide.md");
+        std::fs::write(&guide, "# Guide\n\nThe office opens at nine.\n").unwrap();
+        let broken = workspace.join("broken.md");
+        std::fs::write(&broken, [b'#', b' ', 0xff, 0xfe, b'\n']).unwrap();
+
+        let learned = crate::learn(workspace, &learn_options("docs", vec![broken, guide]), None)
+            .await
+            .unwrap();
+        assert_eq!(learned.sources_count, 1);
+        assert_eq!(learned.errors.len(), 1);
+        let error = &learned.errors[0];
+        assert!(error.source.ends_with("broken.md"), "{:?}", error);
+        assert_eq!(error.stage, LearnStage::Parse);
+        assert!(error.message.contains("Failed to read"), "{:?}", error);
+
+        let json = serde_json::to_value(&learned.errors).unwrap();
+        assert_eq!(json[0]["stage"], "parse");
+    }
+}
@@ -0,0 +1,139 @@
+//! Tests for merging knowledge bases.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::MergeOptions;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_options(target: &str, sources: &[&str]) -> MergeOptions {
+        MergeOptions {
+            target: target.to_string(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            scope: None,
+        }
+    }
+
+    fn write(workspace: &Path, name: &str, text: &str) -> PathBuf {
+        let path = workspace.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_copies_sources_and_skips_duplicate_content() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let billing = write(
+            workspace,
+            "billing.md",
+            "# Billing\n\nInvoices are sent on the first day of every month.\n",
+        );
+        let shipping = write(
+            workspace,
+            "shipping.md",
+            "# Shipping\n\nParcels leave the warehouse within two days.\n",
+        );
+        let handbook = write(
+            workspace,
+            "handbook.md",
+            "# Handbook\n\nEveryone starts with a week of onboarding.\n",
+        );
+
+        crate::learn(
+            workspace,
+            &learn_options("billing", vec![billing, handbook.clone()]),
+            None,
+        )
+        .await
+        .unwrap();
+        crate::learn(
+            workspace,
+            &learn_options("shipping", vec![shipping, handbook]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let stats = crate::merge(
+            workspace,
+            &merge_options("company", &["billing", "shipping"]),
+        )
+        .await
+        .unwrap();
+        assert!(stats.created);
+        assert_eq!(stats.sources_added, 3);
+        assert_eq!(stats.duplicate_sources, 1);
+        assert!(stats.duplicate_chunks > 0);
+
+        // The new base takes over the embedding settings of the first one
+        let config = crate::config::load_config(workspace, "company").unwrap();
+        assert_eq!(config.provider, "trigram");
+        assert_eq!(config.model, "trigram-v2");
+
+        let base_stats = crate::stats(workspace, "company").await.unwrap();
+        assert_eq!(base_stats.chunks_count, stats.chunks_added);
+        let mut paths: Vec<String> = crate::rag::SourceManager::new(workspace, "company")
+            .list_sources()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.path.rsplit('/').next().unwrap().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["billing.md", "handbook.md", "shipping.md"]);
+
+        // Merging again adds nothing
+        let again = crate::merge(workspace, &merge_options("company", &["billing"]))
+            .await
+            .unwrap();
+        assert!(!again.created);
+        assert_eq!(again.sources_added, 0);
+        assert_eq!(again.duplicate_sources, 2);
+        assert_eq!(
+            crate::stats(workspace, "company")
+                .await
+                .unwrap()
+                .chunks_count,
+            base_stats.chunks_count
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_rejects_incompatible_bases() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let notes = write(
+            workspace,
+            "notes.md",
+            "# Notes\n\nThe office opens at nine.\n",
+        );
+        crate::learn(workspace, &learn_options("one", vec![notes.clone()]), None)
+            .await
+            .unwrap();
+        crate::learn(workspace, &learn_options("two", vec![notes]), None)
+            .await
+            .unwrap();
+
+        let mut two = crate::config::load_config(workspace, "two").unwrap();
+        two.model = "nomic-embed-text".to_string();
+        crate::config::save_config(workspace, &two).unwrap();
+
+        let err = crate::merge(workspace, &merge_options("all", &["one", "two"]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("trigram/trigram-v2"), "{}", err);
+        // Nothing is created for a merge that cannot happen
+        assert!(!crate::config::get_config_path(workspace, "all").exists());
+
+        let err = crate::merge(workspace, &merge_options("one", &["one"]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("into itself"), "{}", err);
+        assert!(crate::merge(workspace, &merge_options("one", &["missing"]))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Tests for retrieval mixing code and docs (`AskOptions::mix`).
+
+use crate::tests::fixtures::learn_options;
+use crate::types::AskOptions;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask_options(mix: &str) -> AskOptions {
+        AskOptions {
+            base_name: "kb".to_string(),
+            query: "how are the monthly invoices generated".to_string(),
+            top_k: Some(10),
+            mix: Some(mix.parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_mix_interleaves_code_and_docs() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = [
+            (
+                "invoices.md",
+                "# Invoices\n\nThe monthly invoices are generated on the first day.\n",
+            ),
+            (
+                "billing.md",
+                "# Billing\n\nInvoices are generated monthly and mailed to customers.\n",
+            ),
+            (
+                "faq.md",
+                "# FAQ\n\nHow are invoices generated? Monthly, by the billing job.\n",
+            ),
+            (
+                "invoices.rs",
+                "/// Generates the monthly invoices.\nfn generate_invoices() {}\n",
+            ),
+        ];
+        let paths = files
+            .iter()
+            .map(|(name, text)| {
+                let path = workspace.join(name);
+                std::fs::write(&path, text).unwrap();
+                path
+            })
+            .collect();
+        crate::learn(workspace, &learn_options("kb", paths), None)
+            .await
+            .unwrap();
+
+        let file_types = |result: crate::AskResult| -> Vec<String> {
+            result
+                .chunks
+                .iter()
+                .map(|c| {
+                    c.metadata_values("file_type")
+                        .find_map(|v| v.as_str())
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let result = crate::ask(workspace, ask_options("code:2,docs:2"), None)
+            .await
+            .unwrap();
+        // Only one code chunk exists; the docs keep their share
+        assert_eq!(file_types(result), vec!["code", "markdown", "markdown"]);
+
+        let result = crate::ask(workspace, ask_options("docs:2,code:1"), None)
+            .await
+            .unwrap();
+        assert_eq!(file_types(result), vec!["markdown", "code", "markdown"]);
+    }
+}
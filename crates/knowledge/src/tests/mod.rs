@@ -1 +1,35 @@
+mod answer_cache;
+mod answer_streaming;
+mod base_handle;
+mod best_of;
+mod clone_rename;
+mod connector_sync;
+mod dimension_migration;
+mod dry_run;
+mod fault_injection;
+mod feedback;
+mod feeds;
+pub(crate) mod fixtures;
+mod guardrails;
+mod images;
+mod inline_text;
+mod learn_stats;
+mod merge;
+mod mixed_retrieval;
+mod namespaces;
+mod path_handling;
+mod prompt_context;
 mod rag_ranking;
+mod relearn;
+mod retrieval_depth;
+mod search_steering;
+mod source_tagging;
+pub(crate) mod stub_http;
+mod summaries;
+mod symbols;
+mod title_embeddings;
+mod transcripts;
+mod tune;
+mod verification;
+mod warm_start;
+mod web_pages;
@@ -0,0 +1,99 @@
+//! Tests for namespaces within a knowledge base.
+
+use crate::tests::fixtures;
+use crate::types::{AskOptions, LearnOptions};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learn_options(file: &Path, namespace: Option<&str>, reset: bool) -> LearnOptions {
+        LearnOptions {
+            namespace: namespace.map(str::to_string),
+            reset,
+            ..fixtures::learn_options("kb", vec![file.to_path_buf()])
+        }
+    }
+
+    fn ask_options(namespace: Option<&str>) -> AskOptions {
+        AskOptions {
+            base_name: "kb".to_string(),
+            query: "how are invoices generated".to_string(),
+            top_k: Some(10),
+            namespace: namespace.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    async fn namespaces_found(workspace: &Path, namespace: Option<&str>) -> Vec<String> {
+        let result = crate::ask(workspace, ask_options(namespace), None)
+            .await
+            .unwrap();
+        let mut found: Vec<String> = result
+            .chunks
+            .iter()
+            .map(|c| c.metadata["namespace"].as_str().unwrap().to_string())
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespaces_are_searched_and_reset_separately() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("invoices.md");
+        std::fs::write(
+            &docs,
+            "# Invoices\n\nInvoices are generated on the first day of every month.\n",
+        )
+        .unwrap();
+        let code = workspace.join("invoices.rs");
+        std::fs::write(
+            &code,
+            "/// Generates the invoices of every month.\nfn generate_invoices() {}\n",
+        )
+        .unwrap();
+
+        crate::learn(workspace, &learn_options(&docs, Some("docs/"), false), None)
+            .await
+            .unwrap();
+        crate::learn(workspace, &learn_options(&code, Some("code"), false), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            namespaces_found(workspace, None).await,
+            vec!["code", "docs"]
+        );
+        assert_eq!(
+            namespaces_found(workspace, Some("docs")).await,
+            vec!["docs"]
+        );
+        assert_eq!(
+            namespaces_found(workspace, Some("code")).await,
+            vec!["code"]
+        );
+
+        // Resetting one namespace keeps the other
+        crate::learn(workspace, &learn_options(&code, Some("code"), true), None)
+            .await
+            .unwrap();
+        crate::clean(workspace, "kb", Some("docs")).await.unwrap();
+        assert_eq!(namespaces_found(workspace, None).await, vec!["code"]);
+
+        let sources = crate::rag::SourceManager::new(workspace, "kb")
+            .list_sources()
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].namespace.as_deref(), Some("code"));
+
+        let err = crate::ask(workspace, ask_options(Some("it's")), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid namespace"), "{}", err);
+    }
+}
@@ -0,0 +1,191 @@
+//! Tests for path normalization and exclusion rules in file discovery.
+
+use crate::ignore::IgnoreRules;
+use crate::types::{KnowledgeBaseConfig, LearnOptions};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Whether discovery learns `path`, without ignore files.
+fn should_include(path: &Path, options: &LearnOptions) -> bool {
+    let relative = crate::paths::normalize(path);
+    crate::exclusion_reason(path, &relative, options, &IgnoreRules::default()).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(include: &[&str], exclude: &[&str]) -> LearnOptions {
+        LearnOptions {
+            base_name: "docs".to_string(),
+            include: include.iter().map(|p| p.to_string()).collect(),
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_excludes() {
+        let options = options(&[], &[]);
+        assert!(!should_include(
+            Path::new("app/node_modules/x/index.js"),
+            &options
+        ));
+        assert!(!should_include(Path::new("./.git/config"), &options));
+        assert!(should_include(Path::new("docs/guide.md"), &options));
+    }
+
+    #[test]
+    fn test_user_patterns() {
+        let options = options(&["docs/"], &["/drafts/"]);
+        assert!(should_include(Path::new("./docs/guide.md"), &options));
+        assert!(!should_include(Path::new("./docs/drafts/wip.md"), &options));
+        assert!(!should_include(Path::new("./src/main.rs"), &options));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_paths_match_slash_patterns() {
+        let options = options(&[r"docs\"], &["/drafts/"]);
+        assert!(!should_include(
+            Path::new(r"C:\app\node_modules\x\index.js"),
+            &options
+        ));
+        assert!(should_include(Path::new(r"C:\app\docs\guide.md"), &options));
+        assert!(!should_include(
+            Path::new(r"C:\app\docs\drafts\wip.md"),
+            &options
+        ));
+    }
+
+    #[test]
+    fn test_default_excludes_apply_below_the_workspace() {
+        // A workspace under `/tmp/.tmpXXXX/build/.cache`: only `target/`
+        // is skipped
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path().join("build").join(".cache");
+        let docs = workspace.join("docs");
+        std::fs::create_dir_all(docs.join("target")).unwrap();
+        std::fs::write(docs.join("guide.md"), "# Guide\n").unwrap();
+        std::fs::write(docs.join("target").join("out.md"), "# Out\n").unwrap();
+
+        let mut options = options(&[], &[]);
+        options.paths = vec![docs.clone()];
+        let (files, excluded) = crate::discover_files(&workspace, &options).unwrap();
+        assert_eq!(files, vec![docs.join("guide.md")]);
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].reason, "default pattern '/target/'");
+    }
+
+    #[test]
+    fn test_ignore_file_and_base_config_patterns() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        for dir in ["vendor/lib", "generated", "drafts"] {
+            std::fs::create_dir_all(docs.join(dir)).unwrap();
+        }
+        for file in [
+            "guide.md",
+            "vendor/lib/README.md",
+            "generated/api.md",
+            "drafts/wip.md",
+            "notes.log",
+        ] {
+            std::fs::write(docs.join(file), "# Doc\n").unwrap();
+        }
+        std::fs::create_dir_all(workspace.join(".guided")).unwrap();
+        std::fs::write(
+            crate::ignore::workspace_ignore_path(workspace),
+            "# Index vendored docs, but not generated ones\n!vendor/\n/docs/generated/\n",
+        )
+        .unwrap();
+
+        let config = KnowledgeBaseConfig {
+            exclude: vec!["/drafts/".to_string()],
+            ..Default::default()
+        };
+        let mut options = options(&[], &[]);
+        options.paths = vec![docs.clone()];
+        let options = crate::with_config_patterns(&options, &config);
+        let (files, excluded) = crate::discover_files(workspace, &options).unwrap();
+
+        let mut learned: Vec<String> = files
+            .iter()
+            .map(|file| crate::paths::normalize(file.strip_prefix(&docs).unwrap()))
+            .collect();
+        learned.sort();
+        assert_eq!(learned, vec!["guide.md", "vendor/lib/README.md"]);
+
+        let reason = |name: &str| {
+            excluded
+                .iter()
+                .find(|file| file.path.ends_with(name))
+                .map(|file| file.reason.clone())
+        };
+        assert_eq!(
+            reason("generated/api.md").as_deref(),
+            Some(".guided/ignore '/docs/generated/'")
+        );
+        assert_eq!(
+            reason("drafts/wip.md").as_deref(),
+            Some("exclude pattern '/drafts/'")
+        );
+        assert_eq!(
+            reason("notes.log").as_deref(),
+            Some("default pattern '.log'")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links_learns_each_file_once() {
+        let temp = TempDir::new().unwrap();
+        let shared = temp.path().join("shared");
+        let docs = temp.path().join("docs");
+        std::fs::create_dir_all(shared.join("deep")).unwrap();
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(shared.join("style.md"), "# Style\n").unwrap();
+        std::fs::write(shared.join("deep/notes.md"), "# Notes\n").unwrap();
+        std::fs::write(docs.join("guide.md"), "# Guide\n").unwrap();
+        std::os::unix::fs::symlink(&shared, docs.join("shared")).unwrap();
+        std::os::unix::fs::symlink(&shared, docs.join("shared-again")).unwrap();
+        // A cycle back up to the docs
+        std::os::unix::fs::symlink(&docs, shared.join("back")).unwrap();
+
+        let learned = |options: &LearnOptions| {
+            let (files, excluded) = crate::discover_files(temp.path(), options).unwrap();
+            let mut files: Vec<String> = files
+                .iter()
+                .map(|file| crate::paths::normalize(file.strip_prefix(&docs).unwrap()))
+                .collect();
+            files.sort();
+            (files, excluded)
+        };
+
+        let mut options = options(&[], &[]);
+        options.paths = vec![docs.clone()];
+        let (files, excluded) = learned(&options);
+        assert_eq!(files, vec!["guide.md"]);
+        assert!(excluded
+            .iter()
+            .all(|file| file.reason == "link to a directory (no --follow-links)"));
+
+        options.discovery.follow_links = true;
+        let (files, excluded) = learned(&options);
+        assert_eq!(files.len(), 3, "{:?}", files);
+        assert!(files.contains(&"guide.md".to_string()));
+        assert_eq!(
+            files
+                .iter()
+                .filter(|file| file.ends_with("style.md"))
+                .count(),
+            1
+        );
+        assert!(excluded.is_empty(), "{:?}", excluded);
+
+        options.discovery.max_depth = Some(0);
+        let (files, _) = learned(&options);
+        assert_eq!(files, vec!["guide.md"]);
+    }
+}
@@ -0,0 +1,50 @@
+//! Tests for the prompt context built from learned chunks.
+
+use crate::rag::context::build_context;
+use crate::types::{AskOptions, LearnOptions};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_default_learn_introduces_chunks_by_heading_path() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("guide.md");
+        std::fs::write(
+            &docs,
+            "# Cache\n\nEntries are evicted oldest first once the cache is full.\n",
+        )
+        .unwrap();
+
+        // No saved config: the base is created with the defaults
+        let learn_options = LearnOptions {
+            base_name: "docs".to_string(),
+            paths: vec![docs],
+            provider: Some("trigram".to_string()),
+            model: Some("trigram-v2".to_string()),
+            ..Default::default()
+        };
+        crate::learn(workspace, &learn_options, None).await.unwrap();
+
+        let ask_options = AskOptions {
+            base_name: "docs".to_string(),
+            query: "cache eviction".to_string(),
+            top_k: Some(1),
+            ..Default::default()
+        };
+        let result = crate::ask(workspace, ask_options, None).await.unwrap();
+        assert_eq!(result.chunks.len(), 1);
+
+        let context = build_context(&result.chunks);
+        let header = context.lines().next().unwrap();
+        assert!(header.starts_with("[Document 1] "), "{}", header);
+        assert!(
+            header.ends_with("guide.md > Cache (lines 1-3)"),
+            "{}",
+            header
+        );
+    }
+}
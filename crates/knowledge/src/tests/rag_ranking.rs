@@ -22,6 +22,7 @@ mod tests {
             position: 0,
             text: text.to_string(),
             embedding: Some(embedding),
+            title_embedding: None,
             metadata: serde_json::json!({}),
         }
     }
@@ -0,0 +1,88 @@
+//! Tests for learning files again: earlier versions are replaced, and
+//! incremental learns skip unchanged files.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::AskOptions;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relearned_file_replaces_its_earlier_version() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let guide = workspace.join("guide.md");
+        std::fs::write(&guide, "# Guide\n\nThe office opens at nine.\n").unwrap();
+
+        let first = crate::learn(workspace, &learn_options("docs", vec![guide.clone()]), None)
+            .await
+            .unwrap();
+        let second = crate::learn(workspace, &learn_options("docs", vec![guide.clone()]), None)
+            .await
+            .unwrap();
+        assert_eq!(second.chunks_count, first.chunks_count);
+
+        let sources = crate::rag::SourceManager::new(workspace, "docs")
+            .list_sources()
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        let stats = crate::stats(workspace, "docs").await.unwrap();
+        assert_eq!(stats.chunks_count, first.chunks_count);
+
+        // The same file in another namespace is a separate source
+        let mut options = learn_options("docs", vec![guide]);
+        options.namespace = Some("archive".to_string());
+        crate::learn(workspace, &options, None).await.unwrap();
+        let sources = crate::rag::SourceManager::new(workspace, "docs")
+            .list_sources()
+            .unwrap();
+        assert_eq!(sources.len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_incremental_learn_skips_unchanged_files() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let guide = workspace.join("guide.md");
+        std::fs::write(&guide, "# Guide\n\nThe office opens at nine.\n").unwrap();
+        let notes = workspace.join("notes.md");
+        std::fs::write(&notes, "# Notes\n\nLunch is served at noon.\n").unwrap();
+
+        let mut options = learn_options("docs", vec![guide.clone(), notes]);
+        options.incremental = true;
+        crate::learn(workspace, &options, None).await.unwrap();
+        let sources = crate::rag::SourceManager::new(workspace, "docs");
+        let first = sources.list_sources().unwrap();
+        assert!(first.iter().all(|source| source.content_hash.is_some()));
+
+        let unchanged = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(unchanged.sources_count, 0);
+        assert_eq!(unchanged.unchanged_count, 2);
+        assert_eq!(sources.list_sources().unwrap(), first);
+
+        // A changed file is learned again and replaces its old chunks
+        std::fs::write(&guide, "# Guide\n\nThe office opens at ten on Mondays.\n").unwrap();
+        let changed = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(changed.sources_count, 1);
+        assert_eq!(changed.unchanged_count, 1);
+        assert_eq!(sources.list_sources().unwrap().len(), 2);
+
+        let options = AskOptions {
+            base_name: "docs".to_string(),
+            query: "When does the office open?".to_string(),
+            top_k: Some(5),
+            ..Default::default()
+        };
+        let result = crate::ask(workspace, options, None).await.unwrap();
+        assert!(result
+            .chunks
+            .iter()
+            .any(|chunk| chunk.text.contains("ten on Mondays")));
+        assert!(!result
+            .chunks
+            .iter()
+            .any(|chunk| chunk.text.contains("nine")));
+    }
+}
@@ -0,0 +1,68 @@
+//! Tests for retrieval depth: candidates retrieved versus chunks kept.
+
+use crate::config;
+use crate::tests::fixtures;
+use crate::types::{AskOptions, InlineText, LearnOptions, RetrievalConfig};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learn_options() -> LearnOptions {
+        let texts = (1..=6)
+            .map(|day| InlineText {
+                title: format!("deploys-{}.md", day),
+                text: format!("Deploys are frozen on day {} of the release week.", day),
+                tags: Vec::new(),
+                ..Default::default()
+            })
+            .collect();
+        LearnOptions {
+            texts,
+            ..fixtures::learn_options("notes", Vec::new())
+        }
+    }
+
+    fn ask_options(top_k: Option<u32>, candidates: Option<u32>) -> AskOptions {
+        AskOptions {
+            base_name: "notes".to_string(),
+            query: "When are deploys frozen?".to_string(),
+            top_k,
+            candidates,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_base_sets_depth_and_asks_override_it() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        crate::learn(workspace, &learn_options(), None)
+            .await
+            .unwrap();
+
+        let mut base_config = config::load_config(workspace, "notes").unwrap();
+        base_config.retrieval = RetrievalConfig {
+            top_k: 2,
+            candidates: Some(6),
+        };
+        config::save_config(workspace, &base_config).unwrap();
+
+        let result = crate::ask(workspace, ask_options(None, None), None)
+            .await
+            .unwrap();
+        assert_eq!(result.chunks.len(), 2);
+
+        let result = crate::ask(workspace, ask_options(Some(4), None), None)
+            .await
+            .unwrap();
+        assert_eq!(result.chunks.len(), 4);
+
+        // A pool smaller than top_k still yields top_k chunks
+        let result = crate::ask(workspace, ask_options(Some(3), Some(1)), None)
+            .await
+            .unwrap();
+        assert_eq!(result.chunks.len(), 3);
+    }
+}
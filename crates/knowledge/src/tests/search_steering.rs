@@ -0,0 +1,116 @@
+//! Tests for steering retrieval with path filters, exclusions and boosts.
+
+use crate::rag::SearchFilters;
+use crate::tests::fixtures::learn_options;
+use crate::types::AskOptions;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Source path of the best chunk for `filters`.
+    async fn top_source(workspace: &Path, filters: SearchFilters) -> String {
+        let options = AskOptions {
+            base_name: "kb".to_string(),
+            query: "how are invoices generated".to_string(),
+            top_k: Some(1),
+            filters,
+            ..Default::default()
+        };
+        let result = crate::ask(workspace, options, None).await.unwrap();
+        assert_eq!(result.chunks.len(), 1);
+        let path = result.chunks[0]
+            .metadata_values("source_path")
+            .find_map(|v| v.as_str())
+            .unwrap();
+        crate::paths::file_name(path).to_string()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exclusions_and_boosts_steer_the_top_chunk() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = [
+            (
+                "docs/invoices.md",
+                "# Invoices\n\nInvoices are generated on the first day of every month.\n",
+            ),
+            (
+                "docs/archive/invoices-2019.md",
+                "# Invoices (2019)\n\nInvoices were generated by hand every month.\n",
+            ),
+            (
+                "src/invoices.rs",
+                "/// How invoices are generated every month.\npub fn generate_invoices() {}\n",
+            ),
+        ];
+        for (path, text) in files {
+            std::fs::create_dir_all(workspace.join(path).parent().unwrap()).unwrap();
+            std::fs::write(workspace.join(path), text).unwrap();
+        }
+        crate::learn(
+            workspace,
+            &learn_options("kb", vec![workspace.to_path_buf()]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Paths match as learned, here absolute
+        let archive = workspace
+            .join("docs/archive")
+            .to_string_lossy()
+            .into_owned();
+
+        // A strong boost lifts a chunk from outside the top-k
+        for (file_type, expected) in [("code", "invoices.rs"), ("markdown", "invoices.md")] {
+            let filters = SearchFilters::new()
+                .with_boosts(vec![format!("file_type:{}=10", file_type).parse().unwrap()])
+                .with_exclude_paths(vec![archive.clone()]);
+            assert_eq!(top_source(workspace, filters).await, expected);
+        }
+
+        let filters = SearchFilters::new()
+            .with_path_prefix(workspace.join("docs").to_string_lossy().into_owned())
+            .with_exclude_paths(vec!["**/invoices.md".to_string()]);
+        assert_eq!(top_source(workspace, filters).await, "invoices-2019.md");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_recency_ranks_the_newer_source_first() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let text = "# Invoices\n\nInvoices are generated on the first day of every month.\n";
+        for path in ["design.md", "current.md"] {
+            std::fs::write(workspace.join(path), text).unwrap();
+        }
+        let two_years = std::time::Duration::from_secs(2 * 365 * 86_400);
+        std::fs::File::options()
+            .write(true)
+            .open(workspace.join("design.md"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - two_years)
+            .unwrap();
+        crate::learn(
+            workspace,
+            &learn_options("kb", vec![workspace.to_path_buf()]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut config = crate::config::load_config(workspace, "kb").unwrap();
+        config.recency = Some(crate::types::RecencyConfig {
+            half_life_days: 90.0,
+            weight: 0.5,
+        });
+        crate::config::save_config(workspace, &config).unwrap();
+
+        assert_eq!(
+            top_source(workspace, SearchFilters::new()).await,
+            "current.md"
+        );
+    }
+}
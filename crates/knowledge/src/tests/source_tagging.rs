@@ -0,0 +1,190 @@
+//! Tests for curating source tags and descriptions with `tag`.
+
+use crate::tests::fixtures::learn_options;
+use crate::types::TagOptions;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::SearchFilters;
+    use crate::types::KnowledgeChunk;
+
+    async fn stored_chunks(workspace: &Path) -> Vec<KnowledgeChunk> {
+        let config = crate::config::load_config(workspace, "docs").unwrap();
+        let index_path = crate::config::get_index_path(workspace, "docs");
+        crate::lancedb_index::LanceDbIndex::new(
+            &index_path,
+            "chunks",
+            config.embedding_dim as usize,
+        )
+        .await
+        .unwrap()
+        .all_chunks()
+        .await
+        .unwrap()
+    }
+
+    fn matching(chunks: &[KnowledgeChunk], tag: &str) -> usize {
+        let scored = chunks.iter().cloned().map(|c| (c, 1.0)).collect();
+        SearchFilters::new()
+            .with_tags(vec![tag.to_string()])
+            .apply(scored)
+            .len()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tags_apply_to_chunks_and_survive_relearn() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        std::fs::create_dir_all(workspace.join("guides")).unwrap();
+        let file = workspace.join("guides").join("billing.md");
+        std::fs::write(
+            &file,
+            "# Billing\n\nInvoices are sent on the first day of every month.\n",
+        )
+        .unwrap();
+
+        crate::learn(workspace, &learn_options("docs", vec![file.clone()]), None)
+            .await
+            .unwrap();
+        let chunks = stored_chunks(workspace).await;
+        assert!(matching(&chunks, "guides") > 0);
+        assert_eq!(matching(&chunks, "payments"), 0);
+
+        let options = TagOptions {
+            base_name: "docs".to_string(),
+            path: "guides/billing.md".to_string(),
+            add: vec!["payments".to_string()],
+            remove: vec!["guides".to_string()],
+            description: Some("Billing FAQ".to_string()),
+            weight: None,
+            pinned: None,
+        };
+        let result = crate::tag(workspace, &options).await.unwrap();
+        assert_eq!(result.chunks_updated as usize, chunks.len());
+        assert!(result.tags.contains(&"payments".to_string()));
+        assert!(!result.tags.contains(&"guides".to_string()));
+
+        let chunks = stored_chunks(workspace).await;
+        assert_eq!(matching(&chunks, "payments"), chunks.len());
+        assert_eq!(matching(&chunks, "guides"), 0);
+        assert!(chunks
+            .iter()
+            .all(|c| c.metadata["custom"]["description"] == "Billing FAQ"));
+
+        // Re-learning the file keeps the curation
+        crate::learn(workspace, &learn_options("docs", vec![file.clone()]), None)
+            .await
+            .unwrap();
+        let chunks = stored_chunks(workspace).await;
+        assert_eq!(matching(&chunks, "payments"), chunks.len());
+        assert_eq!(matching(&chunks, "guides"), 0);
+        let source = crate::rag::SourceManager::new(workspace, "docs")
+            .find_source("billing.md")
+            .unwrap();
+        assert_eq!(source.added_tags, vec!["payments"]);
+        assert_eq!(source.description.as_deref(), Some("Billing FAQ"));
+
+        // An empty description clears it; re-adding a removed tag restores it
+        let options = TagOptions {
+            base_name: "docs".to_string(),
+            path: "billing.md".to_string(),
+            add: vec!["guides".to_string()],
+            description: Some(String::new()),
+            ..Default::default()
+        };
+        let result = crate::tag(workspace, &options).await.unwrap();
+        assert!(result.source.removed_tags.is_empty());
+        assert_eq!(result.source.description, None);
+        let chunks = stored_chunks(workspace).await;
+        assert_eq!(matching(&chunks, "guides"), chunks.len());
+        assert!(chunks
+            .iter()
+            .all(|c| c.metadata["custom"].get("description").is_none()));
+    }
+
+    /// Source file names of the chunks an ask returns.
+    async fn answer_sources(workspace: &Path, filters: SearchFilters) -> Vec<String> {
+        let options = crate::types::AskOptions {
+            base_name: "docs".to_string(),
+            query: "how do services talk to each other".to_string(),
+            top_k: Some(1),
+            filters,
+            ..Default::default()
+        };
+        crate::ask(workspace, options, None)
+            .await
+            .unwrap()
+            .chunks
+            .iter()
+            .filter_map(|c| c.metadata_values("source_path").find_map(|v| v.as_str()))
+            .map(|path| crate::paths::file_name(path).to_string())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_weighted_and_pinned_sources_shape_retrieval() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let files = [
+            (
+                "notes/standup.md",
+                "# Standup\n\nServices talk to each other over the message bus, we think.\n",
+            ),
+            (
+                "docs/architecture.md",
+                "# Architecture\n\nServices communicate through the event bus.\n",
+            ),
+            (
+                "docs/glossary.md",
+                "# Glossary\n\nA tenant is one paying organization.\n",
+            ),
+        ];
+        for (path, text) in files {
+            std::fs::create_dir_all(workspace.join(path).parent().unwrap()).unwrap();
+            std::fs::write(workspace.join(path), text).unwrap();
+        }
+        let options = learn_options("docs", vec![workspace.to_path_buf()]);
+        crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(
+            answer_sources(workspace, SearchFilters::new()).await,
+            vec!["standup.md"]
+        );
+
+        let weight = |path: &str, weight: f32| TagOptions {
+            base_name: "docs".to_string(),
+            path: path.to_string(),
+            weight: Some(weight),
+            ..Default::default()
+        };
+        let result = crate::tag(workspace, &weight("architecture.md", 3.0))
+            .await
+            .unwrap();
+        assert_eq!(result.source.weight, Some(3.0));
+        assert_eq!(
+            answer_sources(workspace, SearchFilters::new()).await,
+            vec!["architecture.md"]
+        );
+        assert!(crate::tag(workspace, &weight("standup.md", 0.0))
+            .await
+            .is_err());
+
+        // A pinned source joins every answer filtering on one of its tags
+        let pin = TagOptions {
+            base_name: "docs".to_string(),
+            path: "glossary.md".to_string(),
+            pinned: Some(true),
+            ..Default::default()
+        };
+        crate::tag(workspace, &pin).await.unwrap();
+        let docs = SearchFilters::new().with_tags(vec!["docs".to_string()]);
+        assert_eq!(
+            answer_sources(workspace, docs).await,
+            vec!["architecture.md", "glossary.md"]
+        );
+        let notes = SearchFilters::new().with_tags(vec!["notes".to_string()]);
+        assert_eq!(answer_sources(workspace, notes).await, vec!["standup.md"]);
+    }
+}
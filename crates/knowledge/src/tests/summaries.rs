@@ -0,0 +1,178 @@
+//! Tests for source and directory summaries (`summarize`, `ask --hierarchical`).
+
+use crate::tests::fixtures;
+use crate::types::{AskOptions, SummarizeOptions};
+use guided_core::config::ProviderConfig;
+use guided_core::CancellationToken;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for Ollama that records prompts and answers summary
+    /// requests by the path they name.
+    async fn llm(prompts: Arc<Mutex<Vec<String>>>) -> HashMap<String, ProviderConfig> {
+        fixtures::ollama(move |request| {
+            prompts.lock().unwrap().push(request.to_string());
+            let path = request
+                .split("Path: ")
+                .nth(1)
+                .and_then(|rest| rest.split("\\n").next())
+                .unwrap_or("");
+            let response = if path.ends_with("invoices.md") {
+                "Invoices are issued monthly by the billing service."
+            } else if path.ends_with("refunds.md") {
+                "Refunds are approved by the billing team within five days."
+            } else if path.ends_with("ranking.md") {
+                "Search ranking blends vector similarity with title similarity."
+            } else if path.ends_with("billing") {
+                "The billing directory covers invoices and refunds."
+            } else if !path.is_empty() {
+                "Documentation of the billing service and of search."
+            } else {
+                "Refunds take five days."
+            };
+            response.to_string()
+        })
+        .await
+    }
+
+    fn write_docs(dir: &Path) {
+        std::fs::create_dir_all(dir.join("billing")).unwrap();
+        std::fs::create_dir_all(dir.join("search")).unwrap();
+        std::fs::write(
+            dir.join("billing/invoices.md"),
+            "# Invoices\n\nInvoices are issued on the first day of every month.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("billing/refunds.md"),
+            "# Refunds\n\nRefund requests are approved by the billing team within five days.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("search/ranking.md"),
+            "# Ranking\n\nResults are ranked by vector similarity blended with title similarity.\n",
+        )
+        .unwrap();
+    }
+
+    async fn learn(workspace: &Path) {
+        let docs = workspace.join("docs");
+        write_docs(&docs);
+        let options = fixtures::learn_options("docs", vec![docs]);
+        crate::learn(workspace, &options, None).await.unwrap();
+    }
+
+    fn summarize_options(provider_configs: HashMap<String, ProviderConfig>) -> SummarizeOptions {
+        SummarizeOptions {
+            base_name: "docs".to_string(),
+            llm_provider: "ollama".to_string(),
+            llm_model: "llama3".to_string(),
+            provider_configs,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    fn ask_options(query: &str, provider_configs: HashMap<String, ProviderConfig>) -> AskOptions {
+        AskOptions {
+            base_name: "docs".to_string(),
+            query: query.to_string(),
+            top_k: Some(5),
+            hierarchical: true,
+            provider_configs,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_summaries_cover_sources_and_directories() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let providers = llm(prompts.clone()).await;
+
+        let stats = crate::summarize(workspace, &summarize_options(providers.clone()), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_summarized, 3);
+        // billing/ and docs/; search/ holds a single file
+        assert_eq!(stats.directories_summarized, 2);
+        assert_eq!(stats.summaries_reused, 0);
+
+        let index_path = crate::config::get_index_path(workspace, "docs");
+        let index = crate::lancedb_index::LanceDbIndex::new(
+            &index_path,
+            crate::summaries::SUMMARIES_TABLE,
+            384,
+        )
+        .await
+        .unwrap();
+        let summaries = index.all_chunks().await.unwrap();
+        let billing = summaries
+            .iter()
+            .find(|s| s.text.contains("billing directory"))
+            .unwrap();
+        assert_eq!(billing.metadata["custom"]["summary_level"], "directory");
+        assert_eq!(
+            billing.metadata["custom"]["source_ids"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Unchanged content is not summarized again
+        let calls = prompts.lock().unwrap().len();
+        let stats = crate::summarize(workspace, &summarize_options(providers), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.sources_summarized + stats.directories_summarized, 0);
+        assert_eq!(stats.summaries_reused, 5);
+        assert_eq!(prompts.lock().unwrap().len(), calls);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hierarchical_ask_uses_summaries() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let providers = llm(prompts.clone()).await;
+
+        let err = crate::ask(workspace, ask_options("refunds", providers.clone()), None)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("guided knowledge summarize docs"),
+            "{}",
+            err
+        );
+
+        crate::summarize(workspace, &summarize_options(providers.clone()), None)
+            .await
+            .unwrap();
+        let query = "How long do refunds take to be approved by the billing team?";
+        let result = crate::ask(workspace, ask_options(query, providers.clone()), None)
+            .await
+            .unwrap();
+        assert!(result.chunks[0].text.contains("five days"));
+
+        let response =
+            crate::rag::ask::ask_rag(workspace, ask_options(query, providers), "ollama", None)
+                .await
+                .unwrap();
+        assert_eq!(response.answer, "Refunds take five days.");
+        // Summaries inform the answer but are not cited
+        assert!(!response.sources.is_empty());
+        assert!(response.sources.iter().all(|s| s.source.ends_with(".md")));
+        let prompts = prompts.lock().unwrap();
+        let answer_prompt = prompts.last().unwrap();
+        assert!(answer_prompt.contains("[Overview of"), "{}", answer_prompt);
+    }
+}
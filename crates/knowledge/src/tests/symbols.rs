@@ -0,0 +1,140 @@
+//! Tests for the symbol index and definition lookup in `ask`.
+
+use crate::symbol_index::SymbolIndex;
+use crate::tests::fixtures::learn_options;
+use crate::types::{AskOptions, KnowledgeBaseConfig};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+/// Sum of the bytes, for change detection.
+pub fn compute_checksum(data: &[u8]) -> u32 {
+    data.iter().map(|b| *b as u32).sum()
+}
+
+pub struct Cache {
+    entries: Vec<String>,
+}
+
+impl Cache {
+    /// Evict the oldest cache entry when the cache is over its limit.
+    pub fn evict(&mut self, limit: usize) {
+        while self.entries.len() > limit {
+            self.entries.remove(0);
+        }
+    }
+}
+";
+
+    fn ask_options(query: &str) -> AskOptions {
+        AskOptions {
+            base_name: "code".to_string(),
+            query: query.to_string(),
+            top_k: Some(1),
+            ..Default::default()
+        }
+    }
+
+    /// Learn `SOURCE` as `cache.rs` in small chunks, one definition each.
+    async fn learn_source(workspace: &Path) -> PathBuf {
+        crate::config::save_config(
+            workspace,
+            &KnowledgeBaseConfig {
+                name: "code".to_string(),
+                chunk_size: 120,
+                chunk_overlap: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let path = workspace.join("cache.rs");
+        std::fs::write(&path, SOURCE).unwrap();
+        crate::learn(workspace, &learn_options("code", vec![path.clone()]), None)
+            .await
+            .unwrap();
+        path
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_learn_indexes_symbols_and_ask_adds_definitions() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let path = learn_source(workspace).await;
+
+        let symbols = SymbolIndex::load(workspace, "code").unwrap();
+        let evict = symbols.lookup("Cache::evict");
+        assert_eq!(evict.len(), 1);
+        assert_eq!(evict[0].definition.name, "evict");
+        assert_eq!(
+            evict[0].definition.signature,
+            "pub fn evict(&mut self, limit: usize)"
+        );
+        assert_eq!(evict[0].definition.line_range, (12, 16));
+        assert!(evict[0].path.ends_with("cache.rs"));
+        assert_eq!(symbols.lookup("compute_checksum").len(), 1);
+
+        // The query quotes the body of evict, but names compute_checksum
+        let result = crate::ask(
+            workspace,
+            ask_options(
+                "while self.entries.len() > limit, self.entries.remove(0); and compute_checksum?",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(!result.chunks[0].text.contains("fn evict"));
+        assert!(
+            result.chunks[0].text.contains("fn compute_checksum"),
+            "{}",
+            result.chunks[0].text
+        );
+        assert_eq!(
+            result.chunks[0].metadata["custom"]["defines"][0]["qualified_name"],
+            "compute_checksum"
+        );
+
+        // Learning the file again replaces its entries
+        std::fs::write(&path, SOURCE.replace("compute_checksum", "checksum")).unwrap();
+        crate::learn(workspace, &learn_options("code", vec![path.clone()]), None)
+            .await
+            .unwrap();
+        let symbols = SymbolIndex::load(workspace, "code").unwrap();
+        assert!(symbols.lookup("compute_checksum").is_empty());
+        assert_eq!(symbols.lookup("checksum").len(), 1);
+        assert_eq!(symbols.lookup("evict").len(), 1);
+
+        crate::clean(workspace, "code", None).await.unwrap();
+        assert!(SymbolIndex::load(workspace, "code").unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rag_answers_cite_definitions() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn_source(workspace).await;
+
+        // Extractive, so no LLM is needed to see what the answer cites
+        let options = AskOptions {
+            extractive: true,
+            ..ask_options(
+                "while self.entries.len() > limit, self.entries.remove(0); and compute_checksum?",
+            )
+        };
+        let response = crate::rag::ask::ask_rag(workspace, options, "ollama", None)
+            .await
+            .unwrap();
+        assert!(
+            response
+                .sources
+                .iter()
+                .any(|source| source.snippet.contains("fn compute_checksum")),
+            "{:?}",
+            response.sources
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Tests for title embeddings (`title_weight`).
+
+use crate::tests::fixtures::learn_options;
+use crate::types::{AskOptions, KnowledgeBaseConfig};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_weighted_titles_are_embedded_and_searched() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        crate::config::save_config(
+            workspace,
+            &KnowledgeBaseConfig {
+                name: "docs".to_string(),
+                chunk_size: 120,
+                chunk_overlap: 0,
+                title_weight: 0.5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let docs = workspace.join("chunking.md");
+        std::fs::write(
+            &docs,
+            "# Chunking\n\nDocuments are split before they are embedded.\n\n\
+             ## ChunkConfig defaults\n\nTargets are 512 characters with an overlap of 50, \
+             and chunks under a tenth of that are merged into their neighbours.\n\n\
+             ## Splitters\n\nMarkdown and plain text go to the text splitter, source \
+             files to tree-sitter, anything else to a fixed-size fallback.\n",
+        )
+        .unwrap();
+        crate::learn(workspace, &learn_options("docs", vec![docs.clone()]), None)
+            .await
+            .unwrap();
+
+        let index_path = crate::config::get_index_path(workspace, "docs");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        let chunks = index.all_chunks().await.unwrap();
+        let defaults = chunks
+            .iter()
+            .find(|c| c.text.contains("512 characters"))
+            .unwrap();
+        // The short intro is merged into the section that follows it
+        assert_eq!(
+            defaults.metadata["custom"]["title"],
+            "chunking.md > Chunking (ChunkConfig defaults)"
+        );
+        assert!(defaults.title_embedding.is_some());
+
+        let result = crate::ask(
+            workspace,
+            AskOptions {
+                base_name: "docs".to_string(),
+                query: "ChunkConfig defaults".to_string(),
+                top_k: Some(1),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(
+            result.chunks[0].text.contains("512 characters"),
+            "{}",
+            result.chunks[0].text
+        );
+    }
+}
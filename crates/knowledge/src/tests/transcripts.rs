@@ -0,0 +1,128 @@
+//! Tests for learning meeting recordings (`--transcribe`).
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::tests::fixtures;
+    use crate::transcripts::Transcriber;
+    use crate::types::LearnOptions;
+    use guided_core::CancellationToken;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    /// A stand-in for `whisper` that writes a fixed JSON transcript named
+    /// after the recording into `--output_dir`.
+    fn fake_whisper(dir: &Path) -> PathBuf {
+        let script = dir.join("whisper");
+        std::fs::write(
+            &script,
+            r#"#!/bin/sh
+input="$1"
+shift
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --output_dir) dir="$2"; shift ;;
+    esac
+    shift
+done
+name=$(basename "$input")
+cat > "$dir/${name%.*}.json" <<'JSON'
+{"text": "", "segments": [
+    {"start": 0.0, "end": 6.0, "text": " Welcome to the March planning meeting."},
+    {"start": 750.0, "end": 845.4, "text": " The billing migration ships on April 2nd."}
+]}
+JSON
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    fn learn_options(docs: PathBuf, transcribe: Option<Transcriber>) -> LearnOptions {
+        LearnOptions {
+            transcribe,
+            ..fixtures::learn_options("meetings", vec![docs])
+        }
+    }
+
+    fn write_docs(dir: &Path) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("agenda.md"), "# Agenda\n\nBilling migration.\n").unwrap();
+        std::fs::write(dir.join("meeting-2024-03.mp4"), b"\x00\x00\x00\x18ftypmp42").unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_recordings_are_learned_with_time_ranges() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        write_docs(&docs);
+
+        let transcriber = Transcriber {
+            command: fake_whisper(workspace),
+            ..Transcriber::new("base")
+        };
+        let options = learn_options(docs, Some(transcriber));
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(stats.sources_count, 2);
+
+        let sources = crate::rag::SourceManager::new(workspace, "meetings")
+            .list_sources()
+            .unwrap();
+        let recording = sources
+            .iter()
+            .find(|s| s.path.ends_with("meeting-2024-03.mp4"))
+            .unwrap();
+        assert_eq!(recording.content_type.as_deref(), Some("media"));
+
+        let index_path = crate::config::get_index_path(workspace, "meetings");
+        let index = crate::lancedb_index::LanceDbIndex::new(&index_path, "chunks", 384)
+            .await
+            .unwrap();
+        let chunks = index.all_chunks().await.unwrap();
+
+        let transcript = chunks
+            .iter()
+            .find(|c| c.text.contains("billing migration ships"))
+            .unwrap();
+        assert!(
+            transcript.text.contains("[12:30] The billing migration"),
+            "{}",
+            transcript.text
+        );
+        let custom = &transcript.metadata["custom"];
+        assert_eq!(custom["media_path"], recording.path.as_str());
+        assert_eq!(custom["time_range"], serde_json::json!([0.0, 845.4]));
+        assert!(transcript.metadata["line_range"].is_null());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_recordings_are_skipped_without_transcribe() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let docs = workspace.join("docs");
+        write_docs(&docs);
+
+        let options = learn_options(docs, None);
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(stats.sources_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_whisper_explains_install() {
+        let temp = TempDir::new().unwrap();
+        let recording = temp.path().join("call.wav");
+        std::fs::write(&recording, b"RIFF").unwrap();
+
+        let transcriber = Transcriber {
+            command: temp.path().join("no-whisper-here"),
+            ..Transcriber::new("base")
+        };
+        let err = transcriber
+            .transcribe(&recording, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("openai-whisper"), "{}", err);
+    }
+}
@@ -0,0 +1,119 @@
+//! Tests for tuning chunk settings on a sample corpus.
+
+use crate::types::{ChunkSetting, EvalCase, KnowledgeBaseConfig, TuneOptions};
+use guided_core::CancellationToken;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(query: &str, expected: &str) -> EvalCase {
+        EvalCase {
+            query: query.to_string(),
+            expected: vec![expected.to_string()],
+        }
+    }
+
+    fn tune_options(paths: Vec<PathBuf>, settings: &[&str]) -> TuneOptions {
+        TuneOptions {
+            base_name: "docs".to_string(),
+            paths,
+            cases: vec![
+                case("when does the office open", "office.md"),
+                case("how are invoices sent", "billing/invoices.md"),
+            ],
+            settings: settings.iter().map(|s| s.parse().unwrap()).collect(),
+            top_k: 3,
+            provider_configs: Default::default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tune_scores_every_setting_and_cleans_up() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let sample = workspace.join("sample");
+        std::fs::create_dir_all(sample.join("billing")).unwrap();
+        // Long enough for the small setting to split each file
+        let filler =
+            "Visitors sign in at the front desk and wear a badge in the building.\n\n".repeat(20);
+        let office = sample.join("office.md");
+        let invoices = sample.join("billing/invoices.md");
+        std::fs::write(
+            &office,
+            format!(
+                "# Office\n\nThe office opens at nine every weekday and closes at six.\n\n{}",
+                filler
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            &invoices,
+            format!(
+                "# Invoices\n\nInvoices are sent by email on the first day of every month.\n\n{}",
+                filler
+            ),
+        )
+        .unwrap();
+        crate::config::save_config(
+            workspace,
+            &KnowledgeBaseConfig {
+                name: "docs".to_string(),
+                provider: "trigram".to_string(),
+                model: "trigram-v2".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let result = crate::tune(
+            workspace,
+            &tune_options(vec![office, invoices], &["64:8", "512:64"]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.scores.len(), 2);
+        assert_eq!(result.scores[0].setting.to_string(), "64:8");
+        assert!(result.scores[0].chunks_count > result.scores[1].chunks_count);
+        for score in &result.scores {
+            assert_eq!(score.recall, 1.0, "{:?}", score);
+            assert!(score.mrr > 0.0, "{:?}", score);
+        }
+        assert!(result.scores.iter().any(|s| s.setting == result.best));
+
+        // Only the base's config is left; the throwaway bases are gone
+        let names: Vec<String> =
+            std::fs::read_dir(crate::config::workspace_knowledge_dir(workspace))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+        assert_eq!(names, vec!["docs"]);
+        assert!(!crate::config::get_index_path(workspace, "docs").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tune_loads_query_sets_and_rejects_bad_ones() {
+        let temp = TempDir::new().unwrap();
+        let queries = temp.path().join("queries.yaml");
+        std::fs::write(
+            &queries,
+            "- query: when does the office open\n  expected: [office.md]\n",
+        )
+        .unwrap();
+        let cases = crate::tune::load_cases(&queries).unwrap();
+        assert_eq!(cases, vec![case("when does the office open", "office.md")]);
+
+        std::fs::write(&queries, r#"[{"query": "anything", "expected": []}]"#).unwrap();
+        let err = crate::tune::load_cases(&queries).unwrap_err();
+        assert!(err.to_string().contains("no expected sources"), "{}", err);
+
+        let mut options = tune_options(vec![queries], &[]);
+        options.cases.clear();
+        assert!(crate::tune(temp.path(), &options).await.is_err());
+        assert_eq!(ChunkSetting::defaults().len(), 3);
+    }
+}
@@ -0,0 +1,87 @@
+//! Tests for verifying RAG answers against their sources.
+
+use crate::tests::fixtures;
+use crate::types::AskOptions;
+use guided_core::config::ProviderConfig;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in for Ollama whose answer adds a detail the docs do not
+    /// state, and whose verifier catches it.
+    async fn llm(requests: Arc<Mutex<Vec<String>>>) -> HashMap<String, ProviderConfig> {
+        fixtures::ollama(move |request| {
+            requests.lock().unwrap().push(request.to_string());
+            let response = if request.contains("You check answers against their sources") {
+                "CLAIM: Deploys are frozen on Fridays | SUPPORT: 1.0 | DOCUMENTS: 1\n\
+                 CLAIM: The freeze starts at 3pm | SUPPORT: 0.0 | DOCUMENTS: none"
+            } else {
+                "Deploys are frozen on Fridays, starting at 3pm."
+            };
+            response.to_string()
+        })
+        .await
+    }
+
+    async fn learn(workspace: &Path) {
+        let policy = "# Deploys\n\nDeploys are frozen on Fridays.\n";
+        fixtures::learn_file(workspace, "team", "deploys.md", policy).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verification_flags_unsupported_claims() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        learn(workspace).await;
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let providers = llm(requests.clone()).await;
+
+        let options = |verify| AskOptions {
+            base_name: "team".to_string(),
+            query: "When are deploys frozen?".to_string(),
+            top_k: Some(3),
+            cache: true,
+            verify,
+            provider_configs: providers.clone(),
+            ..Default::default()
+        };
+
+        // Unverified answers leave the verification out
+        let response = crate::rag::ask::ask_rag(workspace, options(false), "ollama", None)
+            .await
+            .unwrap();
+        assert!(response.verification.is_none());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // A cached answer is verified on request; the verifier sees the answer
+        // and the retrieved chunks
+        let response = crate::rag::ask::ask_rag(workspace, options(true), "ollama", None)
+            .await
+            .unwrap();
+        assert!(response.cached);
+        let verification = response.verification.expect("verification");
+        assert!(!verification.grounded);
+        assert_eq!(verification.claims.len(), 2);
+        assert_eq!(verification.claims[0].documents, vec![1]);
+        let unsupported: Vec<_> = verification
+            .unsupported()
+            .map(|c| c.claim.as_str())
+            .collect();
+        assert_eq!(unsupported, vec!["The freeze starts at 3pm"]);
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 2);
+            assert!(requests[1].contains("starting at 3pm"));
+            assert!(requests[1].contains("Deploys are frozen on Fridays."));
+        }
+
+        let json = serde_json::to_value(&verification).unwrap();
+        assert_eq!(json["grounded"], false);
+        assert_eq!(json["claims"][1]["support"], 0.0);
+    }
+}
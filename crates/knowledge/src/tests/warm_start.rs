@@ -0,0 +1,95 @@
+//! Tests for the process-wide cache of open indexes and engines.
+
+use crate::tests::fixtures::{self, learn_text};
+use crate::types::AskOptions;
+use crate::{warm, KnowledgeBase};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask_options(query: &str) -> AskOptions {
+        AskOptions {
+            base_name: "notes".to_string(),
+            query: query.to_string(),
+            top_k: Some(3),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_asks_reuse_the_index_until_the_base_changes() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+
+        crate::learn(
+            workspace,
+            &learn_text(
+                "notes",
+                "billing.md",
+                "Invoices are exported every Monday at noon.",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..2 {
+            let result = crate::ask(workspace, ask_options("When are invoices exported?"), None)
+                .await
+                .unwrap();
+            assert!(result.chunks[0].text.contains("every Monday"));
+        }
+
+        // A learn invalidates the cached index
+        crate::learn(
+            workspace,
+            &learn_text(
+                "notes",
+                "deploys.md",
+                "Deploys are frozen during the holidays.",
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+        let base = KnowledgeBase::open_cached(workspace, "notes", &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(base.stats().await.unwrap().sources_count, 2);
+        let result = crate::ask(workspace, ask_options("When are deploys frozen?"), None)
+            .await
+            .unwrap();
+        assert!(result.chunks[0].text.contains("holidays"));
+
+        // A cleaned base is reopened empty
+        crate::clean(workspace, "notes", None).await.unwrap();
+        let base = KnowledgeBase::open_cached(workspace, "notes", &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(base.stats().await.unwrap().chunks_count, 0);
+    }
+
+    #[test]
+    fn test_engine_is_shared_per_provider_configs() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+
+        let first = warm::engine(workspace, &HashMap::new());
+        assert!(Arc::ptr_eq(
+            &first,
+            &warm::engine(workspace, &HashMap::new())
+        ));
+
+        let provider_configs = fixtures::ollama_at("http://127.0.0.1:1".to_string());
+        let other = warm::engine(workspace, &provider_configs);
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert!(Arc::ptr_eq(
+            &other,
+            &warm::engine(workspace, &provider_configs)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+//! Tests for learning web pages (`--url`, `--crawl`).
+
+use crate::tests::fixtures;
+use crate::tests::stub_http::{self, Reply};
+use crate::types::{AskOptions, CrawlOptions, LearnOptions, SourceType};
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(request: &str) -> Reply {
+        let path = request.split_whitespace().nth(1).unwrap_or("");
+        match path {
+            "/guide/" => Reply::ok(
+                "text/html",
+                r#"<html><head><title>Guide</title></head><body>
+                   <h1>Guide</h1><p>Start with the <a href="billing">billing</a> page.</p>
+                   </body></html>"#,
+            ),
+            "/guide/billing" => Reply::ok(
+                "text/html",
+                r#"<html><head><title>Billing</title>
+                   <link rel="canonical" href="/guide/billing/"></head><body>
+                   <h1>Billing</h1><p>Invoices are sent on the first day of every month.</p>
+                   <a href="/guide/">Back</a></body></html>"#,
+            ),
+            _ => Reply::not_found(),
+        }
+    }
+
+    fn learn_options(url: String, crawl: Option<CrawlOptions>) -> LearnOptions {
+        LearnOptions {
+            urls: vec![url],
+            crawl,
+            ..fixtures::learn_options("site", Vec::new())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_crawl_learns_linked_pages_as_url_sources() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let server = stub_http::serve(site).await;
+
+        let crawl = CrawlOptions {
+            max_depth: 1,
+            same_domain: true,
+            delay: Duration::from_millis(1),
+        };
+        let options = learn_options(format!("{}/guide/", server), Some(crawl));
+        let stats = crate::learn(workspace, &options, None).await.unwrap();
+        assert_eq!(stats.sources_count, 2);
+
+        let sources = crate::rag::SourceManager::new(workspace, "site")
+            .list_sources()
+            .unwrap();
+        let mut paths: Vec<&str> = sources.iter().map(|s| s.path.as_str()).collect();
+        paths.sort();
+        let billing = format!("{}/guide/billing/", server);
+        assert_eq!(paths, vec![format!("{}/guide/", server), billing.clone()]);
+        assert!(sources.iter().all(|s| s.source_type == SourceType::Url));
+
+        let result = crate::ask(
+            workspace,
+            AskOptions {
+                base_name: "site".to_string(),
+                query: "when are invoices sent".to_string(),
+                top_k: Some(1),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let top = &result.chunks[0];
+        assert!(
+            top.text.contains("first day of every month"),
+            "{}",
+            top.text
+        );
+        assert_eq!(top.metadata["custom"]["source_path"], billing.as_str());
+        assert_eq!(top.metadata["custom"]["file_name"], "Billing");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_invalid_url_fails_before_learning() {
+        let temp = TempDir::new().unwrap();
+        let options = learn_options("docs.example.com".to_string(), None);
+        let err = crate::learn(temp.path(), &options, None).await.unwrap_err();
+        assert!(err.to_string().contains("Not an http(s) URL"), "{}", err);
+        assert!(!crate::config::get_index_path(temp.path(), "site").exists());
+    }
+}
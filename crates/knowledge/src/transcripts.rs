@@ -0,0 +1,249 @@
+//! Timestamped transcripts of meeting recordings.
+//!
+//! MP3, MP4, M4A and WAV files are only learned with `learn --transcribe`,
+//! which runs the local `whisper` command (openai-whisper; ffmpeg decodes
+//! the audio). Each transcript line starts with the time it was spoken, and
+//! its chunks carry `time_range` (seconds into the recording) and
+//! `media_path`, so answers can cite `meeting.mp4 @ 12:30–14:05`.
+
+use guided_core::{AppError, AppResult, CancellationToken};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Whisper model used when `--whisper-model` is not given.
+pub const DEFAULT_WHISPER_MODEL: &str = "base";
+
+/// How recordings are transcribed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcriber {
+    /// Whisper model (`tiny`, `base`, `small`, `medium`, `large`)
+    pub model: String,
+
+    /// Spoken language (`en`, `de`, ...); detected by whisper when unset
+    pub language: Option<String>,
+
+    /// Command to run (`whisper` on PATH by default)
+    pub command: PathBuf,
+}
+
+impl Transcriber {
+    /// Transcriber using the `whisper` command with the given model.
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            language: None,
+            command: PathBuf::from("whisper"),
+        }
+    }
+
+    /// Transcribe a recording into timed segments.
+    pub async fn transcribe(
+        &self,
+        path: &Path,
+        cancel: &CancellationToken,
+    ) -> AppResult<Vec<Segment>> {
+        let output_dir =
+            std::env::temp_dir().join(format!("guided-whisper-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&output_dir).map_err(|e| {
+            AppError::Knowledge(format!("Failed to create transcript directory: {}", e))
+        })?;
+        let result = self.read_transcript(path, &output_dir, cancel).await;
+        let _ = std::fs::remove_dir_all(&output_dir);
+        result
+    }
+
+    async fn read_transcript(
+        &self,
+        path: &Path,
+        output_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> AppResult<Vec<Segment>> {
+        cancel
+            .run("learn", self.run_whisper(path, output_dir))
+            .await?;
+
+        // whisper names its output after the recording: meeting.mp4 -> meeting.json
+        let stem = path.file_stem().unwrap_or_default();
+        let json_path = output_dir.join(stem).with_extension("json");
+        let json = std::fs::read_to_string(&json_path).map_err(|e| {
+            AppError::Knowledge(format!("whisper wrote no transcript for {:?}: {}", path, e))
+        })?;
+        parse_segments(&json)
+    }
+
+    async fn run_whisper(&self, path: &Path, output_dir: &Path) -> AppResult<()> {
+        let mut command = tokio::process::Command::new(&self.command);
+        command
+            .arg(path)
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--output_format")
+            .arg("json")
+            .arg("--output_dir")
+            .arg(output_dir)
+            .kill_on_drop(true);
+        if let Some(language) = &self.language {
+            command.arg("--language").arg(language);
+        }
+
+        let output = command.output().await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AppError::Knowledge(format!(
+                    "{} is not installed; install it with `pip install openai-whisper`",
+                    self.command.display()
+                ))
+            } else {
+                AppError::Knowledge(format!("Failed to run {}: {}", self.command.display(), e))
+            }
+        })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(AppError::Knowledge(format!(
+                "whisper failed on {:?}: {}",
+                path,
+                stderr.trim().lines().last().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A stretch of speech, in seconds from the start of the recording.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Read the segments of a whisper JSON transcript, dropping silent ones.
+pub fn parse_segments(json: &str) -> AppResult<Vec<Segment>> {
+    #[derive(Deserialize)]
+    struct Transcript {
+        #[serde(default)]
+        segments: Vec<Segment>,
+    }
+
+    let transcript: Transcript = serde_json::from_str(json)
+        .map_err(|e| AppError::Knowledge(format!("Invalid whisper transcript: {}", e)))?;
+    Ok(transcript
+        .segments
+        .into_iter()
+        .map(|segment| Segment {
+            text: segment.text.trim().to_string(),
+            ..segment
+        })
+        .filter(|segment| !segment.text.is_empty())
+        .collect())
+}
+
+/// Whether a path is a recording that `learn --transcribe` can read.
+pub fn is_media(path: &Path) -> bool {
+    matches!(
+        crate::metadata::detect_file_type(path),
+        crate::metadata::FileType::Media
+    )
+}
+
+/// A transcript as text: a `# title` heading, then one `[mm:ss] text` line
+/// per segment. Also returns the segment spoken on each line (1-based, so
+/// index 0 and the heading lines map to `None`).
+pub fn transcript_text(title: &str, segments: &[Segment]) -> (String, Vec<Option<(f64, f64)>>) {
+    let mut text = format!("# {}\n\n", title);
+    let mut times = vec![None, None, None];
+    for segment in segments {
+        text.push_str(&format!(
+            "[{}] {}\n",
+            timestamp(segment.start),
+            segment.text
+        ));
+        times.push(Some((segment.start, segment.end)));
+    }
+    (text, times)
+}
+
+/// The time span covered by a range of transcript lines, from the times
+/// returned by [`transcript_text`].
+pub fn time_range(times: &[Option<(f64, f64)>], lines: (usize, usize)) -> Option<(f64, f64)> {
+    let (first, last) = lines;
+    times
+        .iter()
+        .take(last.saturating_add(1))
+        .skip(first)
+        .flatten()
+        .fold(None, |span, &(start, end)| match span {
+            None => Some((start, end)),
+            Some((span_start, span_end)) => {
+                Some((f64::min(span_start, start), f64::max(span_end, end)))
+            }
+        })
+}
+
+/// A time span as `12:30–14:05` (`1:02:03–1:04:00` past the first hour).
+pub fn format_time_range(start: f64, end: f64) -> String {
+    format!("{}–{}", timestamp(start), timestamp(end))
+}
+
+/// Seconds as `mm:ss`, or `h:mm:ss` past the first hour.
+fn timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{
+        "text": " Welcome everyone. Let's talk about the release.",
+        "segments": [
+            {"id": 0, "start": 0.0, "end": 4.5, "text": " Welcome everyone."},
+            {"id": 1, "start": 4.5, "end": 5.0, "text": "  "},
+            {"id": 2, "start": 750.2, "end": 845.9, "text": " Let's talk about the release."}
+        ],
+        "language": "en"
+    }"#;
+
+    #[test]
+    fn test_parse_segments_drops_silence() {
+        let segments = parse_segments(TRANSCRIPT).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Welcome everyone.");
+        assert_eq!(segments[1].start, 750.2);
+        assert!(parse_segments("not json").is_err());
+    }
+
+    #[test]
+    fn test_transcript_lines_map_to_times() {
+        let segments = parse_segments(TRANSCRIPT).unwrap();
+        let (text, times) = transcript_text("standup.mp4", &segments);
+        assert_eq!(
+            text,
+            "# standup.mp4\n\n[00:00] Welcome everyone.\n[12:30] Let's talk about the release.\n"
+        );
+        assert_eq!(text.lines().count() + 1, times.len());
+
+        assert_eq!(time_range(&times, (1, 2)), None);
+        assert_eq!(time_range(&times, (3, 3)), Some((0.0, 4.5)));
+        assert_eq!(time_range(&times, (1, 4)), Some((0.0, 845.9)));
+        assert_eq!(time_range(&times, (4, 99)), Some((750.2, 845.9)));
+    }
+
+    #[test]
+    fn test_format_time_range() {
+        assert_eq!(format_time_range(750.2, 845.9), "12:30–14:05");
+        assert_eq!(format_time_range(3723.0, 3840.0), "1:02:03–1:04:00");
+    }
+
+    #[test]
+    fn test_is_media() {
+        assert!(is_media(Path::new("meetings/2024-03.MP4")));
+        assert!(is_media(Path::new("call.wav")));
+        assert!(!is_media(Path::new("notes.md")));
+    }
+}
@@ -0,0 +1,284 @@
+//! Chunk-size tuning on a sample corpus.
+//!
+//! `tune` learns a sample of the documents a base is meant for once per
+//! chunk size and overlap, each into a throwaway base, runs a query set
+//! against every one and scores how well retrieval finds the sources each
+//! question expects: recall at top-k and mean reciprocal rank (MRR). The
+//! throwaway bases are hidden in the workspace's knowledge directory and
+//! removed when the run ends, so the winning setting can be put into the
+//! base's config before its full learn.
+//!
+//! A query set is a YAML (or JSON) list of cases:
+//!
+//! ```yaml
+//! - query: How do I rotate the signing key?
+//!   expected: [docs/security/keys.md]
+//! - query: Which regions do we deploy to?
+//!   expected: [deploy.md, infra/regions.md]
+//! ```
+
+use crate::types::{
+    AskOptions, ChunkSetting, EvalCase, KnowledgeBaseConfig, KnowledgeChunk, KnowledgeScope,
+    LearnOptions, TuneOptions, TuneResult, TuneScore,
+};
+use crate::{config, paths};
+use guided_core::{AppError, AppResult};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Load a query set: a YAML or JSON list of `query`/`expected` cases.
+pub fn load_cases(path: &Path) -> AppResult<Vec<EvalCase>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Knowledge(format!("Failed to read {:?}: {}", path, e)))?;
+    let cases: Vec<EvalCase> = serde_yaml::from_str(&content)
+        .map_err(|e| AppError::Knowledge(format!("Failed to parse query set {:?}: {}", path, e)))?;
+
+    if cases.is_empty() {
+        return Err(AppError::Knowledge(format!(
+            "Query set {:?} has no questions",
+            path
+        )));
+    }
+    if let Some(case) = cases.iter().find(|case| case.expected.is_empty()) {
+        return Err(AppError::Knowledge(format!(
+            "Question '{}' in {:?} lists no expected sources",
+            case.query, path
+        )));
+    }
+    Ok(cases)
+}
+
+/// Learn `options.paths` at every chunk setting and score retrieval of the
+/// query set against each.
+///
+/// Every setting uses the provider, model and other settings of the base
+/// (its defaults when it does not exist yet); the base itself is not
+/// touched.
+pub async fn tune(workspace: &Path, options: &TuneOptions) -> AppResult<TuneResult> {
+    let start = Instant::now();
+    tracing::info!(
+        "Tuning chunk settings for knowledge base '{}'",
+        options.base_name
+    );
+
+    if options.paths.is_empty() {
+        return Err(AppError::Knowledge(
+            "Tuning needs sample files to learn".to_string(),
+        ));
+    }
+    if options.cases.is_empty() {
+        return Err(AppError::Knowledge(
+            "Tuning needs a query set with at least one question".to_string(),
+        ));
+    }
+    let settings = if options.settings.is_empty() {
+        ChunkSetting::defaults()
+    } else {
+        options.settings.clone()
+    };
+
+    let base = config::load_config(workspace, &options.base_name)?;
+    let mut scratch = ScratchBases(Vec::new());
+    let mut scores = Vec::new();
+    for setting in settings {
+        let name = scratch_name(&options.base_name, setting);
+        tracing::info!("Learning the sample with chunk setting {}", setting);
+
+        // Existing directory first, so the name resolves to the workspace
+        let dir = config::workspace_knowledge_dir(workspace).join(&name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| AppError::Knowledge(format!("Failed to create {:?}: {}", dir, e)))?;
+        scratch.0.push(dir);
+        config::save_config(
+            workspace,
+            &KnowledgeBaseConfig {
+                name: name.clone(),
+                chunk_size: setting.chunk_size,
+                chunk_overlap: setting.chunk_overlap,
+                scope: KnowledgeScope::Workspace,
+                feeds: Vec::new(),
+                ..base.clone()
+            },
+        )?;
+
+        let learned = Instant::now();
+        let stats = crate::learn(workspace, &learn_options(&name, options), None).await?;
+        let learn_secs = learned.elapsed().as_secs_f64();
+
+        let mut recall = 0.0;
+        let mut mrr = 0.0;
+        for case in &options.cases {
+            let result =
+                crate::ask(workspace, ask_options(&name, &case.query, options), None).await?;
+            let (case_recall, case_rr) = score(case, &result.chunks);
+            recall += case_recall;
+            mrr += case_rr;
+        }
+        let count = options.cases.len() as f32;
+        scores.push(TuneScore {
+            setting,
+            chunks_count: stats.chunks_count,
+            recall: recall / count,
+            mrr: mrr / count,
+            learn_secs,
+        });
+    }
+
+    let best = scores
+        .iter()
+        .max_by(|a, b| {
+            a.mrr
+                .total_cmp(&b.mrr)
+                .then(a.recall.total_cmp(&b.recall))
+                .then(b.chunks_count.cmp(&a.chunks_count))
+        })
+        .map(|score| score.setting)
+        .ok_or_else(|| AppError::Knowledge("No chunk settings to try".to_string()))?;
+
+    let duration_secs = start.elapsed().as_secs_f64();
+    tracing::info!(
+        "Tuned knowledge base '{}' in {:.2}s: best chunk setting {}",
+        options.base_name,
+        duration_secs,
+        best
+    );
+    Ok(TuneResult {
+        scores,
+        best,
+        duration_secs,
+    })
+}
+
+/// Throwaway bases of a tuning run, removed when it ends, failed or not.
+struct ScratchBases(Vec<PathBuf>);
+
+impl Drop for ScratchBases {
+    fn drop(&mut self) {
+        for dir in &self.0 {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                tracing::warn!("Failed to remove tuning base {:?}: {}", dir, e);
+            }
+        }
+    }
+}
+
+/// Name of the throwaway base of a setting; the leading dot keeps it out
+/// of the names users can give.
+fn scratch_name(base_name: &str, setting: ChunkSetting) -> String {
+    format!(
+        ".tune-{}-{}-{}",
+        base_name, setting.chunk_size, setting.chunk_overlap
+    )
+}
+
+fn learn_options(base_name: &str, options: &TuneOptions) -> LearnOptions {
+    LearnOptions {
+        base_name: base_name.to_string(),
+        paths: options.paths.clone(),
+        provider_configs: options.provider_configs.clone(),
+        cancel: options.cancel.clone(),
+        ..Default::default()
+    }
+}
+
+fn ask_options(base_name: &str, query: &str, options: &TuneOptions) -> AskOptions {
+    AskOptions {
+        base_name: base_name.to_string(),
+        query: query.to_string(),
+        top_k: Some(options.top_k),
+        provider_configs: options.provider_configs.clone(),
+        cancel: options.cancel.clone(),
+        ..Default::default()
+    }
+}
+
+/// Recall and reciprocal rank of one question's retrieved chunks.
+fn score(case: &EvalCase, chunks: &[KnowledgeChunk]) -> (f32, f32) {
+    let sources: Vec<String> = chunks.iter().map(source_path).collect();
+
+    let found = case
+        .expected
+        .iter()
+        .filter(|expected| sources.iter().any(|source| matches(source, expected)))
+        .count();
+    let recall = found as f32 / case.expected.len() as f32;
+
+    let reciprocal_rank = sources
+        .iter()
+        .position(|source| {
+            case.expected
+                .iter()
+                .any(|expected| matches(source, expected))
+        })
+        .map_or(0.0, |rank| 1.0 / (rank + 1) as f32);
+
+    (recall, reciprocal_rank)
+}
+
+/// Path of a chunk's source file, or its source id.
+fn source_path(chunk: &KnowledgeChunk) -> String {
+    chunk.metadata["custom"]["source_path"]
+        .as_str()
+        .unwrap_or(&chunk.source_id)
+        .to_string()
+}
+
+/// Whether `expected` is the whole of `source` or a trailing part of it
+/// made of whole path components.
+fn matches(source: &str, expected: &str) -> bool {
+    let source = paths::normalize_separators(source);
+    let expected = paths::normalize_separators(expected);
+    let expected = expected.trim_start_matches("./");
+    source == expected || source.ends_with(&format!("/{}", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(path: &str) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: path.to_string(),
+            source_id: "source".to_string(),
+            position: 0,
+            text: String::new(),
+            embedding: None,
+            title_embedding: None,
+            metadata: serde_json::json!({"custom": {"source_path": path}}),
+        }
+    }
+
+    #[test]
+    fn test_score_recall_and_reciprocal_rank() {
+        let case = EvalCase {
+            query: "q".to_string(),
+            expected: vec!["keys.md".to_string(), "./deploy/regions.md".to_string()],
+        };
+        let chunks = [
+            chunk("/work/docs/intro.md"),
+            chunk("/work/docs/security/keys.md"),
+            chunk("/work/docs/security/keys.md"),
+        ];
+        assert_eq!(score(&case, &chunks), (0.5, 0.5));
+
+        let chunks = [chunk("/work/deploy/regions.md"), chunk("/work/keys.md")];
+        assert_eq!(score(&case, &chunks), (1.0, 1.0));
+
+        // Only whole path components match
+        let chunks = [chunk("/work/monkeys.md")];
+        assert_eq!(score(&case, &chunks), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_chunk_setting_parse() {
+        let setting: ChunkSetting = "512:64".parse().unwrap();
+        assert_eq!(setting.chunk_size, 512);
+        assert_eq!(setting.chunk_overlap, 64);
+        assert_eq!(setting.to_string(), "512:64");
+
+        assert!("512".parse::<ChunkSetting>().is_err());
+        assert!("64:128".parse::<ChunkSetting>().is_err());
+        assert!("0:0".parse::<ChunkSetting>().is_err());
+    }
+}
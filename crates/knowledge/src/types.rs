@@ -1,8 +1,16 @@
 //! Knowledge system type definitions.
 
+use crate::embeddings::EmbeddingUsage;
+use crate::images::ImageReader;
+use crate::rag::{Mix, SearchFilters};
+use crate::transcripts::Transcriber;
 use chrono::{DateTime, Utc};
+use guided_core::config::ProviderConfig;
+use guided_core::CancellationToken;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Configuration for a knowledge base.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +39,365 @@ pub struct KnowledgeBaseConfig {
     /// Embedding vector dimension
     #[serde(default = "default_embedding_dim")]
     pub embedding_dim: u32,
+
+    /// Maximum texts per embedding request
+    #[serde(default = "default_embedding_batch_size")]
+    pub embedding_batch_size: u32,
+
+    /// Where the base is stored
+    #[serde(default)]
+    pub scope: KnowledgeScope,
+
+    /// Encryption of chunk text and metadata (unset for plaintext bases)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionConfig>,
+
+    /// Feeds the base subscribes to, synced by `guided knowledge refresh`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub feeds: Vec<FeedSubscription>,
+
+    /// Include patterns for every learn of the base, on top of `--include`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Exclude patterns for every learn of the base, on top of `--exclude`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Weight of the chunk title (heading path or symbol names) in search
+    /// scores, from 0 to 1. Above 0, `learn` embeds titles separately and
+    /// searches blend `(1 - w) * body + w * title` similarity.
+    #[serde(default)]
+    pub title_weight: f32,
+
+    /// Checks applied to retrieved chunks before they reach an LLM prompt
+    #[serde(default)]
+    pub guardrails: GuardrailsConfig,
+
+    /// How many chunks an ask retrieves, and how many of them it keeps
+    #[serde(default)]
+    pub retrieval: RetrievalConfig,
+
+    /// Rank recently modified sources above older ones with similar
+    /// wording (unset: similarity only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recency: Option<RecencyConfig>,
+
+    /// Steps applied to chunks after splitting
+    #[serde(default)]
+    pub post_processing: PostProcessing,
+
+    /// Relevance thresholds calibrated for the base by `learn` (unset: the
+    /// defaults)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thresholds: Option<Thresholds>,
+}
+
+impl KnowledgeBaseConfig {
+    /// Thresholds calibrated for the current provider and model, if any.
+    /// Thresholds of another model mean nothing for this one's scores.
+    pub fn calibrated_thresholds(&self) -> Option<&Thresholds> {
+        self.thresholds
+            .as_ref()
+            .filter(|t| t.provider == self.provider && t.model == self.model)
+    }
+
+    /// Score a chunk needs to be retrieved.
+    pub fn min_relevance(&self) -> f32 {
+        self.calibrated_thresholds()
+            .map_or(crate::MIN_RELEVANCE_SCORE, |t| t.min_relevance)
+    }
+
+    /// Score the best chunk needs for an answer not to be low-confidence.
+    pub fn confidence_threshold(&self) -> f32 {
+        self.calibrated_thresholds()
+            .map_or(crate::rag::types::CONFIDENCE_THRESHOLD, |t| t.confidence)
+    }
+}
+
+/// Relevance thresholds calibrated from the scores of random chunk pairs
+/// (see [`crate::calibration`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thresholds {
+    /// Score a chunk needs to be retrieved
+    pub min_relevance: f32,
+
+    /// Score the best chunk needs for a confident answer
+    pub confidence: f32,
+
+    /// Embedding provider the thresholds were calibrated with
+    pub provider: String,
+
+    /// Embedding model the thresholds were calibrated with
+    pub model: String,
+
+    /// Chunk pairs scored
+    pub pairs: usize,
+
+    /// When the thresholds were calibrated
+    pub calibrated_at: DateTime<Utc>,
+}
+
+/// Post-processing of chunks after splitting (see [`crate::chunk`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostProcessing {
+    /// Merge consecutive chunks smaller than the chunk size, dropping those
+    /// under a tenth of it
+    #[serde(default = "default_merge_small")]
+    pub merge_small: bool,
+
+    /// Split chunks over twice the chunk size at the end of a sentence
+    /// rather than at any whitespace
+    #[serde(default)]
+    pub snap_to_sentences: bool,
+
+    /// Start each chunk with up to `chunk_overlap` characters from the end
+    /// of the previous one
+    #[serde(default)]
+    pub inject_overlap: bool,
+}
+
+fn default_merge_small() -> bool {
+    true
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self {
+            merge_small: default_merge_small(),
+            snap_to_sentences: false,
+            inject_overlap: false,
+        }
+    }
+}
+
+/// Post-processing steps one learn turns on or off, over the base config's
+/// (`None` keeps the config's setting).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostProcessingOverrides {
+    pub merge_small: Option<bool>,
+    pub snap_to_sentences: Option<bool>,
+    pub inject_overlap: Option<bool>,
+}
+
+impl PostProcessingOverrides {
+    /// `config` with these overrides applied.
+    pub fn apply(&self, config: PostProcessing) -> PostProcessing {
+        PostProcessing {
+            merge_small: self.merge_small.unwrap_or(config.merge_small),
+            snap_to_sentences: self.snap_to_sentences.unwrap_or(config.snap_to_sentences),
+            inject_overlap: self.inject_overlap.unwrap_or(config.inject_overlap),
+        }
+    }
+}
+
+/// Recency boost of retrieval (see [`crate::rag::recency`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecencyConfig {
+    /// Age, in days, at which a source gets half the boost of a new one
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: f32,
+
+    /// Share of the score recency decides, from 0 to 1: a source far older
+    /// than the half-life keeps `1 - weight` of its similarity
+    #[serde(default = "default_recency_weight")]
+    pub weight: f32,
+}
+
+fn default_half_life_days() -> f32 {
+    180.0
+}
+
+fn default_recency_weight() -> f32 {
+    0.3
+}
+
+impl Default for RecencyConfig {
+    fn default() -> Self {
+        Self {
+            half_life_days: default_half_life_days(),
+            weight: default_recency_weight(),
+        }
+    }
+}
+
+/// Checks on retrieved text before it is put into an LLM prompt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuardrailsConfig {
+    /// What to do with chunks that look like prompt injection
+    #[serde(default)]
+    pub injection_filter: InjectionFilter,
+
+    /// Maximum bytes of chunk text put into a prompt (0 for no limit).
+    /// Chunks that would exceed it are dropped, keeping the best-ranked.
+    #[serde(default = "default_max_context_bytes")]
+    pub max_context_bytes: usize,
+}
+
+fn default_max_context_bytes() -> usize {
+    24_000
+}
+
+impl Default for GuardrailsConfig {
+    fn default() -> Self {
+        Self {
+            injection_filter: InjectionFilter::default(),
+            max_context_bytes: default_max_context_bytes(),
+        }
+    }
+}
+
+/// Retrieval depth of asks: the candidates searched for and the chunks kept
+/// for the answer.
+///
+/// Recall grows with the candidate pool, which filters, recency, source
+/// weights and diversity pick from; the context given to the LLM grows with
+/// `top_k`. Asks can override both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetrievalConfig {
+    /// Chunks kept for the answer and given to the LLM
+    #[serde(default = "default_top_k")]
+    pub top_k: u32,
+
+    /// Candidates retrieved to filter and re-rank before the best `top_k`
+    /// are kept (unset: `top_k`, or four times as many when filtering,
+    /// boosting or diversifying). Never fewer than `top_k`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<u32>,
+}
+
+fn default_top_k() -> u32 {
+    5
+}
+
+impl Default for RetrievalConfig {
+    fn default() -> Self {
+        Self {
+            top_k: default_top_k(),
+            candidates: None,
+        }
+    }
+}
+
+impl RetrievalConfig {
+    /// The depth of one ask: its own `top_k` and `candidates` where set,
+    /// this config's otherwise.
+    pub fn with_overrides(&self, top_k: Option<u32>, candidates: Option<u32>) -> Self {
+        Self {
+            top_k: top_k.unwrap_or(self.top_k),
+            candidates: candidates.or(self.candidates),
+        }
+    }
+}
+
+/// Handling of retrieved text that looks like prompt injection, such as
+/// "ignore previous instructions" or tool-call markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectionFilter {
+    /// Pass chunks through unchanged
+    Off,
+    /// Mark suspicious chunks so the LLM treats them as data
+    #[default]
+    Flag,
+    /// Remove suspicious lines from chunks
+    Strip,
+}
+
+/// An RSS or Atom feed whose entries a base learns.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedSubscription {
+    /// Feed URL
+    pub url: String,
+
+    /// Namespace the entries are learned into, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// When the feed was last fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<DateTime<Utc>>,
+
+    /// `ETag` of the last response, to skip unchanged feeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// `Last-Modified` of the last response, to skip unchanged feeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+/// Key settings of an encrypted knowledge base.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Environment variable holding the hex key
+    #[serde(default = "default_key_env")]
+    pub key_env: String,
+
+    /// Command printing the key, used when the variable is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_command: Option<String>,
+
+    /// Known value encrypted with the key, to reject a wrong key
+    #[serde(default)]
+    pub key_check: String,
+}
+
+fn default_key_env() -> String {
+    crate::encryption::DEFAULT_KEY_ENV.to_string()
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            key_env: default_key_env(),
+            key_command: None,
+            key_check: String::new(),
+        }
+    }
+}
+
+/// Storage location of a knowledge base.
+///
+/// Workspace bases live in `<workspace>/.guided/knowledge/`; global bases in
+/// `~/.guided/knowledge/` and are usable from every workspace. A workspace
+/// base shadows a global base with the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnowledgeScope {
+    #[default]
+    Workspace,
+    Global,
+}
+
+impl KnowledgeScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KnowledgeScope::Workspace => "workspace",
+            KnowledgeScope::Global => "global",
+        }
+    }
+}
+
+impl std::fmt::Display for KnowledgeScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for KnowledgeScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "workspace" => Ok(KnowledgeScope::Workspace),
+            "global" => Ok(KnowledgeScope::Global),
+            other => Err(format!(
+                "Unknown knowledge scope '{}' (expected 'workspace' or 'global')",
+                other
+            )),
+        }
+    }
 }
 
 fn default_chunk_size() -> u32 {
@@ -49,6 +416,10 @@ fn default_max_context_tokens() -> u32 {
     2048
 }
 
+fn default_embedding_batch_size() -> u32 {
+    100
+}
+
 impl Default for KnowledgeBaseConfig {
     fn default() -> Self {
         Self {
@@ -59,21 +430,74 @@ impl Default for KnowledgeBaseConfig {
             chunk_overlap: default_chunk_overlap(),
             max_context_tokens: default_max_context_tokens(),
             embedding_dim: 768, // nomic-embed-text dimensions
+            embedding_batch_size: default_embedding_batch_size(),
+            scope: KnowledgeScope::default(),
+            encryption: None,
+            feeds: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            title_weight: 0.0,
+            guardrails: GuardrailsConfig::default(),
+            retrieval: RetrievalConfig::default(),
+            recency: None,
+            post_processing: PostProcessing::default(),
+            thresholds: None,
         }
     }
 }
 
-/// Represents a source document in the knowledge base (sources.jsonl tracking).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Kind of a learned source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    #[default]
+    File,
+    Url,
+    Zip,
+    /// Text passed to `learn` directly (`--stdin`, `--text`)
+    Text,
+    /// An entry of a subscribed feed
+    Feed,
+}
+
+impl SourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceType::File => "file",
+            SourceType::Url => "url",
+            SourceType::Zip => "zip",
+            SourceType::Text => "text",
+            SourceType::Feed => "feed",
+        }
+    }
+}
+
+impl std::fmt::Display for SourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A source document in the knowledge base, as tracked in `sources.jsonl`
+/// and stored by the index backends.
+///
+/// Records written by older versions (`id`, `url`, `learned_at`,
+/// `size_bytes`, no `chunk_count`) are still read.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SourceRecord")]
 pub struct KnowledgeSource {
     /// Unique source identifier
     pub source_id: String,
 
-    /// Source path (file path or URL)
+    /// Where the source was learned from: a `/`-separated file path or a URL
     pub path: String,
 
-    /// Source type: "file", "url", "zip"
-    pub source_type: String,
+    /// Kind of source
+    pub source_type: SourceType,
+
+    /// Detected content type ("markdown", "code", "json", ...), if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
 
     /// When this source was indexed
     pub indexed_at: DateTime<Utc>,
@@ -83,6 +507,182 @@ pub struct KnowledgeSource {
 
     /// Source size in bytes
     pub byte_count: u64,
+
+    /// Estimated tokens of the source's chunks (unset for sources learned
+    /// before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_count: Option<u64>,
+
+    /// Namespace the source was learned into, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Tags added with `guided knowledge tag`, on top of the derived ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_tags: Vec<String>,
+
+    /// Derived tags removed with `guided knowledge tag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_tags: Vec<String>,
+
+    /// Description set with `guided knowledge tag --description`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Score multiplier set with `guided knowledge tag --weight`; above 1
+    /// for authoritative sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f32>,
+
+    /// Set with `guided knowledge tag --pin`: included in answers to every
+    /// query filtering on one of the source's tags
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+
+    /// Connector name and document id for sources learned through a
+    /// connector (`confluence:12345`), or feed URL and entry id for feed
+    /// entries (`feed:https://example.com/releases.xml#v1.2`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_id: Option<String>,
+
+    /// Remote document version when it was learned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_version: Option<String>,
+
+    /// SHA-256 of the file's bytes when it was learned (files only), to
+    /// skip unchanged files in incremental learns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+impl KnowledgeSource {
+    /// Whether tags were edited, or a description, weight or pin set for
+    /// this source.
+    pub fn is_curated(&self) -> bool {
+        !self.added_tags.is_empty()
+            || !self.removed_tags.is_empty()
+            || self.description.is_some()
+            || self.weight.is_some()
+            || self.pinned
+    }
+
+    /// `derived` tags with this source's removed tags dropped and added tags
+    /// appended, without duplicates.
+    pub fn curate_tags(&self, derived: Vec<String>) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::with_capacity(derived.len() + self.added_tags.len());
+        for tag in derived.into_iter().chain(self.added_tags.iter().cloned()) {
+            if !self.removed_tags.contains(&tag) && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Carry the tag edits, description, weight and pin of an earlier record
+    /// of the same source over to this one (after re-learning it).
+    pub fn inherit_curation(&mut self, previous: &KnowledgeSource) {
+        self.added_tags = previous.added_tags.clone();
+        self.removed_tags = previous.removed_tags.clone();
+        self.description = previous.description.clone();
+        self.weight = previous.weight;
+        self.pinned = previous.pinned;
+    }
+
+    /// Apply the curated tags, description and weight to a chunk's file
+    /// metadata (the `custom` object written by `learn`).
+    pub fn apply_curation(&self, custom: &mut serde_json::Map<String, serde_json::Value>) {
+        let derived = crate::metadata::derive_tags(std::path::Path::new(&self.path));
+        custom.insert(
+            "tags".to_string(),
+            serde_json::json!(self.curate_tags(derived)),
+        );
+        match &self.description {
+            Some(description) => {
+                custom.insert("description".to_string(), serde_json::json!(description));
+            }
+            None => {
+                custom.remove("description");
+            }
+        }
+        match self.weight {
+            Some(weight) => {
+                custom.insert("weight".to_string(), serde_json::json!(weight));
+            }
+            None => {
+                custom.remove("weight");
+            }
+        }
+    }
+}
+
+/// Serialized form of [`KnowledgeSource`] in any version.
+#[derive(Deserialize)]
+struct SourceRecord {
+    #[serde(alias = "id")]
+    source_id: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    source_type: Option<SourceType>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(alias = "learned_at")]
+    indexed_at: DateTime<Utc>,
+    #[serde(default)]
+    chunk_count: u32,
+    #[serde(default, alias = "size_bytes")]
+    byte_count: u64,
+    #[serde(default)]
+    token_count: Option<u64>,
+    #[serde(default)]
+    namespace: Option<String>,
+    #[serde(default)]
+    added_tags: Vec<String>,
+    #[serde(default)]
+    removed_tags: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    weight: Option<f32>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    remote_id: Option<String>,
+    #[serde(default)]
+    remote_version: Option<String>,
+    #[serde(default)]
+    content_hash: Option<String>,
+}
+
+impl From<SourceRecord> for KnowledgeSource {
+    fn from(record: SourceRecord) -> Self {
+        let from_url = record.path.is_none() && record.url.is_some();
+        Self {
+            source_id: record.source_id,
+            path: record.path.or(record.url).unwrap_or_default(),
+            source_type: record.source_type.unwrap_or(if from_url {
+                SourceType::Url
+            } else {
+                SourceType::File
+            }),
+            content_type: record.content_type,
+            indexed_at: record.indexed_at,
+            chunk_count: record.chunk_count,
+            byte_count: record.byte_count,
+            token_count: record.token_count,
+            namespace: record.namespace,
+            added_tags: record.added_tags,
+            removed_tags: record.removed_tags,
+            description: record.description,
+            weight: record.weight,
+            pinned: record.pinned,
+            remote_id: record.remote_id,
+            remote_version: record.remote_version,
+            content_hash: record.content_hash,
+        }
+    }
 }
 
 /// A text chunk with embedding.
@@ -104,13 +704,32 @@ pub struct KnowledgeChunk {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
 
+    /// Embedding of the chunk's title, for bases with a `title_weight`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_embedding: Option<Vec<f32>>,
+
     /// Metadata (e.g., file path, line numbers)
     #[serde(default)]
     pub metadata: serde_json::Value,
 }
 
+impl KnowledgeChunk {
+    /// Values of a metadata field, top-level first, then the file metadata
+    /// that `learn` nests under `custom`.
+    pub fn metadata_values<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Iterator<Item = &'a serde_json::Value> {
+        [Some(&self.metadata), self.metadata.get("custom")]
+            .into_iter()
+            .flatten()
+            .filter_map(move |m| m.get(key))
+            .filter(|v| !v.is_null())
+    }
+}
+
 /// Options for the learn operation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct LearnOptions {
     /// Knowledge base name
     pub base_name: String,
@@ -121,20 +740,144 @@ pub struct LearnOptions {
     /// URLs to fetch and learn
     pub urls: Vec<String>,
 
+    /// Follow links from `urls` to learn whole sites; `None` fetches only
+    /// the given URLs
+    pub crawl: Option<CrawlOptions>,
+
+    /// Text to learn without a file on disk (pasted notes, piped output)
+    pub texts: Vec<InlineText>,
+
+    /// Connector to sync documents from (`.guided/connectors/<name>.yaml`)
+    pub connector: Option<String>,
+
+    /// RSS or Atom feeds to subscribe the base to and learn the new entries
+    /// of
+    pub feeds: Vec<String>,
+
     /// Include patterns (glob)
     pub include: Vec<String>,
 
     /// Exclude patterns (glob)
     pub exclude: Vec<String>,
 
-    /// Reset the base before learning
+    /// How directories in `paths` are walked
+    pub discovery: DiscoveryOptions,
+
+    /// Chunk post-processing to change from the base config's; the changes
+    /// are saved to the config
+    pub post_processing: PostProcessingOverrides,
+
+    /// Learn PNG, JPEG and SVG files through text descriptions made by this
+    /// reader; `None` skips images
+    pub images: Option<ImageReader>,
+
+    /// Learn MP3, MP4, M4A and WAV recordings through timestamped
+    /// transcripts made by this transcriber; `None` skips recordings
+    pub transcribe: Option<Transcriber>,
+
+    /// Namespace to store the chunks in (`docs`, `code/api`, ...); `None`
+    /// stores them outside any namespace
+    pub namespace: Option<String>,
+
+    /// Reset the base (only `namespace`, when given) before learning
     pub reset: bool,
 
+    /// Continue an interrupted learn from its checkpoint instead of
+    /// discovering files from `paths`
+    pub resume: bool,
+
+    /// Skip files whose content has not changed since they were last
+    /// learned (e.g., scheduled profile learns)
+    pub incremental: bool,
+
     /// Embedding provider (optional, uses config or default if not specified)
     pub provider: Option<String>,
 
     /// Embedding model (optional, uses config or default if not specified)
     pub model: Option<String>,
+
+    /// Where to create the base if it does not exist yet (defaults to the
+    /// workspace; existing bases stay where they are)
+    pub scope: Option<KnowledgeScope>,
+
+    /// Encrypt chunk text and metadata (new or reset bases only)
+    pub encrypt: bool,
+
+    /// Re-embed existing chunks when the provider's dimensions differ from
+    /// the index's
+    pub migrate: bool,
+
+    /// Configured providers (`llm.providers`), for the endpoint and timeout
+    /// of the embedding provider
+    pub provider_configs: HashMap<String, ProviderConfig>,
+
+    /// Stops learning between batches, keeping what was already indexed
+    pub cancel: CancellationToken,
+}
+
+/// How `learn` walks the directories in its `paths`.
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryOptions {
+    /// Follow symbolic links to directories, learning each file once however
+    /// many links lead to it; otherwise links to directories are skipped
+    pub follow_links: bool,
+
+    /// Levels of directories to descend below each path; `None` walks all
+    pub max_depth: Option<usize>,
+
+    /// Stay on the file system of each path, skipping mount points below it
+    pub same_file_system: bool,
+}
+
+/// How `learn` crawls sites from its `urls`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlOptions {
+    /// Links followed away from a start URL; 0 learns the start pages (and
+    /// the sitemap entries under them) only
+    pub max_depth: u32,
+
+    /// Only follow links to the hosts of the start URLs
+    pub same_domain: bool,
+
+    /// Minimum time between two requests to one site; a robots.txt
+    /// `Crawl-delay` can raise it
+    pub delay: Duration,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            same_domain: false,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Text learned as a source of its own, without a file on disk.
+#[derive(Debug, Clone, Default)]
+pub struct InlineText {
+    /// Title, stored as the source path (`release-notes`)
+    pub title: String,
+
+    /// Content
+    pub text: String,
+
+    /// Tags, on top of those derived from the title
+    pub tags: Vec<String>,
+
+    /// Web URL, stored as the source path instead of the title
+    pub url: Option<String>,
+
+    /// Connector document id and version (see [`KnowledgeSource::remote_id`])
+    pub remote_id: Option<String>,
+    pub remote_version: Option<String>,
+
+    /// Kind of source to record; `url` when `url` is set, else `text`
+    pub source_type: Option<SourceType>,
+
+    /// Publication date, for feed entries
+    pub published_at: Option<DateTime<Utc>>,
 }
 
 /// Statistics from a learn operation.
@@ -149,12 +892,175 @@ pub struct LearnStats {
     /// Total bytes processed
     pub bytes_processed: u64,
 
+    /// Files (in incremental learns), connector documents and feed entries
+    /// skipped because they did not change
+    #[serde(default)]
+    pub unchanged_count: u32,
+
+    /// Sources removed because their connector document or feed entry
+    /// changed, or the document was deleted
+    #[serde(default)]
+    pub removed_count: u32,
+
+    /// Size of the learned chunks by file type
+    #[serde(default)]
+    pub content: ContentStats,
+
+    /// Embedding provider the chunks were embedded with
+    #[serde(default)]
+    pub provider: String,
+
+    /// Embedding model the chunks were embedded with
+    #[serde(default)]
+    pub model: String,
+
+    /// What the embedding calls of the learn consumed
+    #[serde(default)]
+    pub embedding_usage: EmbeddingUsage,
+
+    /// Files and feeds that could not be learned; the rest were
+    #[serde(default)]
+    pub errors: Vec<LearnError>,
+
     /// Duration in seconds
     pub duration_secs: f64,
 }
 
+/// A file or feed a learn skipped because it failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnError {
+    /// Path of the file, or URL of the feed
+    pub source: String,
+
+    /// Step that failed
+    pub stage: LearnStage,
+
+    /// The error
+    pub message: String,
+}
+
+/// Step of a learn a [`LearnError`] happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LearnStage {
+    /// Reading, converting or chunking a file
+    Parse,
+    /// Embedding or indexing the batch a file was in
+    Embed,
+    /// Reading a subscribed feed
+    Feed,
+}
+
+impl LearnStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LearnStage::Parse => "parse",
+            LearnStage::Embed => "embed",
+            LearnStage::Feed => "feed",
+        }
+    }
+}
+
+impl std::fmt::Display for LearnStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Size of learned text by file type, for [`LearnStats`] and [`BaseStats`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentStats {
+    /// Estimated tokens of the chunk text, at about four characters a token
+    pub tokens_estimate: u64,
+
+    /// Chunks per file type of their source (`markdown`, `code`, ...)
+    pub chunks_by_type: BTreeMap<String, u32>,
+}
+
+impl ContentStats {
+    /// Count the chunks of a learned source. Sources recorded without a
+    /// token count are estimated from their size.
+    pub fn add_source(&mut self, source: &KnowledgeSource) {
+        self.tokens_estimate += source
+            .token_count
+            .unwrap_or_else(|| source.byte_count.div_ceil(4));
+        let file_type = source.content_type.as_deref().unwrap_or("unknown");
+        *self
+            .chunks_by_type
+            .entry(file_type.to_string())
+            .or_default() += source.chunk_count;
+    }
+
+    /// Add the counts of `other` to these.
+    pub fn merge(&mut self, other: &ContentStats) {
+        self.tokens_estimate += other.tokens_estimate;
+        for (file_type, chunks) in &other.chunks_by_type {
+            *self.chunks_by_type.entry(file_type.clone()).or_default() += chunks;
+        }
+    }
+
+    /// Average estimated tokens per chunk, or 0 without chunks.
+    pub fn avg_chunk_tokens(&self) -> u64 {
+        let chunks: u64 = self.chunks_by_type.values().map(|&n| n as u64).sum();
+        self.tokens_estimate.checked_div(chunks).unwrap_or(0)
+    }
+}
+
+/// What a learn would do, from [`crate::dry_run`]: nothing is embedded or
+/// written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunReport {
+    /// Files and inline texts that would be learned
+    pub sources_count: u32,
+
+    /// Chunks they split into
+    pub chunks_count: u32,
+
+    /// Bytes of text they hold
+    pub bytes_processed: u64,
+
+    /// Tokens and chunks by file type
+    pub content: ContentStats,
+
+    /// Files under the given directories that discovery skips, with why
+    pub excluded: Vec<ExcludedFile>,
+
+    /// Files that could not be parsed and would be skipped
+    pub errors: Vec<LearnError>,
+
+    /// Embedding provider and model the learn would use
+    pub provider: String,
+    pub model: String,
+
+    /// Whether the provider runs on this machine, so embedding costs no API
+    /// fees
+    pub local_provider: bool,
+
+    /// Estimated seconds to embed all chunks, timed on a sample; `None`
+    /// when the provider could not be reached
+    pub embed_secs_estimate: Option<f64>,
+
+    /// Why the embedding time could not be estimated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embed_estimate_error: Option<String>,
+
+    /// Time taken by the dry run
+    pub duration_secs: f64,
+}
+
+/// A file a learn skips during discovery.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedFile {
+    /// Normalized path
+    pub path: String,
+
+    /// Exclusion that matched (`default pattern '/target/'`,
+    /// `exclude pattern '/drafts/'`, ...)
+    pub reason: String,
+}
+
 /// Options for the ask operation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AskOptions {
     /// Knowledge base name
     pub base_name: String,
@@ -162,8 +1068,408 @@ pub struct AskOptions {
     /// Query text
     pub query: String,
 
-    /// Number of chunks to retrieve
+    /// Number of chunks to keep for the answer; `None` uses the base's
+    /// `retrieval.top_k`
+    pub top_k: Option<u32>,
+
+    /// Number of candidates to retrieve before filtering and re-ranking
+    /// down to `top_k`; `None` uses the base's `retrieval.candidates`
+    pub candidates: Option<u32>,
+
+    /// Only retrieve chunks of this namespace; `None` searches the whole base
+    pub namespace: Option<String>,
+
+    /// Only retrieve chunks passing these filters, such as a source path
+    /// prefix or glob; the default retrieves from every source
+    pub filters: SearchFilters,
+
+    /// Trade relevance for variety among the retrieved chunks (Maximal
+    /// Marginal Relevance), from 0.0 (relevance only) to 1.0; `None` keeps
+    /// plain top-k retrieval
+    pub diversity: Option<f32>,
+
+    /// Pick the relevant sources by their summaries first, then retrieve
+    /// chunks only from them (needs `summarize`)
+    pub hierarchical: bool,
+
+    /// Retrieve code and docs with separate queries, this many chunks of
+    /// each, and interleave them; `None` retrieves `top_k` chunks of any
+    /// kind
+    pub mix: Option<Mix>,
+
+    /// Reuse answers cached for the same question and retrieved chunks, and
+    /// cache new ones (RAG answers of unencrypted bases only)
+    pub cache: bool,
+
+    /// In RAG answers, sample this many answers and keep the one an LLM
+    /// judge rates best; `None` generates a single answer
+    pub best_of: Option<u32>,
+
+    /// With `best_of`, have the judge write a final answer from the samples
+    pub synthesize: bool,
+
+    /// In RAG answers, answer with the retrieved sentences that best match
+    /// the query instead of calling the LLM
+    pub extractive: bool,
+
+    /// In RAG answers, have the LLM check each claim of the answer against
+    /// the retrieved chunks (see [`crate::rag::verify`])
+    pub verify: bool,
+
+    /// In RAG answers, instructions on the form of the answer (such as a
+    /// bulleted summary or numbered steps) in place of a concise answer;
+    /// `None` keeps the concise answer
+    pub style: Option<String>,
+
+    /// Configured providers (`llm.providers`), for the endpoint and timeout
+    /// of the embedding provider and, in RAG answers, the LLM provider
+    pub provider_configs: HashMap<String, ProviderConfig>,
+
+    /// Abandons retrieval and answer generation when cancelled
+    pub cancel: CancellationToken,
+}
+
+/// Options for the refresh operation.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshOptions {
+    /// Knowledge base name
+    pub base_name: String,
+
+    /// Configured providers (`llm.providers`), for the endpoint and timeout
+    /// of the embedding provider
+    pub provider_configs: HashMap<String, ProviderConfig>,
+
+    /// Stops refreshing between feeds and batches
+    pub cancel: CancellationToken,
+}
+
+/// Options for the tag operation.
+#[derive(Debug, Clone, Default)]
+pub struct TagOptions {
+    /// Knowledge base name
+    pub base_name: String,
+
+    /// Path of the learned file, as given to `learn` or as a path suffix
+    pub path: String,
+
+    /// Tags to add
+    pub add: Vec<String>,
+
+    /// Tags to remove (derived or previously added)
+    pub remove: Vec<String>,
+
+    /// New description; an empty string clears it
+    pub description: Option<String>,
+
+    /// New score multiplier (above 0); 1 clears it
+    pub weight: Option<f32>,
+
+    /// Pin or unpin the source
+    pub pinned: Option<bool>,
+}
+
+/// Result of the tag operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagResult {
+    /// The updated source record
+    pub source: KnowledgeSource,
+
+    /// Tags now stored on the source's chunks
+    pub tags: Vec<String>,
+
+    /// Number of chunks updated
+    pub chunks_updated: u32,
+}
+
+/// Options for the merge operation.
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// Knowledge base to merge into
+    pub target: String,
+
+    /// Knowledge bases to copy chunks and sources from, in order
+    pub sources: Vec<String>,
+
+    /// Where to create the target if it does not exist yet (defaults to the
+    /// workspace; existing bases stay where they are)
+    pub scope: Option<KnowledgeScope>,
+}
+
+/// Result of the merge operation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeStats {
+    /// Number of sources copied into the target
+    pub sources_added: u32,
+
+    /// Number of chunks copied into the target
+    pub chunks_added: u32,
+
+    /// Sources skipped because the target already held their content
+    pub duplicate_sources: u32,
+
+    /// Chunks of the skipped sources
+    pub duplicate_chunks: u32,
+
+    /// Whether the target was created by the merge
+    pub created: bool,
+
+    /// Duration in seconds
+    pub duration_secs: f64,
+}
+
+/// A question of a retrieval query set and the sources that answer it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Question to retrieve chunks for
+    pub query: String,
+
+    /// Paths of the sources a good retrieval returns; a path matches the
+    /// sources it is the whole path or a trailing part of (`guide.md`
+    /// matches `docs/guide.md`)
+    pub expected: Vec<String>,
+}
+
+/// Chunk size and overlap to try in a tuning run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkSetting {
+    /// Chunk size in tokens/characters
+    pub chunk_size: u32,
+
+    /// Overlap between chunks
+    pub chunk_overlap: u32,
+}
+
+impl ChunkSetting {
+    /// Settings tried when none are given: half, the default and twice the
+    /// default chunk size, each with an eighth of it as overlap.
+    pub fn defaults() -> Vec<Self> {
+        [256, default_chunk_size(), 1024]
+            .into_iter()
+            .map(|chunk_size| Self {
+                chunk_size,
+                chunk_overlap: chunk_size / 8,
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ChunkSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.chunk_size, self.chunk_overlap)
+    }
+}
+
+impl std::str::FromStr for ChunkSetting {
+    type Err = String;
+
+    /// Parse `SIZE:OVERLAP`, e.g. `512:64`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid chunk setting '{}' (expected SIZE:OVERLAP, e.g. 512:64)",
+                s
+            )
+        };
+        let (size, overlap) = s.split_once(':').ok_or_else(invalid)?;
+        let chunk_size: u32 = size.trim().parse().map_err(|_| invalid())?;
+        let chunk_overlap: u32 = overlap.trim().parse().map_err(|_| invalid())?;
+        if chunk_size == 0 || chunk_overlap >= chunk_size {
+            return Err(format!(
+                "Invalid chunk setting '{}': the size must be above 0 and above the overlap",
+                s
+            ));
+        }
+        Ok(Self {
+            chunk_size,
+            chunk_overlap,
+        })
+    }
+}
+
+/// Options for the tune operation.
+#[derive(Debug, Clone)]
+pub struct TuneOptions {
+    /// Knowledge base whose provider, model and other settings are tuned for
+    /// (its defaults when it does not exist yet)
+    pub base_name: String,
+
+    /// Sample files or directories to learn at every setting
+    pub paths: Vec<PathBuf>,
+
+    /// Questions to score every setting with
+    pub cases: Vec<EvalCase>,
+
+    /// Chunk settings to try; empty tries [`ChunkSetting::defaults`]
+    pub settings: Vec<ChunkSetting>,
+
+    /// Number of chunks retrieved per question
     pub top_k: u32,
+
+    /// Configured providers (`llm.providers`), for the endpoint and timeout
+    /// of the embedding provider
+    pub provider_configs: HashMap<String, ProviderConfig>,
+
+    /// Abandons the run when cancelled
+    pub cancel: CancellationToken,
+}
+
+/// Retrieval quality of one chunk setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuneScore {
+    /// Setting the sample was learned with
+    pub setting: ChunkSetting,
+
+    /// Chunks the sample was split into
+    pub chunks_count: u32,
+
+    /// Mean share of each question's expected sources found in its top-k
+    /// chunks, from 0 to 1
+    pub recall: f32,
+
+    /// Mean reciprocal rank of the first chunk of an expected source (0 when
+    /// none is retrieved), from 0 to 1
+    pub mrr: f32,
+
+    /// Seconds spent learning the sample
+    pub learn_secs: f64,
+}
+
+/// Result of the tune operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuneResult {
+    /// Scores in the order the settings were tried
+    pub scores: Vec<TuneScore>,
+
+    /// Setting with the best MRR, then recall, then fewest chunks
+    pub best: ChunkSetting,
+
+    /// Duration in seconds
+    pub duration_secs: f64,
+}
+
+/// Options for the bench operation.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Sections of the generated markdown corpus (about 1 KB each); the
+    /// code corpus has three functions per section
+    pub sections: usize,
+
+    /// Sizes of the indexes to time searches on, in chunks
+    pub index_sizes: Vec<usize>,
+
+    /// Searches timed per index
+    pub queries: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            sections: 2_000,
+            index_sizes: vec![1_000, 10_000],
+            queries: 50,
+        }
+    }
+}
+
+/// Chunking speed on one generated corpus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkBench {
+    /// `markdown` or `code`
+    pub corpus: String,
+
+    /// Size of the corpus
+    pub bytes: u64,
+
+    /// Chunks it was split into
+    pub chunks: u32,
+
+    /// Seconds spent chunking
+    pub secs: f64,
+
+    pub mb_per_sec: f64,
+}
+
+/// Embedding speed of a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedBench {
+    pub provider: String,
+
+    pub model: String,
+
+    /// Texts embedded (in one batch)
+    pub texts: u32,
+
+    /// Seconds spent embedding
+    pub secs: f64,
+
+    pub texts_per_sec: f64,
+}
+
+/// Search latency at one index size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchBench {
+    /// Chunks in the index
+    pub index_size: u32,
+
+    /// Searches timed
+    pub queries: u32,
+
+    /// Seconds spent writing the index
+    pub build_secs: f64,
+
+    pub mean_ms: f64,
+
+    pub p50_ms: f64,
+
+    pub p95_ms: f64,
+}
+
+/// Result of the bench operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Markdown, then code
+    pub chunking: Vec<ChunkBench>,
+
+    pub embedding: EmbedBench,
+
+    /// One entry per index size, in the order given
+    pub search: Vec<SearchBench>,
+
+    /// Duration in seconds
+    pub duration_secs: f64,
+}
+
+/// Options for the summarize operation.
+#[derive(Debug, Clone, Default)]
+pub struct SummarizeOptions {
+    /// Knowledge base name
+    pub base_name: String,
+
+    /// LLM provider that writes the summaries
+    pub llm_provider: String,
+
+    /// LLM model that writes the summaries
+    pub llm_model: String,
+
+    /// Configured providers (`llm.providers`), for the endpoint and timeout
+    /// of the LLM and embedding providers
+    pub provider_configs: HashMap<String, ProviderConfig>,
+
+    /// Stops summarizing between sources
+    pub cancel: CancellationToken,
+}
+
+/// Result of the summarize operation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SummarizeStats {
+    /// Sources summarized by the LLM
+    pub sources_summarized: u32,
+
+    /// Directories summarized by the LLM
+    pub directories_summarized: u32,
+
+    /// Summaries kept because their content did not change
+    pub summaries_reused: u32,
 }
 
 /// Result from a knowledge retrieval.
@@ -174,6 +1480,16 @@ pub struct AskResult {
 
     /// Relevance scores
     pub scores: Vec<f32>,
+
+    /// Byte ranges of each chunk's text holding words of the query, in
+    /// chunk order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<Vec<(usize, usize)>>,
+
+    /// IDs of chunks that looked like prompt injection (stripped or marked
+    /// according to the base's guardrails)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flagged: Vec<String>,
 }
 
 /// Statistics for a knowledge base.
@@ -191,8 +1507,23 @@ pub struct BaseStats {
     /// Database size in bytes
     pub db_size_bytes: u64,
 
+    /// Size of the indexed chunks by file type
+    pub content: ContentStats,
+
+    /// Embedding provider of the base
+    pub provider: String,
+
+    /// Embedding model of the base
+    pub model: String,
+
     /// Last learn timestamp
     pub last_learn_at: Option<DateTime<Utc>>,
+
+    /// Where the base is stored
+    pub scope: KnowledgeScope,
+
+    /// Base directory
+    pub base_dir: PathBuf,
 }
 
 /// Internal chunk candidate before embedding.
@@ -34,6 +34,21 @@ pub trait VectorIndex: Send + Sync {
         top_k: usize,
     ) -> AppResult<Vec<(KnowledgeChunk, f32)>>;
 
+    /// Search like [`VectorIndex::search`], returning only chunk ids and scores.
+    ///
+    /// Backends that store columns separately skip loading text and metadata;
+    /// load the chunks that are kept with [`VectorIndex::fetch_chunks`].
+    fn search_ids(&self, query_embedding: &[f32], top_k: usize) -> AppResult<Vec<(String, f32)>> {
+        Ok(self
+            .search(query_embedding, top_k)?
+            .into_iter()
+            .map(|(chunk, score)| (chunk.id, score))
+            .collect())
+    }
+
+    /// Load chunks by id, in the order given. Unknown ids are skipped.
+    fn fetch_chunks(&self, ids: &[String]) -> AppResult<Vec<KnowledgeChunk>>;
+
     /// Get statistics about the index.
     ///
     /// Returns (sources_count, chunks_count).
@@ -0,0 +1,150 @@
+//! Process-wide cache of open indexes and embedding engines.
+//!
+//! Opening a base's LanceDB index and creating its embedding provider take
+//! hundreds of milliseconds, which every `ask` used to pay before retrieval
+//! started. Processes that ask many questions (a REPL, a server, a batch of
+//! queries) keep both here instead, keyed by path.
+//!
+//! A cached index is reopened when the base's files change on disk: its
+//! config, its sources, or the table's versions (a learn, clean or compaction
+//! by this or another process). Engines recreate a base's provider when its
+//! embedding config changes (see [`EmbeddingEngine`]).
+
+use crate::config;
+use crate::embeddings::EmbeddingEngine;
+use crate::lancedb_index::LanceDbIndex;
+use crate::types::KnowledgeBaseConfig;
+use guided_core::config::ProviderConfig;
+use guided_core::AppResult;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Modification times of the files an open index depends on.
+type Fingerprint = Vec<Option<SystemTime>>;
+
+/// An open index and what it was opened from.
+struct CachedIndex {
+    fingerprint: Fingerprint,
+    embedding_dim: u32,
+    index: LanceDbIndex,
+}
+
+/// An engine and the provider configs it was created with.
+struct CachedEngine {
+    provider_configs: serde_json::Value,
+    engine: Arc<EmbeddingEngine>,
+}
+
+fn indexes() -> &'static Mutex<HashMap<PathBuf, CachedIndex>> {
+    static INDEXES: OnceLock<Mutex<HashMap<PathBuf, CachedIndex>>> = OnceLock::new();
+    INDEXES.get_or_init(Default::default)
+}
+
+fn engines() -> &'static Mutex<HashMap<PathBuf, CachedEngine>> {
+    static ENGINES: OnceLock<Mutex<HashMap<PathBuf, CachedEngine>>> = OnceLock::new();
+    ENGINES.get_or_init(Default::default)
+}
+
+/// The open index of a base, `None` if it was never learned.
+///
+/// Reuses the index opened by an earlier call unless the base changed on
+/// disk since. The lock is never held while the index opens, so two callers
+/// may open the same index at once; the last one opened is kept.
+pub async fn index(
+    workspace: &Path,
+    base_name: &str,
+    config: &KnowledgeBaseConfig,
+) -> AppResult<Option<LanceDbIndex>> {
+    let index_path = config::get_index_path(workspace, base_name);
+    if !index_path.exists() {
+        forget(workspace, base_name);
+        return Ok(None);
+    }
+
+    let fingerprint = fingerprint(workspace, base_name);
+    if let Some(cached) = indexes().lock().unwrap().get(&index_path) {
+        if cached.fingerprint == fingerprint && cached.embedding_dim == config.embedding_dim {
+            tracing::debug!("Reusing open index of base '{}'", base_name);
+            return Ok(Some(cached.index.clone()));
+        }
+    }
+
+    tracing::debug!("Opening index of base '{}'", base_name);
+    let index = LanceDbIndex::new(&index_path, "chunks", config.embedding_dim as usize).await?;
+    indexes().lock().unwrap().insert(
+        index_path,
+        CachedIndex {
+            fingerprint,
+            embedding_dim: config.embedding_dim,
+            index: index.clone(),
+        },
+    );
+    Ok(Some(index))
+}
+
+/// The embedding engine of a workspace, shared by every caller using the
+/// same provider configs.
+pub fn engine(
+    workspace: &Path,
+    provider_configs: &HashMap<String, ProviderConfig>,
+) -> Arc<EmbeddingEngine> {
+    // Compared as JSON: provider configs have no equality of their own
+    let key = serde_json::to_value(provider_configs).unwrap_or_default();
+
+    let mut engines = engines().lock().unwrap();
+    if let Some(cached) = engines.get(workspace) {
+        if cached.provider_configs == key {
+            return Arc::clone(&cached.engine);
+        }
+    }
+
+    let engine = Arc::new(
+        EmbeddingEngine::new(workspace.to_path_buf())
+            .with_provider_configs(provider_configs.clone()),
+    );
+    engines.insert(
+        workspace.to_path_buf(),
+        CachedEngine {
+            provider_configs: key,
+            engine: Arc::clone(&engine),
+        },
+    );
+    engine
+}
+
+/// Drop the cached index and provider of a base, so the next call opens
+/// them again.
+pub fn forget(workspace: &Path, base_name: &str) {
+    indexes()
+        .lock()
+        .unwrap()
+        .remove(&config::get_index_path(workspace, base_name));
+    if let Some(cached) = engines().lock().unwrap().get(workspace) {
+        cached.engine.evict_provider(base_name);
+    }
+}
+
+/// Drop every cached index and engine.
+pub fn clear() {
+    indexes().lock().unwrap().clear();
+    engines().lock().unwrap().clear();
+}
+
+/// Modification times of the base's config and sources, and of the index
+/// directories LanceDB writes a new version into on every change.
+fn fingerprint(workspace: &Path, base_name: &str) -> Fingerprint {
+    let index_path = config::get_index_path(workspace, base_name);
+    let table = index_path.join("chunks.lance");
+    [
+        config::get_config_path(workspace, base_name),
+        config::get_sources_path(workspace, base_name),
+        index_path,
+        table.join("_versions"),
+        table,
+    ]
+    .iter()
+    .map(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+    .collect()
+}
@@ -0,0 +1,486 @@
+//! RSS and Atom feeds that a base subscribes to (`learn --feed`).
+//!
+//! Subscriptions are stored in the base config. Each sync fetches the feed,
+//! conditionally on the `ETag` / `Last-Modified` of the previous response,
+//! and learns the entries that are new or changed since they were learned,
+//! one dated source per entry. Entries that drop out of the feed stay
+//! learned: feeds only list their latest entries.
+
+use super::http_client;
+use crate::connectors::markdown::{attribute, decode_entities, html_to_markdown};
+use crate::connectors::SyncPlan;
+use crate::metadata::generate_content_hash;
+use crate::types::{FeedSubscription, InlineText, KnowledgeSource, SourceType};
+use chrono::{DateTime, Utc};
+use guided_core::{AppError, AppResult, CancellationToken};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{StatusCode, Url};
+use std::collections::{HashMap, HashSet};
+
+/// A parsed RSS or Atom feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    /// Feed title
+    pub title: Option<String>,
+
+    /// Entries, in feed order
+    pub entries: Vec<FeedEntry>,
+}
+
+/// An entry (RSS item) of a feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    /// `<guid>` or `<id>`, else the link or title
+    pub id: String,
+
+    /// Entry title
+    pub title: String,
+
+    /// Link to the entry's page, as written in the feed
+    pub link: Option<String>,
+
+    /// Publication date (`pubDate`, `published`, else `updated`)
+    pub published_at: Option<DateTime<Utc>>,
+
+    /// Content converted to markdown
+    pub markdown: String,
+}
+
+impl FeedEntry {
+    /// Changes whenever the entry's title or content does.
+    fn version(&self) -> String {
+        generate_content_hash(&format!("{}\n{}", self.title, self.markdown))[..16].to_string()
+    }
+}
+
+/// Parse an RSS (0.9x, 1.0, 2.0) or Atom feed.
+pub fn parse(xml: &str) -> AppResult<Feed> {
+    let atom = !elements(xml, "feed").is_empty();
+    if !atom && elements(xml, "channel").is_empty() {
+        return Err(AppError::Knowledge("Not an RSS or Atom feed".to_string()));
+    }
+
+    let entry = if atom { "entry" } else { "item" };
+    let entries = elements(xml, entry)
+        .into_iter()
+        .filter_map(|(_, content)| {
+            if atom {
+                atom_entry(content)
+            } else {
+                rss_item(content)
+            }
+        })
+        .collect();
+
+    // The feed's own title comes before its entries
+    let head = match xml.find(&format!("<{}", entry)) {
+        Some(end) => &xml[..end],
+        None => xml,
+    };
+    let title = first_text(head, "title");
+
+    Ok(Feed { title, entries })
+}
+
+/// Fetch a feed and plan learning its new and changed entries.
+///
+/// `sources` are the base's tracked sources; those with a remote id of this
+/// feed are matched to its entries by id and version. Also returns the
+/// subscription as it should be stored once the entries are learned, with
+/// the response's validators and the sync time.
+pub async fn plan_sync(
+    subscription: &FeedSubscription,
+    sources: &[KnowledgeSource],
+    cancel: &CancellationToken,
+) -> AppResult<(SyncPlan, FeedSubscription)> {
+    let mut synced = subscription.clone();
+    let mut plan = SyncPlan::default();
+    let Some(feed) = fetch(&mut synced, cancel).await? else {
+        tracing::info!("Feed {} has not changed", subscription.url);
+        return Ok((plan, synced));
+    };
+    tracing::info!(
+        "Feed {} lists {} entries",
+        subscription.url,
+        feed.entries.len()
+    );
+
+    let prefix = format!("feed:{}#", subscription.url);
+    let learned: HashMap<&str, &KnowledgeSource> = sources
+        .iter()
+        .filter_map(|s| Some((s.remote_id.as_deref()?.strip_prefix(&prefix)?, s)))
+        .collect();
+    let feed_url = Url::parse(&subscription.url).ok();
+
+    let mut seen = HashSet::new();
+    for entry in feed.entries {
+        if !seen.insert(entry.id.clone()) {
+            continue;
+        }
+        let version = entry.version();
+        let previous = learned.get(entry.id.as_str()).copied();
+        if previous.is_some_and(|s| s.remote_version.as_deref() == Some(&version)) {
+            plan.unchanged += 1;
+            continue;
+        }
+        plan.stale.extend(previous.cloned());
+
+        // Relative links are resolved against the feed
+        let url = entry
+            .link
+            .as_deref()
+            .and_then(|link| match &feed_url {
+                Some(base) => base.join(link).ok().map(String::from),
+                None => Some(link.to_string()),
+            })
+            .unwrap_or_else(|| format!("{}#{}", subscription.url, entry.id));
+        let text = match entry.published_at {
+            Some(date) => format!(
+                "# {}\n\n_Published {}_\n\n{}",
+                entry.title,
+                date.format("%Y-%m-%d"),
+                entry.markdown
+            ),
+            None => format!("# {}\n\n{}", entry.title, entry.markdown),
+        };
+        plan.texts.push(InlineText {
+            title: entry.title,
+            text,
+            tags: Vec::new(),
+            url: Some(url),
+            remote_id: Some(format!("{}{}", prefix, entry.id)),
+            remote_version: Some(version),
+            source_type: Some(SourceType::Feed),
+            published_at: entry.published_at,
+        });
+    }
+    Ok((plan, synced))
+}
+
+/// Fetch and parse a feed, or `None` when the server says it did not change
+/// since `subscription`'s validators. Updates the validators and sync time.
+async fn fetch(
+    subscription: &mut FeedSubscription,
+    cancel: &CancellationToken,
+) -> AppResult<Option<Feed>> {
+    let mut request = http_client()?.get(&subscription.url);
+    if let Some(etag) = &subscription.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &subscription.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let url = subscription.url.clone();
+    let response = cancel
+        .run("learn", async {
+            request
+                .send()
+                .await
+                .map_err(|e| AppError::Knowledge(format!("Failed to fetch feed {}: {}", url, e)))
+        })
+        .await?;
+    subscription.last_synced_at = Some(Utc::now());
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(AppError::Knowledge(format!(
+            "Feed {} returned {}",
+            url,
+            response.status()
+        )));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    subscription.etag = header(ETAG);
+    subscription.last_modified = header(LAST_MODIFIED);
+
+    let xml = cancel
+        .run("learn", async {
+            response
+                .text()
+                .await
+                .map_err(|e| AppError::Knowledge(format!("Failed to read feed {}: {}", url, e)))
+        })
+        .await?;
+    parse(&xml)
+        .map(Some)
+        .map_err(|e| AppError::Knowledge(format!("{}: {}", url, e)))
+}
+
+fn rss_item(item: &str) -> Option<FeedEntry> {
+    let title = first_text(item, "title");
+    let link = first_text(item, "link");
+    let id = first_text(item, "guid")
+        .or_else(|| link.clone())
+        .or_else(|| title.clone())?;
+    let content = first_text(item, "content:encoded")
+        .or_else(|| first_text(item, "description"))
+        .unwrap_or_default();
+
+    Some(FeedEntry {
+        title: single_line(&title.unwrap_or_else(|| id.clone())),
+        published_at: first_text(item, "pubDate")
+            .or_else(|| first_text(item, "dc:date"))
+            .and_then(|date| parse_date(&date)),
+        markdown: html_to_markdown(&content),
+        id,
+        link,
+    })
+}
+
+fn atom_entry(entry: &str) -> Option<FeedEntry> {
+    let title = first_text(entry, "title");
+    let link = elements(entry, "link")
+        .into_iter()
+        .filter(|(tag, _)| attribute(tag, "rel").is_none_or(|rel| rel == "alternate"))
+        .find_map(|(tag, _)| attribute(tag, "href"));
+    let id = first_text(entry, "id")
+        .or_else(|| link.clone())
+        .or_else(|| title.clone())?;
+
+    // Atom content is plain text unless its type says otherwise
+    let content = elements(entry, "content")
+        .into_iter()
+        .chain(elements(entry, "summary"))
+        .next();
+    let markdown = match content {
+        Some((tag, content)) => match attribute(tag, "type").as_deref() {
+            Some("html") | Some("text/html") => html_to_markdown(&text(content)),
+            Some("xhtml") => html_to_markdown(content),
+            _ => text(content),
+        },
+        None => String::new(),
+    };
+
+    Some(FeedEntry {
+        title: single_line(&title.unwrap_or_else(|| id.clone())),
+        published_at: first_text(entry, "published")
+            .or_else(|| first_text(entry, "updated"))
+            .and_then(|date| parse_date(&date)),
+        markdown,
+        id,
+        link,
+    })
+}
+
+/// The elements named `name`, as (start tag source, content). Elements of
+/// the same name are not expected to nest.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        // `<link` must not match `<links`
+        if !after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let Some(end) = after.find('>') else {
+            break;
+        };
+        let tag = &after[..end];
+        let body = &after[end + 1..];
+        if tag.ends_with('/') {
+            found.push((tag, ""));
+            rest = body;
+            continue;
+        }
+        let Some(close_at) = body.find(&close) else {
+            break;
+        };
+        found.push((tag, &body[..close_at]));
+        rest = &body[close_at + close.len()..];
+    }
+    found
+}
+
+/// Text of the first non-empty element named `name`.
+fn first_text(xml: &str, name: &str) -> Option<String> {
+    elements(xml, name)
+        .into_iter()
+        .map(|(_, content)| text(content))
+        .find(|text| !text.is_empty())
+}
+
+/// Text content of an element: CDATA sections as they are, the rest with
+/// character references decoded.
+fn text(content: &str) -> String {
+    let mut out = String::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("<![CDATA[") {
+        out.push_str(&decode_entities(&rest[..start]));
+        let cdata = &rest[start + "<![CDATA[".len()..];
+        let end = cdata.find("]]>").unwrap_or(cdata.len());
+        out.push_str(&cdata[..end]);
+        rest = cdata.get(end + "]]>".len()..).unwrap_or("");
+    }
+    out.push_str(&decode_entities(rest));
+    out.trim().to_string()
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse an RFC 3339 (Atom, `dc:date`) or RFC 2822 (RSS) date.
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .or_else(|_| DateTime::parse_from_rfc2822(date))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stub_http::{self, Reply};
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+<channel>
+  <title>Acme releases</title>
+  <link>https://acme.dev/releases</link>
+  <item>
+    <title>v1.2 &amp; friends</title>
+    <link>/releases/1.2</link>
+    <guid isPermaLink="false">release-1.2</guid>
+    <pubDate>Tue, 06 Oct 2026 09:00:00 GMT</pubDate>
+    <description>Short summary</description>
+    <content:encoded><![CDATA[<p>Adds <b>streaming</b> exports.</p>]]></content:encoded>
+  </item>
+  <item>
+    <title>v1.1</title>
+    <link>https://acme.dev/releases/1.1</link>
+    <description>&lt;p&gt;Fixes the importer.&lt;/p&gt;</description>
+  </item>
+</channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Changelog</title>
+  <link rel="self" href="https://acme.dev/changelog.atom"/>
+  <entry>
+    <title type="text">Faster search</title>
+    <id>tag:acme.dev,2026:42</id>
+    <link rel="alternate" href="https://acme.dev/changelog/42"/>
+    <updated>2026-10-02T12:00:00Z</updated>
+    <content type="html">&lt;ul&gt;&lt;li&gt;Search is 2x faster&lt;/li&gt;&lt;/ul&gt;</content>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse(RSS).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Acme releases"));
+        assert_eq!(feed.entries.len(), 2);
+
+        let first = &feed.entries[0];
+        assert_eq!(first.id, "release-1.2");
+        assert_eq!(first.title, "v1.2 & friends");
+        assert_eq!(first.link.as_deref(), Some("/releases/1.2"));
+        assert_eq!(
+            first.published_at.unwrap().to_rfc3339(),
+            "2026-10-06T09:00:00+00:00"
+        );
+        assert_eq!(first.markdown, "Adds **streaming** exports.");
+
+        let second = &feed.entries[1];
+        assert_eq!(second.id, "https://acme.dev/releases/1.1");
+        assert_eq!(second.published_at, None);
+        assert_eq!(second.markdown, "Fixes the importer.");
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let feed = parse(ATOM).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Changelog"));
+        let entry = &feed.entries[0];
+        assert_eq!(entry.id, "tag:acme.dev,2026:42");
+        assert_eq!(entry.link.as_deref(), Some("https://acme.dev/changelog/42"));
+        assert_eq!(
+            entry.published_at.unwrap().to_rfc3339(),
+            "2026-10-02T12:00:00+00:00"
+        );
+        assert_eq!(entry.markdown, "- Search is 2x faster");
+    }
+
+    #[test]
+    fn test_parse_rejects_other_documents() {
+        let err = parse("<html><body>Hi</body></html>").unwrap_err();
+        assert!(
+            err.to_string().contains("Not an RSS or Atom feed"),
+            "{}",
+            err
+        );
+    }
+
+    fn releases(request: &str) -> Reply {
+        if request.contains("if-none-match: \"v1\"") {
+            return Reply {
+                status: 304,
+                content_type: "text/plain",
+                body: String::new(),
+                headers: Vec::new(),
+            };
+        }
+        Reply::ok("application/rss+xml", RSS).with_header("ETag", "\"v1\"")
+    }
+
+    #[tokio::test]
+    async fn test_plan_sync_learns_new_entries_once() {
+        let server = stub_http::serve(releases).await;
+        let subscription = FeedSubscription {
+            url: format!("{}/releases.xml", server),
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+
+        let (plan, synced) = plan_sync(&subscription, &[], &cancel).await.unwrap();
+        assert_eq!(synced.etag.as_deref(), Some("\"v1\""));
+        assert!(synced.last_synced_at.is_some());
+        assert_eq!(plan.texts.len(), 2);
+
+        let release = &plan.texts[0];
+        assert_eq!(
+            release.url.as_deref(),
+            Some(format!("{}/releases/1.2", server).as_str())
+        );
+        assert_eq!(release.source_type, Some(SourceType::Feed));
+        assert!(release.published_at.is_some());
+        assert_eq!(
+            release.text,
+            "# v1.2 & friends\n\n_Published 2026-10-06_\n\nAdds **streaming** exports."
+        );
+
+        // Entries already learned are skipped
+        let learned: Vec<KnowledgeSource> = plan
+            .texts
+            .iter()
+            .map(|text| KnowledgeSource {
+                remote_id: text.remote_id.clone(),
+                remote_version: text.remote_version.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let (plan, _) = plan_sync(&subscription, &learned, &cancel).await.unwrap();
+        assert!(plan.texts.is_empty());
+        assert_eq!(plan.unchanged, 2);
+
+        // An unchanged feed is not downloaded again
+        let (plan, again) = plan_sync(&synced, &[], &cancel).await.unwrap();
+        assert!(plan.texts.is_empty());
+        assert_eq!(plan.unchanged, 0);
+        assert_eq!(again.etag, synced.etag);
+    }
+}
@@ -0,0 +1,738 @@
+//! Web pages for `learn --url`, and crawling of whole sites.
+//!
+//! Without crawling, each URL is fetched once and converted to markdown; a
+//! sitemap URL stands for the pages it lists. With `--crawl`, links are
+//! followed breadth-first up to `max_depth`, and the sites' sitemaps seed
+//! the pages under each start URL. A crawl:
+//!
+//! - obeys robots.txt (the `guided` group, else `*`), including `Crawl-delay`
+//! - waits between requests to the same site
+//! - learns each page once, keyed by its canonical URL (after redirects,
+//!   `<link rel="canonical">`, without fragments and `utm_*` parameters)
+//!
+//! RSS and Atom feeds that a base subscribes to are read by [`feed`].
+
+pub mod feed;
+mod robots;
+
+pub use robots::Robots;
+
+use crate::connectors::markdown::{attribute, decode_entities, html_to_markdown};
+use crate::progress::ProgressReporter;
+use crate::types::CrawlOptions;
+use guided_core::{AppError, AppResult, CancellationToken};
+use reqwest::Url;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// User agent sent with every request, and matched against robots.txt.
+const USER_AGENT: &str = concat!("guided/", env!("CARGO_PKG_VERSION"));
+
+/// Time allowed to connect to a site.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for one request, including reading the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pages learned at most from one `learn` run.
+pub const MAX_PAGES: usize = 500;
+
+/// Sitemaps read at most per site (an index and the sitemaps it lists).
+const MAX_SITEMAPS: usize = 20;
+
+/// Link targets that are never pages.
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "css", "js", "pdf", "zip", "gz", "tgz",
+    "tar", "mp3", "mp4", "woff", "woff2", "ttf", "exe", "dmg",
+];
+
+/// A fetched page, converted to markdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebPage {
+    /// Canonical URL of the page
+    pub url: String,
+
+    /// `<title>`, or the last path segment
+    pub title: String,
+
+    /// Page content
+    pub markdown: String,
+}
+
+/// Parse the URLs given to `learn`, which must be absolute http(s) URLs.
+pub fn parse_urls(urls: &[String]) -> AppResult<Vec<Url>> {
+    urls.iter()
+        .map(|url| {
+            Url::parse(url)
+                .ok()
+                .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+                .ok_or_else(|| AppError::Knowledge(format!("Not an http(s) URL: '{}'", url)))
+        })
+        .collect()
+}
+
+/// Fetch `urls` (crawling from them when `crawl` is set) and convert the
+/// pages to markdown.
+///
+/// Pages that cannot be fetched are skipped with a warning.
+pub async fn fetch_pages(
+    urls: &[Url],
+    crawl: Option<&CrawlOptions>,
+    cancel: &CancellationToken,
+    progress: &ProgressReporter,
+) -> AppResult<Vec<WebPage>> {
+    Crawler {
+        client: http_client()?,
+        crawl: crawl.cloned(),
+        cancel: cancel.clone(),
+        robots: HashMap::new(),
+        last_request: HashMap::new(),
+    }
+    .run(urls, progress)
+    .await
+}
+
+/// HTTP client for web requests, identifying itself as [`USER_AGENT`].
+fn http_client() -> AppResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Knowledge(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// What a response holds.
+enum Body {
+    Html(String),
+    Text(String),
+    Sitemap(String),
+}
+
+struct Crawler {
+    client: reqwest::Client,
+    /// `None` fetches the given pages only
+    crawl: Option<CrawlOptions>,
+    cancel: CancellationToken,
+    /// robots.txt rules by origin (crawls only)
+    robots: HashMap<String, Robots>,
+    /// Time of the last request by origin (crawls only)
+    last_request: HashMap<String, Instant>,
+}
+
+impl Crawler {
+    async fn run(mut self, starts: &[Url], progress: &ProgressReporter) -> AppResult<Vec<WebPage>> {
+        // URLs ever queued, and pages learned, by canonical key
+        let mut queued: HashSet<String> = HashSet::new();
+        let mut learned: HashSet<String> = HashSet::new();
+        let mut queue: VecDeque<(Url, u32)> = VecDeque::new();
+
+        for start in starts {
+            if queued.insert(canonical_key(start)) {
+                queue.push_back((start.clone(), 0));
+            }
+        }
+        if self.crawl.as_ref().is_some_and(|crawl| crawl.max_depth > 0) {
+            for start in starts {
+                for url in self.sitemap_pages(start).await? {
+                    if self.in_scope(&url, starts) && queued.insert(canonical_key(&url)) {
+                        queue.push_back((url, 1));
+                    }
+                }
+            }
+        }
+
+        let mut pages = Vec::new();
+        while let Some((url, depth)) = queue.pop_front() {
+            self.cancel.check("learn")?;
+            if pages.len() >= MAX_PAGES {
+                tracing::warn!(
+                    "Stopping after {} pages; {} more were found",
+                    MAX_PAGES,
+                    queue.len() + 1
+                );
+                break;
+            }
+            if self.crawl.is_some() && !self.allowed(&url).await? {
+                tracing::debug!("robots.txt disallows {}", url);
+                continue;
+            }
+
+            progress.fetch(pages.len() as u64 + 1, None, url.as_str());
+            let (final_url, body) = match self.fetch(&url).await {
+                Ok(Some(fetched)) => fetched,
+                Ok(None) => continue,
+                Err(e @ AppError::Cancelled(_)) => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Skipping {}: {}", url, e);
+                    continue;
+                }
+            };
+
+            let html = match body {
+                Body::Sitemap(xml) => {
+                    let (_, locations) = sitemap_locations(&xml);
+                    for location in locations.iter().filter_map(|l| Url::parse(l).ok()) {
+                        if queued.insert(canonical_key(&location)) {
+                            queue.push_back((location, depth + 1));
+                        }
+                    }
+                    continue;
+                }
+                Body::Text(text) => {
+                    if learned.insert(canonical_key(&final_url)) && !text.trim().is_empty() {
+                        pages.push(WebPage {
+                            title: fallback_title(&final_url),
+                            url: final_url.to_string(),
+                            markdown: text,
+                        });
+                    }
+                    continue;
+                }
+                Body::Html(html) => html,
+            };
+
+            // Redirects and canonical links can lead to a page already learned
+            let canonical = canonical_link(&html, &final_url).unwrap_or(final_url.clone());
+            queued.insert(canonical_key(&final_url));
+            queued.insert(canonical_key(&canonical));
+            if !learned.insert(canonical_key(&canonical)) {
+                tracing::debug!("Skipping {}: same page as {}", url, canonical);
+                continue;
+            }
+
+            if let Some(crawl) = &self.crawl {
+                if depth < crawl.max_depth {
+                    for link in links(&html, &final_url) {
+                        if self.in_scope(&link, starts) && queued.insert(canonical_key(&link)) {
+                            queue.push_back((link, depth + 1));
+                        }
+                    }
+                }
+            }
+
+            let markdown = html_to_markdown(&html);
+            if markdown.trim().is_empty() {
+                tracing::debug!("Skipping {}: no text", url);
+                continue;
+            }
+            pages.push(WebPage {
+                title: page_title(&html).unwrap_or_else(|| fallback_title(&canonical)),
+                url: canonical.to_string(),
+                markdown,
+            });
+        }
+
+        tracing::info!("Fetched {} web pages", pages.len());
+        Ok(pages)
+    }
+
+    /// Whether a crawl may follow a link to `url`.
+    fn in_scope(&self, url: &Url, starts: &[Url]) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let extension = url
+            .path()
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        if extension.is_some_and(|e| SKIPPED_EXTENSIONS.contains(&e.as_str())) {
+            return false;
+        }
+        let same_domain = self.crawl.as_ref().is_some_and(|crawl| crawl.same_domain);
+        !same_domain || starts.iter().any(|start| start.host() == url.host())
+    }
+
+    /// Pages listed in the sitemaps of `start`'s site that are under it.
+    async fn sitemap_pages(&mut self, start: &Url) -> AppResult<Vec<Url>> {
+        let robots = self.robots_for(start).await?;
+        let mut sitemaps: VecDeque<String> = robots.sitemaps.iter().cloned().collect();
+        if sitemaps.is_empty() {
+            sitemaps.push_back(format!("{}/sitemap.xml", origin(start)));
+        }
+
+        // Entries outside the start URL's directory belong to other parts
+        // of the site
+        let scope = &start.as_str()[..start.as_str().rfind('/').unwrap_or(0) + 1];
+
+        let mut pages = Vec::new();
+        let mut read = 0;
+        while let Some(sitemap) = sitemaps.pop_front() {
+            if read == MAX_SITEMAPS {
+                tracing::warn!(
+                    "Reading only the first {} sitemaps of {}",
+                    MAX_SITEMAPS,
+                    origin(start)
+                );
+                break;
+            }
+            read += 1;
+
+            let Ok(url) = Url::parse(&sitemap) else {
+                continue;
+            };
+            let xml = match self.fetch(&url).await {
+                Ok(Some((_, Body::Sitemap(xml)))) => xml,
+                Ok(_) => continue,
+                Err(e @ AppError::Cancelled(_)) => return Err(e),
+                Err(e) => {
+                    tracing::debug!("No sitemap at {}: {}", url, e);
+                    continue;
+                }
+            };
+
+            let (is_index, locations) = sitemap_locations(&xml);
+            if is_index {
+                sitemaps.extend(locations);
+            } else {
+                pages.extend(
+                    locations
+                        .iter()
+                        .filter(|l| l.starts_with(scope))
+                        .filter_map(|l| Url::parse(l).ok()),
+                );
+            }
+        }
+
+        tracing::debug!("Sitemaps list {} pages under {}", pages.len(), start);
+        Ok(pages)
+    }
+
+    /// Whether robots.txt lets us fetch `url`.
+    async fn allowed(&mut self, url: &Url) -> AppResult<bool> {
+        let robots = self.robots_for(url).await?;
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        Ok(robots.allows(&path))
+    }
+
+    /// The robots.txt rules of `url`'s site, fetched once per crawl.
+    async fn robots_for(&mut self, url: &Url) -> AppResult<Robots> {
+        let origin = origin(url);
+        if let Some(robots) = self.robots.get(&origin) {
+            return Ok(robots.clone());
+        }
+
+        let robots_url = format!("{}/robots.txt", origin);
+        self.last_request.insert(origin.clone(), Instant::now());
+        let response = self
+            .cancel
+            .run("learn", async {
+                Ok(self.client.get(&robots_url).send().await)
+            })
+            .await?;
+
+        // RFC 9309: a missing robots.txt allows everything, an unreachable
+        // one forbids everything
+        let robots = match response {
+            Ok(response) if response.status().is_success() => {
+                let text = self
+                    .cancel
+                    .run("learn", async {
+                        Ok(response.text().await.unwrap_or_default())
+                    })
+                    .await?;
+                Robots::parse(&text, "guided")
+            }
+            Ok(response) if response.status().is_client_error() => Robots::allow_all(),
+            Ok(response) => {
+                tracing::warn!(
+                    "{} returned {}; not crawling {}",
+                    robots_url,
+                    response.status(),
+                    origin
+                );
+                Robots::disallow_all()
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to fetch {}: {}; not crawling {}",
+                    robots_url,
+                    e,
+                    origin
+                );
+                Robots::disallow_all()
+            }
+        };
+        self.robots.insert(origin, robots.clone());
+        Ok(robots)
+    }
+
+    /// Wait until the site of `url` may be requested again.
+    async fn wait_turn(&mut self, url: &Url) -> AppResult<()> {
+        let Some(crawl) = &self.crawl else {
+            return Ok(());
+        };
+        let origin = origin(url);
+        let crawl_delay = self
+            .robots
+            .get(&origin)
+            .and_then(|robots| robots.crawl_delay);
+        let delay = crawl_delay.map_or(crawl.delay, |d| d.max(crawl.delay));
+
+        if let Some(last) = self.last_request.get(&origin) {
+            let wait = delay.saturating_sub(last.elapsed());
+            if !wait.is_zero() {
+                self.cancel
+                    .run("learn", async {
+                        tokio::time::sleep(wait).await;
+                        Ok(())
+                    })
+                    .await?;
+            }
+        }
+        self.last_request.insert(origin, Instant::now());
+        Ok(())
+    }
+
+    /// Fetch `url`, returning the URL after redirects and the body, or
+    /// `None` for content that is not text.
+    async fn fetch(&mut self, url: &Url) -> AppResult<Option<(Url, Body)>> {
+        self.wait_turn(url).await?;
+
+        let client = &self.client;
+        let (final_url, content_type, text) = self
+            .cancel
+            .run("learn", async {
+                let response = client
+                    .get(url.clone())
+                    .send()
+                    .await
+                    .map_err(|e| AppError::Knowledge(format!("Request failed: {}", e)))?;
+                if !response.status().is_success() {
+                    return Err(AppError::Knowledge(format!("HTTP {}", response.status())));
+                }
+                let final_url = response.url().clone();
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_ascii_lowercase();
+                let text = response
+                    .text()
+                    .await
+                    .map_err(|e| AppError::Knowledge(format!("Failed to read body: {}", e)))?;
+                Ok((final_url, content_type, text))
+            })
+            .await?;
+
+        let is_sitemap = |text: &str| text.contains("<urlset") || text.contains("<sitemapindex");
+        let body = if content_type.contains("html") {
+            Body::Html(text)
+        } else if content_type.contains("xml") && is_sitemap(&text) {
+            Body::Sitemap(text)
+        } else if content_type.starts_with("text/") && !content_type.contains("xml") {
+            Body::Text(text)
+        } else {
+            tracing::debug!("Skipping {}: content type '{}'", final_url, content_type);
+            return Ok(None);
+        };
+        Ok(Some((final_url, body)))
+    }
+}
+
+/// `scheme://host[:port]` of a URL.
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Key under which URLs naming the same page compare equal: without
+/// fragment, `utm_*` parameters, trailing slash or `index.html`.
+pub fn canonical_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    let path = url.path();
+    let path = path.strip_suffix("index.html").unwrap_or(path);
+    let path = match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    url.set_path(&path);
+
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    url.to_string()
+}
+
+/// Source of each tag in an HTML document, without the angle brackets.
+fn tags(html: &str) -> impl Iterator<Item = &str> {
+    html.split('<')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('>').map(|(tag, _)| tag))
+}
+
+/// Lower-case element name of a tag's source.
+fn tag_name(tag: &str) -> String {
+    tag.split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Links of a page, resolved against its URL (or `<base href>`).
+fn links(html: &str, page: &Url) -> Vec<Url> {
+    let mut base = page.clone();
+    let mut links = Vec::new();
+    for tag in tags(html) {
+        match tag_name(tag).as_str() {
+            "base" => {
+                if let Some(url) = attribute(tag, "href").and_then(|href| page.join(&href).ok()) {
+                    base = url;
+                }
+            }
+            "a" => {
+                let Some(href) = attribute(tag, "href") else {
+                    continue;
+                };
+                let href = href.trim();
+                let ignored = ["#", "mailto:", "javascript:", "tel:"];
+                if href.is_empty() || ignored.iter().any(|prefix| href.starts_with(prefix)) {
+                    continue;
+                }
+                if let Ok(mut url) = base.join(href) {
+                    url.set_fragment(None);
+                    links.push(url);
+                }
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// Target of `<link rel="canonical">`, if the page has one.
+fn canonical_link(html: &str, page: &Url) -> Option<Url> {
+    tags(html)
+        .filter(|tag| tag_name(tag) == "link")
+        .find(|tag| attribute(tag, "rel").is_some_and(|rel| rel.eq_ignore_ascii_case("canonical")))
+        .and_then(|tag| attribute(tag, "href"))
+        .and_then(|href| page.join(href.trim()).ok())
+}
+
+/// Text of the page's `<title>`.
+fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = decode_entities(&html[start..end]);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Title for a page without one: its last path segment, or its host.
+fn fallback_title(url: &Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty()).map(str::to_string))
+        .or_else(|| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// The `<loc>` entries of a sitemap, and whether it is a sitemap index.
+fn sitemap_locations(xml: &str) -> (bool, Vec<String>) {
+    let locations = xml
+        .split("<loc>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</loc>"))
+        .map(|(location, _)| {
+            let location = location.trim();
+            let location = location
+                .strip_prefix("<![CDATA[")
+                .and_then(|l| l.strip_suffix("]]>"))
+                .unwrap_or(location);
+            decode_entities(location.trim())
+        })
+        .collect();
+    (xml.contains("<sitemapindex"), locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stub_http::{self, Reply};
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        assert_eq!(
+            canonical_key(&url(
+                "https://Docs.Example.com/guide/?utm_source=x&v=2#intro"
+            )),
+            "https://docs.example.com/guide?v=2"
+        );
+        assert_eq!(
+            canonical_key(&url("https://docs.example.com/guide/index.html")),
+            canonical_key(&url("https://docs.example.com/guide"))
+        );
+        assert_eq!(
+            canonical_key(&url("https://docs.example.com/")),
+            "https://docs.example.com/"
+        );
+    }
+
+    #[test]
+    fn test_links_and_canonical() {
+        let html = r##"<html><head><title>Install &amp; Setup</title>
+            <link rel="canonical" href="https://docs.example.com/install"></head>
+            <body><a href="next.html">Next</a> <a href="#top">Top</a>
+            <a href="mailto:me@example.com">Mail</a> <A HREF="/api">upper case</A>
+            <a href="https://other.example.com/x#y">Other</a></body></html>"##;
+        let page = url("https://docs.example.com/guide/install.html");
+
+        let links: Vec<String> = links(html, &page).iter().map(Url::to_string).collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://docs.example.com/guide/next.html",
+                "https://other.example.com/x"
+            ]
+        );
+        assert_eq!(
+            canonical_link(html, &page).unwrap().as_str(),
+            "https://docs.example.com/install"
+        );
+        assert_eq!(page_title(html).as_deref(), Some("Install & Setup"));
+        assert_eq!(fallback_title(&page), "install.html");
+    }
+
+    #[test]
+    fn test_sitemap_locations() {
+        let xml =
+            "<?xml version=\"1.0\"?><urlset><url><loc> https://a.com/x?a=1&amp;b=2 </loc></url>\
+                   <url><loc><![CDATA[https://a.com/y]]></loc></url></urlset>";
+        assert_eq!(
+            sitemap_locations(xml),
+            (
+                false,
+                vec![
+                    "https://a.com/x?a=1&b=2".to_string(),
+                    "https://a.com/y".to_string()
+                ]
+            )
+        );
+        let (is_index, _) =
+            sitemap_locations("<sitemapindex><sitemap><loc>x</loc></sitemap></sitemapindex>");
+        assert!(is_index);
+    }
+
+    fn page(body: &str) -> Reply {
+        Reply::ok(
+            "text/html; charset=utf-8",
+            format!("<html><body>{}</body></html>", body),
+        )
+    }
+
+    fn site(request: &str) -> Reply {
+        let path = request.split_whitespace().nth(1).unwrap_or("");
+        match path {
+            "/robots.txt" => Reply::ok(
+                "text/plain",
+                "User-agent: *\nDisallow: /docs/private\nSitemap: SITE/sitemap.xml\n",
+            ),
+            "/sitemap.xml" => Reply::ok(
+                "application/xml",
+                "<urlset><url><loc>SITE/docs/listed</loc></url><url><loc>SITE/blog/post</loc></url></urlset>",
+            ),
+            "/docs/" => page(
+                r#"<h1>Docs</h1><a href="intro">Intro</a> <a href="intro#usage">Usage</a>
+                   <a href="private">Private</a> <a href="/docs/logo.png">Logo</a>
+                   <a href="https://elsewhere.invalid/">Elsewhere</a>"#,
+            ),
+            "/docs/intro" => page(r#"<h1>Intro</h1><a href="deep">Deep</a> <a href="/docs/">Home</a>"#),
+            "/docs/deep" => page("<h1>Deep</h1>"),
+            "/docs/listed" => page("<h1>Listed</h1>"),
+            "/docs/private" => page("<h1>Private</h1>"),
+            _ => Reply::not_found(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crawl_follows_links_within_depth_and_robots() {
+        // The sitemap names the server by its address, known only once it runs
+        let server = stub_http::serve(|request| {
+            let host = request
+                .lines()
+                .find_map(|l| {
+                    l.strip_prefix("host: ")
+                        .or_else(|| l.strip_prefix("Host: "))
+                })
+                .unwrap_or("")
+                .to_string();
+            let reply = site(request);
+            Reply {
+                body: reply
+                    .body
+                    .replace("SITE", &format!("http://{}", host.trim())),
+                ..reply
+            }
+        })
+        .await;
+        let start = url(&format!("{}/docs/", server));
+        let crawl = CrawlOptions {
+            max_depth: 1,
+            same_domain: true,
+            delay: Duration::from_millis(1),
+        };
+
+        let pages = fetch_pages(
+            std::slice::from_ref(&start),
+            Some(&crawl),
+            &CancellationToken::new(),
+            &ProgressReporter::noop(),
+        )
+        .await
+        .unwrap();
+
+        let paths: Vec<String> = pages
+            .iter()
+            .map(|p| url(&p.url).path().to_string())
+            .collect();
+        // "deep" is two links away; the blog post is outside /docs/
+        assert_eq!(paths, vec!["/docs/", "/docs/listed", "/docs/intro"]);
+        assert_eq!(pages[2].title, "intro");
+        assert_eq!(pages[2].markdown, "# Intro\n\n[Deep](deep) [Home](/docs/)");
+    }
+
+    #[tokio::test]
+    async fn test_without_crawl_fetches_only_the_given_pages() {
+        let server = stub_http::serve(site).await;
+        let urls = parse_urls(&[
+            format!("{}/docs/intro", server),
+            format!("{}/missing", server),
+        ])
+        .unwrap();
+
+        let pages = fetch_pages(
+            &urls,
+            None,
+            &CancellationToken::new(),
+            &ProgressReporter::noop(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].url, format!("{}/docs/intro", server));
+    }
+
+    #[test]
+    fn test_parse_urls_rejects_other_schemes() {
+        let err = parse_urls(&["file:///etc/passwd".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Not an http(s) URL"), "{}", err);
+    }
+}
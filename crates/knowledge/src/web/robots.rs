@@ -0,0 +1,208 @@
+//! robots.txt rules (RFC 9309), as far as a documentation crawler needs them.
+
+use std::time::Duration;
+
+/// Rules of one site's robots.txt for our user agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Robots {
+    /// `(allow, pattern)` rules of the group that applies to us
+    rules: Vec<(bool, String)>,
+
+    /// Delay between requests asked for by the site
+    pub crawl_delay: Option<Duration>,
+
+    /// Sitemaps listed by the site (they apply to every agent)
+    pub sitemaps: Vec<String>,
+}
+
+/// A `User-agent` group of robots.txt.
+#[derive(Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl Robots {
+    /// Rules that allow everything (no robots.txt, or a 4xx for it).
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Rules that forbid everything (robots.txt unreachable or failing).
+    pub fn disallow_all() -> Self {
+        Self {
+            rules: vec![(false, "/".to_string())],
+            ..Self::default()
+        }
+    }
+
+    /// Parse robots.txt, keeping the group for `agent` or, failing that, `*`.
+    pub fn parse(content: &str, agent: &str) -> Self {
+        let agent = agent.to_ascii_lowercase();
+        let mut sitemaps = Vec::new();
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push(Group::default());
+                    }
+                    in_agents = true;
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                "sitemap" => sitemaps.push(value.to_string()),
+                key => {
+                    in_agents = false;
+                    let Some(group) = groups.last_mut() else {
+                        continue;
+                    };
+                    match key {
+                        "allow" if !value.is_empty() => group.rules.push((true, value.to_string())),
+                        "disallow" if !value.is_empty() => {
+                            group.rules.push((false, value.to_string()))
+                        }
+                        "crawl-delay" => {
+                            group.crawl_delay = value
+                                .parse::<f64>()
+                                .ok()
+                                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                                .map(Duration::from_secs_f64)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let named = |wanted: &dyn Fn(&str) -> bool| {
+            groups
+                .iter()
+                .filter(|group| group.agents.iter().any(|a| wanted(a)))
+                .fold((Vec::new(), None), |(mut rules, delay), group| {
+                    rules.extend(group.rules.iter().cloned());
+                    (rules, delay.or(group.crawl_delay))
+                })
+        };
+        let (mut rules, mut crawl_delay) = named(&|a| a != "*" && agent.contains(a));
+        if rules.is_empty() && crawl_delay.is_none() {
+            (rules, crawl_delay) = named(&|a| a == "*");
+        }
+
+        Self {
+            rules,
+            crawl_delay,
+            sitemaps,
+        }
+    }
+
+    /// Whether `path` (with its query) may be fetched.
+    ///
+    /// The longest matching rule wins; on a tie, `Allow` does.
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Match a robots.txt path pattern: a prefix, where `*` matches any run of
+/// characters and a trailing `$` anchors the end.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+# Example
+User-agent: *
+Disallow: /private/
+Allow: /private/handbook
+Disallow: /*.pdf$
+Crawl-delay: 2
+
+User-agent: badbot
+User-agent: otherbot
+Disallow: /
+
+Sitemap: https://docs.example.com/sitemap.xml
+";
+
+    #[test]
+    fn test_wildcard_group_rules() {
+        let robots = Robots::parse(ROBOTS, "guided");
+        assert!(robots.allows("/guide/install"));
+        assert!(!robots.allows("/private/keys"));
+        assert!(robots.allows("/private/handbook/leave"));
+        assert!(!robots.allows("/files/manual.pdf"));
+        assert!(robots.allows("/files/manual.pdf.html"));
+        assert_eq!(robots.crawl_delay, Some(Duration::from_secs(2)));
+        assert_eq!(
+            robots.sitemaps,
+            vec!["https://docs.example.com/sitemap.xml"]
+        );
+    }
+
+    #[test]
+    fn test_named_group_replaces_wildcard() {
+        let robots = Robots::parse(ROBOTS, "otherbot/1.0");
+        assert!(!robots.allows("/guide/install"));
+        assert_eq!(robots.crawl_delay, None);
+    }
+
+    #[test]
+    fn test_empty_disallow_allows_everything() {
+        let robots = Robots::parse("User-agent: *\nDisallow:\n", "guided");
+        assert!(robots.allows("/anything"));
+        assert!(!Robots::disallow_all().allows("/anything"));
+        assert!(Robots::allow_all().allows("/anything"));
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(matches("/docs", "/docs/intro"));
+        assert!(matches("/*/edit", "/page/edit?x=1"));
+        assert!(matches("/*.json$", "/a/b.json"));
+        assert!(!matches("/*.json$", "/a/b.json?x"));
+        assert!(matches("/exact$", "/exact"));
+        assert!(!matches("/exact$", "/exactly"));
+    }
+}
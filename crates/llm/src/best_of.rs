@@ -0,0 +1,297 @@
+//! Best-of-N answer selection.
+//!
+//! Small local models often give a wrong answer on any single sample. Best-of
+//! samples the same request several times at a non-zero temperature and has
+//! the model judge the candidates: it either picks the best one or, with
+//! [`JudgeMode::Synthesize`], writes a final answer from them.
+
+use crate::client::{LlmClient, LlmRequest, LlmResponse, LlmUsage};
+use guided_core::{AppError, AppResult};
+
+/// Largest number of samples a best-of run may take.
+pub const MAX_SAMPLES: usize = 10;
+
+/// Sampling temperature used when the request asks for none, or a very low
+/// one: near-greedy samples are nearly identical, leaving nothing to judge.
+pub const SAMPLE_TEMPERATURE: f32 = 0.7;
+
+/// Below this temperature candidates are sampled at [`SAMPLE_TEMPERATURE`].
+const MIN_SAMPLE_TEMPERATURE: f32 = 0.3;
+
+/// How the judge turns candidates into the final answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JudgeMode {
+    /// Return the candidate the judge rates best
+    #[default]
+    Select,
+
+    /// Have the judge write a final answer from the candidates
+    Synthesize,
+}
+
+/// Outcome of a best-of run.
+#[derive(Debug, Clone)]
+pub struct BestOf {
+    /// The final answer
+    pub answer: String,
+
+    /// Index of the selected candidate, or `None` for a synthesized answer
+    pub chosen: Option<usize>,
+
+    /// Every sampled candidate, in sampling order
+    pub candidates: Vec<LlmResponse>,
+
+    /// The judge's response
+    pub judge: LlmResponse,
+
+    /// Tokens used by the samples and the judge together
+    pub usage: LlmUsage,
+}
+
+/// Sample `n` answers to `request` concurrently and judge them.
+///
+/// Samples that fail are left out; the run fails only if none succeed. With
+/// one successful sample there is nothing to judge and it is returned as is.
+pub async fn best_of(
+    client: &dyn LlmClient,
+    request: &LlmRequest,
+    n: usize,
+    mode: JudgeMode,
+) -> AppResult<BestOf> {
+    if !(2..=MAX_SAMPLES).contains(&n) {
+        return Err(AppError::Config(format!(
+            "Best-of needs between 2 and {} samples, got {}",
+            MAX_SAMPLES, n
+        )));
+    }
+
+    let mut sample = request.clone();
+    sample.stream = false;
+    if sample.temperature.unwrap_or(0.0) < MIN_SAMPLE_TEMPERATURE {
+        sample.temperature = Some(SAMPLE_TEMPERATURE);
+    }
+
+    tracing::info!("Sampling {} candidate answers", n);
+    let results = futures::future::join_all((0..n).map(|_| client.complete(&sample))).await;
+    let mut candidates = Vec::new();
+    let mut last_error = None;
+    for result in results {
+        match result {
+            Ok(response) => candidates.push(response),
+            Err(e) => {
+                tracing::warn!("Candidate sample failed: {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return Err(last_error.unwrap_or_else(|| AppError::Llm("No candidates".to_string())));
+    }
+
+    let mut usage = LlmUsage::default();
+    for candidate in &candidates {
+        add_usage(&mut usage, &candidate.usage);
+    }
+
+    if candidates.len() == 1 {
+        let only = candidates[0].clone();
+        return Ok(BestOf {
+            answer: only.content.clone(),
+            chosen: Some(0),
+            candidates,
+            judge: only,
+            usage,
+        });
+    }
+
+    let judge_request = judge_request(request, &candidates, mode);
+    let judge = client.complete(&judge_request).await?;
+    add_usage(&mut usage, &judge.usage);
+
+    let (answer, chosen) = match mode {
+        JudgeMode::Select => {
+            let chosen = parse_choice(&judge.content, candidates.len()).unwrap_or_else(|| {
+                tracing::warn!("Judge did not name a candidate; using the first");
+                0
+            });
+            (candidates[chosen].content.clone(), Some(chosen))
+        }
+        JudgeMode::Synthesize => (judge.content.trim().to_string(), None),
+    };
+
+    Ok(BestOf {
+        answer,
+        chosen,
+        candidates,
+        judge,
+        usage,
+    })
+}
+
+/// Request asking the model to judge the candidates to `request`.
+fn judge_request(request: &LlmRequest, candidates: &[LlmResponse], mode: JudgeMode) -> LlmRequest {
+    let mut prompt = String::from("## Original request\n\n");
+    if let Some(system) = &request.system {
+        prompt.push_str(&format!("Instructions:\n{}\n\n", system));
+    }
+    prompt.push_str(&request.prompt);
+    prompt.push_str("\n\n## Candidate answers\n");
+    for (i, candidate) in candidates.iter().enumerate() {
+        prompt.push_str(&format!(
+            "\n### Candidate {}\n\n{}\n",
+            i + 1,
+            candidate.content.trim()
+        ));
+    }
+
+    let system = match mode {
+        JudgeMode::Select => format!(
+            "You judge answers. Compare the candidate answers to the original request \
+             for correctness, completeness and faithfulness to any context it gives. \
+             Reply with the number of the best candidate on the first line, as \
+             \"Best: N\" with N between 1 and {}, then at most two sentences on why.",
+            candidates.len()
+        ),
+        JudgeMode::Synthesize => "You judge answers. Compare the candidate answers to the \
+             original request for correctness, completeness and faithfulness to any context \
+             it gives. Write the single best final answer to the request, keeping what the \
+             candidates agree on and dropping claims only one makes without support. Reply \
+             with the answer only, without mentioning the candidates."
+            .to_string(),
+    };
+
+    let mut judge = LlmRequest::new(prompt, &request.model)
+        .with_system(system)
+        .with_temperature(0.0);
+    judge.max_tokens = match mode {
+        JudgeMode::Select => Some(200),
+        JudgeMode::Synthesize => request.max_tokens,
+    };
+    judge
+}
+
+/// Candidate index named in the judge's reply ("Best: 2" is index 1).
+fn parse_choice(reply: &str, count: usize) -> Option<usize> {
+    let lower = reply.to_lowercase();
+    // Prefer the requested "best: N" form, then the first number mentioned
+    let from = lower.find("best").map(|i| i + 4).unwrap_or(0);
+    lower[from..]
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())
+        .and_then(|digits| digits.parse::<usize>().ok())
+        .filter(|n| (1..=count).contains(n))
+        .map(|n| n - 1)
+}
+
+fn add_usage(total: &mut LlmUsage, usage: &LlmUsage) {
+    total.prompt_tokens += usage.prompt_tokens;
+    total.completion_tokens += usage.completion_tokens;
+    total.total_tokens += usage.total_tokens;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::LlmStream;
+    use std::sync::Mutex;
+
+    /// Replies with queued answers, then with the judge reply.
+    struct Scripted {
+        answers: Mutex<Vec<AppResult<String>>>,
+        judge: String,
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    impl Scripted {
+        fn new(answers: Vec<AppResult<String>>, judge: &str) -> Self {
+            Self {
+                answers: Mutex::new(answers),
+                judge: judge.to_string(),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmClient for Scripted {
+        fn provider_name(&self) -> &str {
+            "scripted"
+        }
+
+        async fn complete(&self, request: &LlmRequest) -> AppResult<LlmResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            let mut answers = self.answers.lock().unwrap();
+            let content = if answers.is_empty() {
+                self.judge.clone()
+            } else {
+                answers.remove(0)?
+            };
+            Ok(LlmResponse {
+                content,
+                model: request.model.clone(),
+                usage: LlmUsage::new(10, 5),
+                done: true,
+            })
+        }
+
+        async fn stream(&self, _request: &LlmRequest) -> AppResult<LlmStream> {
+            Err(AppError::Llm("streaming is not supported".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("Best: 2\nIt cites the docs.", 3), Some(1));
+        assert_eq!(parse_choice("Candidate 3 is best: 1", 3), Some(0));
+        assert_eq!(parse_choice("I prefer candidate 3.", 3), Some(2));
+        assert_eq!(parse_choice("Best: 7", 3), None);
+        assert_eq!(parse_choice("None of them.", 3), None);
+    }
+
+    #[tokio::test]
+    async fn test_best_of_selects_judged_candidate() {
+        let client = Scripted::new(
+            vec![Ok("Paris".into()), Ok("Lyon".into()), Ok("Paris.".into())],
+            "Best: 3",
+        );
+        let request = LlmRequest::new("Capital of France?", "llama3").with_temperature(0.1);
+        let result = best_of(&client, &request, 3, JudgeMode::Select)
+            .await
+            .unwrap();
+
+        assert_eq!(result.answer, "Paris.");
+        assert_eq!(result.chosen, Some(2));
+        assert_eq!(result.candidates.len(), 3);
+        assert_eq!(result.usage.total_tokens, 60);
+
+        let requests = client.requests.lock().unwrap();
+        // Samples are drawn at a usable temperature, the judge greedily
+        assert_eq!(requests[0].temperature, Some(SAMPLE_TEMPERATURE));
+        assert_eq!(requests[3].temperature, Some(0.0));
+        assert!(requests[3].prompt.contains("### Candidate 2\n\nLyon"));
+    }
+
+    #[tokio::test]
+    async fn test_best_of_synthesizes_and_skips_failed_samples() {
+        let client = Scripted::new(
+            vec![
+                Ok("Use cargo.".into()),
+                Err(AppError::Llm("timeout".into())),
+                Ok("Run make.".into()),
+            ],
+            " Run cargo build. ",
+        );
+        let request = LlmRequest::new("How do I build?", "llama3");
+        let result = best_of(&client, &request, 3, JudgeMode::Synthesize)
+            .await
+            .unwrap();
+
+        assert_eq!(result.answer, "Run cargo build.");
+        assert_eq!(result.chosen, None);
+        assert_eq!(result.candidates.len(), 2);
+
+        assert!(best_of(&client, &request, 1, JudgeMode::Select)
+            .await
+            .is_err());
+    }
+}
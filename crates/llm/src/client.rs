@@ -3,6 +3,7 @@
 //! This module defines the core abstractions for interacting with LLM providers.
 
 use futures::Stream;
+use guided_core::config::GenerationConfig;
 use guided_core::AppResult;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
@@ -28,6 +29,26 @@ pub struct LlmRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
 
+    /// Top-k sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+
+    /// Frequency penalty (-2.0 - 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    /// Presence penalty (-2.0 - 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+
+    /// Sequences that end generation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+
+    /// Sampling seed, for reproducible output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
     /// Enable streaming responses
     #[serde(default)]
     pub stream: bool,
@@ -46,6 +67,11 @@ impl LlmRequest {
             max_tokens: None,
             temperature: None,
             top_p: None,
+            top_k: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: Vec::new(),
+            seed: None,
             stream: false,
             system: None,
         }
@@ -74,6 +100,22 @@ impl LlmRequest {
         self.system = Some(system.into());
         self
     }
+
+    /// Apply generation parameters, overriding those they set.
+    pub fn with_generation(mut self, generation: &GenerationConfig) -> Self {
+        let generation = generation.clone();
+        self.temperature = generation.temperature.or(self.temperature);
+        self.max_tokens = generation.max_tokens.or(self.max_tokens);
+        self.top_p = generation.top_p.or(self.top_p);
+        self.top_k = generation.top_k.or(self.top_k);
+        self.frequency_penalty = generation.frequency_penalty.or(self.frequency_penalty);
+        self.presence_penalty = generation.presence_penalty.or(self.presence_penalty);
+        if !generation.stop.is_empty() {
+            self.stop = generation.stop;
+        }
+        self.seed = generation.seed.or(self.seed);
+        self
+    }
 }
 
 /// LLM completion response.
@@ -154,6 +196,13 @@ pub trait LlmClient: Send + Sync {
     /// Get the provider name (e.g., "ollama", "openai").
     fn provider_name(&self) -> &str;
 
+    /// Check that the provider is reachable, with a diagnostic if not.
+    ///
+    /// Providers without a cheap check report healthy.
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
     /// Perform a non-streaming completion.
     ///
     /// # Arguments
@@ -0,0 +1,394 @@
+//! Fitting prompts into a model's context window.
+//!
+//! Prompts are built from a template plus injected context (workspace,
+//! knowledge, diffs). When the estimated size of a prompt exceeds the
+//! configured window, the context is shortened according to the
+//! [`OverflowStrategy`] and the prompt re-rendered; the template's own text
+//! and the user's question are never cut.
+
+use crate::client::{LlmClient, LlmRequest};
+use guided_core::config::{ContextWindowConfig, OverflowStrategy};
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+
+/// Tokens kept free for the answer when the request sets no `max_tokens`.
+pub const DEFAULT_RESERVE_TOKENS: u32 = 512;
+
+/// Placed where truncated context was cut.
+const TRUNCATION_MARKER: &str = "[... earlier context truncated ...]\n";
+
+/// How a prompt was fitted into the context window, for run metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextOverflow {
+    /// Strategy applied
+    pub strategy: OverflowStrategy,
+
+    /// Configured context window in tokens
+    pub window_tokens: u32,
+
+    /// Estimated prompt tokens before fitting
+    pub prompt_tokens: usize,
+
+    /// Estimated prompt tokens sent
+    pub fitted_tokens: usize,
+
+    /// Context variables that were shortened, in order
+    pub shortened: Vec<String>,
+
+    /// Model that condensed the context, for `summarize`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_model: Option<String>,
+}
+
+/// Rough token count (about four characters per token).
+///
+/// Errs slightly high for English prose, which is the safe side here.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Keep the latest whole lines of `text` that fit in `max_tokens`, marking
+/// the cut. Returns an empty string when not even one line fits.
+pub fn truncate_oldest(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let Some(room) = max_tokens.checked_sub(estimate_tokens(TRUNCATION_MARKER)) else {
+        return String::new();
+    };
+
+    let mut chars = 0;
+    let mut start = text.len();
+    for line in text.split_inclusive('\n').rev() {
+        chars += line.chars().count();
+        if chars > room * 4 {
+            break;
+        }
+        start -= line.len();
+    }
+    if start == text.len() {
+        return String::new();
+    }
+    format!("{}{}", TRUNCATION_MARKER, &text[start..])
+}
+
+/// Fit `request` into the context window described by `window`.
+///
+/// `context` holds the injected context variables in the order they were
+/// added, and `render` rebuilds the user prompt from them. Variables are
+/// shortened oldest first until the prompt fits, leaving room for
+/// `request.max_tokens` (or [`DEFAULT_RESERVE_TOKENS`]) of answer. Returns
+/// the request unchanged, with no record, when it already fits or no window
+/// is configured.
+pub async fn fit_prompt<F>(
+    client: &dyn LlmClient,
+    window: &ContextWindowConfig,
+    mut request: LlmRequest,
+    mut context: Vec<(String, String)>,
+    render: F,
+) -> AppResult<(LlmRequest, Option<ContextOverflow>)>
+where
+    F: Fn(&[(String, String)]) -> AppResult<String>,
+{
+    let Some(window_tokens) = window.tokens else {
+        return Ok((request, None));
+    };
+    let reserve = request
+        .max_tokens
+        .unwrap_or(DEFAULT_RESERVE_TOKENS)
+        .min(window_tokens / 2);
+    let budget = (window_tokens - reserve) as usize;
+
+    let prompt_tokens = request_tokens(&request);
+    if prompt_tokens <= budget {
+        return Ok((request, None));
+    }
+    if window.overflow == OverflowStrategy::Error {
+        return Err(AppError::Llm(format!(
+            "Prompt is about {} tokens, over the {} left in the {}-token context window \
+             after reserving {} for the answer (set llm.contextWindows.<provider>.overflow \
+             to truncate-oldest or summarize to shorten it)",
+            prompt_tokens, budget, window_tokens, reserve
+        )));
+    }
+
+    let summary_model = window
+        .summary_model
+        .clone()
+        .unwrap_or_else(|| request.model.clone());
+    let mut shortened: Vec<String> = Vec::new();
+    let mut tokens = prompt_tokens;
+    let mut i = 0;
+    while tokens > budget && i < context.len() {
+        let (name, text) = &context[i];
+        let text_tokens = estimate_tokens(text);
+        if text_tokens == 0 {
+            i += 1;
+            continue;
+        }
+        let target = text_tokens.saturating_sub(tokens - budget);
+
+        // Each variable is summarized once; later passes only truncate
+        let first_pass = !shortened.contains(name);
+        let mut shorter = if window.overflow == OverflowStrategy::Summarize && first_pass {
+            match summarize(client, &summary_model, name, text, target).await {
+                Ok(summary) => truncate_oldest(&summary, target),
+                Err(e) => {
+                    tracing::warn!("Could not summarize {}, truncating: {}", name, e);
+                    truncate_oldest(text, target)
+                }
+            }
+        } else {
+            truncate_oldest(text, target)
+        };
+        if estimate_tokens(&shorter) >= text_tokens {
+            shorter.clear();
+        }
+
+        tracing::info!(
+            "Shortened {} from about {} to {} tokens to fit the context window",
+            name,
+            text_tokens,
+            estimate_tokens(&shorter)
+        );
+        if first_pass {
+            shortened.push(name.clone());
+        }
+        context[i].1 = shorter;
+        request.prompt = render(&context)?;
+        tokens = request_tokens(&request);
+    }
+
+    if tokens > budget {
+        return Err(AppError::Llm(format!(
+            "Prompt is about {} tokens even without its injected context, over the {} left \
+             in the {}-token context window after reserving {} for the answer",
+            tokens, budget, window_tokens, reserve
+        )));
+    }
+
+    let summarized = window.overflow == OverflowStrategy::Summarize;
+    Ok((
+        request,
+        Some(ContextOverflow {
+            strategy: window.overflow,
+            window_tokens,
+            prompt_tokens,
+            fitted_tokens: tokens,
+            shortened,
+            summary_model: summarized.then_some(summary_model),
+        }),
+    ))
+}
+
+fn request_tokens(request: &LlmRequest) -> usize {
+    estimate_tokens(&request.prompt) + request.system.as_deref().map_or(0, estimate_tokens)
+}
+
+/// Condense `text` to roughly `max_tokens` with `model`.
+async fn summarize(
+    client: &dyn LlmClient,
+    model: &str,
+    name: &str,
+    text: &str,
+    max_tokens: usize,
+) -> AppResult<String> {
+    let prompt = format!(
+        "Condense the following {} to at most about {} words. Keep names, paths, \
+         numbers and facts someone would need to answer questions about it.\n\n{}",
+        name,
+        max_tokens * 3 / 4,
+        text
+    );
+    let mut request = LlmRequest::new(prompt, model)
+        .with_system("You condense context for another model. Reply with the condensed text only.")
+        .with_temperature(0.0);
+    request.max_tokens = Some(max_tokens.max(1) as u32);
+    Ok(client.complete(&request).await?.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{LlmResponse, LlmStream, LlmUsage};
+    use std::sync::Mutex;
+
+    /// Replies to every request with the same text.
+    struct Fixed {
+        reply: String,
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    impl Fixed {
+        fn new(reply: &str) -> Self {
+            Self {
+                reply: reply.to_string(),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmClient for Fixed {
+        fn provider_name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn complete(&self, request: &LlmRequest) -> AppResult<LlmResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(LlmResponse {
+                content: self.reply.clone(),
+                model: request.model.clone(),
+                usage: LlmUsage::new(10, 5),
+                done: true,
+            })
+        }
+
+        async fn stream(&self, _request: &LlmRequest) -> AppResult<LlmStream> {
+            Err(AppError::Llm("streaming is not supported".to_string()))
+        }
+    }
+
+    fn render(context: &[(String, String)]) -> AppResult<String> {
+        Ok(format!(
+            "Answer from the context.\n\n{}\n\n{}\n\nQuestion: what changed?",
+            context[0].1, context[1].1
+        ))
+    }
+
+    fn context() -> Vec<(String, String)> {
+        let log: String = (1..=100).map(|i| format!("entry {:03}\n", i)).collect();
+        vec![
+            ("workspaceContext".to_string(), log),
+            ("diffContext".to_string(), "+ fn new() {}\n".to_string()),
+        ]
+    }
+
+    fn window(tokens: u32, overflow: OverflowStrategy) -> ContextWindowConfig {
+        ContextWindowConfig {
+            tokens: Some(tokens),
+            overflow,
+            summary_model: None,
+        }
+    }
+
+    fn request(max_tokens: u32) -> LlmRequest {
+        let mut request = LlmRequest::new(render(&context()).unwrap(), "llama3");
+        request.max_tokens = Some(max_tokens);
+        request
+    }
+
+    #[test]
+    fn test_truncate_oldest_keeps_latest_lines() {
+        let text: String = (0..10).map(|i| format!("entry {}\n", i)).collect();
+        assert_eq!(truncate_oldest(&text, 100), text);
+
+        let truncated = truncate_oldest(&text, 13);
+        assert_eq!(
+            truncated,
+            format!("{}entry 8\nentry 9\n", TRUNCATION_MARKER)
+        );
+        assert!(estimate_tokens(&truncated) <= 13);
+
+        assert_eq!(truncate_oldest(&text, 3), "");
+    }
+
+    #[tokio::test]
+    async fn test_fit_prompt_leaves_fitting_prompts_alone() {
+        let client = Fixed::new("unused");
+        let original = request(100);
+
+        let unchecked = ContextWindowConfig::default();
+        let (fitted, overflow) =
+            fit_prompt(&client, &unchecked, original.clone(), context(), render)
+                .await
+                .unwrap();
+        assert_eq!(fitted.prompt, original.prompt);
+        assert!(overflow.is_none());
+
+        let roomy = window(4096, OverflowStrategy::Error);
+        let (_, overflow) = fit_prompt(&client, &roomy, original, context(), render)
+            .await
+            .unwrap();
+        assert!(overflow.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fit_prompt_errors_when_configured() {
+        let client = Fixed::new("unused");
+        let err = fit_prompt(
+            &client,
+            &window(300, OverflowStrategy::Error),
+            request(100),
+            context(),
+            render,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("context window"));
+    }
+
+    #[tokio::test]
+    async fn test_fit_prompt_truncates_oldest_context() {
+        let client = Fixed::new("unused");
+        let (fitted, overflow) = fit_prompt(
+            &client,
+            &window(300, OverflowStrategy::TruncateOldest),
+            request(100),
+            context(),
+            render,
+        )
+        .await
+        .unwrap();
+
+        let overflow = overflow.unwrap();
+        assert_eq!(overflow.shortened, vec!["workspaceContext".to_string()]);
+        assert!(overflow.fitted_tokens <= 200);
+        assert!(overflow.prompt_tokens > 200);
+        // The template, the newest context and the question survive
+        assert!(fitted.prompt.starts_with("Answer from the context."));
+        assert!(fitted.prompt.contains("entry 100\n"));
+        assert!(!fitted.prompt.contains("entry 001\n"));
+        assert!(fitted.prompt.contains("+ fn new() {}"));
+        assert!(fitted.prompt.ends_with("Question: what changed?"));
+        assert!(client.requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fit_prompt_summarizes_with_summary_model() {
+        let client = Fixed::new("Entries 1 to 100 were logged.");
+        let mut config = window(300, OverflowStrategy::Summarize);
+        config.summary_model = Some("qwen2.5:0.5b".to_string());
+
+        let (fitted, overflow) = fit_prompt(&client, &config, request(100), context(), render)
+            .await
+            .unwrap();
+
+        let overflow = overflow.unwrap();
+        assert_eq!(overflow.strategy, OverflowStrategy::Summarize);
+        assert_eq!(overflow.summary_model.as_deref(), Some("qwen2.5:0.5b"));
+        assert!(fitted.prompt.contains("Entries 1 to 100 were logged."));
+        assert!(!fitted.prompt.contains("entry 100"));
+
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].model, "qwen2.5:0.5b");
+        assert!(requests[0].prompt.contains("entry 001"));
+    }
+
+    #[tokio::test]
+    async fn test_fit_prompt_fails_when_template_alone_overflows() {
+        let client = Fixed::new("unused");
+        let err = fit_prompt(
+            &client,
+            &window(20, OverflowStrategy::TruncateOldest),
+            request(10),
+            context(),
+            render,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("without its injected context"));
+    }
+}
@@ -5,8 +5,11 @@
 //! injection, and basic health checks.
 
 use crate::client::LlmClient;
-use crate::providers::OllamaClient;
+use crate::providers::{OllamaClient, ScriptedClient};
+use guided_core::config::ProviderConfig;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Create an LLM client based on the provider name.
 ///
@@ -17,7 +20,7 @@ use std::sync::Arc;
 /// 4. Optionally performs health checks
 ///
 /// # Arguments
-/// * `provider` - Provider identifier ("openai", "claude", "ollama", "gguf-local")
+/// * `provider` - Provider identifier ("openai", "claude", "ollama", "gguf-local", "mock")
 /// * `endpoint` - Optional custom endpoint URL
 /// * `api_key` - Optional API key (for providers that require it)
 ///
@@ -58,13 +61,46 @@ pub fn create_client(
             // TODO: Implement GGUF client
             Err("GGUF provider not yet implemented".to_string())
         }
+        "mock" => Ok(Arc::new(ScriptedClient::echo())),
         _ => Err(format!("Unknown provider: {}", provider)),
     }
 }
 
+/// Create an LLM client using a provider's configuration from config.yaml.
+///
+/// Like [`create_client`], with the endpoint and (for Ollama) the request
+/// timeout taken from `provider_config` when given. The mock provider
+/// replies from its configured script.
+pub fn create_client_from_config(
+    provider: &str,
+    provider_config: Option<&ProviderConfig>,
+    api_key: Option<&str>,
+) -> Result<Arc<dyn LlmClient>, String> {
+    let endpoint = provider_config.and_then(ProviderConfig::endpoint);
+    let timeout = provider_config.and_then(ProviderConfig::timeout_secs);
+
+    if let (Some(ProviderConfig::Mock { script }), "mock") =
+        (provider_config, provider.to_lowercase().as_str())
+    {
+        let client = ScriptedClient::from_file(Path::new(script)).map_err(|e| e.to_string())?;
+        return Ok(Arc::new(client));
+    }
+
+    match (provider.to_lowercase().as_str(), timeout) {
+        ("ollama", Some(secs)) => {
+            let base_url = endpoint.unwrap_or("http://localhost:11434");
+            let client =
+                OllamaClient::with_base_url(base_url).with_timeout(Duration::from_secs(secs));
+            Ok(Arc::new(client))
+        }
+        _ => create_client(provider, endpoint, api_key),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::LlmRequest;
 
     #[test]
     fn test_create_ollama_client() {
@@ -78,6 +114,51 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[test]
+    fn test_create_ollama_client_from_config() {
+        let config = ProviderConfig::Ollama {
+            endpoint: "http://gpu-box:11434".to_string(),
+            model: "llama3".to_string(),
+            embedding_model: None,
+            timeout: Some(120),
+        };
+        assert!(create_client_from_config("ollama", Some(&config), None).is_ok());
+        assert!(create_client_from_config("ollama", None, None).is_ok());
+        assert!(create_client_from_config("openai", None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_mock_client() {
+        let echo = create_client("mock", None, None).unwrap();
+        let response = echo
+            .complete(&LlmRequest::new("ping", "any"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "ping");
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("script.yaml");
+        std::fs::write(&script, "replies:\n  - reply: pong\n").unwrap();
+        let config = ProviderConfig::Mock {
+            script: script.to_string_lossy().to_string(),
+        };
+        let scripted = create_client_from_config("mock", Some(&config), None).unwrap();
+        let response = scripted
+            .complete(&LlmRequest::new("ping", "any"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "pong");
+
+        let missing = ProviderConfig::Mock {
+            script: dir
+                .path()
+                .join("missing.yaml")
+                .to_string_lossy()
+                .to_string(),
+        };
+        assert!(create_client_from_config("mock", Some(&missing), None).is_err());
+    }
+
     #[test]
     fn test_openai_requires_api_key() {
         match create_client("openai", None, None) {
@@ -0,0 +1,160 @@
+//! Line framing for streamed provider responses.
+//!
+//! HTTP chunks don't line up with protocol frames: one chunk may carry
+//! several newline-delimited JSON objects (Ollama) or SSE fields, or end in
+//! the middle of one, even inside a multi-byte character. [`LineDecoder`]
+//! buffers bytes and yields only complete lines.
+
+use futures::{Stream, StreamExt};
+use guided_core::{AppError, AppResult};
+use std::fmt::Display;
+
+/// Incremental decoder that splits a byte stream into lines.
+///
+/// Lines end with `\n`; a trailing `\r` is removed. Empty lines are kept,
+/// since SSE uses them to end events.
+#[derive(Debug, Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+}
+
+impl LineDecoder {
+    /// Create an empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add received bytes and return the lines they complete.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let Some(last_newline) = self.buffer.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.buffer.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.buffer, rest);
+
+        complete[..last_newline]
+            .split(|&b| b == b'\n')
+            .map(to_line)
+            .collect()
+    }
+
+    /// Return the final line if the stream did not end with a newline.
+    pub fn finish(&mut self) -> Option<String> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let rest = std::mem::take(&mut self.buffer);
+        Some(to_line(&rest))
+    }
+}
+
+/// Decode a line, dropping a trailing `\r`.
+fn to_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Split a stream of byte chunks into lines.
+///
+/// A transport error is yielded as an `AppError::Llm` in place, and the
+/// final unterminated line (if any) is yielded when the stream ends.
+pub fn lines<S, B, E>(bytes: S) -> impl Stream<Item = AppResult<String>>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut decoder = LineDecoder::new();
+    bytes
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .flat_map(move |item| {
+            let lines: Vec<AppResult<String>> = match item {
+                Some(Ok(bytes)) => decoder.push(bytes.as_ref()).into_iter().map(Ok).collect(),
+                Some(Err(e)) => vec![Err(AppError::Llm(format!("Stream error: {}", e)))],
+                None => decoder.finish().into_iter().map(Ok).collect(),
+            };
+            futures::stream::iter(lines)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_split_across_chunks() {
+        let mut decoder = LineDecoder::new();
+        assert!(decoder.push(br#"{"response":"Hel"#).is_empty());
+        assert!(decoder.push(br#"lo","done":false"#).is_empty());
+        assert_eq!(
+            decoder.push(b"}\n"),
+            vec![r#"{"response":"Hello","done":false}"#]
+        );
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn test_several_objects_in_one_chunk() {
+        let mut decoder = LineDecoder::new();
+        assert_eq!(
+            decoder.push(b"{\"a\":1}\n{\"b\":2}\n{\"c\""),
+            vec![r#"{"a":1}"#, r#"{"b":2}"#]
+        );
+        assert_eq!(decoder.push(b":3}\n"), vec![r#"{"c":3}"#]);
+    }
+
+    #[test]
+    fn test_crlf_and_empty_lines() {
+        let mut decoder = LineDecoder::new();
+        assert_eq!(
+            decoder.push(b"data: x\r\n\r\ndata: y\r"),
+            vec!["data: x", ""]
+        );
+        assert_eq!(decoder.push(b"\n"), vec!["data: y"]);
+    }
+
+    #[test]
+    fn test_character_split_across_chunks() {
+        let bytes = "{\"response\":\"é\"}\n".as_bytes();
+        let split = bytes.iter().position(|&b| b > 0x7f).unwrap() + 1;
+
+        let mut decoder = LineDecoder::new();
+        assert!(decoder.push(&bytes[..split]).is_empty());
+        assert_eq!(decoder.push(&bytes[split..]), vec![r#"{"response":"é"}"#]);
+    }
+
+    #[test]
+    fn test_finish_returns_unterminated_line() {
+        let mut decoder = LineDecoder::new();
+        assert!(decoder.push(b"{\"done\":true}").is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some(r#"{"done":true}"#));
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_lines_stream() {
+        let chunks: Vec<Result<&[u8], String>> =
+            vec![Ok(b"{\"a\":"), Ok(b"1}\n{\"b\":2}\n{\"c\":"), Ok(b"3}")];
+        let lines: Vec<String> = lines(futures::stream::iter(chunks))
+            .map(|line| line.unwrap())
+            .collect()
+            .await;
+        assert_eq!(lines, vec![r#"{"a":1}"#, r#"{"b":2}"#, r#"{"c":3}"#]);
+    }
+
+    #[tokio::test]
+    async fn test_lines_stream_error() {
+        let chunks: Vec<Result<&[u8], String>> =
+            vec![Ok(b"{\"a\":1}\n"), Err("connection reset".to_string())];
+        let lines: Vec<AppResult<String>> = lines(futures::stream::iter(chunks)).collect().await;
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("connection reset"));
+    }
+}
@@ -6,6 +6,7 @@
 //!
 //! # Providers
 //! - **Ollama**: Local LLM runtime (default)
+//! - **Mock**: Scripted or echoed replies, for tests and offline demos
 //! - Future: OpenAI, Anthropic, etc.
 //!
 //! # Example
@@ -21,13 +22,26 @@
 //! # }
 //! ```
 
+pub mod best_of;
 pub mod client;
+pub mod context;
 pub mod factory;
+pub mod filter;
+pub mod framing;
 pub mod providers;
+pub mod rate_limit;
+pub mod runs;
 pub mod types;
+pub mod usage;
 
 // Re-export main types
+pub use best_of::{best_of, BestOf, JudgeMode};
 pub use client::{LlmClient, LlmRequest, LlmResponse, LlmStream, LlmStreamChunk, LlmUsage};
-pub use factory::create_client;
-pub use providers::OllamaClient;
+pub use context::{estimate_tokens, fit_prompt, ContextOverflow};
+pub use factory::{create_client, create_client_from_config};
+pub use filter::{FilterAudit, Filtered, OutputFilter};
+pub use providers::{OllamaClient, ScriptedClient};
+pub use rate_limit::RateLimiter;
+pub use runs::{diff_responses, prompt_hash, DiffLine, RunRecord, RunStore};
 pub use types::{LlmConfig, LlmProviderConfig, ProviderType};
+pub use usage::{UsageEntry, UsageKind, UsageLedger, UsageTotal};
@@ -1,5 +1,7 @@
 //! LLM provider implementations.
 
 pub mod ollama;
+pub mod scripted;
 
 pub use ollama::OllamaClient;
+pub use scripted::{Script, ScriptedClient, ScriptedReply};
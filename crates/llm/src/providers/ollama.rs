@@ -4,9 +4,24 @@
 //! Ollama API: https://github.com/ollama/ollama/blob/main/docs/api.md
 
 use crate::client::{LlmClient, LlmRequest, LlmResponse, LlmStream, LlmStreamChunk, LlmUsage};
+use crate::framing;
+use crate::rate_limit::{self, CallError, RateLimiter};
 use futures::StreamExt;
 use guided_core::{AppError, AppResult};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time allowed to open a connection to the server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time the server may go silent before a request fails, unless configured
+/// (`llm.providers.ollama.timeout`). Generous, since a cold model can take
+/// minutes to load before the first token.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Time allowed for the `/api/tags` health check.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
 
 /// Ollama API request format.
 #[derive(Debug, Serialize)]
@@ -15,11 +30,29 @@ struct OllamaRequest {
     prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     system: Option<String>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+/// Ollama model options (sampling and generation parameters).
+#[derive(Debug, Default, Serialize)]
+struct OllamaOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     num_predict: Option<u32>,
-    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 /// Ollama API response format.
@@ -41,6 +74,15 @@ pub struct OllamaClient {
 
     /// HTTP client
     client: reqwest::Client,
+
+    /// Time the server may go silent before a request fails
+    timeout: Duration,
+
+    /// Time allowed to open a connection
+    connect_timeout: Duration,
+
+    /// Limiter shared with every other Ollama client in the process
+    limiter: Arc<RateLimiter>,
 }
 
 impl OllamaClient {
@@ -54,20 +96,134 @@ impl OllamaClient {
     /// Create a new Ollama client with a custom base URL.
     pub fn with_base_url(base_url: impl Into<String>) -> Self {
         Self {
-            base_url: base_url.into(),
-            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            client: http_client(CONNECT_TIMEOUT, DEFAULT_TIMEOUT),
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: CONNECT_TIMEOUT,
+            limiter: rate_limit::shared_limiter("ollama"),
         }
     }
 
+    /// Fail requests when the server sends nothing for `timeout`.
+    ///
+    /// This bounds the wait between reads rather than the whole response,
+    /// so long streamed answers are not cut off.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.client = http_client(self.connect_timeout, timeout);
+        self
+    }
+
+    /// Fail requests when no connection is made within `connect_timeout`.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self.client = http_client(connect_timeout, self.timeout);
+        self
+    }
+
+    /// Check that the server answers `/api/tags` within a few seconds.
+    pub async fn health_check(&self) -> AppResult<()> {
+        let url = format!("{}/api/tags", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| self.unreachable(&e))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Llm(format!(
+                "Ollama at {} answered the health check with {}. Is this an Ollama server?",
+                self.base_url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Diagnose a request that could not be sent or timed out.
+    ///
+    /// Runs the health check to tell a server that is down from one that is
+    /// up but slow to answer.
+    async fn diagnose(&self, error: reqwest::Error) -> AppError {
+        if let Err(unreachable) = self.health_check().await {
+            return unreachable;
+        }
+        if error.is_timeout() {
+            return AppError::Llm(format!(
+                "Ollama at {} did not respond within {}s. The model may still be loading; \
+                 raise `llm.providers.ollama.timeout` in .guided/config.yaml if this persists",
+                self.base_url,
+                self.timeout.as_secs()
+            ));
+        }
+        AppError::Llm(format!("Failed to send request to Ollama: {}", error))
+    }
+
+    /// Error for a server that can't be reached.
+    fn unreachable(&self, error: &reqwest::Error) -> AppError {
+        AppError::Llm(format!(
+            "Cannot reach Ollama at {} ({}). Is Ollama running? Start it with `ollama serve`, \
+             or set `llm.providers.ollama.endpoint` in .guided/config.yaml",
+            self.base_url, error
+        ))
+    }
+
+    /// POST a generate request under the rate limiter.
+    ///
+    /// Returns the response once it has a success status; 429s are retried
+    /// with backoff and other error statuses become `AppError::Llm`.
+    async fn send(&self, ollama_request: &OllamaRequest) -> AppResult<reqwest::Response> {
+        let url = format!("{}/api/generate", self.base_url);
+
+        self.limiter
+            .call(|| async {
+                let response = match self.client.post(&url).json(ollama_request).send().await {
+                    Ok(response) => response,
+                    Err(e) => return Err(self.diagnose(e).await.into()),
+                };
+
+                let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(CallError::RateLimited {
+                        retry_after: rate_limit::retry_after(response.headers()),
+                    });
+                }
+                if !status.is_success() {
+                    let error_text = response
+                        .text()
+                        .await
+                        .unwrap_or_else(|_| "Unknown error".to_string());
+                    return Err(AppError::Llm(format!(
+                        "Ollama API error ({}): {}",
+                        status, error_text
+                    ))
+                    .into());
+                }
+
+                Ok(response)
+            })
+            .await
+    }
+
     /// Convert LlmRequest to Ollama format.
     fn to_ollama_request(&self, request: &LlmRequest) -> OllamaRequest {
         OllamaRequest {
             model: request.model.clone(),
             prompt: request.prompt.clone(),
             system: request.system.clone(),
-            temperature: request.temperature,
-            num_predict: request.max_tokens,
             stream: request.stream,
+            options: OllamaOptions {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+                top_p: request.top_p,
+                top_k: request.top_k,
+                frequency_penalty: request.frequency_penalty,
+                presence_penalty: request.presence_penalty,
+                stop: request.stop.clone(),
+                seed: request.seed,
+            },
         }
     }
 
@@ -87,6 +243,36 @@ impl OllamaClient {
     }
 }
 
+/// Parse one line of a streamed Ollama response.
+fn parse_stream_line(line: &str) -> AppResult<LlmStreamChunk> {
+    let ollama_response: OllamaResponse = serde_json::from_str(line)
+        .map_err(|e| AppError::Llm(format!("Failed to parse chunk: {}", e)))?;
+
+    Ok(LlmStreamChunk {
+        content: ollama_response.response,
+        model: ollama_response.model,
+        done: ollama_response.done,
+        usage: if ollama_response.done {
+            Some(LlmUsage::new(
+                ollama_response.prompt_eval_count.unwrap_or(0),
+                ollama_response.eval_count.unwrap_or(0),
+            ))
+        } else {
+            None
+        },
+    })
+}
+
+/// HTTP client with the given connect and read timeouts.
+fn http_client(connect_timeout: Duration, timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(connect_timeout)
+        .read_timeout(timeout)
+        .build()
+        // Building fails only if the TLS backend can't initialize
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
 impl Default for OllamaClient {
     fn default() -> Self {
         Self::new()
@@ -99,32 +285,16 @@ impl LlmClient for OllamaClient {
         "ollama"
     }
 
+    async fn health_check(&self) -> AppResult<()> {
+        OllamaClient::health_check(self).await
+    }
+
     async fn complete(&self, request: &LlmRequest) -> AppResult<LlmResponse> {
         tracing::info!("Sending completion request to Ollama");
         tracing::debug!("Request: {:?}", request);
 
         let ollama_request = self.to_ollama_request(request);
-        let url = format!("{}/api/generate", self.base_url);
-
-        let response = self
-            .client
-            .post(&url)
-            .json(&ollama_request)
-            .send()
-            .await
-            .map_err(|e| AppError::Llm(format!("Failed to send request to Ollama: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Llm(format!(
-                "Ollama API error ({}): {}",
-                status, error_text
-            )));
-        }
+        let response = self.send(&ollama_request).await?;
 
         // For non-streaming, Ollama returns a single JSON object
         let ollama_response: OllamaResponse = response
@@ -145,71 +315,24 @@ impl LlmClient for OllamaClient {
         let mut ollama_request = self.to_ollama_request(request);
         ollama_request.stream = true; // Ensure streaming is enabled
 
-        let url = format!("{}/api/generate", self.base_url);
+        let response = self.send(&ollama_request).await?;
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&ollama_request)
-            .send()
-            .await
-            .map_err(|e| AppError::Llm(format!("Failed to send streaming request: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Llm(format!(
-                "Ollama API error ({}): {}",
-                status, error_text
-            )));
-        }
+        // Ollama sends newline-delimited JSON; objects may span HTTP chunks
+        let stream = framing::lines(response.bytes_stream())
+            .filter(|line| {
+                let keep = !matches!(line, Ok(line) if line.trim().is_empty());
+                async move { keep }
+            })
+            .map(|line| parse_stream_line(&line?));
 
-        // Convert byte stream to line-delimited JSON chunks
-        let stream = response.bytes_stream().map(move |result| {
-            let bytes = result.map_err(|e| AppError::Llm(format!("Stream error: {}", e)))?;
-
-            // Parse each line as JSON (Ollama sends newline-delimited JSON)
-            let text = String::from_utf8_lossy(&bytes);
-            let chunks: Vec<AppResult<LlmStreamChunk>> = text
-                .lines()
-                .filter(|line| !line.trim().is_empty())
-                .map(|line| {
-                    let ollama_response: OllamaResponse = serde_json::from_str(line)
-                        .map_err(|e| AppError::Llm(format!("Failed to parse chunk: {}", e)))?;
-
-                    Ok(LlmStreamChunk {
-                        content: ollama_response.response,
-                        model: ollama_response.model,
-                        done: ollama_response.done,
-                        usage: if ollama_response.done {
-                            Some(LlmUsage::new(
-                                ollama_response.prompt_eval_count.unwrap_or(0),
-                                ollama_response.eval_count.unwrap_or(0),
-                            ))
-                        } else {
-                            None
-                        },
-                    })
-                })
-                .collect();
-
-            // Return chunks as a stream
-            Ok(futures::stream::iter(chunks))
-        });
-
-        Ok(Box::pin(stream.flat_map(|result| match result {
-            Ok(chunks) => chunks,
-            Err(e) => futures::stream::iter(vec![Err(e)]),
-        })))
+        Ok(Box::pin(stream))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use guided_core::config::GenerationConfig;
 
     #[test]
     fn test_ollama_client_creation() {
@@ -218,6 +341,121 @@ mod tests {
         assert_eq!(client.base_url, "http://localhost:11434");
     }
 
+    #[test]
+    fn test_base_url_trailing_slash() {
+        let client = OllamaClient::with_base_url("http://gpu-box:11434/");
+        assert_eq!(client.base_url, "http://gpu-box:11434");
+    }
+
+    /// Serve `/api/tags` and never answer anything else.
+    async fn hung_server() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    if String::from_utf8_lossy(&buf[..n]).starts_with("GET /api/tags") {
+                        let body = r#"{"models":[]}"#;
+                        let reply = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        let _ = socket.write_all(reply.as_bytes()).await;
+                    } else {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_health_check_unreachable_server() {
+        // Bind and release a port so nothing listens on it
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = OllamaClient::with_base_url(format!("http://{}", addr));
+
+        let err = client.health_check().await.unwrap_err().to_string();
+        assert!(err.contains("Is Ollama running?"), "{}", err);
+        assert!(err.contains(&addr.to_string()), "{}", err);
+
+        let err = client
+            .complete(&LlmRequest::new("Hello", "llama3"))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Is Ollama running?"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_hung_server_times_out() {
+        let client =
+            OllamaClient::with_base_url(hung_server().await).with_timeout(Duration::from_secs(1));
+        assert!(client.health_check().await.is_ok());
+
+        let err = client
+            .complete(&LlmRequest::new("Hello", "llama3"))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("did not respond within 1s"), "{}", err);
+    }
+
+    /// Answer one request with `pieces` of a streamed body, written separately.
+    async fn split_stream_server(pieces: Vec<&'static str>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for piece in pieces {
+                socket.write_all(piece.as_bytes()).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_stream_objects_split_across_writes() {
+        let server = split_stream_server(vec![
+            r#"{"model":"llama3","response":"Hel"#,
+            "lo\",\"done\":false}\n{\"model\":\"llama3\",\"response\":\" world\",\"done\":false}\n{\"model\"",
+            r#":"llama3","response":"","done":true,"prompt_eval_count":3,"eval_count":2}"#,
+        ])
+        .await;
+        let client = OllamaClient::with_base_url(server);
+
+        let chunks: Vec<LlmStreamChunk> = client
+            .stream(&LlmRequest::new("Hi", "llama3"))
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        let text: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(text, "Hello world");
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.usage.as_ref().unwrap().completion_tokens, 2);
+    }
+
     #[test]
     fn test_ollama_request_conversion() {
         let client = OllamaClient::new();
@@ -228,7 +466,35 @@ mod tests {
         let ollama_req = client.to_ollama_request(&request);
         assert_eq!(ollama_req.model, "llama3");
         assert_eq!(ollama_req.prompt, "Hello");
-        assert_eq!(ollama_req.temperature, Some(0.7));
-        assert_eq!(ollama_req.num_predict, Some(100));
+        assert_eq!(ollama_req.options.temperature, Some(0.7));
+        assert_eq!(ollama_req.options.num_predict, Some(100));
+    }
+
+    #[test]
+    fn test_ollama_request_sends_generation_options() {
+        let client = OllamaClient::new();
+        let request = LlmRequest::new("Hello", "llama3")
+            .with_temperature(0.25)
+            .with_generation(&GenerationConfig {
+                top_p: Some(0.75),
+                top_k: Some(40),
+                presence_penalty: Some(0.5),
+                stop: vec!["</answer>".to_string()],
+                seed: Some(42),
+                ..Default::default()
+            });
+
+        let json = serde_json::to_value(client.to_ollama_request(&request)).unwrap();
+        assert_eq!(
+            json["options"],
+            serde_json::json!({
+                "temperature": 0.25,
+                "top_p": 0.75,
+                "top_k": 40,
+                "presence_penalty": 0.5,
+                "stop": ["</answer>"],
+                "seed": 42
+            })
+        );
     }
 }
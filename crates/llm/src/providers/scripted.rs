@@ -0,0 +1,203 @@
+//! Scripted LLM client for tests and offline demos.
+//!
+//! Selected with `--provider mock`, it answers without a model or a
+//! network. Without a script it echoes the prompt back. With one, set as
+//! `llm.providers.mock.script` in config.yaml, it replies with the first
+//! rule whose `match` text appears in the prompt (ignoring case):
+//!
+//! ```yaml
+//! replies:
+//!   - match: deploy
+//!     reply: Deploys are frozen on Fridays.
+//!   - reply: I don't know.   # no `match`: any prompt
+//! ```
+//!
+//! Prompts no rule matches are echoed too.
+
+use crate::client::{LlmClient, LlmRequest, LlmResponse, LlmStream, LlmStreamChunk, LlmUsage};
+use crate::context::estimate_tokens;
+use guided_core::{AppError, AppResult};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Replies of a [`ScriptedClient`], in the order they are tried.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Script {
+    #[serde(default)]
+    pub replies: Vec<ScriptedReply>,
+}
+
+/// One rule of a [`Script`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedReply {
+    /// Text the prompt must contain; any prompt matches when unset
+    #[serde(default, rename = "match")]
+    pub matches: Option<String>,
+
+    /// The reply
+    pub reply: String,
+}
+
+/// LLM client replying from a script, or echoing prompts.
+pub struct ScriptedClient {
+    script: Script,
+
+    /// Requests received, for tests to inspect
+    requests: Mutex<Vec<LlmRequest>>,
+}
+
+impl ScriptedClient {
+    /// Client echoing every prompt.
+    pub fn echo() -> Self {
+        Self::new(Script::default())
+    }
+
+    pub fn new(script: Script) -> Self {
+        Self {
+            script,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Client replying from a script in YAML.
+    pub fn from_yaml(yaml: &str) -> AppResult<Self> {
+        let script = serde_yaml::from_str(yaml)
+            .map_err(|e| AppError::Llm(format!("Invalid mock script: {}", e)))?;
+        Ok(Self::new(script))
+    }
+
+    /// Client replying from the YAML script at `path`.
+    pub fn from_file(path: &Path) -> AppResult<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Llm(format!("Failed to read mock script {:?}: {}", path, e)))?;
+        Self::from_yaml(&yaml)
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<LlmRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn reply(&self, request: &LlmRequest) -> String {
+        self.requests.lock().unwrap().push(request.clone());
+        let prompt = request.prompt.to_lowercase();
+        self.script
+            .replies
+            .iter()
+            .find(|rule| match &rule.matches {
+                Some(text) => prompt.contains(&text.to_lowercase()),
+                None => true,
+            })
+            .map(|rule| rule.reply.clone())
+            .unwrap_or_else(|| request.prompt.clone())
+    }
+}
+
+fn usage(request: &LlmRequest, reply: &str) -> LlmUsage {
+    LlmUsage::new(
+        estimate_tokens(&request.prompt) as u32,
+        estimate_tokens(reply) as u32,
+    )
+}
+
+#[async_trait::async_trait]
+impl LlmClient for ScriptedClient {
+    fn provider_name(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> AppResult<LlmResponse> {
+        let content = self.reply(request);
+        Ok(LlmResponse {
+            usage: usage(request, &content),
+            content,
+            model: request.model.clone(),
+            done: true,
+        })
+    }
+
+    /// Streams the reply a word at a time.
+    async fn stream(&self, request: &LlmRequest) -> AppResult<LlmStream> {
+        let content = self.reply(request);
+        let chunk = |piece: &str, done: bool| LlmStreamChunk {
+            content: piece.to_string(),
+            model: request.model.clone(),
+            done,
+            usage: done.then(|| usage(request, &content)),
+        };
+        let mut chunks: Vec<AppResult<LlmStreamChunk>> = content
+            .split_inclusive(' ')
+            .map(|word| Ok(chunk(word, false)))
+            .collect();
+        chunks.push(Ok(chunk("", true)));
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    const SCRIPT: &str = "replies:\n  - match: Deploy\n    reply: Deploys are frozen on Fridays.\n  - match: rollback\n    reply: Run make rollback.\n";
+
+    #[tokio::test]
+    async fn test_replies_with_first_matching_rule() {
+        let client = ScriptedClient::from_yaml(SCRIPT).unwrap();
+        let response = client
+            .complete(&LlmRequest::new(
+                "When are deploys frozen? Not a rollback.",
+                "any",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Deploys are frozen on Fridays.");
+        assert_eq!(response.model, "any");
+        assert_eq!(response.usage.completion_tokens, 8);
+
+        let response = client
+            .complete(&LlmRequest::new("Explain the rollback", "any"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Run make rollback.");
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_echoes_unmatched_prompts() {
+        let client = ScriptedClient::from_yaml(SCRIPT).unwrap();
+        let response = client
+            .complete(&LlmRequest::new("Hello", "any"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Hello");
+
+        let response = ScriptedClient::echo()
+            .complete(&LlmRequest::new("Say this back", "any"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Say this back");
+
+        assert!(ScriptedClient::from_yaml("replies: 3").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streams_reply_in_words() {
+        let client = ScriptedClient::from_yaml(SCRIPT).unwrap();
+        let chunks: Vec<LlmStreamChunk> = client
+            .stream(&LlmRequest::new("deploy?", "any").with_streaming())
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        let pieces: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            pieces,
+            vec!["Deploys ", "are ", "frozen ", "on ", "Fridays.", ""]
+        );
+        let last = chunks.last().unwrap();
+        assert!(last.done && last.usage.is_some());
+    }
+}
@@ -0,0 +1,374 @@
+//! Shared rate limiting for provider requests.
+//!
+//! One `RateLimiter` exists per provider name and is shared by every client
+//! talking to that provider (completions and embeddings alike), so a large
+//! `knowledge learn` and an `ask` in the same process draw from the same
+//! budget. Limits come from `llm.rateLimits` in `.guided/config.yaml`.
+//!
+//! Requests are spaced to honor requests-per-minute, capped by a semaphore
+//! for concurrency, and retried with jittered exponential backoff (or the
+//! server's `Retry-After`) when the provider answers 429.
+
+use guided_core::config::{AppConfig, RateLimitConfig};
+use guided_core::{AppError, AppResult};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+/// Longest backoff between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Outcome of a single attempt that did not succeed.
+#[derive(Debug)]
+pub enum CallError {
+    /// The provider answered 429; retry after the given delay if known
+    RateLimited { retry_after: Option<Duration> },
+
+    /// Any other failure; returned to the caller without retrying
+    Failed(AppError),
+}
+
+impl From<AppError> for CallError {
+    fn from(err: AppError) -> Self {
+        CallError::Failed(err)
+    }
+}
+
+/// Limits request rate and concurrency for one provider.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    /// Minimum spacing between request starts
+    interval: Option<Duration>,
+    next_slot: tokio::sync::Mutex<Instant>,
+    concurrency: Option<Arc<Semaphore>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let interval = config
+            .requests_per_minute
+            .filter(|rpm| *rpm > 0)
+            .map(|rpm| Duration::from_secs(60) / rpm);
+        let concurrency = config
+            .max_concurrent
+            .filter(|n| *n > 0)
+            .map(|n| Arc::new(Semaphore::new(n)));
+
+        Self {
+            config,
+            interval,
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+            concurrency,
+        }
+    }
+
+    /// A limiter with no rate or concurrency cap that still retries 429s.
+    pub fn unlimited() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Run `op` under the limits, retrying when it reports a rate limit.
+    ///
+    /// The concurrency permit is held only while `op` runs; for streaming
+    /// requests that means until the response headers arrive.
+    pub async fn call<T, F, Fut>(&self, op: F) -> AppResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, CallError>>,
+    {
+        self.call_observed(op, |_, _, _| {}).await
+    }
+
+    /// Like [`RateLimiter::call`], telling `on_retry` the attempt number,
+    /// the retries allowed and the delay before each retry of a rate
+    /// limited request (e.g., to show why a long run seems stalled).
+    pub async fn call_observed<T, F, Fut, R>(&self, mut op: F, on_retry: R) -> AppResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, CallError>>,
+        R: Fn(u32, u32, Duration),
+    {
+        let mut attempt = 0;
+
+        loop {
+            let permit = match self.concurrency {
+                Some(ref semaphore) => Some(
+                    semaphore
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .map_err(|e| AppError::Llm(format!("Rate limiter closed: {}", e)))?,
+                ),
+                None => None,
+            };
+            self.wait_for_slot().await;
+
+            let result = op().await;
+            drop(permit);
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(CallError::Failed(e)) => return Err(e),
+                Err(CallError::RateLimited { retry_after }) => {
+                    if attempt >= self.config.max_retries {
+                        return Err(AppError::Llm(format!(
+                            "Provider rate limit exceeded; gave up after {} retries",
+                            attempt
+                        )));
+                    }
+
+                    let delay = retry_after
+                        .map(|d| d.min(MAX_BACKOFF))
+                        .unwrap_or_else(|| self.backoff(attempt));
+                    attempt += 1;
+                    tracing::warn!(
+                        "Rate limited by provider, retrying in {}ms (attempt {}/{})",
+                        delay.as_millis(),
+                        attempt,
+                        self.config.max_retries
+                    );
+                    on_retry(attempt, self.config.max_retries, delay);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Delay before retry `attempt` (0-based): exponential with ±50% jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = Duration::from_millis(self.config.backoff_ms)
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF);
+        base.mul_f64(0.5 + jitter())
+    }
+
+    /// Wait until this request may start according to requests-per-minute.
+    async fn wait_for_slot(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Pseudo-random value in [0, 1) for backoff jitter.
+fn jitter() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    // splitmix64 step; quality only needs to decorrelate concurrent clients
+    let mut x = nanos ^ COUNTER.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn limiters() -> &'static Mutex<HashMap<String, Arc<RateLimiter>>> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+    LIMITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Set the limits for a provider, replacing any existing limiter.
+pub fn configure(provider: &str, config: RateLimitConfig) {
+    tracing::debug!("Rate limits for {}: {:?}", provider, config);
+    limiters()
+        .lock()
+        .unwrap()
+        .insert(provider.to_lowercase(), Arc::new(RateLimiter::new(config)));
+}
+
+/// Configure limiters for every provider listed in `llm.rateLimits`.
+pub fn configure_from(config: &AppConfig) {
+    if let Some(ref llm) = config.llm {
+        for (provider, limits) in &llm.rate_limits {
+            configure(provider, limits.clone());
+        }
+    }
+}
+
+/// The limiter shared by all clients of a provider.
+///
+/// Providers without configured limits get an unlimited limiter, which
+/// still retries 429 responses.
+pub fn shared_limiter(provider: &str) -> Arc<RateLimiter> {
+    limiters()
+        .lock()
+        .unwrap()
+        .entry(provider.to_lowercase())
+        .or_insert_with(|| Arc::new(RateLimiter::unlimited()))
+        .clone()
+}
+
+/// Parse a `Retry-After` header given in seconds.
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn limits(rpm: Option<u32>, concurrent: Option<usize>) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_minute: rpm,
+            max_concurrent: concurrent,
+            max_retries: 2,
+            backoff_ms: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spaces_requests() {
+        // 600/minute = one request every 100ms
+        let limiter = RateLimiter::new(limits(Some(600), None));
+        let started = Instant::now();
+
+        for _ in 0..3 {
+            limiter
+                .call(|| async { Ok::<_, CallError>(()) })
+                .await
+                .unwrap();
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_limits_concurrency() {
+        let limiter = Arc::new(RateLimiter::new(limits(None, Some(2))));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..6)
+            .map(|_| {
+                let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+                tokio::spawn(async move {
+                    limiter
+                        .call(|| async {
+                            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            peak.fetch_max(now, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                            Ok::<_, CallError>(())
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_then_gives_up() {
+        let limiter = RateLimiter::new(limits(None, None));
+
+        let attempts = AtomicUsize::new(0);
+        let value = limiter
+            .call(|| async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(CallError::RateLimited { retry_after: None })
+                } else {
+                    Ok(42)
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let result: AppResult<()> = limiter
+            .call(|| async {
+                Err(CallError::RateLimited {
+                    retry_after: Some(Duration::from_millis(1)),
+                })
+            })
+            .await;
+        assert!(result.unwrap_err().to_string().contains("gave up after 2"));
+    }
+
+    #[tokio::test]
+    async fn test_call_observed_reports_each_retry() {
+        let limiter = RateLimiter::new(limits(None, None));
+        let retries = Mutex::new(Vec::new());
+
+        let attempts = AtomicUsize::new(0);
+        limiter
+            .call_observed(
+                || async {
+                    if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                        Err(CallError::RateLimited {
+                            retry_after: Some(Duration::from_millis(3)),
+                        })
+                    } else {
+                        Ok(())
+                    }
+                },
+                |attempt, max, delay| retries.lock().unwrap().push((attempt, max, delay)),
+            )
+            .await
+            .unwrap();
+
+        let delay = Duration::from_millis(3);
+        assert_eq!(*retries.lock().unwrap(), vec![(1, 2, delay), (2, 2, delay)]);
+    }
+
+    #[test]
+    fn test_backoff_grows_with_jitter() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            backoff_ms: 100,
+            ..RateLimitConfig::default()
+        });
+
+        for attempt in 0..4 {
+            let base = 100 * 2u128.pow(attempt);
+            let delay = limiter.backoff(attempt).as_millis();
+            assert!(delay >= base / 2 && delay <= base * 3 / 2, "{}", delay);
+        }
+        assert!(limiter.backoff(30) <= MAX_BACKOFF.mul_f64(1.5));
+    }
+
+    #[test]
+    fn test_shared_limiter_is_per_provider() {
+        configure("test-provider", limits(Some(10), None));
+
+        let a = shared_limiter("Test-Provider");
+        let b = shared_limiter("test-provider");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(a.config().requests_per_minute, Some(10));
+        assert!(!Arc::ptr_eq(&a, &shared_limiter("other-provider")));
+    }
+}
@@ -0,0 +1,350 @@
+//! Persisted records of LLM interactions.
+//!
+//! When run recording is enabled, each completion is saved as
+//! `.guided/runs/<id>/run.json` with the exact request sent to the provider,
+//! the knowledge chunks it was built from, and the response.
+
+use crate::client::{LlmRequest, LlmUsage};
+use crate::context::ContextOverflow;
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A single recorded LLM interaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    /// Run identifier (timestamp-based directory name)
+    #[serde(default)]
+    pub id: String,
+
+    /// When the response completed (RFC 3339)
+    #[serde(default)]
+    pub created_at: String,
+
+    /// CLI command that made the request (e.g., "ask", "review")
+    pub command: String,
+
+    /// Prompt definition used to render the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_id: Option<String>,
+
+    /// SHA-256 of the prompt definition file the request was rendered from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_hash: Option<String>,
+
+    /// SHA-256 of the rendered system and user prompts (see [`prompt_hash`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_hash: Option<String>,
+
+    /// LLM provider name
+    pub provider: String,
+
+    /// IDs of knowledge chunks included in the prompt
+    #[serde(default)]
+    pub chunk_ids: Vec<String>,
+
+    /// Request as sent: rendered prompt, system prompt, model, parameters
+    pub request: LlmRequest,
+
+    /// Full response text
+    #[serde(default)]
+    pub response: String,
+
+    #[serde(default)]
+    pub usage: LlmUsage,
+
+    #[serde(default)]
+    pub duration_ms: u64,
+
+    /// How the prompt was shortened to fit the context window, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_overflow: Option<ContextOverflow>,
+
+    /// Run this one replayed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<String>,
+}
+
+impl RunRecord {
+    /// Start a record for a request.
+    pub fn new(
+        command: impl Into<String>,
+        provider: impl Into<String>,
+        request: &LlmRequest,
+    ) -> Self {
+        Self {
+            id: String::new(),
+            created_at: String::new(),
+            command: command.into(),
+            prompt_id: None,
+            template_hash: None,
+            prompt_hash: Some(prompt_hash(request)),
+            provider: provider.into(),
+            chunk_ids: Vec::new(),
+            request: request.clone(),
+            response: String::new(),
+            usage: LlmUsage::default(),
+            duration_ms: 0,
+            context_overflow: None,
+            replay_of: None,
+        }
+    }
+
+    pub fn with_prompt_id(mut self, prompt_id: impl Into<String>) -> Self {
+        self.prompt_id = Some(prompt_id.into());
+        self
+    }
+
+    pub fn with_template_hash(mut self, template_hash: impl Into<String>) -> Self {
+        self.template_hash = Some(template_hash.into());
+        self
+    }
+
+    pub fn with_context_overflow(mut self, overflow: Option<ContextOverflow>) -> Self {
+        self.context_overflow = overflow;
+        self
+    }
+
+    pub fn with_chunk_ids(mut self, chunk_ids: Vec<String>) -> Self {
+        self.chunk_ids = chunk_ids;
+        self
+    }
+
+    /// Fill in the response once it is complete.
+    pub fn finish(
+        mut self,
+        response: impl Into<String>,
+        usage: LlmUsage,
+        elapsed: Duration,
+    ) -> Self {
+        self.response = response.into();
+        self.usage = usage;
+        self.duration_ms = elapsed.as_millis() as u64;
+        self
+    }
+}
+
+/// SHA-256 of a request's system and user prompts, so runs with
+/// byte-identical prompts can be matched.
+pub fn prompt_hash(request: &LlmRequest) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(system) = &request.system {
+        hasher.update(system.as_bytes());
+    }
+    // Keeps "a" + "bc" apart from "ab" + "c"
+    hasher.update([0]);
+    hasher.update(request.prompt.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Storage for run records in a workspace.
+#[derive(Debug, Clone)]
+pub struct RunStore {
+    dir: PathBuf,
+}
+
+impl RunStore {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join(".guided").join("runs"),
+        }
+    }
+
+    /// Save a record, assigning its ID and timestamp.
+    pub fn save(&self, mut record: RunRecord) -> AppResult<RunRecord> {
+        let now = chrono::Utc::now();
+        let base_id = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+
+        // Disambiguate runs recorded within the same millisecond
+        let mut id = base_id.clone();
+        let mut suffix = 1;
+        while self.dir.join(&id).exists() {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let run_dir = self.dir.join(&id);
+        std::fs::create_dir_all(&run_dir).map_err(|e| {
+            AppError::Llm(format!(
+                "Failed to create run directory {:?}: {}",
+                run_dir, e
+            ))
+        })?;
+
+        record.id = id;
+        record.created_at = now.to_rfc3339();
+
+        let json = serde_json::to_string_pretty(&record)?;
+        std::fs::write(run_dir.join("run.json"), json)?;
+
+        tracing::debug!("Recorded run {}", record.id);
+
+        Ok(record)
+    }
+
+    /// List recorded runs, newest first.
+    pub fn list(&self) -> AppResult<Vec<RunRecord>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut records = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path().join("run.json");
+            if !path.exists() {
+                continue;
+            }
+
+            match Self::read(&path) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping unreadable run {:?}: {}", path, e),
+            }
+        }
+
+        records.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(records)
+    }
+
+    /// Load a run by ID.
+    pub fn load(&self, id: &str) -> AppResult<RunRecord> {
+        let path = self.dir.join(id).join("run.json");
+        if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) || !path.exists() {
+            return Err(AppError::Llm(format!("Run not found: {}", id)));
+        }
+        Self::read(&path)
+    }
+
+    fn read(path: &Path) -> AppResult<RunRecord> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// One line of a response comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Line diff between a recorded response and a replayed one.
+///
+/// Uses a longest-common-subsequence table, which is fine for responses
+/// (hundreds of lines) but not meant for large files.
+pub fn diff_responses(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    diff.extend(new[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_list_load() {
+        let temp = TempDir::new().unwrap();
+        let store = RunStore::new(temp.path());
+
+        let request = LlmRequest::new("What is Rust?", "llama3").with_temperature(0.2);
+        let first = store
+            .save(
+                RunRecord::new("ask", "ollama", &request)
+                    .with_prompt_id("agent.ask.default")
+                    .with_chunk_ids(vec!["chunk-1".to_string()])
+                    .finish(
+                        "A language.",
+                        LlmUsage::new(10, 3),
+                        Duration::from_millis(5),
+                    ),
+            )
+            .unwrap();
+        let second = store
+            .save(RunRecord::new("review", "ollama", &request))
+            .unwrap();
+
+        let runs = store.list().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, second.id);
+
+        let loaded = store.load(&first.id).unwrap();
+        assert_eq!(loaded.request.prompt, "What is Rust?");
+        assert_eq!(loaded.request.temperature, Some(0.2));
+        assert_eq!(loaded.chunk_ids, vec!["chunk-1"]);
+        assert_eq!(loaded.usage.total_tokens, 13);
+
+        assert!(store.load("missing").is_err());
+    }
+
+    #[test]
+    fn test_prompt_hash() {
+        let request = LlmRequest::new("What is Rust?", "llama3").with_system("Be brief.");
+        let record = RunRecord::new("ask", "ollama", &request);
+        assert_eq!(record.prompt_hash, Some(prompt_hash(&request)));
+        assert_eq!(record.prompt_hash.unwrap().len(), 64);
+
+        // Only the prompts count, not the model or sampling parameters
+        let other_model = LlmRequest::new("What is Rust?", "mistral")
+            .with_system("Be brief.")
+            .with_temperature(0.0);
+        assert_eq!(prompt_hash(&request), prompt_hash(&other_model));
+        let moved = LlmRequest::new("brief.What is Rust?", "llama3").with_system("Be ");
+        assert_ne!(prompt_hash(&request), prompt_hash(&moved));
+    }
+
+    #[test]
+    fn test_diff_responses() {
+        let diff = diff_responses("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("d".to_string()),
+            ]
+        );
+
+        assert!(diff_responses("same\n", "same")
+            .iter()
+            .all(|l| matches!(l, DiffLine::Same(_))));
+    }
+}
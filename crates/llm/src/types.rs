@@ -123,6 +123,7 @@ pub enum ProviderType {
     Claude,
     Ollama,
     GgufLocal,
+    Mock,
 }
 
 impl ProviderType {
@@ -133,6 +134,7 @@ impl ProviderType {
             "claude" | "anthropic" => Some(Self::Claude),
             "ollama" => Some(Self::Ollama),
             "gguf-local" | "gguf" => Some(Self::GgufLocal),
+            "mock" => Some(Self::Mock),
             _ => None,
         }
     }
@@ -144,6 +146,7 @@ impl ProviderType {
             Self::Claude => "claude",
             Self::Ollama => "ollama",
             Self::GgufLocal => "gguf-local",
+            Self::Mock => "mock",
         }
     }
 }
@@ -159,6 +162,7 @@ mod tests {
         assert_eq!(ProviderType::parse("anthropic"), Some(ProviderType::Claude));
         assert_eq!(ProviderType::parse("ollama"), Some(ProviderType::Ollama));
         assert_eq!(ProviderType::parse("gguf"), Some(ProviderType::GgufLocal));
+        assert_eq!(ProviderType::parse("mock"), Some(ProviderType::Mock));
         assert_eq!(ProviderType::parse("unknown"), None);
     }
 
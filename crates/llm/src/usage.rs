@@ -0,0 +1,343 @@
+//! Usage ledger of provider calls.
+//!
+//! Each LLM completion and the embedding calls of each learn append an
+//! entry to `.guided/operation/usage.jsonl`: which provider and model were
+//! used, how many requests were sent and how many tokens they carried.
+//! `guided stats` totals the entries, so embedding spend shows next to chat
+//! usage. Only counts are recorded, never prompts or texts.
+
+use crate::client::LlmUsage;
+use chrono::{DateTime, Utc};
+use guided_core::AppResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// What a ledger entry metered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageKind {
+    /// An LLM completion
+    Chat,
+    /// Embedding calls
+    Embedding,
+}
+
+impl UsageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageKind::Chat => "chat",
+            UsageKind::Embedding => "embedding",
+        }
+    }
+}
+
+/// One line of the ledger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageEntry {
+    /// When the calls completed (RFC 3339)
+    pub at: String,
+
+    pub kind: UsageKind,
+
+    /// CLI command that made the calls (e.g., "ask", "learn")
+    pub command: String,
+
+    pub provider: String,
+
+    pub model: String,
+
+    /// Requests sent to the provider
+    #[serde(default)]
+    pub requests: u64,
+
+    /// Input tokens: the prompt, or the embedded texts (0 when the
+    /// provider does not report them)
+    #[serde(default)]
+    pub prompt_tokens: u64,
+
+    /// Tokens generated
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub completion_tokens: u64,
+
+    /// Texts embedded
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub texts: u64,
+
+    /// Characters of the texts embedded
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub characters: u64,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl UsageEntry {
+    /// Entry for one completion, stamped now.
+    pub fn chat(
+        command: impl Into<String>,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        usage: &LlmUsage,
+    ) -> Self {
+        Self {
+            at: Utc::now().to_rfc3339(),
+            kind: UsageKind::Chat,
+            command: command.into(),
+            provider: provider.into(),
+            model: model.into(),
+            requests: 1,
+            prompt_tokens: usage.prompt_tokens as u64,
+            completion_tokens: usage.completion_tokens as u64,
+            texts: 0,
+            characters: 0,
+        }
+    }
+
+    /// Entry for the embedding calls of one command, stamped now.
+    pub fn embedding(
+        command: impl Into<String>,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        requests: u64,
+        tokens: u64,
+        texts: u64,
+        characters: u64,
+    ) -> Self {
+        Self {
+            at: Utc::now().to_rfc3339(),
+            kind: UsageKind::Embedding,
+            command: command.into(),
+            provider: provider.into(),
+            model: model.into(),
+            requests,
+            prompt_tokens: tokens,
+            completion_tokens: 0,
+            texts,
+            characters,
+        }
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.at)
+            .ok()
+            .map(|at| at.with_timezone(&Utc))
+    }
+}
+
+/// Usage summed over ledger entries, by kind and either provider and model
+/// or command.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotal {
+    pub kind: Option<UsageKind>,
+    /// Empty when summed by command
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub provider: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub model: String,
+    /// Empty when summed by provider and model
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub command: String,
+    /// Ledger entries summed
+    pub entries: u64,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub texts: u64,
+    pub characters: u64,
+}
+
+impl UsageTotal {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// The workspace's usage ledger.
+#[derive(Debug, Clone)]
+pub struct UsageLedger {
+    path: PathBuf,
+}
+
+impl UsageLedger {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            path: workspace
+                .join(".guided")
+                .join("operation")
+                .join("usage.jsonl"),
+        }
+    }
+
+    /// Append `entry` to the ledger of `workspace`, if it has a `.guided/`
+    /// directory.
+    ///
+    /// Failures are logged rather than returned, so metering never breaks
+    /// the command being metered.
+    pub fn record(workspace: &Path, entry: &UsageEntry) {
+        if !workspace.join(".guided").is_dir() {
+            return;
+        }
+        if let Err(e) = Self::new(workspace).append(entry) {
+            tracing::warn!("Failed to record usage: {}", e);
+        }
+    }
+
+    /// Append an entry.
+    pub fn append(&self, entry: &UsageEntry) -> AppResult<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// All entries, oldest first. Unreadable lines are skipped.
+    pub fn entries(&self) -> AppResult<Vec<UsageEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(&self.path)?;
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable usage entry: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Delete every entry.
+    pub fn clear(&self) -> AppResult<()> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sum `entries` made at or after `since` (all of them without it) by kind,
+/// provider and model, in that order.
+pub fn totals(entries: &[UsageEntry], since: Option<DateTime<Utc>>) -> Vec<UsageTotal> {
+    sum_by(entries, since, |entry| UsageTotal {
+        kind: Some(entry.kind),
+        provider: entry.provider.clone(),
+        model: entry.model.clone(),
+        ..Default::default()
+    })
+}
+
+/// Sum `entries` made at or after `since` by kind and command.
+pub fn totals_by_command(entries: &[UsageEntry], since: Option<DateTime<Utc>>) -> Vec<UsageTotal> {
+    sum_by(entries, since, |entry| UsageTotal {
+        kind: Some(entry.kind),
+        command: entry.command.clone(),
+        ..Default::default()
+    })
+}
+
+/// Sum `entries` into the empty totals `group` keys them by.
+fn sum_by(
+    entries: &[UsageEntry],
+    since: Option<DateTime<Utc>>,
+    group: impl Fn(&UsageEntry) -> UsageTotal,
+) -> Vec<UsageTotal> {
+    let mut totals: BTreeMap<(Option<UsageKind>, String, String, String), UsageTotal> =
+        BTreeMap::new();
+    for entry in entries {
+        if let (Some(since), Some(at)) = (since, entry.time()) {
+            if at < since {
+                continue;
+            }
+        }
+        let empty = group(entry);
+        let key = (
+            empty.kind,
+            empty.provider.clone(),
+            empty.model.clone(),
+            empty.command.clone(),
+        );
+        let total = totals.entry(key).or_insert(empty);
+        total.entries += 1;
+        total.requests += entry.requests;
+        total.prompt_tokens += entry.prompt_tokens;
+        total.completion_tokens += entry.completion_tokens;
+        total.texts += entry.texts;
+        total.characters += entry.characters;
+    }
+    totals.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ledger_totals_by_kind_and_model() {
+        let temp = TempDir::new().unwrap();
+        let ledger = UsageLedger::new(temp.path());
+
+        // Nothing is recorded outside an initialized workspace
+        let usage = LlmUsage::new(100, 20);
+        UsageLedger::record(
+            temp.path(),
+            &UsageEntry::chat("ask", "ollama", "llama3", &usage),
+        );
+        assert!(ledger.entries().unwrap().is_empty());
+
+        std::fs::create_dir(temp.path().join(".guided")).unwrap();
+        UsageLedger::record(
+            temp.path(),
+            &UsageEntry::chat("ask", "ollama", "llama3", &usage),
+        );
+        UsageLedger::record(
+            temp.path(),
+            &UsageEntry::chat("git", "ollama", "llama3", &usage),
+        );
+        let mut old = UsageEntry::embedding("learn", "ollama", "nomic-embed-text", 2, 0, 10, 900);
+        old.at = "2020-01-01T00:00:00Z".to_string();
+        ledger.append(&old).unwrap();
+        UsageLedger::record(
+            temp.path(),
+            &UsageEntry::embedding("learn", "ollama", "nomic-embed-text", 3, 240, 12, 1000),
+        );
+
+        let all = totals(&ledger.entries().unwrap(), None);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].kind, Some(UsageKind::Chat));
+        assert_eq!((all[0].entries, all[0].requests), (2, 2));
+        assert_eq!(all[0].total_tokens(), 240);
+        assert_eq!(all[1].kind, Some(UsageKind::Embedding));
+        assert_eq!(
+            (all[1].requests, all[1].texts, all[1].characters),
+            (5, 22, 1900)
+        );
+
+        let recent = totals(
+            &ledger.entries().unwrap(),
+            Some(Utc::now() - chrono::Duration::days(1)),
+        );
+        assert_eq!((recent[1].requests, recent[1].prompt_tokens), (3, 240));
+
+        let commands = totals_by_command(&ledger.entries().unwrap(), None);
+        let names: Vec<&str> = commands.iter().map(|t| t.command.as_str()).collect();
+        assert_eq!(names, vec!["ask", "git", "learn"]);
+        assert_eq!(commands[2].texts, 22);
+
+        ledger.clear().unwrap();
+        assert!(ledger.entries().unwrap().is_empty());
+    }
+}
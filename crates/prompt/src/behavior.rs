@@ -0,0 +1,159 @@
+//! Behavior rendering: tone, style, verbosity and audience as a system
+//! prompt.
+//!
+//! Known values get a sentence written for them; anything else is passed
+//! through as "Use a <tone> tone." and the like, so teams can use their own
+//! words without changing the crate.
+
+use crate::types::PromptBehavior;
+use guided_core::config::PersonaConfig;
+
+impl PromptBehavior {
+    /// Fill the fields this prompt leaves unset from the workspace persona.
+    pub fn inherit(&mut self, persona: &PersonaConfig) {
+        if self.tone.trim().is_empty() {
+            self.tone = persona.tone.clone().unwrap_or_default();
+        }
+        if self.style.trim().is_empty() {
+            self.style = persona.style.clone().unwrap_or_default();
+        }
+        if self.verbosity.is_none() {
+            self.verbosity = persona.verbosity.clone();
+        }
+        if self.audience.is_none() {
+            self.audience = persona.audience.clone();
+        }
+    }
+
+    /// The system prompt for these settings, one instruction per line, or
+    /// `None` when none is set.
+    pub fn system_prompt(&self) -> Option<String> {
+        let lines: Vec<String> = [
+            set(&self.tone).map(tone),
+            set(&self.style).map(style),
+            self.verbosity.as_deref().and_then(set).map(verbosity),
+            self.audience.as_deref().and_then(set).map(audience),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// `value` trimmed, unless blank.
+fn set(value: &str) -> Option<&str> {
+    let value = value.trim();
+    (!value.is_empty()).then_some(value)
+}
+
+fn tone(tone: &str) -> String {
+    match tone.to_ascii_lowercase().as_str() {
+        "professional" => "Use a professional tone: clear, neutral and without slang.".to_string(),
+        "casual" | "friendly" => "Use a casual, friendly tone, as with a colleague.".to_string(),
+        "technical" => {
+            "Use a technical tone: precise terminology, exact names and versions.".to_string()
+        }
+        "formal" => "Use a formal tone.".to_string(),
+        _ => format!("Use a {} tone.", tone),
+    }
+}
+
+fn style(style: &str) -> String {
+    match style.to_ascii_lowercase().as_str() {
+        "concise" => "Be concise: answer first, and leave out what was not asked.".to_string(),
+        "detailed" => {
+            "Be thorough: explain the reasoning, the alternatives and the caveats.".to_string()
+        }
+        "conversational" => "Write conversationally, in plain prose rather than lists.".to_string(),
+        "bullets" | "summary" => {
+            "Answer with a short bulleted summary: one point per bullet, most important first."
+                .to_string()
+        }
+        "step-by-step" | "steps" => {
+            "Answer with numbered steps, one action per step, in the order they are done."
+                .to_string()
+        }
+        _ => format!("Write in a {} style.", style),
+    }
+}
+
+fn verbosity(verbosity: &str) -> String {
+    match verbosity.to_ascii_lowercase().as_str() {
+        "low" | "minimal" => "Keep answers to a few sentences or a short snippet.".to_string(),
+        "medium" | "normal" => "Keep answers to a few short paragraphs.".to_string(),
+        "high" | "verbose" => "Give complete answers, with examples where they help.".to_string(),
+        _ => format!("Verbosity: {}.", verbosity),
+    }
+}
+
+fn audience(audience: &str) -> String {
+    match audience.to_ascii_lowercase().as_str() {
+        "beginner" | "beginners" => {
+            "Write for beginners: define terms and do not skip steps.".to_string()
+        }
+        "expert" | "experts" => {
+            "Write for experts: skip the basics and focus on specifics.".to_string()
+        }
+        _ => format!("Write for this audience: {}.", audience),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_prompt_from_behavior() {
+        let behavior = PromptBehavior {
+            tone: "technical".to_string(),
+            style: "Concise".to_string(),
+            verbosity: Some("terse".to_string()),
+            audience: Some("the billing team".to_string()),
+        };
+        assert_eq!(
+            behavior.system_prompt().unwrap(),
+            "Use a technical tone: precise terminology, exact names and versions.\n\
+             Be concise: answer first, and leave out what was not asked.\n\
+             Verbosity: terse.\n\
+             Write for this audience: the billing team."
+        );
+
+        assert_eq!(PromptBehavior::default().system_prompt(), None);
+    }
+
+    #[test]
+    fn test_answer_styles() {
+        let styled = |style: &str| {
+            PromptBehavior {
+                style: style.to_string(),
+                ..Default::default()
+            }
+            .system_prompt()
+            .unwrap()
+        };
+        assert!(styled("Step-by-step").starts_with("Answer with numbered steps"));
+        assert_eq!(styled("steps"), styled("step-by-step"));
+        assert!(styled("bullets").starts_with("Answer with a short bulleted summary"));
+        assert_eq!(styled("haiku"), "Write in a haiku style.");
+    }
+
+    #[test]
+    fn test_inherit_fills_unset_fields_only() {
+        let mut behavior = PromptBehavior {
+            tone: "casual".to_string(),
+            ..Default::default()
+        };
+        behavior.inherit(&PersonaConfig {
+            tone: Some("formal".to_string()),
+            style: Some("detailed".to_string()),
+            verbosity: None,
+            audience: Some("expert".to_string()),
+        });
+
+        assert_eq!(behavior.tone, "casual");
+        assert_eq!(behavior.style, "detailed");
+        assert_eq!(behavior.verbosity, None);
+        assert_eq!(behavior.audience.as_deref(), Some("expert"));
+    }
+}
@@ -1,18 +1,31 @@
 //! Prompt builder for rendering templates and injecting context.
 
-use crate::types::{BuiltPrompt, PromptDefinition};
+use crate::environment::environment_context;
+use crate::types::{BuiltPrompt, BuiltPromptMetadata, PromptDefinition};
 use guided_core::{AppError, AppResult};
 use handlebars::Handlebars;
 use std::collections::HashMap;
 use std::path::Path;
 
+/// Variables holding injected context, in the order they are injected.
+///
+/// These are what gets shortened when a prompt overflows the model's context
+/// window; the template text and the user's input are left alone.
+pub const CONTEXT_VARIABLES: &[&str] = &[
+    "workspaceContext",
+    "knowledgeContext",
+    "diffContext",
+    "memoryContext",
+];
+
 /// Build a prompt from a definition and input variables.
 ///
 /// This function:
 /// 1. Renders the template using Handlebars with provided variables
-/// 2. Injects workspace context if enabled
+/// 2. Injects workspace context (with environment facts) if enabled
 /// 3. Injects knowledge base context if enabled
-/// 4. Returns a `BuiltPrompt` ready for LLM execution
+/// 4. Renders the behavior settings into the system message
+/// 5. Returns a `BuiltPrompt` ready for LLM execution
 ///
 /// # Arguments
 /// * `definition` - Prompt definition loaded from YAML
@@ -46,7 +59,11 @@ pub fn build_prompt(
     // Inject workspace context if enabled
     let workspace_context_included = definition.context.include_workspace_context;
     if workspace_context_included {
-        let workspace_ctx = generate_workspace_context(workspace_path)?;
+        let mut workspace_ctx = generate_workspace_context(workspace_path)?;
+        if definition.context.include_environment {
+            workspace_ctx.push('\n');
+            workspace_ctx.push_str(environment_context());
+        }
         variables.insert("workspaceContext".to_string(), workspace_ctx);
         tracing::debug!("Injected workspace context");
     }
@@ -68,10 +85,9 @@ pub fn build_prompt(
     // Render template using Handlebars
     let rendered = render_template(&definition.template, &variables)?;
 
-    // Split into system and user messages
-    // For now, entire template is user message
-    // Future: support explicit system/user sections in template
-    let system = None;
+    // The template is the user message; the behavior settings become the
+    // system message
+    let system = definition.behavior.system_prompt();
     let user = rendered;
 
     Ok(BuiltPrompt::new(
@@ -84,6 +100,33 @@ pub fn build_prompt(
     ))
 }
 
+/// The injected context variables of a built prompt, in injection order.
+pub fn context_variables(metadata: &BuiltPromptMetadata) -> Vec<(String, String)> {
+    CONTEXT_VARIABLES
+        .iter()
+        .filter_map(|name| {
+            metadata
+                .resolved_variables
+                .get(*name)
+                .map(|value| (name.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+/// Render a definition again with a built prompt's variables, some of them
+/// replaced (e.g., shortened context).
+pub fn rerender(
+    definition: &PromptDefinition,
+    metadata: &BuiltPromptMetadata,
+    replacements: &[(String, String)],
+) -> AppResult<String> {
+    let mut variables = metadata.resolved_variables.clone();
+    for (name, value) in replacements {
+        variables.insert(name.clone(), value.clone());
+    }
+    render_template(&definition.template, &variables)
+}
+
 /// Render a Handlebars template with variables.
 fn render_template(template: &str, variables: &HashMap<String, String>) -> AppResult<String> {
     let mut handlebars = Handlebars::new();
@@ -133,6 +176,7 @@ fn generate_file_tree(path: &Path, max_depth: usize) -> AppResult<String> {
 
     for entry in walkdir::WalkDir::new(path)
         .max_depth(max_depth)
+        .sort_by_file_name()
         .into_iter()
         .filter_entry(|e| {
             // Skip hidden files and common exclude directories
@@ -166,20 +210,26 @@ mod tests {
             title: "Test".to_string(),
             api_version: "1.0".to_string(),
             created_by: "test".to_string(),
+            extends: None,
             behavior: PromptBehavior {
                 tone: "professional".to_string(),
                 style: "concise".to_string(),
+                verbosity: None,
+                audience: None,
             },
             context: PromptContextConfig {
                 include_workspace_context: include_workspace,
+                include_environment: true,
                 include_knowledge_base: include_kb,
                 knowledge_base_name: Some("test-kb".to_string()),
+                overflow: None,
             },
             input: PromptInputSpec::default(),
             template: "Question: {{prompt}}".to_string(),
             output: PromptOutputSpec {
                 format: "markdown".to_string(),
             },
+            source_hash: String::new(),
         }
     }
 
@@ -204,6 +254,7 @@ mod tests {
 
         let built = result.unwrap();
         assert_eq!(built.user, "Question: Test question");
+        assert!(built.system.unwrap().starts_with("Use a professional tone"));
         assert!(!built.metadata.workspace_context_included);
         assert_eq!(built.metadata.knowledge_base_used, None);
     }
@@ -225,6 +276,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_workspace_context_includes_environment() {
+        let mut def = create_test_definition(true, false);
+        def.template = "{{{workspaceContext}}}".to_string();
+        let built = build_prompt(&def, HashMap::new(), Path::new("."), None).unwrap();
+        assert!(built.user.contains("## Environment"));
+        assert!(built.user.contains("- OS: "));
+
+        def.context.include_environment = false;
+        let built = build_prompt(&def, HashMap::new(), Path::new("."), None).unwrap();
+        assert!(built.user.contains("# Workspace Context"));
+        assert!(!built.user.contains("## Environment"));
+    }
+
+    #[test]
+    fn test_rerender_with_shortened_context() {
+        let mut def = create_test_definition(false, true);
+        def.template = "{{{knowledgeContext}}}\nQuestion: {{prompt}}".to_string();
+        let mut vars = HashMap::new();
+        vars.insert("prompt".to_string(), "Why?".to_string());
+        let built = build_prompt(&def, vars, Path::new("."), Some("a\nb\nc".to_string())).unwrap();
+
+        let context = context_variables(&built.metadata);
+        assert_eq!(
+            context,
+            vec![("knowledgeContext".to_string(), "a\nb\nc".to_string())]
+        );
+
+        let shortened = vec![("knowledgeContext".to_string(), "c".to_string())];
+        let user = rerender(&def, &built.metadata, &shortened).unwrap();
+        assert_eq!(user, "c\nQuestion: Why?");
+    }
+
     #[test]
     fn test_render_template_missing_variable() {
         let vars = HashMap::new();
@@ -0,0 +1,103 @@
+//! Environment context for prompts.
+//!
+//! Detects facts about the machine guided runs on (operating system,
+//! toolchain and runtime versions, Docker) so answers to "why does this fail
+//! here" questions can take them into account. Detection runs each tool once
+//! per process.
+
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Tools whose versions are reported, with the arguments that print them.
+const TOOLS: &[(&str, &str, &[&str])] = &[
+    ("rustc", "rustc", &["--version"]),
+    ("cargo", "cargo", &["--version"]),
+    ("node", "node", &["--version"]),
+    ("python", "python3", &["--version"]),
+    ("docker", "docker", &["--version"]),
+];
+
+/// Markdown summary of the environment, starting with an `## Environment`
+/// heading. Tools that are not installed are listed as not found.
+pub fn environment_context() -> &'static str {
+    static CONTEXT: OnceLock<String> = OnceLock::new();
+    CONTEXT.get_or_init(|| {
+        let mut context = String::from("## Environment\n\n");
+        context.push_str(&format!("- OS: {}\n", os_description()));
+        for (label, program, args) in TOOLS {
+            let version = tool_version(program, args).unwrap_or_else(|| "not found".to_string());
+            context.push_str(&format!("- {}: {}\n", label, version));
+        }
+        context
+    })
+}
+
+/// OS family and architecture, with the release name where known.
+fn os_description() -> String {
+    let base = format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH);
+    let release = if cfg!(target_os = "linux") {
+        std::fs::read_to_string("/etc/os-release")
+            .ok()
+            .and_then(|release| {
+                release.lines().find_map(|line| {
+                    line.strip_prefix("PRETTY_NAME=")
+                        .map(|name| name.trim_matches('"').to_string())
+                })
+            })
+    } else if cfg!(target_os = "macos") {
+        tool_version("sw_vers", &["-productVersion"]).map(|v| format!("macOS {}", v))
+    } else if cfg!(windows) {
+        tool_version("cmd", &["/C", "ver"])
+    } else {
+        None
+    };
+    match release {
+        Some(release) if !release.is_empty() => format!("{}, {}", base, release),
+        _ => base,
+    }
+}
+
+/// First line a tool prints for its version, or `None` if it cannot be run.
+fn tool_version(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    // Some tools (older Python) print their version to stderr
+    let text = if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_context_lists_os_and_tools() {
+        let context = environment_context();
+        assert!(context.starts_with("## Environment\n"));
+        assert!(context.contains(&format!("- OS: {}", std::env::consts::OS)));
+        for (label, _, _) in TOOLS {
+            assert!(context.contains(&format!("- {}: ", label)), "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_tool_version() {
+        // Tests run under cargo, so rustc is installed
+        assert!(tool_version("rustc", &["--version"])
+            .unwrap()
+            .starts_with("rustc "));
+        assert_eq!(tool_version("guided-no-such-tool", &["--version"]), None);
+    }
+}
@@ -3,15 +3,18 @@
 //! This crate provides structured prompt management with:
 //! - YAML-based prompt definitions
 //! - Handlebars template rendering
-//! - Workspace context injection
+//! - Workspace and environment context injection
 //! - Knowledge base context injection
+//! - Behavior settings (tone, style, verbosity, audience) as a system prompt
 
+pub mod behavior;
 pub mod builder;
+pub mod environment;
 pub mod loader;
 pub mod types;
 
 // Re-export main types
-pub use builder::build_prompt;
+pub use builder::{build_prompt, context_variables, rerender};
 pub use loader::{list_prompts, load_prompt};
 pub use types::{
     BuiltPrompt, BuiltPromptMetadata, PromptBehavior, PromptContextConfig, PromptDefinition,
@@ -2,12 +2,21 @@
 
 use crate::types::PromptDefinition;
 use guided_core::{AppError, AppResult};
-use std::path::Path;
+use serde_yaml::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Marker in the template of an extending prompt replaced by the base
+/// prompt's template.
+pub const BASE_TEMPLATE: &str = "{{> base}}";
 
 /// Load a prompt definition by ID from the workspace.
 ///
 /// This function searches for a prompt file named `<id>.yml` in the
-/// `.guided/prompts/` directory.
+/// `.guided/prompts/` directory. A prompt with `extends: <id>` is merged
+/// over the prompt it extends: mappings (`behavior`, `context`, ...) field
+/// by field, everything else replaced whole. Its template replaces the
+/// base's, and can include it with `{{> base}}`.
 ///
 /// # Arguments
 /// * `workspace_path` - Root workspace directory containing `.guided/`
@@ -29,15 +38,141 @@ use std::path::Path;
 /// # }
 /// ```
 pub fn load_prompt(workspace_path: &Path, prompt_id: &str) -> AppResult<PromptDefinition> {
+    let (prompt_file, contents) = read_prompt_file(workspace_path, prompt_id)?;
+    let parse_error = |e: serde_yaml::Error| {
+        AppError::Prompt(format!(
+            "Failed to parse prompt YAML {:?}: {}",
+            prompt_file, e
+        ))
+    };
+
+    // The hash covers the whole chain of files, so pins notice a changed base
+    let mut hasher = Sha256::new();
+    hasher.update(contents.as_bytes());
+
+    let own: Value = serde_yaml::from_str(&contents).map_err(parse_error)?;
+    let mut definition: PromptDefinition = match own.get("extends").and_then(Value::as_str) {
+        // Parsed from the text, so errors point at a line
+        None => serde_yaml::from_str(&contents).map_err(parse_error)?,
+        Some(base_id) => {
+            let mut chain = vec![prompt_id.to_string()];
+            let base = resolve(workspace_path, base_id, &mut chain, &mut hasher)?;
+            serde_yaml::from_value(inherit(base, own.clone())).map_err(parse_error)?
+        }
+    };
+
+    // Validate required fields
+    validate_prompt(&definition)?;
+    definition.source_hash = format!("{:x}", hasher.finalize());
+
+    tracing::info!("Loaded prompt: {} ({})", definition.id, definition.title);
+
+    Ok(definition)
+}
+
+/// The prompt `prompt_id` as YAML, merged over the prompts it extends.
+///
+/// `chain` holds the IDs of the prompts extending it, to catch cycles;
+/// every file read is added to `hasher`.
+fn resolve(
+    workspace_path: &Path,
+    prompt_id: &str,
+    chain: &mut Vec<String>,
+    hasher: &mut Sha256,
+) -> AppResult<Value> {
+    if chain.iter().any(|id| id == prompt_id) {
+        chain.push(prompt_id.to_string());
+        return Err(AppError::Prompt(format!(
+            "Prompts extend each other in a cycle: {}",
+            chain.join(" -> ")
+        )));
+    }
+    chain.push(prompt_id.to_string());
+
+    let (prompt_file, contents) = read_prompt_file(workspace_path, prompt_id)?;
+    hasher.update(contents.as_bytes());
+    let value: Value = serde_yaml::from_str(&contents).map_err(|e| {
+        AppError::Prompt(format!(
+            "Failed to parse prompt YAML {:?}: {}",
+            prompt_file, e
+        ))
+    })?;
+
+    match value.get("extends").and_then(Value::as_str) {
+        Some(base_id) => {
+            let base = resolve(workspace_path, base_id, chain, hasher)?;
+            Ok(inherit(base, value))
+        }
+        None => Ok(value),
+    }
+}
+
+/// `child` merged over `base`: the child's ID, its mappings merged field by
+/// field, and its template with [`BASE_TEMPLATE`] replaced by the base's.
+fn inherit(base: Value, mut child: Value) -> Value {
+    let base_template = base
+        .get("template")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if let (Some(base_template), Some(Value::String(template))) =
+        (base_template, child.get_mut("template"))
+    {
+        *template = template.replace(BASE_TEMPLATE, &base_template);
+    }
+
+    // IDs are never inherited: a prompt without one is invalid
+    let mut merged = merge(base, child.clone());
+    if let Value::Mapping(mapping) = &mut merged {
+        if child.get("id").is_none() {
+            mapping.remove("id");
+        }
+    }
+    merged
+}
+
+/// `overlay` merged over `base`, recursing into mappings.
+fn merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (Value::Mapping(mut base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Mapping(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Read the file of the prompt `prompt_id`.
+fn read_prompt_file(workspace_path: &Path, prompt_id: &str) -> AppResult<(PathBuf, String)> {
+    // IDs name files in the prompts directory, never paths
+    if prompt_id.is_empty() || prompt_id.contains(['/', '\\']) || prompt_id.starts_with('.') {
+        return Err(AppError::Prompt(format!(
+            "Invalid prompt ID: '{}'",
+            prompt_id
+        )));
+    }
+
     let prompts_dir = workspace_path.join(".guided/prompts");
     let prompt_file = prompts_dir.join(format!("{}.yml", prompt_id));
 
     tracing::debug!("Loading prompt from: {:?}", prompt_file);
 
     if !prompt_file.exists() {
+        let mut available = list_prompts(workspace_path).unwrap_or_default();
+        available.sort();
+        let hint = if available.is_empty() {
+            String::new()
+        } else {
+            format!(". Available prompts: {}", available.join(", "))
+        };
         return Err(AppError::Prompt(format!(
-            "Prompt file not found: {:?}",
-            prompt_file
+            "Prompt file not found: {:?}{}",
+            prompt_file, hint
         )));
     }
 
@@ -48,19 +183,7 @@ pub fn load_prompt(workspace_path: &Path, prompt_id: &str) -> AppResult<PromptDe
         ))
     })?;
 
-    let definition: PromptDefinition = serde_yaml::from_str(&contents).map_err(|e| {
-        AppError::Prompt(format!(
-            "Failed to parse prompt YAML {:?}: {}",
-            prompt_file, e
-        ))
-    })?;
-
-    // Validate required fields
-    validate_prompt(&definition)?;
-
-    tracing::info!("Loaded prompt: {} ({})", definition.id, definition.title);
-
-    Ok(definition)
+    Ok((prompt_file, contents))
 }
 
 /// List all available prompt IDs in the workspace.
@@ -172,6 +295,19 @@ output:
         let prompt = result.unwrap();
         assert_eq!(prompt.id, "test.prompt");
         assert_eq!(prompt.title, "Test Prompt");
+
+        // The hash follows the file's bytes
+        assert_eq!(prompt.source_hash.len(), 64);
+        let path = create_test_prompt(temp_dir.path(), "test.prompt", true);
+        let hash = load_prompt(temp_dir.path(), "test.prompt")
+            .unwrap()
+            .source_hash;
+        assert_eq!(hash, prompt.source_hash);
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "\n").unwrap();
+        let hash = load_prompt(temp_dir.path(), "test.prompt")
+            .unwrap()
+            .source_hash;
+        assert_ne!(hash, prompt.source_hash);
     }
 
     #[test]
@@ -179,6 +315,21 @@ output:
         let temp_dir = TempDir::new().unwrap();
         let result = load_prompt(temp_dir.path(), "nonexistent");
         assert!(result.is_err());
+
+        create_test_prompt(temp_dir.path(), "agent.ask.brief", true);
+        let err = load_prompt(temp_dir.path(), "agent.ask.breif").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Available prompts: agent.ask.brief"));
+    }
+
+    #[test]
+    fn test_load_rejects_path_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_prompt(temp_dir.path(), "test.prompt", true);
+
+        assert!(load_prompt(temp_dir.path(), "../prompts/test.prompt").is_err());
+        assert!(load_prompt(temp_dir.path(), "").is_err());
     }
 
     #[test]
@@ -190,6 +341,78 @@ output:
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_extends_merges_over_the_base() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_prompt(temp_dir.path(), "base", true);
+        let prompts_dir = temp_dir.path().join(".guided/prompts");
+        fs::write(
+            prompts_dir.join("brief.yml"),
+            r#"
+id: brief
+extends: base
+title: "Brief"
+behavior:
+  style: terse
+context:
+  includeKnowledgeBase: true
+template: "{{> base}}\nAnswer in one line."
+"#,
+        )
+        .unwrap();
+        fs::write(
+            prompts_dir.join("briefer.yml"),
+            "id: briefer\nextends: brief\nbehavior:\n  audience: expert\n",
+        )
+        .unwrap();
+
+        let brief = load_prompt(temp_dir.path(), "brief").unwrap();
+        assert_eq!(brief.id, "brief");
+        assert_eq!(brief.extends.as_deref(), Some("base"));
+        assert_eq!(brief.title, "Brief");
+        assert_eq!(brief.api_version, "1.0");
+        assert_eq!(brief.behavior.tone, "professional");
+        assert_eq!(brief.behavior.style, "terse");
+        assert!(brief.context.include_knowledge_base);
+        assert!(!brief.context.include_workspace_context);
+        assert_eq!(
+            brief.template,
+            "Test template: {{prompt}}\nAnswer in one line."
+        );
+
+        let briefer = load_prompt(temp_dir.path(), "briefer").unwrap();
+        assert_eq!(briefer.id, "briefer");
+        assert_eq!(briefer.behavior.style, "terse");
+        assert_eq!(briefer.behavior.audience.as_deref(), Some("expert"));
+        assert_eq!(briefer.template, brief.template);
+
+        // Changing a base changes the hash of the prompts extending it
+        let path = prompts_dir.join("base.yml");
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "\n").unwrap();
+        let hash = load_prompt(temp_dir.path(), "briefer").unwrap().source_hash;
+        assert_ne!(hash, briefer.source_hash);
+    }
+
+    #[test]
+    fn test_extends_rejects_cycles_and_missing_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_prompt(temp_dir.path(), "base", true);
+        let prompts_dir = temp_dir.path().join(".guided/prompts");
+        fs::write(prompts_dir.join("a.yml"), "id: a\nextends: b\n").unwrap();
+        fs::write(prompts_dir.join("b.yml"), "id: b\nextends: a\n").unwrap();
+        fs::write(prompts_dir.join("anonymous.yml"), "extends: base\n").unwrap();
+        fs::write(
+            prompts_dir.join("orphan.yml"),
+            "id: orphan\nextends: gone\n",
+        )
+        .unwrap();
+
+        let err = load_prompt(temp_dir.path(), "a").unwrap_err();
+        assert!(err.to_string().contains("a -> b -> a"));
+        assert!(load_prompt(temp_dir.path(), "anonymous").is_err());
+        assert!(load_prompt(temp_dir.path(), "orphan").is_err());
+    }
+
     #[test]
     fn test_list_prompts() {
         let temp_dir = TempDir::new().unwrap();
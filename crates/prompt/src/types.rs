@@ -2,6 +2,7 @@
 //!
 //! This module defines the domain entities for the prompt system.
 
+use guided_core::config::OverflowStrategy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -22,7 +23,13 @@ pub struct PromptDefinition {
     #[serde(rename = "createdBy", default)]
     pub created_by: String,
 
-    /// Behavioral settings
+    /// ID of a prompt this one inherits from; fields set here override the
+    /// base's, field by field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+
+    /// Behavioral settings, rendered into the system prompt
+    #[serde(default)]
     pub behavior: PromptBehavior,
 
     /// Context injection settings
@@ -37,16 +44,34 @@ pub struct PromptDefinition {
 
     /// Output specification
     pub output: PromptOutputSpec,
+
+    /// SHA-256 of the definition file, set when loaded
+    #[serde(skip)]
+    pub source_hash: String,
 }
 
 /// Behavioral settings for prompt execution.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Empty fields are filled in from the workspace persona (see
+/// [`PromptBehavior::inherit`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PromptBehavior {
     /// Tone (e.g., "professional", "casual", "technical")
+    #[serde(default)]
     pub tone: String,
 
-    /// Style (e.g., "concise", "detailed", "conversational")
+    /// Style (e.g., "concise", "detailed", "bullets", "step-by-step",
+    /// "conversational")
+    #[serde(default)]
     pub style: String,
+
+    /// How much to say (e.g., "low", "medium", "high")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<String>,
+
+    /// Who the answers are for (e.g., "beginner", "expert")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
 }
 
 /// Context injection configuration.
@@ -56,6 +81,10 @@ pub struct PromptContextConfig {
     #[serde(rename = "includeWorkspaceContext", default)]
     pub include_workspace_context: bool,
 
+    /// Add OS, toolchain and runtime versions to the workspace context
+    #[serde(rename = "includeEnvironment", default = "default_include_environment")]
+    pub include_environment: bool,
+
     /// Include knowledge base context
     #[serde(rename = "includeKnowledgeBase", default)]
     pub include_knowledge_base: bool,
@@ -63,6 +92,15 @@ pub struct PromptContextConfig {
     /// Optional knowledge base name
     #[serde(rename = "knowledgeBaseName", skip_serializing_if = "Option::is_none")]
     pub knowledge_base_name: Option<String>,
+
+    /// How to handle prompts that overflow the model's context window,
+    /// overriding `llm.contextWindows.<provider>.overflow`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<OverflowStrategy>,
+}
+
+fn default_include_environment() -> bool {
+    true
 }
 
 /// Input specification for the prompt.
@@ -0,0 +1,88 @@
+//! Audit log of tool invocations.
+//!
+//! Entries are appended as JSON lines to `.guided/audit/<YYYY-MM-DD>.jsonl`.
+
+use guided_core::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Outcome of an audited invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditStatus {
+    /// Ran to completion (any exit code)
+    Executed,
+    /// Rejected by policy before running
+    Denied,
+    /// Killed after exceeding the timeout
+    TimedOut,
+    /// Could not be started
+    Failed,
+}
+
+/// A single audit record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// When the invocation finished (RFC 3339)
+    pub timestamp: String,
+
+    /// Tool name
+    pub tool: String,
+
+    /// Command line as requested
+    pub command: String,
+
+    /// Working directory, relative to the workspace
+    pub cwd: String,
+
+    pub status: AuditStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    pub duration_ms: u64,
+
+    /// Why the command was denied or failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Append-only audit log for a workspace.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    dir: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join(".guided").join("audit"),
+        }
+    }
+
+    /// Directory holding the audit files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append an entry to today's log file.
+    pub fn record(&self, entry: &AuditEntry) -> AppResult<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let path = self
+            .dir
+            .join(format!("{}.jsonl", chrono::Utc::now().format("%Y-%m-%d")));
+        let line = serde_json::to_string(entry)?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| AppError::Tool(format!("Failed to open audit log {:?}: {}", path, e)))?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+//! Tool-calling framework for the Guided Agent CLI.
+//!
+//! Tools are actions the model can request during a task:
+//! - `Tool` trait with JSON-schema parameter definitions
+//! - `ToolRegistry` for lookup and dispatch by name
+//! - `execute_shell`: allowlisted commands confined to the workspace
+//! - Audit log of every command under `.guided/audit/`
+
+pub mod audit;
+pub mod registry;
+pub mod shell;
+
+// Re-export main types
+pub use audit::{AuditEntry, AuditLog, AuditStatus};
+pub use registry::{Tool, ToolDefinition, ToolOutput, ToolRegistry};
+pub use shell::{ShellPolicy, ShellTool};
+
+use guided_core::config::AppConfig;
+use std::sync::Arc;
+
+/// Create a registry with the built-in tools configured for the workspace.
+pub fn default_registry(config: &AppConfig) -> ToolRegistry {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(ShellTool::new(
+        &config.workspace,
+        ShellPolicy::from_config(&config.tools.shell),
+    )));
+    registry
+}
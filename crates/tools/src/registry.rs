@@ -0,0 +1,99 @@
+//! Tool trait and registry.
+
+use async_trait::async_trait;
+use guided_core::AppResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Description of a tool as advertised to the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDefinition {
+    /// Unique tool name (e.g., "execute_shell")
+    pub name: String,
+
+    /// What the tool does, written for the model
+    pub description: String,
+
+    /// JSON schema of the arguments object
+    pub parameters: serde_json::Value,
+}
+
+/// Result of a tool call, returned to the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolOutput {
+    /// Text shown to the model
+    pub content: String,
+
+    /// Whether the call failed (denied, bad arguments, non-zero exit)
+    pub is_error: bool,
+}
+
+impl ToolOutput {
+    pub fn success(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            is_error: false,
+        }
+    }
+
+    pub fn error(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            is_error: true,
+        }
+    }
+}
+
+/// An action the model can invoke.
+///
+/// Problems the model can fix (bad arguments, denied commands) are reported
+/// as `ToolOutput::error`; `Err` is reserved for failures of the host.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Definition advertised to the model.
+    fn definition(&self) -> ToolDefinition;
+
+    /// Run the tool with JSON arguments.
+    async fn call(&self, arguments: serde_json::Value) -> AppResult<ToolOutput>;
+}
+
+/// Tools available to the model, keyed by name.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool, replacing any tool with the same name.
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.definition().name, tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.get(name).cloned()
+    }
+
+    /// Definitions of all registered tools, sorted by name.
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.values().map(|t| t.definition()).collect()
+    }
+
+    /// Dispatch a call by tool name.
+    pub async fn call(&self, name: &str, arguments: serde_json::Value) -> AppResult<ToolOutput> {
+        match self.tools.get(name) {
+            Some(tool) => tool.call(arguments).await,
+            None => Ok(ToolOutput::error(format!(
+                "Unknown tool: {}. Available: {}",
+                name,
+                self.tools.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))),
+        }
+    }
+}
@@ -0,0 +1,594 @@
+//! The `execute_shell` tool.
+//!
+//! Commands are tokenized and executed directly (never through `sh -c`), so
+//! pipes, redirects and command chaining are rejected rather than
+//! interpreted. Every invocation, including denied ones, is audited.
+
+use crate::audit::{AuditEntry, AuditLog, AuditStatus};
+use crate::registry::{Tool, ToolDefinition, ToolOutput};
+use async_trait::async_trait;
+use guided_core::{config::ShellToolConfig, AppResult};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Which commands may run, and for how long.
+#[derive(Debug, Clone)]
+pub struct ShellPolicy {
+    /// Allowed command prefixes (whole words); "*" allows everything
+    pub allow: Vec<Vec<String>>,
+
+    /// Denied command prefixes, checked before `allow`
+    pub deny: Vec<Vec<String>>,
+
+    pub timeout: Duration,
+
+    pub max_output_bytes: usize,
+}
+
+impl ShellPolicy {
+    pub fn from_config(config: &ShellToolConfig) -> Self {
+        let split = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|e| e.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+                .filter(|words| !words.is_empty())
+                .collect()
+        };
+
+        Self {
+            allow: split(&config.allow),
+            deny: split(&config.deny),
+            timeout: Duration::from_secs(config.timeout_secs),
+            max_output_bytes: config.max_output_bytes,
+        }
+    }
+
+    /// Check tokenized arguments against the deny and allow lists.
+    pub fn check(&self, argv: &[String]) -> Result<(), String> {
+        if let Some(rule) = self.deny.iter().find(|rule| matches_rule(rule, argv)) {
+            return Err(format!("Command denied by rule `{}`", rule.join(" ")));
+        }
+        if let Some(arg) = unsafe_git_arg(argv) {
+            return Err(format!(
+                "Argument `{}` is not allowed: it can write files or run programs outside the workspace",
+                arg
+            ));
+        }
+
+        if self.allow.iter().any(|rule| matches_rule(rule, argv)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Command not in allowlist. Allowed: {}",
+                self.allow
+                    .iter()
+                    .map(|r| r.join(" "))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        }
+    }
+}
+
+/// A rule matches when its words are a prefix of the command's words.
+fn matches_rule(rule: &[String], argv: &[String]) -> bool {
+    rule.len() == 1 && rule[0] == "*"
+        || (rule.len() <= argv.len() && rule.iter().zip(argv).all(|(r, a)| r == a))
+}
+
+/// Git options that write to arbitrary paths, run external programs or
+/// leave the workspace, which an allowlisted `git diff` would otherwise
+/// accept.
+const UNSAFE_GIT_ARGS: &[&str] = &[
+    "-c",
+    "-C",
+    "--config-env",
+    "--exec-path",
+    "--git-dir",
+    "--work-tree",
+    "--output",
+    "--ext-diff",
+    "--textconv",
+    "--upload-pack",
+    "--receive-pack",
+];
+
+/// First argument of a git command that is in [`UNSAFE_GIT_ARGS`], in either
+/// `--opt value` or `--opt=value` form.
+fn unsafe_git_arg(argv: &[String]) -> Option<&str> {
+    if argv.first().map(String::as_str) != Some("git") {
+        return None;
+    }
+    argv[1..]
+        .iter()
+        .map(String::as_str)
+        .take_while(|arg| *arg != "--")
+        .find(|arg| {
+            let name = arg.split('=').next().unwrap_or(arg);
+            UNSAFE_GIT_ARGS.contains(&name)
+        })
+}
+
+/// Runs allowlisted commands inside the workspace.
+pub struct ShellTool {
+    workspace: PathBuf,
+    policy: ShellPolicy,
+    audit: AuditLog,
+}
+
+impl ShellTool {
+    pub fn new(workspace: &Path, policy: ShellPolicy) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            policy,
+            audit: AuditLog::new(workspace),
+        }
+    }
+
+    /// Resolve `cwd` and make sure it stays inside the workspace.
+    fn resolve_cwd(&self, cwd: &str) -> Result<PathBuf, String> {
+        let root = self
+            .workspace
+            .canonicalize()
+            .map_err(|e| format!("Cannot resolve workspace: {}", e))?;
+        let dir = root
+            .join(cwd)
+            .canonicalize()
+            .map_err(|e| format!("Cannot resolve working directory `{}`: {}", cwd, e))?;
+
+        if !dir.starts_with(&root) {
+            return Err(format!(
+                "Working directory `{}` is outside the workspace",
+                cwd
+            ));
+        }
+        if !dir.is_dir() {
+            return Err(format!("Working directory `{}` is not a directory", cwd));
+        }
+
+        Ok(dir)
+    }
+
+    fn audit_entry(
+        &self,
+        command: &str,
+        cwd: &str,
+        status: AuditStatus,
+        started: Instant,
+    ) -> AuditEntry {
+        AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool: "execute_shell".to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_string(),
+            status,
+            exit_code: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+            reason: None,
+        }
+    }
+
+    /// Record an entry and return the matching error output.
+    fn reject(&self, mut entry: AuditEntry, reason: String) -> AppResult<ToolOutput> {
+        tracing::warn!(
+            "Shell command `{}` {:?}: {}",
+            entry.command,
+            entry.status,
+            reason
+        );
+        entry.reason = Some(reason.clone());
+        self.audit.record(&entry)?;
+        Ok(ToolOutput::error(reason))
+    }
+}
+
+#[async_trait]
+impl Tool for ShellTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "execute_shell".to_string(),
+            description: format!(
+                "Run a single command (no pipes, redirects or chaining) in the workspace \
+                 and return its exit code, stdout and stderr. Allowed commands: {}. \
+                 Timeout: {}s.",
+                self.policy
+                    .allow
+                    .iter()
+                    .map(|r| r.join(" "))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.policy.timeout.as_secs()
+            ),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "Command line, e.g. \"cargo test --workspace\""
+                    },
+                    "cwd": {
+                        "type": "string",
+                        "description": "Working directory relative to the workspace (default \".\")"
+                    }
+                },
+                "required": ["command"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> AppResult<ToolOutput> {
+        let started = Instant::now();
+        let command = arguments
+            .get("command")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let cwd = arguments
+            .get("cwd")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".")
+            .to_string();
+
+        let denied = self.audit_entry(&command, &cwd, AuditStatus::Denied, started);
+
+        let argv = match tokenize(&command) {
+            Ok(argv) if argv.is_empty() => {
+                return self.reject(denied, "Missing `command` argument".to_string())
+            }
+            Ok(argv) => argv,
+            Err(e) => return self.reject(denied, e),
+        };
+        if let Err(e) = self.policy.check(&argv) {
+            return self.reject(denied, e);
+        }
+        let dir = match self.resolve_cwd(&cwd) {
+            Ok(dir) => dir,
+            Err(e) => return self.reject(denied, e),
+        };
+
+        tracing::info!("Executing shell command `{}` in {:?}", command, dir);
+
+        let mut process = tokio::process::Command::new(&argv[0]);
+        process
+            .args(&argv[1..])
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Its own process group, so a timeout also stops what it spawned
+        #[cfg(unix)]
+        process.process_group(0);
+
+        let mut child = match process.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let entry = self.audit_entry(&command, &cwd, AuditStatus::Failed, started);
+                return self.reject(entry, format!("Failed to start `{}`: {}", argv[0], e));
+            }
+        };
+        let pid = child.id();
+
+        let max_bytes = self.policy.max_output_bytes;
+        let stdout = read_capped(child.stdout.take(), max_bytes);
+        let stderr = read_capped(child.stderr.take(), max_bytes);
+        let run = async { tokio::join!(child.wait(), stdout, stderr) };
+
+        let (status, stdout, stderr) = match tokio::time::timeout(self.policy.timeout, run).await {
+            Ok((Ok(status), Ok(stdout), Ok(stderr))) => (status, stdout, stderr),
+            Ok((Err(e), _, _)) | Ok((_, Err(e), _)) | Ok((_, _, Err(e))) => {
+                let entry = self.audit_entry(&command, &cwd, AuditStatus::Failed, started);
+                return self.reject(entry, format!("Command failed: {}", e));
+            }
+            Err(_) => {
+                #[cfg(unix)]
+                if let Some(pid) = pid {
+                    kill_group(pid);
+                }
+                let entry = self.audit_entry(&command, &cwd, AuditStatus::TimedOut, started);
+                return self.reject(
+                    entry,
+                    format!("Command timed out after {}s", self.policy.timeout.as_secs()),
+                );
+            }
+        };
+
+        let mut entry = self.audit_entry(&command, &cwd, AuditStatus::Executed, started);
+        entry.exit_code = status.code();
+        self.audit.record(&entry)?;
+
+        let stdout = truncate_output(&stdout.0, stdout.1);
+        let stderr = truncate_output(&stderr.0, stderr.1);
+        let exit = status
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "killed by signal".to_string());
+        let content = format!(
+            "exit code: {}\n\nstdout:\n{}\n\nstderr:\n{}",
+            exit, stdout, stderr
+        );
+
+        Ok(if status.success() {
+            ToolOutput::success(content)
+        } else {
+            ToolOutput::error(content)
+        })
+    }
+}
+
+/// Split a command line into words, honoring single and double quotes.
+///
+/// Unquoted shell operators are rejected since commands are not run
+/// through a shell.
+fn tokenize(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None => match c {
+                '\'' | '"' => {
+                    quote = Some(c);
+                    in_word = true;
+                }
+                '|' | '&' | ';' | '<' | '>' | '`' | '$' | '\n' => {
+                    return Err(format!(
+                        "Shell operator `{}` is not supported; run one command at a time",
+                        c
+                    ));
+                }
+                c if c.is_whitespace() => {
+                    if in_word {
+                        words.push(std::mem::take(&mut current));
+                        in_word = false;
+                    }
+                }
+                c => {
+                    current.push(c);
+                    in_word = true;
+                }
+            },
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Unterminated quote in command".to_string());
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    Ok(words)
+}
+
+/// Read `pipe` to the end, keeping only its first `max_bytes`. Returns the
+/// kept bytes and the total length read.
+async fn read_capped<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    max_bytes: usize,
+) -> std::io::Result<(Vec<u8>, usize)> {
+    let mut kept = Vec::new();
+    let mut total = 0;
+    let Some(mut pipe) = pipe else {
+        return Ok((kept, total));
+    };
+
+    // Keep draining past the cap so the command does not block on a full pipe
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = pipe.read(&mut buf).await?;
+        if n == 0 {
+            return Ok((kept, total));
+        }
+        let room = max_bytes.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+        total += n;
+    }
+}
+
+/// Kill the process group led by `pid`: the command and anything it spawned.
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    // SAFETY: killpg only sends a signal; a group that already exited is
+    // reported through the ignored return value
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+/// Decode output lossily, noting how much was cut when `kept` is a prefix of
+/// `total` bytes.
+fn truncate_output(kept: &[u8], total: usize) -> String {
+    if total <= kept.len() {
+        return String::from_utf8_lossy(kept).trim_end().to_string();
+    }
+
+    // Drop a multi-byte char split by the cap
+    let end = match std::str::from_utf8(kept) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => kept.len(),
+    };
+    format!(
+        "{}\n[... truncated {} bytes]",
+        String::from_utf8_lossy(&kept[..end]),
+        total - end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> ShellPolicy {
+        ShellPolicy::from_config(&ShellToolConfig {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+            timeout_secs: 1,
+            max_output_bytes: 1024,
+        })
+    }
+
+    fn audit_lines(temp: &TempDir) -> Vec<AuditEntry> {
+        let dir = temp.path().join(".guided/audit");
+        std::fs::read_dir(dir)
+            .unwrap()
+            .flat_map(|e| {
+                std::fs::read_to_string(e.unwrap().path())
+                    .unwrap()
+                    .lines()
+                    .map(|l| serde_json::from_str(l).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("cargo test -- --nocapture 'two words' \"x y\"").unwrap(),
+            vec!["cargo", "test", "--", "--nocapture", "two words", "x y"]
+        );
+        assert!(tokenize("cargo test && rm -rf /").is_err());
+        assert!(tokenize("echo $(whoami)").is_err());
+        assert!(tokenize("echo 'a|b'").is_ok());
+        assert!(tokenize("echo 'open").is_err());
+    }
+
+    #[test]
+    fn test_policy_matches_whole_words() {
+        let policy = policy(&["cargo test", "git"], &["git push"]);
+        let argv = |s: &str| tokenize(s).unwrap();
+
+        assert!(policy.check(&argv("cargo test --workspace")).is_ok());
+        assert!(policy.check(&argv("cargo testx")).is_err());
+        assert!(policy.check(&argv("cargo build")).is_err());
+        assert!(policy.check(&argv("git status")).is_ok());
+        assert!(policy.check(&argv("git push origin main")).is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_unsafe_git_args() {
+        let policy = policy(&["git diff", "git log"], &[]);
+        let argv = |s: &str| tokenize(s).unwrap();
+
+        assert!(policy.check(&argv("git diff --stat HEAD~1")).is_ok());
+        assert!(policy.check(&argv("git log -- --output")).is_ok());
+        assert!(policy.check(&argv("git diff --output=../x")).is_err());
+        assert!(policy.check(&argv("git log --output ../x")).is_err());
+        assert!(policy.check(&argv("git diff --ext-diff")).is_err());
+        assert!(policy.check(&argv("git -c diff.external=sh diff")).is_err());
+        assert!(policy.check(&argv("git -C .. diff")).is_err());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output(b"ok\n", 3), "ok");
+        assert_eq!(truncate_output(b"abc", 10), "abc\n[... truncated 7 bytes]");
+        // A char split by the cap is dropped, not replaced
+        assert_eq!(
+            truncate_output("aé".as_bytes().split_at(2).0, 3),
+            "a\n[... truncated 2 bytes]"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_executes_and_audits() {
+        let temp = TempDir::new().unwrap();
+        let tool = ShellTool::new(temp.path(), policy(&["echo", "sleep"], &[]));
+
+        let output = tool
+            .call(serde_json::json!({"command": "echo hello"}))
+            .await
+            .unwrap();
+        assert!(!output.is_error);
+        assert!(output.content.contains("exit code: 0"));
+        assert!(output.content.contains("hello"));
+
+        let denied = tool
+            .call(serde_json::json!({"command": "rm -rf src"}))
+            .await
+            .unwrap();
+        assert!(denied.is_error);
+
+        let timed_out = tool
+            .call(serde_json::json!({"command": "sleep 5"}))
+            .await
+            .unwrap();
+        assert!(timed_out.content.contains("timed out"));
+
+        let statuses: Vec<_> = audit_lines(&temp).iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                AuditStatus::Executed,
+                AuditStatus::Denied,
+                AuditStatus::TimedOut
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cwd_confined_to_workspace() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("sub")).unwrap();
+        let tool = ShellTool::new(temp.path(), policy(&["pwd"], &[]));
+
+        let inside = tool
+            .call(serde_json::json!({"command": "pwd", "cwd": "sub"}))
+            .await
+            .unwrap();
+        assert!(!inside.is_error);
+
+        let outside = tool
+            .call(serde_json::json!({"command": "pwd", "cwd": ".."}))
+            .await
+            .unwrap();
+        assert!(outside.is_error);
+        assert!(outside.content.contains("outside the workspace"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_capped_while_reading() {
+        let temp = TempDir::new().unwrap();
+        let tool = ShellTool::new(temp.path(), policy(&["head"], &[]));
+
+        let output = tool
+            .call(serde_json::json!({"command": "head -c 1000000 /dev/zero"}))
+            .await
+            .unwrap();
+        assert!(!output.is_error);
+        assert!(output.content.contains("[... truncated 998976 bytes]"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let temp = TempDir::new().unwrap();
+        let tool = ShellTool::new(temp.path(), policy(&["sh"], &[]));
+
+        let timed_out = tool
+            .call(serde_json::json!({"command": "sh -c 'sleep 30 & echo $! > pid; wait'"}))
+            .await
+            .unwrap();
+        assert!(timed_out.content.contains("timed out"));
+
+        // The background sleep went with the shell: gone, or a zombie
+        // waiting to be reaped
+        let pid = std::fs::read_to_string(temp.path().join("pid")).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let alive = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false);
+        assert!(!alive);
+    }
+}